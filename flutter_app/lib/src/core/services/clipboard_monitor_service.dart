@@ -6,6 +6,7 @@ import 'dart:async';
 import 'dart:io';
 import 'package:flutter/foundation.dart';
 import 'package:flutter_riverpod/flutter_riverpod.dart';
+import 'package:url_launcher/url_launcher.dart';
 
 import 'toss_service.dart';
 import 'notification_service.dart';
@@ -24,7 +25,7 @@ class ClipboardMonitorService {
   ClipboardMonitorService._internal();
 
   Timer? _monitorTimer;
-  Timer? _eventPollTimer;
+  StreamSubscription<TossEvent>? _eventSubscription;
   bool _isMonitoring = false;
 
   // Rate limiting: minimum time between clipboard syncs
@@ -52,12 +53,9 @@ class ClipboardMonitorService {
       }
     });
 
-    // Poll for network events every 500ms
-    _eventPollTimer = Timer.periodic(const Duration(milliseconds: 500), (_) {
-      final event = TossService.pollEvent();
-      if (event != null) {
-        _handleEvent(event, ref);
-      }
+    // Handle network events as they arrive
+    _eventSubscription = TossService.listenEvents().listen((event) {
+      _handleEvent(event, ref);
     });
   }
 
@@ -66,8 +64,8 @@ class ClipboardMonitorService {
     _isMonitoring = false;
     _monitorTimer?.cancel();
     _monitorTimer = null;
-    _eventPollTimer?.cancel();
-    _eventPollTimer = null;
+    _eventSubscription?.cancel();
+    _eventSubscription = null;
     _rateLimitTimer?.cancel();
     _rateLimitTimer = null;
     _pendingSync = false;
@@ -136,6 +134,12 @@ class ClipboardMonitorService {
             }
           }
 
+          // Lost to a concurrent copy in the core: history only
+          if (itemData.superseded) {
+            ref.read(clipboardHistoryProvider.notifier).addItem(newItem);
+            break;
+          }
+
           // Conflict resolution based on user preference
           final currentClipboard = ref.read(currentClipboardProvider);
           bool shouldUpdate;
@@ -228,6 +232,31 @@ class ClipboardMonitorService {
           }
         }
         break;
+      case 'events_lost':
+        // Events were dropped, so re-query everything they could have changed
+        debugPrint('Missed ${event.data?['count']} events, refreshing state');
+        ref.read(devicesProvider.notifier).refresh();
+        ref.read(currentClipboardProvider.notifier).refresh();
+        ref.read(clipboardHistoryProvider.notifier).loadHistory();
+        break;
+      case 'storage_degraded':
+        // Always surfaced: the user would otherwise lose data silently
+        final message = event.data?['message'] as String?;
+        if (message != null) {
+          NotificationService().showError(message);
+        }
+        break;
+      case 'open_url_requested':
+        // Only emitted when remote actions are allowed in settings
+        final url = event.data?['url'] as String?;
+        final uri = url != null ? Uri.tryParse(url) : null;
+        if (uri != null) {
+          launchUrl(uri, mode: LaunchMode.externalApplication).catchError((e) {
+            debugPrint('Failed to open remotely requested URL: $e');
+            return false;
+          });
+        }
+        break;
     }
   }
 
@@ -81,19 +81,31 @@ class DeviceInfo {
 /// Clipboard item from core
 class ClipboardItemInfo {
   final String id;
+
+  /// One of "text", "rich_text", "image", "file", "url", "code"
   final String contentType;
+  final String mimeType;
   final String preview;
   final int sizeBytes;
   final int timestamp;
   final String? sourceDevice;
 
+  /// Language hint for "code" items, e.g. "rust"
+  final String? language;
+
+  /// Received but lost to a concurrent copy, so not written to the clipboard
+  final bool superseded;
+
   const ClipboardItemInfo({
     required this.id,
     required this.contentType,
+    required this.mimeType,
     required this.preview,
     required this.sizeBytes,
     required this.timestamp,
     this.sourceDevice,
+    this.language,
+    this.superseded = false,
   });
 }
 
@@ -127,10 +139,13 @@ class TossEvent {
           'item': ClipboardItemInfo(
             id: item.id,
             contentType: item.contentType,
+            mimeType: item.mimeType,
             preview: item.preview,
             sizeBytes: item.sizeBytes.toInt(),
             timestamp: item.timestamp.toInt(),
             sourceDevice: item.sourceDevice,
+            language: item.language,
+            superseded: item.superseded,
           ),
         },
       ),
@@ -166,6 +181,75 @@ class TossEvent {
         type: 'error',
         data: {'message': message},
       ),
+      eventsLost: (count) => TossEvent(
+        type: 'events_lost',
+        data: {'count': count.toInt()},
+      ),
+      storageDegraded: (message) => TossEvent(
+        type: 'storage_degraded',
+        data: {'message': message},
+      ),
+      openUrlRequested: (url, deviceId) => TossEvent(
+        type: 'open_url_requested',
+        data: {'url': url, 'device_id': deviceId},
+      ),
+      presenceChanged: (deviceId, state) => TossEvent(
+        type: 'presence_changed',
+        data: {'device_id': deviceId, 'state': state},
+      ),
+      deviceIdentityChanged: (oldDeviceId, newDeviceId) => TossEvent(
+        type: 'device_identity_changed',
+        data: {'old_device_id': oldDeviceId, 'new_device_id': newDeviceId},
+      ),
+      storageRecovered: (backupPath, dataLost) => TossEvent(
+        type: 'storage_recovered',
+        data: {'backup_path': backupPath, 'data_lost': dataLost},
+      ),
+      pairingConfirmationRequested: (device) => TossEvent(
+        type: 'pairing_confirmation_requested',
+        data: {
+          'device': DeviceInfo(
+            id: device.id,
+            name: device.name,
+            isOnline: device.isOnline,
+            lastSeen: device.lastSeen.toInt(),
+            platform: device.platform,
+          ),
+        },
+      ),
+      fileReceived: (path, deviceId) => TossEvent(
+        type: 'file_received',
+        data: {'path': path, 'device_id': deviceId},
+      ),
+      relayCapReached: (deviceId, bytesSent, capBytes) => TossEvent(
+        type: 'relay_cap_reached',
+        data: {
+          'device_id': deviceId,
+          'bytes_sent': bytesSent.toInt(),
+          'cap_bytes': capBytes.toInt(),
+        },
+      ),
+      scratchpadChanged: (text, deviceId) => TossEvent(
+        type: 'scratchpad_changed',
+        data: {'text': text, 'device_id': deviceId},
+      ),
+      transferProgress: (
+        transferId,
+        direction,
+        deviceId,
+        totalBytes,
+        transferredBytes,
+      ) =>
+          TossEvent(
+        type: 'transfer_progress',
+        data: {
+          'transfer_id': transferId.toInt(),
+          'direction': direction,
+          'device_id': deviceId,
+          'total_bytes': totalBytes.toInt(),
+          'transferred_bytes': transferredBytes.toInt(),
+        },
+      ),
     );
   }
 }
@@ -333,10 +417,11 @@ class TossService {
 
   /// Complete pairing with manual code
   static Future<DeviceInfo> completePairingCode(
-      String code, List<int> publicKey) async {
+      String code, List<int> publicKey,
+      {String? peerDeviceName}) async {
     try {
-      final device =
-          api.completePairingCode(code: code, peerPublicKey: publicKey);
+      final device = api.completePairingCode(
+          code: code, peerPublicKey: publicKey, peerDeviceName: peerDeviceName);
       return DeviceInfo(
         id: device.id,
         name: device.name,
@@ -396,6 +481,53 @@ class TossService {
     }
   }
 
+  /// Accept or reject a pairing held by a pairing_confirmation_requested event
+  ///
+  /// Returns the paired device when accepted.
+  static Future<DeviceInfo?> confirmPairing(bool accept) async {
+    try {
+      final result = api.confirmPairing(accept: accept);
+      if (result == null) return null;
+      return DeviceInfo(
+        id: result.id,
+        name: result.name,
+        isOnline: result.isOnline,
+        lastSeen: result.lastSeen.toInt(),
+        platform: result.platform,
+      );
+    } catch (e) {
+      throw Exception('Failed to confirm pairing: $e');
+    }
+  }
+
+  /// Accept pairing requests without asking for [duration] (one hour if null)
+  static Future<void> trustPairingRequests([Duration? duration]) async {
+    try {
+      api.trustPairingRequests(durationSecs: duration?.inSeconds);
+    } catch (e) {
+      LoggingService.warn(' Failed to trust pairing requests: $e');
+    }
+  }
+
+  /// Ask before pairing again, ending any trust window early
+  static void revokePairingTrust() {
+    try {
+      api.revokePairingTrust();
+    } catch (e) {
+      LoggingService.warn(' Failed to revoke pairing trust: $e');
+    }
+  }
+
+  /// Always accept pairing requests from a device without asking
+  static Future<void> setDeviceAutoAcceptPairing(
+      String deviceId, bool enabled) async {
+    try {
+      api.setDeviceAutoAcceptPairing(deviceId: deviceId, enabled: enabled);
+    } catch (e) {
+      LoggingService.warn(' Failed to set pairing auto-accept: $e');
+    }
+  }
+
   /// Register pairing code on relay server and via mDNS for discovery
   /// Returns an AdvertisementResult indicating which methods succeeded/failed
   static Future<AdvertisementResult> registerPairingAdvertisement() async {
@@ -496,6 +628,18 @@ class TossService {
     }
   }
 
+  /// Check that a connected device shares this device's session key
+  ///
+  /// Returns false if the keys differ and the device should be paired again.
+  static Future<bool> verifySessionKey(String deviceId) async {
+    try {
+      return await api.verifySessionKey(deviceId: deviceId);
+    } catch (e) {
+      LoggingService.warn(' Failed to verify session key: $e');
+      rethrow;
+    }
+  }
+
   // ============================================================================
   // Clipboard Operations
   // ============================================================================
@@ -508,10 +652,13 @@ class TossService {
       return ClipboardItemInfo(
         id: item.id,
         contentType: item.contentType,
+        mimeType: item.mimeType,
         preview: item.preview,
         sizeBytes: item.sizeBytes.toInt(),
         timestamp: item.timestamp.toInt(),
         sourceDevice: item.sourceDevice,
+        language: item.language,
+        superseded: item.superseded,
       );
     } catch (e) {
       LoggingService.warn(' Failed to get current clipboard: $e');
@@ -528,6 +675,18 @@ class TossService {
     );
   }
 
+  /// Send current clipboard to one paired device with retry logic
+  static Future<void> sendClipboardToDevice(
+    String deviceId, {
+    int maxRetries = 3,
+  }) async {
+    await _retryOperation(
+      () => api.sendClipboardToDevice(deviceId: deviceId),
+      'send clipboard to device',
+      maxRetries: maxRetries,
+    );
+  }
+
   /// Send text to all devices with retry logic
   static Future<void> sendText(String text, {int maxRetries = 3}) async {
     await _retryOperation(
@@ -537,6 +696,42 @@ class TossService {
     );
   }
 
+  /// Send text to one paired device with retry logic
+  static Future<void> sendTextToDevice(
+    String deviceId,
+    String text, {
+    int maxRetries = 3,
+  }) async {
+    await _retryOperation(
+      () => api.sendTextToDevice(deviceId: deviceId, text: text),
+      'send text to device',
+      maxRetries: maxRetries,
+    );
+  }
+
+  /// Send a URL to all devices, asking those that allow remote actions to open it
+  static Future<void> sendUrlToOpen(String url) async {
+    await api.sendUrlToOpen(url: url);
+  }
+
+  /// Tell paired devices this device is 'idle' or 'copying' (throttled, no content)
+  static Future<void> broadcastPresence(String state) async {
+    try {
+      await api.broadcastPresence(state: state);
+    } catch (e) {
+      LoggingService.warn(' Failed to broadcast presence: $e');
+    }
+  }
+
+  /// Tell paired devices whether this device's screen is locked
+  static Future<void> setLocalLockState(bool locked) async {
+    try {
+      await api.setLocalLockState(locked: locked);
+    } catch (e) {
+      LoggingService.warn(' Failed to report lock state: $e');
+    }
+  }
+
   /// Retry an operation with exponential backoff
   static Future<void> _retryOperation(
     Future<void> Function() operation,
@@ -575,10 +770,13 @@ class TossService {
           .map((item) => ClipboardItemInfo(
                 id: item.id,
                 contentType: item.contentType,
+                mimeType: item.mimeType,
                 preview: item.preview,
                 sizeBytes: item.sizeBytes.toInt(),
                 timestamp: item.timestamp.toInt(),
                 sourceDevice: item.sourceDevice,
+                language: item.language,
+                superseded: item.superseded,
               ))
           .toList();
     } catch (e) {
@@ -605,6 +803,16 @@ class TossService {
     }
   }
 
+  /// Delete history older than the retention setting, returning how many items
+  static Future<int> pruneHistoryNow() async {
+    try {
+      return api.pruneHistoryNow();
+    } catch (e) {
+      LoggingService.warn(' Failed to prune clipboard history: $e');
+      return 0;
+    }
+  }
+
   /// Get decrypted content from clipboard history item
   static Future<ClipboardContent?> getHistoryItemContent(String itemId) async {
     try {
@@ -634,6 +842,35 @@ class TossService {
     required bool historyEnabled,
     required int historyDays,
     String? relayUrl,
+    String? relayPinnedKey,
+    int maxImageDimension = 0,
+    bool advertiseContentHint = false,
+    int maxPairedDevices = 20,
+    bool evictOldestDevice = false,
+    bool collectSyncStats = false,
+    bool quietHoursEnabled = false,
+    int quietHoursStart = 22 * 60,
+    int quietHoursEnd = 7 * 60,
+    bool allowRemoteActions = false,
+    bool sharePresence = false,
+    bool keyedContentHash = false,
+    int historyWriteWindowMs = 500,
+    bool confirmPairing = false,
+    bool lanOnlyMode = false,
+    String? fileReceiveDirectory,
+    int minSyncBytes = 0,
+    int monthlyRelayCapBytes = 0,
+    List<String> contentDenylist = const [],
+    bool requirePeerUnlocked = false,
+    String serviceName = 'toss',
+    bool historyText = true,
+    bool historyRichText = true,
+    bool historyImages = true,
+    bool historyFiles = true,
+    String textDirection = 'both',
+    String richTextDirection = 'both',
+    String imageDirection = 'both',
+    String fileDirection = 'both',
   }) async {
     try {
       final settings = api.TossSettings(
@@ -642,11 +879,43 @@ class TossService {
         syncRichText: syncRichText,
         syncImages: syncImages,
         syncFiles: syncFiles,
+        textDirection: textDirection,
+        richTextDirection: richTextDirection,
+        imageDirection: imageDirection,
+        fileDirection: fileDirection,
         maxFileSizeMb: maxFileSizeMb,
+        maxImageDimension: maxImageDimension,
         historyEnabled: historyEnabled,
+        historyText: historyText,
+        historyRichText: historyRichText,
+        historyImages: historyImages,
+        historyFiles: historyFiles,
         historyDays: historyDays,
         relayUrl: relayUrl,
+        relayPinnedKey: relayPinnedKey,
+        advertiseContentHint: advertiseContentHint,
+        maxPairedDevices: maxPairedDevices,
+        evictOldestDevice: evictOldestDevice,
+        collectSyncStats: collectSyncStats,
+        quietHoursEnabled: quietHoursEnabled,
+        quietHoursStart: quietHoursStart,
+        quietHoursEnd: quietHoursEnd,
+        allowRemoteActions: allowRemoteActions,
+        sharePresence: sharePresence,
+        keyedContentHash: keyedContentHash,
+        historyWriteWindowMs: historyWriteWindowMs,
+        confirmPairing: confirmPairing,
+        lanOnlyMode: lanOnlyMode,
+        fileReceiveDirectory: fileReceiveDirectory,
+        minSyncBytes: minSyncBytes,
+        monthlyRelayCapBytes: BigInt.from(monthlyRelayCapBytes),
+        contentDenylist: contentDenylist,
+        requirePeerUnlocked: requirePeerUnlocked,
+        serviceName: serviceName,
       );
+      // Quiet hours are in local time
+      api.setUtcOffset(
+          utcOffsetMinutes: DateTime.now().timeZoneOffset.inMinutes);
       api.updateSettings(settings: settings);
     } catch (e) {
       LoggingService.warn(' Failed to update settings: $e');
@@ -680,6 +949,16 @@ class TossService {
     }
   }
 
+  /// Stream network events as they happen
+  ///
+  /// Keeps working across network restarts; cancel the subscription to stop.
+  static Stream<TossEvent> listenEvents() {
+    if (!_ffiAvailable) return const Stream.empty();
+    return api.listenEvents().map(TossEvent.fromApi).handleError((e) {
+      LoggingService.warn(' Event stream failed: $e');
+    });
+  }
+
   /// Check if clipboard has changed since last check
   static bool checkClipboardChanged() {
     if (!_ffiAvailable) return false;
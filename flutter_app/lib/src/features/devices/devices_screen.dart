@@ -3,6 +3,7 @@ import 'package:flutter_riverpod/flutter_riverpod.dart';
 
 import '../../core/providers/devices_provider.dart';
 import '../../core/models/device.dart';
+import '../../core/services/toss_service.dart';
 
 class DevicesScreen extends ConsumerStatefulWidget {
   const DevicesScreen({super.key});
@@ -44,12 +45,32 @@ class _DevicesScreenState extends ConsumerState<DevicesScreen> {
                   onRename: () {
                     _showRenameDialog(context, ref, device);
                   },
+                  onVerify: () {
+                    _verifyConnection(context, device);
+                  },
                 );
               },
             ),
     );
   }
 
+  Future<void> _verifyConnection(BuildContext context, Device device) async {
+    String message;
+    try {
+      final verified = await TossService.verifySessionKey(device.id);
+      message = verified
+          ? 'Connection verified'
+          : 'Keys don\'t match; pair "${device.name}" again';
+    } catch (e) {
+      message = 'Could not verify connection: $e';
+    }
+    if (context.mounted) {
+      ScaffoldMessenger.of(context).showSnackBar(
+        SnackBar(content: Text(message)),
+      );
+    }
+  }
+
   void _showRenameDialog(BuildContext context, WidgetRef ref, Device device) {
     final controller = TextEditingController(text: device.name);
     showDialog(
@@ -138,11 +159,13 @@ class _DeviceListItem extends ConsumerWidget {
   final Device device;
   final VoidCallback onRemove;
   final VoidCallback onRename;
+  final VoidCallback onVerify;
 
   const _DeviceListItem({
     required this.device,
     required this.onRemove,
     required this.onRename,
+    required this.onVerify,
   });
 
   @override
@@ -190,6 +213,15 @@ class _DeviceListItem extends ConsumerWidget {
                 contentPadding: EdgeInsets.zero,
               ),
             ),
+            if (device.isOnline)
+              const PopupMenuItem(
+                value: 'verify',
+                child: ListTile(
+                  leading: Icon(Icons.verified_user),
+                  title: Text('Verify connection'),
+                  contentPadding: EdgeInsets.zero,
+                ),
+              ),
             const PopupMenuItem(
               value: 'remove',
               child: ListTile(
@@ -202,6 +234,8 @@ class _DeviceListItem extends ConsumerWidget {
           onSelected: (value) {
             if (value == 'rename') {
               onRename();
+            } else if (value == 'verify') {
+              onVerify();
             } else if (value == 'remove') {
               onRemove();
             }
@@ -4,6 +4,7 @@
 //! flutter_rust_bridge needs to parse source files with #[frb] attributes directly,
 //! so we can't just re-export from toss_core.
 
+use crate::frb_generated::StreamSink;
 use flutter_rust_bridge::frb;
 
 // ============================================================================
@@ -19,10 +20,42 @@ pub struct TossSettings {
     pub sync_rich_text: bool,
     pub sync_images: bool,
     pub sync_files: bool,
+    /// "both", "send_only" or "receive_only"
+    pub text_direction: String,
+    pub rich_text_direction: String,
+    pub image_direction: String,
+    pub file_direction: String,
     pub max_file_size_mb: u32,
+    pub max_image_dimension: u32,
     pub history_enabled: bool,
+    pub history_text: bool,
+    pub history_rich_text: bool,
+    pub history_images: bool,
+    pub history_files: bool,
     pub history_days: u32,
     pub relay_url: Option<String>,
+    pub relay_pinned_key: Option<String>,
+    pub advertise_content_hint: bool,
+    pub max_paired_devices: u32,
+    pub evict_oldest_device: bool,
+    pub collect_sync_stats: bool,
+    pub quiet_hours_enabled: bool,
+    /// Minutes after local midnight
+    pub quiet_hours_start: u32,
+    pub quiet_hours_end: u32,
+    pub allow_remote_actions: bool,
+    pub share_presence: bool,
+    pub keyed_content_hash: bool,
+    pub history_write_window_ms: u32,
+    pub confirm_pairing: bool,
+    pub lan_only_mode: bool,
+    pub file_receive_directory: Option<String>,
+    pub min_sync_bytes: u32,
+    pub monthly_relay_cap_bytes: u64,
+    pub content_denylist: Vec<String>,
+    pub require_peer_unlocked: bool,
+    /// mDNS service name, e.g. "toss"; applied when networking next starts
+    pub service_name: String,
 }
 
 impl From<toss_core::api::TossSettings> for TossSettings {
@@ -33,27 +66,87 @@ impl From<toss_core::api::TossSettings> for TossSettings {
             sync_rich_text: s.sync_rich_text,
             sync_images: s.sync_images,
             sync_files: s.sync_files,
+            text_direction: s.text_direction.as_str().to_string(),
+            rich_text_direction: s.rich_text_direction.as_str().to_string(),
+            image_direction: s.image_direction.as_str().to_string(),
+            file_direction: s.file_direction.as_str().to_string(),
             max_file_size_mb: s.max_file_size_mb,
+            max_image_dimension: s.max_image_dimension,
             history_enabled: s.history_enabled,
+            history_text: s.history_text,
+            history_rich_text: s.history_rich_text,
+            history_images: s.history_images,
+            history_files: s.history_files,
             history_days: s.history_days,
             relay_url: s.relay_url,
+            relay_pinned_key: s.relay_pinned_key,
+            advertise_content_hint: s.advertise_content_hint,
+            max_paired_devices: s.max_paired_devices,
+            evict_oldest_device: s.evict_oldest_device,
+            collect_sync_stats: s.collect_sync_stats,
+            quiet_hours_enabled: s.quiet_hours_enabled,
+            quiet_hours_start: s.quiet_hours_start,
+            quiet_hours_end: s.quiet_hours_end,
+            allow_remote_actions: s.allow_remote_actions,
+            share_presence: s.share_presence,
+            keyed_content_hash: s.keyed_content_hash,
+            history_write_window_ms: s.history_write_window_ms,
+            confirm_pairing: s.confirm_pairing,
+            lan_only_mode: s.lan_only_mode,
+            file_receive_directory: s.file_receive_directory,
+            min_sync_bytes: s.min_sync_bytes,
+            monthly_relay_cap_bytes: s.monthly_relay_cap_bytes,
+            content_denylist: s.content_denylist,
+            require_peer_unlocked: s.require_peer_unlocked,
+            service_name: s.service_name,
         }
     }
 }
 
-impl From<TossSettings> for toss_core::api::TossSettings {
-    fn from(s: TossSettings) -> Self {
-        Self {
+impl TryFrom<TossSettings> for toss_core::api::TossSettings {
+    type Error = String;
+
+    fn try_from(s: TossSettings) -> Result<Self, Self::Error> {
+        Ok(Self {
             auto_sync: s.auto_sync,
             sync_text: s.sync_text,
             sync_rich_text: s.sync_rich_text,
             sync_images: s.sync_images,
             sync_files: s.sync_files,
+            text_direction: s.text_direction.parse()?,
+            rich_text_direction: s.rich_text_direction.parse()?,
+            image_direction: s.image_direction.parse()?,
+            file_direction: s.file_direction.parse()?,
             max_file_size_mb: s.max_file_size_mb,
+            max_image_dimension: s.max_image_dimension,
             history_enabled: s.history_enabled,
+            history_text: s.history_text,
+            history_rich_text: s.history_rich_text,
+            history_images: s.history_images,
+            history_files: s.history_files,
             history_days: s.history_days,
             relay_url: s.relay_url,
-        }
+            relay_pinned_key: s.relay_pinned_key,
+            advertise_content_hint: s.advertise_content_hint,
+            max_paired_devices: s.max_paired_devices,
+            evict_oldest_device: s.evict_oldest_device,
+            collect_sync_stats: s.collect_sync_stats,
+            quiet_hours_enabled: s.quiet_hours_enabled,
+            quiet_hours_start: s.quiet_hours_start,
+            quiet_hours_end: s.quiet_hours_end,
+            allow_remote_actions: s.allow_remote_actions,
+            share_presence: s.share_presence,
+            keyed_content_hash: s.keyed_content_hash,
+            history_write_window_ms: s.history_write_window_ms,
+            confirm_pairing: s.confirm_pairing,
+            lan_only_mode: s.lan_only_mode,
+            file_receive_directory: s.file_receive_directory,
+            min_sync_bytes: s.min_sync_bytes,
+            monthly_relay_cap_bytes: s.monthly_relay_cap_bytes,
+            content_denylist: s.content_denylist,
+            require_peer_unlocked: s.require_peer_unlocked,
+            service_name: s.service_name,
+        })
     }
 }
 
@@ -80,16 +173,93 @@ impl From<toss_core::api::DeviceInfoDto> for DeviceInfoDto {
     }
 }
 
+/// Whether a paired device can be reached right now
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct DeviceReachabilityDto {
+    pub id: String,
+    pub name: String,
+    pub reachable: bool,
+    /// "connected", "local" or "relay"; None if unreachable
+    pub path: Option<String>,
+}
+
+impl From<toss_core::api::DeviceReachabilityDto> for DeviceReachabilityDto {
+    fn from(d: toss_core::api::DeviceReachabilityDto) -> Self {
+        Self {
+            id: d.id,
+            name: d.name,
+            reachable: d.reachable,
+            path: d.path,
+        }
+    }
+}
+
+/// Cryptographic details of a paired device
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct DeviceSecurityDto {
+    pub device_id: String,
+    /// "ed25519"; as advertised while connected
+    pub key_algorithm: String,
+    pub fingerprint: String,
+    pub fingerprint_verified: bool,
+    /// Unix seconds
+    pub fingerprint_verified_at: Option<u64>,
+    /// When the session key was set up or last rotated, while connected (Unix seconds)
+    pub session_key_since: Option<u64>,
+    /// Last authenticated message from the device (Unix seconds)
+    pub last_authenticated_at: Option<u64>,
+}
+
+impl From<toss_core::api::DeviceSecurityDto> for DeviceSecurityDto {
+    fn from(d: toss_core::api::DeviceSecurityDto) -> Self {
+        Self {
+            device_id: d.device_id,
+            key_algorithm: d.key_algorithm,
+            fingerprint: d.fingerprint,
+            fingerprint_verified: d.fingerprint_verified,
+            fingerprint_verified_at: d.fingerprint_verified_at,
+            session_key_since: d.session_key_since,
+            last_authenticated_at: d.last_authenticated_at,
+        }
+    }
+}
+
+/// Outcome of replacing this device's identity key
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct IdentityRotationDto {
+    pub device_id: String,
+    /// Paired devices that were told about the new key
+    pub migrated: Vec<String>,
+    /// Paired devices that must be paired again
+    pub needs_repair: Vec<String>,
+}
+
+impl From<toss_core::api::IdentityRotationDto> for IdentityRotationDto {
+    fn from(r: toss_core::api::IdentityRotationDto) -> Self {
+        Self {
+            device_id: r.device_id,
+            migrated: r.migrated,
+            needs_repair: r.needs_repair,
+        }
+    }
+}
+
 /// Clipboard item for display
 #[derive(Debug, Clone)]
 #[frb(dart_metadata=("freezed"))]
 pub struct ClipboardItemDto {
     pub id: String,
     pub content_type: String,
+    pub mime_type: String,
     pub preview: String,
     pub size_bytes: u64,
     pub timestamp: u64,
     pub source_device: Option<String>,
+    pub language: Option<String>,
+    pub superseded: bool,
 }
 
 impl From<toss_core::api::ClipboardItemDto> for ClipboardItemDto {
@@ -97,10 +267,326 @@ impl From<toss_core::api::ClipboardItemDto> for ClipboardItemDto {
         Self {
             id: c.id,
             content_type: c.content_type,
+            mime_type: c.mime_type,
             preview: c.preview,
             size_bytes: c.size_bytes,
             timestamp: c.timestamp,
             source_device: c.source_device,
+            language: c.language,
+            superseded: c.superseded,
+        }
+    }
+}
+
+/// Clipboard history items from one source device on one local day
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct HistoryGroupDto {
+    pub day: String,
+    pub source_device: Option<String>,
+    pub items: Vec<ClipboardItemDto>,
+}
+
+impl From<toss_core::api::HistoryGroupDto> for HistoryGroupDto {
+    fn from(g: toss_core::api::HistoryGroupDto) -> Self {
+        Self {
+            day: g.day,
+            source_device: g.source_device,
+            items: g.items.into_iter().map(|c| c.into()).collect(),
+        }
+    }
+}
+
+/// Local sync statistics for Flutter
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct SyncStatsDto {
+    pub send_attempts: u64,
+    pub send_successes: u64,
+    pub relay_fallbacks: u64,
+    pub failures: Vec<SyncFailureCountDto>,
+}
+
+impl From<toss_core::api::SyncStatsDto> for SyncStatsDto {
+    fn from(s: toss_core::api::SyncStatsDto) -> Self {
+        Self {
+            send_attempts: s.send_attempts,
+            send_successes: s.send_successes,
+            relay_fallbacks: s.relay_fallbacks,
+            failures: s.failures.into_iter().map(|f| f.into()).collect(),
+        }
+    }
+}
+
+/// Number of failed sends in one error category
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct SyncFailureCountDto {
+    pub category: String,
+    pub count: u64,
+}
+
+impl From<toss_core::api::SyncFailureCountDto> for SyncFailureCountDto {
+    fn from(f: toss_core::api::SyncFailureCountDto) -> Self {
+        Self {
+            category: f.category,
+            count: f.count,
+        }
+    }
+}
+
+/// One device's relay and internet traffic this month
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct RelayUsageDto {
+    pub device_id: String,
+    pub month: String,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub capped: bool,
+}
+
+impl From<toss_core::api::RelayUsageDto> for RelayUsageDto {
+    fn from(u: toss_core::api::RelayUsageDto) -> Self {
+        Self {
+            device_id: u.device_id,
+            month: u.month,
+            bytes_sent: u.bytes_sent,
+            bytes_received: u.bytes_received,
+            capped: u.capped,
+        }
+    }
+}
+
+/// Relay server identity, for pinning its key
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct RelayIdentityDto {
+    pub public_key: String,
+    pub matches_pinned: Option<bool>,
+}
+
+impl From<toss_core::api::RelayIdentityDto> for RelayIdentityDto {
+    fn from(r: toss_core::api::RelayIdentityDto) -> Self {
+        Self {
+            public_key: r.public_key,
+            matches_pinned: r.matches_pinned,
+        }
+    }
+}
+
+/// Messages the relay holds for this device
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct RelayQueueStatusDto {
+    pub count: u64,
+    pub oldest_queued_at: Option<i64>,
+}
+
+impl From<toss_core::api::RelayQueueStatusDto> for RelayQueueStatusDto {
+    fn from(q: toss_core::api::RelayQueueStatusDto) -> Self {
+        Self {
+            count: q.count,
+            oldest_queued_at: q.oldest_queued_at,
+        }
+    }
+}
+
+/// Snapshot of the network state, for troubleshooting
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct NetworkDiagnosticsDto {
+    pub local_addr: Option<String>,
+    pub mdns_status: String,
+    pub discovered_peers: Vec<DiscoveredPeerDto>,
+    pub connected_peers: Vec<ConnectedPeerDto>,
+    pub relay_status: String,
+    pub relay_url: Option<String>,
+    pub nat_type: String,
+    pub candidates: Vec<IceCandidateDto>,
+}
+
+impl From<toss_core::api::NetworkDiagnosticsDto> for NetworkDiagnosticsDto {
+    fn from(d: toss_core::api::NetworkDiagnosticsDto) -> Self {
+        Self {
+            local_addr: d.local_addr,
+            mdns_status: d.mdns_status,
+            discovered_peers: d.discovered_peers.into_iter().map(|p| p.into()).collect(),
+            connected_peers: d.connected_peers.into_iter().map(|p| p.into()).collect(),
+            relay_status: d.relay_status,
+            relay_url: d.relay_url,
+            nat_type: d.nat_type,
+            candidates: d.candidates.into_iter().map(|c| c.into()).collect(),
+        }
+    }
+}
+
+/// A device that answered an mDNS browse
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct DiscoveredPeerDto {
+    pub device_id: String,
+    pub device_name: String,
+    pub addresses: Vec<String>,
+    pub version: String,
+}
+
+impl From<toss_core::api::DiscoveredPeerDto> for DiscoveredPeerDto {
+    fn from(p: toss_core::api::DiscoveredPeerDto) -> Self {
+        Self {
+            device_id: p.device_id,
+            device_name: p.device_name,
+            addresses: p.addresses,
+            version: p.version,
+        }
+    }
+}
+
+/// A device with an open connection
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct ConnectedPeerDto {
+    pub device_id: String,
+    pub device_name: String,
+    pub addresses: Vec<String>,
+    pub transport: String,
+    pub route: String,
+    pub rtt_ms: u64,
+}
+
+impl From<toss_core::api::ConnectedPeerDto> for ConnectedPeerDto {
+    fn from(p: toss_core::api::ConnectedPeerDto) -> Self {
+        Self {
+            device_id: p.device_id,
+            device_name: p.device_name,
+            addresses: p.addresses,
+            transport: p.transport,
+            route: p.route,
+            rtt_ms: p.rtt_ms,
+        }
+    }
+}
+
+/// A gathered ICE candidate
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct IceCandidateDto {
+    pub candidate_type: String,
+    pub address: String,
+    pub priority: u64,
+}
+
+impl From<toss_core::api::IceCandidateDto> for IceCandidateDto {
+    fn from(c: toss_core::api::IceCandidateDto) -> Self {
+        Self {
+            candidate_type: c.candidate_type,
+            address: c.address,
+            priority: c.priority,
+        }
+    }
+}
+
+/// Limits and formats the core enforces
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct LimitsDto {
+    pub max_message_size: u64,
+    pub max_preview_size: u64,
+    pub protocol_version: u32,
+    pub pairing_code_length: u32,
+    pub pairing_code_format: String,
+    pub max_device_name_length: u32,
+}
+
+impl From<toss_core::api::LimitsDto> for LimitsDto {
+    fn from(l: toss_core::api::LimitsDto) -> Self {
+        Self {
+            max_message_size: l.max_message_size,
+            max_preview_size: l.max_preview_size,
+            protocol_version: l.protocol_version,
+            pairing_code_length: l.pairing_code_length,
+            pairing_code_format: l.pairing_code_format,
+            max_device_name_length: l.max_device_name_length,
+        }
+    }
+}
+
+/// Why a clipboard send was skipped or only partly delivered
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct SkipReasonDto {
+    pub reason: String,
+    pub message: String,
+    pub timestamp: u64,
+}
+
+impl From<toss_core::api::SkipReasonDto> for SkipReasonDto {
+    fn from(s: toss_core::api::SkipReasonDto) -> Self {
+        Self {
+            reason: s.reason,
+            message: s.message,
+            timestamp: s.timestamp,
+        }
+    }
+}
+
+/// Result of one self-test check
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct SelfTestCheckDto {
+    pub name: String,
+    pub status: String,
+    pub error: Option<String>,
+}
+
+impl From<toss_core::api::SelfTestCheckDto> for SelfTestCheckDto {
+    fn from(c: toss_core::api::SelfTestCheckDto) -> Self {
+        Self {
+            name: c.name,
+            status: c.status,
+            error: c.error,
+        }
+    }
+}
+
+/// Self-test report
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct SelfTestReportDto {
+    pub passed: bool,
+    pub checks: Vec<SelfTestCheckDto>,
+}
+
+impl From<toss_core::api::SelfTestReportDto> for SelfTestReportDto {
+    fn from(r: toss_core::api::SelfTestReportDto) -> Self {
+        Self {
+            passed: r.passed,
+            checks: r.checks.into_iter().map(|c| c.into()).collect(),
+        }
+    }
+}
+
+/// Transfer progress for Flutter
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct TransferProgressDto {
+    pub transfer_id: u64,
+    pub direction: String,
+    pub device_id: String,
+    pub total_bytes: u64,
+    pub received_bytes: u64,
+    pub percent: f64,
+}
+
+impl From<toss_core::api::TransferProgressDto> for TransferProgressDto {
+    fn from(t: toss_core::api::TransferProgressDto) -> Self {
+        Self {
+            transfer_id: t.transfer_id,
+            direction: t.direction,
+            device_id: t.device_id,
+            total_bytes: t.total_bytes,
+            received_bytes: t.received_bytes,
+            percent: t.percent,
         }
     }
 }
@@ -126,6 +612,29 @@ impl From<toss_core::api::PairingInfoDto> for PairingInfoDto {
     }
 }
 
+/// Pairing QR preview from parse_pairing_qr
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct PairingQrInfoDto {
+    pub code: String,
+    pub public_key: String,
+    pub device_name: String,
+    pub device_id: String,
+    pub expires_at: Option<u64>,
+}
+
+impl From<toss_core::api::PairingQrInfoDto> for PairingQrInfoDto {
+    fn from(p: toss_core::api::PairingQrInfoDto) -> Self {
+        Self {
+            code: p.code,
+            public_key: p.public_key,
+            device_name: p.device_name,
+            device_id: p.device_id,
+            expires_at: p.expires_at,
+        }
+    }
+}
+
 /// Pairing device info from find_pairing_device
 #[derive(Debug, Clone)]
 #[frb(dart_metadata=("freezed"))]
@@ -189,15 +698,93 @@ impl From<toss_core::api::ClipboardContentDto> for ClipboardContentDto {
     }
 }
 
+/// Outcome of a history export
+#[derive(Debug, Clone)]
+#[frb(dart_metadata=("freezed"))]
+pub struct HistoryExportDto {
+    pub count: u32,
+    pub next_after: Option<u64>,
+}
+
+impl From<toss_core::api::HistoryExportDto> for HistoryExportDto {
+    fn from(e: toss_core::api::HistoryExportDto) -> Self {
+        Self {
+            count: e.count,
+            next_after: e.next_after,
+        }
+    }
+}
+
 /// Event types for Flutter
 #[derive(Debug, Clone)]
 #[frb(dart_metadata=("freezed"))]
 pub enum TossEvent {
-    ClipboardReceived { item: ClipboardItemDto },
-    DeviceConnected { device: DeviceInfoDto },
-    DeviceDisconnected { device_id: String },
-    PairingRequest { device: DeviceInfoDto },
-    Error { message: String },
+    ClipboardReceived {
+        item: ClipboardItemDto,
+    },
+    DeviceConnected {
+        device: DeviceInfoDto,
+    },
+    DeviceDisconnected {
+        device_id: String,
+    },
+    PairingRequest {
+        device: DeviceInfoDto,
+    },
+    Error {
+        message: String,
+    },
+    EventsLost {
+        count: u64,
+    },
+    StorageDegraded {
+        message: String,
+    },
+    OpenUrlRequested {
+        url: String,
+        device_id: String,
+    },
+    /// "idle" or "copying"
+    PresenceChanged {
+        device_id: String,
+        state: String,
+    },
+    DeviceIdentityChanged {
+        old_device_id: String,
+        new_device_id: String,
+    },
+    /// The database was corrupt and rebuilt
+    StorageRecovered {
+        backup_path: String,
+        data_lost: bool,
+    },
+    /// A device completed our pairing code; answer with confirm_pairing
+    PairingConfirmationRequested {
+        device: DeviceInfoDto,
+    },
+    /// A received file was saved to the file receive directory
+    FileReceived {
+        path: String,
+        device_id: String,
+    },
+    /// Sync with the device is LAN-only until next month or a usage reset
+    RelayCapReached {
+        device_id: String,
+        bytes_sent: u64,
+        cap_bytes: u64,
+    },
+    ScratchpadChanged {
+        text: String,
+        device_id: String,
+    },
+    /// A transfer reached a new whole percent or completed
+    TransferProgress {
+        transfer_id: u64,
+        direction: String,
+        device_id: String,
+        total_bytes: u64,
+        transferred_bytes: u64,
+    },
 }
 
 impl From<toss_core::api::TossEvent> for TossEvent {
@@ -216,6 +803,63 @@ impl From<toss_core::api::TossEvent> for TossEvent {
                 device: device.into(),
             },
             toss_core::api::TossEvent::Error { message } => TossEvent::Error { message },
+            toss_core::api::TossEvent::EventsLost { count } => TossEvent::EventsLost { count },
+            toss_core::api::TossEvent::StorageDegraded { message } => {
+                TossEvent::StorageDegraded { message }
+            }
+            toss_core::api::TossEvent::OpenUrlRequested { url, device_id } => {
+                TossEvent::OpenUrlRequested { url, device_id }
+            }
+            toss_core::api::TossEvent::PresenceChanged { device_id, state } => {
+                TossEvent::PresenceChanged { device_id, state }
+            }
+            toss_core::api::TossEvent::DeviceIdentityChanged {
+                old_device_id,
+                new_device_id,
+            } => TossEvent::DeviceIdentityChanged {
+                old_device_id,
+                new_device_id,
+            },
+            toss_core::api::TossEvent::StorageRecovered {
+                backup_path,
+                data_lost,
+            } => TossEvent::StorageRecovered {
+                backup_path,
+                data_lost,
+            },
+            toss_core::api::TossEvent::PairingConfirmationRequested { device } => {
+                TossEvent::PairingConfirmationRequested {
+                    device: device.into(),
+                }
+            }
+            toss_core::api::TossEvent::FileReceived { path, device_id } => {
+                TossEvent::FileReceived { path, device_id }
+            }
+            toss_core::api::TossEvent::RelayCapReached {
+                device_id,
+                bytes_sent,
+                cap_bytes,
+            } => TossEvent::RelayCapReached {
+                device_id,
+                bytes_sent,
+                cap_bytes,
+            },
+            toss_core::api::TossEvent::ScratchpadChanged { text, device_id } => {
+                TossEvent::ScratchpadChanged { text, device_id }
+            }
+            toss_core::api::TossEvent::TransferProgress {
+                transfer_id,
+                direction,
+                device_id,
+                total_bytes,
+                transferred_bytes,
+            } => TossEvent::TransferProgress {
+                transfer_id,
+                direction,
+                device_id,
+                total_bytes,
+                transferred_bytes,
+            },
         }
     }
 }
@@ -230,12 +874,30 @@ pub fn init_toss(data_dir: String, device_name: String) -> Result<(), String> {
     toss_core::api::init_toss(data_dir, device_name)
 }
 
+/// Allow or forbid falling back to in-memory storage in `init_toss`
+#[frb(sync)]
+pub fn set_storage_fallback_enabled(enabled: bool) {
+    toss_core::api::set_storage_fallback_enabled(enabled)
+}
+
+/// Check whether storage is persisted, or in memory after a failed open
+#[frb(sync)]
+pub fn is_storage_persistent() -> Result<bool, String> {
+    toss_core::api::is_storage_persistent()
+}
+
 /// Shutdown Toss
 #[frb]
 pub async fn shutdown_toss() {
     toss_core::api::shutdown_toss().await
 }
 
+/// Securely wipe all local data and keys
+#[frb]
+pub async fn wipe_all_data() -> Result<(), String> {
+    toss_core::api::wipe_all_data().await
+}
+
 // ============================================================================
 // Device Identity
 // ============================================================================
@@ -246,18 +908,36 @@ pub fn get_device_id() -> String {
     toss_core::api::get_device_id()
 }
 
+/// Get this device's key fingerprint, as paired devices show it
+#[frb(sync)]
+pub fn get_device_fingerprint() -> String {
+    toss_core::api::get_device_fingerprint()
+}
+
 /// Get device name
 #[frb(sync)]
 pub fn get_device_name() -> String {
     toss_core::api::get_device_name()
 }
 
+/// Get the limits and formats the core enforces
+#[frb(sync)]
+pub fn get_limits() -> LimitsDto {
+    toss_core::api::get_limits().into()
+}
+
 /// Set device name
 #[frb(sync)]
 pub fn set_device_name(name: String) -> Result<(), String> {
     toss_core::api::set_device_name(name)
 }
 
+/// Replace the identity key, migrating connected paired devices to it
+#[frb]
+pub async fn rotate_identity() -> Result<IdentityRotationDto, String> {
+    toss_core::api::rotate_identity().await.map(|r| r.into())
+}
+
 // ============================================================================
 // Pairing
 // ============================================================================
@@ -268,19 +948,26 @@ pub fn start_pairing() -> Result<PairingInfoDto, String> {
     toss_core::api::start_pairing().map(|p| p.into())
 }
 
+/// Parse and validate scanned QR data without completing pairing
+#[frb(sync)]
+pub fn parse_pairing_qr(qr_data: String) -> Result<PairingQrInfoDto, String> {
+    toss_core::api::parse_pairing_qr(qr_data).map(|p| p.into())
+}
+
 /// Complete pairing with QR data
 #[frb(sync)]
 pub fn complete_pairing_qr(qr_data: String) -> Result<DeviceInfoDto, String> {
     toss_core::api::complete_pairing_qr(qr_data).map(|d| d.into())
 }
 
-/// Complete pairing with manual code
+/// Complete pairing with manual code, using the peer's advertised name if known
 #[frb(sync)]
 pub fn complete_pairing_code(
     code: String,
     peer_public_key: Vec<u8>,
+    peer_device_name: Option<String>,
 ) -> Result<DeviceInfoDto, String> {
-    toss_core::api::complete_pairing_code(code, peer_public_key).map(|d| d.into())
+    toss_core::api::complete_pairing_code(code, peer_public_key, peer_device_name).map(|d| d.into())
 }
 
 /// Cancel active pairing session
@@ -289,6 +976,36 @@ pub fn cancel_pairing() {
     toss_core::api::cancel_pairing()
 }
 
+/// Abort the find_pairing_device search in progress, if any
+#[frb(sync)]
+pub fn cancel_find_pairing() {
+    toss_core::api::cancel_find_pairing()
+}
+
+/// Accept or reject the pairing held by PairingConfirmationRequested
+#[frb(sync)]
+pub fn confirm_pairing(accept: bool) -> Result<Option<DeviceInfoDto>, String> {
+    toss_core::api::confirm_pairing(accept).map(|device| device.map(Into::into))
+}
+
+/// Accept pairing requests without asking for a while (default one hour)
+#[frb(sync)]
+pub fn trust_pairing_requests(duration_secs: Option<u32>) -> Result<u64, String> {
+    toss_core::api::trust_pairing_requests(duration_secs)
+}
+
+/// End the trust window opened by trust_pairing_requests
+#[frb(sync)]
+pub fn revoke_pairing_trust() {
+    toss_core::api::revoke_pairing_trust()
+}
+
+/// Always accept pairing requests from this device without asking
+#[frb(sync)]
+pub fn set_device_auto_accept_pairing(device_id: String, enabled: bool) -> Result<(), String> {
+    toss_core::api::set_device_auto_accept_pairing(device_id, enabled)
+}
+
 /// Find a device by pairing code (searches mDNS and relay server)
 #[frb]
 pub async fn find_pairing_device(code: String) -> Result<PairingDeviceDto, String> {
@@ -306,6 +1023,26 @@ pub fn complete_manual_pairing(
     toss_core::api::complete_manual_pairing(peer_public_key, peer_device_name).map(|d| d.into())
 }
 
+/// Complete pairing with a device found via the relay server and notify it
+#[frb]
+pub async fn complete_relay_pairing(
+    code: String,
+    peer_public_key: String,
+    peer_device_name: String,
+) -> Result<DeviceInfoDto, String> {
+    toss_core::api::complete_relay_pairing(code, peer_public_key, peer_device_name)
+        .await
+        .map(|d| d.into())
+}
+
+/// Rotate the pairing code and expiry, keeping the public key, and re-advertise it
+#[frb]
+pub async fn refresh_pairing_code() -> Result<PairingInfoDto, String> {
+    toss_core::api::refresh_pairing_code()
+        .await
+        .map(|p| p.into())
+}
+
 /// Register pairing code on relay server and via mDNS for discovery
 /// Returns the result indicating which methods succeeded/failed
 #[frb]
@@ -328,6 +1065,24 @@ pub fn get_paired_devices() -> Vec<DeviceInfoDto> {
         .collect()
 }
 
+/// Check which paired devices are reachable, without connecting to them
+#[frb]
+pub async fn probe_paired_devices() -> Vec<DeviceReachabilityDto> {
+    toss_core::api::probe_paired_devices()
+        .await
+        .into_iter()
+        .map(|d| d.into())
+        .collect()
+}
+
+/// Export a snapshot of the network state, for troubleshooting
+#[frb]
+pub async fn export_network_diagnostics() -> Result<NetworkDiagnosticsDto, String> {
+    toss_core::api::export_network_diagnostics()
+        .await
+        .map(|d| d.into())
+}
+
 /// Remove a paired device
 #[frb(sync)]
 pub fn remove_device(device_id: String) -> Result<(), String> {
@@ -340,6 +1095,18 @@ pub fn rename_device(device_id: String, new_name: String) -> Result<(), String>
     toss_core::api::rename_device(device_id, new_name)
 }
 
+/// Get the key algorithm, fingerprint and key state of a paired device
+#[frb(sync)]
+pub fn get_device_security_info(device_id: String) -> Result<DeviceSecurityDto, String> {
+    toss_core::api::get_device_security_info(device_id).map(Into::into)
+}
+
+/// Record that the user compared a device's fingerprint and it matched
+#[frb(sync)]
+pub fn mark_fingerprint_verified(device_id: String) -> Result<(), String> {
+    toss_core::api::mark_fingerprint_verified(device_id)
+}
+
 // ============================================================================
 // Clipboard Operations
 // ============================================================================
@@ -356,12 +1123,92 @@ pub async fn send_clipboard() -> Result<(), String> {
     toss_core::api::send_clipboard().await
 }
 
+/// Send current clipboard to one paired device
+#[frb]
+pub async fn send_clipboard_to_device(device_id: String) -> Result<(), String> {
+    toss_core::api::send_clipboard_to_device(device_id).await
+}
+
+/// Abort the clipboard sends in progress
+#[frb(sync)]
+pub fn cancel_send() {
+    toss_core::api::cancel_send()
+}
+
+/// Get the most recent reason a clipboard send was skipped or partly failed
+#[frb(sync)]
+pub fn last_skip_reason() -> Option<SkipReasonDto> {
+    toss_core::api::last_skip_reason().map(|s| s.into())
+}
+
 /// Send text to all devices
 #[frb]
 pub async fn send_text(text: String) -> Result<(), String> {
     toss_core::api::send_text(text).await
 }
 
+/// Send text to one paired device
+#[frb]
+pub async fn send_text_to_device(device_id: String, text: String) -> Result<(), String> {
+    toss_core::api::send_text_to_device(device_id, text).await
+}
+
+/// Send text to all devices as an explicit "text", "url" or "code" item
+#[frb]
+pub async fn send_text_as(
+    text: String,
+    content_type: String,
+    language: Option<String>,
+) -> Result<(), String> {
+    toss_core::api::send_text_as(text, content_type, language).await
+}
+
+/// Send an http(s) URL to all devices and ask those allowing remote actions to open it
+#[frb]
+pub async fn send_url_to_open(url: String) -> Result<(), String> {
+    toss_core::api::send_url_to_open(url).await
+}
+
+/// Current text of the shared scratchpad
+#[frb(sync)]
+pub fn get_scratchpad() -> String {
+    toss_core::api::get_scratchpad()
+}
+
+/// Replace the text of the shared scratchpad, sending the edit to paired devices
+#[frb]
+pub async fn set_scratchpad(text: String) -> Result<(), String> {
+    toss_core::api::set_scratchpad(text).await
+}
+
+/// Tell paired devices this device is "idle" or "copying", if presence sharing is on
+#[frb]
+pub async fn broadcast_presence(state: String) -> Result<(), String> {
+    toss_core::api::broadcast_presence(state).await
+}
+
+/// Report whether this device's screen is locked, telling paired devices when it changes
+#[frb]
+pub async fn set_local_lock_state(locked: bool) -> Result<(), String> {
+    toss_core::api::set_local_lock_state(locked).await
+}
+
+/// Send data from an OS share sheet to all devices without touching the clipboard
+#[frb]
+pub async fn share_content(
+    mime_type: String,
+    data: Vec<u8>,
+    suggested_name: Option<String>,
+) -> Result<(), String> {
+    toss_core::api::share_content(mime_type, data, suggested_name).await
+}
+
+/// Send a file on disk to all devices, streamed without loading it into memory
+#[frb]
+pub async fn send_file(path: String) -> Result<(), String> {
+    toss_core::api::send_file(path).await
+}
+
 /// Check if clipboard has changed since last check
 #[frb(sync)]
 pub fn check_clipboard_changed() -> bool {
@@ -381,7 +1228,69 @@ pub fn get_settings() -> TossSettings {
 /// Update settings
 #[frb(sync)]
 pub fn update_settings(settings: TossSettings) -> Result<(), String> {
-    toss_core::api::update_settings(settings.into())
+    toss_core::api::update_settings(settings.try_into()?)
+}
+
+/// Report the device's current offset from UTC in minutes
+#[frb(sync)]
+pub fn set_utc_offset(utc_offset_minutes: i32) -> Result<(), String> {
+    toss_core::api::set_utc_offset(utc_offset_minutes)
+}
+
+/// Get the local sync statistics
+#[frb(sync)]
+pub fn get_sync_stats() -> Result<SyncStatsDto, String> {
+    toss_core::api::get_sync_stats().map(|s| s.into())
+}
+
+/// Reset the local sync statistics to zero
+#[frb(sync)]
+pub fn reset_sync_stats() -> Result<(), String> {
+    toss_core::api::reset_sync_stats()
+}
+
+/// Get this month's relay and internet traffic per device
+#[frb(sync)]
+pub fn get_relay_usage() -> Result<Vec<RelayUsageDto>, String> {
+    toss_core::api::get_relay_usage().map(|usage| usage.into_iter().map(|u| u.into()).collect())
+}
+
+/// Reset every device's relay usage, lifting the monthly caps
+#[frb(sync)]
+pub fn reset_relay_usage() -> Result<(), String> {
+    toss_core::api::reset_relay_usage()
+}
+
+// ============================================================================
+// Logging
+// ============================================================================
+
+/// Change the log level at runtime (a level or full filter directives)
+#[frb(sync)]
+pub fn set_log_level(level: String) -> Result<(), String> {
+    toss_core::api::set_log_level(level)
+}
+
+/// Get up to `limit` of the most recent log lines, oldest first
+#[frb(sync)]
+pub fn get_recent_logs(limit: u32) -> Vec<String> {
+    toss_core::api::get_recent_logs(limit)
+}
+
+/// Write buffered log records to the log file now
+#[frb(sync)]
+pub fn flush_logs() {
+    toss_core::api::flush_logs()
+}
+
+// ============================================================================
+// Diagnostics
+// ============================================================================
+
+/// Run a self-test of secure storage, crypto, database, clipboard and relay
+#[frb]
+pub async fn run_self_test() -> Result<SelfTestReportDto, String> {
+    toss_core::api::run_self_test().await.map(|r| r.into())
 }
 
 // ============================================================================
@@ -400,18 +1309,73 @@ pub async fn stop_network() {
     toss_core::api::stop_network().await
 }
 
+/// Check a relay server and report its identity key for pinning
+#[frb]
+pub async fn test_relay(url: String) -> Result<RelayIdentityDto, String> {
+    toss_core::api::test_relay(url).await.map(|r| r.into())
+}
+
+/// Get how many messages the relay holds for this device
+#[frb]
+pub async fn get_relay_queue_status() -> Result<RelayQueueStatusDto, String> {
+    toss_core::api::get_relay_queue_status()
+        .await
+        .map(|q| q.into())
+}
+
+/// Drop every message the relay holds for this device
+#[frb]
+pub async fn flush_relay_queue() -> Result<u64, String> {
+    toss_core::api::flush_relay_queue().await
+}
+
+/// Notify that the OS network connectivity changed
+#[frb]
+pub async fn notify_network_changed() -> Result<(), String> {
+    toss_core::api::notify_network_changed().await
+}
+
+/// Transfers currently being sent or received
+#[frb(sync)]
+pub fn get_active_transfers() -> Vec<TransferProgressDto> {
+    toss_core::api::get_active_transfers()
+        .into_iter()
+        .map(|t| t.into())
+        .collect()
+}
+
+/// Cancel a transfer and notify the peer
+#[frb]
+pub async fn cancel_transfer(transfer_id: u64) -> Result<(), String> {
+    toss_core::api::cancel_transfer(transfer_id).await
+}
+
+/// Check that a connected device stored the same session key as this one
+#[frb]
+pub async fn verify_session_key(device_id: String) -> Result<bool, String> {
+    toss_core::api::verify_session_key(device_id).await
+}
+
 /// Start listening to network events
 #[frb]
 pub async fn start_event_listener() -> Result<(), String> {
     toss_core::api::start_event_listener().await
 }
 
-/// Poll for network events (polling-based approach until streams are available)
+/// Poll for network events (non-blocking)
 #[frb(sync)]
 pub fn poll_event() -> Option<TossEvent> {
     toss_core::api::poll_event().map(|e| e.into())
 }
 
+/// Stream events as they happen, until the stream is cancelled
+///
+/// Resubscribes on its own when the network restarts.
+#[frb]
+pub async fn listen_events(sink: StreamSink<TossEvent>) {
+    toss_core::api::listen_events(move |event| sink.add(event.into()).is_ok()).await
+}
+
 /// Get connected devices
 #[frb(sync)]
 pub fn get_connected_devices() -> Vec<DeviceInfoDto> {
@@ -421,6 +1385,12 @@ pub fn get_connected_devices() -> Vec<DeviceInfoDto> {
         .collect()
 }
 
+/// Get the last end-to-end sync latency to a device in milliseconds
+#[frb(sync)]
+pub fn get_last_sync_latency(device_id: String) -> Option<u64> {
+    toss_core::api::get_last_sync_latency(device_id)
+}
+
 // ============================================================================
 // History
 // ============================================================================
@@ -434,6 +1404,15 @@ pub fn get_clipboard_history(limit: Option<u32>) -> Vec<ClipboardItemDto> {
         .collect()
 }
 
+/// Get clipboard history grouped by local calendar day and source device
+#[frb(sync)]
+pub fn get_history_grouped(limit: Option<u32>, utc_offset_minutes: i32) -> Vec<HistoryGroupDto> {
+    toss_core::api::get_history_grouped(limit, utc_offset_minutes)
+        .into_iter()
+        .map(|g| g.into())
+        .collect()
+}
+
 /// Remove clipboard history item
 #[frb(sync)]
 pub fn remove_history_item(item_id: String) -> Result<(), String> {
@@ -446,12 +1425,69 @@ pub fn clear_clipboard_history() -> Result<(), String> {
     toss_core::api::clear_clipboard_history()
 }
 
+/// Delete history older than `history_days` now, returning how many items
+#[frb(sync)]
+pub fn prune_history_now() -> Result<u32, String> {
+    toss_core::api::prune_history_now()
+}
+
 /// Get decrypted clipboard content from history item
 #[frb(sync)]
 pub fn get_clipboard_history_content(item_id: String) -> Result<ClipboardContentDto, String> {
     toss_core::api::get_clipboard_history_content(item_id).map(|c| c.into())
 }
 
+/// Decrypt a history item straight into a file, returning the bytes written
+#[frb]
+pub async fn export_history_item_to_file(
+    item_id: String,
+    dest_path: String,
+) -> Result<u64, String> {
+    toss_core::api::export_history_item_to_file(item_id, dest_path).await
+}
+
+/// Export history items created after `after` as gzip-compressed JSON
+#[frb]
+pub async fn export_history_to_file(
+    after: u64,
+    limit: Option<u32>,
+    dest_path: String,
+) -> Result<HistoryExportDto, String> {
+    toss_core::api::export_history_to_file(after, limit, dest_path)
+        .await
+        .map(|e| e.into())
+}
+
+/// Find history items whose content can no longer be decrypted
+#[frb]
+pub async fn find_corrupt_history_items() -> Result<Vec<String>, String> {
+    toss_core::api::find_corrupt_history_items().await
+}
+
+/// Remove history items that can no longer be decrypted, returning how many
+#[frb]
+pub async fn remove_corrupt_history_items() -> Result<u32, String> {
+    toss_core::api::remove_corrupt_history_items().await
+}
+
+/// Get the full content of the live clipboard for export
+#[frb(sync)]
+pub fn get_current_clipboard_content() -> Result<ClipboardContentDto, String> {
+    toss_core::api::get_current_clipboard_content().map(|c| c.into())
+}
+
+/// Capture all clipboard formats as one history entry
+#[frb(sync)]
+pub fn capture_clipboard_snapshot() -> Result<ClipboardItemDto, String> {
+    toss_core::api::capture_clipboard_snapshot().map(|c| c.into())
+}
+
+/// Restore a history item to the clipboard with all its formats
+#[frb(sync)]
+pub fn restore_history_item(item_id: String) -> Result<(), String> {
+    toss_core::api::restore_history_item(item_id)
+}
+
 /// Decrypt and retrieve session key for a paired device
 #[frb(sync)]
 pub fn get_device_session_key(device_id: String) -> Result<Vec<u8>, String> {
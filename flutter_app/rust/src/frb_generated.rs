@@ -45,16 +45,17 @@ flutter_rust_bridge::frb_generated_default_handler!();
 
 // Section: wire_funcs
 
-fn wire__crate__api__cancel_pairing_impl(
+fn wire__crate__api__broadcast_presence_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
-) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "cancel_pairing",
-            port: None,
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+            debug_name: "broadcast_presence",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
         },
         move || {
             let message = unsafe {
@@ -66,24 +67,28 @@ fn wire__crate__api__cancel_pairing_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_state = <String>::sse_decode(&mut deserializer);
             deserializer.end();
-            transform_result_sse::<_, ()>((move || {
-                let output_ok = Result::<_, ()>::Ok({
-                    crate::api::cancel_pairing();
-                })?;
-                Ok(output_ok)
-            })())
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::broadcast_presence(api_state).await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
         },
     )
 }
-fn wire__crate__api__check_clipboard_changed_impl(
+fn wire__crate__api__cancel_find_pairing_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "check_clipboard_changed",
+            debug_name: "cancel_find_pairing",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -99,20 +104,22 @@ fn wire__crate__api__check_clipboard_changed_impl(
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
             deserializer.end();
             transform_result_sse::<_, ()>((move || {
-                let output_ok = Result::<_, ()>::Ok(crate::api::check_clipboard_changed())?;
+                let output_ok = Result::<_, ()>::Ok({
+                    crate::api::cancel_find_pairing();
+                })?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__clear_clipboard_history_impl(
+fn wire__crate__api__cancel_pairing_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "clear_clipboard_history",
+            debug_name: "cancel_pairing",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -127,21 +134,23 @@ fn wire__crate__api__clear_clipboard_history_impl(
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
             deserializer.end();
-            transform_result_sse::<_, String>((move || {
-                let output_ok = crate::api::clear_clipboard_history()?;
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok({
+                    crate::api::cancel_pairing();
+                })?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__complete_manual_pairing_impl(
+fn wire__crate__api__cancel_send_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "complete_manual_pairing",
+            debug_name: "cancel_send",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -155,27 +164,27 @@ fn wire__crate__api__complete_manual_pairing_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_peer_public_key = <String>::sse_decode(&mut deserializer);
-            let api_peer_device_name = <String>::sse_decode(&mut deserializer);
             deserializer.end();
-            transform_result_sse::<_, String>((move || {
-                let output_ok =
-                    crate::api::complete_manual_pairing(api_peer_public_key, api_peer_device_name)?;
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok({
+                    crate::api::cancel_send();
+                })?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__complete_pairing_code_impl(
+fn wire__crate__api__cancel_transfer_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
-) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "complete_pairing_code",
-            port: None,
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+            debug_name: "cancel_transfer",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
         },
         move || {
             let message = unsafe {
@@ -187,24 +196,28 @@ fn wire__crate__api__complete_pairing_code_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_code = <String>::sse_decode(&mut deserializer);
-            let api_peer_public_key = <Vec<u8>>::sse_decode(&mut deserializer);
+            let api_transfer_id = <u64>::sse_decode(&mut deserializer);
             deserializer.end();
-            transform_result_sse::<_, String>((move || {
-                let output_ok = crate::api::complete_pairing_code(api_code, api_peer_public_key)?;
-                Ok(output_ok)
-            })())
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::cancel_transfer(api_transfer_id).await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
         },
     )
 }
-fn wire__crate__api__complete_pairing_qr_impl(
+fn wire__crate__api__capture_clipboard_snapshot_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "complete_pairing_qr",
+            debug_name: "capture_clipboard_snapshot",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -218,26 +231,24 @@ fn wire__crate__api__complete_pairing_qr_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_qr_data = <String>::sse_decode(&mut deserializer);
             deserializer.end();
             transform_result_sse::<_, String>((move || {
-                let output_ok = crate::api::complete_pairing_qr(api_qr_data)?;
+                let output_ok = crate::api::capture_clipboard_snapshot()?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__find_pairing_device_impl(
-    port_: flutter_rust_bridge::for_generated::MessagePort,
+fn wire__crate__api__check_clipboard_changed_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
-) {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "find_pairing_device",
-            port: Some(port_),
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+            debug_name: "check_clipboard_changed",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
         move || {
             let message = unsafe {
@@ -249,28 +260,22 @@ fn wire__crate__api__find_pairing_device_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_code = <String>::sse_decode(&mut deserializer);
             deserializer.end();
-            move |context| async move {
-                transform_result_sse::<_, String>(
-                    (move || async move {
-                        let output_ok = crate::api::find_pairing_device(api_code).await?;
-                        Ok(output_ok)
-                    })()
-                    .await,
-                )
-            }
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::check_clipboard_changed())?;
+                Ok(output_ok)
+            })())
         },
     )
 }
-fn wire__crate__api__get_clipboard_history_impl(
+fn wire__crate__api__clear_clipboard_history_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "get_clipboard_history",
+            debug_name: "clear_clipboard_history",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -284,23 +289,22 @@ fn wire__crate__api__get_clipboard_history_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_limit = <Option<u32>>::sse_decode(&mut deserializer);
             deserializer.end();
-            transform_result_sse::<_, ()>((move || {
-                let output_ok = Result::<_, ()>::Ok(crate::api::get_clipboard_history(api_limit))?;
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::clear_clipboard_history()?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__get_clipboard_history_content_impl(
+fn wire__crate__api__complete_manual_pairing_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "get_clipboard_history_content",
+            debug_name: "complete_manual_pairing",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -314,23 +318,25 @@ fn wire__crate__api__get_clipboard_history_content_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_item_id = <String>::sse_decode(&mut deserializer);
+            let api_peer_public_key = <String>::sse_decode(&mut deserializer);
+            let api_peer_device_name = <String>::sse_decode(&mut deserializer);
             deserializer.end();
             transform_result_sse::<_, String>((move || {
-                let output_ok = crate::api::get_clipboard_history_content(api_item_id)?;
+                let output_ok =
+                    crate::api::complete_manual_pairing(api_peer_public_key, api_peer_device_name)?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__get_connected_devices_impl(
+fn wire__crate__api__complete_pairing_code_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "get_connected_devices",
+            debug_name: "complete_pairing_code",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -344,22 +350,29 @@ fn wire__crate__api__get_connected_devices_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_code = <String>::sse_decode(&mut deserializer);
+            let api_peer_public_key = <Vec<u8>>::sse_decode(&mut deserializer);
+            let api_peer_device_name = <Option<String>>::sse_decode(&mut deserializer);
             deserializer.end();
-            transform_result_sse::<_, ()>((move || {
-                let output_ok = Result::<_, ()>::Ok(crate::api::get_connected_devices())?;
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::complete_pairing_code(
+                    api_code,
+                    api_peer_public_key,
+                    api_peer_device_name,
+                )?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__get_current_clipboard_impl(
+fn wire__crate__api__complete_pairing_qr_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "get_current_clipboard",
+            debug_name: "complete_pairing_qr",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -373,24 +386,26 @@ fn wire__crate__api__get_current_clipboard_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_qr_data = <String>::sse_decode(&mut deserializer);
             deserializer.end();
-            transform_result_sse::<_, ()>((move || {
-                let output_ok = Result::<_, ()>::Ok(crate::api::get_current_clipboard())?;
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::complete_pairing_qr(api_qr_data)?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__get_device_id_impl(
+fn wire__crate__api__complete_relay_pairing_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
-) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "get_device_id",
-            port: None,
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+            debug_name: "complete_relay_pairing",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
         },
         move || {
             let message = unsafe {
@@ -402,22 +417,35 @@ fn wire__crate__api__get_device_id_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_code = <String>::sse_decode(&mut deserializer);
+            let api_peer_public_key = <String>::sse_decode(&mut deserializer);
+            let api_peer_device_name = <String>::sse_decode(&mut deserializer);
             deserializer.end();
-            transform_result_sse::<_, ()>((move || {
-                let output_ok = Result::<_, ()>::Ok(crate::api::get_device_id())?;
-                Ok(output_ok)
-            })())
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::complete_relay_pairing(
+                            api_code,
+                            api_peer_public_key,
+                            api_peer_device_name,
+                        )
+                        .await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
         },
     )
 }
-fn wire__crate__api__get_device_name_impl(
+fn wire__crate__api__confirm_pairing_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "get_device_name",
+            debug_name: "confirm_pairing",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -431,24 +459,26 @@ fn wire__crate__api__get_device_name_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_accept = <bool>::sse_decode(&mut deserializer);
             deserializer.end();
-            transform_result_sse::<_, ()>((move || {
-                let output_ok = Result::<_, ()>::Ok(crate::api::get_device_name())?;
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::confirm_pairing(api_accept)?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__get_device_session_key_impl(
+fn wire__crate__api__export_history_item_to_file_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
-) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "get_device_session_key",
-            port: None,
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+            debug_name: "export_history_item_to_file",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
         },
         move || {
             let message = unsafe {
@@ -460,25 +490,34 @@ fn wire__crate__api__get_device_session_key_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_device_id = <String>::sse_decode(&mut deserializer);
+            let api_item_id = <String>::sse_decode(&mut deserializer);
+            let api_dest_path = <String>::sse_decode(&mut deserializer);
             deserializer.end();
-            transform_result_sse::<_, String>((move || {
-                let output_ok = crate::api::get_device_session_key(api_device_id)?;
-                Ok(output_ok)
-            })())
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok =
+                            crate::api::export_history_item_to_file(api_item_id, api_dest_path)
+                                .await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
         },
     )
 }
-fn wire__crate__api__get_paired_devices_impl(
+fn wire__crate__api__export_history_to_file_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
-) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "get_paired_devices",
-            port: None,
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+            debug_name: "export_history_to_file",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
         },
         move || {
             let message = unsafe {
@@ -490,24 +529,35 @@ fn wire__crate__api__get_paired_devices_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_after = <u64>::sse_decode(&mut deserializer);
+            let api_limit = <Option<u32>>::sse_decode(&mut deserializer);
+            let api_dest_path = <String>::sse_decode(&mut deserializer);
             deserializer.end();
-            transform_result_sse::<_, ()>((move || {
-                let output_ok = Result::<_, ()>::Ok(crate::api::get_paired_devices())?;
-                Ok(output_ok)
-            })())
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok =
+                            crate::api::export_history_to_file(api_after, api_limit, api_dest_path)
+                                .await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
         },
     )
 }
-fn wire__crate__api__get_settings_impl(
+fn wire__crate__api__export_network_diagnostics_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
-) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "get_settings",
-            port: None,
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+            debug_name: "export_network_diagnostics",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
         },
         move || {
             let message = unsafe {
@@ -520,23 +570,29 @@ fn wire__crate__api__get_settings_impl(
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
             deserializer.end();
-            transform_result_sse::<_, ()>((move || {
-                let output_ok = Result::<_, ()>::Ok(crate::api::get_settings())?;
-                Ok(output_ok)
-            })())
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::export_network_diagnostics().await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
         },
     )
 }
-fn wire__crate__api__init_toss_impl(
+fn wire__crate__api__find_corrupt_history_items_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
-) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "init_toss",
-            port: None,
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+            debug_name: "find_corrupt_history_items",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
         },
         move || {
             let message = unsafe {
@@ -548,24 +604,63 @@ fn wire__crate__api__init_toss_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_data_dir = <String>::sse_decode(&mut deserializer);
-            let api_device_name = <String>::sse_decode(&mut deserializer);
             deserializer.end();
-            transform_result_sse::<_, String>((move || {
-                let output_ok = crate::api::init_toss(api_data_dir, api_device_name)?;
-                Ok(output_ok)
-            })())
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::find_corrupt_history_items().await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
         },
     )
 }
-fn wire__crate__api__poll_event_impl(
+fn wire__crate__api__find_pairing_device_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "find_pairing_device",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_code = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::find_pairing_device(api_code).await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__flush_logs_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "poll_event",
+            debug_name: "flush_logs",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -581,13 +676,15 @@ fn wire__crate__api__poll_event_impl(
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
             deserializer.end();
             transform_result_sse::<_, ()>((move || {
-                let output_ok = Result::<_, ()>::Ok(crate::api::poll_event())?;
+                let output_ok = Result::<_, ()>::Ok({
+                    crate::api::flush_logs();
+                })?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__register_pairing_advertisement_impl(
+fn wire__crate__api__flush_relay_queue_impl(
     port_: flutter_rust_bridge::for_generated::MessagePort,
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
@@ -595,7 +692,7 @@ fn wire__crate__api__register_pairing_advertisement_impl(
 ) {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "register_pairing_advertisement",
+            debug_name: "flush_relay_queue",
             port: Some(port_),
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
         },
@@ -613,7 +710,7 @@ fn wire__crate__api__register_pairing_advertisement_impl(
             move |context| async move {
                 transform_result_sse::<_, String>(
                     (move || async move {
-                        let output_ok = crate::api::register_pairing_advertisement().await?;
+                        let output_ok = crate::api::flush_relay_queue().await?;
                         Ok(output_ok)
                     })()
                     .await,
@@ -622,14 +719,14 @@ fn wire__crate__api__register_pairing_advertisement_impl(
         },
     )
 }
-fn wire__crate__api__remove_device_impl(
+fn wire__crate__api__get_active_transfers_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "remove_device",
+            debug_name: "get_active_transfers",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -643,23 +740,22 @@ fn wire__crate__api__remove_device_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_device_id = <String>::sse_decode(&mut deserializer);
             deserializer.end();
-            transform_result_sse::<_, String>((move || {
-                let output_ok = crate::api::remove_device(api_device_id)?;
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::get_active_transfers())?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__remove_history_item_impl(
+fn wire__crate__api__get_clipboard_history_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "remove_history_item",
+            debug_name: "get_clipboard_history",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -673,23 +769,23 @@ fn wire__crate__api__remove_history_item_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_item_id = <String>::sse_decode(&mut deserializer);
+            let api_limit = <Option<u32>>::sse_decode(&mut deserializer);
             deserializer.end();
-            transform_result_sse::<_, String>((move || {
-                let output_ok = crate::api::remove_history_item(api_item_id)?;
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::get_clipboard_history(api_limit))?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__rename_device_impl(
+fn wire__crate__api__get_clipboard_history_content_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "rename_device",
+            debug_name: "get_clipboard_history_content",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -703,27 +799,25 @@ fn wire__crate__api__rename_device_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_device_id = <String>::sse_decode(&mut deserializer);
-            let api_new_name = <String>::sse_decode(&mut deserializer);
+            let api_item_id = <String>::sse_decode(&mut deserializer);
             deserializer.end();
             transform_result_sse::<_, String>((move || {
-                let output_ok = crate::api::rename_device(api_device_id, api_new_name)?;
+                let output_ok = crate::api::get_clipboard_history_content(api_item_id)?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__send_clipboard_impl(
-    port_: flutter_rust_bridge::for_generated::MessagePort,
+fn wire__crate__api__get_connected_devices_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
-) {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "send_clipboard",
-            port: Some(port_),
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+            debug_name: "get_connected_devices",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
         move || {
             let message = unsafe {
@@ -736,29 +830,23 @@ fn wire__crate__api__send_clipboard_impl(
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
             deserializer.end();
-            move |context| async move {
-                transform_result_sse::<_, String>(
-                    (move || async move {
-                        let output_ok = crate::api::send_clipboard().await?;
-                        Ok(output_ok)
-                    })()
-                    .await,
-                )
-            }
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::get_connected_devices())?;
+                Ok(output_ok)
+            })())
         },
     )
 }
-fn wire__crate__api__send_text_impl(
-    port_: flutter_rust_bridge::for_generated::MessagePort,
+fn wire__crate__api__get_current_clipboard_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
-) {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "send_text",
-            port: Some(port_),
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+            debug_name: "get_current_clipboard",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
         move || {
             let message = unsafe {
@@ -770,28 +858,22 @@ fn wire__crate__api__send_text_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_text = <String>::sse_decode(&mut deserializer);
             deserializer.end();
-            move |context| async move {
-                transform_result_sse::<_, String>(
-                    (move || async move {
-                        let output_ok = crate::api::send_text(api_text).await?;
-                        Ok(output_ok)
-                    })()
-                    .await,
-                )
-            }
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::get_current_clipboard())?;
+                Ok(output_ok)
+            })())
         },
     )
 }
-fn wire__crate__api__set_device_name_impl(
+fn wire__crate__api__get_current_clipboard_content_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "set_device_name",
+            debug_name: "get_current_clipboard_content",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -805,26 +887,24 @@ fn wire__crate__api__set_device_name_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_name = <String>::sse_decode(&mut deserializer);
             deserializer.end();
             transform_result_sse::<_, String>((move || {
-                let output_ok = crate::api::set_device_name(api_name)?;
+                let output_ok = crate::api::get_current_clipboard_content()?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__shutdown_toss_impl(
-    port_: flutter_rust_bridge::for_generated::MessagePort,
+fn wire__crate__api__get_device_fingerprint_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
-) {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "shutdown_toss",
-            port: Some(port_),
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+            debug_name: "get_device_fingerprint",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
         move || {
             let message = unsafe {
@@ -837,31 +917,23 @@ fn wire__crate__api__shutdown_toss_impl(
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
             deserializer.end();
-            move |context| async move {
-                transform_result_sse::<_, ()>(
-                    (move || async move {
-                        let output_ok = Result::<_, ()>::Ok({
-                            crate::api::shutdown_toss().await;
-                        })?;
-                        Ok(output_ok)
-                    })()
-                    .await,
-                )
-            }
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::get_device_fingerprint())?;
+                Ok(output_ok)
+            })())
         },
     )
 }
-fn wire__crate__api__start_event_listener_impl(
-    port_: flutter_rust_bridge::for_generated::MessagePort,
+fn wire__crate__api__get_device_id_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
-) {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "start_event_listener",
-            port: Some(port_),
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+            debug_name: "get_device_id",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
         move || {
             let message = unsafe {
@@ -874,29 +946,23 @@ fn wire__crate__api__start_event_listener_impl(
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
             deserializer.end();
-            move |context| async move {
-                transform_result_sse::<_, String>(
-                    (move || async move {
-                        let output_ok = crate::api::start_event_listener().await?;
-                        Ok(output_ok)
-                    })()
-                    .await,
-                )
-            }
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::get_device_id())?;
+                Ok(output_ok)
+            })())
         },
     )
 }
-fn wire__crate__api__start_network_impl(
-    port_: flutter_rust_bridge::for_generated::MessagePort,
+fn wire__crate__api__get_device_name_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
-) {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "start_network",
-            port: Some(port_),
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+            debug_name: "get_device_name",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
         move || {
             let message = unsafe {
@@ -909,26 +975,21 @@ fn wire__crate__api__start_network_impl(
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
             deserializer.end();
-            move |context| async move {
-                transform_result_sse::<_, String>(
-                    (move || async move {
-                        let output_ok = crate::api::start_network().await?;
-                        Ok(output_ok)
-                    })()
-                    .await,
-                )
-            }
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::get_device_name())?;
+                Ok(output_ok)
+            })())
         },
     )
 }
-fn wire__crate__api__start_pairing_impl(
+fn wire__crate__api__get_device_security_info_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "start_pairing",
+            debug_name: "get_device_security_info",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -942,25 +1003,25 @@ fn wire__crate__api__start_pairing_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_device_id = <String>::sse_decode(&mut deserializer);
             deserializer.end();
             transform_result_sse::<_, String>((move || {
-                let output_ok = crate::api::start_pairing()?;
+                let output_ok = crate::api::get_device_security_info(api_device_id)?;
                 Ok(output_ok)
             })())
         },
     )
 }
-fn wire__crate__api__stop_network_impl(
-    port_: flutter_rust_bridge::for_generated::MessagePort,
+fn wire__crate__api__get_device_session_key_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
-) {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "stop_network",
-            port: Some(port_),
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+            debug_name: "get_device_session_key",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
         move || {
             let message = unsafe {
@@ -972,29 +1033,23 @@ fn wire__crate__api__stop_network_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_device_id = <String>::sse_decode(&mut deserializer);
             deserializer.end();
-            move |context| async move {
-                transform_result_sse::<_, ()>(
-                    (move || async move {
-                        let output_ok = Result::<_, ()>::Ok({
-                            crate::api::stop_network().await;
-                        })?;
-                        Ok(output_ok)
-                    })()
-                    .await,
-                )
-            }
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::get_device_session_key(api_device_id)?;
+                Ok(output_ok)
+            })())
         },
     )
 }
-fn wire__crate__api__update_settings_impl(
+fn wire__crate__api__get_history_grouped_impl(
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
     rust_vec_len_: i32,
     data_len_: i32,
 ) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
         flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "update_settings",
+            debug_name: "get_history_grouped",
             port: None,
             mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
         },
@@ -1008,486 +1063,3444 @@ fn wire__crate__api__update_settings_impl(
             };
             let mut deserializer =
                 flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_settings = <crate::api::TossSettings>::sse_decode(&mut deserializer);
+            let api_limit = <Option<u32>>::sse_decode(&mut deserializer);
+            let api_utc_offset_minutes = <i32>::sse_decode(&mut deserializer);
             deserializer.end();
-            transform_result_sse::<_, String>((move || {
-                let output_ok = crate::api::update_settings(api_settings)?;
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::get_history_grouped(
+                    api_limit,
+                    api_utc_offset_minutes,
+                ))?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__get_last_sync_latency_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "get_last_sync_latency",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_device_id = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, ()>((move || {
+                let output_ok =
+                    Result::<_, ()>::Ok(crate::api::get_last_sync_latency(api_device_id))?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__get_limits_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "get_limits",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::get_limits())?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__get_paired_devices_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "get_paired_devices",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::get_paired_devices())?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__get_recent_logs_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "get_recent_logs",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_limit = <u32>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::get_recent_logs(api_limit))?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__get_relay_queue_status_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "get_relay_queue_status",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::get_relay_queue_status().await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__get_relay_usage_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "get_relay_usage",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::get_relay_usage()?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__get_scratchpad_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "get_scratchpad",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::get_scratchpad())?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__get_settings_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "get_settings",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::get_settings())?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__get_sync_stats_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "get_sync_stats",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::get_sync_stats()?;
                 Ok(output_ok)
             })())
         },
     )
 }
+fn wire__crate__api__init_toss_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "init_toss",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_data_dir = <String>::sse_decode(&mut deserializer);
+            let api_device_name = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::init_toss(api_data_dir, api_device_name)?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__is_storage_persistent_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "is_storage_persistent",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::is_storage_persistent()?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__last_skip_reason_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "last_skip_reason",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::last_skip_reason())?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__listen_events_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "listen_events",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_sink = <StreamSink<
+                crate::api::TossEvent,
+                flutter_rust_bridge::for_generated::SseCodec,
+            >>::sse_decode(&mut deserializer);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, ()>(
+                    (move || async move {
+                        let output_ok = Result::<_, ()>::Ok({
+                            crate::api::listen_events(api_sink).await;
+                        })?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__mark_fingerprint_verified_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "mark_fingerprint_verified",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_device_id = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::mark_fingerprint_verified(api_device_id)?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__notify_network_changed_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "notify_network_changed",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::notify_network_changed().await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__parse_pairing_qr_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "parse_pairing_qr",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_qr_data = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::parse_pairing_qr(api_qr_data)?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__poll_event_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "poll_event",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok(crate::api::poll_event())?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__probe_paired_devices_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "probe_paired_devices",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, ()>(
+                    (move || async move {
+                        let output_ok =
+                            Result::<_, ()>::Ok(crate::api::probe_paired_devices().await)?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__prune_history_now_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "prune_history_now",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::prune_history_now()?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__refresh_pairing_code_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "refresh_pairing_code",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::refresh_pairing_code().await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__register_pairing_advertisement_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "register_pairing_advertisement",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::register_pairing_advertisement().await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__remove_corrupt_history_items_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "remove_corrupt_history_items",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::remove_corrupt_history_items().await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__remove_device_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "remove_device",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_device_id = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::remove_device(api_device_id)?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__remove_history_item_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "remove_history_item",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_item_id = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::remove_history_item(api_item_id)?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__rename_device_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "rename_device",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_device_id = <String>::sse_decode(&mut deserializer);
+            let api_new_name = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::rename_device(api_device_id, api_new_name)?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__reset_relay_usage_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "reset_relay_usage",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::reset_relay_usage()?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__reset_sync_stats_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "reset_sync_stats",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::reset_sync_stats()?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__restore_history_item_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "restore_history_item",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_item_id = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::restore_history_item(api_item_id)?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__revoke_pairing_trust_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "revoke_pairing_trust",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok({
+                    crate::api::revoke_pairing_trust();
+                })?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__rotate_identity_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "rotate_identity",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::rotate_identity().await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__run_self_test_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "run_self_test",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::run_self_test().await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__send_clipboard_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "send_clipboard",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::send_clipboard().await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__send_clipboard_to_device_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "send_clipboard_to_device",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_device_id = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::send_clipboard_to_device(api_device_id).await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__send_file_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "send_file",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_path = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::send_file(api_path).await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__send_text_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "send_text",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_text = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::send_text(api_text).await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__send_text_as_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "send_text_as",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_text = <String>::sse_decode(&mut deserializer);
+            let api_content_type = <String>::sse_decode(&mut deserializer);
+            let api_language = <Option<String>>::sse_decode(&mut deserializer);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok =
+                            crate::api::send_text_as(api_text, api_content_type, api_language)
+                                .await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__send_text_to_device_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "send_text_to_device",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_device_id = <String>::sse_decode(&mut deserializer);
+            let api_text = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok =
+                            crate::api::send_text_to_device(api_device_id, api_text).await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__send_url_to_open_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "send_url_to_open",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_url = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::send_url_to_open(api_url).await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__set_device_auto_accept_pairing_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "set_device_auto_accept_pairing",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_device_id = <String>::sse_decode(&mut deserializer);
+            let api_enabled = <bool>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok =
+                    crate::api::set_device_auto_accept_pairing(api_device_id, api_enabled)?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__set_device_name_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "set_device_name",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_name = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::set_device_name(api_name)?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__set_local_lock_state_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "set_local_lock_state",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_locked = <bool>::sse_decode(&mut deserializer);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::set_local_lock_state(api_locked).await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__set_log_level_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "set_log_level",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_level = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::set_log_level(api_level)?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__set_scratchpad_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "set_scratchpad",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_text = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::set_scratchpad(api_text).await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__set_storage_fallback_enabled_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "set_storage_fallback_enabled",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_enabled = <bool>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, ()>((move || {
+                let output_ok = Result::<_, ()>::Ok({
+                    crate::api::set_storage_fallback_enabled(api_enabled);
+                })?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__set_utc_offset_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "set_utc_offset",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_utc_offset_minutes = <i32>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::set_utc_offset(api_utc_offset_minutes)?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__share_content_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "share_content",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_mime_type = <String>::sse_decode(&mut deserializer);
+            let api_data = <Vec<u8>>::sse_decode(&mut deserializer);
+            let api_suggested_name = <Option<String>>::sse_decode(&mut deserializer);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok =
+                            crate::api::share_content(api_mime_type, api_data, api_suggested_name)
+                                .await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__shutdown_toss_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "shutdown_toss",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, ()>(
+                    (move || async move {
+                        let output_ok = Result::<_, ()>::Ok({
+                            crate::api::shutdown_toss().await;
+                        })?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__start_event_listener_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "start_event_listener",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::start_event_listener().await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__start_network_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "start_network",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::start_network().await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__start_pairing_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "start_pairing",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::start_pairing()?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__stop_network_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "stop_network",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, ()>(
+                    (move || async move {
+                        let output_ok = Result::<_, ()>::Ok({
+                            crate::api::stop_network().await;
+                        })?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__test_relay_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "test_relay",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_url = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::test_relay(api_url).await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__trust_pairing_requests_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "trust_pairing_requests",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_duration_secs = <Option<u32>>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::trust_pairing_requests(api_duration_secs)?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__update_settings_impl(
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_sync::<flutter_rust_bridge::for_generated::SseCodec, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "update_settings",
+            port: None,
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Sync,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_settings = <crate::api::TossSettings>::sse_decode(&mut deserializer);
+            deserializer.end();
+            transform_result_sse::<_, String>((move || {
+                let output_ok = crate::api::update_settings(api_settings)?;
+                Ok(output_ok)
+            })())
+        },
+    )
+}
+fn wire__crate__api__verify_session_key_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "verify_session_key",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            let api_device_id = <String>::sse_decode(&mut deserializer);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::verify_session_key(api_device_id).await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+fn wire__crate__api__wipe_all_data_impl(
+    port_: flutter_rust_bridge::for_generated::MessagePort,
+    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len_: i32,
+    data_len_: i32,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap_async::<flutter_rust_bridge::for_generated::SseCodec, _, _, _>(
+        flutter_rust_bridge::for_generated::TaskInfo {
+            debug_name: "wipe_all_data",
+            port: Some(port_),
+            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
+        },
+        move || {
+            let message = unsafe {
+                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
+                    ptr_,
+                    rust_vec_len_,
+                    data_len_,
+                )
+            };
+            let mut deserializer =
+                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
+            deserializer.end();
+            move |context| async move {
+                transform_result_sse::<_, String>(
+                    (move || async move {
+                        let output_ok = crate::api::wipe_all_data().await?;
+                        Ok(output_ok)
+                    })()
+                    .await,
+                )
+            }
+        },
+    )
+}
+
+// Section: dart2rust
+
+impl SseDecode for StreamSink<crate::api::TossEvent, flutter_rust_bridge::for_generated::SseCodec> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut inner = <String>::sse_decode(deserializer);
+        return StreamSink::deserialize(inner);
+    }
+}
+
+impl SseDecode for String {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut inner = <Vec<u8>>::sse_decode(deserializer);
+        return String::from_utf8(inner).unwrap();
+    }
+}
+
+impl SseDecode for crate::api::AdvertisementResultDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_mdnsRegistered = <bool>::sse_decode(deserializer);
+        let mut var_relayRegistered = <bool>::sse_decode(deserializer);
+        let mut var_mdnsError = <Option<String>>::sse_decode(deserializer);
+        let mut var_relayError = <Option<String>>::sse_decode(deserializer);
+        return crate::api::AdvertisementResultDto {
+            mdns_registered: var_mdnsRegistered,
+            relay_registered: var_relayRegistered,
+            mdns_error: var_mdnsError,
+            relay_error: var_relayError,
+        };
+    }
+}
+
+impl SseDecode for bool {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        deserializer.cursor.read_u8().unwrap() != 0
+    }
+}
+
+impl SseDecode for crate::api::ClipboardContentDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_contentType = <String>::sse_decode(deserializer);
+        let mut var_data = <Vec<u8>>::sse_decode(deserializer);
+        return crate::api::ClipboardContentDto {
+            content_type: var_contentType,
+            data: var_data,
+        };
+    }
+}
+
+impl SseDecode for crate::api::ClipboardItemDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_id = <String>::sse_decode(deserializer);
+        let mut var_contentType = <String>::sse_decode(deserializer);
+        let mut var_mimeType = <String>::sse_decode(deserializer);
+        let mut var_preview = <String>::sse_decode(deserializer);
+        let mut var_sizeBytes = <u64>::sse_decode(deserializer);
+        let mut var_timestamp = <u64>::sse_decode(deserializer);
+        let mut var_sourceDevice = <Option<String>>::sse_decode(deserializer);
+        let mut var_language = <Option<String>>::sse_decode(deserializer);
+        let mut var_superseded = <bool>::sse_decode(deserializer);
+        return crate::api::ClipboardItemDto {
+            id: var_id,
+            content_type: var_contentType,
+            mime_type: var_mimeType,
+            preview: var_preview,
+            size_bytes: var_sizeBytes,
+            timestamp: var_timestamp,
+            source_device: var_sourceDevice,
+            language: var_language,
+            superseded: var_superseded,
+        };
+    }
+}
+
+impl SseDecode for crate::api::ConnectedPeerDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_deviceId = <String>::sse_decode(deserializer);
+        let mut var_deviceName = <String>::sse_decode(deserializer);
+        let mut var_addresses = <Vec<String>>::sse_decode(deserializer);
+        let mut var_transport = <String>::sse_decode(deserializer);
+        let mut var_route = <String>::sse_decode(deserializer);
+        let mut var_rttMs = <u64>::sse_decode(deserializer);
+        return crate::api::ConnectedPeerDto {
+            device_id: var_deviceId,
+            device_name: var_deviceName,
+            addresses: var_addresses,
+            transport: var_transport,
+            route: var_route,
+            rtt_ms: var_rttMs,
+        };
+    }
+}
+
+impl SseDecode for crate::api::DeviceInfoDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_id = <String>::sse_decode(deserializer);
+        let mut var_name = <String>::sse_decode(deserializer);
+        let mut var_isOnline = <bool>::sse_decode(deserializer);
+        let mut var_lastSeen = <u64>::sse_decode(deserializer);
+        let mut var_platform = <String>::sse_decode(deserializer);
+        return crate::api::DeviceInfoDto {
+            id: var_id,
+            name: var_name,
+            is_online: var_isOnline,
+            last_seen: var_lastSeen,
+            platform: var_platform,
+        };
+    }
+}
+
+impl SseDecode for crate::api::DeviceReachabilityDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_id = <String>::sse_decode(deserializer);
+        let mut var_name = <String>::sse_decode(deserializer);
+        let mut var_reachable = <bool>::sse_decode(deserializer);
+        let mut var_path = <Option<String>>::sse_decode(deserializer);
+        return crate::api::DeviceReachabilityDto {
+            id: var_id,
+            name: var_name,
+            reachable: var_reachable,
+            path: var_path,
+        };
+    }
+}
+
+impl SseDecode for crate::api::DeviceSecurityDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_deviceId = <String>::sse_decode(deserializer);
+        let mut var_keyAlgorithm = <String>::sse_decode(deserializer);
+        let mut var_fingerprint = <String>::sse_decode(deserializer);
+        let mut var_fingerprintVerified = <bool>::sse_decode(deserializer);
+        let mut var_fingerprintVerifiedAt = <Option<u64>>::sse_decode(deserializer);
+        let mut var_sessionKeySince = <Option<u64>>::sse_decode(deserializer);
+        let mut var_lastAuthenticatedAt = <Option<u64>>::sse_decode(deserializer);
+        return crate::api::DeviceSecurityDto {
+            device_id: var_deviceId,
+            key_algorithm: var_keyAlgorithm,
+            fingerprint: var_fingerprint,
+            fingerprint_verified: var_fingerprintVerified,
+            fingerprint_verified_at: var_fingerprintVerifiedAt,
+            session_key_since: var_sessionKeySince,
+            last_authenticated_at: var_lastAuthenticatedAt,
+        };
+    }
+}
+
+impl SseDecode for crate::api::DiscoveredPeerDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_deviceId = <String>::sse_decode(deserializer);
+        let mut var_deviceName = <String>::sse_decode(deserializer);
+        let mut var_addresses = <Vec<String>>::sse_decode(deserializer);
+        let mut var_version = <String>::sse_decode(deserializer);
+        return crate::api::DiscoveredPeerDto {
+            device_id: var_deviceId,
+            device_name: var_deviceName,
+            addresses: var_addresses,
+            version: var_version,
+        };
+    }
+}
+
+impl SseDecode for f64 {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        deserializer.cursor.read_f64::<NativeEndian>().unwrap()
+    }
+}
+
+impl SseDecode for crate::api::HistoryExportDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_count = <u32>::sse_decode(deserializer);
+        let mut var_nextAfter = <Option<u64>>::sse_decode(deserializer);
+        return crate::api::HistoryExportDto {
+            count: var_count,
+            next_after: var_nextAfter,
+        };
+    }
+}
+
+impl SseDecode for crate::api::HistoryGroupDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_day = <String>::sse_decode(deserializer);
+        let mut var_sourceDevice = <Option<String>>::sse_decode(deserializer);
+        let mut var_items = <Vec<crate::api::ClipboardItemDto>>::sse_decode(deserializer);
+        return crate::api::HistoryGroupDto {
+            day: var_day,
+            source_device: var_sourceDevice,
+            items: var_items,
+        };
+    }
+}
+
+impl SseDecode for i32 {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        deserializer.cursor.read_i32::<NativeEndian>().unwrap()
+    }
+}
+
+impl SseDecode for i64 {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        deserializer.cursor.read_i64::<NativeEndian>().unwrap()
+    }
+}
+
+impl SseDecode for crate::api::IceCandidateDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_candidateType = <String>::sse_decode(deserializer);
+        let mut var_address = <String>::sse_decode(deserializer);
+        let mut var_priority = <u64>::sse_decode(deserializer);
+        return crate::api::IceCandidateDto {
+            candidate_type: var_candidateType,
+            address: var_address,
+            priority: var_priority,
+        };
+    }
+}
+
+impl SseDecode for crate::api::IdentityRotationDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_deviceId = <String>::sse_decode(deserializer);
+        let mut var_migrated = <Vec<String>>::sse_decode(deserializer);
+        let mut var_needsRepair = <Vec<String>>::sse_decode(deserializer);
+        return crate::api::IdentityRotationDto {
+            device_id: var_deviceId,
+            migrated: var_migrated,
+            needs_repair: var_needsRepair,
+        };
+    }
+}
+
+impl SseDecode for crate::api::LimitsDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_maxMessageSize = <u64>::sse_decode(deserializer);
+        let mut var_maxPreviewSize = <u64>::sse_decode(deserializer);
+        let mut var_protocolVersion = <u32>::sse_decode(deserializer);
+        let mut var_pairingCodeLength = <u32>::sse_decode(deserializer);
+        let mut var_pairingCodeFormat = <String>::sse_decode(deserializer);
+        let mut var_maxDeviceNameLength = <u32>::sse_decode(deserializer);
+        return crate::api::LimitsDto {
+            max_message_size: var_maxMessageSize,
+            max_preview_size: var_maxPreviewSize,
+            protocol_version: var_protocolVersion,
+            pairing_code_length: var_pairingCodeLength,
+            pairing_code_format: var_pairingCodeFormat,
+            max_device_name_length: var_maxDeviceNameLength,
+        };
+    }
+}
+
+impl SseDecode for Vec<String> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut len_ = <i32>::sse_decode(deserializer);
+        let mut ans_ = vec![];
+        for idx_ in 0..len_ {
+            ans_.push(<String>::sse_decode(deserializer));
+        }
+        return ans_;
+    }
+}
+
+impl SseDecode for Vec<crate::api::ClipboardItemDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut len_ = <i32>::sse_decode(deserializer);
+        let mut ans_ = vec![];
+        for idx_ in 0..len_ {
+            ans_.push(<crate::api::ClipboardItemDto>::sse_decode(deserializer));
+        }
+        return ans_;
+    }
+}
+
+impl SseDecode for Vec<crate::api::ConnectedPeerDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut len_ = <i32>::sse_decode(deserializer);
+        let mut ans_ = vec![];
+        for idx_ in 0..len_ {
+            ans_.push(<crate::api::ConnectedPeerDto>::sse_decode(deserializer));
+        }
+        return ans_;
+    }
+}
+
+impl SseDecode for Vec<crate::api::DeviceInfoDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut len_ = <i32>::sse_decode(deserializer);
+        let mut ans_ = vec![];
+        for idx_ in 0..len_ {
+            ans_.push(<crate::api::DeviceInfoDto>::sse_decode(deserializer));
+        }
+        return ans_;
+    }
+}
+
+impl SseDecode for Vec<crate::api::DeviceReachabilityDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut len_ = <i32>::sse_decode(deserializer);
+        let mut ans_ = vec![];
+        for idx_ in 0..len_ {
+            ans_.push(<crate::api::DeviceReachabilityDto>::sse_decode(
+                deserializer,
+            ));
+        }
+        return ans_;
+    }
+}
+
+impl SseDecode for Vec<crate::api::DiscoveredPeerDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut len_ = <i32>::sse_decode(deserializer);
+        let mut ans_ = vec![];
+        for idx_ in 0..len_ {
+            ans_.push(<crate::api::DiscoveredPeerDto>::sse_decode(deserializer));
+        }
+        return ans_;
+    }
+}
+
+impl SseDecode for Vec<crate::api::HistoryGroupDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut len_ = <i32>::sse_decode(deserializer);
+        let mut ans_ = vec![];
+        for idx_ in 0..len_ {
+            ans_.push(<crate::api::HistoryGroupDto>::sse_decode(deserializer));
+        }
+        return ans_;
+    }
+}
+
+impl SseDecode for Vec<crate::api::IceCandidateDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut len_ = <i32>::sse_decode(deserializer);
+        let mut ans_ = vec![];
+        for idx_ in 0..len_ {
+            ans_.push(<crate::api::IceCandidateDto>::sse_decode(deserializer));
+        }
+        return ans_;
+    }
+}
+
+impl SseDecode for Vec<u8> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut len_ = <i32>::sse_decode(deserializer);
+        let mut ans_ = vec![];
+        for idx_ in 0..len_ {
+            ans_.push(<u8>::sse_decode(deserializer));
+        }
+        return ans_;
+    }
+}
+
+impl SseDecode for Vec<crate::api::RelayUsageDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut len_ = <i32>::sse_decode(deserializer);
+        let mut ans_ = vec![];
+        for idx_ in 0..len_ {
+            ans_.push(<crate::api::RelayUsageDto>::sse_decode(deserializer));
+        }
+        return ans_;
+    }
+}
+
+impl SseDecode for Vec<crate::api::SelfTestCheckDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut len_ = <i32>::sse_decode(deserializer);
+        let mut ans_ = vec![];
+        for idx_ in 0..len_ {
+            ans_.push(<crate::api::SelfTestCheckDto>::sse_decode(deserializer));
+        }
+        return ans_;
+    }
+}
+
+impl SseDecode for Vec<crate::api::SyncFailureCountDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut len_ = <i32>::sse_decode(deserializer);
+        let mut ans_ = vec![];
+        for idx_ in 0..len_ {
+            ans_.push(<crate::api::SyncFailureCountDto>::sse_decode(deserializer));
+        }
+        return ans_;
+    }
+}
+
+impl SseDecode for Vec<crate::api::TransferProgressDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut len_ = <i32>::sse_decode(deserializer);
+        let mut ans_ = vec![];
+        for idx_ in 0..len_ {
+            ans_.push(<crate::api::TransferProgressDto>::sse_decode(deserializer));
+        }
+        return ans_;
+    }
+}
+
+impl SseDecode for crate::api::NetworkDiagnosticsDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_localAddr = <Option<String>>::sse_decode(deserializer);
+        let mut var_mdnsStatus = <String>::sse_decode(deserializer);
+        let mut var_discoveredPeers =
+            <Vec<crate::api::DiscoveredPeerDto>>::sse_decode(deserializer);
+        let mut var_connectedPeers = <Vec<crate::api::ConnectedPeerDto>>::sse_decode(deserializer);
+        let mut var_relayStatus = <String>::sse_decode(deserializer);
+        let mut var_relayUrl = <Option<String>>::sse_decode(deserializer);
+        let mut var_natType = <String>::sse_decode(deserializer);
+        let mut var_candidates = <Vec<crate::api::IceCandidateDto>>::sse_decode(deserializer);
+        return crate::api::NetworkDiagnosticsDto {
+            local_addr: var_localAddr,
+            mdns_status: var_mdnsStatus,
+            discovered_peers: var_discoveredPeers,
+            connected_peers: var_connectedPeers,
+            relay_status: var_relayStatus,
+            relay_url: var_relayUrl,
+            nat_type: var_natType,
+            candidates: var_candidates,
+        };
+    }
+}
+
+impl SseDecode for Option<String> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        if (<bool>::sse_decode(deserializer)) {
+            return Some(<String>::sse_decode(deserializer));
+        } else {
+            return None;
+        }
+    }
+}
+
+impl SseDecode for Option<bool> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        if (<bool>::sse_decode(deserializer)) {
+            return Some(<bool>::sse_decode(deserializer));
+        } else {
+            return None;
+        }
+    }
+}
+
+impl SseDecode for Option<crate::api::ClipboardItemDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        if (<bool>::sse_decode(deserializer)) {
+            return Some(<crate::api::ClipboardItemDto>::sse_decode(deserializer));
+        } else {
+            return None;
+        }
+    }
+}
+
+impl SseDecode for Option<crate::api::DeviceInfoDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        if (<bool>::sse_decode(deserializer)) {
+            return Some(<crate::api::DeviceInfoDto>::sse_decode(deserializer));
+        } else {
+            return None;
+        }
+    }
+}
+
+impl SseDecode for Option<i64> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        if (<bool>::sse_decode(deserializer)) {
+            return Some(<i64>::sse_decode(deserializer));
+        } else {
+            return None;
+        }
+    }
+}
+
+impl SseDecode for Option<crate::api::SkipReasonDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        if (<bool>::sse_decode(deserializer)) {
+            return Some(<crate::api::SkipReasonDto>::sse_decode(deserializer));
+        } else {
+            return None;
+        }
+    }
+}
+
+impl SseDecode for Option<crate::api::TossEvent> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        if (<bool>::sse_decode(deserializer)) {
+            return Some(<crate::api::TossEvent>::sse_decode(deserializer));
+        } else {
+            return None;
+        }
+    }
+}
+
+impl SseDecode for Option<u32> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        if (<bool>::sse_decode(deserializer)) {
+            return Some(<u32>::sse_decode(deserializer));
+        } else {
+            return None;
+        }
+    }
+}
+
+impl SseDecode for Option<u64> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        if (<bool>::sse_decode(deserializer)) {
+            return Some(<u64>::sse_decode(deserializer));
+        } else {
+            return None;
+        }
+    }
+}
+
+impl SseDecode for crate::api::PairingDeviceDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_code = <String>::sse_decode(deserializer);
+        let mut var_publicKey = <String>::sse_decode(deserializer);
+        let mut var_deviceName = <String>::sse_decode(deserializer);
+        let mut var_viaRelay = <bool>::sse_decode(deserializer);
+        return crate::api::PairingDeviceDto {
+            code: var_code,
+            public_key: var_publicKey,
+            device_name: var_deviceName,
+            via_relay: var_viaRelay,
+        };
+    }
+}
+
+impl SseDecode for crate::api::PairingInfoDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_code = <String>::sse_decode(deserializer);
+        let mut var_qrData = <String>::sse_decode(deserializer);
+        let mut var_expiresAt = <u64>::sse_decode(deserializer);
+        let mut var_publicKey = <String>::sse_decode(deserializer);
+        return crate::api::PairingInfoDto {
+            code: var_code,
+            qr_data: var_qrData,
+            expires_at: var_expiresAt,
+            public_key: var_publicKey,
+        };
+    }
+}
+
+impl SseDecode for crate::api::PairingQrInfoDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_code = <String>::sse_decode(deserializer);
+        let mut var_publicKey = <String>::sse_decode(deserializer);
+        let mut var_deviceName = <String>::sse_decode(deserializer);
+        let mut var_deviceId = <String>::sse_decode(deserializer);
+        let mut var_expiresAt = <Option<u64>>::sse_decode(deserializer);
+        return crate::api::PairingQrInfoDto {
+            code: var_code,
+            public_key: var_publicKey,
+            device_name: var_deviceName,
+            device_id: var_deviceId,
+            expires_at: var_expiresAt,
+        };
+    }
+}
+
+impl SseDecode for crate::api::RelayIdentityDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_publicKey = <String>::sse_decode(deserializer);
+        let mut var_matchesPinned = <Option<bool>>::sse_decode(deserializer);
+        return crate::api::RelayIdentityDto {
+            public_key: var_publicKey,
+            matches_pinned: var_matchesPinned,
+        };
+    }
+}
+
+impl SseDecode for crate::api::RelayQueueStatusDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_count = <u64>::sse_decode(deserializer);
+        let mut var_oldestQueuedAt = <Option<i64>>::sse_decode(deserializer);
+        return crate::api::RelayQueueStatusDto {
+            count: var_count,
+            oldest_queued_at: var_oldestQueuedAt,
+        };
+    }
+}
+
+impl SseDecode for crate::api::RelayUsageDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_deviceId = <String>::sse_decode(deserializer);
+        let mut var_month = <String>::sse_decode(deserializer);
+        let mut var_bytesSent = <u64>::sse_decode(deserializer);
+        let mut var_bytesReceived = <u64>::sse_decode(deserializer);
+        let mut var_capped = <bool>::sse_decode(deserializer);
+        return crate::api::RelayUsageDto {
+            device_id: var_deviceId,
+            month: var_month,
+            bytes_sent: var_bytesSent,
+            bytes_received: var_bytesReceived,
+            capped: var_capped,
+        };
+    }
+}
+
+impl SseDecode for crate::api::SelfTestCheckDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_name = <String>::sse_decode(deserializer);
+        let mut var_status = <String>::sse_decode(deserializer);
+        let mut var_error = <Option<String>>::sse_decode(deserializer);
+        return crate::api::SelfTestCheckDto {
+            name: var_name,
+            status: var_status,
+            error: var_error,
+        };
+    }
+}
+
+impl SseDecode for crate::api::SelfTestReportDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_passed = <bool>::sse_decode(deserializer);
+        let mut var_checks = <Vec<crate::api::SelfTestCheckDto>>::sse_decode(deserializer);
+        return crate::api::SelfTestReportDto {
+            passed: var_passed,
+            checks: var_checks,
+        };
+    }
+}
+
+impl SseDecode for crate::api::SkipReasonDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_reason = <String>::sse_decode(deserializer);
+        let mut var_message = <String>::sse_decode(deserializer);
+        let mut var_timestamp = <u64>::sse_decode(deserializer);
+        return crate::api::SkipReasonDto {
+            reason: var_reason,
+            message: var_message,
+            timestamp: var_timestamp,
+        };
+    }
+}
+
+impl SseDecode for crate::api::SyncFailureCountDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_category = <String>::sse_decode(deserializer);
+        let mut var_count = <u64>::sse_decode(deserializer);
+        return crate::api::SyncFailureCountDto {
+            category: var_category,
+            count: var_count,
+        };
+    }
+}
+
+impl SseDecode for crate::api::SyncStatsDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut var_sendAttempts = <u64>::sse_decode(deserializer);
+        let mut var_sendSuccesses = <u64>::sse_decode(deserializer);
+        let mut var_relayFallbacks = <u64>::sse_decode(deserializer);
+        let mut var_failures = <Vec<crate::api::SyncFailureCountDto>>::sse_decode(deserializer);
+        return crate::api::SyncStatsDto {
+            send_attempts: var_sendAttempts,
+            send_successes: var_sendSuccesses,
+            relay_fallbacks: var_relayFallbacks,
+            failures: var_failures,
+        };
+    }
+}
+
+impl SseDecode for crate::api::TossEvent {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
+        let mut tag_ = <i32>::sse_decode(deserializer);
+        match tag_ {
+            0 => {
+                let mut var_item = <crate::api::ClipboardItemDto>::sse_decode(deserializer);
+                return crate::api::TossEvent::ClipboardReceived { item: var_item };
+            }
+            1 => {
+                let mut var_device = <crate::api::DeviceInfoDto>::sse_decode(deserializer);
+                return crate::api::TossEvent::DeviceConnected { device: var_device };
+            }
+            2 => {
+                let mut var_deviceId = <String>::sse_decode(deserializer);
+                return crate::api::TossEvent::DeviceDisconnected {
+                    device_id: var_deviceId,
+                };
+            }
+            3 => {
+                let mut var_device = <crate::api::DeviceInfoDto>::sse_decode(deserializer);
+                return crate::api::TossEvent::PairingRequest { device: var_device };
+            }
+            4 => {
+                let mut var_message = <String>::sse_decode(deserializer);
+                return crate::api::TossEvent::Error {
+                    message: var_message,
+                };
+            }
+            5 => {
+                let mut var_count = <u64>::sse_decode(deserializer);
+                return crate::api::TossEvent::EventsLost { count: var_count };
+            }
+            6 => {
+                let mut var_message = <String>::sse_decode(deserializer);
+                return crate::api::TossEvent::StorageDegraded {
+                    message: var_message,
+                };
+            }
+            7 => {
+                let mut var_url = <String>::sse_decode(deserializer);
+                let mut var_deviceId = <String>::sse_decode(deserializer);
+                return crate::api::TossEvent::OpenUrlRequested {
+                    url: var_url,
+                    device_id: var_deviceId,
+                };
+            }
+            8 => {
+                let mut var_deviceId = <String>::sse_decode(deserializer);
+                let mut var_state = <String>::sse_decode(deserializer);
+                return crate::api::TossEvent::PresenceChanged {
+                    device_id: var_deviceId,
+                    state: var_state,
+                };
+            }
+            9 => {
+                let mut var_oldDeviceId = <String>::sse_decode(deserializer);
+                let mut var_newDeviceId = <String>::sse_decode(deserializer);
+                return crate::api::TossEvent::DeviceIdentityChanged {
+                    old_device_id: var_oldDeviceId,
+                    new_device_id: var_newDeviceId,
+                };
+            }
+            10 => {
+                let mut var_backupPath = <String>::sse_decode(deserializer);
+                let mut var_dataLost = <bool>::sse_decode(deserializer);
+                return crate::api::TossEvent::StorageRecovered {
+                    backup_path: var_backupPath,
+                    data_lost: var_dataLost,
+                };
+            }
+            11 => {
+                let mut var_device = <crate::api::DeviceInfoDto>::sse_decode(deserializer);
+                return crate::api::TossEvent::PairingConfirmationRequested { device: var_device };
+            }
+            12 => {
+                let mut var_path = <String>::sse_decode(deserializer);
+                let mut var_deviceId = <String>::sse_decode(deserializer);
+                return crate::api::TossEvent::FileReceived {
+                    path: var_path,
+                    device_id: var_deviceId,
+                };
+            }
+            13 => {
+                let mut var_deviceId = <String>::sse_decode(deserializer);
+                let mut var_bytesSent = <u64>::sse_decode(deserializer);
+                let mut var_capBytes = <u64>::sse_decode(deserializer);
+                return crate::api::TossEvent::RelayCapReached {
+                    device_id: var_deviceId,
+                    bytes_sent: var_bytesSent,
+                    cap_bytes: var_capBytes,
+                };
+            }
+            14 => {
+                let mut var_text = <String>::sse_decode(deserializer);
+                let mut var_deviceId = <String>::sse_decode(deserializer);
+                return crate::api::TossEvent::ScratchpadChanged {
+                    text: var_text,
+                    device_id: var_deviceId,
+                };
+            }
+            15 => {
+                let mut var_transferId = <u64>::sse_decode(deserializer);
+                let mut var_direction = <String>::sse_decode(deserializer);
+                let mut var_deviceId = <String>::sse_decode(deserializer);
+                let mut var_totalBytes = <u64>::sse_decode(deserializer);
+                let mut var_transferredBytes = <u64>::sse_decode(deserializer);
+                return crate::api::TossEvent::TransferProgress {
+                    transfer_id: var_transferId,
+                    direction: var_direction,
+                    device_id: var_deviceId,
+                    total_bytes: var_totalBytes,
+                    transferred_bytes: var_transferredBytes,
+                };
+            }
+            _ => {
+                unimplemented!("");
+            }
+        }
+    }
+}
 
-// Section: dart2rust
-
-impl SseDecode for String {
+impl SseDecode for crate::api::TossSettings {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        let mut inner = <Vec<u8>>::sse_decode(deserializer);
-        return String::from_utf8(inner).unwrap();
+        let mut var_autoSync = <bool>::sse_decode(deserializer);
+        let mut var_syncText = <bool>::sse_decode(deserializer);
+        let mut var_syncRichText = <bool>::sse_decode(deserializer);
+        let mut var_syncImages = <bool>::sse_decode(deserializer);
+        let mut var_syncFiles = <bool>::sse_decode(deserializer);
+        let mut var_textDirection = <String>::sse_decode(deserializer);
+        let mut var_richTextDirection = <String>::sse_decode(deserializer);
+        let mut var_imageDirection = <String>::sse_decode(deserializer);
+        let mut var_fileDirection = <String>::sse_decode(deserializer);
+        let mut var_maxFileSizeMb = <u32>::sse_decode(deserializer);
+        let mut var_maxImageDimension = <u32>::sse_decode(deserializer);
+        let mut var_historyEnabled = <bool>::sse_decode(deserializer);
+        let mut var_historyText = <bool>::sse_decode(deserializer);
+        let mut var_historyRichText = <bool>::sse_decode(deserializer);
+        let mut var_historyImages = <bool>::sse_decode(deserializer);
+        let mut var_historyFiles = <bool>::sse_decode(deserializer);
+        let mut var_historyDays = <u32>::sse_decode(deserializer);
+        let mut var_relayUrl = <Option<String>>::sse_decode(deserializer);
+        let mut var_relayPinnedKey = <Option<String>>::sse_decode(deserializer);
+        let mut var_advertiseContentHint = <bool>::sse_decode(deserializer);
+        let mut var_maxPairedDevices = <u32>::sse_decode(deserializer);
+        let mut var_evictOldestDevice = <bool>::sse_decode(deserializer);
+        let mut var_collectSyncStats = <bool>::sse_decode(deserializer);
+        let mut var_quietHoursEnabled = <bool>::sse_decode(deserializer);
+        let mut var_quietHoursStart = <u32>::sse_decode(deserializer);
+        let mut var_quietHoursEnd = <u32>::sse_decode(deserializer);
+        let mut var_allowRemoteActions = <bool>::sse_decode(deserializer);
+        let mut var_sharePresence = <bool>::sse_decode(deserializer);
+        let mut var_keyedContentHash = <bool>::sse_decode(deserializer);
+        let mut var_historyWriteWindowMs = <u32>::sse_decode(deserializer);
+        let mut var_confirmPairing = <bool>::sse_decode(deserializer);
+        let mut var_lanOnlyMode = <bool>::sse_decode(deserializer);
+        let mut var_fileReceiveDirectory = <Option<String>>::sse_decode(deserializer);
+        let mut var_minSyncBytes = <u32>::sse_decode(deserializer);
+        let mut var_monthlyRelayCapBytes = <u64>::sse_decode(deserializer);
+        let mut var_contentDenylist = <Vec<String>>::sse_decode(deserializer);
+        let mut var_requirePeerUnlocked = <bool>::sse_decode(deserializer);
+        let mut var_serviceName = <String>::sse_decode(deserializer);
+        return crate::api::TossSettings {
+            auto_sync: var_autoSync,
+            sync_text: var_syncText,
+            sync_rich_text: var_syncRichText,
+            sync_images: var_syncImages,
+            sync_files: var_syncFiles,
+            text_direction: var_textDirection,
+            rich_text_direction: var_richTextDirection,
+            image_direction: var_imageDirection,
+            file_direction: var_fileDirection,
+            max_file_size_mb: var_maxFileSizeMb,
+            max_image_dimension: var_maxImageDimension,
+            history_enabled: var_historyEnabled,
+            history_text: var_historyText,
+            history_rich_text: var_historyRichText,
+            history_images: var_historyImages,
+            history_files: var_historyFiles,
+            history_days: var_historyDays,
+            relay_url: var_relayUrl,
+            relay_pinned_key: var_relayPinnedKey,
+            advertise_content_hint: var_advertiseContentHint,
+            max_paired_devices: var_maxPairedDevices,
+            evict_oldest_device: var_evictOldestDevice,
+            collect_sync_stats: var_collectSyncStats,
+            quiet_hours_enabled: var_quietHoursEnabled,
+            quiet_hours_start: var_quietHoursStart,
+            quiet_hours_end: var_quietHoursEnd,
+            allow_remote_actions: var_allowRemoteActions,
+            share_presence: var_sharePresence,
+            keyed_content_hash: var_keyedContentHash,
+            history_write_window_ms: var_historyWriteWindowMs,
+            confirm_pairing: var_confirmPairing,
+            lan_only_mode: var_lanOnlyMode,
+            file_receive_directory: var_fileReceiveDirectory,
+            min_sync_bytes: var_minSyncBytes,
+            monthly_relay_cap_bytes: var_monthlyRelayCapBytes,
+            content_denylist: var_contentDenylist,
+            require_peer_unlocked: var_requirePeerUnlocked,
+            service_name: var_serviceName,
+        };
     }
 }
 
-impl SseDecode for crate::api::AdvertisementResultDto {
+impl SseDecode for crate::api::TransferProgressDto {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        let mut var_mdnsRegistered = <bool>::sse_decode(deserializer);
-        let mut var_relayRegistered = <bool>::sse_decode(deserializer);
-        let mut var_mdnsError = <Option<String>>::sse_decode(deserializer);
-        let mut var_relayError = <Option<String>>::sse_decode(deserializer);
-        return crate::api::AdvertisementResultDto {
-            mdns_registered: var_mdnsRegistered,
-            relay_registered: var_relayRegistered,
-            mdns_error: var_mdnsError,
-            relay_error: var_relayError,
+        let mut var_transferId = <u64>::sse_decode(deserializer);
+        let mut var_direction = <String>::sse_decode(deserializer);
+        let mut var_deviceId = <String>::sse_decode(deserializer);
+        let mut var_totalBytes = <u64>::sse_decode(deserializer);
+        let mut var_receivedBytes = <u64>::sse_decode(deserializer);
+        let mut var_percent = <f64>::sse_decode(deserializer);
+        return crate::api::TransferProgressDto {
+            transfer_id: var_transferId,
+            direction: var_direction,
+            device_id: var_deviceId,
+            total_bytes: var_totalBytes,
+            received_bytes: var_receivedBytes,
+            percent: var_percent,
         };
     }
 }
 
-impl SseDecode for bool {
+impl SseDecode for u32 {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        deserializer.cursor.read_u8().unwrap() != 0
+        deserializer.cursor.read_u32::<NativeEndian>().unwrap()
     }
 }
 
-impl SseDecode for crate::api::ClipboardContentDto {
+impl SseDecode for u64 {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        let mut var_contentType = <String>::sse_decode(deserializer);
-        let mut var_data = <Vec<u8>>::sse_decode(deserializer);
-        return crate::api::ClipboardContentDto {
-            content_type: var_contentType,
-            data: var_data,
-        };
+        deserializer.cursor.read_u64::<NativeEndian>().unwrap()
     }
 }
 
-impl SseDecode for crate::api::ClipboardItemDto {
+impl SseDecode for u8 {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        let mut var_id = <String>::sse_decode(deserializer);
-        let mut var_contentType = <String>::sse_decode(deserializer);
-        let mut var_preview = <String>::sse_decode(deserializer);
-        let mut var_sizeBytes = <u64>::sse_decode(deserializer);
-        let mut var_timestamp = <u64>::sse_decode(deserializer);
-        let mut var_sourceDevice = <Option<String>>::sse_decode(deserializer);
-        return crate::api::ClipboardItemDto {
-            id: var_id,
-            content_type: var_contentType,
-            preview: var_preview,
-            size_bytes: var_sizeBytes,
-            timestamp: var_timestamp,
-            source_device: var_sourceDevice,
-        };
+        deserializer.cursor.read_u8().unwrap()
+    }
+}
+
+impl SseDecode for () {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {}
+}
+
+fn pde_ffi_dispatcher_primary_impl(
+    func_id: i32,
+    port: flutter_rust_bridge::for_generated::MessagePort,
+    ptr: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len: i32,
+    data_len: i32,
+) {
+    // Codec=Pde (Serialization + dispatch), see doc to use other codecs
+    match func_id {
+        1 => wire__crate__api__broadcast_presence_impl(port, ptr, rust_vec_len, data_len),
+        5 => wire__crate__api__cancel_transfer_impl(port, ptr, rust_vec_len, data_len),
+        12 => wire__crate__api__complete_relay_pairing_impl(port, ptr, rust_vec_len, data_len),
+        14 => wire__crate__api__export_history_item_to_file_impl(port, ptr, rust_vec_len, data_len),
+        15 => wire__crate__api__export_history_to_file_impl(port, ptr, rust_vec_len, data_len),
+        16 => wire__crate__api__export_network_diagnostics_impl(port, ptr, rust_vec_len, data_len),
+        17 => wire__crate__api__find_corrupt_history_items_impl(port, ptr, rust_vec_len, data_len),
+        18 => wire__crate__api__find_pairing_device_impl(port, ptr, rust_vec_len, data_len),
+        20 => wire__crate__api__flush_relay_queue_impl(port, ptr, rust_vec_len, data_len),
+        37 => wire__crate__api__get_relay_queue_status_impl(port, ptr, rust_vec_len, data_len),
+        45 => wire__crate__api__listen_events_impl(port, ptr, rust_vec_len, data_len),
+        47 => wire__crate__api__notify_network_changed_impl(port, ptr, rust_vec_len, data_len),
+        50 => wire__crate__api__probe_paired_devices_impl(port, ptr, rust_vec_len, data_len),
+        52 => wire__crate__api__refresh_pairing_code_impl(port, ptr, rust_vec_len, data_len),
+        53 => {
+            wire__crate__api__register_pairing_advertisement_impl(port, ptr, rust_vec_len, data_len)
+        }
+        54 => {
+            wire__crate__api__remove_corrupt_history_items_impl(port, ptr, rust_vec_len, data_len)
+        }
+        62 => wire__crate__api__rotate_identity_impl(port, ptr, rust_vec_len, data_len),
+        63 => wire__crate__api__run_self_test_impl(port, ptr, rust_vec_len, data_len),
+        64 => wire__crate__api__send_clipboard_impl(port, ptr, rust_vec_len, data_len),
+        65 => wire__crate__api__send_clipboard_to_device_impl(port, ptr, rust_vec_len, data_len),
+        66 => wire__crate__api__send_file_impl(port, ptr, rust_vec_len, data_len),
+        67 => wire__crate__api__send_text_impl(port, ptr, rust_vec_len, data_len),
+        68 => wire__crate__api__send_text_as_impl(port, ptr, rust_vec_len, data_len),
+        69 => wire__crate__api__send_text_to_device_impl(port, ptr, rust_vec_len, data_len),
+        70 => wire__crate__api__send_url_to_open_impl(port, ptr, rust_vec_len, data_len),
+        73 => wire__crate__api__set_local_lock_state_impl(port, ptr, rust_vec_len, data_len),
+        75 => wire__crate__api__set_scratchpad_impl(port, ptr, rust_vec_len, data_len),
+        78 => wire__crate__api__share_content_impl(port, ptr, rust_vec_len, data_len),
+        79 => wire__crate__api__shutdown_toss_impl(port, ptr, rust_vec_len, data_len),
+        80 => wire__crate__api__start_event_listener_impl(port, ptr, rust_vec_len, data_len),
+        81 => wire__crate__api__start_network_impl(port, ptr, rust_vec_len, data_len),
+        83 => wire__crate__api__stop_network_impl(port, ptr, rust_vec_len, data_len),
+        84 => wire__crate__api__test_relay_impl(port, ptr, rust_vec_len, data_len),
+        87 => wire__crate__api__verify_session_key_impl(port, ptr, rust_vec_len, data_len),
+        88 => wire__crate__api__wipe_all_data_impl(port, ptr, rust_vec_len, data_len),
+        _ => unreachable!(),
+    }
+}
+
+fn pde_ffi_dispatcher_sync_impl(
+    func_id: i32,
+    ptr: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
+    rust_vec_len: i32,
+    data_len: i32,
+) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
+    // Codec=Pde (Serialization + dispatch), see doc to use other codecs
+    match func_id {
+        2 => wire__crate__api__cancel_find_pairing_impl(ptr, rust_vec_len, data_len),
+        3 => wire__crate__api__cancel_pairing_impl(ptr, rust_vec_len, data_len),
+        4 => wire__crate__api__cancel_send_impl(ptr, rust_vec_len, data_len),
+        6 => wire__crate__api__capture_clipboard_snapshot_impl(ptr, rust_vec_len, data_len),
+        7 => wire__crate__api__check_clipboard_changed_impl(ptr, rust_vec_len, data_len),
+        8 => wire__crate__api__clear_clipboard_history_impl(ptr, rust_vec_len, data_len),
+        9 => wire__crate__api__complete_manual_pairing_impl(ptr, rust_vec_len, data_len),
+        10 => wire__crate__api__complete_pairing_code_impl(ptr, rust_vec_len, data_len),
+        11 => wire__crate__api__complete_pairing_qr_impl(ptr, rust_vec_len, data_len),
+        13 => wire__crate__api__confirm_pairing_impl(ptr, rust_vec_len, data_len),
+        19 => wire__crate__api__flush_logs_impl(ptr, rust_vec_len, data_len),
+        21 => wire__crate__api__get_active_transfers_impl(ptr, rust_vec_len, data_len),
+        22 => wire__crate__api__get_clipboard_history_impl(ptr, rust_vec_len, data_len),
+        23 => wire__crate__api__get_clipboard_history_content_impl(ptr, rust_vec_len, data_len),
+        24 => wire__crate__api__get_connected_devices_impl(ptr, rust_vec_len, data_len),
+        25 => wire__crate__api__get_current_clipboard_impl(ptr, rust_vec_len, data_len),
+        26 => wire__crate__api__get_current_clipboard_content_impl(ptr, rust_vec_len, data_len),
+        27 => wire__crate__api__get_device_fingerprint_impl(ptr, rust_vec_len, data_len),
+        28 => wire__crate__api__get_device_id_impl(ptr, rust_vec_len, data_len),
+        29 => wire__crate__api__get_device_name_impl(ptr, rust_vec_len, data_len),
+        30 => wire__crate__api__get_device_security_info_impl(ptr, rust_vec_len, data_len),
+        31 => wire__crate__api__get_device_session_key_impl(ptr, rust_vec_len, data_len),
+        32 => wire__crate__api__get_history_grouped_impl(ptr, rust_vec_len, data_len),
+        33 => wire__crate__api__get_last_sync_latency_impl(ptr, rust_vec_len, data_len),
+        34 => wire__crate__api__get_limits_impl(ptr, rust_vec_len, data_len),
+        35 => wire__crate__api__get_paired_devices_impl(ptr, rust_vec_len, data_len),
+        36 => wire__crate__api__get_recent_logs_impl(ptr, rust_vec_len, data_len),
+        38 => wire__crate__api__get_relay_usage_impl(ptr, rust_vec_len, data_len),
+        39 => wire__crate__api__get_scratchpad_impl(ptr, rust_vec_len, data_len),
+        40 => wire__crate__api__get_settings_impl(ptr, rust_vec_len, data_len),
+        41 => wire__crate__api__get_sync_stats_impl(ptr, rust_vec_len, data_len),
+        42 => wire__crate__api__init_toss_impl(ptr, rust_vec_len, data_len),
+        43 => wire__crate__api__is_storage_persistent_impl(ptr, rust_vec_len, data_len),
+        44 => wire__crate__api__last_skip_reason_impl(ptr, rust_vec_len, data_len),
+        46 => wire__crate__api__mark_fingerprint_verified_impl(ptr, rust_vec_len, data_len),
+        48 => wire__crate__api__parse_pairing_qr_impl(ptr, rust_vec_len, data_len),
+        49 => wire__crate__api__poll_event_impl(ptr, rust_vec_len, data_len),
+        51 => wire__crate__api__prune_history_now_impl(ptr, rust_vec_len, data_len),
+        55 => wire__crate__api__remove_device_impl(ptr, rust_vec_len, data_len),
+        56 => wire__crate__api__remove_history_item_impl(ptr, rust_vec_len, data_len),
+        57 => wire__crate__api__rename_device_impl(ptr, rust_vec_len, data_len),
+        58 => wire__crate__api__reset_relay_usage_impl(ptr, rust_vec_len, data_len),
+        59 => wire__crate__api__reset_sync_stats_impl(ptr, rust_vec_len, data_len),
+        60 => wire__crate__api__restore_history_item_impl(ptr, rust_vec_len, data_len),
+        61 => wire__crate__api__revoke_pairing_trust_impl(ptr, rust_vec_len, data_len),
+        71 => wire__crate__api__set_device_auto_accept_pairing_impl(ptr, rust_vec_len, data_len),
+        72 => wire__crate__api__set_device_name_impl(ptr, rust_vec_len, data_len),
+        74 => wire__crate__api__set_log_level_impl(ptr, rust_vec_len, data_len),
+        76 => wire__crate__api__set_storage_fallback_enabled_impl(ptr, rust_vec_len, data_len),
+        77 => wire__crate__api__set_utc_offset_impl(ptr, rust_vec_len, data_len),
+        82 => wire__crate__api__start_pairing_impl(ptr, rust_vec_len, data_len),
+        85 => wire__crate__api__trust_pairing_requests_impl(ptr, rust_vec_len, data_len),
+        86 => wire__crate__api__update_settings_impl(ptr, rust_vec_len, data_len),
+        _ => unreachable!(),
+    }
+}
+
+// Section: rust2dart
+
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::AdvertisementResultDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.mdns_registered.into_into_dart().into_dart(),
+            self.relay_registered.into_into_dart().into_dart(),
+            self.mdns_error.into_into_dart().into_dart(),
+            self.relay_error.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive
+    for crate::api::AdvertisementResultDto
+{
+}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::AdvertisementResultDto>
+    for crate::api::AdvertisementResultDto
+{
+    fn into_into_dart(self) -> crate::api::AdvertisementResultDto {
+        self
+    }
+}
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::ClipboardContentDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.content_type.into_into_dart().into_dart(),
+            self.data.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive
+    for crate::api::ClipboardContentDto
+{
+}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::ClipboardContentDto>
+    for crate::api::ClipboardContentDto
+{
+    fn into_into_dart(self) -> crate::api::ClipboardContentDto {
+        self
+    }
+}
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::ClipboardItemDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.id.into_into_dart().into_dart(),
+            self.content_type.into_into_dart().into_dart(),
+            self.mime_type.into_into_dart().into_dart(),
+            self.preview.into_into_dart().into_dart(),
+            self.size_bytes.into_into_dart().into_dart(),
+            self.timestamp.into_into_dart().into_dart(),
+            self.source_device.into_into_dart().into_dart(),
+            self.language.into_into_dart().into_dart(),
+            self.superseded.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::ClipboardItemDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::ClipboardItemDto>
+    for crate::api::ClipboardItemDto
+{
+    fn into_into_dart(self) -> crate::api::ClipboardItemDto {
+        self
+    }
+}
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::ConnectedPeerDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.device_id.into_into_dart().into_dart(),
+            self.device_name.into_into_dart().into_dart(),
+            self.addresses.into_into_dart().into_dart(),
+            self.transport.into_into_dart().into_dart(),
+            self.route.into_into_dart().into_dart(),
+            self.rtt_ms.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::ConnectedPeerDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::ConnectedPeerDto>
+    for crate::api::ConnectedPeerDto
+{
+    fn into_into_dart(self) -> crate::api::ConnectedPeerDto {
+        self
+    }
+}
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::DeviceInfoDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.id.into_into_dart().into_dart(),
+            self.name.into_into_dart().into_dart(),
+            self.is_online.into_into_dart().into_dart(),
+            self.last_seen.into_into_dart().into_dart(),
+            self.platform.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::DeviceInfoDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::DeviceInfoDto> for crate::api::DeviceInfoDto {
+    fn into_into_dart(self) -> crate::api::DeviceInfoDto {
+        self
+    }
+}
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::DeviceReachabilityDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.id.into_into_dart().into_dart(),
+            self.name.into_into_dart().into_dart(),
+            self.reachable.into_into_dart().into_dart(),
+            self.path.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive
+    for crate::api::DeviceReachabilityDto
+{
+}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::DeviceReachabilityDto>
+    for crate::api::DeviceReachabilityDto
+{
+    fn into_into_dart(self) -> crate::api::DeviceReachabilityDto {
+        self
+    }
+}
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::DeviceSecurityDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.device_id.into_into_dart().into_dart(),
+            self.key_algorithm.into_into_dart().into_dart(),
+            self.fingerprint.into_into_dart().into_dart(),
+            self.fingerprint_verified.into_into_dart().into_dart(),
+            self.fingerprint_verified_at.into_into_dart().into_dart(),
+            self.session_key_since.into_into_dart().into_dart(),
+            self.last_authenticated_at.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::DeviceSecurityDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::DeviceSecurityDto>
+    for crate::api::DeviceSecurityDto
+{
+    fn into_into_dart(self) -> crate::api::DeviceSecurityDto {
+        self
+    }
+}
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::DiscoveredPeerDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.device_id.into_into_dart().into_dart(),
+            self.device_name.into_into_dart().into_dart(),
+            self.addresses.into_into_dart().into_dart(),
+            self.version.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::DiscoveredPeerDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::DiscoveredPeerDto>
+    for crate::api::DiscoveredPeerDto
+{
+    fn into_into_dart(self) -> crate::api::DiscoveredPeerDto {
+        self
+    }
+}
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::HistoryExportDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.count.into_into_dart().into_dart(),
+            self.next_after.into_into_dart().into_dart(),
+        ]
+        .into_dart()
     }
 }
-
-impl SseDecode for crate::api::DeviceInfoDto {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        let mut var_id = <String>::sse_decode(deserializer);
-        let mut var_name = <String>::sse_decode(deserializer);
-        let mut var_isOnline = <bool>::sse_decode(deserializer);
-        let mut var_lastSeen = <u64>::sse_decode(deserializer);
-        let mut var_platform = <String>::sse_decode(deserializer);
-        return crate::api::DeviceInfoDto {
-            id: var_id,
-            name: var_name,
-            is_online: var_isOnline,
-            last_seen: var_lastSeen,
-            platform: var_platform,
-        };
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::HistoryExportDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::HistoryExportDto>
+    for crate::api::HistoryExportDto
+{
+    fn into_into_dart(self) -> crate::api::HistoryExportDto {
+        self
     }
 }
-
-impl SseDecode for Vec<crate::api::ClipboardItemDto> {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        let mut len_ = <i32>::sse_decode(deserializer);
-        let mut ans_ = vec![];
-        for idx_ in 0..len_ {
-            ans_.push(<crate::api::ClipboardItemDto>::sse_decode(deserializer));
-        }
-        return ans_;
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::HistoryGroupDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.day.into_into_dart().into_dart(),
+            self.source_device.into_into_dart().into_dart(),
+            self.items.into_into_dart().into_dart(),
+        ]
+        .into_dart()
     }
 }
-
-impl SseDecode for Vec<crate::api::DeviceInfoDto> {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        let mut len_ = <i32>::sse_decode(deserializer);
-        let mut ans_ = vec![];
-        for idx_ in 0..len_ {
-            ans_.push(<crate::api::DeviceInfoDto>::sse_decode(deserializer));
-        }
-        return ans_;
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::HistoryGroupDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::HistoryGroupDto>
+    for crate::api::HistoryGroupDto
+{
+    fn into_into_dart(self) -> crate::api::HistoryGroupDto {
+        self
     }
 }
-
-impl SseDecode for Vec<u8> {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        let mut len_ = <i32>::sse_decode(deserializer);
-        let mut ans_ = vec![];
-        for idx_ in 0..len_ {
-            ans_.push(<u8>::sse_decode(deserializer));
-        }
-        return ans_;
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::IceCandidateDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.candidate_type.into_into_dart().into_dart(),
+            self.address.into_into_dart().into_dart(),
+            self.priority.into_into_dart().into_dart(),
+        ]
+        .into_dart()
     }
 }
-
-impl SseDecode for Option<String> {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        if (<bool>::sse_decode(deserializer)) {
-            return Some(<String>::sse_decode(deserializer));
-        } else {
-            return None;
-        }
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::IceCandidateDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::IceCandidateDto>
+    for crate::api::IceCandidateDto
+{
+    fn into_into_dart(self) -> crate::api::IceCandidateDto {
+        self
     }
 }
-
-impl SseDecode for Option<crate::api::ClipboardItemDto> {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        if (<bool>::sse_decode(deserializer)) {
-            return Some(<crate::api::ClipboardItemDto>::sse_decode(deserializer));
-        } else {
-            return None;
-        }
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::IdentityRotationDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.device_id.into_into_dart().into_dart(),
+            self.migrated.into_into_dart().into_dart(),
+            self.needs_repair.into_into_dart().into_dart(),
+        ]
+        .into_dart()
     }
 }
-
-impl SseDecode for Option<crate::api::TossEvent> {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        if (<bool>::sse_decode(deserializer)) {
-            return Some(<crate::api::TossEvent>::sse_decode(deserializer));
-        } else {
-            return None;
-        }
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive
+    for crate::api::IdentityRotationDto
+{
+}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::IdentityRotationDto>
+    for crate::api::IdentityRotationDto
+{
+    fn into_into_dart(self) -> crate::api::IdentityRotationDto {
+        self
     }
 }
-
-impl SseDecode for Option<u32> {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        if (<bool>::sse_decode(deserializer)) {
-            return Some(<u32>::sse_decode(deserializer));
-        } else {
-            return None;
-        }
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::LimitsDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.max_message_size.into_into_dart().into_dart(),
+            self.max_preview_size.into_into_dart().into_dart(),
+            self.protocol_version.into_into_dart().into_dart(),
+            self.pairing_code_length.into_into_dart().into_dart(),
+            self.pairing_code_format.into_into_dart().into_dart(),
+            self.max_device_name_length.into_into_dart().into_dart(),
+        ]
+        .into_dart()
     }
 }
-
-impl SseDecode for crate::api::PairingDeviceDto {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        let mut var_code = <String>::sse_decode(deserializer);
-        let mut var_publicKey = <String>::sse_decode(deserializer);
-        let mut var_deviceName = <String>::sse_decode(deserializer);
-        let mut var_viaRelay = <bool>::sse_decode(deserializer);
-        return crate::api::PairingDeviceDto {
-            code: var_code,
-            public_key: var_publicKey,
-            device_name: var_deviceName,
-            via_relay: var_viaRelay,
-        };
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::LimitsDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::LimitsDto> for crate::api::LimitsDto {
+    fn into_into_dart(self) -> crate::api::LimitsDto {
+        self
     }
 }
-
-impl SseDecode for crate::api::PairingInfoDto {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        let mut var_code = <String>::sse_decode(deserializer);
-        let mut var_qrData = <String>::sse_decode(deserializer);
-        let mut var_expiresAt = <u64>::sse_decode(deserializer);
-        let mut var_publicKey = <String>::sse_decode(deserializer);
-        return crate::api::PairingInfoDto {
-            code: var_code,
-            qr_data: var_qrData,
-            expires_at: var_expiresAt,
-            public_key: var_publicKey,
-        };
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::NetworkDiagnosticsDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.local_addr.into_into_dart().into_dart(),
+            self.mdns_status.into_into_dart().into_dart(),
+            self.discovered_peers.into_into_dart().into_dart(),
+            self.connected_peers.into_into_dart().into_dart(),
+            self.relay_status.into_into_dart().into_dart(),
+            self.relay_url.into_into_dart().into_dart(),
+            self.nat_type.into_into_dart().into_dart(),
+            self.candidates.into_into_dart().into_dart(),
+        ]
+        .into_dart()
     }
 }
-
-impl SseDecode for crate::api::TossEvent {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        let mut tag_ = <i32>::sse_decode(deserializer);
-        match tag_ {
-            0 => {
-                let mut var_item = <crate::api::ClipboardItemDto>::sse_decode(deserializer);
-                return crate::api::TossEvent::ClipboardReceived { item: var_item };
-            }
-            1 => {
-                let mut var_device = <crate::api::DeviceInfoDto>::sse_decode(deserializer);
-                return crate::api::TossEvent::DeviceConnected { device: var_device };
-            }
-            2 => {
-                let mut var_deviceId = <String>::sse_decode(deserializer);
-                return crate::api::TossEvent::DeviceDisconnected {
-                    device_id: var_deviceId,
-                };
-            }
-            3 => {
-                let mut var_device = <crate::api::DeviceInfoDto>::sse_decode(deserializer);
-                return crate::api::TossEvent::PairingRequest { device: var_device };
-            }
-            4 => {
-                let mut var_message = <String>::sse_decode(deserializer);
-                return crate::api::TossEvent::Error {
-                    message: var_message,
-                };
-            }
-            _ => {
-                unimplemented!("");
-            }
-        }
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive
+    for crate::api::NetworkDiagnosticsDto
+{
+}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::NetworkDiagnosticsDto>
+    for crate::api::NetworkDiagnosticsDto
+{
+    fn into_into_dart(self) -> crate::api::NetworkDiagnosticsDto {
+        self
     }
 }
-
-impl SseDecode for crate::api::TossSettings {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        let mut var_autoSync = <bool>::sse_decode(deserializer);
-        let mut var_syncText = <bool>::sse_decode(deserializer);
-        let mut var_syncRichText = <bool>::sse_decode(deserializer);
-        let mut var_syncImages = <bool>::sse_decode(deserializer);
-        let mut var_syncFiles = <bool>::sse_decode(deserializer);
-        let mut var_maxFileSizeMb = <u32>::sse_decode(deserializer);
-        let mut var_historyEnabled = <bool>::sse_decode(deserializer);
-        let mut var_historyDays = <u32>::sse_decode(deserializer);
-        let mut var_relayUrl = <Option<String>>::sse_decode(deserializer);
-        return crate::api::TossSettings {
-            auto_sync: var_autoSync,
-            sync_text: var_syncText,
-            sync_rich_text: var_syncRichText,
-            sync_images: var_syncImages,
-            sync_files: var_syncFiles,
-            max_file_size_mb: var_maxFileSizeMb,
-            history_enabled: var_historyEnabled,
-            history_days: var_historyDays,
-            relay_url: var_relayUrl,
-        };
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::PairingDeviceDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.code.into_into_dart().into_dart(),
+            self.public_key.into_into_dart().into_dart(),
+            self.device_name.into_into_dart().into_dart(),
+            self.via_relay.into_into_dart().into_dart(),
+        ]
+        .into_dart()
     }
 }
-
-impl SseDecode for u32 {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        deserializer.cursor.read_u32::<NativeEndian>().unwrap()
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::PairingDeviceDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::PairingDeviceDto>
+    for crate::api::PairingDeviceDto
+{
+    fn into_into_dart(self) -> crate::api::PairingDeviceDto {
+        self
     }
 }
-
-impl SseDecode for u64 {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        deserializer.cursor.read_u64::<NativeEndian>().unwrap()
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::PairingInfoDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.code.into_into_dart().into_dart(),
+            self.qr_data.into_into_dart().into_dart(),
+            self.expires_at.into_into_dart().into_dart(),
+            self.public_key.into_into_dart().into_dart(),
+        ]
+        .into_dart()
     }
 }
-
-impl SseDecode for u8 {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        deserializer.cursor.read_u8().unwrap()
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::PairingInfoDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::PairingInfoDto> for crate::api::PairingInfoDto {
+    fn into_into_dart(self) -> crate::api::PairingInfoDto {
+        self
     }
 }
-
-impl SseDecode for () {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {}
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::PairingQrInfoDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.code.into_into_dart().into_dart(),
+            self.public_key.into_into_dart().into_dart(),
+            self.device_name.into_into_dart().into_dart(),
+            self.device_id.into_into_dart().into_dart(),
+            self.expires_at.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
 }
-
-impl SseDecode for i32 {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_decode(deserializer: &mut flutter_rust_bridge::for_generated::SseDeserializer) -> Self {
-        deserializer.cursor.read_i32::<NativeEndian>().unwrap()
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::PairingQrInfoDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::PairingQrInfoDto>
+    for crate::api::PairingQrInfoDto
+{
+    fn into_into_dart(self) -> crate::api::PairingQrInfoDto {
+        self
     }
 }
-
-fn pde_ffi_dispatcher_primary_impl(
-    func_id: i32,
-    port: flutter_rust_bridge::for_generated::MessagePort,
-    ptr: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
-    rust_vec_len: i32,
-    data_len: i32,
-) {
-    // Codec=Pde (Serialization + dispatch), see doc to use other codecs
-    match func_id {
-        7 => wire__crate__api__find_pairing_device_impl(port, ptr, rust_vec_len, data_len),
-        19 => {
-            wire__crate__api__register_pairing_advertisement_impl(port, ptr, rust_vec_len, data_len)
-        }
-        23 => wire__crate__api__send_clipboard_impl(port, ptr, rust_vec_len, data_len),
-        24 => wire__crate__api__send_text_impl(port, ptr, rust_vec_len, data_len),
-        26 => wire__crate__api__shutdown_toss_impl(port, ptr, rust_vec_len, data_len),
-        27 => wire__crate__api__start_event_listener_impl(port, ptr, rust_vec_len, data_len),
-        28 => wire__crate__api__start_network_impl(port, ptr, rust_vec_len, data_len),
-        30 => wire__crate__api__stop_network_impl(port, ptr, rust_vec_len, data_len),
-        _ => unreachable!(),
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::RelayIdentityDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.public_key.into_into_dart().into_dart(),
+            self.matches_pinned.into_into_dart().into_dart(),
+        ]
+        .into_dart()
     }
 }
-
-fn pde_ffi_dispatcher_sync_impl(
-    func_id: i32,
-    ptr: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
-    rust_vec_len: i32,
-    data_len: i32,
-) -> flutter_rust_bridge::for_generated::WireSyncRust2DartSse {
-    // Codec=Pde (Serialization + dispatch), see doc to use other codecs
-    match func_id {
-        1 => wire__crate__api__cancel_pairing_impl(ptr, rust_vec_len, data_len),
-        2 => wire__crate__api__check_clipboard_changed_impl(ptr, rust_vec_len, data_len),
-        3 => wire__crate__api__clear_clipboard_history_impl(ptr, rust_vec_len, data_len),
-        4 => wire__crate__api__complete_manual_pairing_impl(ptr, rust_vec_len, data_len),
-        5 => wire__crate__api__complete_pairing_code_impl(ptr, rust_vec_len, data_len),
-        6 => wire__crate__api__complete_pairing_qr_impl(ptr, rust_vec_len, data_len),
-        8 => wire__crate__api__get_clipboard_history_impl(ptr, rust_vec_len, data_len),
-        9 => wire__crate__api__get_clipboard_history_content_impl(ptr, rust_vec_len, data_len),
-        10 => wire__crate__api__get_connected_devices_impl(ptr, rust_vec_len, data_len),
-        11 => wire__crate__api__get_current_clipboard_impl(ptr, rust_vec_len, data_len),
-        12 => wire__crate__api__get_device_id_impl(ptr, rust_vec_len, data_len),
-        13 => wire__crate__api__get_device_name_impl(ptr, rust_vec_len, data_len),
-        14 => wire__crate__api__get_device_session_key_impl(ptr, rust_vec_len, data_len),
-        15 => wire__crate__api__get_paired_devices_impl(ptr, rust_vec_len, data_len),
-        16 => wire__crate__api__get_settings_impl(ptr, rust_vec_len, data_len),
-        17 => wire__crate__api__init_toss_impl(ptr, rust_vec_len, data_len),
-        18 => wire__crate__api__poll_event_impl(ptr, rust_vec_len, data_len),
-        20 => wire__crate__api__remove_device_impl(ptr, rust_vec_len, data_len),
-        21 => wire__crate__api__remove_history_item_impl(ptr, rust_vec_len, data_len),
-        22 => wire__crate__api__rename_device_impl(ptr, rust_vec_len, data_len),
-        25 => wire__crate__api__set_device_name_impl(ptr, rust_vec_len, data_len),
-        29 => wire__crate__api__start_pairing_impl(ptr, rust_vec_len, data_len),
-        31 => wire__crate__api__update_settings_impl(ptr, rust_vec_len, data_len),
-        _ => unreachable!(),
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::RelayIdentityDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::RelayIdentityDto>
+    for crate::api::RelayIdentityDto
+{
+    fn into_into_dart(self) -> crate::api::RelayIdentityDto {
+        self
     }
 }
-
-// Section: rust2dart
-
 // Codec=Dco (DartCObject based), see doc to use other codecs
-impl flutter_rust_bridge::IntoDart for crate::api::AdvertisementResultDto {
+impl flutter_rust_bridge::IntoDart for crate::api::RelayQueueStatusDto {
     fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
         [
-            self.mdns_registered.into_into_dart().into_dart(),
-            self.relay_registered.into_into_dart().into_dart(),
-            self.mdns_error.into_into_dart().into_dart(),
-            self.relay_error.into_into_dart().into_dart(),
+            self.count.into_into_dart().into_dart(),
+            self.oldest_queued_at.into_into_dart().into_dart(),
         ]
         .into_dart()
     }
 }
 impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive
-    for crate::api::AdvertisementResultDto
+    for crate::api::RelayQueueStatusDto
 {
 }
-impl flutter_rust_bridge::IntoIntoDart<crate::api::AdvertisementResultDto>
-    for crate::api::AdvertisementResultDto
+impl flutter_rust_bridge::IntoIntoDart<crate::api::RelayQueueStatusDto>
+    for crate::api::RelayQueueStatusDto
 {
-    fn into_into_dart(self) -> crate::api::AdvertisementResultDto {
+    fn into_into_dart(self) -> crate::api::RelayQueueStatusDto {
         self
     }
 }
 // Codec=Dco (DartCObject based), see doc to use other codecs
-impl flutter_rust_bridge::IntoDart for crate::api::ClipboardContentDto {
+impl flutter_rust_bridge::IntoDart for crate::api::RelayUsageDto {
     fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
         [
-            self.content_type.into_into_dart().into_dart(),
-            self.data.into_into_dart().into_dart(),
+            self.device_id.into_into_dart().into_dart(),
+            self.month.into_into_dart().into_dart(),
+            self.bytes_sent.into_into_dart().into_dart(),
+            self.bytes_received.into_into_dart().into_dart(),
+            self.capped.into_into_dart().into_dart(),
         ]
         .into_dart()
     }
 }
-impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive
-    for crate::api::ClipboardContentDto
-{
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::RelayUsageDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::RelayUsageDto> for crate::api::RelayUsageDto {
+    fn into_into_dart(self) -> crate::api::RelayUsageDto {
+        self
+    }
 }
-impl flutter_rust_bridge::IntoIntoDart<crate::api::ClipboardContentDto>
-    for crate::api::ClipboardContentDto
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::SelfTestCheckDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.name.into_into_dart().into_dart(),
+            self.status.into_into_dart().into_dart(),
+            self.error.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::SelfTestCheckDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::SelfTestCheckDto>
+    for crate::api::SelfTestCheckDto
 {
-    fn into_into_dart(self) -> crate::api::ClipboardContentDto {
+    fn into_into_dart(self) -> crate::api::SelfTestCheckDto {
         self
     }
 }
 // Codec=Dco (DartCObject based), see doc to use other codecs
-impl flutter_rust_bridge::IntoDart for crate::api::ClipboardItemDto {
+impl flutter_rust_bridge::IntoDart for crate::api::SelfTestReportDto {
     fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
         [
-            self.id.into_into_dart().into_dart(),
-            self.content_type.into_into_dart().into_dart(),
-            self.preview.into_into_dart().into_dart(),
-            self.size_bytes.into_into_dart().into_dart(),
-            self.timestamp.into_into_dart().into_dart(),
-            self.source_device.into_into_dart().into_dart(),
+            self.passed.into_into_dart().into_dart(),
+            self.checks.into_into_dart().into_dart(),
         ]
         .into_dart()
     }
 }
-impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::ClipboardItemDto {}
-impl flutter_rust_bridge::IntoIntoDart<crate::api::ClipboardItemDto>
-    for crate::api::ClipboardItemDto
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::SelfTestReportDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::SelfTestReportDto>
+    for crate::api::SelfTestReportDto
 {
-    fn into_into_dart(self) -> crate::api::ClipboardItemDto {
+    fn into_into_dart(self) -> crate::api::SelfTestReportDto {
         self
     }
 }
 // Codec=Dco (DartCObject based), see doc to use other codecs
-impl flutter_rust_bridge::IntoDart for crate::api::DeviceInfoDto {
+impl flutter_rust_bridge::IntoDart for crate::api::SkipReasonDto {
     fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
         [
-            self.id.into_into_dart().into_dart(),
-            self.name.into_into_dart().into_dart(),
-            self.is_online.into_into_dart().into_dart(),
-            self.last_seen.into_into_dart().into_dart(),
-            self.platform.into_into_dart().into_dart(),
+            self.reason.into_into_dart().into_dart(),
+            self.message.into_into_dart().into_dart(),
+            self.timestamp.into_into_dart().into_dart(),
         ]
         .into_dart()
     }
 }
-impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::DeviceInfoDto {}
-impl flutter_rust_bridge::IntoIntoDart<crate::api::DeviceInfoDto> for crate::api::DeviceInfoDto {
-    fn into_into_dart(self) -> crate::api::DeviceInfoDto {
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::SkipReasonDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::SkipReasonDto> for crate::api::SkipReasonDto {
+    fn into_into_dart(self) -> crate::api::SkipReasonDto {
         self
     }
 }
 // Codec=Dco (DartCObject based), see doc to use other codecs
-impl flutter_rust_bridge::IntoDart for crate::api::PairingDeviceDto {
+impl flutter_rust_bridge::IntoDart for crate::api::SyncFailureCountDto {
     fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
         [
-            self.code.into_into_dart().into_dart(),
-            self.public_key.into_into_dart().into_dart(),
-            self.device_name.into_into_dart().into_dart(),
-            self.via_relay.into_into_dart().into_dart(),
+            self.category.into_into_dart().into_dart(),
+            self.count.into_into_dart().into_dart(),
         ]
         .into_dart()
     }
 }
-impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::PairingDeviceDto {}
-impl flutter_rust_bridge::IntoIntoDart<crate::api::PairingDeviceDto>
-    for crate::api::PairingDeviceDto
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive
+    for crate::api::SyncFailureCountDto
 {
-    fn into_into_dart(self) -> crate::api::PairingDeviceDto {
+}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::SyncFailureCountDto>
+    for crate::api::SyncFailureCountDto
+{
+    fn into_into_dart(self) -> crate::api::SyncFailureCountDto {
         self
     }
 }
 // Codec=Dco (DartCObject based), see doc to use other codecs
-impl flutter_rust_bridge::IntoDart for crate::api::PairingInfoDto {
+impl flutter_rust_bridge::IntoDart for crate::api::SyncStatsDto {
     fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
         [
-            self.code.into_into_dart().into_dart(),
-            self.qr_data.into_into_dart().into_dart(),
-            self.expires_at.into_into_dart().into_dart(),
-            self.public_key.into_into_dart().into_dart(),
+            self.send_attempts.into_into_dart().into_dart(),
+            self.send_successes.into_into_dart().into_dart(),
+            self.relay_fallbacks.into_into_dart().into_dart(),
+            self.failures.into_into_dart().into_dart(),
         ]
         .into_dart()
     }
 }
-impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::PairingInfoDto {}
-impl flutter_rust_bridge::IntoIntoDart<crate::api::PairingInfoDto> for crate::api::PairingInfoDto {
-    fn into_into_dart(self) -> crate::api::PairingInfoDto {
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::SyncStatsDto {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::SyncStatsDto> for crate::api::SyncStatsDto {
+    fn into_into_dart(self) -> crate::api::SyncStatsDto {
         self
     }
 }
@@ -1510,6 +4523,83 @@ impl flutter_rust_bridge::IntoDart for crate::api::TossEvent {
             crate::api::TossEvent::Error { message } => {
                 [4.into_dart(), message.into_into_dart().into_dart()].into_dart()
             }
+            crate::api::TossEvent::EventsLost { count } => {
+                [5.into_dart(), count.into_into_dart().into_dart()].into_dart()
+            }
+            crate::api::TossEvent::StorageDegraded { message } => {
+                [6.into_dart(), message.into_into_dart().into_dart()].into_dart()
+            }
+            crate::api::TossEvent::OpenUrlRequested { url, device_id } => [
+                7.into_dart(),
+                url.into_into_dart().into_dart(),
+                device_id.into_into_dart().into_dart(),
+            ]
+            .into_dart(),
+            crate::api::TossEvent::PresenceChanged { device_id, state } => [
+                8.into_dart(),
+                device_id.into_into_dart().into_dart(),
+                state.into_into_dart().into_dart(),
+            ]
+            .into_dart(),
+            crate::api::TossEvent::DeviceIdentityChanged {
+                old_device_id,
+                new_device_id,
+            } => [
+                9.into_dart(),
+                old_device_id.into_into_dart().into_dart(),
+                new_device_id.into_into_dart().into_dart(),
+            ]
+            .into_dart(),
+            crate::api::TossEvent::StorageRecovered {
+                backup_path,
+                data_lost,
+            } => [
+                10.into_dart(),
+                backup_path.into_into_dart().into_dart(),
+                data_lost.into_into_dart().into_dart(),
+            ]
+            .into_dart(),
+            crate::api::TossEvent::PairingConfirmationRequested { device } => {
+                [11.into_dart(), device.into_into_dart().into_dart()].into_dart()
+            }
+            crate::api::TossEvent::FileReceived { path, device_id } => [
+                12.into_dart(),
+                path.into_into_dart().into_dart(),
+                device_id.into_into_dart().into_dart(),
+            ]
+            .into_dart(),
+            crate::api::TossEvent::RelayCapReached {
+                device_id,
+                bytes_sent,
+                cap_bytes,
+            } => [
+                13.into_dart(),
+                device_id.into_into_dart().into_dart(),
+                bytes_sent.into_into_dart().into_dart(),
+                cap_bytes.into_into_dart().into_dart(),
+            ]
+            .into_dart(),
+            crate::api::TossEvent::ScratchpadChanged { text, device_id } => [
+                14.into_dart(),
+                text.into_into_dart().into_dart(),
+                device_id.into_into_dart().into_dart(),
+            ]
+            .into_dart(),
+            crate::api::TossEvent::TransferProgress {
+                transfer_id,
+                direction,
+                device_id,
+                total_bytes,
+                transferred_bytes,
+            } => [
+                15.into_dart(),
+                transfer_id.into_into_dart().into_dart(),
+                direction.into_into_dart().into_dart(),
+                device_id.into_into_dart().into_dart(),
+                total_bytes.into_into_dart().into_dart(),
+                transferred_bytes.into_into_dart().into_dart(),
+            ]
+            .into_dart(),
             _ => {
                 unimplemented!("");
             }
@@ -1522,82 +4612,269 @@ impl flutter_rust_bridge::IntoIntoDart<crate::api::TossEvent> for crate::api::To
         self
     }
 }
-// Codec=Dco (DartCObject based), see doc to use other codecs
-impl flutter_rust_bridge::IntoDart for crate::api::TossSettings {
-    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
-        [
-            self.auto_sync.into_into_dart().into_dart(),
-            self.sync_text.into_into_dart().into_dart(),
-            self.sync_rich_text.into_into_dart().into_dart(),
-            self.sync_images.into_into_dart().into_dart(),
-            self.sync_files.into_into_dart().into_dart(),
-            self.max_file_size_mb.into_into_dart().into_dart(),
-            self.history_enabled.into_into_dart().into_dart(),
-            self.history_days.into_into_dart().into_dart(),
-            self.relay_url.into_into_dart().into_dart(),
-        ]
-        .into_dart()
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::TossSettings {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.auto_sync.into_into_dart().into_dart(),
+            self.sync_text.into_into_dart().into_dart(),
+            self.sync_rich_text.into_into_dart().into_dart(),
+            self.sync_images.into_into_dart().into_dart(),
+            self.sync_files.into_into_dart().into_dart(),
+            self.text_direction.into_into_dart().into_dart(),
+            self.rich_text_direction.into_into_dart().into_dart(),
+            self.image_direction.into_into_dart().into_dart(),
+            self.file_direction.into_into_dart().into_dart(),
+            self.max_file_size_mb.into_into_dart().into_dart(),
+            self.max_image_dimension.into_into_dart().into_dart(),
+            self.history_enabled.into_into_dart().into_dart(),
+            self.history_text.into_into_dart().into_dart(),
+            self.history_rich_text.into_into_dart().into_dart(),
+            self.history_images.into_into_dart().into_dart(),
+            self.history_files.into_into_dart().into_dart(),
+            self.history_days.into_into_dart().into_dart(),
+            self.relay_url.into_into_dart().into_dart(),
+            self.relay_pinned_key.into_into_dart().into_dart(),
+            self.advertise_content_hint.into_into_dart().into_dart(),
+            self.max_paired_devices.into_into_dart().into_dart(),
+            self.evict_oldest_device.into_into_dart().into_dart(),
+            self.collect_sync_stats.into_into_dart().into_dart(),
+            self.quiet_hours_enabled.into_into_dart().into_dart(),
+            self.quiet_hours_start.into_into_dart().into_dart(),
+            self.quiet_hours_end.into_into_dart().into_dart(),
+            self.allow_remote_actions.into_into_dart().into_dart(),
+            self.share_presence.into_into_dart().into_dart(),
+            self.keyed_content_hash.into_into_dart().into_dart(),
+            self.history_write_window_ms.into_into_dart().into_dart(),
+            self.confirm_pairing.into_into_dart().into_dart(),
+            self.lan_only_mode.into_into_dart().into_dart(),
+            self.file_receive_directory.into_into_dart().into_dart(),
+            self.min_sync_bytes.into_into_dart().into_dart(),
+            self.monthly_relay_cap_bytes.into_into_dart().into_dart(),
+            self.content_denylist.into_into_dart().into_dart(),
+            self.require_peer_unlocked.into_into_dart().into_dart(),
+            self.service_name.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::TossSettings {}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::TossSettings> for crate::api::TossSettings {
+    fn into_into_dart(self) -> crate::api::TossSettings {
+        self
+    }
+}
+// Codec=Dco (DartCObject based), see doc to use other codecs
+impl flutter_rust_bridge::IntoDart for crate::api::TransferProgressDto {
+    fn into_dart(self) -> flutter_rust_bridge::for_generated::DartAbi {
+        [
+            self.transfer_id.into_into_dart().into_dart(),
+            self.direction.into_into_dart().into_dart(),
+            self.device_id.into_into_dart().into_dart(),
+            self.total_bytes.into_into_dart().into_dart(),
+            self.received_bytes.into_into_dart().into_dart(),
+            self.percent.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive
+    for crate::api::TransferProgressDto
+{
+}
+impl flutter_rust_bridge::IntoIntoDart<crate::api::TransferProgressDto>
+    for crate::api::TransferProgressDto
+{
+    fn into_into_dart(self) -> crate::api::TransferProgressDto {
+        self
+    }
+}
+
+impl SseEncode for StreamSink<crate::api::TossEvent, flutter_rust_bridge::for_generated::SseCodec> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        unimplemented!("")
+    }
+}
+
+impl SseEncode for String {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <Vec<u8>>::sse_encode(self.into_bytes(), serializer);
+    }
+}
+
+impl SseEncode for crate::api::AdvertisementResultDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <bool>::sse_encode(self.mdns_registered, serializer);
+        <bool>::sse_encode(self.relay_registered, serializer);
+        <Option<String>>::sse_encode(self.mdns_error, serializer);
+        <Option<String>>::sse_encode(self.relay_error, serializer);
+    }
+}
+
+impl SseEncode for bool {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        serializer.cursor.write_u8(self as _).unwrap();
+    }
+}
+
+impl SseEncode for crate::api::ClipboardContentDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.content_type, serializer);
+        <Vec<u8>>::sse_encode(self.data, serializer);
+    }
+}
+
+impl SseEncode for crate::api::ClipboardItemDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.id, serializer);
+        <String>::sse_encode(self.content_type, serializer);
+        <String>::sse_encode(self.mime_type, serializer);
+        <String>::sse_encode(self.preview, serializer);
+        <u64>::sse_encode(self.size_bytes, serializer);
+        <u64>::sse_encode(self.timestamp, serializer);
+        <Option<String>>::sse_encode(self.source_device, serializer);
+        <Option<String>>::sse_encode(self.language, serializer);
+        <bool>::sse_encode(self.superseded, serializer);
+    }
+}
+
+impl SseEncode for crate::api::ConnectedPeerDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.device_id, serializer);
+        <String>::sse_encode(self.device_name, serializer);
+        <Vec<String>>::sse_encode(self.addresses, serializer);
+        <String>::sse_encode(self.transport, serializer);
+        <String>::sse_encode(self.route, serializer);
+        <u64>::sse_encode(self.rtt_ms, serializer);
+    }
+}
+
+impl SseEncode for crate::api::DeviceInfoDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.id, serializer);
+        <String>::sse_encode(self.name, serializer);
+        <bool>::sse_encode(self.is_online, serializer);
+        <u64>::sse_encode(self.last_seen, serializer);
+        <String>::sse_encode(self.platform, serializer);
+    }
+}
+
+impl SseEncode for crate::api::DeviceReachabilityDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.id, serializer);
+        <String>::sse_encode(self.name, serializer);
+        <bool>::sse_encode(self.reachable, serializer);
+        <Option<String>>::sse_encode(self.path, serializer);
+    }
+}
+
+impl SseEncode for crate::api::DeviceSecurityDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.device_id, serializer);
+        <String>::sse_encode(self.key_algorithm, serializer);
+        <String>::sse_encode(self.fingerprint, serializer);
+        <bool>::sse_encode(self.fingerprint_verified, serializer);
+        <Option<u64>>::sse_encode(self.fingerprint_verified_at, serializer);
+        <Option<u64>>::sse_encode(self.session_key_since, serializer);
+        <Option<u64>>::sse_encode(self.last_authenticated_at, serializer);
+    }
+}
+
+impl SseEncode for crate::api::DiscoveredPeerDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.device_id, serializer);
+        <String>::sse_encode(self.device_name, serializer);
+        <Vec<String>>::sse_encode(self.addresses, serializer);
+        <String>::sse_encode(self.version, serializer);
+    }
+}
+
+impl SseEncode for f64 {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        serializer.cursor.write_f64::<NativeEndian>(self).unwrap();
+    }
+}
+
+impl SseEncode for crate::api::HistoryExportDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <u32>::sse_encode(self.count, serializer);
+        <Option<u64>>::sse_encode(self.next_after, serializer);
     }
 }
-impl flutter_rust_bridge::for_generated::IntoDartExceptPrimitive for crate::api::TossSettings {}
-impl flutter_rust_bridge::IntoIntoDart<crate::api::TossSettings> for crate::api::TossSettings {
-    fn into_into_dart(self) -> crate::api::TossSettings {
-        self
+
+impl SseEncode for crate::api::HistoryGroupDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.day, serializer);
+        <Option<String>>::sse_encode(self.source_device, serializer);
+        <Vec<crate::api::ClipboardItemDto>>::sse_encode(self.items, serializer);
     }
 }
 
-impl SseEncode for String {
+impl SseEncode for i32 {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
-        <Vec<u8>>::sse_encode(self.into_bytes(), serializer);
+        serializer.cursor.write_i32::<NativeEndian>(self).unwrap();
     }
 }
 
-impl SseEncode for crate::api::AdvertisementResultDto {
+impl SseEncode for i64 {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
-        <bool>::sse_encode(self.mdns_registered, serializer);
-        <bool>::sse_encode(self.relay_registered, serializer);
-        <Option<String>>::sse_encode(self.mdns_error, serializer);
-        <Option<String>>::sse_encode(self.relay_error, serializer);
+        serializer.cursor.write_i64::<NativeEndian>(self).unwrap();
     }
 }
 
-impl SseEncode for bool {
+impl SseEncode for crate::api::IceCandidateDto {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
-        serializer.cursor.write_u8(self as _).unwrap();
+        <String>::sse_encode(self.candidate_type, serializer);
+        <String>::sse_encode(self.address, serializer);
+        <u64>::sse_encode(self.priority, serializer);
     }
 }
 
-impl SseEncode for crate::api::ClipboardContentDto {
+impl SseEncode for crate::api::IdentityRotationDto {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
-        <String>::sse_encode(self.content_type, serializer);
-        <Vec<u8>>::sse_encode(self.data, serializer);
+        <String>::sse_encode(self.device_id, serializer);
+        <Vec<String>>::sse_encode(self.migrated, serializer);
+        <Vec<String>>::sse_encode(self.needs_repair, serializer);
     }
 }
 
-impl SseEncode for crate::api::ClipboardItemDto {
+impl SseEncode for crate::api::LimitsDto {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
-        <String>::sse_encode(self.id, serializer);
-        <String>::sse_encode(self.content_type, serializer);
-        <String>::sse_encode(self.preview, serializer);
-        <u64>::sse_encode(self.size_bytes, serializer);
-        <u64>::sse_encode(self.timestamp, serializer);
-        <Option<String>>::sse_encode(self.source_device, serializer);
+        <u64>::sse_encode(self.max_message_size, serializer);
+        <u64>::sse_encode(self.max_preview_size, serializer);
+        <u32>::sse_encode(self.protocol_version, serializer);
+        <u32>::sse_encode(self.pairing_code_length, serializer);
+        <String>::sse_encode(self.pairing_code_format, serializer);
+        <u32>::sse_encode(self.max_device_name_length, serializer);
     }
 }
 
-impl SseEncode for crate::api::DeviceInfoDto {
+impl SseEncode for Vec<String> {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
-        <String>::sse_encode(self.id, serializer);
-        <String>::sse_encode(self.name, serializer);
-        <bool>::sse_encode(self.is_online, serializer);
-        <u64>::sse_encode(self.last_seen, serializer);
-        <String>::sse_encode(self.platform, serializer);
+        <i32>::sse_encode(self.len() as _, serializer);
+        for item in self {
+            <String>::sse_encode(item, serializer);
+        }
     }
 }
 
@@ -1611,6 +4888,16 @@ impl SseEncode for Vec<crate::api::ClipboardItemDto> {
     }
 }
 
+impl SseEncode for Vec<crate::api::ConnectedPeerDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <i32>::sse_encode(self.len() as _, serializer);
+        for item in self {
+            <crate::api::ConnectedPeerDto>::sse_encode(item, serializer);
+        }
+    }
+}
+
 impl SseEncode for Vec<crate::api::DeviceInfoDto> {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -1621,6 +4908,46 @@ impl SseEncode for Vec<crate::api::DeviceInfoDto> {
     }
 }
 
+impl SseEncode for Vec<crate::api::DeviceReachabilityDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <i32>::sse_encode(self.len() as _, serializer);
+        for item in self {
+            <crate::api::DeviceReachabilityDto>::sse_encode(item, serializer);
+        }
+    }
+}
+
+impl SseEncode for Vec<crate::api::DiscoveredPeerDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <i32>::sse_encode(self.len() as _, serializer);
+        for item in self {
+            <crate::api::DiscoveredPeerDto>::sse_encode(item, serializer);
+        }
+    }
+}
+
+impl SseEncode for Vec<crate::api::HistoryGroupDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <i32>::sse_encode(self.len() as _, serializer);
+        for item in self {
+            <crate::api::HistoryGroupDto>::sse_encode(item, serializer);
+        }
+    }
+}
+
+impl SseEncode for Vec<crate::api::IceCandidateDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <i32>::sse_encode(self.len() as _, serializer);
+        for item in self {
+            <crate::api::IceCandidateDto>::sse_encode(item, serializer);
+        }
+    }
+}
+
 impl SseEncode for Vec<u8> {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -1631,6 +4958,60 @@ impl SseEncode for Vec<u8> {
     }
 }
 
+impl SseEncode for Vec<crate::api::RelayUsageDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <i32>::sse_encode(self.len() as _, serializer);
+        for item in self {
+            <crate::api::RelayUsageDto>::sse_encode(item, serializer);
+        }
+    }
+}
+
+impl SseEncode for Vec<crate::api::SelfTestCheckDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <i32>::sse_encode(self.len() as _, serializer);
+        for item in self {
+            <crate::api::SelfTestCheckDto>::sse_encode(item, serializer);
+        }
+    }
+}
+
+impl SseEncode for Vec<crate::api::SyncFailureCountDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <i32>::sse_encode(self.len() as _, serializer);
+        for item in self {
+            <crate::api::SyncFailureCountDto>::sse_encode(item, serializer);
+        }
+    }
+}
+
+impl SseEncode for Vec<crate::api::TransferProgressDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <i32>::sse_encode(self.len() as _, serializer);
+        for item in self {
+            <crate::api::TransferProgressDto>::sse_encode(item, serializer);
+        }
+    }
+}
+
+impl SseEncode for crate::api::NetworkDiagnosticsDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <Option<String>>::sse_encode(self.local_addr, serializer);
+        <String>::sse_encode(self.mdns_status, serializer);
+        <Vec<crate::api::DiscoveredPeerDto>>::sse_encode(self.discovered_peers, serializer);
+        <Vec<crate::api::ConnectedPeerDto>>::sse_encode(self.connected_peers, serializer);
+        <String>::sse_encode(self.relay_status, serializer);
+        <Option<String>>::sse_encode(self.relay_url, serializer);
+        <String>::sse_encode(self.nat_type, serializer);
+        <Vec<crate::api::IceCandidateDto>>::sse_encode(self.candidates, serializer);
+    }
+}
+
 impl SseEncode for Option<String> {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -1641,6 +5022,16 @@ impl SseEncode for Option<String> {
     }
 }
 
+impl SseEncode for Option<bool> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <bool>::sse_encode(self.is_some(), serializer);
+        if let Some(value) = self {
+            <bool>::sse_encode(value, serializer);
+        }
+    }
+}
+
 impl SseEncode for Option<crate::api::ClipboardItemDto> {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -1651,6 +5042,36 @@ impl SseEncode for Option<crate::api::ClipboardItemDto> {
     }
 }
 
+impl SseEncode for Option<crate::api::DeviceInfoDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <bool>::sse_encode(self.is_some(), serializer);
+        if let Some(value) = self {
+            <crate::api::DeviceInfoDto>::sse_encode(value, serializer);
+        }
+    }
+}
+
+impl SseEncode for Option<i64> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <bool>::sse_encode(self.is_some(), serializer);
+        if let Some(value) = self {
+            <i64>::sse_encode(value, serializer);
+        }
+    }
+}
+
+impl SseEncode for Option<crate::api::SkipReasonDto> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <bool>::sse_encode(self.is_some(), serializer);
+        if let Some(value) = self {
+            <crate::api::SkipReasonDto>::sse_encode(value, serializer);
+        }
+    }
+}
+
 impl SseEncode for Option<crate::api::TossEvent> {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -1671,6 +5092,16 @@ impl SseEncode for Option<u32> {
     }
 }
 
+impl SseEncode for Option<u64> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <bool>::sse_encode(self.is_some(), serializer);
+        if let Some(value) = self {
+            <u64>::sse_encode(value, serializer);
+        }
+    }
+}
+
 impl SseEncode for crate::api::PairingDeviceDto {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -1691,6 +5122,88 @@ impl SseEncode for crate::api::PairingInfoDto {
     }
 }
 
+impl SseEncode for crate::api::PairingQrInfoDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.code, serializer);
+        <String>::sse_encode(self.public_key, serializer);
+        <String>::sse_encode(self.device_name, serializer);
+        <String>::sse_encode(self.device_id, serializer);
+        <Option<u64>>::sse_encode(self.expires_at, serializer);
+    }
+}
+
+impl SseEncode for crate::api::RelayIdentityDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.public_key, serializer);
+        <Option<bool>>::sse_encode(self.matches_pinned, serializer);
+    }
+}
+
+impl SseEncode for crate::api::RelayQueueStatusDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <u64>::sse_encode(self.count, serializer);
+        <Option<i64>>::sse_encode(self.oldest_queued_at, serializer);
+    }
+}
+
+impl SseEncode for crate::api::RelayUsageDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.device_id, serializer);
+        <String>::sse_encode(self.month, serializer);
+        <u64>::sse_encode(self.bytes_sent, serializer);
+        <u64>::sse_encode(self.bytes_received, serializer);
+        <bool>::sse_encode(self.capped, serializer);
+    }
+}
+
+impl SseEncode for crate::api::SelfTestCheckDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.name, serializer);
+        <String>::sse_encode(self.status, serializer);
+        <Option<String>>::sse_encode(self.error, serializer);
+    }
+}
+
+impl SseEncode for crate::api::SelfTestReportDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <bool>::sse_encode(self.passed, serializer);
+        <Vec<crate::api::SelfTestCheckDto>>::sse_encode(self.checks, serializer);
+    }
+}
+
+impl SseEncode for crate::api::SkipReasonDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.reason, serializer);
+        <String>::sse_encode(self.message, serializer);
+        <u64>::sse_encode(self.timestamp, serializer);
+    }
+}
+
+impl SseEncode for crate::api::SyncFailureCountDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.category, serializer);
+        <u64>::sse_encode(self.count, serializer);
+    }
+}
+
+impl SseEncode for crate::api::SyncStatsDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <u64>::sse_encode(self.send_attempts, serializer);
+        <u64>::sse_encode(self.send_successes, serializer);
+        <u64>::sse_encode(self.relay_fallbacks, serializer);
+        <Vec<crate::api::SyncFailureCountDto>>::sse_encode(self.failures, serializer);
+    }
+}
+
 impl SseEncode for crate::api::TossEvent {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -1715,6 +5228,78 @@ impl SseEncode for crate::api::TossEvent {
                 <i32>::sse_encode(4, serializer);
                 <String>::sse_encode(message, serializer);
             }
+            crate::api::TossEvent::EventsLost { count } => {
+                <i32>::sse_encode(5, serializer);
+                <u64>::sse_encode(count, serializer);
+            }
+            crate::api::TossEvent::StorageDegraded { message } => {
+                <i32>::sse_encode(6, serializer);
+                <String>::sse_encode(message, serializer);
+            }
+            crate::api::TossEvent::OpenUrlRequested { url, device_id } => {
+                <i32>::sse_encode(7, serializer);
+                <String>::sse_encode(url, serializer);
+                <String>::sse_encode(device_id, serializer);
+            }
+            crate::api::TossEvent::PresenceChanged { device_id, state } => {
+                <i32>::sse_encode(8, serializer);
+                <String>::sse_encode(device_id, serializer);
+                <String>::sse_encode(state, serializer);
+            }
+            crate::api::TossEvent::DeviceIdentityChanged {
+                old_device_id,
+                new_device_id,
+            } => {
+                <i32>::sse_encode(9, serializer);
+                <String>::sse_encode(old_device_id, serializer);
+                <String>::sse_encode(new_device_id, serializer);
+            }
+            crate::api::TossEvent::StorageRecovered {
+                backup_path,
+                data_lost,
+            } => {
+                <i32>::sse_encode(10, serializer);
+                <String>::sse_encode(backup_path, serializer);
+                <bool>::sse_encode(data_lost, serializer);
+            }
+            crate::api::TossEvent::PairingConfirmationRequested { device } => {
+                <i32>::sse_encode(11, serializer);
+                <crate::api::DeviceInfoDto>::sse_encode(device, serializer);
+            }
+            crate::api::TossEvent::FileReceived { path, device_id } => {
+                <i32>::sse_encode(12, serializer);
+                <String>::sse_encode(path, serializer);
+                <String>::sse_encode(device_id, serializer);
+            }
+            crate::api::TossEvent::RelayCapReached {
+                device_id,
+                bytes_sent,
+                cap_bytes,
+            } => {
+                <i32>::sse_encode(13, serializer);
+                <String>::sse_encode(device_id, serializer);
+                <u64>::sse_encode(bytes_sent, serializer);
+                <u64>::sse_encode(cap_bytes, serializer);
+            }
+            crate::api::TossEvent::ScratchpadChanged { text, device_id } => {
+                <i32>::sse_encode(14, serializer);
+                <String>::sse_encode(text, serializer);
+                <String>::sse_encode(device_id, serializer);
+            }
+            crate::api::TossEvent::TransferProgress {
+                transfer_id,
+                direction,
+                device_id,
+                total_bytes,
+                transferred_bytes,
+            } => {
+                <i32>::sse_encode(15, serializer);
+                <u64>::sse_encode(transfer_id, serializer);
+                <String>::sse_encode(direction, serializer);
+                <String>::sse_encode(device_id, serializer);
+                <u64>::sse_encode(total_bytes, serializer);
+                <u64>::sse_encode(transferred_bytes, serializer);
+            }
             _ => {
                 unimplemented!("");
             }
@@ -1730,10 +5315,51 @@ impl SseEncode for crate::api::TossSettings {
         <bool>::sse_encode(self.sync_rich_text, serializer);
         <bool>::sse_encode(self.sync_images, serializer);
         <bool>::sse_encode(self.sync_files, serializer);
+        <String>::sse_encode(self.text_direction, serializer);
+        <String>::sse_encode(self.rich_text_direction, serializer);
+        <String>::sse_encode(self.image_direction, serializer);
+        <String>::sse_encode(self.file_direction, serializer);
         <u32>::sse_encode(self.max_file_size_mb, serializer);
+        <u32>::sse_encode(self.max_image_dimension, serializer);
         <bool>::sse_encode(self.history_enabled, serializer);
+        <bool>::sse_encode(self.history_text, serializer);
+        <bool>::sse_encode(self.history_rich_text, serializer);
+        <bool>::sse_encode(self.history_images, serializer);
+        <bool>::sse_encode(self.history_files, serializer);
         <u32>::sse_encode(self.history_days, serializer);
         <Option<String>>::sse_encode(self.relay_url, serializer);
+        <Option<String>>::sse_encode(self.relay_pinned_key, serializer);
+        <bool>::sse_encode(self.advertise_content_hint, serializer);
+        <u32>::sse_encode(self.max_paired_devices, serializer);
+        <bool>::sse_encode(self.evict_oldest_device, serializer);
+        <bool>::sse_encode(self.collect_sync_stats, serializer);
+        <bool>::sse_encode(self.quiet_hours_enabled, serializer);
+        <u32>::sse_encode(self.quiet_hours_start, serializer);
+        <u32>::sse_encode(self.quiet_hours_end, serializer);
+        <bool>::sse_encode(self.allow_remote_actions, serializer);
+        <bool>::sse_encode(self.share_presence, serializer);
+        <bool>::sse_encode(self.keyed_content_hash, serializer);
+        <u32>::sse_encode(self.history_write_window_ms, serializer);
+        <bool>::sse_encode(self.confirm_pairing, serializer);
+        <bool>::sse_encode(self.lan_only_mode, serializer);
+        <Option<String>>::sse_encode(self.file_receive_directory, serializer);
+        <u32>::sse_encode(self.min_sync_bytes, serializer);
+        <u64>::sse_encode(self.monthly_relay_cap_bytes, serializer);
+        <Vec<String>>::sse_encode(self.content_denylist, serializer);
+        <bool>::sse_encode(self.require_peer_unlocked, serializer);
+        <String>::sse_encode(self.service_name, serializer);
+    }
+}
+
+impl SseEncode for crate::api::TransferProgressDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <u64>::sse_encode(self.transfer_id, serializer);
+        <String>::sse_encode(self.direction, serializer);
+        <String>::sse_encode(self.device_id, serializer);
+        <u64>::sse_encode(self.total_bytes, serializer);
+        <u64>::sse_encode(self.received_bytes, serializer);
+        <f64>::sse_encode(self.percent, serializer);
     }
 }
 
@@ -1763,13 +5389,6 @@ impl SseEncode for () {
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {}
 }
 
-impl SseEncode for i32 {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
-        serializer.cursor.write_i32::<NativeEndian>(self).unwrap();
-    }
-}
-
 #[cfg(not(target_family = "wasm"))]
 mod io {
     // This file is automatically generated, so please do not edit it.
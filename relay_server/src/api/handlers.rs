@@ -1,18 +1,20 @@
 //! API request handlers
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
+use curve25519_dalek::MontgomeryPoint;
 use serde::{Deserialize, Serialize};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::Instant;
 use uuid::Uuid;
 
 use crate::{
     auth::{create_token, verify_signature, AuthenticatedDevice},
     error::{ApiError, ApiResult},
-    relay::RelayMessage,
+    relay::{payload_size, RelayMessage, MAX_MESSAGE_ID_LEN, MAX_SIGNATURE_LEN},
     AppState,
 };
 
@@ -27,12 +29,18 @@ pub struct RegisterRequest {
     pub device_name: String,
     pub timestamp: u64,
     pub signature: String, // Base64 encoded
+    /// Nonce for the relay to sign in its response, proving its identity
+    #[serde(default)]
+    pub challenge: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct RegisterResponse {
     pub token: String,
     pub expires_at: u64,
+    /// Base64 encoded, over "relay-session:" + challenge + ":" + device_id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity_signature: Option<String>,
 }
 
 pub async fn register_device(
@@ -50,14 +58,19 @@ pub async fn register_device(
             .map_err(|_| ApiError::BadRequest("Invalid signature encoding".to_string()))?;
 
     // Verify timestamp freshness (5 minute window)
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    let now = state.clock.now_secs() as u64;
     if now.abs_diff(req.timestamp) > 300 {
         return Err(ApiError::BadRequest("Timestamp too old".to_string()));
     }
 
+    if req
+        .challenge
+        .as_ref()
+        .is_some_and(|c| c.len() > MAX_IDENTITY_CHALLENGE_LEN)
+    {
+        return Err(ApiError::BadRequest("Challenge too long".to_string()));
+    }
+
     // Verify signature
     let message = format!("register:{}:{}", req.device_id, req.timestamp);
     if !verify_signature(&public_key, message.as_bytes(), &signature)? {
@@ -75,11 +88,24 @@ pub async fn register_device(
         &req.device_id,
         &state.config.jwt_secret,
         state.config.jwt_expiration,
+        state.clock.as_ref(),
     )?;
 
     let expires_at = now + state.config.jwt_expiration;
 
-    Ok(Json(RegisterResponse { token, expires_at }))
+    // Prove our identity in the same response that hands out the token
+    let identity_signature = req.challenge.map(|challenge| {
+        base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            state.identity.sign_session(&challenge, &req.device_id),
+        )
+    });
+
+    Ok(Json(RegisterResponse {
+        token,
+        expires_at,
+        identity_signature,
+    }))
 }
 
 // ============================================================================
@@ -103,6 +129,15 @@ pub async fn unregister_device(
 #[derive(Debug, Deserialize)]
 pub struct RelayRequest {
     pub encrypted_message: String, // Base64 encoded
+    /// Client-generated id, stable across retries of the same send
+    #[serde(default)]
+    pub message_id: Option<String>,
+    /// Sender's send time, covered by `signature`
+    #[serde(default)]
+    pub signed_at: Option<u64>,
+    /// Sender's signature over the payload, recipient and `signed_at`
+    #[serde(default)]
+    pub signature: Option<String>,
 }
 
 pub async fn relay_message(
@@ -111,31 +146,72 @@ pub async fn relay_message(
     Path(target_device_id): Path<String>,
     Json(req): Json<RelayRequest>,
 ) -> ApiResult<StatusCode> {
+    deliver_message(
+        &state,
+        &auth.device_id,
+        &target_device_id,
+        req.message_id,
+        req.encrypted_message,
+        req.signed_at,
+        req.signature,
+    )
+    .await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Deliver a message directly if the target is connected, otherwise queue it
+///
+/// The sender's signature is passed through untouched; only the recipient
+/// can check it.
+async fn deliver_message(
+    state: &AppState,
+    from_device: &str,
+    target_device_id: &str,
+    message_id: Option<String>,
+    encrypted_payload: String,
+    signed_at: Option<u64>,
+    signature: Option<String>,
+) -> ApiResult<()> {
     // Check if target device exists
     let _target = state
         .db
-        .get_device(&target_device_id)
+        .get_device(target_device_id)
         .await?
         .ok_or_else(|| ApiError::NotFound("Target device not found".to_string()))?;
 
+    if message_id
+        .as_ref()
+        .is_some_and(|id| id.len() > MAX_MESSAGE_ID_LEN)
+    {
+        return Err(ApiError::BadRequest("Message id too long".to_string()));
+    }
+    if signature
+        .as_ref()
+        .is_some_and(|signature| signature.len() > MAX_SIGNATURE_LEN)
+    {
+        return Err(ApiError::BadRequest("Signature too long".to_string()));
+    }
+    if payload_size(&encrypted_payload) > state.config.max_payload_bytes {
+        return Err(ApiError::BadRequest("Payload too large".to_string()));
+    }
+
     let message = RelayMessage {
-        id: Uuid::new_v4().to_string(),
-        from_device: auth.device_id.clone(),
-        to_device: target_device_id.clone(),
-        encrypted_payload: req.encrypted_message.clone(),
+        id: message_id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        from_device: from_device.to_string(),
+        to_device: target_device_id.to_string(),
+        encrypted_payload,
         timestamp: SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_millis() as u64,
+        signed_at,
+        signature,
     };
 
     // Try to send directly if device is connected
-    if state
-        .relay
-        .send_to(&target_device_id, message.clone())
-        .await
-    {
-        return Ok(StatusCode::ACCEPTED);
+    if state.relay.send_to(target_device_id, message.clone()).await {
+        return Ok(());
     }
 
     // Otherwise queue for later delivery
@@ -143,15 +219,208 @@ pub async fn relay_message(
         .db
         .queue_message(
             &message.id,
-            &auth.device_id,
-            &target_device_id,
-            &req.encrypted_message,
+            from_device,
+            target_device_id,
+            &message.encrypted_payload,
+            message.signed_at,
+            message.signature.as_deref(),
         )
         .await?;
+    state.relay.notify_queued(target_device_id);
+
+    Ok(())
+}
+
+// ============================================================================
+// HTTP Long-Poll Transport
+// ============================================================================
+
+/// Default time a poll waits for a message
+const DEFAULT_POLL_TIMEOUT_SECS: u64 = 30;
+
+/// Upper bound on the wait a client may request
+const MAX_POLL_TIMEOUT_SECS: u64 = 60;
+
+#[derive(Debug, Deserialize)]
+pub struct SendMessageRequest {
+    pub to_device: String,
+    pub encrypted_payload: String, // Base64 encoded
+    /// Client-generated id, stable across retries of the same send
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Sender's send time, covered by `signature`
+    #[serde(default)]
+    pub signed_at: Option<u64>,
+    /// Sender's signature over the payload, recipient and `signed_at`
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+pub async fn send_message(
+    State(state): State<AppState>,
+    auth: AuthenticatedDevice,
+    Json(req): Json<SendMessageRequest>,
+) -> ApiResult<StatusCode> {
+    deliver_message(
+        &state,
+        &auth.device_id,
+        &req.to_device,
+        req.id,
+        req.encrypted_payload,
+        req.signed_at,
+        req.signature,
+    )
+    .await?;
 
     Ok(StatusCode::ACCEPTED)
 }
 
+#[derive(Debug, Deserialize)]
+pub struct PollQuery {
+    /// Seconds to wait for a message (default 30, max 60)
+    pub timeout_secs: Option<u64>,
+    /// `cursor` of the last poll response the client received
+    pub ack: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PollResponse {
+    pub messages: Vec<RelayMessage>,
+    /// Position of the last message, to acknowledge with the next poll
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<i64>,
+}
+
+/// Wait for messages for the authenticated device
+///
+/// Messages up to `ack` are removed from the queue first. The rest of the
+/// queue is returned immediately if there is any; otherwise the poll waits
+/// for a message to be queued. Returned messages stay queued until a later
+/// poll acknowledges them, so a response lost on the way is sent again. An
+/// empty list means the poll timed out.
+pub async fn poll_messages(
+    State(state): State<AppState>,
+    auth: AuthenticatedDevice,
+    Query(query): Query<PollQuery>,
+) -> ApiResult<Json<PollResponse>> {
+    let timeout = Duration::from_secs(
+        query
+            .timeout_secs
+            .unwrap_or(DEFAULT_POLL_TIMEOUT_SECS)
+            .min(MAX_POLL_TIMEOUT_SECS),
+    );
+    let deadline = Instant::now() + timeout;
+
+    if let Some(ack) = query.ack {
+        state
+            .db
+            .delete_queued_messages_through(&auth.device_id, ack)
+            .await?;
+    }
+
+    // Counts the device as connected, so sends to it are queued right away
+    let poll = state.relay.open_poll(&auth.device_id);
+    loop {
+        // Listen before reading the queue so nothing queued in between is missed
+        let queued = poll.queued();
+        tokio::pin!(queued);
+        queued.as_mut().enable();
+
+        let messages = state.db.get_queued_messages(&auth.device_id).await?;
+        if !messages.is_empty() || tokio::time::timeout_at(deadline, queued).await.is_err() {
+            return Ok(Json(PollResponse {
+                cursor: messages.last().map(|msg| msg.seq),
+                messages: messages.into_iter().map(Into::into).collect(),
+            }));
+        }
+    }
+}
+
+// ============================================================================
+// Message Queue
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+pub struct QueueStatusResponse {
+    pub count: u64,
+    /// When the oldest message was queued (unix seconds)
+    pub oldest_queued_at: Option<i64>,
+}
+
+/// Report the messages queued for the authenticated device
+pub async fn queue_status(
+    State(state): State<AppState>,
+    auth: AuthenticatedDevice,
+) -> ApiResult<Json<QueueStatusResponse>> {
+    let (count, oldest_queued_at) = state.db.queue_status(&auth.device_id).await?;
+
+    Ok(Json(QueueStatusResponse {
+        count,
+        oldest_queued_at,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct FlushQueueResponse {
+    pub deleted: u64,
+}
+
+/// Drop every message queued for the authenticated device
+///
+/// A recovery path for a queue that can't be delivered, e.g. because a
+/// message in it keeps failing on the client.
+pub async fn flush_queue(
+    State(state): State<AppState>,
+    auth: AuthenticatedDevice,
+) -> ApiResult<Json<FlushQueueResponse>> {
+    let deleted = state.db.delete_queued_messages(&auth.device_id).await?;
+
+    Ok(Json(FlushQueueResponse { deleted }))
+}
+
+// ============================================================================
+// Server Identity
+// ============================================================================
+
+/// Longest accepted identity challenge
+pub(crate) const MAX_IDENTITY_CHALLENGE_LEN: usize = 128;
+
+#[derive(Debug, Deserialize)]
+pub struct IdentityQuery {
+    pub challenge: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IdentityResponse {
+    pub public_key: String,        // Base64 encoded
+    pub signature: Option<String>, // Base64 encoded, over "relay-identity:" + challenge
+}
+
+/// Report the relay's identity key, signing the client's challenge if given
+pub async fn server_identity(
+    State(state): State<AppState>,
+    Query(query): Query<IdentityQuery>,
+) -> ApiResult<Json<IdentityResponse>> {
+    let signature = match query.challenge {
+        Some(challenge) if challenge.len() > MAX_IDENTITY_CHALLENGE_LEN => {
+            return Err(ApiError::BadRequest("Challenge too long".to_string()));
+        }
+        Some(challenge) => Some(base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            state.identity.sign_challenge(&challenge),
+        )),
+        None => None,
+    };
+
+    Ok(Json(IdentityResponse {
+        public_key: base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            state.identity.public_key(),
+        ),
+        signature,
+    }))
+}
+
 // ============================================================================
 // Device Status
 // ============================================================================
@@ -188,6 +457,31 @@ pub async fn device_status(
 // Pairing
 // ============================================================================
 
+/// Decode a base64 X25519 pairing key, refusing keys no exchange can use
+///
+/// The key must be 32 bytes and a point on Curve25519 outside the small
+/// subgroup; twist and low-order points would otherwise only fail later, on
+/// the client, during the key exchange.
+fn decode_pairing_key(encoded: &str) -> ApiResult<Vec<u8>> {
+    let public_key = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, encoded)
+        .map_err(|_| ApiError::BadRequest("Invalid public key encoding".to_string()))?;
+
+    let bytes: [u8; 32] = public_key
+        .as_slice()
+        .try_into()
+        .map_err(|_| ApiError::BadRequest("Public key must be 32 bytes".to_string()))?;
+    let valid = MontgomeryPoint(bytes)
+        .to_edwards(0)
+        .is_some_and(|point| !point.is_small_order());
+    if !valid {
+        return Err(ApiError::BadRequest(
+            "Public key is not a valid X25519 key".to_string(),
+        ));
+    }
+
+    Ok(public_key)
+}
+
 #[derive(Debug, Deserialize)]
 pub struct RegisterPairingRequest {
     pub code: String,
@@ -213,24 +507,11 @@ pub async fn register_pairing(
         ));
     }
 
-    // Decode public key
-    let public_key =
-        base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &req.public_key)
-            .map_err(|_| ApiError::BadRequest("Invalid public key encoding".to_string()))?;
-
-    // Validate public key length (32 bytes for X25519)
-    if public_key.len() != 32 {
-        return Err(ApiError::BadRequest(
-            "Public key must be 32 bytes".to_string(),
-        ));
-    }
+    let public_key = decode_pairing_key(&req.public_key)?;
 
     // Calculate expiration (default 5 minutes)
     let expires_in = req.expires_in_secs.unwrap_or(300);
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    let now = state.clock.now_secs() as u64;
     let expires_at = now + expires_in;
 
     // Register pairing session
@@ -270,6 +551,9 @@ pub async fn find_pairing(
             ApiError::NotFound("Pairing session not found or expired".to_string())
         })?;
 
+    // Allows the finder to report completion to the advertiser later
+    state.db.record_pairing_find(&code).await?;
+
     // Encode public key as base64
     let public_key = base64::Engine::encode(
         &base64::engine::general_purpose::STANDARD,
@@ -284,6 +568,113 @@ pub async fn find_pairing(
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CompletePairingRequest {
+    pub public_key: String, // Base64 encoded
+    pub device_name: String,
+}
+
+/// Report that pairing with a looked-up session was completed
+///
+/// Wakes the advertiser if it is waiting on the session's status.
+pub async fn complete_pairing(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    Json(req): Json<CompletePairingRequest>,
+) -> ApiResult<StatusCode> {
+    if code.len() != 6 || !code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(ApiError::BadRequest(
+            "Pairing code must be 6 digits".to_string(),
+        ));
+    }
+
+    let public_key = decode_pairing_key(&req.public_key)?;
+
+    if !state
+        .db
+        .complete_pairing(&code, &public_key, &req.device_name)
+        .await?
+    {
+        return Err(ApiError::NotFound(
+            "No pending pairing lookup for this code".to_string(),
+        ));
+    }
+
+    state.relay.notify_pairing_completed(&code);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Serialize)]
+pub struct PairingStatusResponse {
+    pub code: String,
+    pub completed: bool,
+    pub peer_public_key: Option<String>, // Base64 encoded
+    pub peer_device_name: Option<String>,
+}
+
+/// Wait for a pairing session to be completed by the device that found it
+///
+/// Returns as soon as the session is completed, or with `completed: false`
+/// after `timeout_secs` (default 30, max 60).
+pub async fn pairing_status(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    Query(query): Query<PollQuery>,
+) -> ApiResult<Json<PairingStatusResponse>> {
+    let timeout = Duration::from_secs(
+        query
+            .timeout_secs
+            .unwrap_or(DEFAULT_POLL_TIMEOUT_SECS)
+            .min(MAX_POLL_TIMEOUT_SECS),
+    );
+
+    // Subscribe before checking so a completion in between isn't missed
+    let mut completions = state.relay.subscribe_pairing_completions();
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    loop {
+        if let Some(event) = state.db.get_pairing_event(&code).await? {
+            if event.completed_at.is_some() {
+                return Ok(Json(PairingStatusResponse {
+                    code,
+                    completed: true,
+                    peer_public_key: event.peer_public_key.map(|key| {
+                        base64::Engine::encode(&base64::engine::general_purpose::STANDARD, key)
+                    }),
+                    peer_device_name: event.peer_device_name,
+                }));
+            }
+        }
+
+        if state.db.find_pairing(&code).await?.is_none() {
+            return Err(ApiError::NotFound(
+                "Pairing session not found or expired".to_string(),
+            ));
+        }
+
+        // Wait for a completion of this code (or a lagged channel, then recheck)
+        let woke = tokio::time::timeout_at(deadline, async {
+            loop {
+                match completions.recv().await {
+                    Ok(completed) if completed == code => break,
+                    Ok(_) => continue,
+                    Err(_) => break,
+                }
+            }
+        })
+        .await;
+
+        if woke.is_err() {
+            return Ok(Json(PairingStatusResponse {
+                code,
+                completed: false,
+                peer_public_key: None,
+                peer_device_name: None,
+            }));
+        }
+    }
+}
+
 pub async fn cancel_pairing(
     State(state): State<AppState>,
     Path(code): Path<String>,
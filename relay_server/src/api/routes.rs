@@ -14,12 +14,22 @@ pub fn create_router() -> Router<AppState> {
         // Health check
         .route("/health", get(health_check))
         .route("/api/health", get(health_check))
+        // Relay identity, for key pinning
+        .route("/api/v1/identity", get(handlers::server_identity))
         // Device registration
         .route("/api/register", post(handlers::register_device))
         .route("/api/v1/register", post(handlers::register_device))
         .route("/api/v1/register", delete(handlers::unregister_device))
         // Message relay (Axum 0.8 uses {param} instead of :param)
         .route("/api/v1/relay/{device_id}", post(handlers::relay_message))
+        // HTTP long-poll transport for clients that can't use WebSockets
+        .route("/api/v1/messages/send", post(handlers::send_message))
+        .route("/api/v1/messages/poll", get(handlers::poll_messages))
+        // Messages queued for the authenticated device
+        .route(
+            "/api/v1/queue",
+            get(handlers::queue_status).delete(handlers::flush_queue),
+        )
         // Device status
         .route(
             "/api/v1/devices/{device_id}/status",
@@ -29,8 +39,18 @@ pub fn create_router() -> Router<AppState> {
         .route("/api/v1/pairing/register", post(handlers::register_pairing))
         .route("/api/v1/pairing/find/{code}", get(handlers::find_pairing))
         .route("/api/v1/pairing/{code}", delete(handlers::cancel_pairing))
+        .route(
+            "/api/v1/pairing/{code}/complete",
+            post(handlers::complete_pairing),
+        )
+        .route(
+            "/api/v1/pairing/{code}/status",
+            get(handlers::pairing_status),
+        )
         // WebSocket
         .route("/api/v1/ws", get(websocket::ws_handler))
+        // Per-peer WebSocket used by the client fallback transport
+        .route("/ws/{device_id}", get(websocket::peer_ws_handler))
 }
 
 async fn health_check() -> &'static str {
@@ -3,7 +3,7 @@
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        Path, State,
     },
     response::IntoResponse,
 };
@@ -13,7 +13,15 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
-use crate::{auth::verify_signature, relay::RelayMessage, AppState};
+use crate::{
+    api::handlers::MAX_IDENTITY_CHALLENGE_LEN,
+    auth::verify_signature,
+    relay::{
+        frame::{self, BINARY_SUBPROTOCOL},
+        payload_size, RelayMessage, MAX_MESSAGE_ID_LEN, MAX_SIGNATURE_LEN,
+    },
+    AppState,
+};
 
 /// WebSocket authentication message (for documentation)
 #[allow(dead_code)]
@@ -22,6 +30,7 @@ struct WsAuthMessage {
     device_id: String,
     timestamp: u64,
     signature: String,
+    challenge: Option<String>,
 }
 
 /// WebSocket relay message (for documentation)
@@ -30,6 +39,8 @@ struct WsAuthMessage {
 struct WsRelayRequest {
     to_device: String,
     encrypted_payload: String,
+    signed_at: Option<u64>,
+    signature: Option<String>,
 }
 
 /// WebSocket message envelope
@@ -41,39 +52,85 @@ enum WsMessage {
         device_id: String,
         timestamp: u64,
         signature: String,
+        /// Client nonce for the relay to sign, proving its identity on this connection
+        #[serde(default)]
+        challenge: Option<String>,
     },
     #[serde(rename = "relay")]
     Relay { message: RelayMessage },
     #[serde(rename = "send")]
     Send {
+        /// Client-generated id, stable across retries of the same send
+        #[serde(default)]
+        id: Option<String>,
         to_device: String,
         encrypted_payload: String,
+        /// Sender's send time, covered by `signature`
+        #[serde(default)]
+        signed_at: Option<u64>,
+        /// Sender's signature over the payload, recipient and `signed_at`
+        #[serde(default)]
+        signature: Option<String>,
     },
     #[serde(rename = "auth_response")]
     AuthResponse {
         success: bool,
         error: Option<String>,
+        /// Relay's signature over the auth `challenge` (base64), see [`crate::auth::ServerIdentity::sign_session`]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        identity_signature: Option<String>,
     },
     #[serde(rename = "error")]
     Error { message: String },
 }
 
 /// Handle WebSocket upgrade
+///
+/// Clients offering [`BINARY_SUBPROTOCOL`] get relay messages as binary
+/// frames; everyone else gets JSON.
 pub async fn ws_handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+    let ws = ws.protocols([BINARY_SUBPROTOCOL]);
+    let binary = ws.selected_protocol().is_some();
+    ws.on_upgrade(move |socket| handle_socket(socket, state, binary, None))
+}
+
+/// Handle WebSocket upgrade for a connection dedicated to one peer
+///
+/// Used by the client's fallback transport when a direct connection to
+/// `peer_id` fails. Authentication and send frames work as on [`ws_handler`],
+/// but the connection is send-only: it isn't registered for delivery, so the
+/// device's primary connection keeps receiving its messages. Every send must
+/// be addressed to `peer_id` and carry the sender's signature.
+pub async fn peer_ws_handler(
+    Path(peer_id): Path<String>,
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let ws = ws.protocols([BINARY_SUBPROTOCOL]);
+    let binary = ws.selected_protocol().is_some();
+    ws.on_upgrade(move |socket| handle_socket(socket, state, binary, Some(peer_id)))
 }
 
 /// Handle WebSocket connection
-async fn handle_socket(socket: WebSocket, state: AppState) {
+///
+/// With a `peer`, the connection only sends to it, see [`peer_ws_handler`].
+async fn handle_socket(socket: WebSocket, state: AppState, binary: bool, peer: Option<String>) {
     let (mut sender, mut receiver) = socket.split();
 
     // Wait for authentication message
     let device_id = match authenticate(&mut receiver, &state).await {
-        Ok(id) => {
-            // Send success response
+        Ok((id, challenge)) => {
+            // Send success response, proving our identity if asked to
+            let identity_signature = challenge.map(|challenge| {
+                base64::Engine::encode(
+                    &base64::engine::general_purpose::STANDARD,
+                    state.identity.sign_session(&challenge, &id),
+                )
+            });
             let response = WsMessage::AuthResponse {
                 success: true,
                 error: None,
+                identity_signature,
             };
             if let Ok(json) = serde_json::to_string(&response) {
                 let _ = sender.send(Message::Text(json.into())).await;
@@ -85,6 +142,7 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
             let response = WsMessage::AuthResponse {
                 success: false,
                 error: Some(e),
+                identity_signature: None,
             };
             if let Ok(json) = serde_json::to_string(&response) {
                 let _ = sender.send(Message::Text(json.into())).await;
@@ -98,31 +156,34 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
     // Create channel for outgoing messages
     let (tx, mut rx) = mpsc::channel::<RelayMessage>(100);
 
-    // Register connection
-    state.relay.register(device_id.clone(), tx);
-
-    // Update device status
-    let _ = state.db.update_device_status(&device_id, true).await;
-
-    // Deliver queued messages
-    if let Ok(queued) = state.db.get_queued_messages(&device_id).await {
-        for msg in queued {
-            let relay_msg = RelayMessage {
-                id: msg.id,
-                from_device: msg.from_device,
-                to_device: msg.to_device,
-                encrypted_payload: msg.encrypted_payload,
-                timestamp: msg.created_at as u64,
-            };
-            let envelope = WsMessage::Relay { message: relay_msg };
-            if let Ok(json) = serde_json::to_string(&envelope) {
-                if sender.send(Message::Text(json.into())).await.is_err() {
-                    break;
+    // Peer connections are send-only and leave the primary one registered
+    if peer.is_none() {
+        // Register connection
+        state.relay.register(device_id.clone(), tx.clone());
+
+        // Update device status
+        let _ = state.db.update_device_status(&device_id, true).await;
+
+        // Deliver queued messages
+        if let Ok(queued) = state.db.get_queued_messages(&device_id).await {
+            let mut delivered = None;
+            for msg in queued {
+                let seq = msg.seq;
+                if let Some(frame) = relay_frame(msg.into(), binary) {
+                    if sender.send(frame).await.is_err() {
+                        break;
+                    }
                 }
+                delivered = Some(seq);
+            }
+            // Clear delivered messages, leaving any queued since
+            if let Some(seq) = delivered {
+                let _ = state
+                    .db
+                    .delete_queued_messages_through(&device_id, seq)
+                    .await;
             }
         }
-        // Clear delivered messages
-        let _ = state.db.delete_queued_messages(&device_id).await;
     }
 
     // Main loop
@@ -132,7 +193,15 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
             Some(msg) = receiver.next() => {
                 match msg {
                     Ok(Message::Text(text)) => {
-                        if let Err(e) = handle_client_message(&text.to_string(), &device_id, &state).await {
+                        if let Err(e) = handle_client_message(&text.to_string(), &device_id, peer.as_deref(), &state).await {
+                            let error = WsMessage::Error { message: e };
+                            if let Ok(json) = serde_json::to_string(&error) {
+                                let _ = sender.send(Message::Text(json.into())).await;
+                            }
+                        }
+                    }
+                    Ok(Message::Binary(data)) => {
+                        if let Err(e) = handle_client_frame(&data, &device_id, peer.as_deref(), &state).await {
                             let error = WsMessage::Error { message: e };
                             if let Ok(json) = serde_json::to_string(&error) {
                                 let _ = sender.send(Message::Text(json.into())).await;
@@ -150,9 +219,8 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
 
             // Handle outgoing relay messages
             Some(relay_msg) = rx.recv() => {
-                let envelope = WsMessage::Relay { message: relay_msg };
-                if let Ok(json) = serde_json::to_string(&envelope) {
-                    if sender.send(Message::Text(json.into())).await.is_err() {
+                if let Some(frame) = relay_frame(relay_msg, binary) {
+                    if sender.send(frame).await.is_err() {
                         break;
                     }
                 }
@@ -164,15 +232,38 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
 
     // Cleanup
     tracing::info!("Device {} disconnected", device_id);
-    state.relay.unregister(&device_id);
-    let _ = state.db.update_device_status(&device_id, false).await;
+    if peer.is_none() {
+        // A newer connection for the same device stays registered
+        state.relay.unregister_sender(&device_id, &tx);
+        let _ = state.db.update_device_status(&device_id, false).await;
+    }
+}
+
+/// Wrap a relay message for delivery
+///
+/// Binary connections get a binary frame unless the message can't be encoded
+/// as one, in which case it goes out as JSON like on any other connection.
+fn relay_frame(message: RelayMessage, binary: bool) -> Option<Message> {
+    if binary {
+        match frame::encode(&message) {
+            Ok(bytes) => return Some(Message::Binary(bytes.into())),
+            Err(e) => tracing::debug!("Sending message {} as JSON: {}", message.id, e),
+        }
+    }
+
+    let envelope = WsMessage::Relay { message };
+    serde_json::to_string(&envelope)
+        .ok()
+        .map(|json| Message::Text(json.into()))
 }
 
 /// Authenticate WebSocket connection
+///
+/// Returns the device id and the client's identity challenge, if it sent one.
 async fn authenticate(
     receiver: &mut futures::stream::SplitStream<WebSocket>,
     state: &AppState,
-) -> Result<String, String> {
+) -> Result<(String, Option<String>), String> {
     // Wait for auth message with timeout
     let auth_msg = tokio::time::timeout(std::time::Duration::from_secs(10), receiver.next())
         .await
@@ -188,20 +279,25 @@ async fn authenticate(
     let msg: WsMessage =
         serde_json::from_str(&text).map_err(|e| format!("Invalid message format: {}", e))?;
 
-    let (device_id, timestamp, signature) = match msg {
+    let (device_id, timestamp, signature, challenge) = match msg {
         WsMessage::Auth {
             device_id,
             timestamp,
             signature,
-        } => (device_id, timestamp, signature),
+            challenge,
+        } => (device_id, timestamp, signature, challenge),
         _ => return Err("Expected auth message".to_string()),
     };
 
+    if challenge
+        .as_ref()
+        .is_some_and(|c| c.len() > MAX_IDENTITY_CHALLENGE_LEN)
+    {
+        return Err("Challenge too long".to_string());
+    }
+
     // Verify timestamp freshness
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    let now = state.clock.now_secs() as u64;
     if now.abs_diff(timestamp) > 300 {
         return Err("Timestamp too old".to_string());
     }
@@ -226,13 +322,16 @@ async fn authenticate(
         return Err("Invalid signature".to_string());
     }
 
-    Ok(device_id)
+    Ok((device_id, challenge))
 }
 
 /// Handle incoming client message
+///
+/// On a peer connection, sends must go to `peer`, see [`check_peer_send`].
 async fn handle_client_message(
     text: &str,
     from_device: &str,
+    peer: Option<&str>,
     state: &AppState,
 ) -> Result<(), String> {
     let msg: WsMessage =
@@ -240,32 +339,132 @@ async fn handle_client_message(
 
     match msg {
         WsMessage::Send {
+            id,
             to_device,
             encrypted_payload,
+            signed_at,
+            signature,
         } => {
-            let relay_msg = RelayMessage {
-                id: Uuid::new_v4().to_string(),
-                from_device: from_device.to_string(),
-                to_device: to_device.clone(),
-                encrypted_payload: encrypted_payload.clone(),
-                timestamp: SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as u64,
-            };
-
-            // Try direct delivery
-            if !state.relay.send_to(&to_device, relay_msg.clone()).await {
-                // Queue for later
-                state
-                    .db
-                    .queue_message(&relay_msg.id, from_device, &to_device, &encrypted_payload)
-                    .await
-                    .map_err(|e| format!("Failed to queue message: {}", e))?;
-            }
-
-            Ok(())
+            check_peer_send(peer, &to_device, signed_at, signature.as_deref())?;
+            relay_send(
+                state,
+                from_device,
+                id,
+                to_device,
+                encrypted_payload,
+                signed_at,
+                signature,
+            )
+            .await
         }
         _ => Err("Unexpected message type".to_string()),
     }
 }
+
+/// Handle a binary send frame from the client
+///
+/// The frame's `from_device` and `timestamp` are ignored. On a peer
+/// connection, sends must go to `peer`, see [`check_peer_send`].
+async fn handle_client_frame(
+    data: &[u8],
+    from_device: &str,
+    peer: Option<&str>,
+    state: &AppState,
+) -> Result<(), String> {
+    let msg = frame::decode(data).map_err(|e| format!("Invalid message: {}", e))?;
+    check_peer_send(
+        peer,
+        &msg.to_device,
+        msg.signed_at,
+        msg.signature.as_deref(),
+    )?;
+
+    relay_send(
+        state,
+        from_device,
+        (!msg.id.is_empty()).then_some(msg.id),
+        msg.to_device,
+        msg.encrypted_payload,
+        msg.signed_at,
+        msg.signature,
+    )
+    .await
+}
+
+/// Check a send on a peer connection is a signed send to its peer
+///
+/// Sends on the primary connection (`peer` is `None`) always pass.
+fn check_peer_send(
+    peer: Option<&str>,
+    to_device: &str,
+    signed_at: Option<u64>,
+    signature: Option<&str>,
+) -> Result<(), String> {
+    let Some(peer) = peer else {
+        return Ok(());
+    };
+    if to_device != peer {
+        return Err("Peer connections only send to their peer".to_string());
+    }
+    if signed_at.is_none() || signature.is_none_or(str::is_empty) {
+        return Err("Sends on peer connections must be signed".to_string());
+    }
+    Ok(())
+}
+
+/// Deliver a client's send, or queue it if the recipient isn't connected
+async fn relay_send(
+    state: &AppState,
+    from_device: &str,
+    id: Option<String>,
+    to_device: String,
+    encrypted_payload: String,
+    signed_at: Option<u64>,
+    signature: Option<String>,
+) -> Result<(), String> {
+    if id.as_ref().is_some_and(|id| id.len() > MAX_MESSAGE_ID_LEN) {
+        return Err("Message id too long".to_string());
+    }
+    if signature
+        .as_ref()
+        .is_some_and(|signature| signature.len() > MAX_SIGNATURE_LEN)
+    {
+        return Err("Signature too long".to_string());
+    }
+    if payload_size(&encrypted_payload) > state.config.max_payload_bytes {
+        return Err("Payload too large".to_string());
+    }
+
+    let relay_msg = RelayMessage {
+        id: id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        from_device: from_device.to_string(),
+        to_device: to_device.clone(),
+        encrypted_payload: encrypted_payload.clone(),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64,
+        signed_at,
+        signature,
+    };
+
+    // Try direct delivery
+    if !state.relay.send_to(&to_device, relay_msg.clone()).await {
+        // Queue for later
+        state
+            .db
+            .queue_message(
+                &relay_msg.id,
+                from_device,
+                &to_device,
+                &encrypted_payload,
+                relay_msg.signed_at,
+                relay_msg.signature.as_deref(),
+            )
+            .await
+            .map_err(|e| format!("Failed to queue message: {}", e))?;
+        state.relay.notify_queued(&to_device);
+    }
+
+    Ok(())
+}
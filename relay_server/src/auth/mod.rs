@@ -9,13 +9,18 @@ use axum_extra::{
     headers::{authorization::Bearer, Authorization},
     TypedHeader,
 };
-use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::{config::Config, error::ApiError, AppState};
+use crate::{clock::Clock, config::Config, db::Database, error::ApiError, AppState};
+
+/// Prefix of the message the relay signs to prove its identity
+pub const IDENTITY_CHALLENGE_PREFIX: &str = "relay-identity:";
+
+/// Prefix of the message the relay signs to prove its identity within a session
+pub const SESSION_CHALLENGE_PREFIX: &str = "relay-session:";
 
 impl FromRef<AppState> for Arc<Config> {
     fn from_ref(state: &AppState) -> Self {
@@ -54,15 +59,14 @@ impl FromRequestParts<AppState> for AuthenticatedDevice {
             .await
             .map_err(|_| ApiError::Unauthorized("Missing authorization header".to_string()))?;
 
-        let token_data = decode::<Claims>(
+        let claims = validate_token_with_secrets(
             bearer.token(),
-            &DecodingKey::from_secret(config.jwt_secret.as_bytes()),
-            &Validation::default(),
-        )
-        .map_err(|e| ApiError::Unauthorized(format!("Invalid token: {}", e)))?;
+            &config.jwt_validation_secrets(),
+            state.clock.as_ref(),
+        )?;
 
         Ok(AuthenticatedDevice {
-            device_id: token_data.claims.sub,
+            device_id: claims.sub,
         })
     }
 }
@@ -72,11 +76,9 @@ pub fn create_token(
     device_id: &str,
     secret: &str,
     expiration_secs: u64,
+    clock: &dyn Clock,
 ) -> Result<String, ApiError> {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    let now = clock.now_secs().max(0) as u64;
 
     let claims = Claims {
         sub: device_id.to_string(),
@@ -92,6 +94,103 @@ pub fn create_token(
     .map_err(|e| ApiError::Internal(format!("Failed to create token: {}", e)))
 }
 
+/// Decode a JWT token and check it has not expired
+///
+/// Expiry is checked against `clock` with no leeway: a token is rejected from
+/// its `exp` second onwards.
+pub fn validate_token(token: &str, secret: &str, clock: &dyn Clock) -> Result<Claims, ApiError> {
+    validate_token_with_secrets(token, &[secret], clock)
+}
+
+/// Decode a JWT token signed with any of `secrets` and check it has not expired
+///
+/// Secrets are tried in order; the first one whose signature matches decides.
+/// Used during secret rotation, with the primary secret first.
+pub fn validate_token_with_secrets(
+    token: &str,
+    secrets: &[&str],
+    clock: &dyn Clock,
+) -> Result<Claims, ApiError> {
+    let mut validation = Validation::default();
+    validation.validate_exp = false;
+
+    let mut last_error = ApiError::Unauthorized("Invalid token: no secret configured".to_string());
+    for secret in secrets {
+        match decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(secret.as_bytes()),
+            &validation,
+        ) {
+            Ok(data) => {
+                if clock.now_secs() >= data.claims.exp as i64 {
+                    return Err(ApiError::Unauthorized("Token expired".to_string()));
+                }
+                return Ok(data.claims);
+            }
+            Err(e) => last_error = ApiError::Unauthorized(format!("Invalid token: {}", e)),
+        }
+    }
+    Err(last_error)
+}
+
+/// The relay's long-term Ed25519 identity
+///
+/// Clients can pin its public key and check a signed challenge on connect.
+pub struct ServerIdentity {
+    signing_key: SigningKey,
+}
+
+impl ServerIdentity {
+    /// Create an identity from a 32-byte Ed25519 seed
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        Self {
+            signing_key: SigningKey::from_bytes(seed),
+        }
+    }
+
+    /// Load the configured identity, or the one stored in the database
+    ///
+    /// A new key is generated and stored on first start.
+    pub async fn load(config: &Config, db: &Database) -> anyhow::Result<Self> {
+        let seed = match &config.identity_key {
+            Some(encoded) => {
+                base64::Engine::decode(&base64::engine::general_purpose::STANDARD, encoded)?
+            }
+            None => {
+                use rand::Rng;
+                let mut seed = [0u8; 32];
+                rand::thread_rng().fill(&mut seed);
+                db.get_or_create_identity_seed(&seed).await?
+            }
+        };
+
+        let seed: [u8; 32] = seed
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Identity key seed must be 32 bytes"))?;
+        Ok(Self::from_seed(&seed))
+    }
+
+    /// The identity public key
+    pub fn public_key(&self) -> [u8; 32] {
+        self.signing_key.verifying_key().to_bytes()
+    }
+
+    /// Sign a client's identity challenge
+    pub fn sign_challenge(&self, challenge: &str) -> [u8; 64] {
+        let message = format!("{}{}", IDENTITY_CHALLENGE_PREFIX, challenge);
+        self.signing_key.sign(message.as_bytes()).to_bytes()
+    }
+
+    /// Sign the challenge a client sent while authenticating as `device_id`
+    ///
+    /// Answered on the connection being authenticated, so the proof can't be
+    /// fetched separately and replayed by whoever sits between client and relay.
+    pub fn sign_session(&self, challenge: &str, device_id: &str) -> [u8; 64] {
+        let message = format!("{}{}:{}", SESSION_CHALLENGE_PREFIX, challenge, device_id);
+        self.signing_key.sign(message.as_bytes()).to_bytes()
+    }
+}
+
 /// Verify an Ed25519 signature
 pub fn verify_signature(
     public_key: &[u8],
@@ -117,13 +216,16 @@ pub fn verify_signature(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::{ManualClock, SystemClock};
+    use chrono::DateTime;
+    use std::time::Duration;
 
     #[test]
     fn test_create_and_decode_token() {
         let secret = "test-secret";
         let device_id = "test-device";
 
-        let token = create_token(device_id, secret, 3600).unwrap();
+        let token = create_token(device_id, secret, 3600, &SystemClock).unwrap();
 
         let token_data = decode::<Claims>(
             &token,
@@ -134,4 +236,78 @@ mod tests {
 
         assert_eq!(token_data.claims.sub, device_id);
     }
+
+    #[test]
+    fn test_server_identity_signs_challenge() {
+        let identity = ServerIdentity::from_seed(&[7u8; 32]);
+        let signature = identity.sign_challenge("abc");
+
+        let message = format!("{}abc", IDENTITY_CHALLENGE_PREFIX);
+        assert!(verify_signature(&identity.public_key(), message.as_bytes(), &signature).unwrap());
+        assert!(!verify_signature(&identity.public_key(), b"abc", &signature).unwrap());
+    }
+
+    #[test]
+    fn test_session_signature_is_not_an_identity_signature() {
+        let identity = ServerIdentity::from_seed(&[7u8; 32]);
+        let signature = identity.sign_session("abc", "device");
+
+        let message = format!("{}abc:device", SESSION_CHALLENGE_PREFIX);
+        assert!(verify_signature(&identity.public_key(), message.as_bytes(), &signature).unwrap());
+        // Nor can an identity challenge be passed off as a session proof
+        let forged = identity.sign_challenge("abc:device");
+        assert!(!verify_signature(&identity.public_key(), message.as_bytes(), &forged).unwrap());
+    }
+
+    #[test]
+    fn test_token_expires_at_boundary() {
+        let clock = ManualClock::new(DateTime::from_timestamp(1_000_000, 0).unwrap());
+        let token = create_token("test-device", "test-secret", 3600, &clock).unwrap();
+
+        clock.advance(Duration::from_secs(3599));
+        let claims = validate_token(&token, "test-secret", &clock).unwrap();
+        assert_eq!(claims.sub, "test-device");
+        assert_eq!(claims.exp, 1_003_600);
+
+        clock.advance(Duration::from_secs(1));
+        assert!(matches!(
+            validate_token(&token, "test-secret", &clock),
+            Err(ApiError::Unauthorized(_))
+        ));
+
+        // A wrong secret is rejected regardless of the time
+        clock.set(DateTime::from_timestamp(1_000_000, 0).unwrap());
+        assert!(validate_token(&token, "other-secret", &clock).is_err());
+    }
+
+    #[test]
+    fn test_token_survives_secret_rotation() {
+        let clock = ManualClock::new(DateTime::from_timestamp(1_000_000, 0).unwrap());
+        let mut config = Config {
+            jwt_secret: "old-secret".to_string(),
+            ..Default::default()
+        };
+        let old_token = create_token("test-device", &config.jwt_secret, 3600, &clock).unwrap();
+
+        // Rotate: new tokens use the new primary, the old secret stays listed
+        config.jwt_previous_secret = Some(config.jwt_secret.clone());
+        config.jwt_secret = "new-secret".to_string();
+        let new_token = create_token("test-device", &config.jwt_secret, 3600, &clock).unwrap();
+
+        let secrets = config.jwt_validation_secrets();
+        let claims = validate_token_with_secrets(&old_token, &secrets, &clock).unwrap();
+        assert_eq!(claims.sub, "test-device");
+        assert!(validate_token_with_secrets(&new_token, &secrets, &clock).is_ok());
+
+        // Expiry still applies to tokens under the previous secret
+        clock.advance(Duration::from_secs(3600));
+        assert!(validate_token_with_secrets(&old_token, &secrets, &clock).is_err());
+        clock.set(DateTime::from_timestamp(1_000_000, 0).unwrap());
+
+        // Once the old secret is dropped its tokens are refused
+        config.jwt_previous_secret = None;
+        let secrets = config.jwt_validation_secrets();
+        assert!(validate_token_with_secrets(&old_token, &secrets, &clock).is_err());
+        assert!(validate_token_with_secrets(&new_token, &secrets, &clock).is_ok());
+    }
 }
@@ -0,0 +1,110 @@
+//! Periodic database housekeeping
+//!
+//! Expired pairing sessions and queued messages nobody collected would
+//! otherwise stay in the database forever.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{db::Database, error::ApiError};
+
+/// Rows removed by one cleanup run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    /// Expired pairing sessions
+    pub pairings: u64,
+    /// Queued messages older than the maximum age
+    pub messages: u64,
+}
+
+/// Remove expired pairing sessions and messages queued more than `message_max_age` ago
+pub async fn run_cleanup(
+    db: &Database,
+    message_max_age: Duration,
+) -> Result<CleanupReport, ApiError> {
+    let pairings = db.cleanup_expired_pairings().await?;
+    let messages = db
+        .cleanup_old_messages(message_max_age.as_secs() as i64)
+        .await?;
+
+    Ok(CleanupReport { pairings, messages })
+}
+
+/// Spawn a task that runs the cleanup every `interval`
+pub fn spawn_cleanup_task(
+    db: Arc<Database>,
+    interval: Duration,
+    message_max_age: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick completes immediately; skip it so startup isn't delayed
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            match run_cleanup(&db, message_max_age).await {
+                Ok(report) => tracing::info!(
+                    "Cleanup removed {} expired pairing sessions and {} old queued messages",
+                    report.pairings,
+                    report.messages
+                ),
+                Err(e) => tracing::warn!("Cleanup failed: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use chrono::DateTime;
+
+    #[tokio::test]
+    async fn test_cleanup_removes_stale_rows() {
+        let clock = Arc::new(ManualClock::new(
+            DateTime::from_timestamp(1_000_000, 0).unwrap(),
+        ));
+        let db = Database::new_with_clock("sqlite::memory:", clock.clone())
+            .await
+            .unwrap();
+        db.migrate().await.unwrap();
+
+        db.upsert_device("a", &[1u8; 32], "A").await.unwrap();
+        db.upsert_device("b", &[2u8; 32], "B").await.unwrap();
+        db.register_pairing("123456", &[7u8; 32], "Device", 1_000_300)
+            .await
+            .unwrap();
+        db.queue_message("m1", "a", "b", "payload", None, None)
+            .await
+            .unwrap();
+
+        let max_age = Duration::from_secs(3600);
+        assert_eq!(
+            run_cleanup(&db, max_age).await.unwrap(),
+            CleanupReport::default()
+        );
+
+        // Past the pairing expiry, but the message is still young enough
+        clock.advance(Duration::from_secs(301));
+        assert_eq!(
+            run_cleanup(&db, max_age).await.unwrap(),
+            CleanupReport {
+                pairings: 1,
+                messages: 0
+            }
+        );
+        assert!(db.find_pairing("123456").await.unwrap().is_none());
+
+        clock.advance(max_age);
+        assert_eq!(
+            run_cleanup(&db, max_age).await.unwrap(),
+            CleanupReport {
+                pairings: 0,
+                messages: 1
+            }
+        );
+        assert!(db.get_queued_messages("b").await.unwrap().is_empty());
+    }
+}
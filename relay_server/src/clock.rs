@@ -0,0 +1,70 @@
+//! Time source for expiry checks
+//!
+//! Token and pairing expiry read the time through [`Clock`] so tests can
+//! move time forward instead of sleeping.
+
+use chrono::{DateTime, Utc};
+#[cfg(test)]
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+#[cfg(test)]
+use std::time::Duration;
+
+/// Source of the current time
+pub trait Clock: Send + Sync {
+    /// Current time
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Current Unix timestamp in seconds
+    fn now_secs(&self) -> i64 {
+        self.now().timestamp()
+    }
+}
+
+/// Clock shared between the database and request handlers
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The real system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that only moves when told to, for tests
+#[cfg(test)]
+#[derive(Debug)]
+pub struct ManualClock {
+    millis: AtomicI64,
+}
+
+#[cfg(test)]
+impl ManualClock {
+    /// Create a clock stopped at `start`
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            millis: AtomicI64::new(start.timestamp_millis()),
+        }
+    }
+
+    /// Jump to `time`
+    pub fn set(&self, time: DateTime<Utc>) {
+        self.millis.store(time.timestamp_millis(), Ordering::SeqCst);
+    }
+
+    /// Move forward by `by`
+    pub fn advance(&self, by: Duration) {
+        self.millis
+            .fetch_add(by.as_millis() as i64, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(self.millis.load(Ordering::SeqCst)).unwrap_or_default()
+    }
+}
@@ -2,6 +2,15 @@
 
 use std::env;
 
+/// Default seconds between database cleanups
+pub const DEFAULT_CLEANUP_INTERVAL_SECS: u64 = 300;
+
+/// Default age after which undelivered queued messages are dropped (7 days)
+pub const DEFAULT_MESSAGE_MAX_AGE_SECS: u64 = 7 * 24 * 3600;
+
+/// Default largest relayed payload, in bytes after base64 decoding (1 MiB)
+pub const DEFAULT_MAX_PAYLOAD_BYTES: usize = 1024 * 1024;
+
 /// Server configuration
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -11,14 +20,30 @@ pub struct Config {
     pub port: u16,
     /// Database URL
     pub database_url: String,
-    /// JWT secret for authentication
+    /// JWT secret for authentication; new tokens are signed with it
     pub jwt_secret: String,
+    /// Secret being rotated out: tokens signed with it are still accepted
+    ///
+    /// Keep it set for at least `jwt_expiration` after rotating, then remove it.
+    pub jwt_previous_secret: Option<String>,
     /// JWT token expiration in seconds
     pub jwt_expiration: u64,
     /// Rate limit for relay messages (per minute)
     pub rate_limit_messages: u32,
     /// Rate limit for registration (per hour)
     pub rate_limit_register: u32,
+    /// Origins allowed to make cross-origin (browser) requests
+    pub allowed_origins: Vec<String>,
+    /// Development mode: allow any origin when no allow-list is set
+    pub dev_mode: bool,
+    /// Seconds between cleanups of expired pairings and old messages (0 = never)
+    pub cleanup_interval_secs: u64,
+    /// Queued messages older than this many seconds are dropped by the cleanup
+    pub message_max_age_secs: u64,
+    /// Largest payload the relay forwards or queues, in bytes after base64 decoding
+    pub max_payload_bytes: usize,
+    /// Base64 Ed25519 seed for the relay's identity key (stored in the database if unset)
+    pub identity_key: Option<String>,
 }
 
 impl Config {
@@ -36,6 +61,9 @@ impl Config {
                 tracing::warn!("JWT_SECRET not set, using random secret");
                 generate_random_secret()
             }),
+            jwt_previous_secret: env::var("JWT_PREVIOUS_SECRET")
+                .ok()
+                .filter(|secret| !secret.is_empty()),
             jwt_expiration: env::var("JWT_EXPIRATION")
                 .ok()
                 .and_then(|e| e.parse().ok())
@@ -48,8 +76,43 @@ impl Config {
                 .ok()
                 .and_then(|r| r.parse().ok())
                 .unwrap_or(10),
+            allowed_origins: env::var("CORS_ALLOWED_ORIGINS")
+                .map(|origins| parse_origins(&origins))
+                .unwrap_or_default(),
+            dev_mode: env::var("DEV_MODE")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            cleanup_interval_secs: env::var("CLEANUP_INTERVAL_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_CLEANUP_INTERVAL_SECS),
+            message_max_age_secs: env::var("MESSAGE_MAX_AGE_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_MESSAGE_MAX_AGE_SECS),
+            max_payload_bytes: env::var("MAX_PAYLOAD_BYTES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_MAX_PAYLOAD_BYTES),
+            identity_key: env::var("RELAY_IDENTITY_KEY").ok(),
         })
     }
+
+    /// Secrets a token may be signed with, primary first
+    pub fn jwt_validation_secrets(&self) -> Vec<&str> {
+        std::iter::once(self.jwt_secret.as_str())
+            .chain(self.jwt_previous_secret.as_deref())
+            .collect()
+    }
+}
+
+/// Split a comma-separated origin list, dropping empty entries
+fn parse_origins(origins: &str) -> Vec<String> {
+    origins
+        .split(',')
+        .map(|origin| origin.trim().trim_end_matches('/').to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect()
 }
 
 fn generate_random_secret() -> String {
@@ -67,9 +130,30 @@ impl Default for Config {
             port: 8080,
             database_url: "sqlite:./data/toss.db?mode=rwc".to_string(),
             jwt_secret: generate_random_secret(),
+            jwt_previous_secret: None,
             jwt_expiration: 86400,
             rate_limit_messages: 100,
             rate_limit_register: 10,
+            allowed_origins: Vec::new(),
+            dev_mode: false,
+            cleanup_interval_secs: DEFAULT_CLEANUP_INTERVAL_SECS,
+            message_max_age_secs: DEFAULT_MESSAGE_MAX_AGE_SECS,
+            max_payload_bytes: DEFAULT_MAX_PAYLOAD_BYTES,
+            identity_key: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_origins() {
+        assert_eq!(
+            parse_origins(" https://a.example.com/, ,https://b.example.com "),
+            vec!["https://a.example.com", "https://b.example.com"]
+        );
+        assert!(parse_origins("").is_empty());
+    }
+}
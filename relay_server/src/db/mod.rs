@@ -1,28 +1,35 @@
 //! Database operations
 
-use chrono::Utc;
 use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+use std::sync::Arc;
 
+use crate::clock::{SharedClock, SystemClock};
 use crate::error::ApiError;
 
 mod models;
 
-pub use models::{Device, PairingSession, QueuedMessage};
+pub use models::{Device, PairingEvent, PairingSession, QueuedMessage};
 
 /// Database wrapper
 pub struct Database {
     pool: Pool<Sqlite>,
+    clock: SharedClock,
 }
 
 impl Database {
     /// Create a new database connection
     pub async fn new(url: &str) -> Result<Self, sqlx::Error> {
+        Self::new_with_clock(url, Arc::new(SystemClock)).await
+    }
+
+    /// Create a new database connection that reads the time from `clock`
+    pub async fn new_with_clock(url: &str, clock: SharedClock) -> Result<Self, sqlx::Error> {
         let pool = SqlitePoolOptions::new()
             .max_connections(5)
             .connect(url)
             .await?;
 
-        Ok(Self { pool })
+        Ok(Self { pool, clock })
     }
 
     /// Run migrations
@@ -43,21 +50,60 @@ impl Database {
         .execute(&self.pool)
         .await?;
 
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS message_queue (
-                id TEXT PRIMARY KEY,
-                from_device TEXT NOT NULL,
-                to_device TEXT NOT NULL,
-                encrypted_payload TEXT NOT NULL,
-                created_at INTEGER NOT NULL,
-                FOREIGN KEY (from_device) REFERENCES devices(id),
-                FOREIGN KEY (to_device) REFERENCES devices(id)
+        sqlx::query(&message_queue_schema("message_queue"))
+            .execute(&self.pool)
+            .await?;
+
+        // Sender signature columns, missing from queues created before them
+        for (column, column_type) in [("signed_at", "INTEGER"), ("signature", "TEXT")] {
+            let exists: bool = sqlx::query_scalar(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('message_queue') WHERE name = ?",
             )
-            "#,
+            .bind(column)
+            .fetch_one(&self.pool)
+            .await?;
+            if !exists {
+                sqlx::query(&format!(
+                    "ALTER TABLE message_queue ADD COLUMN {} {}",
+                    column, column_type
+                ))
+                .execute(&self.pool)
+                .await?;
+            }
+        }
+
+        // Message ids are chosen by the sender, so queues keyed on the id
+        // alone let one sender's message shadow another's; queues without a
+        // sequence number can't be acknowledged up to a poll cursor
+        let sequenced: bool = sqlx::query_scalar(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('message_queue') WHERE name = 'seq'",
         )
-        .execute(&self.pool)
+        .fetch_one(&self.pool)
         .await?;
+        if !sequenced {
+            let mut tx = self.pool.begin().await?;
+            sqlx::query(&message_queue_schema("message_queue_rekeyed"))
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(
+                r#"
+                INSERT INTO message_queue_rekeyed
+                    (id, from_device, to_device, encrypted_payload, created_at, signed_at, signature)
+                SELECT id, from_device, to_device, encrypted_payload, created_at, signed_at, signature
+                FROM message_queue
+                ORDER BY created_at ASC
+                "#,
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query("DROP TABLE message_queue")
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("ALTER TABLE message_queue_rekeyed RENAME TO message_queue")
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+        }
 
         sqlx::query(
             r#"
@@ -83,9 +129,56 @@ impl Database {
         .execute(&self.pool)
         .await?;
 
+        // Lookups and completions of pairing sessions, for notifying the advertiser
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS pairing_events (
+                code TEXT PRIMARY KEY,
+                found_at INTEGER NOT NULL,
+                completed_at INTEGER,
+                peer_public_key BLOB,
+                peer_device_name TEXT
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // The relay's own identity key, generated on first start
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS server_identity (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                seed BLOB NOT NULL,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
         Ok(())
     }
 
+    // Server identity operations
+
+    /// Get the stored identity key seed, storing `seed` first if there is none
+    pub async fn get_or_create_identity_seed(&self, seed: &[u8; 32]) -> Result<Vec<u8>, ApiError> {
+        sqlx::query(
+            "INSERT OR IGNORE INTO server_identity (id, seed, created_at) VALUES (1, ?, ?)",
+        )
+        .bind(seed.as_slice())
+        .bind(self.clock.now_secs())
+        .execute(&self.pool)
+        .await?;
+
+        let (seed,): (Vec<u8>,) = sqlx::query_as("SELECT seed FROM server_identity WHERE id = 1")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(seed)
+    }
+
     // Device operations
 
     /// Register or update a device
@@ -95,7 +188,7 @@ impl Database {
         public_key: &[u8],
         device_name: &str,
     ) -> Result<Device, ApiError> {
-        let now = Utc::now().timestamp();
+        let now = self.clock.now_secs();
 
         sqlx::query(
             r#"
@@ -137,7 +230,7 @@ impl Database {
 
     /// Update device online status
     pub async fn update_device_status(&self, id: &str, is_online: bool) -> Result<(), ApiError> {
-        let now = Utc::now().timestamp();
+        let now = self.clock.now_secs();
 
         sqlx::query(
             r#"
@@ -177,19 +270,26 @@ impl Database {
     // Message queue operations
 
     /// Queue a message for later delivery
+    ///
+    /// Queuing an id that is already queued from the same sender to the same
+    /// recipient is a no-op. The sender's
+    /// signature, if any, is stored as is and delivered with the message.
     pub async fn queue_message(
         &self,
         id: &str,
         from_device: &str,
         to_device: &str,
         encrypted_payload: &str,
+        signed_at: Option<u64>,
+        signature: Option<&str>,
     ) -> Result<(), ApiError> {
-        let now = Utc::now().timestamp();
+        let now = self.clock.now_secs();
 
         sqlx::query(
             r#"
-            INSERT INTO message_queue (id, from_device, to_device, encrypted_payload, created_at)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT OR IGNORE INTO message_queue
+                (id, from_device, to_device, encrypted_payload, created_at, signed_at, signature)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(id)
@@ -197,6 +297,8 @@ impl Database {
         .bind(to_device)
         .bind(encrypted_payload)
         .bind(now)
+        .bind(signed_at.map(|t| t as i64))
+        .bind(signature)
         .execute(&self.pool)
         .await?;
 
@@ -210,10 +312,10 @@ impl Database {
     ) -> Result<Vec<QueuedMessage>, ApiError> {
         let messages = sqlx::query_as::<_, QueuedMessage>(
             r#"
-            SELECT id, from_device, to_device, encrypted_payload, created_at
+            SELECT seq, id, from_device, to_device, encrypted_payload, created_at, signed_at, signature
             FROM message_queue
             WHERE to_device = ?
-            ORDER BY created_at ASC
+            ORDER BY seq ASC
             "#,
         )
         .bind(device_id)
@@ -223,6 +325,18 @@ impl Database {
         Ok(messages)
     }
 
+    /// Count a device's queued messages and get when the oldest was queued
+    pub async fn queue_status(&self, device_id: &str) -> Result<(u64, Option<i64>), ApiError> {
+        let (count, oldest): (i64, Option<i64>) = sqlx::query_as(
+            "SELECT COUNT(*), MIN(created_at) FROM message_queue WHERE to_device = ?",
+        )
+        .bind(device_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((count as u64, oldest))
+    }
+
     /// Delete queued messages for a device
     pub async fn delete_queued_messages(&self, device_id: &str) -> Result<u64, ApiError> {
         let result = sqlx::query("DELETE FROM message_queue WHERE to_device = ?")
@@ -233,9 +347,24 @@ impl Database {
         Ok(result.rows_affected())
     }
 
+    /// Delete a device's queued messages up to and including sequence number `seq`
+    pub async fn delete_queued_messages_through(
+        &self,
+        device_id: &str,
+        seq: i64,
+    ) -> Result<u64, ApiError> {
+        let result = sqlx::query("DELETE FROM message_queue WHERE to_device = ? AND seq <= ?")
+            .bind(device_id)
+            .bind(seq)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
     /// Delete old queued messages (cleanup)
     pub async fn cleanup_old_messages(&self, older_than_secs: i64) -> Result<u64, ApiError> {
-        let cutoff = Utc::now().timestamp() - older_than_secs;
+        let cutoff = self.clock.now_secs() - older_than_secs;
 
         let result = sqlx::query("DELETE FROM message_queue WHERE created_at < ?")
             .bind(cutoff)
@@ -255,7 +384,7 @@ impl Database {
         device_name: &str,
         expires_at: i64,
     ) -> Result<(), ApiError> {
-        let now = Utc::now().timestamp();
+        let now = self.clock.now_secs();
 
         sqlx::query(
             r#"
@@ -271,12 +400,18 @@ impl Database {
         .execute(&self.pool)
         .await?;
 
+        // A re-registered code starts without lookups or completions
+        sqlx::query("DELETE FROM pairing_events WHERE code = ?")
+            .bind(code)
+            .execute(&self.pool)
+            .await?;
+
         Ok(())
     }
 
     /// Find a pairing session by code
     pub async fn find_pairing(&self, code: &str) -> Result<Option<PairingSession>, ApiError> {
-        let now = Utc::now().timestamp();
+        let now = self.clock.now_secs();
 
         // Find non-expired session
         let session = sqlx::query_as::<_, PairingSession>(
@@ -294,6 +429,67 @@ impl Database {
         Ok(session)
     }
 
+    /// Record that a pairing session was looked up
+    pub async fn record_pairing_find(&self, code: &str) -> Result<(), ApiError> {
+        let now = self.clock.now_secs();
+
+        sqlx::query("INSERT OR IGNORE INTO pairing_events (code, found_at) VALUES (?, ?)")
+            .bind(code)
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Record that the device which looked up a pairing session completed pairing
+    ///
+    /// Returns false if the session expired, was never looked up, or was
+    /// already completed.
+    pub async fn complete_pairing(
+        &self,
+        code: &str,
+        peer_public_key: &[u8],
+        peer_device_name: &str,
+    ) -> Result<bool, ApiError> {
+        let now = self.clock.now_secs();
+
+        let result = sqlx::query(
+            r#"
+            UPDATE pairing_events
+            SET completed_at = ?, peer_public_key = ?, peer_device_name = ?
+            WHERE code = ? AND completed_at IS NULL
+            AND EXISTS (SELECT 1 FROM pairing_sessions WHERE code = ? AND expires_at > ?)
+            "#,
+        )
+        .bind(now)
+        .bind(peer_public_key)
+        .bind(peer_device_name)
+        .bind(code)
+        .bind(code)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the lookup/completion record of a pairing session
+    pub async fn get_pairing_event(&self, code: &str) -> Result<Option<PairingEvent>, ApiError> {
+        let event = sqlx::query_as::<_, PairingEvent>(
+            r#"
+            SELECT code, found_at, completed_at, peer_public_key, peer_device_name
+            FROM pairing_events
+            WHERE code = ?
+            "#,
+        )
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(event)
+    }
+
     /// Cancel/delete a pairing session
     pub async fn cancel_pairing(&self, code: &str) -> Result<bool, ApiError> {
         let result = sqlx::query("DELETE FROM pairing_sessions WHERE code = ?")
@@ -301,18 +497,178 @@ impl Database {
             .execute(&self.pool)
             .await?;
 
+        sqlx::query("DELETE FROM pairing_events WHERE code = ?")
+            .bind(code)
+            .execute(&self.pool)
+            .await?;
+
         Ok(result.rows_affected() > 0)
     }
 
     /// Cleanup expired pairing sessions
     pub async fn cleanup_expired_pairings(&self) -> Result<u64, ApiError> {
-        let now = Utc::now().timestamp();
+        let now = self.clock.now_secs();
 
         let result = sqlx::query("DELETE FROM pairing_sessions WHERE expires_at < ?")
             .bind(now)
             .execute(&self.pool)
             .await?;
 
+        sqlx::query(
+            "DELETE FROM pairing_events WHERE code NOT IN (SELECT code FROM pairing_sessions)",
+        )
+        .execute(&self.pool)
+        .await?;
+
         Ok(result.rows_affected())
     }
 }
+
+/// `CREATE TABLE` statement for the message queue, named `table`
+fn message_queue_schema(table: &str) -> String {
+    format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS {} (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            id TEXT NOT NULL,
+            from_device TEXT NOT NULL,
+            to_device TEXT NOT NULL,
+            encrypted_payload TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            signed_at INTEGER,
+            signature TEXT,
+            UNIQUE (from_device, to_device, id),
+            FOREIGN KEY (from_device) REFERENCES devices(id),
+            FOREIGN KEY (to_device) REFERENCES devices(id)
+        )
+        "#,
+        table
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use chrono::DateTime;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_find_pairing_expires_at_boundary() {
+        let clock = Arc::new(ManualClock::new(
+            DateTime::from_timestamp(1_000_000, 0).unwrap(),
+        ));
+        let db = Database::new_with_clock("sqlite::memory:", clock.clone())
+            .await
+            .unwrap();
+        db.migrate().await.unwrap();
+
+        db.register_pairing("123456", &[7u8; 32], "Device", 1_000_300)
+            .await
+            .unwrap();
+
+        clock.advance(Duration::from_secs(299));
+        let session = db.find_pairing("123456").await.unwrap().unwrap();
+        assert_eq!(session.device_name, "Device");
+
+        // Expired from the `expires_at` second onwards
+        clock.advance(Duration::from_secs(1));
+        assert!(db.find_pairing("123456").await.unwrap().is_none());
+
+        // Cleanup only removes sessions strictly past their expiry
+        assert_eq!(db.cleanup_expired_pairings().await.unwrap(), 0);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(db.cleanup_expired_pairings().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_queued_message_ids_are_scoped_to_sender_and_recipient() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        for (id, key) in [("a", 1u8), ("b", 2), ("c", 3), ("d", 4)] {
+            db.upsert_device(id, &[key; 32], id).await.unwrap();
+        }
+
+        db.queue_message("same-id", "a", "c", "from a", None, None)
+            .await
+            .unwrap();
+        // A retry from the same sender is ignored
+        db.queue_message("same-id", "a", "c", "retry", None, None)
+            .await
+            .unwrap();
+        // Another sender, or another recipient, picking the same id is not
+        db.queue_message("same-id", "b", "c", "from b", None, None)
+            .await
+            .unwrap();
+        db.queue_message("same-id", "a", "d", "to d", None, None)
+            .await
+            .unwrap();
+
+        let queued = db.get_queued_messages("c").await.unwrap();
+        let mut payloads: Vec<_> = queued
+            .iter()
+            .map(|m| m.encrypted_payload.as_str())
+            .collect();
+        payloads.sort();
+        assert_eq!(payloads, ["from a", "from b"]);
+        assert_eq!(db.get_queued_messages("d").await.unwrap().len(), 1);
+
+        // Acknowledging the first of c's messages leaves the rest queued
+        let first = &queued[0];
+        assert_eq!(first.from_device, "a");
+        db.delete_queued_messages_through("c", first.seq)
+            .await
+            .unwrap();
+        let queued = db.get_queued_messages("c").await.unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].from_device, "b");
+        assert_eq!(db.get_queued_messages("d").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_rekeys_queue_keyed_on_id() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        for (id, key) in [("a", 1u8), ("b", 2), ("c", 3)] {
+            db.upsert_device(id, &[key; 32], id).await.unwrap();
+        }
+
+        // A queue from before messages were keyed by sender
+        sqlx::query("DROP TABLE message_queue")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+            CREATE TABLE message_queue (
+                id TEXT PRIMARY KEY,
+                from_device TEXT NOT NULL,
+                to_device TEXT NOT NULL,
+                encrypted_payload TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO message_queue VALUES ('same-id', 'a', 'c', 'from a', 1)")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        db.migrate().await.unwrap();
+        // Running again leaves the new key alone
+        db.migrate().await.unwrap();
+
+        db.queue_message("same-id", "b", "c", "from b", Some(5), Some("sig"))
+            .await
+            .unwrap();
+        let queued = db.get_queued_messages("c").await.unwrap();
+        assert_eq!(queued.len(), 2);
+        // Migrated messages keep their place ahead of newer ones
+        assert_eq!(queued[0].from_device, "a");
+        assert_eq!(queued[0].encrypted_payload, "from a");
+        assert!(queued[0].seq < queued[1].seq);
+    }
+}
@@ -18,11 +18,15 @@ pub struct Device {
 /// Queued message record
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct QueuedMessage {
+    /// Position in the queue; increases with every queued message
+    pub seq: i64,
     pub id: String,
     pub from_device: String,
     pub to_device: String,
     pub encrypted_payload: String,
     pub created_at: i64,
+    pub signed_at: Option<i64>,
+    pub signature: Option<String>,
 }
 
 /// Pairing session record
@@ -34,3 +38,13 @@ pub struct PairingSession {
     pub expires_at: i64,
     pub created_at: i64,
 }
+
+/// Lookup and completion record of a pairing session
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PairingEvent {
+    pub code: String,
+    pub found_at: i64,
+    pub completed_at: Option<i64>,
+    pub peer_public_key: Option<Vec<u8>>,
+    pub peer_device_name: Option<String>,
+}
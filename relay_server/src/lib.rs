@@ -5,18 +5,25 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
-use axum::Router;
+use axum::{
+    http::{header, HeaderValue, Method},
+    Router,
+};
 use tokio::net::TcpListener;
-use tower_http::cors::CorsLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
 
 pub mod api;
 pub mod auth;
+pub mod cleanup;
+pub mod clock;
 pub mod config;
 pub mod db;
 pub mod error;
 pub mod relay;
 
+pub use auth::ServerIdentity;
+pub use clock::{Clock, SharedClock, SystemClock};
 pub use config::Config;
 pub use db::Database;
 pub use relay::RelayState;
@@ -27,17 +34,52 @@ pub struct AppState {
     pub config: Arc<Config>,
     pub db: Arc<Database>,
     pub relay: Arc<RelayState>,
+    pub clock: SharedClock,
+    pub identity: Arc<ServerIdentity>,
 }
 
 /// Create the application router with the given state
 pub fn create_app(state: AppState) -> Router {
+    let cors = cors_layer(&state.config);
+
     Router::new()
         .merge(api::routes::create_router())
-        .layer(CorsLayer::permissive())
+        .layer(cors)
         .layer(TraceLayer::new_for_http())
         .with_state(state)
 }
 
+/// Build the CORS policy from the configured origin allow-list
+///
+/// With no allow-list, cross-origin requests are refused unless `dev_mode`
+/// is set, in which case any origin is allowed.
+pub fn cors_layer(config: &Config) -> CorsLayer {
+    if config.allowed_origins.is_empty() {
+        return if config.dev_mode {
+            CorsLayer::permissive()
+        } else {
+            CorsLayer::new()
+        };
+    }
+
+    let origins: Vec<HeaderValue> = config
+        .allowed_origins
+        .iter()
+        .filter_map(|origin| match HeaderValue::from_str(origin) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                tracing::warn!("Ignoring invalid CORS origin: {}", origin);
+                None
+            }
+        })
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+}
+
 /// Test server handle for integration tests
 pub struct TestServer {
     pub addr: SocketAddr,
@@ -60,14 +102,18 @@ impl TestServer {
         config.database_url = "sqlite::memory:".to_string();
 
         // Initialize database
-        let database = Database::new(&config.database_url).await?;
+        let clock: SharedClock = Arc::new(SystemClock);
+        let database = Database::new_with_clock(&config.database_url, clock.clone()).await?;
         database.migrate().await?;
+        let identity = ServerIdentity::load(&config, &database).await?;
 
         // Create application state
         let state = AppState {
             config: Arc::new(config),
             db: Arc::new(database),
             relay: Arc::new(RelayState::new()),
+            clock,
+            identity: Arc::new(identity),
         };
 
         // Create the app
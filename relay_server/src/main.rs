@@ -5,11 +5,15 @@
 
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use tokio::net::TcpListener;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use toss_relay::{create_app, AppState, Config, Database, RelayState};
+use toss_relay::{
+    cleanup, create_app, AppState, Config, Database, RelayState, ServerIdentity, SharedClock,
+    SystemClock,
+};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -28,16 +32,45 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::info!("Starting Toss Relay Server");
     tracing::info!("Listening on {}:{}", config.host, config.port);
+    if !config.allowed_origins.is_empty() {
+        tracing::info!(
+            "CORS allowed origins: {}",
+            config.allowed_origins.join(", ")
+        );
+    } else if config.dev_mode {
+        tracing::warn!("DEV_MODE set: allowing cross-origin requests from any origin");
+    }
 
     // Initialize database
-    let database = Database::new(&config.database_url).await?;
+    let clock: SharedClock = Arc::new(SystemClock);
+    let database = Database::new_with_clock(&config.database_url, clock.clone()).await?;
     database.migrate().await?;
 
+    let identity = ServerIdentity::load(&config, &database).await?;
+    tracing::info!(
+        "Relay identity key: {}",
+        base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            identity.public_key()
+        )
+    );
+
+    let database = Arc::new(database);
+    if config.cleanup_interval_secs > 0 {
+        cleanup::spawn_cleanup_task(
+            database.clone(),
+            Duration::from_secs(config.cleanup_interval_secs),
+            Duration::from_secs(config.message_max_age_secs),
+        );
+    }
+
     // Create application state
     let state = AppState {
         config: Arc::new(config.clone()),
-        db: Arc::new(database),
+        db: database,
         relay: Arc::new(RelayState::new()),
+        clock,
+        identity: Arc::new(identity),
     };
 
     // Build router
@@ -0,0 +1,145 @@
+//! Binary relay frames
+//!
+//! A WebSocket client that negotiates [`BINARY_SUBPROTOCOL`] exchanges relay
+//! messages as binary frames instead of JSON, so the payload travels as raw
+//! bytes rather than base64. Authentication and errors stay JSON text.
+//! Integers are big-endian:
+//!
+//! ```text
+//! [from_len: u8][from_device][to_len: u8][to_device][id_len: u8][id]
+//! [timestamp: u64][signed_at: u64, 0 if unsigned][sig_len: u8][signature]
+//! [payload: rest of the frame]
+//! ```
+//!
+//! Clients send frames with an empty `from_device` and a zero `timestamp`;
+//! the relay fills in both.
+
+use base64::Engine;
+
+use super::RelayMessage;
+
+/// WebSocket subprotocol selecting binary relay frames
+pub const BINARY_SUBPROTOCOL: &str = "toss-relay-binary.v1";
+
+/// Encode a relay message as a binary frame
+///
+/// Fails if a field is too long for its length prefix, or if the payload or
+/// signature isn't valid base64; such messages can still be sent as JSON.
+pub fn encode(message: &RelayMessage) -> Result<Vec<u8>, String> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let payload = engine
+        .decode(&message.encrypted_payload)
+        .map_err(|_| "Invalid payload encoding".to_string())?;
+    let signature = match &message.signature {
+        Some(signature) => engine
+            .decode(signature)
+            .map_err(|_| "Invalid signature encoding".to_string())?,
+        None => Vec::new(),
+    };
+
+    let mut frame = Vec::with_capacity(
+        message.from_device.len()
+            + message.to_device.len()
+            + message.id.len()
+            + signature.len()
+            + payload.len()
+            + 20,
+    );
+    put_field(&mut frame, message.from_device.as_bytes())?;
+    put_field(&mut frame, message.to_device.as_bytes())?;
+    put_field(&mut frame, message.id.as_bytes())?;
+    frame.extend_from_slice(&message.timestamp.to_be_bytes());
+    frame.extend_from_slice(&message.signed_at.unwrap_or(0).to_be_bytes());
+    put_field(&mut frame, &signature)?;
+    frame.extend_from_slice(&payload);
+    Ok(frame)
+}
+
+/// Decode a binary frame
+///
+/// An empty id, zero `signed_at` or empty signature decode as absent.
+pub fn decode(frame: &[u8]) -> Result<RelayMessage, String> {
+    let mut reader = Reader(frame);
+    let from_device = reader.string()?;
+    let to_device = reader.string()?;
+    let id = reader.string()?;
+    let timestamp = reader.u64()?;
+    let signed_at = reader.u64()?;
+    let signature = reader.field()?;
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    Ok(RelayMessage {
+        id,
+        from_device,
+        to_device,
+        encrypted_payload: engine.encode(reader.0),
+        timestamp,
+        signed_at: (signed_at != 0).then_some(signed_at),
+        signature: (!signature.is_empty()).then(|| engine.encode(signature)),
+    })
+}
+
+fn put_field(frame: &mut Vec<u8>, field: &[u8]) -> Result<(), String> {
+    let len = u8::try_from(field.len()).map_err(|_| "Frame field too long".to_string())?;
+    frame.push(len);
+    frame.extend_from_slice(field);
+    Ok(())
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < len {
+            return Err("Truncated frame".to_string());
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn field(&mut self) -> Result<&'a [u8], String> {
+        let len = self.take(1)?[0] as usize;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        String::from_utf8(self.field()?.to_vec()).map_err(|_| "Invalid frame field".to_string())
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_be_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let message = RelayMessage {
+            id: "msg-1".to_string(),
+            from_device: "a".repeat(32),
+            to_device: "b".repeat(32),
+            encrypted_payload: base64::engine::general_purpose::STANDARD.encode([0, 1, 2, 255]),
+            timestamp: 1_700_000_000_000,
+            signed_at: Some(1_700_000_000_001),
+            signature: Some(base64::engine::general_purpose::STANDARD.encode([9u8; 64])),
+        };
+
+        let decoded = decode(&encode(&message).unwrap()).unwrap();
+        assert_eq!(decoded.id, message.id);
+        assert_eq!(decoded.from_device, message.from_device);
+        assert_eq!(decoded.to_device, message.to_device);
+        assert_eq!(decoded.encrypted_payload, message.encrypted_payload);
+        assert_eq!(decoded.timestamp, message.timestamp);
+        assert_eq!(decoded.signed_at, message.signed_at);
+        assert_eq!(decoded.signature, message.signature);
+
+        // A frame cut off inside its header is refused
+        let frame = encode(&message).unwrap();
+        assert!(decode(&frame[..40]).is_err());
+    }
+}
@@ -1,8 +1,44 @@
 //! Real-time relay functionality
 
+pub mod frame;
+
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::futures::Notified;
+use tokio::sync::{broadcast, mpsc, Notify};
+
+use crate::db::QueuedMessage;
+
+/// How long a message id is remembered for deduplication
+const DEDUP_WINDOW: Duration = Duration::from_secs(300);
+
+/// Number of remembered ids above which expired entries are pruned
+const DEDUP_PRUNE_THRESHOLD: usize = 10_000;
+
+/// Maximum accepted length of a client-supplied message id
+pub const MAX_MESSAGE_ID_LEN: usize = 128;
+
+/// Maximum accepted length of a sender signature (base64)
+pub const MAX_SIGNATURE_LEN: usize = 128;
+
+/// Size of a base64 payload once decoded, without decoding it
+///
+/// Malformed base64 is measured as if it were valid; it's forwarded as is.
+pub fn payload_size(encrypted_payload: &str) -> usize {
+    let padding = encrypted_payload
+        .bytes()
+        .rev()
+        .take(2)
+        .take_while(|&b| b == b'=')
+        .count();
+    (encrypted_payload.len() / 4 * 3).saturating_sub(padding)
+}
+
+/// Buffered pairing completion notifications
+const PAIRING_NOTIFY_CAPACITY: usize = 64;
 
 /// Message to be relayed
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,12 +48,74 @@ pub struct RelayMessage {
     pub to_device: String,
     pub encrypted_payload: String,
     pub timestamp: u64,
+    /// Sender's send time (Unix milliseconds), covered by `signature`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_at: Option<u64>,
+    /// Sender's Ed25519 signature (base64), checked by the recipient
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl From<QueuedMessage> for RelayMessage {
+    fn from(msg: QueuedMessage) -> Self {
+        Self {
+            id: msg.id,
+            from_device: msg.from_device,
+            to_device: msg.to_device,
+            encrypted_payload: msg.encrypted_payload,
+            timestamp: msg.created_at as u64,
+            signed_at: msg.signed_at.map(|t| t as u64),
+            signature: msg.signature,
+        }
+    }
+}
+
+/// Long-polls open for one device
+#[derive(Default)]
+struct Pollers {
+    /// Woken when a message is queued for the device
+    queued: Notify,
+    /// Number of open polls
+    open: AtomicUsize,
+}
+
+/// An open long-poll; the device counts as connected until it's dropped
+pub struct PollGuard<'a> {
+    state: &'a RelayState,
+    device_id: String,
+    pollers: Arc<Pollers>,
+}
+
+impl PollGuard<'_> {
+    /// Wait for the next message queued for the device
+    ///
+    /// Enable the future before reading the queue, so a message queued in
+    /// between still wakes it.
+    pub fn queued(&self) -> Notified<'_> {
+        self.pollers.queued.notified()
+    }
+}
+
+impl Drop for PollGuard<'_> {
+    fn drop(&mut self) {
+        if self.pollers.open.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.state.pollers.remove_if(&self.device_id, |_, pollers| {
+                pollers.open.load(Ordering::Acquire) == 0
+            });
+        }
+    }
 }
 
 /// Relay state managing active connections
 pub struct RelayState {
     /// Active WebSocket connections: device_id -> message sender
     connections: DashMap<String, mpsc::Sender<RelayMessage>>,
+    /// Devices with open long-polls, which take messages from the queue
+    pollers: DashMap<String, Arc<Pollers>>,
+    /// Recently seen message ids: (from_device, to_device, id) -> first seen
+    recent_ids: DashMap<(String, String, String), Instant>,
+    /// Codes of pairing sessions that were just completed
+    pairing_completions: broadcast::Sender<String>,
 }
 
 impl RelayState {
@@ -25,6 +123,9 @@ impl RelayState {
     pub fn new() -> Self {
         Self {
             connections: DashMap::new(),
+            pollers: DashMap::new(),
+            recent_ids: DashMap::new(),
+            pairing_completions: broadcast::channel(PAIRING_NOTIFY_CAPACITY).0,
         }
     }
 
@@ -38,13 +139,92 @@ impl RelayState {
         self.connections.remove(device_id);
     }
 
-    /// Check if a device is connected
+    /// Unregister a connection only if it still uses `sender`
+    ///
+    /// Leaves a newer connection for the same device in place.
+    pub fn unregister_sender(&self, device_id: &str, sender: &mpsc::Sender<RelayMessage>) {
+        self.connections
+            .remove_if(device_id, |_, current| current.same_channel(sender));
+    }
+
+    /// Check if a device is connected, over a WebSocket or an open long-poll
     pub fn is_connected(&self, device_id: &str) -> bool {
         self.connections.contains_key(device_id)
+            || self
+                .pollers
+                .get(device_id)
+                .is_some_and(|pollers| pollers.open.load(Ordering::Acquire) > 0)
+    }
+
+    /// Open a long-poll for a device
+    ///
+    /// Polls don't take messages directly; they wait for the queue and are
+    /// woken by [`notify_queued`](Self::notify_queued).
+    pub fn open_poll(&self, device_id: &str) -> PollGuard<'_> {
+        let pollers = self.pollers.entry(device_id.to_string()).or_default();
+        pollers.open.fetch_add(1, Ordering::AcqRel);
+        PollGuard {
+            state: self,
+            device_id: device_id.to_string(),
+            pollers: pollers.clone(),
+        }
+    }
+
+    /// Wake the long-polls of a device that just had a message queued
+    pub fn notify_queued(&self, device_id: &str) {
+        if let Some(pollers) = self.pollers.get(device_id) {
+            pollers.queued.notify_waiters();
+        }
+    }
+
+    /// Record a message id, returning true if it was already seen within the dedup window
+    ///
+    /// Ids are chosen by senders, so they are only compared between messages
+    /// with the same sender and recipient.
+    pub fn is_duplicate(&self, from_device: &str, to_device: &str, message_id: &str) -> bool {
+        let now = Instant::now();
+
+        if self.recent_ids.len() > DEDUP_PRUNE_THRESHOLD {
+            self.recent_ids
+                .retain(|_, seen| now.duration_since(*seen) < DEDUP_WINDOW);
+        }
+
+        let key = (
+            from_device.to_string(),
+            to_device.to_string(),
+            message_id.to_string(),
+        );
+        match self.recent_ids.entry(key) {
+            dashmap::mapref::entry::Entry::Occupied(mut entry) => {
+                if now.duration_since(*entry.get()) < DEDUP_WINDOW {
+                    true
+                } else {
+                    entry.insert(now);
+                    false
+                }
+            }
+            dashmap::mapref::entry::Entry::Vacant(entry) => {
+                entry.insert(now);
+                false
+            }
+        }
     }
 
     /// Send a message to a connected device
+    ///
+    /// A message whose id was already seen from the same sender to the same
+    /// device within the dedup window is dropped and reported as handled, so retried sends are
+    /// delivered at most once.
     pub async fn send_to(&self, device_id: &str, message: RelayMessage) -> bool {
+        if self.is_duplicate(&message.from_device, device_id, &message.id) {
+            tracing::debug!(
+                "Dropping duplicate message {} from {}",
+                message.id,
+                message.from_device
+            );
+            return true;
+        }
+
         if let Some(sender) = self.connections.get(device_id) {
             sender.send(message).await.is_ok()
         } else {
@@ -52,6 +232,16 @@ impl RelayState {
         }
     }
 
+    /// Wake advertisers waiting on the pairing session `code`
+    pub fn notify_pairing_completed(&self, code: &str) {
+        let _ = self.pairing_completions.send(code.to_string());
+    }
+
+    /// Subscribe to pairing completion notifications
+    pub fn subscribe_pairing_completions(&self) -> broadcast::Receiver<String> {
+        self.pairing_completions.subscribe()
+    }
+
     /// Get count of connected devices
     pub fn connection_count(&self) -> usize {
         self.connections.len()
@@ -84,6 +274,8 @@ mod tests {
             to_device: "device1".to_string(),
             encrypted_payload: "test".to_string(),
             timestamp: 0,
+            signed_at: None,
+            signature: None,
         };
 
         assert!(state.send_to("device1", msg.clone()).await);
@@ -94,4 +286,88 @@ mod tests {
         state.unregister("device1");
         assert!(!state.is_connected("device1"));
     }
+
+    #[tokio::test]
+    async fn test_unregister_sender_keeps_newer_connection() {
+        let state = RelayState::new();
+        let (old_tx, _old_rx) = mpsc::channel(10);
+        let (new_tx, _new_rx) = mpsc::channel(10);
+
+        state.register("device1".to_string(), old_tx.clone());
+        state.register("device1".to_string(), new_tx.clone());
+
+        state.unregister_sender("device1", &old_tx);
+        assert!(state.is_connected("device1"));
+
+        state.unregister_sender("device1", &new_tx);
+        assert!(!state.is_connected("device1"));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_message_delivered_once() {
+        let state = RelayState::new();
+        let (tx, mut rx) = mpsc::channel(10);
+        state.register("device1".to_string(), tx);
+
+        let msg = RelayMessage {
+            id: "retry-id".to_string(),
+            from_device: "device2".to_string(),
+            to_device: "device1".to_string(),
+            encrypted_payload: "test".to_string(),
+            timestamp: 0,
+            signed_at: None,
+            signature: None,
+        };
+
+        assert!(state.send_to("device1", msg.clone()).await);
+        assert!(state.send_to("device1", msg.clone()).await);
+
+        assert_eq!(rx.recv().await.unwrap().id, "retry-id");
+        assert!(rx.try_recv().is_err());
+
+        // Same id from a different sender is not a duplicate
+        let other = RelayMessage {
+            from_device: "device3".to_string(),
+            ..msg.clone()
+        };
+        assert!(state.send_to("device1", other).await);
+        assert_eq!(rx.recv().await.unwrap().from_device, "device3");
+
+        // Nor is the same id from the same sender to a different device
+        let (other_tx, mut other_rx) = mpsc::channel(10);
+        state.register("device4".to_string(), other_tx);
+        let other = RelayMessage {
+            to_device: "device4".to_string(),
+            ..msg
+        };
+        assert!(state.send_to("device4", other).await);
+        assert_eq!(other_rx.recv().await.unwrap().id, "retry-id");
+    }
+
+    #[tokio::test]
+    async fn test_poll_leaves_websocket_registration() {
+        let state = RelayState::new();
+        let (tx, _rx) = mpsc::channel(10);
+        state.register("device1".to_string(), tx);
+
+        let poll = state.open_poll("device1");
+        drop(poll);
+        assert!(state.is_connected("device1"));
+        assert_eq!(state.connection_count(), 1);
+
+        let poll = state.open_poll("device2");
+        assert!(state.is_connected("device2"));
+        {
+            let queued = poll.queued();
+            tokio::pin!(queued);
+            queued.as_mut().enable();
+            state.notify_queued("device2");
+            tokio::time::timeout(Duration::from_secs(1), queued)
+                .await
+                .expect("poll not woken");
+        }
+
+        drop(poll);
+        assert!(!state.is_connected("device2"));
+    }
 }
@@ -4,9 +4,20 @@
 
 use base64::Engine;
 use ed25519_dalek::{Signer, SigningKey};
+use futures::{SinkExt, StreamExt};
 use rand::rngs::OsRng;
 use serde_json::{json, Value};
 use std::time::{SystemTime, UNIX_EPOCH};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+
+type WsStream =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Base64 X25519 public key for the secret `[seed; 32]`
+fn x25519_public_key(seed: u8) -> String {
+    let point = curve25519_dalek::MontgomeryPoint::mul_base_clamped([seed; 32]);
+    base64::engine::general_purpose::STANDARD.encode(point.to_bytes())
+}
 
 /// Helper to generate test signing key pair
 fn generate_keypair() -> (SigningKey, String, String) {
@@ -46,6 +57,117 @@ fn create_register_request(
     })
 }
 
+/// Helper to register a new device, returning its id and bearer token
+async fn register_device(
+    client: &reqwest::Client,
+    server: &toss_relay::TestServer,
+    device_name: &str,
+) -> (String, String) {
+    let (signing_key, device_id, public_key) = generate_keypair();
+    let request = create_register_request(&signing_key, &device_id, &public_key, device_name);
+
+    let body: Value = client
+        .post(server.url("/api/v1/register"))
+        .json(&request)
+        .send()
+        .await
+        .expect("Failed to send registration request")
+        .json()
+        .await
+        .expect("Invalid registration response");
+
+    let token = body["token"]
+        .as_str()
+        .expect("Registration response should contain a token")
+        .to_string();
+
+    (device_id, token)
+}
+
+/// Helper to register a device and open an authenticated WebSocket
+///
+/// Offers `subprotocol` if given, and returns the one the server selected.
+async fn connect_ws(
+    client: &reqwest::Client,
+    server: &toss_relay::TestServer,
+    device_name: &str,
+    subprotocol: Option<&str>,
+) -> (String, WsStream, Option<String>) {
+    connect_ws_at(client, server, "/api/v1/ws", device_name, subprotocol).await
+}
+
+/// Like [`connect_ws`], on the WebSocket route at `path`
+async fn connect_ws_at(
+    client: &reqwest::Client,
+    server: &toss_relay::TestServer,
+    path: &str,
+    device_name: &str,
+    subprotocol: Option<&str>,
+) -> (String, WsStream, Option<String>) {
+    let (signing_key, device_id, public_key) = generate_keypair();
+    let request = create_register_request(&signing_key, &device_id, &public_key, device_name);
+    let response = client
+        .post(server.url("/api/v1/register"))
+        .json(&request)
+        .send()
+        .await
+        .expect("Failed to send registration request");
+    assert!(response.status().is_success());
+
+    let (ws, selected) = open_ws(server, path, &signing_key, &device_id, subprotocol).await;
+    (device_id, ws, selected)
+}
+
+/// Open a WebSocket on `path` and authenticate as an already registered device
+async fn open_ws(
+    server: &toss_relay::TestServer,
+    path: &str,
+    signing_key: &SigningKey,
+    device_id: &str,
+    subprotocol: Option<&str>,
+) -> (WsStream, Option<String>) {
+    let mut request = server
+        .url(path)
+        .replace("http", "ws")
+        .into_client_request()
+        .unwrap();
+    if let Some(subprotocol) = subprotocol {
+        request
+            .headers_mut()
+            .insert("Sec-WebSocket-Protocol", subprotocol.parse().unwrap());
+    }
+    let (mut ws, response) = tokio_tungstenite::connect_async(request)
+        .await
+        .expect("Failed to open WebSocket");
+    let selected = response
+        .headers()
+        .get("Sec-WebSocket-Protocol")
+        .map(|value| value.to_str().unwrap().to_string());
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let signature = signing_key.sign(format!("auth:{}:{}", device_id, timestamp).as_bytes());
+    let auth = json!({
+        "type": "auth",
+        "device_id": device_id,
+        "timestamp": timestamp,
+        "signature": base64::engine::general_purpose::STANDARD.encode(signature.to_bytes()),
+    });
+    ws.send(Message::Text(auth.to_string().into()))
+        .await
+        .unwrap();
+
+    let Some(Ok(Message::Text(response))) = ws.next().await else {
+        panic!("Expected an auth response");
+    };
+    let response: Value = serde_json::from_str(&response).unwrap();
+    assert_eq!(response["success"], true);
+
+    (ws, selected)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -263,4 +385,915 @@ mod api_integration_tests {
         // Cleanup
         server.shutdown().await;
     }
+
+    #[tokio::test]
+    async fn test_long_poll_send_and_receive() {
+        let server = TestServer::start()
+            .await
+            .expect("Failed to start test server");
+        let client = reqwest::Client::new();
+
+        let (sender_id, sender_token) = register_device(&client, &server, "Sender").await;
+        let (receiver_id, receiver_token) = register_device(&client, &server, "Receiver").await;
+
+        // Queued before the receiver polls: returned immediately
+        let response = client
+            .post(server.url("/api/v1/messages/send"))
+            .bearer_auth(&sender_token)
+            .json(&json!({
+                "id": "queued-1",
+                "to_device": receiver_id,
+                "encrypted_payload": "cXVldWVk",
+            }))
+            .send()
+            .await
+            .expect("Failed to send message");
+        assert_eq!(response.status(), reqwest::StatusCode::ACCEPTED);
+
+        let poll_once = |query: String| {
+            let request = client
+                .get(server.url(&format!("/api/v1/messages/poll?{}", query)))
+                .bearer_auth(&receiver_token);
+            async move {
+                request
+                    .send()
+                    .await
+                    .expect("Failed to poll")
+                    .json::<Value>()
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let body = poll_once("timeout_secs=5".to_string()).await;
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["id"], "queued-1");
+        assert_eq!(messages[0]["from_device"], sender_id.as_str());
+        assert_eq!(messages[0]["encrypted_payload"], "cXVldWVk");
+        let cursor = body["cursor"]
+            .as_i64()
+            .expect("Response should have a cursor");
+
+        // Until a poll acknowledges it, a response lost on the way is sent again
+        let body = poll_once("timeout_secs=5".to_string()).await;
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["id"], "queued-1");
+        assert_eq!(body["cursor"], cursor);
+
+        // Sent while the receiver is waiting: delivered to the open poll
+        let poll = tokio::spawn(poll_once(format!("timeout_secs=10&ack={}", cursor)));
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let response = client
+            .post(server.url("/api/v1/messages/send"))
+            .bearer_auth(&sender_token)
+            .json(&json!({
+                "id": "live-1",
+                "to_device": receiver_id,
+                "encrypted_payload": "bGl2ZQ==",
+            }))
+            .send()
+            .await
+            .expect("Failed to send message");
+        assert_eq!(response.status(), reqwest::StatusCode::ACCEPTED);
+
+        let body = tokio::time::timeout(std::time::Duration::from_secs(5), poll)
+            .await
+            .expect("Poll should return once a message arrives")
+            .unwrap();
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["id"], "live-1");
+        let cursor = body["cursor"].as_i64().unwrap();
+
+        // Everything acknowledged: an empty poll times out
+        let body = poll_once(format!("timeout_secs=1&ack={}", cursor)).await;
+        assert!(body["messages"].as_array().unwrap().is_empty());
+        assert!(body.get("cursor").is_none());
+
+        // Polling requires authentication
+        let response = client
+            .get(server.url("/api/v1/messages/poll"))
+            .send()
+            .await
+            .expect("Failed to poll");
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_long_poll_keeps_websocket_registration() {
+        let server = TestServer::start()
+            .await
+            .expect("Failed to start test server");
+        let client = reqwest::Client::new();
+
+        let (_, sender_token) = register_device(&client, &server, "Sender").await;
+        let (signing_key, receiver_id, public_key) = generate_keypair();
+        let request = create_register_request(&signing_key, &receiver_id, &public_key, "Receiver");
+        let body: Value = client
+            .post(server.url("/api/v1/register"))
+            .json(&request)
+            .send()
+            .await
+            .expect("Failed to send registration request")
+            .json()
+            .await
+            .unwrap();
+        let receiver_token = body["token"].as_str().unwrap().to_string();
+        let (mut ws, _) = open_ws(&server, "/api/v1/ws", &signing_key, &receiver_id, None).await;
+
+        // A poll by the same device neither takes over nor ends the WebSocket
+        let body: Value = client
+            .get(server.url("/api/v1/messages/poll?timeout_secs=1"))
+            .bearer_auth(&receiver_token)
+            .send()
+            .await
+            .expect("Failed to poll")
+            .json()
+            .await
+            .unwrap();
+        assert!(body["messages"].as_array().unwrap().is_empty());
+
+        let response = client
+            .post(server.url("/api/v1/messages/send"))
+            .bearer_auth(&sender_token)
+            .json(&json!({
+                "id": "after-poll",
+                "to_device": receiver_id,
+                "encrypted_payload": "d3M=",
+            }))
+            .send()
+            .await
+            .expect("Failed to send message");
+        assert_eq!(response.status(), reqwest::StatusCode::ACCEPTED);
+
+        let Ok(Some(Ok(Message::Text(text)))) =
+            tokio::time::timeout(std::time::Duration::from_secs(5), ws.next()).await
+        else {
+            panic!("Expected the message on the WebSocket");
+        };
+        let envelope: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(envelope["message"]["id"], "after-poll");
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_oversized_payload_rejected() {
+        let config = toss_relay::Config {
+            max_payload_bytes: 1024,
+            ..Default::default()
+        };
+        let server = TestServer::start_with_config(config)
+            .await
+            .expect("Failed to start test server");
+        let client = reqwest::Client::new();
+
+        let (_, sender_token) = register_device(&client, &server, "Sender").await;
+        let (receiver_id, _) = register_device(&client, &server, "Receiver").await;
+
+        let send = |payload: Vec<u8>| {
+            client
+                .post(server.url("/api/v1/messages/send"))
+                .bearer_auth(&sender_token)
+                .json(&json!({
+                    "to_device": receiver_id,
+                    "encrypted_payload": base64::engine::general_purpose::STANDARD.encode(payload),
+                }))
+                .send()
+        };
+
+        let response = send(vec![7u8; 1024]).await.expect("Failed to send message");
+        assert_eq!(response.status(), reqwest::StatusCode::ACCEPTED);
+
+        let response = send(vec![7u8; 1025]).await.expect("Failed to send message");
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_queued_message_keeps_sender_signature() {
+        let server = TestServer::start()
+            .await
+            .expect("Failed to start test server");
+        let client = reqwest::Client::new();
+
+        let (_, sender_token) = register_device(&client, &server, "Sender").await;
+        let (receiver_id, receiver_token) = register_device(&client, &server, "Receiver").await;
+
+        let signature = "c2lnbmF0dXJl";
+        let response = client
+            .post(server.url("/api/v1/messages/send"))
+            .bearer_auth(&sender_token)
+            .json(&json!({
+                "id": "signed-1",
+                "to_device": receiver_id,
+                "encrypted_payload": "c2lnbmVk",
+                "signed_at": 1_700_000_000_000u64,
+                "signature": signature,
+            }))
+            .send()
+            .await
+            .expect("Failed to send message");
+        assert_eq!(response.status(), reqwest::StatusCode::ACCEPTED);
+
+        let body: Value = client
+            .get(server.url("/api/v1/messages/poll?timeout_secs=5"))
+            .bearer_auth(&receiver_token)
+            .send()
+            .await
+            .expect("Failed to poll")
+            .json()
+            .await
+            .unwrap();
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["signed_at"], 1_700_000_000_000u64);
+        assert_eq!(messages[0]["signature"], signature);
+
+        // Oversized signatures are refused rather than stored
+        let response = client
+            .post(server.url("/api/v1/messages/send"))
+            .bearer_auth(&sender_token)
+            .json(&json!({
+                "to_device": receiver_id,
+                "encrypted_payload": "c2lnbmVk",
+                "signed_at": 1_700_000_000_000u64,
+                "signature": "A".repeat(1024),
+            }))
+            .send()
+            .await
+            .expect("Failed to send message");
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_queue_status_and_flush() {
+        let server = TestServer::start()
+            .await
+            .expect("Failed to start test server");
+        let client = reqwest::Client::new();
+
+        let (_sender_id, sender_token) = register_device(&client, &server, "Sender").await;
+        let (receiver_id, receiver_token) = register_device(&client, &server, "Receiver").await;
+
+        let queue_status = |token: String| {
+            let client = client.clone();
+            let url = server.url("/api/v1/queue");
+            async move {
+                client
+                    .get(url)
+                    .bearer_auth(&token)
+                    .send()
+                    .await
+                    .expect("Failed to get queue status")
+                    .json::<Value>()
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let status = queue_status(receiver_token.clone()).await;
+        assert_eq!(status["count"], 0);
+        assert!(status["oldest_queued_at"].is_null());
+
+        // The receiver isn't connected, so everything is queued
+        for i in 0..3 {
+            let response = client
+                .post(server.url("/api/v1/messages/send"))
+                .bearer_auth(&sender_token)
+                .json(&json!({
+                    "id": format!("queued-{}", i),
+                    "to_device": receiver_id,
+                    "encrypted_payload": "cXVldWVk",
+                }))
+                .send()
+                .await
+                .expect("Failed to send message");
+            assert_eq!(response.status(), reqwest::StatusCode::ACCEPTED);
+        }
+
+        let status = queue_status(receiver_token.clone()).await;
+        assert_eq!(status["count"], 3);
+        assert!(status["oldest_queued_at"].is_number());
+        // Only the device's own queue is reported
+        assert_eq!(queue_status(sender_token.clone()).await["count"], 0);
+
+        let body: Value = client
+            .delete(server.url("/api/v1/queue"))
+            .bearer_auth(&receiver_token)
+            .send()
+            .await
+            .expect("Failed to flush queue")
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["deleted"], 3);
+        assert_eq!(queue_status(receiver_token.clone()).await["count"], 0);
+
+        // Nothing is delivered after a flush
+        let body: Value = client
+            .get(server.url("/api/v1/messages/poll?timeout_secs=1"))
+            .bearer_auth(&receiver_token)
+            .send()
+            .await
+            .expect("Failed to poll")
+            .json()
+            .await
+            .unwrap();
+        assert!(body["messages"].as_array().unwrap().is_empty());
+
+        // The queue belongs to an authenticated device
+        let response = client
+            .delete(server.url("/api/v1/queue"))
+            .send()
+            .await
+            .expect("Failed to flush queue");
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_binary_subprotocol_delivers_exact_payload() {
+        use toss_relay::relay::frame::{self, BINARY_SUBPROTOCOL};
+
+        let server = TestServer::start()
+            .await
+            .expect("Failed to start test server");
+        let client = reqwest::Client::new();
+
+        let (_, mut sender, selected) =
+            connect_ws(&client, &server, "Sender", Some(BINARY_SUBPROTOCOL)).await;
+        assert_eq!(selected.as_deref(), Some(BINARY_SUBPROTOCOL));
+        let (receiver_id, mut receiver, _) =
+            connect_ws(&client, &server, "Receiver", Some(BINARY_SUBPROTOCOL)).await;
+        // A client that offers nothing keeps JSON
+        let (legacy_id, mut legacy, selected) = connect_ws(&client, &server, "Legacy", None).await;
+        assert!(selected.is_none());
+
+        let payload: Vec<u8> = (0..4096u32).map(|i| (i % 256) as u8).collect();
+        let signature = [7u8; 64];
+        // Client frames leave from_device empty and the timestamp zero
+        let send_frame = |to_device: &str, id: &str| {
+            let mut frame = vec![0];
+            frame.push(to_device.len() as u8);
+            frame.extend_from_slice(to_device.as_bytes());
+            frame.push(id.len() as u8);
+            frame.extend_from_slice(id.as_bytes());
+            frame.extend_from_slice(&0u64.to_be_bytes());
+            frame.extend_from_slice(&1234u64.to_be_bytes());
+            frame.push(signature.len() as u8);
+            frame.extend_from_slice(&signature);
+            frame.extend_from_slice(&payload);
+            Message::Binary(frame.into())
+        };
+
+        sender
+            .send(send_frame(&receiver_id, "binary-1"))
+            .await
+            .unwrap();
+        let Some(Ok(Message::Binary(data))) = receiver.next().await else {
+            panic!("Expected a binary frame");
+        };
+        let delivered = frame::decode(&data).unwrap();
+        let engine = base64::engine::general_purpose::STANDARD;
+        assert_eq!(
+            engine.decode(&delivered.encrypted_payload).unwrap(),
+            payload
+        );
+        assert_eq!(delivered.id, "binary-1");
+        assert_eq!(delivered.to_device, receiver_id);
+        assert!(!delivered.from_device.is_empty());
+        assert!(delivered.timestamp > 0);
+        assert_eq!(delivered.signed_at, Some(1234));
+        assert_eq!(
+            engine.decode(delivered.signature.unwrap()).unwrap(),
+            signature
+        );
+        // The binary payload isn't inflated by base64
+        assert!(data.len() < payload.len() + 200);
+
+        // The same send reaches a JSON client as base64
+        sender
+            .send(send_frame(&legacy_id, "binary-2"))
+            .await
+            .unwrap();
+        let Some(Ok(Message::Text(text))) = legacy.next().await else {
+            panic!("Expected a JSON message");
+        };
+        let envelope: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(envelope["type"], "relay");
+        let encrypted_payload = envelope["message"]["encrypted_payload"].as_str().unwrap();
+        assert_eq!(engine.decode(encrypted_payload).unwrap(), payload);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_peer_websocket_only_sends_signed_frames_to_peer() {
+        use toss_relay::relay::frame;
+
+        let server = TestServer::start()
+            .await
+            .expect("Failed to start test server");
+        let client = reqwest::Client::new();
+        let engine = base64::engine::general_purpose::STANDARD;
+
+        let (peer_id, mut peer, _) = connect_ws(&client, &server, "Peer", None).await;
+        let (other_id, _other, _) = connect_ws(&client, &server, "Other", None).await;
+
+        // The device's primary connection, then a peer connection for the same device
+        let (signing_key, device_id, public_key) = generate_keypair();
+        let request = create_register_request(&signing_key, &device_id, &public_key, "Fallback");
+        let response = client
+            .post(server.url("/api/v1/register"))
+            .json(&request)
+            .send()
+            .await
+            .expect("Failed to send registration request");
+        assert!(response.status().is_success());
+        let (mut primary, _) = open_ws(&server, "/api/v1/ws", &signing_key, &device_id, None).await;
+        let (mut fallback, _) = open_ws(
+            &server,
+            &format!("/ws/{}", peer_id),
+            &signing_key,
+            &device_id,
+            None,
+        )
+        .await;
+
+        let payload = vec![1u8, 2, 3, 0xff];
+        let send_frame = |to_device: &str, signed: bool| {
+            let message = toss_relay::relay::RelayMessage {
+                id: String::new(),
+                from_device: String::new(),
+                to_device: to_device.to_string(),
+                encrypted_payload: engine.encode(&payload),
+                timestamp: 0,
+                signed_at: signed.then_some(1234),
+                signature: signed.then(|| engine.encode([7u8; 64])),
+            };
+            Message::Binary(frame::encode(&message).unwrap().into())
+        };
+        let expect_error = |message: Option<Result<Message, _>>| {
+            let Some(Ok(Message::Text(text))) = message else {
+                panic!("Expected an error");
+            };
+            let envelope: Value = serde_json::from_str(&text).unwrap();
+            assert_eq!(envelope["type"], "error");
+        };
+
+        // Unsigned sends and sends to anyone but the peer are refused
+        fallback.send(send_frame(&peer_id, false)).await.unwrap();
+        expect_error(fallback.next().await);
+        fallback.send(send_frame(&other_id, true)).await.unwrap();
+        expect_error(fallback.next().await);
+        let send = json!({
+            "type": "send",
+            "to_device": peer_id,
+            "encrypted_payload": engine.encode(&payload),
+        });
+        fallback
+            .send(Message::Text(send.to_string().into()))
+            .await
+            .unwrap();
+        expect_error(fallback.next().await);
+
+        // A signed send frame reaches the peer with its signature
+        fallback.send(send_frame(&peer_id, true)).await.unwrap();
+        let Some(Ok(Message::Text(text))) = peer.next().await else {
+            panic!("Expected a relay message");
+        };
+        let envelope: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(envelope["type"], "relay");
+        assert_eq!(envelope["message"]["from_device"], device_id.as_str());
+        assert_eq!(envelope["message"]["to_device"], peer_id.as_str());
+        assert_eq!(envelope["message"]["signed_at"], 1234);
+        let encrypted_payload = envelope["message"]["encrypted_payload"].as_str().unwrap();
+        assert_eq!(engine.decode(encrypted_payload).unwrap(), payload);
+
+        // Messages for the device still arrive on its primary connection
+        let send = json!({
+            "type": "send",
+            "to_device": device_id,
+            "encrypted_payload": engine.encode(b"reply"),
+        });
+        peer.send(Message::Text(send.to_string().into()))
+            .await
+            .unwrap();
+        let Some(Ok(Message::Text(text))) = primary.next().await else {
+            panic!("Expected a relay message");
+        };
+        let envelope: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(envelope["message"]["from_device"], peer_id.as_str());
+        let encrypted_payload = envelope["message"]["encrypted_payload"].as_str().unwrap();
+        assert_eq!(engine.decode(encrypted_payload).unwrap(), b"reply");
+
+        // Closing the peer connection leaves the device online
+        fallback.close(None).await.unwrap();
+        drop(fallback);
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let (_, token) = register_device(&client, &server, "Observer").await;
+        let body: Value = client
+            .get(server.url(&format!("/api/v1/devices/{}/status", device_id)))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .expect("Failed to get status")
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["is_online"], true);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_register_pairing_rejects_invalid_public_key() {
+        let server = TestServer::start()
+            .await
+            .expect("Failed to start test server");
+        let client = reqwest::Client::new();
+        let engine = base64::engine::general_purpose::STANDARD;
+
+        let register = |public_key: String| {
+            client
+                .post(server.url("/api/v1/pairing/register"))
+                .json(&json!({
+                    "code": "731904",
+                    "public_key": public_key,
+                    "device_name": "Advertiser",
+                }))
+                .send()
+        };
+
+        // Wrong length
+        let response = register(engine.encode([9u8; 16])).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let body: Value = response.json().await.unwrap();
+        assert!(body["error"].as_str().unwrap().contains("32 bytes"));
+
+        // Right length, but a low-order point no exchange can use
+        let response = register(engine.encode([0u8; 32])).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        // Nothing was stored for the code
+        let response = client
+            .get(server.url("/api/v1/pairing/find/731904"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        let response = register(x25519_public_key(3)).await.unwrap();
+        assert!(response.status().is_success());
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_pairing_completion_notifies_advertiser() {
+        let server = TestServer::start()
+            .await
+            .expect("Failed to start test server");
+        let client = reqwest::Client::new();
+        let advertiser_key = x25519_public_key(1);
+        let finder_key = x25519_public_key(2);
+
+        let response = client
+            .post(server.url("/api/v1/pairing/register"))
+            .json(&json!({
+                "code": "482913",
+                "public_key": advertiser_key,
+                "device_name": "Advertiser",
+            }))
+            .send()
+            .await
+            .expect("Failed to register pairing");
+        assert!(response.status().is_success());
+
+        // Completion is only accepted after the code was looked up
+        let complete = json!({ "public_key": finder_key, "device_name": "Finder" });
+        let response = client
+            .post(server.url("/api/v1/pairing/482913/complete"))
+            .json(&complete)
+            .send()
+            .await
+            .expect("Failed to complete pairing");
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        // The advertiser waits for the session to be completed
+        let status = tokio::spawn({
+            let client = client.clone();
+            let url = server.url("/api/v1/pairing/482913/status?timeout_secs=10");
+            async move {
+                client
+                    .get(url)
+                    .send()
+                    .await
+                    .expect("Failed to get pairing status")
+                    .json::<Value>()
+                    .await
+                    .unwrap()
+            }
+        });
+
+        let response = client
+            .get(server.url("/api/v1/pairing/find/482913"))
+            .send()
+            .await
+            .expect("Failed to find pairing");
+        assert!(response.status().is_success());
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let response = client
+            .post(server.url("/api/v1/pairing/482913/complete"))
+            .json(&complete)
+            .send()
+            .await
+            .expect("Failed to complete pairing");
+        assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+        let body = tokio::time::timeout(std::time::Duration::from_secs(5), status)
+            .await
+            .expect("Status should return once pairing completes")
+            .unwrap();
+        assert_eq!(body["completed"], true);
+        assert_eq!(body["peer_public_key"], finder_key.as_str());
+        assert_eq!(body["peer_device_name"], "Finder");
+
+        // A session completes only once, and is gone after the advertiser cancels it
+        let response = client
+            .post(server.url("/api/v1/pairing/482913/complete"))
+            .json(&complete)
+            .send()
+            .await
+            .expect("Failed to complete pairing");
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        let response = client
+            .delete(server.url("/api/v1/pairing/482913"))
+            .send()
+            .await
+            .expect("Failed to cancel pairing");
+        assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+        let response = client
+            .get(server.url("/api/v1/pairing/482913/status?timeout_secs=1"))
+            .send()
+            .await
+            .expect("Failed to get pairing status");
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        server.shutdown().await;
+    }
+
+    /// Send a CORS preflight for `POST /api/v1/messages/send` from `origin`
+    async fn preflight(server: &TestServer, origin: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .request(
+                reqwest::Method::OPTIONS,
+                server.url("/api/v1/messages/send"),
+            )
+            .header("Origin", origin)
+            .header("Access-Control-Request-Method", "POST")
+            .header(
+                "Access-Control-Request-Headers",
+                "authorization,content-type",
+            )
+            .send()
+            .await
+            .expect("Failed to send preflight")
+    }
+
+    #[tokio::test]
+    async fn test_cors_allow_list() {
+        let config = toss_relay::Config {
+            allowed_origins: vec!["https://app.example.com".to_string()],
+            ..Default::default()
+        };
+        let server = TestServer::start_with_config(config)
+            .await
+            .expect("Failed to start test server");
+
+        let allowed = preflight(&server, "https://app.example.com").await;
+        assert_eq!(
+            allowed
+                .headers()
+                .get("access-control-allow-origin")
+                .and_then(|v| v.to_str().ok()),
+            Some("https://app.example.com")
+        );
+
+        let rejected = preflight(&server, "https://evil.example.com").await;
+        assert!(
+            rejected
+                .headers()
+                .get("access-control-allow-origin")
+                .is_none(),
+            "Disallowed origin should not be granted access"
+        );
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_cors_without_allow_list() {
+        // Refused by default
+        let server = TestServer::start()
+            .await
+            .expect("Failed to start test server");
+        let response = preflight(&server, "https://app.example.com").await;
+        assert!(response
+            .headers()
+            .get("access-control-allow-origin")
+            .is_none());
+        server.shutdown().await;
+
+        // Any origin in dev mode
+        let config = toss_relay::Config {
+            dev_mode: true,
+            ..Default::default()
+        };
+        let server = TestServer::start_with_config(config)
+            .await
+            .expect("Failed to start test server");
+        let response = preflight(&server, "https://app.example.com").await;
+        assert_eq!(
+            response
+                .headers()
+                .get("access-control-allow-origin")
+                .and_then(|v| v.to_str().ok()),
+            Some("*")
+        );
+        server.shutdown().await;
+    }
+
+    /// Check a relay identity response the way a client with a pinned key does
+    fn check_pinned_identity(body: &Value, challenge: &str, pinned: &[u8; 32]) -> bool {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let public_key = engine.decode(body["public_key"].as_str().unwrap()).unwrap();
+        let signature = engine.decode(body["signature"].as_str().unwrap()).unwrap();
+        let message = format!("relay-identity:{}", challenge);
+
+        let verifying_key =
+            ed25519_dalek::VerifyingKey::from_bytes(&public_key.try_into().unwrap()).unwrap();
+        let signature = ed25519_dalek::Signature::from_bytes(&signature.try_into().unwrap());
+        verifying_key.to_bytes() == *pinned
+            && ed25519_dalek::Verifier::verify(&verifying_key, message.as_bytes(), &signature)
+                .is_ok()
+    }
+
+    #[tokio::test]
+    async fn test_relay_identity_pinning() {
+        let seed = [7u8; 32];
+        let config = toss_relay::Config {
+            identity_key: Some(base64::engine::general_purpose::STANDARD.encode(seed)),
+            ..Default::default()
+        };
+        let server = TestServer::start_with_config(config)
+            .await
+            .expect("Failed to start test server");
+        let client = reqwest::Client::new();
+
+        let challenge = hex::encode(rand::random::<[u8; 16]>());
+        let body: Value = client
+            .get(server.url(&format!("/api/v1/identity?challenge={}", challenge)))
+            .send()
+            .await
+            .expect("Failed to fetch relay identity")
+            .json()
+            .await
+            .unwrap();
+
+        // The configured key is reported and signs the challenge
+        let expected = SigningKey::from_bytes(&seed).verifying_key().to_bytes();
+        assert!(check_pinned_identity(&body, &challenge, &expected));
+
+        // A client that pinned a different key refuses this relay
+        let other = SigningKey::from_bytes(&[9u8; 32])
+            .verifying_key()
+            .to_bytes();
+        assert!(!check_pinned_identity(&body, &challenge, &other));
+
+        // Without a challenge only the key is reported
+        let body: Value = client
+            .get(server.url("/api/v1/identity"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(
+            body["public_key"].as_str(),
+            Some(
+                base64::engine::general_purpose::STANDARD
+                    .encode(expected)
+                    .as_str()
+            )
+        );
+        assert!(body["signature"].is_null());
+
+        server.shutdown().await;
+    }
+
+    /// Check a session proof over `challenge` for `device_id` against `pinned`
+    fn check_session_proof(
+        signature: &Value,
+        challenge: &str,
+        device_id: &str,
+        pinned: &[u8; 32],
+    ) -> bool {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let signature = engine.decode(signature.as_str().unwrap()).unwrap();
+        let message = format!("relay-session:{}:{}", challenge, device_id);
+
+        let verifying_key = ed25519_dalek::VerifyingKey::from_bytes(pinned).unwrap();
+        let signature = ed25519_dalek::Signature::from_bytes(&signature.try_into().unwrap());
+        ed25519_dalek::Verifier::verify(&verifying_key, message.as_bytes(), &signature).is_ok()
+    }
+
+    #[tokio::test]
+    async fn test_relay_proves_identity_on_the_authenticated_session() {
+        let seed = [7u8; 32];
+        let config = toss_relay::Config {
+            identity_key: Some(base64::engine::general_purpose::STANDARD.encode(seed)),
+            ..Default::default()
+        };
+        let server = TestServer::start_with_config(config)
+            .await
+            .expect("Failed to start test server");
+        let client = reqwest::Client::new();
+        let expected = SigningKey::from_bytes(&seed).verifying_key().to_bytes();
+
+        // Registration answers the challenge alongside the token
+        let (signing_key, device_id, public_key) = generate_keypair();
+        let mut request = create_register_request(&signing_key, &device_id, &public_key, "Pinned");
+        request["challenge"] = json!("register-nonce");
+        let body: Value = client
+            .post(server.url("/api/v1/register"))
+            .json(&request)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert!(check_session_proof(
+            &body["identity_signature"],
+            "register-nonce",
+            &device_id,
+            &expected
+        ));
+
+        // So does the WebSocket auth response
+        let (mut ws, _) =
+            tokio_tungstenite::connect_async(server.url("/api/v1/ws").replace("http", "ws"))
+                .await
+                .expect("Failed to open WebSocket");
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let signature = signing_key.sign(format!("auth:{}:{}", device_id, timestamp).as_bytes());
+        let auth = json!({
+            "type": "auth",
+            "device_id": device_id,
+            "timestamp": timestamp,
+            "signature": base64::engine::general_purpose::STANDARD.encode(signature.to_bytes()),
+            "challenge": "ws-nonce",
+        });
+        ws.send(Message::Text(auth.to_string().into()))
+            .await
+            .unwrap();
+        let Some(Ok(Message::Text(response))) = ws.next().await else {
+            panic!("Expected an auth response");
+        };
+        let response: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["success"], true);
+        assert!(check_session_proof(
+            &response["identity_signature"],
+            "ws-nonce",
+            &device_id,
+            &expected
+        ));
+        // Bound to the challenge it answers
+        assert!(!check_session_proof(
+            &response["identity_signature"],
+            "register-nonce",
+            &device_id,
+            &expected
+        ));
+
+        server.shutdown().await;
+    }
 }
@@ -4,39 +4,196 @@
 //! via flutter_rust_bridge.
 
 use flutter_rust_bridge::frb;
+use once_cell::sync::Lazy;
 use parking_lot::RwLock;
-use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
 
-use crate::clipboard::ClipboardManager;
+use crate::cancel::CancellationToken;
+use crate::clipboard::{
+    downscale_image, get_image_dimensions, get_image_mime_type, save_received_file,
+    ClipboardManager, ClipboardSnapshot, ConflictResolver, ContentDenylist, QuietHours,
+    QuietHoursQueue, Resolution, MINUTES_PER_DAY,
+};
 use crate::crypto::{
-    decrypt, derive_key, encrypt, DerivedKeyPurpose, DeviceIdentity, EncryptedMessage,
-    PairingSession,
+    decrypt, derive_key, encrypt, is_valid_pairing_code, paired_device_id, parse_qr_data,
+    CompletedPairing, DerivedKeyPurpose, DeviceIdentity, EncryptedMessage, PairingSession,
+    PAIRING_CODE_LENGTH,
+};
+use crate::log_buffer::{parse_log_filter, LogBuffer, DEFAULT_LOG_CAPACITY};
+use crate::log_file::LogFile;
+use crate::network::{
+    GetPublicKeyFn, GetSessionKeyFn, IncomingMessage, NetworkConfig, NetworkEvent, NetworkManager,
+    PresenceThrottle, SendOutcome, TransferDirection, DEFAULT_PROBE_TIMEOUT, KEY_CHECK_TIMEOUT,
+};
+use crate::pairing::{PairingTrust, DEFAULT_TRUST_WINDOW};
+use crate::protocol::{
+    encoding, hash_content_reader, AutoAction, Capabilities, ClipboardContent, ClipboardUpdate,
+    ContentType, DeviceRemoved, IdentityMigration, LockState, Message, Presence, PresenceState,
+    MAX_SCRATCHPAD_SIZE,
+};
+use crate::scratchpad::Scratchpad;
+use crate::storage::{
+    probe_secure_storage, store_identity_key, wipe_key_material, DeviceLimitPolicy, HistoryPrune,
+    HistoryStore, Storage, StoreDeviceOutcome, StoredDevice, StoredHistoryFormat,
+    StoredHistoryItem,
 };
-use crate::network::{GetSessionKeyFn, NetworkConfig, NetworkEvent, NetworkManager};
-use crate::protocol::{ClipboardContent, ClipboardUpdate, ContentType, Message};
-use crate::storage::{Storage, StoredDevice};
 
 /// Global Toss instance
 static TOSS_INSTANCE: RwLock<Option<TossCore>> = RwLock::new(None);
 
-/// Guard for file logger (must be kept alive for logging to work)
-static LOG_GUARD: RwLock<Option<WorkerGuard>> = RwLock::new(None);
+/// File logger, for flushing on demand
+static LOG_FILE: RwLock<Option<LogFile>> = RwLock::new(None);
+
+/// Maximum device name length in UTF-8 bytes
+const MAX_DEVICE_NAME_LEN: usize = 100;
+
+/// Name stored for a paired device that didn't advertise one
+const DEFAULT_PAIRED_DEVICE_NAME: &str = "Paired Device";
+
+/// How long each wait for a relay pairing completion lasts, in seconds
+const RELAY_PAIRING_POLL_SECS: u64 = 30;
+
+/// Delay before retrying a failed wait for a relay pairing completion
+const RELAY_PAIRING_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Handle for changing the log filter at runtime
+static LOG_FILTER: RwLock<Option<LogFilterHandle>> = RwLock::new(None);
+
+/// Whether `init_toss` may fall back to in-memory storage
+static STORAGE_FALLBACK: AtomicBool = AtomicBool::new(true);
+
+/// Recent log lines, viewable from the app
+static LOG_BUFFER: Lazy<LogBuffer> = Lazy::new(|| LogBuffer::new(DEFAULT_LOG_CAPACITY));
+
+type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+type EventReceiver = Arc<tokio::sync::Mutex<tokio::sync::broadcast::Receiver<NetworkEvent>>>;
 
 /// Core Toss functionality
 pub struct TossCore {
     identity: Arc<DeviceIdentity>,
     device_name: String,
     clipboard: ClipboardManager,
-    network: Option<NetworkManager>,
+    network: Option<Arc<NetworkManager>>,
     pairing_session: Option<PairingSession>,
+    /// Coordinator advertising the pairing session's code, once advertised
+    pairing_advertiser: Option<Arc<crate::pairing::PairingCoordinator>>,
+    /// Cancels the `find_pairing_device` search in progress, if any
+    pairing_search: Mutex<Option<CancellationToken>>,
+    /// Cancels the sends in progress; replaced once used
+    send_cancel: Mutex<CancellationToken>,
+    /// Most recent reason a send was skipped or only partly delivered
+    last_skip: Mutex<Option<SkipReasonDto>>,
+    /// Devices over `monthly_relay_cap_bytes` this month
+    relay_caps: Mutex<RelayCaps>,
     settings: TossSettings,
+    /// `settings.content_denylist`, compiled
+    denylist: ContentDenylist,
     storage: Storage,
-    event_receiver: Option<Arc<Mutex<tokio::sync::broadcast::Receiver<NetworkEvent>>>>,
+    /// Held by `listen_events` while it waits, so `poll_event` only tries it
+    event_receiver: Option<EventReceiver>,
+    /// Events raised outside the network manager, delivered before network events
+    pending_events: Mutex<VecDeque<TossEvent>>,
+    /// Orders local and received clipboard updates
+    conflicts: ConflictResolver,
+    /// Shared scratchpad document, also applied to by the network manager
+    scratchpad: Arc<Scratchpad>,
+    /// Rate limits presence hints in both directions
+    presence: PresenceThrottle,
+    /// Whether the host reported the screen locked
+    screen_locked: bool,
+    /// Latest received update held back during quiet hours
+    quiet_hours_queue: QuietHoursQueue<(ClipboardContent, ClipboardItemDto)>,
+    /// The device's offset from UTC, as reported by the host
+    utc_offset: chrono::FixedOffset,
     last_sync_time: std::sync::Mutex<std::time::Instant>,
+    /// Window in which relay pairings skip `confirm_pairing`
+    pairing_trust: PairingTrust,
+    /// Relay pairing waiting for `confirm_pairing`
+    pending_pairing: Mutex<Option<PendingPairing>>,
+    /// Deletes history past `history_days` every `HISTORY_PRUNE_INTERVAL`
+    history_prune_task: Option<tokio::task::JoinHandle<()>>,
+}
+
+/// A relay pairing waiting for the user to confirm it
+struct PendingPairing {
+    /// Pairing code the finder completed
+    code: String,
+    /// Finder's public key, base64 encoded
+    public_key: String,
+    device_name: String,
+}
+
+impl TossCore {
+    /// Whether quiet hours are on and the device's local time is inside them
+    fn in_quiet_hours(&self) -> bool {
+        self.settings
+            .quiet_hours()
+            .is_some_and(|quiet| quiet.is_active(chrono::Utc::now(), self.utc_offset))
+    }
+
+    /// Remember why a send was skipped, returning the error message for it
+    fn record_skip(&self, skip: SendSkip) -> String {
+        tracing::debug!("Send skipped ({}): {}", skip.reason.as_str(), skip.message);
+        *self.last_skip.lock().unwrap() = Some(SkipReasonDto {
+            reason: skip.reason.as_str().to_string(),
+            message: skip.message.clone(),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+        });
+        skip.message
+    }
+}
+
+/// Which way a content type is synced, once its `sync_*` toggle is on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncDirection {
+    /// Send local changes and accept incoming ones
+    #[default]
+    Both,
+    /// Send local changes, ignore incoming ones
+    SendOnly,
+    /// Accept incoming content, never send it
+    ReceiveOnly,
+}
+
+impl SyncDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Both => "both",
+            Self::SendOnly => "send_only",
+            Self::ReceiveOnly => "receive_only",
+        }
+    }
+
+    pub fn sends(&self) -> bool {
+        !matches!(self, Self::ReceiveOnly)
+    }
+
+    pub fn receives(&self) -> bool {
+        !matches!(self, Self::SendOnly)
+    }
+}
+
+impl std::str::FromStr for SyncDirection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "both" => Ok(Self::Both),
+            "send_only" => Ok(Self::SendOnly),
+            "receive_only" => Ok(Self::ReceiveOnly),
+            other => Err(format!("Unknown sync direction: {}", other)),
+        }
+    }
 }
 
 /// Toss settings
@@ -47,10 +204,118 @@ pub struct TossSettings {
     pub sync_rich_text: bool,
     pub sync_images: bool,
     pub sync_files: bool,
+    /// Direction for text, URLs and code when `sync_text` is on
+    #[serde(default)]
+    pub text_direction: SyncDirection,
+    /// Direction for rich text when `sync_rich_text` is on
+    #[serde(default)]
+    pub rich_text_direction: SyncDirection,
+    /// Direction for images when `sync_images` is on
+    #[serde(default)]
+    pub image_direction: SyncDirection,
+    /// Direction for files when `sync_files` is on
+    #[serde(default)]
+    pub file_direction: SyncDirection,
     pub max_file_size_mb: u32,
+    /// Downscale outbound images so neither side exceeds this (0 = send the original)
+    pub max_image_dimension: u32,
     pub history_enabled: bool,
+    /// Keep history for text, URLs and code when `history_enabled` is on
+    #[serde(default = "default_keep_history")]
+    pub history_text: bool,
+    /// Keep history for rich text when `history_enabled` is on
+    #[serde(default = "default_keep_history")]
+    pub history_rich_text: bool,
+    /// Keep history for images when `history_enabled` is on
+    #[serde(default = "default_keep_history")]
+    pub history_images: bool,
+    /// Keep history for files when `history_enabled` is on
+    #[serde(default = "default_keep_history")]
+    pub history_files: bool,
+    /// Days to keep clipboard history (0 = keep forever)
     pub history_days: u32,
     pub relay_url: Option<String>,
+    /// Base64 relay identity key to pin; the relay is refused if it can't prove it
+    pub relay_pinned_key: Option<String>,
+    /// Advertise the latest content type and digest via mDNS (never the content)
+    pub advertise_content_hint: bool,
+    /// Maximum number of stored paired devices (0 = no limit)
+    pub max_paired_devices: u32,
+    /// Evict the least recently seen device at the limit instead of rejecting pairing
+    pub evict_oldest_device: bool,
+    /// Keep local aggregate counters of send outcomes (never content or peer identities)
+    pub collect_sync_stats: bool,
+    /// Don't auto-sync between `quiet_hours_start` and `quiet_hours_end` local time
+    #[serde(default)]
+    pub quiet_hours_enabled: bool,
+    /// Start of quiet hours, in minutes after local midnight
+    #[serde(default = "default_quiet_hours_start")]
+    pub quiet_hours_start: u32,
+    /// End of quiet hours, in minutes after local midnight (before the start to cross midnight)
+    #[serde(default = "default_quiet_hours_end")]
+    pub quiet_hours_end: u32,
+    /// Act on `auto_action` requests from paired devices (e.g. open a pushed URL)
+    #[serde(default)]
+    pub allow_remote_actions: bool,
+    /// Exchange "copying" presence hints with paired devices (never content)
+    #[serde(default)]
+    pub share_presence: bool,
+    /// Identify content to peers by a MAC keyed with the pairing's session
+    /// key, so observers can't match it against hashes of guessed content
+    #[serde(default)]
+    pub keyed_content_hash: bool,
+    /// Hold history writes back this long to coalesce bursts of copies (0 = write each)
+    #[serde(default = "default_history_write_window_ms")]
+    pub history_write_window_ms: u32,
+    /// Ask before pairing with a device that completed our code on the relay
+    #[serde(default)]
+    pub confirm_pairing: bool,
+    /// Sync only with peers on the local network, never through the relay
+    #[serde(default)]
+    pub lan_only_mode: bool,
+    /// Save received files into this directory instead of writing them to
+    /// the clipboard (None = clipboard)
+    #[serde(default)]
+    pub file_receive_directory: Option<String>,
+    /// Don't sync or keep history for content smaller than this many bytes
+    /// (0 = sync everything); empty content, an explicit clear, always syncs
+    #[serde(default)]
+    pub min_sync_bytes: u32,
+    /// Stop syncing with a device through the relay or over the internet once
+    /// this many bytes were sent to it this month (0 = no cap); LAN sync goes on
+    #[serde(default)]
+    pub monthly_relay_cap_bytes: u64,
+    /// Regular expressions for text that must never be sent; content
+    /// matching any of them is skipped
+    #[serde(default)]
+    pub content_denylist: Vec<String>,
+    /// Pause clipboard sync with a device while it reports its screen locked
+    #[serde(default)]
+    pub require_peer_unlocked: bool,
+    /// mDNS service name; devices only discover and pair with devices using
+    /// the same one. Takes effect when networking next starts.
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+}
+
+fn default_keep_history() -> bool {
+    true
+}
+
+fn default_history_write_window_ms() -> u32 {
+    crate::storage::DEFAULT_HISTORY_WRITE_WINDOW.as_millis() as u32
+}
+
+fn default_service_name() -> String {
+    crate::network::discovery::DEFAULT_SERVICE_NAME.to_string()
+}
+
+fn default_quiet_hours_start() -> u32 {
+    22 * 60
+}
+
+fn default_quiet_hours_end() -> u32 {
+    7 * 60
 }
 
 impl Default for TossSettings {
@@ -61,11 +326,88 @@ impl Default for TossSettings {
             sync_rich_text: true,
             sync_images: true,
             sync_files: true,
+            text_direction: SyncDirection::Both,
+            rich_text_direction: SyncDirection::Both,
+            image_direction: SyncDirection::Both,
+            file_direction: SyncDirection::Both,
             max_file_size_mb: 50,
+            max_image_dimension: 0,
             history_enabled: true,
+            history_text: true,
+            history_rich_text: true,
+            history_images: true,
+            history_files: true,
             history_days: 7,
             relay_url: None,
+            relay_pinned_key: None,
+            advertise_content_hint: false,
+            max_paired_devices: 20,
+            evict_oldest_device: false,
+            collect_sync_stats: false,
+            quiet_hours_enabled: false,
+            quiet_hours_start: default_quiet_hours_start(),
+            quiet_hours_end: default_quiet_hours_end(),
+            allow_remote_actions: false,
+            share_presence: false,
+            keyed_content_hash: false,
+            history_write_window_ms: default_history_write_window_ms(),
+            confirm_pairing: false,
+            lan_only_mode: false,
+            file_receive_directory: None,
+            min_sync_bytes: 0,
+            monthly_relay_cap_bytes: 0,
+            content_denylist: Vec::new(),
+            require_peer_unlocked: false,
+            service_name: default_service_name(),
+        }
+    }
+}
+
+impl TossSettings {
+    /// The direction a content type syncs in, or `None` if its sync is off
+    pub fn direction_for(&self, content_type: ContentType) -> Option<SyncDirection> {
+        let (enabled, direction) = match content_type {
+            ContentType::PlainText | ContentType::Url | ContentType::Code => {
+                (self.sync_text, self.text_direction)
+            }
+            ContentType::RichText => (self.sync_rich_text, self.rich_text_direction),
+            ContentType::Image => (self.sync_images, self.image_direction),
+            ContentType::File => (self.sync_files, self.file_direction),
+        };
+        enabled.then_some(direction)
+    }
+
+    /// Whether local content of this type may be sent
+    pub fn sends(&self, content_type: ContentType) -> bool {
+        self.direction_for(content_type)
+            .is_some_and(|direction| direction.sends())
+    }
+
+    /// Whether incoming content of this type may be written to the clipboard
+    pub fn receives(&self, content_type: ContentType) -> bool {
+        self.direction_for(content_type)
+            .is_some_and(|direction| direction.receives())
+    }
+
+    /// Whether content of this type is saved to history
+    ///
+    /// `history_enabled` off keeps no history regardless of the per-type toggles.
+    pub fn keeps_history(&self, content_type: ContentType) -> bool {
+        self.history_enabled
+            && match content_type {
+                ContentType::PlainText | ContentType::Url | ContentType::Code => self.history_text,
+                ContentType::RichText => self.history_rich_text,
+                ContentType::Image => self.history_images,
+                ContentType::File => self.history_files,
+            }
+    }
+
+    /// The quiet hours window, or `None` if quiet hours are off or empty
+    pub fn quiet_hours(&self) -> Option<QuietHours> {
+        if !self.quiet_hours_enabled {
+            return None;
         }
+        QuietHours::new(self.quiet_hours_start, self.quiet_hours_end)
     }
 }
 
@@ -79,25 +421,249 @@ pub struct DeviceInfoDto {
     pub platform: String, // Platform name: "macos", "windows", "linux", "ios", "android", "unknown"
 }
 
+/// Whether a paired device can be reached right now
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DeviceReachabilityDto {
+    pub id: String,
+    pub name: String,
+    pub reachable: bool,
+    pub path: Option<String>, // "connected", "local" or "relay"; None if unreachable
+}
+
+/// Cryptographic details of a paired device, for a security screen
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DeviceSecurityDto {
+    pub device_id: String,
+    pub key_algorithm: String, // "ed25519"; as advertised while connected
+    /// Identity key fingerprint to compare with the one the device shows
+    pub fingerprint: String,
+    pub fingerprint_verified: bool,
+    pub fingerprint_verified_at: Option<u64>, // Unix seconds
+    /// When the session key was set up or last rotated, while connected (Unix seconds)
+    pub session_key_since: Option<u64>,
+    /// Last authenticated message received from the device (Unix seconds)
+    pub last_authenticated_at: Option<u64>,
+}
+
 /// Clipboard item for display
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ClipboardItemDto {
-    pub id: String, // Unique identifier for history item
-    pub content_type: String,
+    pub id: String,           // Unique identifier for history item
+    pub content_type: String, // "text", "rich_text", "image", "file", "url", "code"
+    pub mime_type: String,
     pub preview: String,
     pub size_bytes: u64,
     pub timestamp: u64,
     pub source_device: Option<String>,
+    pub language: Option<String>, // Language hint for "code" items, e.g. "rust"
+    pub superseded: bool,         // Received but lost to a concurrent copy, so not applied
+}
+
+/// Clipboard history items from one source device on one local day
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct HistoryGroupDto {
+    pub day: String, // Local calendar day, "YYYY-MM-DD"
+    pub source_device: Option<String>,
+    pub items: Vec<ClipboardItemDto>,
+}
+
+/// Local sync statistics for Flutter
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SyncStatsDto {
+    pub send_attempts: u64,
+    pub send_successes: u64,
+    pub relay_fallbacks: u64,
+    pub failures: Vec<SyncFailureCountDto>,
+}
+
+/// Number of failed sends in one error category
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SyncFailureCountDto {
+    pub category: String, // "timeout", "connection", "relay", "other"
+    pub count: u64,
+}
+
+/// One device's traffic through the relay or over the internet this month
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RelayUsageDto {
+    pub device_id: String,
+    pub month: String, // "YYYY-MM", in the device's local time
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub capped: bool, // Over `monthly_relay_cap_bytes`; synced over LAN only
+}
+
+/// Relay server identity, for pinning its key
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RelayIdentityDto {
+    pub public_key: String, // Base64, the value to store as `relay_pinned_key`
+    pub matches_pinned: Option<bool>, // None when no key is pinned
+}
+
+/// Messages the relay holds for this device
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RelayQueueStatusDto {
+    pub count: u64,
+    pub oldest_queued_at: Option<i64>, // Unix seconds; None when the queue is empty
+}
+
+/// Snapshot of the network state, for troubleshooting
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NetworkDiagnosticsDto {
+    pub local_addr: Option<String>, // QUIC listen address; None if not listening
+    pub mdns_status: String,        // "disabled", "registered", "not_registered"
+    pub discovered_peers: Vec<DiscoveredPeerDto>,
+    pub connected_peers: Vec<ConnectedPeerDto>,
+    pub relay_status: String, // "not_configured", "lan_only", "connected", "long_poll", "disconnected"
+    pub relay_url: Option<String>,
+    pub nat_type: String, // "none", "full_cone", ..., "unknown"
+    pub candidates: Vec<IceCandidateDto>,
+}
+
+/// A device that answered an mDNS browse
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DiscoveredPeerDto {
+    pub device_id: String,
+    pub device_name: String,
+    pub addresses: Vec<String>,
+    pub version: String,
+}
+
+/// A device with an open connection
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ConnectedPeerDto {
+    pub device_id: String,
+    pub device_name: String,
+    pub addresses: Vec<String>,
+    pub transport: String, // "quic"
+    pub route: String,     // "lan" or "internet"
+    pub rtt_ms: u64,
+}
+
+/// A gathered ICE candidate
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct IceCandidateDto {
+    pub candidate_type: String, // "host", "server_reflexive", "relay"
+    pub address: String,
+    pub priority: u64,
+}
+
+/// Result of one self-test check
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SelfTestCheckDto {
+    pub name: String,   // "secure_storage", "crypto", "database", "clipboard", "relay"
+    pub status: String, // "passed", "failed", "skipped"
+    pub error: Option<String>,
+}
+
+/// Self-test report, one entry per check
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SelfTestReportDto {
+    pub passed: bool, // No check failed (skipped checks don't count)
+    pub checks: Vec<SelfTestCheckDto>,
+}
+
+/// Outcome of replacing this device's identity key
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct IdentityRotationDto {
+    pub device_id: String,         // New device ID
+    pub migrated: Vec<String>,     // Paired devices that were told about the new key
+    pub needs_repair: Vec<String>, // Paired devices that must be paired again
+}
+
+/// Limits and formats the core enforces, so clients can validate against them
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LimitsDto {
+    pub max_message_size: u64, // Bytes
+    pub max_preview_size: u64, // Bytes, per metadata preview
+    pub protocol_version: u32,
+    pub pairing_code_length: u32,
+    pub pairing_code_format: String, // Regular expression, e.g. "^[0-9]{6}$"
+    pub max_device_name_length: u32, // UTF-8 bytes
+}
+
+/// Progress of a transfer being sent or received
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TransferProgressDto {
+    pub transfer_id: u64,
+    pub direction: String, // "incoming" or "outgoing"
+    pub device_id: String, // Peer on the other end
+    pub total_bytes: u64,  // Estimated for incoming transfers until complete
+    pub received_bytes: u64,
+    pub percent: f64,
+}
+
+/// Why a clipboard send was skipped or only partly delivered
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SkipReasonDto {
+    pub reason: String, // e.g. "type_disabled", "quiet_hours", "no_peers"; see SkipReason
+    pub message: String,
+    pub timestamp: u64, // Unix milliseconds
 }
 
 /// Event types for Flutter
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum TossEvent {
-    ClipboardReceived { item: ClipboardItemDto },
-    DeviceConnected { device: DeviceInfoDto },
-    DeviceDisconnected { device_id: String },
-    PairingRequest { device: DeviceInfoDto },
-    Error { message: String },
+    ClipboardReceived {
+        item: ClipboardItemDto,
+    },
+    DeviceConnected {
+        device: DeviceInfoDto,
+    },
+    DeviceDisconnected {
+        device_id: String,
+    },
+    PairingRequest {
+        device: DeviceInfoDto,
+    },
+    Error {
+        message: String,
+    },
+    EventsLost {
+        count: u64,
+    }, // Events dropped before they were polled; refresh all state
+    StorageDegraded {
+        message: String,
+    }, // Running on in-memory storage; nothing is persisted
+    StorageRecovered {
+        backup_path: String,
+        data_lost: bool,
+    }, // The database was corrupt and rebuilt; the damaged file is kept at backup_path
+    OpenUrlRequested {
+        url: String,
+        device_id: String,
+    }, // A paired device asked to open a URL
+    PresenceChanged {
+        device_id: String,
+        state: String,
+    }, // "idle" or "copying"; a hint only
+    DeviceIdentityChanged {
+        old_device_id: String,
+        new_device_id: String,
+    }, // A paired device replaced its identity key
+    PairingConfirmationRequested {
+        device: DeviceInfoDto,
+    }, // Answer with confirm_pairing
+    FileReceived {
+        path: String,
+        device_id: String,
+    }, // A received file was saved to `file_receive_directory`
+    RelayCapReached {
+        device_id: String,
+        bytes_sent: u64,
+        cap_bytes: u64,
+    }, // Sync with the device is LAN-only until next month or `reset_relay_usage`
+    ScratchpadChanged {
+        text: String,
+        device_id: String,
+    }, // A paired device's edit changed the shared scratchpad
+    TransferProgress {
+        transfer_id: u64,
+        direction: String, // "incoming" or "outgoing"
+        device_id: String,
+        total_bytes: u64, // Estimated for incoming transfers until complete
+        transferred_bytes: u64,
+    }, // Raised at each whole percent and on completion
 }
 
 /// Event stream for Flutter (simplified - full stream support requires flutter_rust_bridge stream support)
@@ -124,15 +690,14 @@ pub fn init_toss(data_dir: String, device_name: String) -> Result<(), String> {
     crate::panic_handler::install_panic_hook(&log_dir);
 
     // Initialize file-based logging
-    let file_appender = tracing_appender::rolling::daily(&log_dir, "toss.log");
-    let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
-
-    // Store the guard to keep logging active
-    *LOG_GUARD.write() = Some(guard);
+    let appender_dir = log_dir.clone();
+    let log_file =
+        LogFile::new(move || tracing_appender::rolling::daily(&appender_dir, "toss.log"));
 
     // Initialize tracing subscriber with both stdout and file output
     let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("toss_core=debug"));
+    let (env_filter, filter_handle) = reload::Layer::new(env_filter);
 
     match tracing_subscriber::registry()
         .with(env_filter)
@@ -143,12 +708,17 @@ pub fn init_toss(data_dir: String, device_name: String) -> Result<(), String> {
         )
         .with(
             tracing_subscriber::fmt::layer()
-                .with_writer(non_blocking)
+                .with_writer(log_file.clone())
                 .with_ansi(false),
         )
+        .with(LOG_BUFFER.layer())
         .try_init()
     {
-        Ok(_) => tracing::info!("Toss core initializing with data_dir: {}", data_dir),
+        Ok(_) => {
+            *LOG_FILTER.write() = Some(filter_handle);
+            *LOG_FILE.write() = Some(log_file);
+            tracing::info!("Toss core initializing with data_dir: {}", data_dir)
+        }
         Err(e) => eprintln!(
             "Warning: tracing init failed (may already be initialized): {}",
             e
@@ -157,27 +727,71 @@ pub fn init_toss(data_dir: String, device_name: String) -> Result<(), String> {
 
     // Initialize storage
     let db_path = std::path::Path::new(&data_dir).join("toss.db");
-    let storage =
-        Storage::new(&db_path).map_err(|e| format!("Failed to initialize storage: {}", e))?;
+    let (storage, storage_warning) =
+        open_storage(&db_path, STORAGE_FALLBACK.load(Ordering::SeqCst))?;
+
+    // Settings saved by an earlier session
+    let settings: TossSettings = storage.settings().load().unwrap_or_else(|e| {
+        tracing::warn!("Failed to load settings, using defaults: {}", e);
+        TossSettings::default()
+    });
+    let denylist = ContentDenylist::new(&settings.content_denylist).unwrap_or_else(|e| {
+        tracing::warn!("{}, not applying the stored denylist", e);
+        ContentDenylist::default()
+    });
+    let window = std::time::Duration::from_millis(settings.history_write_window_ms as u64);
+    if let Err(e) = storage.set_history_write_window(window) {
+        tracing::warn!("Failed to set the history write window: {}", e);
+    }
 
     // Load or create identity
     let identity =
         DeviceIdentity::generate().map_err(|e| format!("Failed to generate identity: {}", e))?;
 
     // Create clipboard manager
-    let clipboard =
+    let mut clipboard =
         ClipboardManager::new().map_err(|e| format!("Failed to initialize clipboard: {}", e))?;
+    // Whatever was copied before startup is not a new copy to sync
+    clipboard.seed();
 
+    let conflicts = ConflictResolver::new(*identity.device_id());
+    let scratchpad = Arc::new(Scratchpad::new(*identity.device_id()));
+    let mut pending_events: VecDeque<TossEvent> = storage_warning
+        .map(|message| TossEvent::StorageDegraded { message })
+        .into_iter()
+        .collect();
+    if let Some(recovery) = storage.recovery() {
+        pending_events.push_back(TossEvent::StorageRecovered {
+            backup_path: recovery.backup_path.to_string_lossy().into_owned(),
+            data_lost: !recovery.lost_tables.is_empty(),
+        });
+    }
     let core = TossCore {
         identity: Arc::new(identity),
         device_name,
         clipboard,
         network: None,
         pairing_session: None,
-        settings: TossSettings::default(),
+        pairing_advertiser: None,
+        pairing_search: Mutex::new(None),
+        send_cancel: Mutex::new(CancellationToken::new()),
+        last_skip: Mutex::new(None),
+        relay_caps: Mutex::new(RelayCaps::default()),
+        settings,
+        denylist,
         storage,
         event_receiver: None,
+        pending_events: Mutex::new(pending_events),
+        conflicts,
+        scratchpad,
+        presence: PresenceThrottle::default(),
+        screen_locked: false,
+        quiet_hours_queue: QuietHoursQueue::new(),
+        utc_offset: chrono::FixedOffset::east_opt(0).expect("zero offset"),
         last_sync_time: std::sync::Mutex::new(std::time::Instant::now()),
+        pairing_trust: PairingTrust::new(),
+        pending_pairing: Mutex::new(None),
+        history_prune_task: None,
     };
 
     *TOSS_INSTANCE.write() = Some(core);
@@ -185,17 +799,120 @@ pub fn init_toss(data_dir: String, device_name: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Allow or forbid the in-memory storage fallback for later `init_toss` calls
+///
+/// Enabled by default. When disabled, `init_toss` fails if the database
+/// can't be opened.
+#[frb(sync)]
+pub fn set_storage_fallback_enabled(enabled: bool) {
+    STORAGE_FALLBACK.store(enabled, Ordering::SeqCst);
+}
+
+/// Check whether storage is persisted, or in memory after a failed open
+#[frb(sync)]
+pub fn is_storage_persistent() -> Result<bool, String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+    Ok(!core.storage.is_in_memory())
+}
+
+/// Open the database, or in-memory storage if that fails and `allow_fallback`
+///
+/// Returns a warning to surface to the user when falling back.
+fn open_storage(
+    db_path: &std::path::Path,
+    allow_fallback: bool,
+) -> Result<(Storage, Option<String>), String> {
+    let error = match Storage::new(db_path) {
+        Ok(storage) => return Ok((storage, None)),
+        Err(e) => format!("Failed to initialize storage: {}", e),
+    };
+    if !allow_fallback {
+        return Err(error);
+    }
+
+    let storage =
+        Storage::in_memory().map_err(|e| format!("{}; in-memory fallback failed: {}", error, e))?;
+    let warning = format!(
+        "{}. Using temporary storage: paired devices, history and settings will be lost when the app closes.",
+        error
+    );
+    tracing::warn!("{}", warning);
+    Ok((storage, Some(warning)))
+}
+
 /// Shutdown Toss
 #[frb]
 pub async fn shutdown_toss() {
     // Extract network manager while holding lock, then release lock before await
     let network = {
         let mut guard = TOSS_INSTANCE.write();
-        guard.take().and_then(|mut core| core.network.take())
+        guard.take().and_then(|mut core| {
+            if let Some(task) = core.history_prune_task.take() {
+                task.abort();
+            }
+            std::mem::take(&mut *core.send_cancel.lock().unwrap()).cancel();
+            core.network.take()
+        })
     };
 
-    if let Some(mut network) = network {
-        network.stop().await;
+    if let Some(network) = network {
+        stop_reclaimed_network(network).await;
+    }
+}
+
+/// Securely wipe all local data ("reset / sign out everywhere")
+///
+/// Notifies connected paired devices of the removal, tears down networking,
+/// deletes devices, history and settings from the database, and overwrites and
+/// deletes the identity and storage keys from secure storage. The core is shut
+/// down first, so nothing can go on using the old identity; call `init_toss`
+/// to start over. Every step is attempted; any that fail are reported
+/// together in the error.
+#[frb]
+pub async fn wipe_all_data() -> Result<(), String> {
+    // Take the core out while holding the lock; later calls find it uninitialized
+    let mut core = TOSS_INSTANCE.write().take().ok_or("Toss not initialized")?;
+    if let Some(task) = core.history_prune_task.take() {
+        task.abort();
+    }
+    core.pairing_trust.revoke();
+    std::mem::take(&mut *core.send_cancel.lock().unwrap()).cancel();
+    // History this writes out is deleted by the wipe below
+    let _ = core
+        .storage
+        .set_history_write_window(crate::storage::DEFAULT_HISTORY_WRITE_WINDOW);
+    let device_id = *core.identity.device_id();
+
+    let mut failures = Vec::new();
+
+    if let Some(network) = core.network.take() {
+        let message = Message::DeviceRemoved(DeviceRemoved { device_id });
+        for peer in network.connected_peers() {
+            if let Err(e) = network.send_to_peer(&peer.device_id, &message).await {
+                failures.push(format!(
+                    "notify {}: {}",
+                    hex::encode(&peer.device_id[..8]),
+                    e
+                ));
+            }
+        }
+        stop_reclaimed_network(network).await;
+    }
+
+    if let Err(e) = core.storage.wipe_all() {
+        failures.push(format!("database: {}", e));
+    }
+    drop(core);
+
+    if let Err(e) = wipe_key_material() {
+        failures.push(format!("keystore: {}", e));
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Wipe incomplete: {}", failures.join("; ")))
     }
 }
 
@@ -213,6 +930,16 @@ pub fn get_device_id() -> String {
         .unwrap_or_default()
 }
 
+/// Get this device's key fingerprint, as paired devices show it
+#[frb(sync)]
+pub fn get_device_fingerprint() -> String {
+    TOSS_INSTANCE
+        .read()
+        .as_ref()
+        .map(|core| DeviceIdentity::fingerprint_for(&core.identity.public_key()))
+        .unwrap_or_default()
+}
+
 /// Get device name
 #[frb(sync)]
 pub fn get_device_name() -> String {
@@ -223,26 +950,142 @@ pub fn get_device_name() -> String {
         .unwrap_or_default()
 }
 
+/// Get the limits and formats the core enforces
+///
+/// Available before `init_toss`.
+#[frb(sync)]
+pub fn get_limits() -> LimitsDto {
+    LimitsDto {
+        max_message_size: crate::protocol::MAX_MESSAGE_SIZE as u64,
+        max_preview_size: crate::protocol::MAX_PREVIEW_SIZE as u64,
+        protocol_version: crate::PROTOCOL_VERSION as u32,
+        pairing_code_length: PAIRING_CODE_LENGTH as u32,
+        pairing_code_format: format!("^[0-9]{{{}}}$", PAIRING_CODE_LENGTH),
+        max_device_name_length: MAX_DEVICE_NAME_LEN as u32,
+    }
+}
+
 /// Set device name
 #[frb(sync)]
 pub fn set_device_name(name: String) -> Result<(), String> {
-    // Validate device name
-    let name = name.trim();
-    if name.is_empty() {
-        return Err("Device name cannot be empty".to_string());
-    }
-    if name.len() > 100 {
-        return Err("Device name too long (max 100 characters)".to_string());
-    }
+    let name = normalize_device_name(&name)?;
 
     if let Some(ref mut core) = *TOSS_INSTANCE.write() {
-        core.device_name = name.to_string();
+        core.device_name = name;
         Ok(())
     } else {
         Err("Toss not initialized".to_string())
     }
 }
 
+/// Replace this device's identity key, keeping pairings where possible
+///
+/// For recovering from a compromised identity key. The new key is stored in
+/// secure storage, then every connected paired device that supports it is
+/// sent an `IdentityMigration` signed by the old key, so it can move the
+/// pairing over to the new key. Devices that are offline or too old must be
+/// paired again and are listed in `needs_repair`. Networking is restarted
+/// under the new identity if it was running.
+#[frb]
+pub async fn rotate_identity() -> Result<IdentityRotationDto, String> {
+    let new_identity =
+        DeviceIdentity::generate().map_err(|e| format!("Failed to generate identity: {}", e))?;
+    store_identity_key(&new_identity.to_tagged_bytes())
+        .map_err(|e| format!("Failed to store identity key: {}", e))?;
+
+    let (network, old_identity, paired) = {
+        let mut guard = TOSS_INSTANCE.write();
+        let core = guard.as_mut().ok_or("Toss not initialized")?;
+        let paired: Vec<String> = core
+            .storage
+            .devices()
+            .get_all_devices()
+            .map_err(|e| format!("Failed to get devices: {}", e))?
+            .into_iter()
+            .filter(|device| device.is_active)
+            .map(|device| device.id)
+            .collect();
+        (core.network.take(), core.identity.clone(), paired)
+    };
+
+    let restart_network = network.is_some();
+    let mut migrated = Vec::new();
+    if let Some(network) = network {
+        let message =
+            Message::IdentityMigration(IdentityMigration::new(&old_identity, &new_identity));
+        for peer in network.connected_peers() {
+            let supported = network
+                .peer_capabilities(&peer.device_id)
+                .is_some_and(|caps| caps.contains(Capabilities::IDENTITY_MIGRATION));
+            if !supported {
+                continue;
+            }
+            match network.send_to_peer(&peer.device_id, &message).await {
+                Ok(()) => migrated.push(hex::encode(peer.device_id)),
+                Err(e) => tracing::warn!(
+                    "Failed to send identity migration to {}: {}",
+                    hex::encode(peer.device_id),
+                    e
+                ),
+            }
+        }
+        stop_reclaimed_network(network).await;
+    }
+
+    let device_id = new_identity.device_id_hex();
+    {
+        let mut guard = TOSS_INSTANCE.write();
+        let core = guard.as_mut().ok_or("Toss not initialized")?;
+        core.conflicts = ConflictResolver::new(*new_identity.device_id());
+        core.scratchpad.set_device_id(*new_identity.device_id());
+        core.identity = Arc::new(new_identity);
+        core.event_receiver = None;
+    }
+
+    if restart_network {
+        if let Err(e) = start_network().await {
+            tracing::warn!("Failed to restart network after identity rotation: {}", e);
+            if let Some(core) = TOSS_INSTANCE.read().as_ref() {
+                core.pending_events
+                    .lock()
+                    .unwrap()
+                    .push_back(TossEvent::Error { message: e });
+            }
+        }
+    }
+
+    let needs_repair = paired
+        .into_iter()
+        .filter(|id| !migrated.contains(id))
+        .collect();
+    Ok(IdentityRotationDto {
+        device_id,
+        migrated,
+        needs_repair,
+    })
+}
+
+/// Trim and validate a device name
+///
+/// Used for our own name and for every paired device, so names stay
+/// displayable and fit in an mDNS TXT value, which is limited in bytes.
+fn normalize_device_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Device name cannot be empty".to_string());
+    }
+    if name.len() > MAX_DEVICE_NAME_LEN {
+        return Err(format!(
+            "Device name too long (max {} bytes)",
+            MAX_DEVICE_NAME_LEN
+        ));
+    }
+    if name.chars().any(char::is_control) {
+        return Err("Device name cannot contain control characters".to_string());
+    }
+    Ok(name.to_string())
+}
+
 // ============================================================================
 // Pairing
 // ============================================================================
@@ -257,6 +1100,7 @@ pub fn start_pairing() -> Result<PairingInfoDto, String> {
     let info = session.info(&core.device_name);
 
     core.pairing_session = Some(session);
+    core.pairing_advertiser = None;
 
     Ok(PairingInfoDto {
         code: info.code,
@@ -275,6 +1119,41 @@ pub struct PairingInfoDto {
     pub public_key: String,
 }
 
+/// Pairing QR preview for Flutter
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PairingQrInfoDto {
+    pub code: String,
+    pub public_key: String,
+    pub device_name: String,
+    pub device_id: String,
+    pub expires_at: Option<u64>,
+}
+
+/// Parse and validate scanned QR data without touching any pairing session
+#[frb(sync)]
+pub fn parse_pairing_qr(qr_data: String) -> Result<PairingQrInfoDto, String> {
+    let qr_data = qr_data.trim();
+    if qr_data.is_empty() {
+        return Err("QR data cannot be empty".to_string());
+    }
+    if qr_data.len() > 1000 {
+        return Err("QR data too long (max 1000 characters)".to_string());
+    }
+
+    let payload = parse_qr_data(qr_data).map_err(|e| format!("Invalid pairing QR: {}", e))?;
+    let public_key = payload
+        .public_key_bytes()
+        .map_err(|e| format!("Invalid pairing QR: {}", e))?;
+
+    Ok(PairingQrInfoDto {
+        code: payload.code,
+        public_key: payload.pk,
+        device_name: payload.name,
+        device_id: paired_device_id(&public_key),
+        expires_at: payload.exp,
+    })
+}
+
 /// Complete pairing with QR data
 #[frb(sync)]
 pub fn complete_pairing_qr(qr_data: String) -> Result<DeviceInfoDto, String> {
@@ -287,6 +1166,10 @@ pub fn complete_pairing_qr(qr_data: String) -> Result<DeviceInfoDto, String> {
         return Err("QR data too long (max 1000 characters)".to_string());
     }
 
+    // Check the advertised name before the pairing session is consumed
+    let payload = parse_qr_data(qr_data).map_err(|e| format!("Pairing failed: {}", e))?;
+    let device_name = normalize_device_name(&payload.name)?;
+
     let mut guard = TOSS_INSTANCE.write();
     let core = guard.as_mut().ok_or("Toss not initialized")?;
 
@@ -295,130 +1178,94 @@ pub fn complete_pairing_qr(qr_data: String) -> Result<DeviceInfoDto, String> {
         .take()
         .ok_or("No active pairing session")?;
 
-    let (session_key, device_name, public_key_base64) = session
+    let completed = session
         .complete_from_qr(qr_data)
         .map_err(|e| format!("Pairing failed: {}", e))?;
 
-    // Decode public key from base64
-    let public_key = base64::Engine::decode(
-        &base64::engine::general_purpose::STANDARD,
-        &public_key_base64,
-    )
-    .map_err(|e| format!("Invalid public key: {}", e))?;
+    store_completed_pairing(core, completed, device_name)
+}
 
-    // Derive device ID from public key hash
-    let device_id = hex::encode(&Sha256::digest(&public_key)[..16]);
+/// Complete pairing with manual code
+///
+/// `peer_device_name` is the name the peer advertised, if known; otherwise
+/// the device is stored as "Paired Device".
+#[frb(sync)]
+pub fn complete_pairing_code(
+    code: String,
+    peer_public_key: Vec<u8>,
+    peer_device_name: Option<String>,
+) -> Result<DeviceInfoDto, String> {
+    let device_name = match peer_device_name {
+        Some(name) => normalize_device_name(&name)?,
+        None => DEFAULT_PAIRED_DEVICE_NAME.to_string(),
+    };
 
-    // Encrypt session key before storing
-    let encrypted_session_key = {
-        let storage_key = derive_key(
-            core.identity.device_id() as &[u8],
-            DerivedKeyPurpose::StorageEncryption,
-            Some(b"toss-session-key-v1"),
-        )
-        .map_err(|e| format!("Failed to derive storage key: {}", e))?;
+    let mut guard = TOSS_INSTANCE.write();
+    let core = guard.as_mut().ok_or("Toss not initialized")?;
 
-        let aad = format!("session:{}", device_id).into_bytes();
-        let encrypted = encrypt(&storage_key, &session_key, &aad)
-            .map_err(|e| format!("Failed to encrypt session key: {}", e))?;
-        Some(encrypted.to_bytes())
-    };
-
-    // Store the paired device
-    let stored_device = StoredDevice {
-        id: device_id.clone(),
-        name: device_name.clone(),
-        public_key,
-        session_key: encrypted_session_key,
-        last_seen: None,
-        created_at: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
-        is_active: true,
-        platform: Some(format!("{:?}", crate::protocol::Platform::current()).to_lowercase()),
-    };
-
-    core.storage
-        .devices()
-        .store_device(&stored_device)
-        .map_err(|e| format!("Failed to store device: {}", e))?;
-
-    Ok(DeviceInfoDto {
-        id: device_id,
-        name: device_name,
-        is_online: false,
-        last_seen: 0,
-        platform: format!("{:?}", crate::protocol::Platform::current()).to_lowercase(),
-    })
-}
-
-/// Complete pairing with manual code
-#[frb(sync)]
-pub fn complete_pairing_code(
-    code: String,
-    peer_public_key: Vec<u8>,
-) -> Result<DeviceInfoDto, String> {
-    let mut guard = TOSS_INSTANCE.write();
-    let core = guard.as_mut().ok_or("Toss not initialized")?;
-
-    let session = core
-        .pairing_session
-        .take()
-        .ok_or("No active pairing session")?;
+    let session = core
+        .pairing_session
+        .take()
+        .ok_or("No active pairing session")?;
 
     let peer_key: [u8; 32] = peer_public_key
         .try_into()
         .map_err(|_| "Invalid public key length")?;
 
-    let session_key = session
+    let completed = session
         .complete(&peer_key, &code)
         .map_err(|e| format!("Pairing failed: {}", e))?;
 
-    // Derive device ID from public key
-    let device_id = hex::encode(&sha2::Sha256::digest(&peer_key)[..16]);
+    store_completed_pairing(core, completed, device_name)
+}
 
-    // Encrypt session key before storing
-    let encrypted_session_key = {
-        let storage_key = derive_key(
-            core.identity.device_id() as &[u8],
-            DerivedKeyPurpose::StorageEncryption,
-            Some(b"toss-session-key-v1"),
-        )
-        .map_err(|e| format!("Failed to derive storage key: {}", e))?;
+/// Rotate the active pairing session's code and expiry, keeping its public key
+///
+/// The old code is withdrawn from mDNS and the relay server through the
+/// coordinator that advertised it, and the new one advertised, so a QR code
+/// that was already scanned keeps working.
+#[frb]
+pub async fn refresh_pairing_code() -> Result<PairingInfoDto, String> {
+    let (old_code, info, public_key, coordinator) = {
+        let mut guard = TOSS_INSTANCE.write();
+        let core = guard.as_mut().ok_or("Toss not initialized")?;
+        let device_name = core.device_name.clone();
+        let coordinator = pairing_advertiser(core)?;
 
-        let aad = format!("session:{}", device_id).into_bytes();
-        let encrypted = encrypt(&storage_key, &session_key, &aad)
-            .map_err(|e| format!("Failed to encrypt session key: {}", e))?;
-        Some(encrypted.to_bytes())
-    };
+        let session = core
+            .pairing_session
+            .as_mut()
+            .ok_or("No active pairing session")?;
 
-    // Store the paired device
-    let stored_device = StoredDevice {
-        id: device_id.clone(),
-        name: "Paired Device".to_string(),
-        public_key: peer_key.to_vec(),
-        session_key: encrypted_session_key,
-        last_seen: None,
-        created_at: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
-        is_active: true,
-        platform: Some(format!("{:?}", crate::protocol::Platform::current()).to_lowercase()),
+        let old_code = session.code().to_string();
+        session.refresh_code();
+
+        (
+            old_code,
+            session.info(&device_name),
+            *session.public_key_bytes(),
+            coordinator,
+        )
     };
 
-    core.storage
-        .devices()
-        .store_device(&stored_device)
-        .map_err(|e| format!("Failed to store device: {}", e))?;
+    let result = coordinator
+        .refresh_advertisement(&old_code, &info.code, &public_key)
+        .await;
+    if result.relay_registered {
+        tokio::spawn(watch_relay_pairing(coordinator, info.code.clone()));
+    } else if !result.mdns_registered {
+        tracing::warn!(
+            "Refreshed pairing code is not advertised: mDNS: {:?}, relay: {:?}",
+            result.mdns_error,
+            result.relay_error
+        );
+    }
 
-    Ok(DeviceInfoDto {
-        id: device_id,
-        name: "Paired Device".to_string(),
-        is_online: false,
-        last_seen: 0,
-        platform: format!("{:?}", crate::protocol::Platform::current()).to_lowercase(),
+    Ok(PairingInfoDto {
+        code: info.code,
+        qr_data: info.qr_data,
+        expires_at: info.expires_at,
+        public_key: info.public_key,
     })
 }
 
@@ -427,7 +1274,96 @@ pub fn complete_pairing_code(
 pub fn cancel_pairing() {
     if let Some(ref mut core) = *TOSS_INSTANCE.write() {
         core.pairing_session = None;
+        core.pairing_advertiser = None;
+        *core.pending_pairing.lock().unwrap() = None;
+    }
+}
+
+/// Abort the `find_pairing_device` search in progress, if any
+#[frb(sync)]
+pub fn cancel_find_pairing() {
+    if let Some(ref core) = *TOSS_INSTANCE.read() {
+        if let Some(cancel) = core.pairing_search.lock().unwrap().take() {
+            cancel.cancel();
+        }
+    }
+}
+
+/// Accept or reject the relay pairing awaiting confirmation
+///
+/// Accepting stores the device and returns it, as pairing without
+/// confirmation would have. Rejecting ends the pairing session, so its code
+/// can't be completed again.
+#[frb(sync)]
+pub fn confirm_pairing(accept: bool) -> Result<Option<DeviceInfoDto>, String> {
+    let pending = {
+        let mut guard = TOSS_INSTANCE.write();
+        let core = guard.as_mut().ok_or("Toss not initialized")?;
+        let pending = core
+            .pending_pairing
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or("No pairing is awaiting confirmation")?;
+        if !accept {
+            core.pairing_session = None;
+            return Ok(None);
+        }
+        pending
+    };
+
+    if !pairing_code_active(&pending.code) {
+        return Err("Pairing request expired".to_string());
+    }
+    complete_manual_pairing(pending.public_key, pending.device_name).map(Some)
+}
+
+/// Pair with relay pairing requests without asking for a while
+///
+/// Lasts `duration_secs`, or an hour if not given. It ends early with
+/// `revoke_pairing_trust` or a restart; use
+/// `set_device_auto_accept_pairing` to trust a device for good. Returns
+/// when it ends, in Unix seconds.
+#[frb(sync)]
+pub fn trust_pairing_requests(duration_secs: Option<u32>) -> Result<u64, String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    let duration = duration_secs
+        .map(|secs| std::time::Duration::from_secs(secs as u64))
+        .unwrap_or(DEFAULT_TRUST_WINDOW);
+    core.pairing_trust
+        .trust_for(duration, std::time::Instant::now());
+    Ok(unix_now_secs() + duration.as_secs())
+}
+
+/// End the window opened by `trust_pairing_requests`
+#[frb(sync)]
+pub fn revoke_pairing_trust() {
+    if let Some(ref core) = *TOSS_INSTANCE.read() {
+        core.pairing_trust.revoke();
+    }
+}
+
+/// Pair with a paired device again without asking, or stop doing so
+///
+/// Stored with the device, so it lasts across restarts. Only requests
+/// presenting the device's public key are accepted; a device pairing with a
+/// new key is asked about again.
+#[frb(sync)]
+pub fn set_device_auto_accept_pairing(device_id: String, enabled: bool) -> Result<(), String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    let updated = core
+        .storage
+        .devices()
+        .set_auto_accept_pairing(&device_id, enabled)
+        .map_err(|e| format!("Failed to update device: {}", e))?;
+    if !updated {
+        return Err(format!("Device {} is not paired", device_id));
     }
+    Ok(())
 }
 
 /// Pairing device info returned from find_pairing_device
@@ -452,30 +1388,58 @@ pub struct AdvertisementResultDto {
     pub relay_error: Option<String>,
 }
 
+impl From<crate::pairing::AdvertisementResult> for AdvertisementResultDto {
+    fn from(result: crate::pairing::AdvertisementResult) -> Self {
+        Self {
+            mdns_registered: result.mdns_registered,
+            relay_registered: result.relay_registered,
+            mdns_error: result.mdns_error,
+            relay_error: result.relay_error,
+        }
+    }
+}
+
 /// Find a device by pairing code (searches mDNS and relay server)
+///
+/// Fails with "Pairing search cancelled" if `cancel_find_pairing` is called
+/// before the search finishes. Starting a new search cancels the previous one.
 #[frb]
 pub async fn find_pairing_device(code: String) -> Result<PairingDeviceDto, String> {
     // Validate code format
-    if code.len() != 6 || !code.chars().all(|c| c.is_ascii_digit()) {
-        return Err("Pairing code must be 6 digits".to_string());
+    if !is_valid_pairing_code(&code) {
+        return Err(format!(
+            "Pairing code must be {} digits",
+            PAIRING_CODE_LENGTH
+        ));
     }
 
-    // Get relay URL and device name from settings
-    let (relay_url, device_name) = {
+    // Get relay URL, device name and service type from settings
+    let cancel = CancellationToken::new();
+    let config = {
         let guard = TOSS_INSTANCE.read();
         let core = guard.as_ref().ok_or("Toss not initialized")?;
-        (core.settings.relay_url.clone(), core.device_name.clone())
+        if let Some(previous) = core.pairing_search.lock().unwrap().replace(cancel.clone()) {
+            previous.cancel();
+        }
+        network_config(core)
     };
 
     // Create pairing coordinator
-    let coordinator = crate::pairing::PairingCoordinator::new(&device_name, relay_url)
-        .map_err(|e| format!("Failed to create pairing coordinator: {}", e))?;
+    let coordinator = crate::pairing::PairingCoordinator::new_with_service_type(
+        &config.device_name,
+        config.relay_url.clone(),
+        &config.pairing_service_type(),
+    )
+    .map_err(|e| format!("Failed to create pairing coordinator: {}", e))?;
 
     // Find device
     let device_info = coordinator
-        .find_device(&code)
+        .find_device_cancellable(&code, &cancel)
         .await
-        .map_err(|e| format!("Failed to find device: {}", e))?;
+        .map_err(|e| match e {
+            crate::error::NetworkError::Cancelled => "Pairing search cancelled".to_string(),
+            e => format!("Failed to find device: {}", e),
+        })?;
 
     // Encode public key as base64
     let public_key = base64::Engine::encode(
@@ -509,6 +1473,8 @@ pub fn complete_manual_pairing(
     let mut peer_key = [0u8; 32];
     peer_key.copy_from_slice(&public_key_bytes);
 
+    let peer_device_name = normalize_device_name(&peer_device_name)?;
+
     let mut guard = TOSS_INSTANCE.write();
     let core = guard.as_mut().ok_or("Toss not initialized")?;
 
@@ -519,65 +1485,99 @@ pub fn complete_manual_pairing(
         .unwrap_or_else(|| PairingSession::new(&core.device_name));
 
     // Complete the pairing using X25519 key exchange
-    let session_key = session
+    let completed = session
         .complete_with_peer_key(&peer_key)
         .map_err(|e| format!("Pairing failed: {}", e))?;
 
-    // Derive device ID from public key
-    let device_id = hex::encode(&Sha256::digest(&peer_key)[..16]);
+    store_completed_pairing(core, completed, peer_device_name)
+}
 
-    // Encrypt session key before storing
-    let encrypted_session_key = {
-        let storage_key = derive_key(
-            core.identity.device_id() as &[u8],
-            DerivedKeyPurpose::StorageEncryption,
-            Some(b"toss-session-key-v1"),
-        )
-        .map_err(|e| format!("Failed to derive storage key: {}", e))?;
+/// Store the device from a completed pairing under `device_name`
+///
+/// The session key is encrypted with the storage key before it's written.
+fn store_completed_pairing(
+    core: &TossCore,
+    completed: CompletedPairing,
+    device_name: String,
+) -> Result<DeviceInfoDto, String> {
+    let storage_key = derive_key(
+        core.identity.device_id() as &[u8],
+        DerivedKeyPurpose::StorageEncryption,
+        Some(b"toss-session-key-v1"),
+    )
+    .map_err(|e| format!("Failed to derive storage key: {}", e))?;
 
-        let aad = format!("session:{}", device_id).into_bytes();
-        let encrypted = encrypt(&storage_key, &session_key, &aad)
-            .map_err(|e| format!("Failed to encrypt session key: {}", e))?;
-        Some(encrypted.to_bytes())
-    };
+    let aad = format!("session:{}", completed.device_id).into_bytes();
+    let encrypted_session_key = encrypt(&storage_key, &completed.session_key, &aad)
+        .map_err(|e| format!("Failed to encrypt session key: {}", e))?;
 
-    // Store the paired device
     let stored_device = StoredDevice {
-        id: device_id.clone(),
-        name: peer_device_name.clone(),
-        public_key: peer_key.to_vec(),
-        session_key: encrypted_session_key,
+        id: completed.device_id.clone(),
+        advertised_name: device_name.clone(),
+        public_key: completed.public_key.to_vec(),
+        session_key: Some(encrypted_session_key.to_bytes()),
         last_seen: None,
         created_at: std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs(),
         is_active: true,
-        platform: Some("unknown".to_string()), // Platform not available from pairing info
+        platform: completed.platform.clone(),
+        local_nickname: None,
     };
 
-    core.storage
-        .devices()
-        .store_device(&stored_device)
-        .map_err(|e| format!("Failed to store device: {}", e))?;
+    store_paired_device(core, &stored_device)?;
 
     Ok(DeviceInfoDto {
-        id: device_id,
-        name: peer_device_name,
+        id: completed.device_id,
+        name: device_name,
         is_online: false,
         last_seen: 0,
-        platform: "unknown".to_string(),
+        platform: completed.platform.unwrap_or_else(|| "unknown".to_string()),
     })
 }
 
+/// Store a newly paired device, applying the paired device limit from settings
+fn store_paired_device(core: &TossCore, device: &StoredDevice) -> Result<(), String> {
+    let max_devices = core.settings.max_paired_devices;
+    let policy = if core.settings.evict_oldest_device {
+        DeviceLimitPolicy::EvictOldest
+    } else {
+        DeviceLimitPolicy::Reject
+    };
+
+    let outcome = core
+        .storage
+        .devices()
+        .store_device_with_limit(device, max_devices as usize, policy)
+        .map_err(|e| format!("Failed to store device: {}", e))?;
+
+    match outcome {
+        StoreDeviceOutcome::Stored { evicted } => {
+            for device_id in evicted {
+                tracing::info!(
+                    "Evicted device {} to stay within the limit of {} paired devices",
+                    device_id,
+                    max_devices
+                );
+            }
+            Ok(())
+        }
+        StoreDeviceOutcome::LimitReached => Err(format!(
+            "Maximum of {} paired devices reached; remove a device before pairing another",
+            max_devices
+        )),
+    }
+}
+
 /// Register pairing code on relay server and via mDNS
 /// Returns the result indicating which methods succeeded/failed
 #[frb]
 pub async fn register_pairing_advertisement() -> Result<AdvertisementResultDto, String> {
-    // Get current pairing session, relay URL, and device name
-    let (code, public_key, relay_url, device_name) = {
-        let guard = TOSS_INSTANCE.read();
-        let core = guard.as_ref().ok_or("Toss not initialized")?;
+    // Get current pairing session and the coordinator advertising it
+    let (code, public_key, coordinator) = {
+        let mut guard = TOSS_INSTANCE.write();
+        let core = guard.as_mut().ok_or("Toss not initialized")?;
 
         let session = core
             .pairing_session
@@ -592,31 +1592,199 @@ pub async fn register_pairing_advertisement() -> Result<AdvertisementResultDto,
         let mut pk = [0u8; 32];
         pk.copy_from_slice(&public_key_bytes);
 
-        (
-            info.code,
-            pk,
-            core.settings.relay_url.clone(),
-            core.device_name.clone(),
+        (info.code, pk, pairing_advertiser(core)?)
+    };
+
+    // Each channel reports its own outcome; one failing doesn't fail the call
+    let result = coordinator.start_advertisement(&code, &public_key).await;
+
+    // Finders on other networks pair through the relay, so wait for them there
+    if result.relay_registered {
+        tokio::spawn(watch_relay_pairing(coordinator, code));
+    }
+
+    Ok(result.into())
+}
+
+/// The coordinator advertising the pairing session, created on first use
+///
+/// mDNS registrations belong to the daemon that made them, so withdrawing a
+/// code has to go through the coordinator that advertised it.
+fn pairing_advertiser(
+    core: &mut TossCore,
+) -> Result<Arc<crate::pairing::PairingCoordinator>, String> {
+    if let Some(coordinator) = &core.pairing_advertiser {
+        return Ok(coordinator.clone());
+    }
+
+    let config = network_config(core);
+    let coordinator = Arc::new(
+        crate::pairing::PairingCoordinator::new_with_service_type(
+            &config.device_name,
+            config.relay_url.clone(),
+            &config.pairing_service_type(),
         )
+        .map_err(|e| format!("Failed to create pairing coordinator: {}", e))?
+        .with_registration_retry(config.mdns_registration_retry),
+    );
+    core.pairing_advertiser = Some(coordinator.clone());
+    Ok(coordinator)
+}
+
+/// Complete pairing with a device found via the relay server and notify it
+///
+/// Like `complete_manual_pairing`, but also tells the advertiser of `code`
+/// which key we paired with so it can finish pairing on its side.
+#[frb]
+pub async fn complete_relay_pairing(
+    code: String,
+    peer_public_key: String,
+    peer_device_name: String,
+) -> Result<DeviceInfoDto, String> {
+    let (public_key, config) = {
+        let mut guard = TOSS_INSTANCE.write();
+        let core = guard.as_mut().ok_or("Toss not initialized")?;
+        let device_name = core.device_name.clone();
+        let session = core
+            .pairing_session
+            .get_or_insert_with(|| PairingSession::new(&device_name));
+        (*session.public_key_bytes(), network_config(core))
     };
 
-    // Create pairing coordinator and start advertisement
-    let coordinator = crate::pairing::PairingCoordinator::new(&device_name, relay_url)
-        .map_err(|e| format!("Failed to create pairing coordinator: {}", e))?;
+    let device = complete_manual_pairing(peer_public_key, peer_device_name)?;
 
-    let result = coordinator
-        .start_advertisement(&code, &public_key)
-        .await
-        .map_err(|e| format!("Failed to start advertisement: {}", e))?;
+    let coordinator = crate::pairing::PairingCoordinator::new_with_service_type(
+        &config.device_name,
+        config.relay_url.clone(),
+        &config.pairing_service_type(),
+    )
+    .map_err(|e| format!("Failed to create pairing coordinator: {}", e))?;
+
+    if let Err(e) = coordinator.notify_completion(&code, &public_key).await {
+        tracing::warn!("Failed to notify pairing advertiser: {}", e);
+    }
+
+    Ok(device)
+}
+
+/// Wait on the relay server for a finder to complete pairing with `code`
+///
+/// Pairs with the finder's key, reports it as a `PairingRequest` event and
+/// withdraws the advertisement of `code`. Stops once the pairing session is
+/// cancelled, its code rotates, or it expires.
+async fn watch_relay_pairing(coordinator: Arc<crate::pairing::PairingCoordinator>, code: String) {
+    while pairing_code_active(&code) {
+        match coordinator
+            .wait_for_completion(&code, RELAY_PAIRING_POLL_SECS)
+            .await
+        {
+            Ok(Some(peer)) => {
+                if !pairing_code_active(&code) {
+                    break;
+                }
+                push_event(relay_pairing_event(
+                    &code,
+                    peer.public_key,
+                    peer.device_name,
+                ));
+                break;
+            }
+            Ok(None) => {}
+            Err(crate::error::NetworkError::Discovery(_)) => break,
+            Err(e) => {
+                tracing::warn!("Failed to wait for relay pairing: {}", e);
+                tokio::time::sleep(RELAY_PAIRING_RETRY_DELAY).await;
+            }
+        }
+    }
+
+    // After a rotation the coordinator advertises the new code; leave it be
+    coordinator.withdraw_advertisement(&code).await;
+}
+
+/// Pair with a finder that completed `code` on the relay, or ask first
+///
+/// With `confirm_pairing` on, the pairing is held for `confirm_pairing`
+/// unless pairing requests are trusted right now or the finder's public key
+/// belongs to a device marked to auto-accept.
+fn relay_pairing_event(code: &str, public_key: [u8; 32], device_name: String) -> TossEvent {
+    let encoded = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, public_key);
+    let held = {
+        let guard = TOSS_INSTANCE.read();
+        guard.as_ref().and_then(|core| {
+            hold_unconfirmed_pairing(core, code, &public_key, &encoded, &device_name)
+        })
+    };
+    if let Some(device) = held {
+        return TossEvent::PairingConfirmationRequested { device };
+    }
+
+    match complete_manual_pairing(encoded, device_name) {
+        Ok(device) => TossEvent::PairingRequest { device },
+        Err(e) => TossEvent::Error {
+            message: format!("Relay pairing failed: {}", e),
+        },
+    }
+}
+
+/// Hold a relay pairing that needs confirmation, returning the device to ask about
+///
+/// Returns `None` if it can go ahead without asking.
+fn hold_unconfirmed_pairing(
+    core: &TossCore,
+    code: &str,
+    public_key: &[u8; 32],
+    encoded_key: &str,
+    device_name: &str,
+) -> Option<DeviceInfoDto> {
+    // An invalid name fails pairing itself, which reports it
+    let name = normalize_device_name(device_name).ok()?;
+    let auto_accept = core
+        .storage
+        .devices()
+        .auto_accepts_pairing_from(public_key)
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to check pairing auto-accept: {}", e);
+            false
+        });
+    if !core.pairing_trust.needs_confirmation(
+        core.settings.confirm_pairing,
+        auto_accept,
+        std::time::Instant::now(),
+    ) {
+        return None;
+    }
 
-    Ok(AdvertisementResultDto {
-        mdns_registered: result.mdns_registered,
-        relay_registered: result.relay_registered,
-        mdns_error: result.mdns_error,
-        relay_error: result.relay_error,
+    *core.pending_pairing.lock().unwrap() = Some(PendingPairing {
+        code: code.to_string(),
+        public_key: encoded_key.to_string(),
+        device_name: name.clone(),
+    });
+    Some(DeviceInfoDto {
+        id: paired_device_id(public_key),
+        name,
+        is_online: false,
+        last_seen: 0,
+        platform: "unknown".to_string(),
     })
 }
 
+/// Whether the current pairing session still uses `code` and hasn't expired
+fn pairing_code_active(code: &str) -> bool {
+    TOSS_INSTANCE
+        .read()
+        .as_ref()
+        .and_then(|core| core.pairing_session.as_ref())
+        .is_some_and(|session| session.code() == code && !session.is_expired())
+}
+
+/// Queue an event for `poll_event`
+fn push_event(event: TossEvent) {
+    if let Some(core) = TOSS_INSTANCE.read().as_ref() {
+        core.pending_events.lock().unwrap().push_back(event);
+    }
+}
+
 // ============================================================================
 // Device Management
 // ============================================================================
@@ -651,7 +1819,7 @@ pub fn get_paired_devices() -> Vec<DeviceInfoDto> {
         .into_iter()
         .map(|d| DeviceInfoDto {
             id: d.id.clone(),
-            name: d.name,
+            name: d.display_name().to_string(),
             is_online: connected_device_ids.contains(&d.id),
             last_seen: d.last_seen.unwrap_or(0),
             platform: d.platform.unwrap_or_else(|| "unknown".to_string()),
@@ -659,6 +1827,109 @@ pub fn get_paired_devices() -> Vec<DeviceInfoDto> {
         .collect()
 }
 
+/// Check which paired devices are reachable, without connecting to them
+///
+/// Looks for each device on the local network and asks the relay whether
+/// it's online, giving up after a couple of seconds. Every device is
+/// unreachable while networking is stopped.
+#[frb]
+pub async fn probe_paired_devices() -> Vec<DeviceReachabilityDto> {
+    let (stored_devices, network) = {
+        let guard = TOSS_INSTANCE.read();
+        let Some(core) = guard.as_ref() else {
+            return Vec::new();
+        };
+        let stored_devices = core.storage.devices().get_all_devices().unwrap_or_default();
+        (stored_devices, core.network.clone())
+    }; // Guard is dropped here
+
+    let device_ids: Vec<[u8; 32]> = stored_devices
+        .iter()
+        .filter_map(|d| hex::decode(&d.id).ok()?.try_into().ok())
+        .collect();
+    let reachability = match network {
+        Some(network) => {
+            network
+                .probe_reachability(&device_ids, DEFAULT_PROBE_TIMEOUT)
+                .await
+        }
+        None => Vec::new(),
+    };
+
+    stored_devices
+        .into_iter()
+        .map(|d| {
+            let path = reachability
+                .iter()
+                .find(|r| hex::encode(r.device_id) == d.id)
+                .and_then(|r| r.path);
+            DeviceReachabilityDto {
+                id: d.id.clone(),
+                name: d.display_name().to_string(),
+                reachable: path.is_some(),
+                path: path.map(|p| p.as_str().to_string()),
+            }
+        })
+        .collect()
+}
+
+/// Export a snapshot of the network state, for troubleshooting
+///
+/// Browses mDNS for a couple of seconds to list the devices on the local
+/// network.
+#[frb]
+pub async fn export_network_diagnostics() -> Result<NetworkDiagnosticsDto, String> {
+    let network = {
+        let guard = TOSS_INSTANCE.read();
+        let core = guard.as_ref().ok_or("Toss not initialized")?;
+        core.network.clone().ok_or("Network not started")?
+    }; // Guard is dropped here
+
+    let snapshot = network.diagnostics(DEFAULT_PROBE_TIMEOUT).await;
+
+    let addresses = |addrs: &[std::net::SocketAddr]| -> Vec<String> {
+        addrs.iter().map(|a| a.to_string()).collect()
+    };
+    Ok(NetworkDiagnosticsDto {
+        local_addr: snapshot.local_addr.map(|a| a.to_string()),
+        mdns_status: snapshot.mdns.as_str().to_string(),
+        discovered_peers: snapshot
+            .discovered_peers
+            .iter()
+            .map(|p| DiscoveredPeerDto {
+                device_id: p.device_id.clone(),
+                device_name: p.device_name.clone(),
+                addresses: addresses(&p.addresses),
+                version: p.version.clone(),
+            })
+            .collect(),
+        connected_peers: snapshot
+            .connected_peers
+            .iter()
+            .map(|p| ConnectedPeerDto {
+                device_id: hex::encode(p.device_id),
+                device_name: p.device_name.clone(),
+                addresses: addresses(&p.addresses),
+                transport: p.transport.to_string(),
+                route: if p.is_local { "lan" } else { "internet" }.to_string(),
+                rtt_ms: p.rtt_ms,
+            })
+            .collect(),
+        relay_status: snapshot.relay.as_str().to_string(),
+        relay_url: snapshot.relay_url,
+        nat_type: snapshot.nat_type.as_str().to_string(),
+        candidates: snapshot
+            .candidates
+            .iter()
+            .map(|c| IceCandidateDto {
+                candidate_type: c.candidate_type.as_str().to_string(),
+                address: c.address.to_string(),
+                priority: c.priority,
+            })
+            .collect(),
+    })
+}
+
 /// Remove a paired device
 #[frb(sync)]
 pub fn remove_device(device_id: String) -> Result<(), String> {
@@ -673,7 +1944,72 @@ pub fn remove_device(device_id: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Get the key algorithm, fingerprint and key state of a paired device
+#[frb(sync)]
+pub fn get_device_security_info(device_id: String) -> Result<DeviceSecurityDto, String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+    device_security_info(&core.storage, core.network.as_deref(), &device_id)
+}
+
+/// Record that the user compared a device's fingerprint and it matched
+///
+/// The mark is kept until the device's identity key changes.
+#[frb(sync)]
+pub fn mark_fingerprint_verified(device_id: String) -> Result<(), String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    let marked = core
+        .storage
+        .devices()
+        .mark_fingerprint_verified(&device_id)
+        .map_err(|e| format!("Failed to mark fingerprint verified: {}", e))?;
+    if !marked {
+        return Err(format!("Device {} is not paired", device_id));
+    }
+    Ok(())
+}
+
+/// Security details of a stored device, with key state from its connection if any
+fn device_security_info(
+    storage: &Storage,
+    network: Option<&NetworkManager>,
+    device_id: &str,
+) -> Result<DeviceSecurityDto, String> {
+    let devices = storage.devices();
+    let device = devices
+        .get_device(device_id)
+        .map_err(|e| format!("Failed to load device: {}", e))?
+        .filter(|device| device.is_active)
+        .ok_or_else(|| format!("Device {} is not paired", device_id))?;
+    let fingerprint_verified_at = devices
+        .fingerprint_verified_at(device_id)
+        .map_err(|e| format!("Failed to load device: {}", e))?;
+
+    let id: Option<[u8; 32]> = hex::decode(device_id).ok().and_then(|d| d.try_into().ok());
+    let connected = network.zip(id);
+    let key_algorithm = connected
+        .and_then(|(network, id)| network.peer_identity_algorithm(&id))
+        .unwrap_or_default();
+    let session_key_since = connected
+        .and_then(|(network, id)| network.session_key_since(&id))
+        .map(|since| since.timestamp().max(0) as u64);
+
+    Ok(DeviceSecurityDto {
+        device_id: device.id,
+        key_algorithm: key_algorithm.as_str().to_string(),
+        fingerprint: DeviceIdentity::fingerprint_for(&device.public_key),
+        fingerprint_verified: fingerprint_verified_at.is_some(),
+        fingerprint_verified_at,
+        session_key_since,
+        last_authenticated_at: device.last_seen,
+    })
+}
+
 /// Rename a paired device
+///
+/// Sets a local nickname; the name the device advertises is kept separately.
 #[frb(sync)]
 pub fn rename_device(device_id: String, new_name: String) -> Result<(), String> {
     // Validate device name
@@ -690,7 +2026,7 @@ pub fn rename_device(device_id: String, new_name: String) -> Result<(), String>
 
     core.storage
         .devices()
-        .update_device_name(&device_id, new_name)
+        .set_local_nickname(&device_id, Some(new_name))
         .map_err(|e| format!("Failed to rename device: {}", e))?;
 
     Ok(())
@@ -708,53 +2044,88 @@ pub fn get_current_clipboard() -> Option<ClipboardItemDto> {
 
     let content = core.clipboard.read().ok()??;
 
-    Some(ClipboardItemDto {
+    Some(clipboard_item_dto(&content, None))
+}
+
+/// Build a display item for live clipboard content
+fn clipboard_item_dto(
+    content: &ClipboardContent,
+    source_device: Option<String>,
+) -> ClipboardItemDto {
+    ClipboardItemDto {
         id: uuid::Uuid::new_v4().to_string(),
-        content_type: match content.content_type {
-            ContentType::PlainText => "text".to_string(),
-            ContentType::RichText => "rich_text".to_string(),
-            ContentType::Image => "image".to_string(),
-            ContentType::File => "file".to_string(),
-            ContentType::Url => "url".to_string(),
-        },
-        preview: content.as_text().unwrap_or_default(),
+        content_type: content.content_type.as_str().to_string(),
+        mime_type: content.mime_type(),
+        preview: content.preview(),
         size_bytes: content.metadata.size_bytes,
         timestamp: std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_millis() as u64,
-        source_device: None,
-    })
+        source_device,
+        language: content.metadata.language.clone(),
+        superseded: false,
+    }
+}
+
+/// Build a display item for a stored history entry
+fn history_item_dto(item: StoredHistoryItem, size_bytes: u64) -> ClipboardItemDto {
+    let content_type = ContentType::try_from(item.content_type).unwrap_or(ContentType::PlainText);
+    ClipboardItemDto {
+        id: item.id,
+        content_type: content_type.as_str().to_string(),
+        mime_type: content_type.mime_type().to_string(),
+        preview: item.preview,
+        size_bytes,
+        timestamp: item.created_at * 1000, // Convert seconds to milliseconds
+        source_device: item.source_device,
+        language: item.language,
+        superseded: item.superseded,
+    }
 }
 
 /// Send current clipboard to all devices
 #[frb]
 pub async fn send_clipboard() -> Result<(), String> {
+    send_clipboard_to(None).await
+}
+
+/// Send current clipboard to one paired device
+///
+/// The same gates as `send_clipboard` apply. If the device isn't connected
+/// or the direct send fails, the relay is tried.
+#[frb]
+pub async fn send_clipboard_to_device(device_id: String) -> Result<(), String> {
+    let device_id = target_device(&device_id)?;
+    send_clipboard_to(Some(device_id)).await
+}
+
+/// Send current clipboard to one device, or to all of them
+async fn send_clipboard_to(target: Option<[u8; 32]>) -> Result<(), String> {
     // Rate limiting: prevent rapid-fire syncs (minimum 100ms between syncs)
     {
         let guard = TOSS_INSTANCE.read();
         if let Some(core) = guard.as_ref() {
-            let last_sync = core.last_sync_time.lock().unwrap();
-            let elapsed = last_sync.elapsed();
-            if elapsed.as_millis() < 100 {
-                return Err(format!(
-                    "Rate limit: please wait {}ms",
-                    100 - elapsed.as_millis()
-                ));
+            let elapsed = core.last_sync_time.lock().unwrap().elapsed();
+            if let Some(skip) = rate_limit_skip(elapsed) {
+                return Err(core.record_skip(skip));
             }
         }
     }
 
     // Read all needed data while holding the lock, then drop it before await
     let (
-        message_clone,
+        content,
+        max_image_dimension,
         has_network,
         history_item,
         content_data_for_encryption,
         identity_for_encryption,
+        cancel,
     ) = {
         let guard = TOSS_INSTANCE.read();
         let core = guard.as_ref().ok_or("Toss not initialized")?;
+        let cancel = core.send_cancel.lock().unwrap().clone();
 
         let content = core
             .clipboard
@@ -762,39 +2133,16 @@ pub async fn send_clipboard() -> Result<(), String> {
             .map_err(|e| format!("Clipboard read failed: {}", e))?
             .ok_or("Clipboard is empty")?;
 
-        // Check settings
-        let settings = &core.settings;
-        match content.content_type {
-            ContentType::PlainText | ContentType::Url if !settings.sync_text => {
-                return Err("Text sync disabled".to_string());
-            }
-            ContentType::RichText if !settings.sync_rich_text => {
-                return Err("Rich text sync disabled".to_string());
-            }
-            ContentType::Image if !settings.sync_images => {
-                return Err("Image sync disabled".to_string());
-            }
-            ContentType::File if !settings.sync_files => {
-                return Err("File sync disabled".to_string());
-            }
-            _ => {}
-        }
-
-        // Check size limit
-        let max_bytes = (settings.max_file_size_mb as u64) * 1024 * 1024;
-        if content.metadata.size_bytes > max_bytes {
-            return Err(format!(
-                "Content too large (max {} MB)",
-                settings.max_file_size_mb
-            ));
-        }
+        check_send_allowed(&core.settings, &content).map_err(|skip| core.record_skip(skip))?;
+        check_min_size(&core.settings, &content).map_err(|skip| core.record_skip(skip))?;
+        check_denylist(&core.denylist, &content).map_err(|skip| core.record_skip(skip))?;
 
         // Prepare history item if enabled (we'll save it after dropping the guard)
         // Note: Encryption will happen when saving, not here, to avoid holding lock during crypto ops
         let (history_item, content_data_for_encryption, identity_for_encryption) =
-            if core.settings.history_enabled {
+            if core.settings.keeps_history(content.content_type) {
                 let item_id = uuid::Uuid::new_v4().to_string();
-                match bincode::serialize(&content) {
+                match encoding::encode(&content) {
                     Ok(content_data) => {
                         let identity = core.identity.clone();
                         let history_item = crate::storage::StoredHistoryItem {
@@ -802,14 +2150,14 @@ pub async fn send_clipboard() -> Result<(), String> {
                             content_type: content.content_type as u8,
                             content_hash: hex::encode(content.hash()),
                             encrypted_content: vec![], // Will be populated after encryption
-                            preview: content.metadata.text_preview.clone().unwrap_or_else(|| {
-                                format!("{} bytes", content.metadata.size_bytes)
-                            }),
+                            preview: content.preview(),
                             source_device: None, // Local clipboard
                             created_at: std::time::SystemTime::now()
                                 .duration_since(std::time::UNIX_EPOCH)
                                 .unwrap()
                                 .as_secs(),
+                            language: content.metadata.language.clone(),
+                            superseded: false,
                         };
                         (
                             Some(history_item),
@@ -827,20 +2175,20 @@ pub async fn send_clipboard() -> Result<(), String> {
                 (None, None, None)
             };
 
-        // Broadcast to connected devices
-        let update = ClipboardUpdate::new(content);
-        let message = Message::ClipboardUpdate(update);
+        if core.settings.advertise_content_hint {
+            advertise_content_hint(core, &content);
+        }
 
-        // Clone message and check if network exists before dropping guard
-        let message_clone = message.clone();
         let has_network = core.network.is_some();
 
         (
-            message_clone,
+            content,
+            core.settings.max_image_dimension,
             has_network,
             history_item,
             content_data_for_encryption,
             identity_for_encryption,
+            cancel,
         )
     }; // Guard is dropped here
 
@@ -878,505 +2226,2101 @@ pub async fn send_clipboard() -> Result<(), String> {
 
     // Broadcast message (after dropping all guards)
     if has_network {
-        let network_ptr: Option<*const NetworkManager> = {
+        // History keeps the original; only the sent copy is downscaled
+        let content = downscale_for_sync(content, max_image_dimension);
+        let message_clone = {
             let guard = TOSS_INSTANCE.read();
-            guard
-                .as_ref()
-                .and_then(|c| c.network.as_ref())
-                .map(|n| n as *const NetworkManager)
+            let core = guard.as_ref().ok_or("Toss not initialized")?;
+            Message::ClipboardUpdate(local_update(core, content))
         };
 
-        if let Some(ptr) = network_ptr {
-            // SAFETY:
-            // 1. NetworkManager::broadcast takes &self, not &mut self, so no mutation
-            // 2. The network is owned by TossCore in TOSS_INSTANCE which is behind a RwLock
-            // 3. We've dropped the guard, so we're not holding a lock
-            // 4. The network will remain valid as long as TOSS_INSTANCE exists
-            // 5. broadcast() only reads from network, so concurrent access is safe
-            let network = unsafe { &*ptr };
-            network
-                .broadcast(&message_clone)
-                .await
-                .map_err(|e| format!("Failed to broadcast message: {}", e))?;
+        if let Some(network) = running_network() {
+            let (result, outcomes) = match target {
+                Some(device_id) => {
+                    let (result, outcome) = network
+                        .send_to_device(&device_id, &message_clone, &cancel)
+                        .await;
+                    (result, vec![outcome])
+                }
+                None => network.broadcast_cancellable(&message_clone, &cancel).await,
+            };
+            record_send_outcomes(&outcomes);
+            record_broadcast_skip(&result, &outcomes);
+            result.map_err(|e| match e {
+                crate::error::NetworkError::Cancelled => "Send cancelled".to_string(),
+                e if target.is_some() => format!("Failed to send to device: {}", e),
+                e => format!("Failed to broadcast message: {}", e),
+            })?;
         }
+    } else {
+        record_broadcast_skip(&Ok(()), &[]);
     }
 
     Ok(())
 }
 
-/// Send text to all devices
-#[frb]
-pub async fn send_text(text: String) -> Result<(), String> {
-    // Read all needed data while holding the lock, then drop it before await
-    let (message_clone, has_network) = {
-        let guard = TOSS_INSTANCE.read();
-        let core = guard.as_ref().ok_or("Toss not initialized")?;
-
-        let content = ClipboardContent::text(&text);
-        let update = ClipboardUpdate::new(content);
-        let message = Message::ClipboardUpdate(update);
-
-        // Clone message and check if network exists before dropping guard
-        let message_clone = message.clone();
-        let has_network = core.network.is_some();
-
-        (message_clone, has_network)
-    }; // Guard is dropped here
-
-    // Broadcast message (after dropping the guard)
-    if has_network {
-        let network_ptr: Option<*const NetworkManager> = {
-            let guard = TOSS_INSTANCE.read();
-            guard
-                .as_ref()
-                .and_then(|c| c.network.as_ref())
-                .map(|n| n as *const NetworkManager)
-        };
-
-        if let Some(ptr) = network_ptr {
-            // SAFETY:
-            // 1. NetworkManager::broadcast takes &self, not &mut self, so no mutation
-            // 2. The network is owned by TossCore in TOSS_INSTANCE which is behind a RwLock
-            // 3. We've dropped the guard, so we're not holding a lock
-            // 4. The network will remain valid as long as TOSS_INSTANCE exists
-            // 5. broadcast() only reads from network, so concurrent access is safe
-            let network = unsafe { &*ptr };
-            network
-                .broadcast(&message_clone)
-                .await
-                .map_err(|e| format!("Failed to broadcast message: {}", e))?;
-        }
+/// Abort the `send_clipboard` broadcasts in progress
+///
+/// Peers receiving large content in chunks are sent no further chunks and
+/// are told the transfer was canceled. The canceled sends fail with
+/// "Send cancelled"; sends started afterwards are unaffected.
+#[frb(sync)]
+pub fn cancel_send() {
+    if let Some(ref core) = *TOSS_INSTANCE.read() {
+        let cancel = std::mem::take(&mut *core.send_cancel.lock().unwrap());
+        cancel.cancel();
     }
-
-    Ok(())
 }
 
-// ============================================================================
-// Settings
-// ============================================================================
-
-/// Get current settings
+/// Get the most recent reason a clipboard send was skipped or only partly
+/// delivered
+///
+/// Covers disabled or receive-only content types, the size limits, rate
+/// limiting, local changes during quiet hours, having no peer to send to,
+/// and broadcasts that failed, partly failed or were canceled. `None` until
+/// a send has been skipped; a later successful send doesn't clear it.
 #[frb(sync)]
-pub fn get_settings() -> TossSettings {
+pub fn last_skip_reason() -> Option<SkipReasonDto> {
     TOSS_INSTANCE
         .read()
         .as_ref()
-        .map(|core| core.settings.clone())
-        .unwrap_or_default()
+        .and_then(|core| core.last_skip.lock().unwrap().clone())
 }
 
-/// Update settings
-#[frb(sync)]
-pub fn update_settings(settings: TossSettings) -> Result<(), String> {
-    if let Some(ref mut core) = *TOSS_INSTANCE.write() {
-        core.settings = settings;
-        Ok(())
-    } else {
-        Err("Toss not initialized".to_string())
+/// Build an update for local content, ordered after the clipboard's current version
+fn local_update(core: &TossCore, content: ClipboardContent) -> ClipboardUpdate {
+    let mut update = ClipboardUpdate::new(content);
+    update.sent_at = core.conflicts.stamp_local(update.sent_at);
+    update
+}
+
+/// Why a clipboard send was skipped or only partly delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SkipReason {
+    /// Syncing this content type is turned off
+    TypeDisabled,
+    /// This content type is only received
+    ReceiveOnly,
+    /// The content is over `max_file_size_mb`
+    SizeLimit,
+    /// The content is under `min_sync_bytes`
+    BelowMinSize,
+    /// The content matches a `content_denylist` pattern
+    Denylisted,
+    /// The previous send was too recent
+    RateLimited,
+    /// A local change came in during quiet hours
+    QuietHours,
+    /// No peer was there to send to
+    NoPeers,
+    /// Some peers received the content and some didn't
+    PartialFailure,
+    /// No peer received the content
+    SendFailed,
+    /// The send was canceled with `cancel_send`
+    Cancelled,
+}
+
+impl SkipReason {
+    /// Stable lowercase name used in API DTOs
+    fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::TypeDisabled => "type_disabled",
+            SkipReason::ReceiveOnly => "receive_only",
+            SkipReason::SizeLimit => "size_limit",
+            SkipReason::BelowMinSize => "below_min_size",
+            SkipReason::Denylisted => "denylisted",
+            SkipReason::RateLimited => "rate_limited",
+            SkipReason::QuietHours => "quiet_hours",
+            SkipReason::NoPeers => "no_peers",
+            SkipReason::PartialFailure => "partial_failure",
+            SkipReason::SendFailed => "send_failed",
+            SkipReason::Cancelled => "cancelled",
+        }
     }
 }
 
-// ============================================================================
-// Network
-// ============================================================================
+/// A send that was skipped, with the message reported for it
+#[derive(Debug)]
+struct SendSkip {
+    reason: SkipReason,
+    message: String,
+}
 
-/// Start networking
-#[frb]
-pub async fn start_network() -> Result<(), String> {
-    // Extract config while holding lock, then release before async operations
-    let (identity, config, get_session_key) = {
-        let guard = TOSS_INSTANCE.read();
-        let core = guard.as_ref().ok_or("Toss not initialized")?;
+impl SendSkip {
+    fn new(reason: SkipReason, message: impl Into<String>) -> Self {
+        Self {
+            reason,
+            message: message.into(),
+        }
+    }
+}
 
-        let config = NetworkConfig {
-            device_name: core.device_name.clone(),
-            relay_url: core.settings.relay_url.clone(),
-            ..Default::default()
-        };
+/// Minimum time between `send_clipboard` calls
+const MIN_SEND_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
 
-        // Create a callback to retrieve session keys from storage
-        // We need to capture the storage database path for later use
-        let db_path = core.storage.db_path().to_path_buf();
-        let get_session_key: Arc<GetSessionKeyFn> =
-            Arc::new(Box::new(move |device_id: &[u8; 32]| {
-                // Open a temporary connection to look up the session key
-                if let Ok(storage) = Storage::new(&db_path) {
-                    let device_id_hex = hex::encode(device_id);
-                    if let Ok(Some(device)) = storage.devices().get_device(&device_id_hex) {
-                        if let Some(session_key_bytes) = device.session_key {
-                            if session_key_bytes.len() == 32 {
-                                let mut key = [0u8; 32];
-                                key.copy_from_slice(&session_key_bytes);
-                                return Some(key);
-                            }
-                        }
-                    }
-                }
-                None
-            }));
+/// Refuse a send this soon after the last one
+fn rate_limit_skip(elapsed: std::time::Duration) -> Option<SendSkip> {
+    let wait = MIN_SEND_INTERVAL.checked_sub(elapsed)?;
+    (!wait.is_zero()).then(|| {
+        SendSkip::new(
+            SkipReason::RateLimited,
+            format!("Rate limit: please wait {}ms", wait.as_millis()),
+        )
+    })
+}
 
-        (core.identity.clone(), config, get_session_key)
+/// Check that the settings allow sending this content type
+fn check_direction(settings: &TossSettings, content_type: ContentType) -> Result<(), SendSkip> {
+    let kind = match content_type {
+        ContentType::PlainText | ContentType::Url | ContentType::Code => "Text",
+        ContentType::RichText => "Rich text",
+        ContentType::Image => "Image",
+        ContentType::File => "File",
     };
+    match settings.direction_for(content_type) {
+        None => Err(SendSkip::new(
+            SkipReason::TypeDisabled,
+            format!("{} sync disabled", kind),
+        )),
+        Some(direction) if !direction.sends() => Err(SendSkip::new(
+            SkipReason::ReceiveOnly,
+            format!("{} sync is receive-only", kind),
+        )),
+        Some(_) => Ok(()),
+    }
+}
 
-    // Perform async operations without holding lock
-    let mut network =
-        NetworkManager::new_with_callbacks(identity, config, None, Some(get_session_key))
-            .await
-            .map_err(|e| format!("Network init failed: {}", e))?;
-
-    network
-        .start()
-        .await
-        .map_err(|e| format!("Network start failed: {}", e))?;
+/// Check that the settings allow sending content of this type and size
+fn check_send_allowed(settings: &TossSettings, content: &ClipboardContent) -> Result<(), SendSkip> {
+    check_direction(settings, content.content_type)?;
 
-    // Re-acquire lock to store network and subscribe to events
-    {
-        let mut guard = TOSS_INSTANCE.write();
-        let core = guard.as_mut().ok_or("Toss not initialized")?;
-        let receiver = network.subscribe();
-        core.event_receiver = Some(Arc::new(Mutex::new(receiver)));
-        core.network = Some(network);
+    let max_bytes = (settings.max_file_size_mb as u64) * 1024 * 1024;
+    if content.metadata.size_bytes > max_bytes {
+        return Err(SendSkip::new(
+            SkipReason::SizeLimit,
+            format!("Content too large (max {} MB)", settings.max_file_size_mb),
+        ));
     }
-
     Ok(())
 }
 
-/// Stop networking
-#[frb]
-pub async fn stop_network() {
-    // Extract network while holding lock, then release before async operation
-    let network = {
-        let mut guard = TOSS_INSTANCE.write();
-        guard.as_mut().and_then(|core| core.network.take())
-    };
+/// Check that content isn't below `min_sync_bytes`
+///
+/// Empty content clears the peers' clipboards and is always sent.
+fn check_min_size(settings: &TossSettings, content: &ClipboardContent) -> Result<(), SendSkip> {
+    let size = content.data.len() as u64;
+    if size > 0 && size < settings.min_sync_bytes as u64 {
+        return Err(SendSkip::new(
+            SkipReason::BelowMinSize,
+            format!("Content too small (min {} bytes)", settings.min_sync_bytes),
+        ));
+    }
+    Ok(())
+}
 
-    if let Some(mut network) = network {
-        network.stop().await;
+/// Check that text content doesn't match a `content_denylist` pattern
+fn check_denylist(denylist: &ContentDenylist, content: &ClipboardContent) -> Result<(), SendSkip> {
+    match denylist.check(content) {
+        Some(pattern) => Err(SendSkip::new(
+            SkipReason::Denylisted,
+            format!("Content matches denylist pattern '{}'", pattern),
+        )),
+        None => Ok(()),
     }
 }
 
-/// Start listening to network events
-/// Returns a receiver that can be polled for events
-/// Note: Full stream support requires flutter_rust_bridge stream support
-#[frb]
-pub async fn start_event_listener() -> Result<(), String> {
-    let guard = TOSS_INSTANCE.read();
-    let core = guard.as_ref().ok_or("Toss not initialized")?;
+/// What kept a broadcast from reaching every peer, if anything did
+///
+/// An empty outcome list means there was no peer to send to.
+fn broadcast_skip(
+    result: &Result<(), crate::error::NetworkError>,
+    outcomes: &[SendOutcome],
+) -> Option<SendSkip> {
+    let failed = outcomes
+        .iter()
+        .filter(|outcome| matches!(outcome, SendOutcome::Failed(_)))
+        .count();
+    match result {
+        Err(crate::error::NetworkError::Cancelled) => {
+            Some(SendSkip::new(SkipReason::Cancelled, "Send cancelled"))
+        }
+        Err(e) => Some(SendSkip::new(
+            SkipReason::SendFailed,
+            format!("Failed to broadcast message: {}", e),
+        )),
+        Ok(()) if outcomes.is_empty() => {
+            Some(SendSkip::new(SkipReason::NoPeers, "No devices connected"))
+        }
+        Ok(()) if failed > 0 => Some(SendSkip::new(
+            SkipReason::PartialFailure,
+            format!(
+                "Delivered to {} of {} devices",
+                outcomes.len() - failed,
+                outcomes.len()
+            ),
+        )),
+        Ok(()) => None,
+    }
+}
 
-    if let Some(ref network) = core.network {
-        // Subscribe to network events
-        let receiver = network.subscribe();
-        // Store receiver for polling
-        let mut guard = TOSS_INSTANCE.write();
-        if let Some(ref mut core) = *guard {
-            core.event_receiver = Some(Arc::new(Mutex::new(receiver)));
+/// Remember why a clipboard broadcast didn't reach every peer
+fn record_broadcast_skip(
+    result: &Result<(), crate::error::NetworkError>,
+    outcomes: &[SendOutcome],
+) {
+    if let Some(skip) = broadcast_skip(result, outcomes) {
+        if let Some(core) = TOSS_INSTANCE.read().as_ref() {
+            core.record_skip(skip);
         }
     }
+}
 
-    Ok(())
+/// Downscale an outbound image above `max_dimension` (0 = keep the original)
+///
+/// Non-image content, and images that fail to downscale, are sent unchanged.
+fn downscale_for_sync(content: ClipboardContent, max_dimension: u32) -> ClipboardContent {
+    if content.content_type != ContentType::Image {
+        return content;
+    }
+
+    match downscale_image(&content.data, max_dimension) {
+        Ok(Some(data)) => {
+            let dimensions = get_image_dimensions(&data).ok();
+            let mime_type = get_image_mime_type(&data).map(str::to_string);
+            ClipboardContent::image(data, dimensions, mime_type)
+        }
+        Ok(None) => content,
+        Err(e) => {
+            tracing::warn!("Failed to downscale image, sending the original: {}", e);
+            content
+        }
+    }
 }
 
-/// Poll for network events (polling-based approach until streams are available)
-/// Returns the next event if available, or None
-/// Note: This uses try_recv which is non-blocking
-#[frb(sync)]
-pub fn poll_event() -> Option<TossEvent> {
+/// Add broadcast outcomes to the local sync statistics, if enabled
+fn record_send_outcomes(outcomes: &[SendOutcome]) {
+    if outcomes.is_empty() {
+        return;
+    }
     let guard = TOSS_INSTANCE.read();
-    let core = guard.as_ref()?;
-
-    if let Some(ref receiver_arc) = core.event_receiver {
-        // Try to receive an event (non-blocking)
-        let mut receiver = receiver_arc.lock().unwrap();
-        match receiver.try_recv() {
-            Ok(NetworkEvent::PeerConnected {
-                device_id,
-                device_name,
-            }) => {
-                Some(TossEvent::DeviceConnected {
-                    device: DeviceInfoDto {
-                        id: hex::encode(device_id),
-                        name: device_name,
-                        is_online: true,
-                        last_seen: 0,
-                        platform: "unknown".to_string(), // Platform info not available in event yet
-                    },
-                })
+    if let Some(core) = guard.as_ref() {
+        if core.settings.collect_sync_stats {
+            if let Err(e) = core.storage.stats().record_sends(outcomes) {
+                tracing::warn!("Failed to record sync statistics: {}", e);
             }
-            Ok(NetworkEvent::PeerDisconnected { device_id }) => {
-                Some(TossEvent::DeviceDisconnected {
-                    device_id: hex::encode(device_id),
-                })
-            }
-            Ok(NetworkEvent::MessageReceived {
-                from_device_id,
-                message,
-            }) => {
-                // Verify that the message is from a paired device and not from ourselves
-                let (is_paired, is_self) = {
-                    let guard = TOSS_INSTANCE.read();
-                    if let Some(core) = guard.as_ref() {
-                        let device_id_str = hex::encode(from_device_id);
-                        let is_paired = matches!(
-                            core.storage.devices().get_device(&device_id_str),
-                            Ok(Some(_))
-                        );
-                        let is_self = from_device_id == *core.identity.device_id();
-                        (is_paired, is_self)
-                    } else {
-                        (false, false)
-                    }
-                };
-
-                // Only process messages from paired devices (not ourselves)
-                if !is_paired {
-                    tracing::warn!(
-                        "Received message from unpaired device: {}",
-                        hex::encode(from_device_id)
-                    );
-                    return None;
-                }
+        }
+    }
+}
 
-                // Ignore messages from ourselves to prevent self-sync loops
-                if is_self {
-                    tracing::debug!("Ignoring message from self");
-                    return None;
-                }
+/// Update the mDNS content hint for locally copied content
+fn advertise_content_hint(core: &TossCore, content: &ClipboardContent) {
+    if let Some(ref network) = core.network {
+        if let Err(e) = network.advertise_content_hint(content.content_type, &content.hash()) {
+            tracing::warn!("Failed to advertise content hint: {}", e);
+        }
+    }
+}
 
-                // Convert Message to ClipboardItemDto if it's a clipboard update
-                if let crate::protocol::Message::ClipboardUpdate(update) = message {
-                    // Validate content hash to ensure integrity
-                    let computed_hash = update.content.hash();
-                    if computed_hash != update.content_hash {
-                        tracing::warn!(
-                            "Content hash mismatch for received clipboard update from device {}",
-                            hex::encode(from_device_id)
-                        );
-                        // Continue anyway - hash mismatch might be due to serialization differences
-                        // In production, this should probably reject the message
-                    }
+/// Send text to all devices
+///
+/// Text under `min_sync_bytes` isn't sent; empty text always is.
+#[frb]
+pub async fn send_text(text: String) -> Result<(), String> {
+    let content = ClipboardContent::text(&text);
+    {
+        let guard = TOSS_INSTANCE.read();
+        let core = guard.as_ref().ok_or("Toss not initialized")?;
+        check_min_size(&core.settings, &content).map_err(|skip| core.record_skip(skip))?;
+    }
+    send_text_content(content, None, None).await
+}
 
-                    // Validate content size limit
-                    let max_size = {
-                        let guard = TOSS_INSTANCE.read();
-                        if let Some(core) = guard.as_ref() {
-                            (core.settings.max_file_size_mb as u64) * 1024 * 1024
-                        } else {
-                            return None;
-                        }
-                    };
+/// Send text to one paired device
+///
+/// Like `send_text`, falling back to the relay if the device isn't
+/// connected or the direct send fails.
+#[frb]
+pub async fn send_text_to_device(device_id: String, text: String) -> Result<(), String> {
+    let device_id = target_device(&device_id)?;
+    let content = ClipboardContent::text(&text);
+    {
+        let guard = TOSS_INSTANCE.read();
+        let core = guard.as_ref().ok_or("Toss not initialized")?;
+        check_min_size(&core.settings, &content).map_err(|skip| core.record_skip(skip))?;
+    }
+    send_text_content(content, None, Some(device_id)).await
+}
 
-                    if update.content.metadata.size_bytes > max_size {
-                        tracing::warn!("Received clipboard content exceeds size limit ({} bytes > {} bytes) from device {}", 
-                            update.content.metadata.size_bytes, max_size, hex::encode(from_device_id));
-                        return None;
-                    }
+/// Send text to all devices with an explicit content type
+///
+/// `content_type` is "text", "url" or "code"; `language` is an optional hint
+/// for code, e.g. "rust". Skips the heuristic detection done by `send_text`.
+#[frb]
+pub async fn send_text_as(
+    text: String,
+    content_type: String,
+    language: Option<String>,
+) -> Result<(), String> {
+    let content = match content_type.as_str() {
+        "text" => ClipboardContent::new_text(ContentType::PlainText, &text),
+        "url" => ClipboardContent::new_text(ContentType::Url, &text),
+        "code" => ClipboardContent::code(&text, language.as_deref()),
+        other => return Err(format!("Unsupported text content type: {}", other)),
+    };
+    send_text_content(content, None, None).await
+}
 
-                    // Check settings and write to clipboard if sync is enabled for this content type
-                    let should_write = {
-                        let guard = TOSS_INSTANCE.read();
-                        if let Some(core) = guard.as_ref() {
-                            let settings = &core.settings;
-                            match update.content.content_type {
-                                ContentType::PlainText | ContentType::Url => settings.sync_text,
-                                ContentType::RichText => settings.sync_rich_text,
-                                ContentType::Image => settings.sync_images,
-                                ContentType::File => settings.sync_files,
-                            }
-                        } else {
-                            false
-                        }
-                    };
+/// Send a URL to all devices and ask them to open it
+///
+/// Devices that allow remote actions open it as well as putting it on the
+/// clipboard; all others only put it on the clipboard. Only http and https
+/// URLs can be sent this way.
+#[frb]
+pub async fn send_url_to_open(url: String) -> Result<(), String> {
+    let url = url.trim();
+    if !is_web_url(url) {
+        return Err("Only http and https URLs can be opened remotely".to_string());
+    }
+    let content = ClipboardContent::new_text(ContentType::Url, url);
+    send_text_content(content, Some(AutoAction::OpenUrl), None).await
+}
 
-                    // Write to clipboard if sync is enabled for this content type
-                    if should_write {
-                        let mut guard = TOSS_INSTANCE.write();
-                        if let Some(ref mut core) = guard.as_mut() {
-                            if let Err(e) = core.clipboard.write(&update.content) {
-                                tracing::warn!("Failed to write received clipboard content: {}", e);
-                            } else {
-                                // Update monitor hash to prevent re-syncing this content
-                                core.clipboard.monitor_mut().update_hash(&update.content);
-                            }
-                        }
-                    }
+/// Whether a URL uses a scheme safe to open on request of another device
+fn is_web_url(url: &str) -> bool {
+    let url = url.to_ascii_lowercase();
+    url.starts_with("https://") || url.starts_with("http://")
+}
 
-                    // Save to history if enabled (with encryption)
-                    {
-                        let guard = TOSS_INSTANCE.read();
-                        if let Some(core) = guard.as_ref() {
-                            if core.settings.history_enabled {
-                                let item_id = uuid::Uuid::new_v4().to_string();
-                                let content_data = match bincode::serialize(&update.content) {
-                                    Ok(data) => data,
-                                    Err(e) => {
-                                        tracing::warn!(
-                                            "Failed to serialize received content for history: {}",
-                                            e
-                                        );
-                                        // Skip history if serialization fails
-                                        return Some(TossEvent::ClipboardReceived {
-                                            item: ClipboardItemDto {
-                                                id: uuid::Uuid::new_v4().to_string(),
-                                                content_type: format!(
-                                                    "{:?}",
-                                                    update.content.content_type
-                                                ),
-                                                preview: update
-                                                    .content
-                                                    .metadata
-                                                    .text_preview
-                                                    .clone()
-                                                    .unwrap_or_else(|| {
-                                                        format!(
-                                                            "{} bytes",
-                                                            update.content.metadata.size_bytes
-                                                        )
-                                                    }),
-                                                size_bytes: update.content.metadata.size_bytes,
-                                                timestamp: std::time::SystemTime::now()
-                                                    .duration_since(std::time::UNIX_EPOCH)
-                                                    .unwrap()
-                                                    .as_millis()
-                                                    as u64,
-                                                source_device: Some(hex::encode(from_device_id)),
-                                            },
-                                        });
-                                    }
-                                };
-
-                                // Derive storage encryption key
-                                if let Ok(storage_key) = derive_key(
-                                    core.identity.device_id(),
-                                    DerivedKeyPurpose::StorageEncryption,
-                                    Some(b"toss-clipboard-history-v1"),
-                                ) {
-                                    // Encrypt content
-                                    let aad = format!("history:{}", item_id).into_bytes();
-                                    if let Ok(encrypted) =
-                                        encrypt(&storage_key, &content_data, &aad)
-                                    {
-                                        let history_item = crate::storage::StoredHistoryItem {
-                                            id: item_id,
-                                            content_type: update.content.content_type as u8,
-                                            content_hash: hex::encode(update.content_hash),
-                                            encrypted_content: encrypted.to_bytes(),
-                                            preview: update
-                                                .content
-                                                .metadata
-                                                .text_preview
-                                                .clone()
-                                                .unwrap_or_else(|| {
-                                                    format!(
-                                                        "{} bytes",
-                                                        update.content.metadata.size_bytes
-                                                    )
-                                                }),
-                                            source_device: Some(hex::encode(from_device_id)),
-                                            created_at: std::time::SystemTime::now()
-                                                .duration_since(std::time::UNIX_EPOCH)
-                                                .unwrap()
-                                                .as_secs(),
-                                        };
-                                        if let Err(e) =
-                                            core.storage.history().store_item(&history_item)
-                                        {
-                                            tracing::warn!(
-                                                "Failed to save received clipboard history: {}",
-                                                e
-                                            );
-                                        }
-                                    } else {
-                                        tracing::warn!(
-                                            "Failed to encrypt received clipboard history content"
-                                        );
-                                    }
-                                } else {
-                                    tracing::warn!("Failed to derive storage key for received clipboard history");
-                                }
-                            }
-                        }
-                    }
+/// Send text content to one device or all of them, if text sync is enabled
+async fn send_text_content(
+    content: ClipboardContent,
+    auto_action: Option<AutoAction>,
+    target: Option<[u8; 32]>,
+) -> Result<(), String> {
+    let message = {
+        let guard = TOSS_INSTANCE.read();
+        let core = guard.as_ref().ok_or("Toss not initialized")?;
 
-                    // Return event for Flutter
-                    Some(TossEvent::ClipboardReceived {
-                        item: ClipboardItemDto {
-                            id: uuid::Uuid::new_v4().to_string(),
-                            content_type: format!("{:?}", update.content.content_type),
-                            preview: update.content.metadata.text_preview.clone().unwrap_or_else(
-                                || format!("{} bytes", update.content.metadata.size_bytes),
-                            ),
-                            size_bytes: update.content.metadata.size_bytes,
-                            timestamp: std::time::SystemTime::now()
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap()
-                                .as_millis() as u64,
-                            source_device: Some(hex::encode(from_device_id)),
-                        },
-                    })
-                } else {
-                    None
-                }
-            }
-            Ok(NetworkEvent::Error(msg)) => Some(TossEvent::Error { message: msg }),
-            Ok(NetworkEvent::PeerDiscovered(_)) | Ok(NetworkEvent::PeerLost(_)) => {
-                // These events are less critical for Flutter UI
-                None
-            }
-            Err(_) => None, // No event available or channel closed
+        check_direction(&core.settings, content.content_type)
+            .map_err(|skip| core.record_skip(skip))?;
+        check_denylist(&core.denylist, &content).map_err(|skip| core.record_skip(skip))?;
+        if core.settings.advertise_content_hint {
+            advertise_content_hint(core, &content);
         }
-    } else {
-        None
+
+        let mut update = local_update(core, content);
+        update.auto_action = auto_action;
+        Message::ClipboardUpdate(update)
+    }; // Guard is dropped here
+
+    match target {
+        Some(device_id) => send_message_to_device(&device_id, &message).await,
+        None => broadcast_message(&message).await,
     }
 }
 
-/// Get clipboard history
-#[frb(sync)]
-pub fn get_clipboard_history(limit: Option<u32>) -> Vec<ClipboardItemDto> {
-    let guard = TOSS_INSTANCE.read();
-    let core = match guard.as_ref() {
-        Some(c) => c,
-        None => return Vec::new(),
-    };
+/// Send data handed over by an OS share sheet to all devices
+///
+/// The MIME type picks the content type: `text/uri-list` is a URL,
+/// `text/html` and `text/rtf` are rich text, other `text/*` types are text
+/// (with URL and code detection), `image/*` is an image, and anything else
+/// is sent as a file named `suggested_name`. The same type and size gates as
+/// `send_clipboard` apply. The system clipboard is not touched.
+#[frb]
+pub async fn share_content(
+    mime_type: String,
+    data: Vec<u8>,
+    suggested_name: Option<String>,
+) -> Result<(), String> {
+    let content = shared_content(&mime_type, data, suggested_name)?;
+
+    let message = {
+        let guard = TOSS_INSTANCE.read();
+        let core = guard.as_ref().ok_or("Toss not initialized")?;
 
-    let history_items = match core.storage.history().get_all_items(limit) {
-        Ok(items) => items,
-        Err(_) => return Vec::new(),
-    };
+        check_send_allowed(&core.settings, &content).map_err(|skip| core.record_skip(skip))?;
+        check_denylist(&core.denylist, &content).map_err(|skip| core.record_skip(skip))?;
+        if core.settings.advertise_content_hint {
+            advertise_content_hint(core, &content);
+        }
 
-    history_items
-        .into_iter()
-        .map(|item| ClipboardItemDto {
-            id: item.id,
-            content_type: format!(
-                "{:?}",
-                ContentType::try_from(item.content_type).unwrap_or(ContentType::PlainText)
-            ),
-            preview: item.preview,
-            size_bytes: item.encrypted_content.len() as u64,
-            timestamp: item.created_at * 1000, // Convert seconds to milliseconds
-            source_device: item.source_device,
-        })
-        .collect()
+        let content = downscale_for_sync(content, core.settings.max_image_dimension);
+        Message::ClipboardUpdate(local_update(core, content))
+    }; // Guard is dropped here
+
+    broadcast_message(&message).await
 }
 
-/// Remove clipboard history item
-#[frb(sync)]
-pub fn remove_history_item(item_id: String) -> Result<(), String> {
-    let guard = TOSS_INSTANCE.read();
-    let core = guard.as_ref().ok_or("Toss not initialized")?;
+/// Send a file on disk to all devices
+///
+/// Unlike `share_content`, the file is never read into memory as a whole: it
+/// goes to each peer as a resumable transfer, read and encrypted one chunk
+/// at a time. Peers without resumable transfers aren't sent it. The same
+/// type and size gates as `send_clipboard` apply, and `cancel_send` stops
+/// it. The file must not change while it is sent.
+#[frb]
+pub async fn send_file(path: String) -> Result<(), String> {
+    let path = std::path::PathBuf::from(path);
+    let read_error = |e: std::io::Error| format!("Failed to read {}: {}", path.display(), e);
+    let file = std::fs::File::open(&path).map_err(read_error)?;
+    let size = file.metadata().map_err(read_error)?.len();
+
+    let mut content = ClipboardContent::new(ContentType::File, Vec::new());
+    content.metadata.size_bytes = size;
+    content.metadata.filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned());
+    {
+        let guard = TOSS_INSTANCE.read();
+        let core = guard.as_ref().ok_or("Toss not initialized")?;
+        check_send_allowed(&core.settings, &content).map_err(|skip| core.record_skip(skip))?;
+    }
+    let content_hash = hash_content_reader(file, None).map_err(read_error)?;
 
-    core.storage
-        .history()
-        .remove_item(&item_id)
-        .map_err(|e| format!("Failed to remove history item: {}", e))?;
+    let (update, cancel, network) = {
+        let guard = TOSS_INSTANCE.read();
+        let core = guard.as_ref().ok_or("Toss not initialized")?;
+        let mut update = local_update(core, content);
+        update.content_hash = content_hash;
+        let cancel = core.send_cancel.lock().unwrap().clone();
+        (update, cancel, core.network.clone())
+    }; // Guard is dropped here
 
-    Ok(())
+    let Some(network) = network else {
+        record_broadcast_skip(&Ok(()), &[]);
+        return Ok(());
+    };
+    let (result, outcomes) = network.broadcast_file(&update, &path, &cancel).await;
+    record_send_outcomes(&outcomes);
+    record_broadcast_skip(&result, &outcomes);
+    result.map_err(|e| match e {
+        crate::error::NetworkError::Cancelled => "Send cancelled".to_string(),
+        e => format!("Failed to broadcast message: {}", e),
+    })
 }
 
-/// Clear clipboard history
-#[frb(sync)]
-pub fn clear_clipboard_history() -> Result<(), String> {
-    let guard = TOSS_INSTANCE.read();
+/// Build clipboard content for shared data of a declared MIME type
+fn shared_content(
+    mime_type: &str,
+    data: Vec<u8>,
+    suggested_name: Option<String>,
+) -> Result<ClipboardContent, String> {
+    // Drop parameters such as "; charset=utf-8"
+    let essence = mime_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    if data.is_empty() {
+        return Err("Shared content is empty".to_string());
+    }
+
+    let text = || std::str::from_utf8(&data).map_err(|_| "Shared text is not valid UTF-8");
+    let mut content = match essence.as_str() {
+        "text/uri-list" => ClipboardContent::new_text(ContentType::Url, text()?.trim()),
+        "text/html" | "text/rtf" | "application/rtf" => {
+            let mut content = ClipboardContent::new_text(ContentType::RichText, text()?);
+            content.metadata.mime_type = Some(essence.clone());
+            content
+        }
+        _ if essence.starts_with("text/") => ClipboardContent::text(text()?),
+        _ if essence.starts_with("image/") => {
+            let dimensions = get_image_dimensions(&data).ok();
+            ClipboardContent::image(data, dimensions, Some(essence.clone()))
+        }
+        _ => {
+            let mut content = ClipboardContent::new(ContentType::File, data);
+            content.metadata.mime_type = Some(essence.clone());
+            content
+        }
+    };
+
+    if content.metadata.filename.is_none() {
+        content.metadata.filename = suggested_name;
+    }
+    Ok(content)
+}
+
+/// Current text of the shared scratchpad
+#[frb(sync)]
+pub fn get_scratchpad() -> String {
+    TOSS_INSTANCE
+        .read()
+        .as_ref()
+        .map(|core| core.scratchpad.text())
+        .unwrap_or_default()
+}
+
+/// Replace the text of the shared scratchpad
+///
+/// Only the changed range is sent to paired devices. Fails for text over
+/// `MAX_SCRATCHPAD_SIZE` bytes.
+#[frb]
+pub async fn set_scratchpad(text: String) -> Result<(), String> {
+    if text.len() > MAX_SCRATCHPAD_SIZE {
+        return Err(format!(
+            "Scratchpad too large ({} bytes, max {})",
+            text.len(),
+            MAX_SCRATCHPAD_SIZE
+        ));
+    }
+
+    let update = {
+        let guard = TOSS_INSTANCE.read();
+        let core = guard.as_ref().ok_or("Toss not initialized")?;
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        core.scratchpad.edit(&text, now_ms)
+    }; // Guard is dropped here
+
+    match update {
+        Some(update) => broadcast_message(&Message::ScratchpadUpdate(update)).await,
+        None => Ok(()),
+    }
+}
+
+/// Tell paired devices what this device is doing ("idle" or "copying")
+///
+/// Does nothing unless `share_presence` is on. Hints are throttled: only
+/// changes are sent, and "copying" at most once every few seconds.
+#[frb]
+pub async fn broadcast_presence(state: String) -> Result<(), String> {
+    let state =
+        PresenceState::parse(&state).ok_or_else(|| format!("Unknown presence state: {}", state))?;
+
+    {
+        let guard = TOSS_INSTANCE.read();
+        let core = guard.as_ref().ok_or("Toss not initialized")?;
+        if !core.settings.share_presence
+            || !core.presence.allow_send(state, std::time::Instant::now())
+        {
+            return Ok(());
+        }
+    } // Guard is dropped here
+
+    broadcast_message(&Message::Presence(Presence { state })).await
+}
+
+/// Report whether this device's screen is locked
+///
+/// Paired devices are told when the state changes, and devices connecting
+/// later learn it on connect. Devices with `require_peer_unlocked` on pause
+/// clipboard sync with this one while it is locked.
+#[frb]
+pub async fn set_local_lock_state(locked: bool) -> Result<(), String> {
+    let changed = {
+        let mut guard = TOSS_INSTANCE.write();
+        let core = guard.as_mut().ok_or("Toss not initialized")?;
+        core.screen_locked = locked;
+        core.network
+            .as_ref()
+            .is_some_and(|network| network.set_local_locked(locked))
+    }; // Guard is dropped here
+
+    if changed {
+        broadcast_message(&Message::LockState(LockState { locked })).await
+    } else {
+        Ok(())
+    }
+}
+
+/// Broadcast a message to all devices, if the network is running
+async fn broadcast_message(message: &Message) -> Result<(), String> {
+    let is_clipboard_update = matches!(message, Message::ClipboardUpdate(_));
+    if let Some(network) = running_network() {
+        let (result, outcomes) = network.broadcast_with_outcomes(message).await;
+        record_send_outcomes(&outcomes);
+        if is_clipboard_update {
+            record_broadcast_skip(&result, &outcomes);
+        }
+        result.map_err(|e| format!("Failed to broadcast message: {}", e))?;
+    } else if is_clipboard_update {
+        record_broadcast_skip(&Ok(()), &[]);
+    }
+
+    Ok(())
+}
+
+/// Send a message to one device, falling back to the relay
+async fn send_message_to_device(device_id: &[u8; 32], message: &Message) -> Result<(), String> {
+    let is_clipboard_update = matches!(message, Message::ClipboardUpdate(_));
+    let network = running_network().ok_or("Network not started")?;
+    let (result, outcome) = network
+        .send_to_device(device_id, message, &CancellationToken::new())
+        .await;
+    record_send_outcomes(&[outcome]);
+    if is_clipboard_update {
+        record_broadcast_skip(&result, &[outcome]);
+    }
+    result.map_err(|e| format!("Failed to send to device: {}", e))
+}
+
+/// Parse the ID of a paired device to send to
+fn target_device(device_id: &str) -> Result<[u8; 32], String> {
+    let id: [u8; 32] = hex::decode(device_id)
+        .ok()
+        .and_then(|id| id.try_into().ok())
+        .ok_or("Invalid device ID")?;
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+    match core.storage.devices().get_device(&hex::encode(id)) {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err("Device not paired".to_string()),
+        Err(e) => return Err(format!("Failed to look up device: {}", e)),
+    }
+    if core.network.is_none() {
+        return Err("Network not started".to_string());
+    }
+    Ok(id)
+}
+
+// ============================================================================
+// Settings
+// ============================================================================
+
+/// Get current settings
+#[frb(sync)]
+pub fn get_settings() -> TossSettings {
+    TOSS_INSTANCE
+        .read()
+        .as_ref()
+        .map(|core| core.settings.clone())
+        .unwrap_or_default()
+}
+
+/// Update settings
+///
+/// They are saved to storage and loaded again by `init_toss`.
+#[frb(sync)]
+pub fn update_settings(settings: TossSettings) -> Result<(), String> {
+    if let Some(ref key) = settings.relay_pinned_key {
+        decode_relay_key(key).ok_or("Invalid pinned relay key")?;
+    }
+    if settings.quiet_hours_start >= MINUTES_PER_DAY || settings.quiet_hours_end >= MINUTES_PER_DAY
+    {
+        return Err("Quiet hours must be between 00:00 and 23:59".to_string());
+    }
+    if let Some(ref dir) = settings.file_receive_directory {
+        let dir = std::path::Path::new(dir);
+        if !dir.is_absolute() {
+            return Err("File receive directory must be absolute".to_string());
+        }
+        if !dir.is_dir() {
+            return Err(format!("Directory {} does not exist", dir.display()));
+        }
+    }
+    let denylist = ContentDenylist::new(&settings.content_denylist)?;
+    crate::network::discovery::validate_service_name(&settings.service_name)
+        .map_err(|e| e.to_string())?;
+
+    if let Some(ref mut core) = *TOSS_INSTANCE.write() {
+        core.storage
+            .settings()
+            .save(&settings)
+            .map_err(|e| format!("Failed to save settings: {}", e))?;
+        if core.settings.advertise_content_hint && !settings.advertise_content_hint {
+            if let Some(ref network) = core.network {
+                if let Err(e) = network.clear_content_hint() {
+                    tracing::warn!("Failed to clear content hint: {}", e);
+                }
+            }
+        }
+        if core.settings.history_write_window_ms != settings.history_write_window_ms {
+            let window = std::time::Duration::from_millis(settings.history_write_window_ms as u64);
+            if let Err(e) = core.storage.set_history_write_window(window) {
+                tracing::warn!("Failed to write buffered clipboard history: {}", e);
+            }
+        }
+        if core.settings.lan_only_mode != settings.lan_only_mode {
+            if let Some(ref network) = core.network {
+                network.set_lan_only(settings.lan_only_mode);
+            }
+        }
+        if core.settings.require_peer_unlocked != settings.require_peer_unlocked {
+            if let Some(ref network) = core.network {
+                network.set_require_peer_unlocked(settings.require_peer_unlocked);
+            }
+        }
+        let cap_changed = core.settings.monthly_relay_cap_bytes != settings.monthly_relay_cap_bytes;
+        let retention_changed = core.settings.history_days != settings.history_days;
+        core.settings = settings;
+        core.denylist = denylist;
+        if cap_changed {
+            account_relay_usage(core, true);
+        }
+        if retention_changed {
+            prune_history(core);
+        }
+        Ok(())
+    } else {
+        Err("Toss not initialized".to_string())
+    }
+}
+
+/// Report the device's current offset from UTC, e.g. 120 for UTC+02:00
+///
+/// Quiet hours are evaluated in this local time. Call again when the
+/// offset changes (time zone or daylight saving changes).
+#[frb(sync)]
+pub fn set_utc_offset(utc_offset_minutes: i32) -> Result<(), String> {
+    let offset = utc_offset_minutes
+        .checked_mul(60)
+        .and_then(chrono::FixedOffset::east_opt)
+        .ok_or_else(|| format!("Invalid UTC offset: {} minutes", utc_offset_minutes))?;
+
+    let mut guard = TOSS_INSTANCE.write();
+    let core = guard.as_mut().ok_or("Toss not initialized")?;
+    core.utc_offset = offset;
+    Ok(())
+}
+
+/// Get the local sync statistics
+///
+/// Counters only grow while `collect_sync_stats` is enabled.
+#[frb(sync)]
+pub fn get_sync_stats() -> Result<SyncStatsDto, String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    let stats = core
+        .storage
+        .stats()
+        .get_sync_stats()
+        .map_err(|e| format!("Failed to read sync statistics: {}", e))?;
+
+    Ok(SyncStatsDto {
+        send_attempts: stats.send_attempts,
+        send_successes: stats.send_successes,
+        relay_fallbacks: stats.relay_fallbacks,
+        failures: stats
+            .failures
+            .into_iter()
+            .map(|(category, count)| SyncFailureCountDto { category, count })
+            .collect(),
+    })
+}
+
+/// Reset the local sync statistics to zero
+#[frb(sync)]
+pub fn reset_sync_stats() -> Result<(), String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    core.storage
+        .stats()
+        .reset()
+        .map_err(|e| format!("Failed to reset sync statistics: {}", e))
+}
+
+/// Get this month's relay and internet traffic for every device that had any
+#[frb(sync)]
+pub fn get_relay_usage() -> Result<Vec<RelayUsageDto>, String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+    account_relay_usage(core, false);
+
+    let usage = core
+        .storage
+        .relay_usage()
+        .get_month(&relay_usage_month(core))
+        .map_err(|e| format!("Failed to read relay usage: {}", e))?;
+    let caps = core.relay_caps.lock().unwrap();
+    Ok(usage
+        .into_iter()
+        .map(|u| RelayUsageDto {
+            capped: caps.capped.contains(&u.device_id),
+            device_id: u.device_id,
+            month: u.month,
+            bytes_sent: u.bytes_sent,
+            bytes_received: u.bytes_received,
+        })
+        .collect())
+}
+
+/// Reset every device's relay usage to zero, lifting the monthly caps
+#[frb(sync)]
+pub fn reset_relay_usage() -> Result<(), String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    core.storage
+        .relay_usage()
+        .reset()
+        .map_err(|e| format!("Failed to reset relay usage: {}", e))?;
+    account_relay_usage(core, true);
+    Ok(())
+}
+
+/// Devices over `monthly_relay_cap_bytes`, and the month that was checked
+#[derive(Default)]
+struct RelayCaps {
+    month: String,
+    capped: HashSet<String>,
+}
+
+/// Calendar month relay usage is counted in, in the device's local time
+fn relay_usage_month(core: &TossCore) -> String {
+    chrono::Utc::now()
+        .with_timezone(&core.utc_offset)
+        .format("%Y-%m")
+        .to_string()
+}
+
+/// Store the metered traffic counted by the network, then cap or uncap
+/// devices against `monthly_relay_cap_bytes`
+///
+/// Caps are checked again when traffic was stored, a new month began or
+/// `force` is set. A device newly over its cap raises `RelayCapReached`.
+fn account_relay_usage(core: &TossCore, force: bool) {
+    let month = relay_usage_month(core);
+    let usage = core.storage.relay_usage();
+    let mut stored = false;
+    if let Some(ref network) = core.network {
+        for (device_id, bytes) in network.take_metered_traffic() {
+            let id = hex::encode(device_id);
+            match usage.add(&id, &month, bytes.sent, bytes.received) {
+                Ok(_) => stored = true,
+                Err(e) => tracing::warn!("Failed to record relay usage for {}: {}", id, e),
+            }
+        }
+    }
+
+    let mut caps = core.relay_caps.lock().unwrap();
+    if !stored && !force && caps.month == month {
+        return;
+    }
+    let cap = core.settings.monthly_relay_cap_bytes;
+    let over: HashMap<String, u64> = if cap == 0 {
+        HashMap::new()
+    } else {
+        match usage.get_month(&month) {
+            Ok(devices) => devices
+                .into_iter()
+                .filter(|u| u.bytes_sent >= cap)
+                .map(|u| (u.device_id, u.bytes_sent))
+                .collect(),
+            Err(e) => {
+                tracing::warn!("Failed to read relay usage: {}", e);
+                return;
+            }
+        }
+    };
+
+    let set_capped = |id: &str, capped: bool| {
+        let device_id: Option<[u8; 32]> = hex::decode(id).ok().and_then(|d| d.try_into().ok());
+        if let (Some(network), Some(device_id)) = (core.network.as_ref(), device_id) {
+            network.set_relay_capped(&device_id, capped);
+        }
+    };
+    for id in caps.capped.iter().filter(|id| !over.contains_key(*id)) {
+        set_capped(id, false);
+    }
+    for (id, &bytes_sent) in &over {
+        set_capped(id, true);
+        if !caps.capped.contains(id) {
+            tracing::info!("Device {} reached the monthly relay cap", id);
+            core.pending_events
+                .lock()
+                .unwrap()
+                .push_back(TossEvent::RelayCapReached {
+                    device_id: id.clone(),
+                    bytes_sent,
+                    cap_bytes: cap,
+                });
+        }
+    }
+    *caps = RelayCaps {
+        month,
+        capped: over.into_keys().collect(),
+    };
+}
+
+// ============================================================================
+// Logging
+// ============================================================================
+
+/// Change the log level at runtime
+///
+/// Accepts a level (`"trace"` to `"error"`) applied to Toss, or full filter
+/// directives such as `"toss_core=debug,quinn=warn"`.
+#[frb(sync)]
+pub fn set_log_level(level: String) -> Result<(), String> {
+    let filter = parse_log_filter(&level)?;
+
+    let guard = LOG_FILTER.read();
+    let handle = guard.as_ref().ok_or("Logging not initialized")?;
+    handle
+        .reload(filter)
+        .map_err(|e| format!("Failed to set log level: {}", e))?;
+
+    tracing::info!("Log level set to {}", level.trim());
+    Ok(())
+}
+
+/// Get up to `limit` of the most recent log lines, oldest first
+#[frb(sync)]
+pub fn get_recent_logs(limit: u32) -> Vec<String> {
+    LOG_BUFFER.recent(limit as usize)
+}
+
+/// Write buffered log records to the log file now
+///
+/// Records are written in the background, so call this before exporting
+/// logs or reporting an issue. Does nothing if logging isn't initialized.
+#[frb(sync)]
+pub fn flush_logs() {
+    if let Some(log_file) = LOG_FILE.read().as_ref() {
+        log_file.flush();
+    }
+}
+
+// ============================================================================
+// Diagnostics
+// ============================================================================
+
+/// Run a self-test of secure storage, crypto, the database, the clipboard
+/// and, if one is configured, the relay
+///
+/// Every check runs even if an earlier one fails. Nothing is left behind:
+/// the secure storage probe is deleted and the database write rolled back.
+#[frb]
+pub async fn run_self_test() -> Result<SelfTestReportDto, String> {
+    let (mut checks, relay) = {
+        let guard = TOSS_INSTANCE.read();
+        let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+        let checks = vec![
+            self_test_check(
+                "secure_storage",
+                probe_secure_storage().map_err(|e| e.to_string()),
+            ),
+            self_test_check("crypto", check_crypto_roundtrip()),
+            self_test_check("database", check_database(&core.storage)),
+            self_test_check(
+                "clipboard",
+                core.clipboard.read().map(|_| ()).map_err(|e| e.to_string()),
+            ),
+        ];
+        let relay = core
+            .settings
+            .relay_url
+            .clone()
+            .map(|url| (url, core.identity.clone()));
+        (checks, relay)
+    }; // Guard is dropped here
+
+    checks.push(match relay {
+        Some((url, identity)) => {
+            let relay = crate::network::RelayClient::new(url.trim_end_matches('/'), identity);
+            self_test_check(
+                "relay",
+                relay
+                    .fetch_identity()
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+            )
+        }
+        None => SelfTestCheckDto {
+            name: "relay".to_string(),
+            status: "skipped".to_string(),
+            error: None,
+        },
+    });
+
+    Ok(self_test_report(checks))
+}
+
+fn self_test_check(name: &str, result: Result<(), String>) -> SelfTestCheckDto {
+    let (status, error) = match result {
+        Ok(()) => ("passed", None),
+        Err(e) => ("failed", Some(e)),
+    };
+    SelfTestCheckDto {
+        name: name.to_string(),
+        status: status.to_string(),
+        error,
+    }
+}
+
+fn self_test_report(checks: Vec<SelfTestCheckDto>) -> SelfTestReportDto {
+    SelfTestReportDto {
+        passed: checks.iter().all(|check| check.status != "failed"),
+        checks,
+    }
+}
+
+/// Encrypt and decrypt a probe message with a fresh key
+fn check_crypto_roundtrip() -> Result<(), String> {
+    use rand::RngCore;
+    let mut key = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut key);
+
+    let plaintext = b"toss self-test";
+    let encrypted = encrypt(&key, plaintext, b"self-test").map_err(|e| e.to_string())?;
+    let decrypted = decrypt(&key, &encrypted, b"self-test").map_err(|e| e.to_string())?;
+    if decrypted != plaintext {
+        return Err("Decrypted data does not match".to_string());
+    }
+    if decrypt(&key, &encrypted, b"other").is_ok() {
+        return Err("Decryption accepted the wrong associated data".to_string());
+    }
+    Ok(())
+}
+
+/// Check that the data directory and the database both accept writes
+fn check_database(storage: &Storage) -> Result<(), String> {
+    if storage.is_in_memory() {
+        return Err("Database could not be opened; using temporary in-memory storage".to_string());
+    }
+    let data_dir = storage
+        .db_path()
+        .parent()
+        .ok_or("Database has no data directory")?;
+    let probe = data_dir.join(".self_test");
+    std::fs::write(&probe, b"toss")
+        .map_err(|e| format!("Data directory {} not writable: {}", data_dir.display(), e))?;
+    let _ = std::fs::remove_file(&probe);
+
+    storage
+        .check_writable()
+        .map_err(|e| format!("Database not writable: {}", e))
+}
+
+// ============================================================================
+// Network
+// ============================================================================
+
+/// Start networking
+#[frb]
+pub async fn start_network() -> Result<(), String> {
+    // Extract config while holding lock, then release before async operations
+    let (identity, config, get_public_key, get_session_key, scratchpad, screen_locked) = {
+        let guard = TOSS_INSTANCE.read();
+        let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+        let config = network_config(core);
+
+        // Create callbacks to retrieve session and identity keys from
+        // storage. File storage is read through a separate connection;
+        // in-memory storage only exists in the core instance.
+        let (get_public_key, get_session_key): (Arc<GetPublicKeyFn>, Arc<GetSessionKeyFn>) =
+            if core.storage.is_in_memory() {
+                (
+                    Arc::new(Box::new(|device_id: &[u8; 32]| {
+                        let guard = TOSS_INSTANCE.read();
+                        stored_public_key(&guard.as_ref()?.storage, device_id)
+                    })),
+                    Arc::new(Box::new(|device_id: &[u8; 32]| {
+                        let guard = TOSS_INSTANCE.read();
+                        stored_session_key(&guard.as_ref()?.storage, device_id)
+                    })),
+                )
+            } else {
+                let db_path = core.storage.db_path().to_path_buf();
+                let key_db_path = db_path.clone();
+                (
+                    Arc::new(Box::new(move |device_id: &[u8; 32]| {
+                        // Open a temporary connection to look up the identity key
+                        let storage = Storage::new(&key_db_path).ok()?;
+                        stored_public_key(&storage, device_id)
+                    })),
+                    Arc::new(Box::new(move |device_id: &[u8; 32]| {
+                        // Open a temporary connection to look up the session key
+                        let storage = Storage::new(&db_path).ok()?;
+                        stored_session_key(&storage, device_id)
+                    })),
+                )
+            };
+
+        (
+            core.identity.clone(),
+            config,
+            get_public_key,
+            get_session_key,
+            core.scratchpad.clone(),
+            core.screen_locked,
+        )
+    };
+
+    // Perform async operations without holding lock
+    let mut network = NetworkManager::builder()
+        .identity(identity)
+        .config(config)
+        .public_key_fn(move |device_id| get_public_key(device_id))
+        .session_key_fn(move |device_id| get_session_key(device_id))
+        .scratchpad(scratchpad)
+        .build()
+        .await
+        .map_err(|e| format!("Network init failed: {}", e))?;
+    network.set_local_locked(screen_locked);
+
+    network
+        .start()
+        .await
+        .map_err(|e| format!("Network start failed: {}", e))?;
+
+    // Re-acquire lock to store network and subscribe to events
+    {
+        let mut guard = TOSS_INSTANCE.write();
+        let core = guard.as_mut().ok_or("Toss not initialized")?;
+        let receiver = network.subscribe();
+        core.event_receiver = Some(Arc::new(tokio::sync::Mutex::new(receiver)));
+        if let Some(incoming) = network.take_incoming() {
+            tokio::spawn(process_incoming(incoming));
+        }
+        core.network = Some(Arc::new(network));
+        // Carry the caps over to the new network manager
+        account_relay_usage(core, true);
+        if let Some(task) = core.history_prune_task.take() {
+            task.abort();
+        }
+        core.history_prune_task = Some(tokio::spawn(prune_history_periodically()));
+    }
+
+    Ok(())
+}
+
+/// Session key stored for a paired device
+fn stored_session_key(storage: &Storage, device_id: &[u8; 32]) -> Option<[u8; 32]> {
+    let device = storage
+        .devices()
+        .get_device(&hex::encode(device_id))
+        .ok()??;
+    device.session_key?.try_into().ok()
+}
+
+/// Identity key of a paired device, for checking its signatures
+fn stored_public_key(storage: &Storage, device_id: &[u8; 32]) -> Option<[u8; 32]> {
+    let device = storage
+        .devices()
+        .get_device(&hex::encode(device_id))
+        .ok()??;
+    device.public_key.try_into().ok()
+}
+
+/// Network configuration shared by the network manager and pairing coordinators
+///
+/// Both must use the same mDNS service types to see each other.
+fn network_config(core: &TossCore) -> NetworkConfig {
+    NetworkConfig {
+        device_name: core.device_name.clone(),
+        relay_url: core.settings.relay_url.clone(),
+        relay_pinned_key: core
+            .settings
+            .relay_pinned_key
+            .as_deref()
+            .and_then(decode_relay_key),
+        keyed_content_hash: core.settings.keyed_content_hash,
+        lan_only: core.settings.lan_only_mode,
+        require_peer_unlocked: core.settings.require_peer_unlocked,
+        service_name: core.settings.service_name.clone(),
+        // The largest content accepted, with room for the update around it
+        max_transfer_bytes: (core.settings.max_file_size_mb as u64 + 1) * 1024 * 1024,
+        ..Default::default()
+    }
+}
+
+/// Decode a base64 relay identity key
+fn decode_relay_key(key: &str) -> Option<[u8; 32]> {
+    base64::Engine::decode(&base64::engine::general_purpose::STANDARD, key.trim())
+        .ok()?
+        .try_into()
+        .ok()
+}
+
+/// Stop networking
+#[frb]
+pub async fn stop_network() {
+    // Extract network while holding lock, then release before async operation
+    let network = {
+        let mut guard = TOSS_INSTANCE.write();
+        guard.as_mut().and_then(|core| {
+            std::mem::take(&mut *core.send_cancel.lock().unwrap()).cancel();
+            core.network.take()
+        })
+    };
+
+    if let Some(network) = network {
+        stop_reclaimed_network(network).await;
+    }
+}
+
+/// How often `reclaim_network` checks whether the network is still in use
+const NETWORK_RECLAIM_POLL: std::time::Duration = std::time::Duration::from_millis(20);
+
+/// How long `reclaim_network` waits for calls still using the network
+const NETWORK_RECLAIM_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Clone the running network out of `TossCore`, to await on it without
+/// holding the lock
+fn running_network() -> Option<Arc<NetworkManager>> {
+    TOSS_INSTANCE.read().as_ref()?.network.clone()
+}
+
+/// Pass the messages the network reads from peers to `process_message`
+///
+/// Ends once the network is dropped. The network is looked up for each
+/// message, so it can still be taken out of `TossCore` to be stopped or
+/// re-initialized; messages arriving meanwhile are dropped.
+async fn process_incoming(mut incoming: tokio::sync::mpsc::Receiver<IncomingMessage>) {
+    while let Some((device_id, message)) = incoming.recv().await {
+        let Some(network) = running_network() else {
+            tracing::debug!(
+                "Dropping message from {}, network is restarting",
+                hex::encode(device_id)
+            );
+            continue;
+        };
+        if let Err(e) = network.process_message(&device_id, message).await {
+            tracing::warn!(
+                "Failed to process message from {}: {}",
+                hex::encode(device_id),
+                e
+            );
+        }
+    }
+}
+
+/// Take sole ownership of a network taken out of `TossCore`, to stop or
+/// re-initialize it
+///
+/// Calls that cloned the network before it was taken keep using it until
+/// they return, so this waits for them, for up to `NETWORK_RECLAIM_TIMEOUT`.
+/// Returns the still shared network if they don't finish in time.
+async fn reclaim_network(
+    mut network: Arc<NetworkManager>,
+) -> Result<NetworkManager, Arc<NetworkManager>> {
+    let deadline = tokio::time::Instant::now() + NETWORK_RECLAIM_TIMEOUT;
+    loop {
+        match Arc::try_unwrap(network) {
+            Ok(network) => return Ok(network),
+            Err(shared) if tokio::time::Instant::now() >= deadline => return Err(shared),
+            Err(shared) => network = shared,
+        }
+        tokio::time::sleep(NETWORK_RECLAIM_POLL).await;
+    }
+}
+
+/// Stop a network taken out of `TossCore`
+///
+/// If calls still using it don't finish in time, it's left to stop itself
+/// when the last of them drops it.
+async fn stop_reclaimed_network(network: Arc<NetworkManager>) {
+    match reclaim_network(network).await {
+        Ok(mut network) => network.stop().await,
+        Err(_) => tracing::warn!("Network still in use, stopping it once released"),
+    }
+}
+
+/// Check a relay server and report its identity key
+///
+/// The relay must sign a fresh challenge with the key it reports, so the
+/// result can be pinned as `relay_pinned_key` (trust on first use).
+#[frb]
+pub async fn test_relay(url: String) -> Result<RelayIdentityDto, String> {
+    let (identity, pinned_key) = {
+        let guard = TOSS_INSTANCE.read();
+        let core = guard.as_ref().ok_or("Toss not initialized")?;
+        let pinned_key = core
+            .settings
+            .relay_pinned_key
+            .as_deref()
+            .and_then(decode_relay_key);
+        (core.identity.clone(), pinned_key)
+    };
+
+    let relay = crate::network::RelayClient::new(url.trim_end_matches('/'), identity);
+    let public_key = relay
+        .fetch_identity()
+        .await
+        .map_err(|e| format!("Relay check failed: {}", e))?;
+
+    Ok(RelayIdentityDto {
+        public_key: base64::Engine::encode(&base64::engine::general_purpose::STANDARD, public_key),
+        matches_pinned: pinned_key.map(|pinned| pinned == public_key),
+    })
+}
+
+/// Get how many messages the relay holds for this device, and the oldest
+///
+/// Queued messages are normally delivered when the relay connection comes
+/// up; a queue that keeps growing means delivery is failing.
+#[frb]
+pub async fn get_relay_queue_status() -> Result<RelayQueueStatusDto, String> {
+    let status = configured_relay_client()?
+        .queue_status()
+        .await
+        .map_err(|e| format!("Failed to get relay queue status: {}", e))?;
+
+    Ok(RelayQueueStatusDto {
+        count: status.count,
+        oldest_queued_at: status.oldest_queued_at,
+    })
+}
+
+/// Drop every message the relay holds for this device, returning how many
+///
+/// Recovers a queue that can't be delivered, e.g. one holding a message the
+/// device fails to read. The dropped messages are lost.
+#[frb]
+pub async fn flush_relay_queue() -> Result<u64, String> {
+    configured_relay_client()?
+        .flush_queue()
+        .await
+        .map_err(|e| format!("Failed to flush relay queue: {}", e))
+}
+
+/// Client for one-off requests to the relay in settings
+fn configured_relay_client() -> Result<crate::network::RelayClient, String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+    let url = core
+        .settings
+        .relay_url
+        .as_deref()
+        .ok_or("No relay server configured")?;
+
+    Ok(crate::network::RelayClient::new_with_device_name(
+        url.trim_end_matches('/'),
+        core.identity.clone(),
+        &core.device_name,
+    ))
+}
+
+/// Notify that the OS network connectivity changed
+///
+/// Re-binds sockets, restarts mDNS, reconnects the relay and re-runs NAT
+/// discovery. Rapid successive calls are debounced. Does nothing if networking
+/// has not been started.
+#[frb]
+pub async fn notify_network_changed() -> Result<(), String> {
+    // Extract network while holding lock, then release before async operation
+    let network = {
+        let mut guard = TOSS_INSTANCE.write();
+        let core = guard.as_mut().ok_or("Toss not initialized")?;
+        core.network.take()
+    };
+
+    let mut network = match network {
+        Some(network) => match reclaim_network(network).await {
+            Ok(network) => network,
+            Err(shared) => {
+                // Keep it running as it is; the next notification tries again
+                if let Some(core) = TOSS_INSTANCE.write().as_mut() {
+                    core.network.get_or_insert(shared);
+                }
+                return Err("Network is busy, try again".to_string());
+            }
+        },
+        None => return Ok(()),
+    };
+
+    let result = network.handle_network_change().await;
+
+    // Put the network back, unless Toss was shut down in the meantime
+    let orphaned = {
+        let mut guard = TOSS_INSTANCE.write();
+        match guard.as_mut() {
+            Some(core) => {
+                core.network = Some(Arc::new(network));
+                None
+            }
+            None => Some(network),
+        }
+    };
+    if let Some(mut network) = orphaned {
+        network.stop().await;
+    }
+
+    let reinitialized = result.map_err(|e| format!("Failed to handle network change: {}", e))?;
+    if reinitialized {
+        readvertise_pairing().await;
+    }
+    Ok(())
+}
+
+/// Re-register an active pairing code via mDNS after a network change
+async fn readvertise_pairing() {
+    let (code, public_key, config) = {
+        let guard = TOSS_INSTANCE.read();
+        let Some(core) = guard.as_ref() else {
+            return;
+        };
+        let Some(session) = core.pairing_session.as_ref() else {
+            return;
+        };
+        let info = session.info(&core.device_name);
+        (info.code, info.public_key, network_config(core))
+    };
+
+    let coordinator = match crate::pairing::PairingCoordinator::new_with_service_type(
+        &config.device_name,
+        config.relay_url.clone(),
+        &config.pairing_service_type(),
+    ) {
+        Ok(coordinator) => coordinator.with_registration_retry(config.mdns_registration_retry),
+        Err(e) => {
+            tracing::warn!("Failed to create pairing coordinator: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = coordinator.advertise_mdns(&code, &public_key).await {
+        tracing::warn!(
+            "Failed to re-advertise pairing code after network change: {}",
+            e
+        );
+    }
+}
+
+/// Transfers currently being sent or received
+#[frb(sync)]
+pub fn get_active_transfers() -> Vec<TransferProgressDto> {
+    let guard = TOSS_INSTANCE.read();
+    let Some(network) = guard.as_ref().and_then(|core| core.network.as_ref()) else {
+        return Vec::new();
+    };
+
+    network
+        .active_transfers()
+        .into_iter()
+        .map(|progress| TransferProgressDto {
+            transfer_id: progress.transfer_id,
+            direction: transfer_direction(progress.direction).to_string(),
+            device_id: hex::encode(progress.device_id),
+            total_bytes: progress.total_bytes,
+            received_bytes: progress.transferred_bytes,
+            percent: progress.percent(),
+        })
+        .collect()
+}
+
+fn transfer_direction(direction: TransferDirection) -> &'static str {
+    match direction {
+        TransferDirection::Incoming => "incoming",
+        TransferDirection::Outgoing => "outgoing",
+    }
+}
+
+/// Cancel a transfer and notify the peer
+///
+/// Frees the transfer's buffers on this device even if the peer can't be
+/// reached.
+#[frb]
+pub async fn cancel_transfer(transfer_id: u64) -> Result<(), String> {
+    let network = running_network().ok_or("Network not started")?;
+    match network.cancel_transfer(transfer_id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!("Unknown transfer: {}", transfer_id)),
+        Err(e) => {
+            tracing::warn!("Failed to notify peer of canceled transfer: {}", e);
+            Ok(())
+        }
+    }
+}
+
+/// Check that a connected device stored the same session key as this one
+///
+/// Runs an encrypted challenge-response over the direct connection. `true`
+/// means the connection is verified; `false` means the stored keys differ
+/// (or the device didn't answer in time) and it should be paired again.
+#[frb]
+pub async fn verify_session_key(device_id: String) -> Result<bool, String> {
+    let device_id: [u8; 32] = hex::decode(&device_id)
+        .ok()
+        .and_then(|id| id.try_into().ok())
+        .ok_or("Invalid device ID")?;
+    let network = running_network().ok_or("Network not started")?;
+    network
+        .verify_session_key(&device_id, KEY_CHECK_TIMEOUT)
+        .await
+        .map_err(|e| match e {
+            crate::error::NetworkError::PeerNotFound(_) => "Device not connected".to_string(),
+            crate::error::NetworkError::NotAuthenticated => "Device not paired".to_string(),
+            e => format!("Key check failed: {}", e),
+        })
+}
+
+/// Start listening to network events
+/// Returns a receiver that can be polled for events
+/// Note: Full stream support requires flutter_rust_bridge stream support
+#[frb]
+pub async fn start_event_listener() -> Result<(), String> {
+    let mut guard = TOSS_INSTANCE.write();
+    let core = guard.as_mut().ok_or("Toss not initialized")?;
+
+    if let Some(ref network) = core.network {
+        // Subscribe to network events and store the receiver for polling
+        let receiver = network.subscribe();
+        core.event_receiver = Some(Arc::new(tokio::sync::Mutex::new(receiver)));
+    }
+
+    Ok(())
+}
+
+/// Write the update held during quiet hours once they are over
+///
+/// Returns its `ClipboardReceived` event, which was withheld until now.
+fn apply_held_update() -> Option<TossEvent> {
+    let mut guard = TOSS_INSTANCE.write();
+    let core = guard.as_mut()?;
+    let (content, item) = core.quiet_hours_queue.release(
+        core.settings.quiet_hours(),
+        chrono::Utc::now(),
+        core.utc_offset,
+    )?;
+
+    if let Err(e) = core.clipboard.write(&content) {
+        tracing::warn!(
+            "Failed to write clipboard content held during quiet hours: {}",
+            e
+        );
+    } else {
+        // Update monitor hash to prevent re-syncing this content
+        core.clipboard.monitor_mut().update_hash(&content);
+    }
+    Some(TossEvent::ClipboardReceived { item })
+}
+
+/// Events that don't come from the network
+///
+/// Releases an update held for quiet hours once they end, does periodic
+/// housekeeping, and pops events raised outside the network manager.
+fn queued_event() -> Option<TossEvent> {
+    if let Some(event) = apply_held_update() {
+        return Some(event);
+    }
+
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref()?;
+
+    // Write out a held-back burst once its window has passed
+    if let Err(e) = core.storage.write_due_history() {
+        tracing::warn!("Failed to write buffered clipboard history: {}", e);
+    }
+    account_relay_usage(core, false);
+
+    let event = core.pending_events.lock().unwrap().pop_front();
+    event
+}
+
+/// Poll for network events
+/// Returns the next event if available, or None
+/// Note: This uses try_recv which is non-blocking. While `listen_events` is
+/// running it owns the receiver, and this only returns queued events.
+#[frb(sync)]
+pub fn poll_event() -> Option<TossEvent> {
+    if let Some(event) = queued_event() {
+        return Some(event);
+    }
+
+    let receiver = {
+        let guard = TOSS_INSTANCE.read();
+        guard.as_ref()?.event_receiver.clone()?
+    };
+    let next = try_recv_network_event(&mut *receiver.try_lock().ok()?);
+    match next {
+        Ok(event) => network_event(event),
+        // No event available, channel closed, or events dropped
+        Err(lost) => lost.map(|count| TossEvent::EventsLost { count }),
+    }
+}
+
+/// How long `listen_events` waits on the network before checking queued events
+const LISTEN_TICK: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Deliver events to `emit` as they happen, until it returns false
+///
+/// The streaming alternative to `poll_event`: the Flutter wrapper passes a
+/// closure adding to a `StreamSink`, and returns false once the sink is
+/// closed. Events are only taken from the network as `emit` accepts them, so
+/// a slow listener never queues more than the event channel buffers, and
+/// overflowing it is reported as `EventsLost`. After the network restarts
+/// the new event receiver is picked up automatically. Returns once Toss is
+/// shut down.
+#[frb(ignore)]
+pub async fn listen_events<F>(mut emit: F)
+where
+    F: FnMut(TossEvent) -> bool + Send,
+{
+    loop {
+        while let Some(event) = queued_event() {
+            if !emit(event) {
+                return;
+            }
+        }
+
+        // Re-read each time, so a restarted network's receiver is used
+        let receiver = {
+            let guard = TOSS_INSTANCE.read();
+            match guard.as_ref() {
+                Some(core) => core.event_receiver.clone(),
+                None => return,
+            }
+        };
+        let Some(receiver) = receiver else {
+            tokio::time::sleep(LISTEN_TICK).await;
+            continue;
+        };
+
+        let next = tokio::time::timeout(LISTEN_TICK, async {
+            recv_network_event(&mut *receiver.lock().await).await
+        })
+        .await;
+        let event = match next {
+            Err(_) => continue, // Nothing yet, check queued events again
+            Ok(Ok(event)) => network_event(event),
+            Ok(Err(Some(count))) => Some(TossEvent::EventsLost { count }),
+            Ok(Err(None)) => {
+                // The network stopped; wait for it to be restarted
+                tokio::time::sleep(LISTEN_TICK).await;
+                continue;
+            }
+        };
+        if let Some(event) = event {
+            if !emit(event) {
+                return;
+            }
+        }
+    }
+}
+
+/// The event to report for a network event, if any
+fn network_event(event: NetworkEvent) -> Option<TossEvent> {
+    // Taken once: the lock isn't reentrant, and received content is
+    // written to the clipboard
+    let mut guard = TOSS_INSTANCE.write();
+    let core = guard.as_mut()?;
+    core_network_event(core, event)
+}
+
+/// The event to report for a network event, handled against `core`
+fn core_network_event(core: &mut TossCore, event: NetworkEvent) -> Option<TossEvent> {
+    match event {
+        NetworkEvent::PeerConnected {
+            device_id,
+            device_name,
+        } => {
+            let id = hex::encode(device_id);
+            let devices = core.storage.devices();
+            // Track the name a paired device advertises without touching its nickname
+            let name = match devices.get_device(&id) {
+                Ok(Some(mut stored)) => {
+                    if !device_name.is_empty() && stored.advertised_name != device_name {
+                        let _ = devices.update_advertised_name(&id, &device_name);
+                        stored.advertised_name = device_name;
+                    }
+                    stored.display_name().to_string()
+                }
+                _ => device_name,
+            };
+            Some(TossEvent::DeviceConnected {
+                device: DeviceInfoDto {
+                    id,
+                    name,
+                    is_online: true,
+                    last_seen: 0,
+                    platform: "unknown".to_string(), // Platform info not available in event yet
+                },
+            })
+        }
+        NetworkEvent::PeerDisconnected { device_id } => {
+            core.presence.forget(&device_id);
+            Some(TossEvent::DeviceDisconnected {
+                device_id: hex::encode(device_id),
+            })
+        }
+        NetworkEvent::MessageReceived {
+            from_device_id,
+            message,
+        } => {
+            // Verify that the message is from a paired device and not from ourselves
+            let is_paired = matches!(
+                core.storage
+                    .devices()
+                    .get_device(&hex::encode(from_device_id)),
+                Ok(Some(_))
+            );
+            let is_self = from_device_id == *core.identity.device_id();
+
+            // Only process messages from paired devices (not ourselves)
+            if !is_paired {
+                tracing::warn!(
+                    "Received message from unpaired device: {}",
+                    hex::encode(from_device_id)
+                );
+                return None;
+            }
+
+            // Ignore messages from ourselves to prevent self-sync loops
+            if is_self {
+                tracing::debug!("Ignoring message from self");
+                return None;
+            }
+
+            // Only messages the device's session accepted get this far
+            if let Err(e) = core
+                .storage
+                .devices()
+                .update_last_seen(&hex::encode(from_device_id))
+            {
+                tracing::warn!("Failed to record device last seen: {}", e);
+            }
+
+            // Convert Message to ClipboardItemDto if it's a clipboard update
+            if let crate::protocol::Message::ClipboardUpdate(update) = message {
+                // The hash covers the raw data bytes, so a mismatch means
+                // the content was altered in transit. A keyed hash is
+                // checked with the pairing's session key.
+                let session_key = if update.hash_keyed {
+                    stored_session_key(&core.storage, &from_device_id)
+                } else {
+                    None
+                };
+                if !update.verify_hash(session_key.as_ref()) {
+                    tracing::warn!(
+                        "Rejecting clipboard update with mismatched content hash from device {}",
+                        hex::encode(from_device_id)
+                    );
+                    return None;
+                }
+
+                // Drop an update overtaken by a later one from the same device
+                if !core
+                    .conflicts
+                    .accept_sequence(&from_device_id, update.sequence)
+                {
+                    tracing::debug!(
+                        "Dropping stale clipboard update {} from device {}",
+                        update.sequence,
+                        hex::encode(from_device_id)
+                    );
+                    return None;
+                }
+
+                // Validate content size limit
+                let max_size = (core.settings.max_file_size_mb as u64) * 1024 * 1024;
+
+                if update.content.metadata.size_bytes > max_size {
+                    tracing::warn!("Received clipboard content exceeds size limit ({} bytes > {} bytes) from device {}", 
+                        update.content.metadata.size_bytes, max_size, hex::encode(from_device_id));
+                    return None;
+                }
+
+                // Check settings and write to clipboard if sync is enabled for this
+                // content type and the update isn't older than the current content.
+                // During quiet hours it is held and written once they end.
+                // Files bound for a directory leave the clipboard alone, so
+                // they are neither held for quiet hours nor superseded.
+                let save_dir = (update.content.content_type == ContentType::File)
+                    .then(|| core.settings.file_receive_directory.clone())
+                    .flatten();
+                let (should_write, superseded, held) =
+                    if !core.settings.receives(update.content.content_type) {
+                        (false, false, false)
+                    } else if save_dir.is_some() {
+                        (true, false, false)
+                    } else {
+                        let resolution = core
+                            .conflicts
+                            .resolve_remote(update.sent_at, from_device_id);
+                        let superseded = resolution == Resolution::Superseded;
+                        let held = !superseded && core.in_quiet_hours();
+                        (!superseded && !held, superseded, held)
+                    };
+                if superseded {
+                    tracing::debug!(
+                        "Clipboard update from device {} lost to a concurrent copy, keeping it in history only",
+                        hex::encode(from_device_id)
+                    );
+                }
+
+                // Save to the receive directory, or write to clipboard if sync
+                // is enabled for this content type
+                let mut saved_to = None;
+                if let (true, Some(dir)) = (should_write, &save_dir) {
+                    match save_received_file(std::path::Path::new(dir), &update.content) {
+                        Ok(path) => saved_to = Some(path),
+                        Err(e) => tracing::warn!("Failed to save received file: {}", e),
+                    }
+                } else if should_write {
+                    if let Err(e) = core.clipboard.write(&update.content) {
+                        tracing::warn!("Failed to write received clipboard content: {}", e);
+                    } else {
+                        // Update monitor hash to prevent re-syncing this content
+                        core.clipboard.monitor_mut().update_hash(&update.content);
+                    }
+                }
+
+                // Requested actions only follow updates that were applied now,
+                // and are reported after the update itself
+                if should_write {
+                    if let Some(event) =
+                        remote_action_event(&core.settings, &update, from_device_id)
+                    {
+                        core.pending_events.lock().unwrap().push_back(event);
+                    }
+                }
+
+                // Save to history if kept for this content type (with encryption)
+                if let Err(e) = store_received_history(
+                    &core.settings,
+                    &core.storage,
+                    &core.identity,
+                    &update.content,
+                    &from_device_id,
+                    superseded,
+                ) {
+                    tracing::warn!("Failed to save received clipboard history: {}", e);
+                }
+
+                // Return event for Flutter, or hold it until quiet hours end
+                let item = ClipboardItemDto {
+                    superseded,
+                    ..clipboard_item_dto(&update.content, Some(hex::encode(from_device_id)))
+                };
+                if held {
+                    core.quiet_hours_queue.hold((update.content, item));
+                    return None;
+                }
+                if let Some(path) = saved_to {
+                    return Some(TossEvent::FileReceived {
+                        path: path.to_string_lossy().into_owned(),
+                        device_id: hex::encode(from_device_id),
+                    });
+                }
+                Some(TossEvent::ClipboardReceived { item })
+            } else if let crate::protocol::Message::Presence(presence) = message {
+                presence_event(
+                    &core.settings,
+                    &core.presence,
+                    &presence,
+                    from_device_id,
+                    std::time::Instant::now(),
+                )
+            } else if let crate::protocol::Message::IdentityMigration(migration) = message {
+                // The network layer checked it against the device's current key
+                let old_device_id = hex::encode(from_device_id);
+                let new_device_id = hex::encode(migration.new_device_id());
+                match core.storage.devices().migrate_identity(
+                    &old_device_id,
+                    &new_device_id,
+                    &migration.new_public_key,
+                ) {
+                    Ok(true) => Some(TossEvent::DeviceIdentityChanged {
+                        old_device_id,
+                        new_device_id,
+                    }),
+                    Ok(false) => None,
+                    Err(e) => {
+                        tracing::warn!("Failed to migrate device identity: {}", e);
+                        None
+                    }
+                }
+            } else if let crate::protocol::Message::ScratchpadUpdate(_) = message {
+                // The network manager already applied it to the shared document
+                Some(TossEvent::ScratchpadChanged {
+                    text: core.scratchpad.text(),
+                    device_id: hex::encode(from_device_id),
+                })
+            } else if let crate::protocol::Message::DeviceRemoved(_) = message {
+                // The peer unpaired us, so stop treating it as paired
+                let device_id = hex::encode(from_device_id);
+                if let Err(e) = core.storage.devices().remove_device(&device_id) {
+                    tracing::warn!("Failed to remove unpaired device: {}", e);
+                }
+                Some(TossEvent::DeviceDisconnected { device_id })
+            } else {
+                None
+            }
+        }
+        NetworkEvent::Error(msg) => Some(TossEvent::Error { message: msg }),
+        NetworkEvent::TransferProgress(progress) => Some(TossEvent::TransferProgress {
+            transfer_id: progress.transfer_id,
+            direction: transfer_direction(progress.direction).to_string(),
+            device_id: hex::encode(progress.device_id),
+            total_bytes: progress.total_bytes,
+            transferred_bytes: progress.transferred_bytes,
+        }),
+        NetworkEvent::PeerDiscovered(_) | NetworkEvent::PeerLost(_) => {
+            // These events are less critical for Flutter UI
+            None
+        }
+    }
+}
+
+/// The event for the action a received update requests, if this device acts on it
+///
+/// Requires `allow_remote_actions`, and content that suits the action: an
+/// `OpenUrl` request is only honored for a URL with an http or https scheme.
+fn remote_action_event(
+    settings: &TossSettings,
+    update: &ClipboardUpdate,
+    from_device_id: [u8; 32],
+) -> Option<TossEvent> {
+    if !settings.allow_remote_actions {
+        return None;
+    }
+    match update.auto_action? {
+        AutoAction::OpenUrl => {
+            if update.content.content_type != ContentType::Url {
+                return None;
+            }
+            let url = update.content.as_text()?.trim().to_string();
+            is_web_url(&url).then(|| TossEvent::OpenUrlRequested {
+                url,
+                device_id: hex::encode(from_device_id),
+            })
+        }
+    }
+}
+
+/// The event for a peer's presence hint, unless presence is off or throttled
+///
+/// Presence never touches the clipboard or history.
+fn presence_event(
+    settings: &TossSettings,
+    throttle: &PresenceThrottle,
+    presence: &Presence,
+    from_device_id: [u8; 32],
+    now: std::time::Instant,
+) -> Option<TossEvent> {
+    if !settings.share_presence || !throttle.allow_receive(&from_device_id, presence.state, now) {
+        return None;
+    }
+    Some(TossEvent::PresenceChanged {
+        device_id: hex::encode(from_device_id),
+        state: presence.state.as_str().to_string(),
+    })
+}
+
+/// Receive the next network event without blocking
+///
+/// On error, returns how many events were dropped when the receiver fell
+/// behind (it then resumes at the oldest buffered event), to report as
+/// `EventsLost`, or `None` when no event is available.
+fn try_recv_network_event(
+    receiver: &mut tokio::sync::broadcast::Receiver<NetworkEvent>,
+) -> Result<NetworkEvent, Option<u64>> {
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    receiver.try_recv().map_err(|e| match e {
+        TryRecvError::Lagged(count) => {
+            tracing::warn!("Event receiver fell behind; {} events were dropped", count);
+            Some(count)
+        }
+        TryRecvError::Empty | TryRecvError::Closed => None,
+    })
+}
+
+/// Wait for the next network event, returning how many were dropped as the error
+///
+/// `Err(None)` means the channel closed because the network stopped.
+async fn recv_network_event(
+    receiver: &mut tokio::sync::broadcast::Receiver<NetworkEvent>,
+) -> Result<NetworkEvent, Option<u64>> {
+    use tokio::sync::broadcast::error::RecvError;
+
+    receiver.recv().await.map_err(|e| match e {
+        RecvError::Lagged(count) => {
+            tracing::warn!("Event receiver fell behind; {} events were dropped", count);
+            Some(count)
+        }
+        RecvError::Closed => None,
+    })
+}
+
+/// Get clipboard history
+#[frb(sync)]
+pub fn get_clipboard_history(limit: Option<u32>) -> Vec<ClipboardItemDto> {
+    let guard = TOSS_INSTANCE.read();
+    let core = match guard.as_ref() {
+        Some(c) => c,
+        None => return Vec::new(),
+    };
+
+    let history_items = match core.storage.history().get_all_items(limit) {
+        Ok(items) => items,
+        Err(_) => return Vec::new(),
+    };
+
+    history_items
+        .into_iter()
+        .map(|item| {
+            let size_bytes = item.encrypted_content.len() as u64;
+            history_item_dto(item, size_bytes)
+        })
+        .collect()
+}
+
+/// Get clipboard history grouped by local calendar day and source device
+///
+/// `utc_offset_minutes` is the device's current offset from UTC (e.g. 120 for
+/// UTC+02:00). Groups are ordered by their newest item.
+#[frb(sync)]
+pub fn get_history_grouped(limit: Option<u32>, utc_offset_minutes: i32) -> Vec<HistoryGroupDto> {
+    let guard = TOSS_INSTANCE.read();
+    let core = match guard.as_ref() {
+        Some(c) => c,
+        None => return Vec::new(),
+    };
+
+    let offset = utc_offset_minutes
+        .checked_mul(60)
+        .and_then(chrono::FixedOffset::east_opt)
+        .unwrap_or_else(|| {
+            tracing::warn!(
+                "Invalid UTC offset {} minutes, using UTC",
+                utc_offset_minutes
+            );
+            chrono::FixedOffset::east_opt(0).expect("zero offset")
+        });
+
+    let groups = match core.storage.history().get_grouped_items(limit, offset) {
+        Ok(groups) => groups,
+        Err(_) => return Vec::new(),
+    };
+
+    groups
+        .into_iter()
+        .map(|group| HistoryGroupDto {
+            day: group.day.format("%Y-%m-%d").to_string(),
+            source_device: group.source_device,
+            items: group
+                .items
+                .into_iter()
+                .map(|item| {
+                    let size_bytes = item.encrypted_content.len() as u64;
+                    history_item_dto(item, size_bytes)
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Remove clipboard history item
+#[frb(sync)]
+pub fn remove_history_item(item_id: String) -> Result<(), String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    core.storage
+        .history()
+        .remove_item(&item_id)
+        .map_err(|e| format!("Failed to remove history item: {}", e))?;
+
+    Ok(())
+}
+
+/// Clear clipboard history
+#[frb(sync)]
+pub fn clear_clipboard_history() -> Result<(), String> {
+    let guard = TOSS_INSTANCE.read();
     let core = guard.as_ref().ok_or("Toss not initialized")?;
 
     core.storage
@@ -1387,6 +4331,101 @@ pub fn clear_clipboard_history() -> Result<(), String> {
     Ok(())
 }
 
+/// Delete history older than `history_days` now
+///
+/// Also done every hour once the network is started. Returns how many items
+/// were deleted; none when `history_days` is 0.
+#[frb(sync)]
+pub fn prune_history_now() -> Result<u32, String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    prune_expired_history(
+        core.storage.history(),
+        core.settings.history_days,
+        unix_now_secs(),
+    )
+    .map(|count| count as u32)
+    .map_err(|e| format!("Failed to prune history: {}", e))
+}
+
+/// How often history past `history_days` is pruned
+const HISTORY_PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Prune expired history now and every `HISTORY_PRUNE_INTERVAL`, until Toss is shut down
+async fn prune_history_periodically() {
+    let mut interval = tokio::time::interval(HISTORY_PRUNE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let pruned = {
+            let guard = TOSS_INSTANCE.read();
+            guard.as_ref().map(prune_history)
+        };
+        if pruned.is_none() {
+            return;
+        }
+    }
+}
+
+/// Prune expired history, logging the outcome
+fn prune_history(core: &TossCore) {
+    match prune_expired_history(
+        core.storage.history(),
+        core.settings.history_days,
+        unix_now_secs(),
+    ) {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("Pruned {} expired history items", count),
+        Err(e) => tracing::warn!("Failed to prune history: {}", e),
+    }
+}
+
+/// Delete history items created more than `history_days` days before `now`
+///
+/// Their previews and stored representations go with them.
+fn prune_expired_history(
+    history: &dyn HistoryStore,
+    history_days: u32,
+    now: u64,
+) -> rusqlite::Result<usize> {
+    if history_days == 0 {
+        return Ok(0);
+    }
+    let cutoff = now.saturating_sub(history_days as u64 * 24 * 60 * 60);
+    history.prune(HistoryPrune::OlderThan(cutoff))
+}
+
+/// Seconds since the Unix epoch
+fn unix_now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Find history items whose content can no longer be decrypted
+///
+/// A row only partly written, or a storage key that has since changed,
+/// leaves items that fail to load. Returns their IDs, oldest first.
+#[frb]
+pub async fn find_corrupt_history_items() -> Result<Vec<String>, String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    find_corrupt_history(&core.storage, &core.identity)
+}
+
+/// Remove every history item whose content can no longer be decrypted
+///
+/// Returns the number of items removed.
+#[frb]
+pub async fn remove_corrupt_history_items() -> Result<u32, String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    remove_corrupt_history(&core.storage, &core.identity)
+}
+
 /// Get connected devices
 #[frb(sync)]
 pub fn get_connected_devices() -> Vec<DeviceInfoDto> {
@@ -1409,110 +4448,668 @@ pub fn get_connected_devices() -> Vec<DeviceInfoDto> {
     Vec::new()
 }
 
-/// Decrypt and retrieve session key for a paired device
-/// This is used internally when establishing connections with stored devices
+/// Get the last end-to-end sync latency to a device in milliseconds
+/// Returns None if no latency has been reported yet
+#[frb(sync)]
+pub fn get_last_sync_latency(device_id: String) -> Option<u64> {
+    let device_id: [u8; 32] = hex::decode(&device_id).ok()?.try_into().ok()?;
+
+    let guard = TOSS_INSTANCE.read();
+    let network = guard.as_ref()?.network.as_ref()?;
+    network.last_sync_latency(&device_id)
+}
+
+/// Decrypt and retrieve session key for a paired device
+/// This is used internally when establishing connections with stored devices
+#[frb(sync)]
+pub fn get_device_session_key(device_id: String) -> Result<Vec<u8>, String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    // Get stored device
+    let device = core
+        .storage
+        .devices()
+        .get_device(&device_id)
+        .map_err(|e| format!("Failed to get device: {}", e))?
+        .ok_or("Device not found")?;
+
+    // Check if session key exists
+    let encrypted_session_key = device
+        .session_key
+        .ok_or("No session key stored for this device")?;
+
+    // Derive storage decryption key
+    let storage_key = derive_key(
+        core.identity.device_id(),
+        DerivedKeyPurpose::StorageEncryption,
+        Some(b"toss-session-key-v1"),
+    )
+    .map_err(|e| format!("Failed to derive storage key: {}", e))?;
+
+    // Decrypt session key
+    let aad = format!("session:{}", device_id).into_bytes();
+    let encrypted_message = EncryptedMessage::from_bytes(&encrypted_session_key)
+        .map_err(|e| format!("Failed to parse encrypted session key: {}", e))?;
+
+    let decrypted_key = decrypt(&storage_key, &encrypted_message, &aad)
+        .map_err(|e| format!("Failed to decrypt session key: {}", e))?;
+
+    Ok(decrypted_key)
+}
+
+/// Check if clipboard has changed since last check
+///
+/// Always `false` during quiet hours: local changes then aren't auto-synced,
+/// and replace any received update held until the window ends.
+#[frb(sync)]
+pub fn check_clipboard_changed() -> bool {
+    let mut guard = TOSS_INSTANCE.write();
+    if let Some(ref mut core) = *guard {
+        let changed = core.clipboard.has_changed();
+        if changed && core.in_quiet_hours() {
+            core.quiet_hours_queue.clear();
+            core.record_skip(SendSkip::new(
+                SkipReason::QuietHours,
+                "Local changes aren't synced during quiet hours",
+            ));
+            return false;
+        }
+        changed
+    } else {
+        false
+    }
+}
+
+/// Decrypted clipboard content from history
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ClipboardContentDto {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Get decrypted clipboard content from history item
+#[frb(sync)]
+pub fn get_clipboard_history_content(item_id: String) -> Result<ClipboardContentDto, String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    let content = load_history_content(&core.storage, &core.identity, &item_id)?;
+
+    Ok(ClipboardContentDto {
+        content_type: content.content_type.as_str().to_string(),
+        data: content.data,
+    })
+}
+
+/// Decrypt a history item's content straight into a file
+///
+/// Avoids copying large items across the FFI boundary. `dest_path` must be
+/// an absolute path without `..` in an existing directory; an existing file
+/// there is replaced. Returns the number of bytes written.
+#[frb]
+pub async fn export_history_item_to_file(
+    item_id: String,
+    dest_path: String,
+) -> Result<u64, String> {
+    let dest = validate_export_path(&dest_path)?;
+
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    export_history_content(&core.storage, &core.identity, &item_id, &dest)
+}
+
+/// Decrypt a history item's primary content
+fn load_history_content(
+    storage: &Storage,
+    identity: &DeviceIdentity,
+    item_id: &str,
+) -> Result<ClipboardContent, String> {
+    // Get stored history item
+    let stored_item = storage
+        .history()
+        .get_item(item_id)
+        .map_err(|e| format!("Failed to get history item: {}", e))?
+        .ok_or("History item not found")?;
+
+    // Derive storage decryption key
+    let storage_key = derive_key(
+        identity.device_id().as_slice(),
+        DerivedKeyPurpose::StorageEncryption,
+        Some(b"toss-clipboard-history-v1"),
+    )
+    .map_err(|e| format!("Failed to derive storage key: {}", e))?;
+
+    decrypt_history_item(&storage_key, &stored_item)
+}
+
+/// Decrypt a stored history item's primary content with the storage key
+fn decrypt_history_item(
+    storage_key: &[u8; 32],
+    stored_item: &StoredHistoryItem,
+) -> Result<ClipboardContent, String> {
+    // Decrypt content
+    let aad = format!("history:{}", stored_item.id).into_bytes();
+    let encrypted_message = EncryptedMessage::from_bytes(&stored_item.encrypted_content)
+        .map_err(|e| format!("Failed to parse encrypted content: {}", e))?;
+
+    let decrypted_data = decrypt(storage_key, &encrypted_message, &aad)
+        .map_err(|e| format!("Failed to decrypt history content: {}", e))?;
+
+    // Deserialize to ClipboardContent to get the actual data
+    encoding::decode(&decrypted_data)
+        .map_err(|e| format!("Failed to deserialize clipboard content: {}", e))
+}
+
+/// IDs of history items that fail to decrypt, oldest first
+fn find_corrupt_history(
+    storage: &Storage,
+    identity: &DeviceIdentity,
+) -> Result<Vec<String>, String> {
+    let storage_key = derive_key(
+        identity.device_id().as_slice(),
+        DerivedKeyPurpose::StorageEncryption,
+        Some(b"toss-clipboard-history-v1"),
+    )
+    .map_err(|e| format!("Failed to derive storage key: {}", e))?;
+
+    // Page through rather than loading every item's content at once
+    let mut corrupt = Vec::new();
+    let mut after = 0;
+    loop {
+        let page = storage
+            .history()
+            .get_items_after(after, HISTORY_EXPORT_PAGE_SIZE)
+            .map_err(|e| format!("Failed to get history items: {}", e))?;
+        let Some(last) = page.last().map(|item| item.created_at) else {
+            break;
+        };
+
+        for item in page {
+            if let Err(e) = decrypt_history_item(&storage_key, &item) {
+                tracing::warn!("History item {} is unreadable: {}", item.id, e);
+                corrupt.push(item.id);
+            }
+        }
+        after = last;
+    }
+
+    Ok(corrupt)
+}
+
+/// Remove the items [`find_corrupt_history`] reports, returning how many
+fn remove_corrupt_history(storage: &Storage, identity: &DeviceIdentity) -> Result<u32, String> {
+    let corrupt = find_corrupt_history(storage, identity)?;
+    for item_id in &corrupt {
+        storage
+            .history()
+            .remove_item(item_id)
+            .map_err(|e| format!("Failed to remove history item: {}", e))?;
+    }
+
+    Ok(corrupt.len() as u32)
+}
+
+/// Check an export destination: absolute, no `..`, not a directory or
+/// symlink, in a directory that exists
+fn validate_export_path(dest_path: &str) -> Result<std::path::PathBuf, String> {
+    if dest_path.is_empty() || dest_path.contains('\0') {
+        return Err("Invalid destination path".to_string());
+    }
+
+    let dest = std::path::PathBuf::from(dest_path);
+    if !dest.is_absolute() {
+        return Err("Destination path must be absolute".to_string());
+    }
+    if dest
+        .components()
+        .any(|c| matches!(c, std::path::Component::ParentDir))
+    {
+        return Err("Destination path must not contain '..'".to_string());
+    }
+    if dest.file_name().is_none() {
+        return Err("Destination path has no file name".to_string());
+    }
+
+    let parent = dest
+        .parent()
+        .ok_or("Destination path has no parent directory")?;
+    if !parent.is_dir() {
+        return Err(format!("Directory {} does not exist", parent.display()));
+    }
+    if let Ok(metadata) = std::fs::symlink_metadata(&dest) {
+        if metadata.is_dir() {
+            return Err("Destination is a directory".to_string());
+        }
+        if metadata.file_type().is_symlink() {
+            return Err("Destination is a symlink".to_string());
+        }
+    }
+    Ok(dest)
+}
+
+/// Decrypt a history item into `dest`, via a temporary file in the same
+/// directory so a failed export never leaves a partial file behind
+fn export_history_content(
+    storage: &Storage,
+    identity: &DeviceIdentity,
+    item_id: &str,
+    dest: &std::path::Path,
+) -> Result<u64, String> {
+    use std::io::Write;
+
+    let content = load_history_content(storage, identity, item_id)?;
+
+    let file_name = dest
+        .file_name()
+        .ok_or("Destination path has no file name")?
+        .to_string_lossy();
+    let temp_path = dest.with_file_name(format!(".{}.toss-export", file_name));
+
+    let write = || -> std::io::Result<()> {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(&content.data)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, dest)
+    };
+    if let Err(e) = write() {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(format!("Failed to write {}: {}", dest.display(), e));
+    }
+
+    Ok(content.data.len() as u64)
+}
+
+/// Items read from storage at a time when exporting history
+const HISTORY_EXPORT_PAGE_SIZE: u32 = 100;
+
+/// Outcome of a history export
+#[derive(Debug, Clone)]
+pub struct HistoryExportDto {
+    /// Number of items written
+    pub count: u32,
+    /// Creation time of the last item written; pass it as `after` to export
+    /// only newer items next time
+    pub next_after: Option<u64>,
+}
+
+/// One decrypted item in a history export
+#[derive(serde::Serialize)]
+struct HistoryExportItem {
+    id: String,
+    content_type: &'static str,
+    created_at: u64,
+    source_device: Option<String>,
+    superseded: bool,
+    /// Content data, base64 encoded
+    data: String,
+}
+
+/// Export decrypted history items created after `after` to a gzip-compressed
+/// JSON array, oldest first
+///
+/// Meant for incremental backups: pass the previous export's `next_after` to
+/// get only what was copied since. `limit` caps the number of items, but is
+/// exceeded rather than splitting items that share a timestamp. `dest_path`
+/// follows the same rules as [`export_history_item_to_file`].
+#[frb]
+pub async fn export_history_to_file(
+    after: u64,
+    limit: Option<u32>,
+    dest_path: String,
+) -> Result<HistoryExportDto, String> {
+    let dest = validate_export_path(&dest_path)?;
+
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    let file_name = dest
+        .file_name()
+        .ok_or("Destination path has no file name")?
+        .to_string_lossy();
+    let temp_path = dest.with_file_name(format!(".{}.toss-export", file_name));
+
+    let export = || -> Result<HistoryExportDto, String> {
+        let file = std::fs::File::create(&temp_path)
+            .map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+        let (exported, file) =
+            write_history_export(&core.storage, &core.identity, after, limit, file)?;
+        file.sync_all()
+            .and_then(|_| std::fs::rename(&temp_path, &dest))
+            .map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+        Ok(exported)
+    };
+    let result = export();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Stream history items created after `after` into `out` as a
+/// gzip-compressed JSON array, a page at a time
+///
+/// Only one page of items is held in memory, however large the history.
+/// Returns the export outcome and the writer.
+fn write_history_export<W: std::io::Write>(
+    storage: &Storage,
+    identity: &DeviceIdentity,
+    after: u64,
+    limit: Option<u32>,
+    out: W,
+) -> Result<(HistoryExportDto, W), String> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let write_error = |e: std::io::Error| format!("Failed to write history export: {}", e);
+
+    let storage_key = derive_key(
+        identity.device_id().as_slice(),
+        DerivedKeyPurpose::StorageEncryption,
+        Some(b"toss-clipboard-history-v1"),
+    )
+    .map_err(|e| format!("Failed to derive storage key: {}", e))?;
+
+    let mut gz = GzEncoder::new(out, Compression::default());
+    gz.write_all(b"[").map_err(write_error)?;
+
+    let mut exported = HistoryExportDto {
+        count: 0,
+        next_after: None,
+    };
+    loop {
+        let page_size = match limit {
+            Some(limit) => limit
+                .saturating_sub(exported.count)
+                .min(HISTORY_EXPORT_PAGE_SIZE),
+            None => HISTORY_EXPORT_PAGE_SIZE,
+        };
+        if page_size == 0 {
+            break;
+        }
+
+        let page = storage
+            .history()
+            .get_items_after(exported.next_after.unwrap_or(after), page_size)
+            .map_err(|e| format!("Failed to get history items: {}", e))?;
+        let Some(last) = page.last().map(|item| item.created_at) else {
+            break;
+        };
+
+        for item in page {
+            let content = decrypt_history_item(&storage_key, &item)?;
+            if exported.count > 0 {
+                gz.write_all(b",").map_err(write_error)?;
+            }
+            serde_json::to_writer(
+                &mut gz,
+                &HistoryExportItem {
+                    id: item.id,
+                    content_type: content.content_type.as_str(),
+                    created_at: item.created_at,
+                    source_device: item.source_device,
+                    superseded: item.superseded,
+                    data: base64::Engine::encode(
+                        &base64::engine::general_purpose::STANDARD,
+                        &content.data,
+                    ),
+                },
+            )
+            .map_err(|e| format!("Failed to write history export: {}", e))?;
+            exported.count += 1;
+        }
+        exported.next_after = Some(last);
+    }
+
+    gz.write_all(b"]").map_err(write_error)?;
+    let out = gz.finish().map_err(write_error)?;
+    Ok((exported, out))
+}
+
+/// Get the full content of the live clipboard, e.g. to save it to a file
+#[frb(sync)]
+pub fn get_current_clipboard_content() -> Result<ClipboardContentDto, String> {
+    let guard = TOSS_INSTANCE.read();
+    let core = guard.as_ref().ok_or("Toss not initialized")?;
+
+    let content = core
+        .clipboard
+        .read()
+        .map_err(|e| format!("Failed to read clipboard: {}", e))?;
+
+    current_clipboard_content_dto(content)
+}
+
+/// Build the export DTO for live clipboard content
+fn current_clipboard_content_dto(
+    content: Option<ClipboardContent>,
+) -> Result<ClipboardContentDto, String> {
+    let content = content.ok_or("Clipboard is empty")?;
+    Ok(ClipboardContentDto {
+        content_type: content.content_type.as_str().to_string(),
+        data: content.data,
+    })
+}
+
+/// Capture every format currently on the clipboard as one history entry
+///
+/// Formats that would push the group over `max_file_size_mb` are dropped,
+/// highest fidelity kept first.
 #[frb(sync)]
-pub fn get_device_session_key(device_id: String) -> Result<Vec<u8>, String> {
+pub fn capture_clipboard_snapshot() -> Result<ClipboardItemDto, String> {
     let guard = TOSS_INSTANCE.read();
     let core = guard.as_ref().ok_or("Toss not initialized")?;
 
-    // Get stored device
-    let device = core
-        .storage
-        .devices()
-        .get_device(&device_id)
-        .map_err(|e| format!("Failed to get device: {}", e))?
-        .ok_or("Device not found")?;
+    let max_bytes = (core.settings.max_file_size_mb as u64) * 1024 * 1024;
+    let snapshot = core
+        .clipboard
+        .snapshot(max_bytes)
+        .map_err(|e| format!("Clipboard read failed: {}", e))?
+        .ok_or("Clipboard is empty")?;
 
-    // Check if session key exists
-    let encrypted_session_key = device
-        .session_key
-        .ok_or("No session key stored for this device")?;
+    let item = store_snapshot(&core.storage, &core.identity, &snapshot, None)?;
 
-    // Derive storage decryption key
+    Ok(history_item_dto(item, snapshot.total_size()))
+}
+
+/// Write a history item back to the clipboard with all its stored formats
+#[frb(sync)]
+pub fn restore_history_item(item_id: String) -> Result<(), String> {
+    let mut guard = TOSS_INSTANCE.write();
+    let core = guard.as_mut().ok_or("Toss not initialized")?;
+
+    let snapshot = load_snapshot(&core.storage, &core.identity, &item_id)?;
+    snapshot
+        .restore(core.clipboard.handler())
+        .map_err(|e| format!("Failed to restore clipboard: {}", e))?;
+
+    // Update monitor hash to prevent re-syncing the restored content
+    if let Some(primary) = snapshot.primary() {
+        core.clipboard.monitor_mut().update_hash(primary);
+    }
+
+    Ok(())
+}
+
+/// Encrypt and store content received from a peer as a history entry
+///
+/// Returns `false` without storing anything if history isn't kept for the
+/// content's type.
+fn store_received_history(
+    settings: &TossSettings,
+    storage: &Storage,
+    identity: &DeviceIdentity,
+    content: &ClipboardContent,
+    from_device_id: &[u8; 32],
+    superseded: bool,
+) -> Result<bool, String> {
+    if !settings.keeps_history(content.content_type) {
+        return Ok(false);
+    }
+
+    let item_id = uuid::Uuid::new_v4().to_string();
+    let content_data =
+        encoding::encode(content).map_err(|e| format!("Failed to serialize content: {}", e))?;
     let storage_key = derive_key(
-        core.identity.device_id(),
+        identity.device_id(),
         DerivedKeyPurpose::StorageEncryption,
-        Some(b"toss-session-key-v1"),
+        Some(b"toss-clipboard-history-v1"),
     )
     .map_err(|e| format!("Failed to derive storage key: {}", e))?;
+    let aad = format!("history:{}", item_id).into_bytes();
+    let encrypted = encrypt(&storage_key, &content_data, &aad)
+        .map_err(|e| format!("Failed to encrypt content: {}", e))?;
+
+    let history_item = crate::storage::StoredHistoryItem {
+        id: item_id,
+        content_type: content.content_type as u8,
+        content_hash: hex::encode(content.hash()),
+        encrypted_content: encrypted.to_bytes(),
+        preview: content.preview(),
+        source_device: Some(hex::encode(from_device_id)),
+        created_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        language: content.metadata.language.clone(),
+        superseded,
+    };
+    storage
+        .history()
+        .store_item(&history_item)
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
 
-    // Decrypt session key
-    let aad = format!("session:{}", device_id).into_bytes();
-    let encrypted_message = EncryptedMessage::from_bytes(&encrypted_session_key)
-        .map_err(|e| format!("Failed to parse encrypted session key: {}", e))?;
+/// Encrypt and store a snapshot as one history entry
+///
+/// The primary representation goes into `clipboard_history` like any other
+/// item; every representation is additionally stored as a format row.
+fn store_snapshot(
+    storage: &Storage,
+    identity: &DeviceIdentity,
+    snapshot: &ClipboardSnapshot,
+    source_device: Option<String>,
+) -> Result<StoredHistoryItem, String> {
+    let primary = snapshot.primary().ok_or("Snapshot is empty")?;
+    let item_id = uuid::Uuid::new_v4().to_string();
 
-    let decrypted_key = decrypt(&storage_key, &encrypted_message, &aad)
-        .map_err(|e| format!("Failed to decrypt session key: {}", e))?;
+    let storage_key = derive_key(
+        identity.device_id() as &[u8],
+        DerivedKeyPurpose::StorageEncryption,
+        Some(b"toss-clipboard-history-v1"),
+    )
+    .map_err(|e| format!("Failed to derive storage key: {}", e))?;
 
-    Ok(decrypted_key)
-}
+    let encrypt_content = |content: &ClipboardContent, aad: String| {
+        let data = encoding::encode(content)
+            .map_err(|e| format!("Failed to serialize clipboard content: {}", e))?;
+        encrypt(&storage_key, &data, aad.as_bytes())
+            .map(|encrypted| encrypted.to_bytes())
+            .map_err(|e| format!("Failed to encrypt history content: {}", e))
+    };
 
-/// Check if clipboard has changed since last check
-#[frb(sync)]
-pub fn check_clipboard_changed() -> bool {
-    let mut guard = TOSS_INSTANCE.write();
-    if let Some(ref mut core) = *guard {
-        core.clipboard.has_changed()
-    } else {
-        false
-    }
-}
+    let preview = snapshot
+        .representations()
+        .iter()
+        .find_map(|c| c.metadata.text_preview.clone())
+        .unwrap_or_else(|| format!("{} bytes", snapshot.total_size()));
+
+    let item = StoredHistoryItem {
+        id: item_id.clone(),
+        content_type: primary.content_type as u8,
+        content_hash: hex::encode(primary.hash()),
+        encrypted_content: encrypt_content(primary, format!("history:{}", item_id))?,
+        preview,
+        source_device,
+        created_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        language: primary.metadata.language.clone(),
+        superseded: false,
+    };
 
-/// Decrypted clipboard content from history
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct ClipboardContentDto {
-    pub content_type: String,
-    pub data: Vec<u8>,
-}
+    let formats = snapshot
+        .representations()
+        .iter()
+        .map(|content| {
+            let content_type = content.content_type as u8;
+            Ok(StoredHistoryFormat {
+                item_id: item_id.clone(),
+                content_type,
+                encrypted_content: encrypt_content(
+                    content,
+                    format!("history:{}:{}", item_id, content_type),
+                )?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
 
-/// Get decrypted clipboard content from history item
-#[frb(sync)]
-pub fn get_clipboard_history_content(item_id: String) -> Result<ClipboardContentDto, String> {
-    let guard = TOSS_INSTANCE.read();
-    let core = guard.as_ref().ok_or("Toss not initialized")?;
+    let history = storage.history();
+    history
+        .store_item(&item)
+        .map_err(|e| format!("Failed to save clipboard history: {}", e))?;
+    history
+        .store_formats(&item_id, &formats)
+        .map_err(|e| format!("Failed to save clipboard history: {}", e))?;
 
-    // Get stored history item
-    let stored_item = core
-        .storage
-        .history()
-        .get_item(&item_id)
+    Ok(item)
+}
+
+/// Load and decrypt all representations of a history item
+///
+/// Items stored before snapshots existed yield a single-format snapshot.
+fn load_snapshot(
+    storage: &Storage,
+    identity: &DeviceIdentity,
+    item_id: &str,
+) -> Result<ClipboardSnapshot, String> {
+    let history = storage.history();
+    let item = history
+        .get_item(item_id)
         .map_err(|e| format!("Failed to get history item: {}", e))?
         .ok_or("History item not found")?;
+    let formats = history
+        .get_formats(item_id)
+        .map_err(|e| format!("Failed to get history formats: {}", e))?;
 
-    // Derive storage decryption key
     let storage_key = derive_key(
-        core.identity.device_id().as_slice(),
+        identity.device_id() as &[u8],
         DerivedKeyPurpose::StorageEncryption,
         Some(b"toss-clipboard-history-v1"),
     )
     .map_err(|e| format!("Failed to derive storage key: {}", e))?;
 
-    // Decrypt content
-    let aad = format!("history:{}", item_id).into_bytes();
-    let encrypted_message = EncryptedMessage::from_bytes(&stored_item.encrypted_content)
-        .map_err(|e| format!("Failed to parse encrypted content: {}", e))?;
-
-    let decrypted_data = decrypt(&storage_key, &encrypted_message, &aad)
-        .map_err(|e| format!("Failed to decrypt history content: {}", e))?;
+    let decrypt_content = |bytes: &[u8], aad: String| {
+        let encrypted = EncryptedMessage::from_bytes(bytes)
+            .map_err(|e| format!("Failed to parse encrypted content: {}", e))?;
+        let data = decrypt(&storage_key, &encrypted, aad.as_bytes())
+            .map_err(|e| format!("Failed to decrypt history content: {}", e))?;
+        encoding::decode::<ClipboardContent>(&data)
+            .map_err(|e| format!("Failed to deserialize clipboard content: {}", e))
+    };
 
-    // Deserialize to ClipboardContent to get the actual data
-    let content: ClipboardContent = bincode::deserialize(&decrypted_data)
-        .map_err(|e| format!("Failed to deserialize clipboard content: {}", e))?;
-
-    // Convert content type to string
-    let content_type_str = match content.content_type {
-        ContentType::PlainText => "text".to_string(),
-        ContentType::RichText => "rich_text".to_string(),
-        ContentType::Image => "image".to_string(),
-        ContentType::File => "file".to_string(),
-        ContentType::Url => "url".to_string(),
+    let representations = if formats.is_empty() {
+        vec![decrypt_content(
+            &item.encrypted_content,
+            format!("history:{}", item_id),
+        )?]
+    } else {
+        formats
+            .iter()
+            .map(|f| {
+                decrypt_content(
+                    &f.encrypted_content,
+                    format!("history:{}:{}", item_id, f.content_type),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?
     };
 
-    Ok(ClipboardContentDto {
-        content_type: content_type_str,
-        data: content.data,
-    })
+    Ok(ClipboardSnapshot::new(representations))
 }
 
 #[cfg(test)]
@@ -1526,6 +5123,426 @@ mod tests {
         assert!(settings.sync_text);
         assert!(settings.sync_images);
         assert_eq!(settings.max_file_size_mb, 50);
+        assert_eq!(settings.max_paired_devices, 20);
+        assert!(!settings.evict_oldest_device);
+        assert!(!settings.allow_remote_actions);
+        assert_eq!(settings.service_name, "toss");
+
+        // Service names outside RFC 6335 are refused
+        let settings = TossSettings {
+            service_name: "my_toss".to_string(),
+            ..Default::default()
+        };
+        let error = update_settings(settings).unwrap_err();
+        assert!(error.contains("service name \"my_toss\""), "{}", error);
+    }
+
+    #[test]
+    fn test_settings_survive_storage_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("toss.db");
+        let settings = TossSettings {
+            auto_sync: false,
+            max_file_size_mb: 5,
+            relay_url: Some("https://relay.example".to_string()),
+            content_denylist: vec!["secret".to_string()],
+            quiet_hours_enabled: true,
+            ..TossSettings::default()
+        };
+        Storage::new(&db_path)
+            .unwrap()
+            .settings()
+            .save(&settings)
+            .unwrap();
+
+        let loaded: TossSettings = Storage::new(&db_path).unwrap().settings().load().unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&settings).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_limits_match_core_constants() {
+        let limits = get_limits();
+        assert_eq!(
+            limits.max_message_size,
+            crate::protocol::MAX_MESSAGE_SIZE as u64
+        );
+        assert_eq!(
+            limits.max_preview_size,
+            crate::protocol::MAX_PREVIEW_SIZE as u64
+        );
+        assert_eq!(limits.protocol_version, crate::PROTOCOL_VERSION as u32);
+        assert_eq!(limits.pairing_code_length, PAIRING_CODE_LENGTH as u32);
+        assert_eq!(limits.pairing_code_format, "^[0-9]{6}$");
+        assert_eq!(limits.max_device_name_length, MAX_DEVICE_NAME_LEN as u32);
+
+        // Generated codes have the advertised format
+        let session = PairingSession::new("Test");
+        assert!(is_valid_pairing_code(session.code()));
+        assert_eq!(session.code().len(), limits.pairing_code_length as usize);
+    }
+
+    #[test]
+    fn test_normalize_device_name() {
+        assert_eq!(
+            normalize_device_name("  Work Laptop \n").unwrap(),
+            "Work Laptop"
+        );
+        assert_eq!(
+            normalize_device_name(&"é".repeat(MAX_DEVICE_NAME_LEN / 2)).unwrap(),
+            "é".repeat(MAX_DEVICE_NAME_LEN / 2)
+        );
+
+        assert!(normalize_device_name("").is_err());
+        assert!(normalize_device_name(" \t ").is_err());
+        assert!(normalize_device_name(&"a".repeat(MAX_DEVICE_NAME_LEN + 1)).is_err());
+        // Three bytes each, so fewer characters fit
+        assert!(normalize_device_name(&"名".repeat(MAX_DEVICE_NAME_LEN / 3 + 1)).is_err());
+        assert!(normalize_device_name(&"a".repeat(10 * 1024)).is_err());
+        assert!(normalize_device_name("Work\nLaptop").is_err());
+        assert!(normalize_device_name("Laptop\u{7}").is_err());
+        assert!(normalize_device_name("Lap\u{1b}[31mtop").is_err());
+    }
+
+    #[test]
+    fn test_parse_pairing_qr() {
+        let session = PairingSession::new("Desk");
+        let info = session.info("Desk");
+
+        let preview = parse_pairing_qr(info.qr_data.clone()).unwrap();
+        assert_eq!(preview.code, info.code);
+        assert_eq!(preview.device_name, "Desk");
+        assert_eq!(preview.expires_at, Some(info.expires_at));
+        assert_eq!(preview.device_id.len(), 32);
+
+        let expired = crate::crypto::generate_qr_data("123456", &[1u8; 32], "Desk", 1);
+        assert!(parse_pairing_qr(expired).is_err());
+        assert!(parse_pairing_qr("{\"v\":1}".to_string()).is_err());
+        assert!(parse_pairing_qr("   ".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_dto_paths_use_canonical_content_type() {
+        let contents = [
+            ClipboardContent::text("Hello"),
+            ClipboardContent::text("https://example.com"),
+            ClipboardContent::new(ContentType::RichText, b"<b>Hi</b>".to_vec()),
+            ClipboardContent::image(vec![0u8; 16], Some((2, 2)), None),
+            ClipboardContent::new(ContentType::File, vec![0u8; 8]),
+            ClipboardContent::code("fn main() {}", Some("rust")),
+        ];
+
+        for content in &contents {
+            // Current clipboard and received items
+            let live = clipboard_item_dto(content, Some("peer".to_string()));
+
+            // History items
+            let stored = StoredHistoryItem {
+                id: "item".to_string(),
+                content_type: content.content_type as u8,
+                content_hash: hex::encode(content.hash()),
+                encrypted_content: vec![],
+                preview: content.preview(),
+                source_device: None,
+                created_at: 1,
+                language: content.metadata.language.clone(),
+                superseded: false,
+            };
+            let history = history_item_dto(stored, content.metadata.size_bytes);
+
+            let expected = match content.content_type {
+                ContentType::PlainText => "text",
+                ContentType::RichText => "rich_text",
+                ContentType::Image => "image",
+                ContentType::File => "file",
+                ContentType::Url => "url",
+                ContentType::Code => "code",
+            };
+            assert_eq!(live.content_type, expected);
+            assert_eq!(history.content_type, expected);
+            assert_eq!(live.mime_type, content.mime_type());
+            assert_eq!(history.mime_type, content.content_type.mime_type());
+            assert_eq!(live.preview, history.preview);
+            assert_eq!(live.language, content.metadata.language);
+            assert_eq!(history.language, content.metadata.language);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_store_and_restore() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().join("test.db")).unwrap();
+        let identity = DeviceIdentity::generate().unwrap();
+
+        let source = crate::clipboard::rich_clipboard();
+        let snapshot = ClipboardSnapshot::capture(&source, u64::MAX)
+            .unwrap()
+            .unwrap();
+        let item = store_snapshot(&storage, &identity, &snapshot, None).unwrap();
+        assert_eq!(item.content_type, ContentType::RichText as u8);
+        assert_eq!(item.preview, "Hello");
+
+        let target = crate::clipboard::MockClipboard::default();
+        load_snapshot(&storage, &identity, &item.id)
+            .unwrap()
+            .restore(&target)
+            .unwrap();
+
+        let restored = target.formats.lock();
+        assert_eq!(restored.len(), 3);
+        for (restored, original) in restored.iter().zip(snapshot.representations()) {
+            assert_eq!(restored.content_type, original.content_type);
+            assert_eq!(restored.data, original.data);
+        }
+    }
+
+    #[test]
+    fn test_expired_history_is_pruned() {
+        use crate::storage::MemoryHistoryStore;
+
+        const DAY: u64 = 24 * 60 * 60;
+        let now = 100 * DAY;
+        let history = MemoryHistoryStore::new();
+        let item = |id: &str, created_at: u64| StoredHistoryItem {
+            id: id.to_string(),
+            content_type: ContentType::PlainText as u8,
+            content_hash: id.to_string(),
+            encrypted_content: vec![1, 2, 3],
+            preview: id.to_string(),
+            source_device: None,
+            created_at,
+            language: None,
+            superseded: false,
+        };
+        history.store_item(&item("today", now)).unwrap();
+        history
+            .store_item(&item("at-cutoff", now - 7 * DAY))
+            .unwrap();
+        history
+            .store_item(&item("just-expired", now - 7 * DAY - 1))
+            .unwrap();
+        history.store_item(&item("old", now - 30 * DAY)).unwrap();
+        history
+            .store_formats(
+                "old",
+                &[StoredHistoryFormat {
+                    item_id: "old".to_string(),
+                    content_type: ContentType::RichText as u8,
+                    encrypted_content: vec![4, 5, 6],
+                }],
+            )
+            .unwrap();
+
+        // 0 keeps history forever
+        assert_eq!(prune_expired_history(&history, 0, now).unwrap(), 0);
+        assert_eq!(history.get_all_items(None).unwrap().len(), 4);
+
+        assert_eq!(prune_expired_history(&history, 7, now).unwrap(), 2);
+        let remaining: Vec<String> = history
+            .get_all_items(None)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.id)
+            .collect();
+        assert_eq!(remaining, ["today", "at-cutoff"]);
+        assert!(history.get_formats("old").unwrap().is_empty());
+        assert_eq!(prune_expired_history(&history, 7, now).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_history_export_pages_miss_and_repeat_nothing() {
+        use std::io::Read;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().join("test.db")).unwrap();
+        let identity = DeviceIdentity::generate().unwrap();
+
+        // 25 items, two per second so page boundaries fall inside a second
+        for i in 0..25u64 {
+            let content = ClipboardContent::text(&format!("copy {}", i));
+            let snapshot = ClipboardSnapshot::new(vec![content]);
+            let item = store_snapshot(&storage, &identity, &snapshot, None).unwrap();
+            storage
+                .history()
+                .store_item(&StoredHistoryItem {
+                    created_at: 1_000 + i / 2,
+                    ..item
+                })
+                .unwrap();
+        }
+
+        let mut exported = Vec::new();
+        let mut after = 0;
+        let mut pages = 0;
+        loop {
+            let (page, gz) =
+                write_history_export(&storage, &identity, after, Some(5), Vec::new()).unwrap();
+            let mut json = String::new();
+            flate2::read::GzDecoder::new(gz.as_slice())
+                .read_to_string(&mut json)
+                .unwrap();
+            let items: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+            assert_eq!(items.len(), page.count as usize);
+
+            let Some(next_after) = page.next_after else {
+                break;
+            };
+            assert!(next_after > after);
+            after = next_after;
+            pages += 1;
+            exported.extend(items.into_iter().map(|item| {
+                let data = base64::Engine::decode(
+                    &base64::engine::general_purpose::STANDARD,
+                    item["data"].as_str().unwrap(),
+                )
+                .unwrap();
+                String::from_utf8(data).unwrap()
+            }));
+        }
+
+        // Pages of five grow to six to finish their last second
+        assert_eq!(pages, 5);
+        let mut expected: Vec<_> = (0..25).map(|i| format!("copy {}", i)).collect();
+        expected.sort();
+        exported.sort();
+        assert_eq!(exported, expected);
+    }
+
+    #[test]
+    fn test_corrupt_history_items_are_found_and_removed() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().join("test.db")).unwrap();
+        let identity = DeviceIdentity::generate().unwrap();
+
+        let store_text = |text: &str| {
+            let snapshot = ClipboardSnapshot::new(vec![ClipboardContent::text(text)]);
+            store_snapshot(&storage, &identity, &snapshot, None).unwrap()
+        };
+        let intact = store_text("intact");
+        let flipped = store_text("flipped");
+        let truncated = store_text("truncated");
+
+        // A flipped ciphertext byte fails authentication
+        let mut encrypted_content = flipped.encrypted_content.clone();
+        let last = encrypted_content.len() - 1;
+        encrypted_content[last] ^= 0xFF;
+        storage
+            .history()
+            .store_item(&StoredHistoryItem {
+                encrypted_content,
+                ..flipped.clone()
+            })
+            .unwrap();
+        // A half-written row doesn't even parse
+        storage
+            .history()
+            .store_item(&StoredHistoryItem {
+                encrypted_content: truncated.encrypted_content[..8].to_vec(),
+                ..truncated.clone()
+            })
+            .unwrap();
+
+        let mut corrupt = find_corrupt_history(&storage, &identity).unwrap();
+        corrupt.sort();
+        let mut expected = vec![flipped.id.clone(), truncated.id.clone()];
+        expected.sort();
+        assert_eq!(corrupt, expected);
+
+        assert_eq!(remove_corrupt_history(&storage, &identity).unwrap(), 2);
+        assert!(find_corrupt_history(&storage, &identity)
+            .unwrap()
+            .is_empty());
+        let remaining = storage.history().get_all_items(None).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, intact.id);
+    }
+
+    #[test]
+    fn test_export_large_history_item_to_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().join("test.db")).unwrap();
+        let identity = DeviceIdentity::generate().unwrap();
+
+        let data: Vec<u8> = (0..8 * 1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+        let content = shared_content(
+            "application/octet-stream",
+            data.clone(),
+            Some("big.bin".into()),
+        )
+        .unwrap();
+        let snapshot = ClipboardSnapshot::new(vec![content]);
+        let item = store_snapshot(&storage, &identity, &snapshot, None).unwrap();
+
+        let dest = temp_dir.path().join("export.bin");
+        let dest = validate_export_path(dest.to_str().unwrap()).unwrap();
+        let written = export_history_content(&storage, &identity, &item.id, &dest).unwrap();
+        assert_eq!(written, data.len() as u64);
+        assert_eq!(std::fs::read(&dest).unwrap(), data);
+        assert!(!temp_dir.path().join(".export.bin.toss-export").exists());
+
+        // Unknown items leave nothing behind
+        let missing = temp_dir.path().join("missing.bin");
+        assert!(export_history_content(&storage, &identity, "nope", &missing).is_err());
+        assert!(!missing.exists());
+    }
+
+    #[test]
+    fn test_export_path_validation() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().to_str().unwrap().to_string();
+
+        assert!(validate_export_path(&format!("{}/out.bin", dir)).is_ok());
+        assert!(validate_export_path("").is_err());
+        assert!(validate_export_path("relative/out.bin").is_err());
+        assert!(validate_export_path(&format!("{}/../out.bin", dir)).is_err());
+        assert!(validate_export_path(&format!("{}/missing/out.bin", dir)).is_err());
+        assert!(validate_export_path(&dir).is_err());
+        assert!(validate_export_path(&format!("{}/out\0.bin", dir)).is_err());
+    }
+
+    #[test]
+    fn test_lagging_event_receiver_reports_lost_events() {
+        let (tx, mut receiver) = tokio::sync::broadcast::channel(2);
+        for i in 0..5 {
+            tx.send(NetworkEvent::PeerLost(format!("peer-{}", i)))
+                .unwrap();
+        }
+
+        assert_eq!(try_recv_network_event(&mut receiver).unwrap_err(), Some(3));
+
+        // The receiver resumes at the oldest event still buffered
+        for expected in ["peer-3", "peer-4"] {
+            match try_recv_network_event(&mut receiver) {
+                Ok(NetworkEvent::PeerLost(name)) => assert_eq!(name, expected),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        assert!(matches!(try_recv_network_event(&mut receiver), Err(None)));
+    }
+
+    #[tokio::test]
+    async fn test_listening_receiver_reports_lost_events_and_close() {
+        let (tx, mut receiver) = tokio::sync::broadcast::channel(2);
+        for i in 0..3 {
+            tx.send(NetworkEvent::PeerLost(format!("peer-{}", i)))
+                .unwrap();
+        }
+
+        assert_eq!(
+            recv_network_event(&mut receiver).await.unwrap_err(),
+            Some(1)
+        );
+        assert!(matches!(
+            recv_network_event(&mut receiver).await,
+            Ok(NetworkEvent::PeerLost(name)) if name == "peer-1"
+        ));
+        recv_network_event(&mut receiver).await.unwrap();
+
+        // Once the network is gone the listener waits for a new receiver
+        drop(tx);
+        assert!(matches!(recv_network_event(&mut receiver).await, Err(None)));
     }
 
     #[test]
@@ -1552,4 +5569,550 @@ mod tests {
         // Cleanup
         *TOSS_INSTANCE.write() = None;
     }
+
+    #[tokio::test]
+    #[ignore] // Requires clipboard access (X11 server)
+    async fn test_wipe_shuts_down_core() {
+        init_toss("/tmp/toss-test".to_string(), "Test Device".to_string()).unwrap();
+
+        // Key material may fail to wipe without a keystore; the core goes regardless
+        let _ = wipe_all_data().await;
+        assert!(TOSS_INSTANCE.read().is_none());
+        assert!(get_device_id().is_empty());
+        assert!(start_pairing().is_err());
+    }
+
+    #[test]
+    fn test_current_clipboard_content_returns_raw_bytes() {
+        let text = current_clipboard_content_dto(Some(ClipboardContent::text("hello"))).unwrap();
+        assert_eq!(text.content_type, "text");
+        assert_eq!(text.data, b"hello");
+
+        let png = vec![0x89, b'P', b'N', b'G', 0x00, 0xFF, 0x10];
+        let image = current_clipboard_content_dto(Some(ClipboardContent::image(
+            png.clone(),
+            Some((1, 1)),
+            Some("image/png".to_string()),
+        )))
+        .unwrap();
+        assert_eq!(image.content_type, "image");
+        assert_eq!(image.data, png);
+
+        assert_eq!(
+            current_clipboard_content_dto(None).unwrap_err(),
+            "Clipboard is empty"
+        );
+    }
+
+    #[test]
+    fn test_shared_content_maps_mime_types() {
+        let url = shared_content("text/uri-list", b"https://example.com\n".to_vec(), None).unwrap();
+        assert_eq!(url.content_type, ContentType::Url);
+        assert_eq!(url.data, b"https://example.com");
+
+        let text = shared_content("text/plain; charset=UTF-8", b"hello".to_vec(), None).unwrap();
+        assert_eq!(text.content_type, ContentType::PlainText);
+
+        let html = shared_content("text/html", b"<b>hi</b>".to_vec(), None).unwrap();
+        assert_eq!(html.content_type, ContentType::RichText);
+        assert_eq!(html.mime_type(), "text/html");
+
+        let image = shared_content("IMAGE/JPEG", vec![0xFF, 0xD8, 0xFF], None).unwrap();
+        assert_eq!(image.content_type, ContentType::Image);
+        assert_eq!(image.mime_type(), "image/jpeg");
+
+        let file = shared_content(
+            "application/pdf",
+            b"%PDF-1.7".to_vec(),
+            Some("report.pdf".to_string()),
+        )
+        .unwrap();
+        assert_eq!(file.content_type, ContentType::File);
+        assert_eq!(file.metadata.filename.as_deref(), Some("report.pdf"));
+        assert_eq!(file.mime_type(), "application/pdf");
+
+        assert!(shared_content("text/plain", vec![0xFF, 0xFE], None).is_err());
+        assert!(shared_content("image/png", Vec::new(), None).is_err());
+    }
+
+    #[test]
+    fn test_check_send_allowed_gates_type_and_size() {
+        let mut settings = TossSettings {
+            max_file_size_mb: 1,
+            ..Default::default()
+        };
+        let image = shared_content("image/png", vec![0u8; 1024], None).unwrap();
+        let file = shared_content("application/zip", vec![0u8; 2 * 1024 * 1024], None).unwrap();
+
+        assert!(check_send_allowed(&settings, &image).is_ok());
+        assert_eq!(
+            check_send_allowed(&settings, &file).unwrap_err().message,
+            "Content too large (max 1 MB)"
+        );
+
+        settings.sync_images = false;
+        assert_eq!(
+            check_send_allowed(&settings, &image).unwrap_err().message,
+            "Image sync disabled"
+        );
+
+        settings.max_file_size_mb = 50;
+        settings.sync_files = false;
+        assert_eq!(
+            check_send_allowed(&settings, &file).unwrap_err().message,
+            "File sync disabled"
+        );
+    }
+
+    #[test]
+    fn test_content_below_min_sync_bytes_is_skipped() {
+        let mut settings = TossSettings {
+            min_sync_bytes: 2,
+            ..Default::default()
+        };
+        let one = ClipboardContent::text("a");
+        let two = ClipboardContent::text("ab");
+
+        let skip = check_min_size(&settings, &one).unwrap_err();
+        assert_eq!(skip.reason, SkipReason::BelowMinSize);
+        assert_eq!(skip.message, "Content too small (min 2 bytes)");
+        assert!(check_min_size(&settings, &two).is_ok());
+        assert!(check_min_size(&settings, &ClipboardContent::text("abc")).is_ok());
+
+        // Clearing is never too small
+        assert!(check_min_size(&settings, &ClipboardContent::text("")).is_ok());
+
+        settings.min_sync_bytes = 0;
+        assert!(check_min_size(&settings, &one).is_ok());
+    }
+
+    #[test]
+    fn test_denylisted_content_is_skipped() {
+        let denylist = ContentDenylist::new(&[r"corp\.internal".to_string()]).unwrap();
+
+        let skip = check_denylist(
+            &denylist,
+            &ClipboardContent::text("ssh build01.corp.internal"),
+        )
+        .unwrap_err();
+        assert_eq!(skip.reason, SkipReason::Denylisted);
+        assert_eq!(
+            skip.message,
+            r"Content matches denylist pattern 'corp\.internal'"
+        );
+        assert!(check_denylist(&denylist, &ClipboardContent::text("ssh example.com")).is_ok());
+
+        // Invalid patterns are reported when the settings are updated
+        let settings = TossSettings {
+            content_denylist: vec!["[a-".to_string()],
+            ..Default::default()
+        };
+        let error = update_settings(settings).unwrap_err();
+        assert!(error.starts_with("Invalid denylist pattern '[a-'"));
+    }
+
+    #[test]
+    fn test_each_skipped_send_reports_its_reason() {
+        use crate::network::FailureCategory;
+
+        let reason = |skip: Option<SendSkip>| skip.map(|skip| skip.reason.as_str());
+        let settings = TossSettings {
+            max_file_size_mb: 1,
+            image_direction: SyncDirection::ReceiveOnly,
+            sync_files: false,
+            ..Default::default()
+        };
+        let text = ClipboardContent::text("hello");
+        let big = "x".repeat(2 * 1024 * 1024);
+        let big = ClipboardContent::new_text(ContentType::PlainText, &big);
+        let image = shared_content("image/png", vec![0u8; 16], None).unwrap();
+        let file = shared_content("application/zip", vec![0u8; 16], None).unwrap();
+
+        assert_eq!(reason(check_send_allowed(&settings, &text).err()), None);
+        assert_eq!(
+            reason(check_send_allowed(&settings, &file).err()),
+            Some("type_disabled")
+        );
+        assert_eq!(
+            reason(check_send_allowed(&settings, &image).err()),
+            Some("receive_only")
+        );
+        assert_eq!(
+            reason(check_send_allowed(&settings, &big).err()),
+            Some("size_limit")
+        );
+
+        let skip = rate_limit_skip(std::time::Duration::from_millis(40)).unwrap();
+        assert_eq!(skip.reason, SkipReason::RateLimited);
+        assert_eq!(skip.message, "Rate limit: please wait 60ms");
+        assert!(rate_limit_skip(MIN_SEND_INTERVAL).is_none());
+
+        // An empty outcome list is a broadcast with nobody to send to
+        assert_eq!(reason(broadcast_skip(&Ok(()), &[])), Some("no_peers"));
+        let delivered = [SendOutcome::Direct, SendOutcome::Relay];
+        assert_eq!(reason(broadcast_skip(&Ok(()), &delivered)), None);
+        let partial = [
+            SendOutcome::Direct,
+            SendOutcome::Failed(FailureCategory::Timeout),
+        ];
+        let skip = broadcast_skip(&Ok(()), &partial).unwrap();
+        assert_eq!(skip.reason, SkipReason::PartialFailure);
+        assert_eq!(skip.message, "Delivered to 1 of 2 devices");
+        let failed = [SendOutcome::Failed(FailureCategory::Connection)];
+        assert_eq!(
+            reason(broadcast_skip(
+                &Err(crate::error::NetworkError::ConnectionFailed(
+                    "refused".to_string()
+                )),
+                &failed
+            )),
+            Some("send_failed")
+        );
+        assert_eq!(
+            reason(broadcast_skip(
+                &Err(crate::error::NetworkError::Cancelled),
+                &failed
+            )),
+            Some("cancelled")
+        );
+        assert_eq!(SkipReason::QuietHours.as_str(), "quiet_hours");
+    }
+
+    #[test]
+    fn test_self_test_reports_unwritable_data_dir() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().join("data");
+        std::fs::create_dir(&data_dir).unwrap();
+        let storage = Storage::new(data_dir.join("toss.db")).unwrap();
+
+        let report = self_test_report(vec![
+            self_test_check("crypto", check_crypto_roundtrip()),
+            self_test_check("database", check_database(&storage)),
+        ]);
+        assert!(report.passed);
+        assert!(!data_dir.join(".self_test").exists());
+
+        // Swap the data directory for a plain file (unwritable even as root)
+        std::fs::rename(&data_dir, temp_dir.path().join("moved")).unwrap();
+        std::fs::write(&data_dir, b"not a directory").unwrap();
+
+        let report = self_test_report(vec![
+            self_test_check("crypto", check_crypto_roundtrip()),
+            self_test_check("database", check_database(&storage)),
+        ]);
+        assert!(!report.passed);
+        assert_eq!(report.checks[0].status, "passed");
+        assert_eq!(report.checks[1].name, "database");
+        assert_eq!(report.checks[1].status, "failed");
+        assert!(report.checks[1]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("not writable"));
+    }
+
+    #[test]
+    fn test_unwritable_db_path_falls_back_to_memory() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        // The data directory is a plain file, so the database can't be created
+        let data_dir = temp_dir.path().join("data");
+        std::fs::write(&data_dir, b"not a directory").unwrap();
+        let db_path = data_dir.join("toss.db");
+
+        assert!(open_storage(&db_path, false).is_err());
+
+        let (storage, warning) = open_storage(&db_path, true).unwrap();
+        assert!(storage.is_in_memory());
+        assert!(warning.unwrap().contains("temporary storage"));
+        assert!(check_database(&storage).is_err());
+
+        // The in-memory store works through the usual accessors
+        let device = StoredDevice {
+            id: hex::encode([7u8; 32]),
+            advertised_name: "Laptop".to_string(),
+            public_key: vec![1u8; 32],
+            session_key: Some(vec![9u8; 32]),
+            last_seen: None,
+            created_at: 1,
+            is_active: true,
+            platform: None,
+            local_nickname: None,
+        };
+        storage.devices().store_device(&device).unwrap();
+        assert_eq!(storage.devices().get_all_devices().unwrap().len(), 1);
+        assert_eq!(stored_public_key(&storage, &[7u8; 32]), Some([1u8; 32]));
+        assert!(storage.history().get_all_items(None).unwrap().is_empty());
+
+        // A usable path opens file storage without a warning
+        let good_path = temp_dir.path().join("toss.db");
+        let (storage, warning) = open_storage(&good_path, true).unwrap();
+        assert!(!storage.is_in_memory());
+        assert!(warning.is_none());
+    }
+
+    #[test]
+    fn test_verified_fingerprint_persists_until_key_changes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let peer = DeviceIdentity::generate().unwrap();
+        let device_id = peer.device_id_hex();
+        let device = StoredDevice {
+            id: device_id.clone(),
+            advertised_name: "Phone".to_string(),
+            public_key: peer.public_key().to_vec(),
+            session_key: Some(vec![9u8; 32]),
+            last_seen: None,
+            created_at: 1,
+            is_active: true,
+            platform: None,
+            local_nickname: None,
+        };
+
+        {
+            let storage = Storage::new(&db_path).unwrap();
+            storage.devices().store_device(&device).unwrap();
+            let info = device_security_info(&storage, None, &device_id).unwrap();
+            assert_eq!(info.key_algorithm, "ed25519");
+            assert_eq!(
+                info.fingerprint,
+                DeviceIdentity::fingerprint_for(&peer.public_key())
+            );
+            assert!(info.fingerprint.starts_with(&device_id[..4].to_uppercase()));
+            assert!(!info.fingerprint_verified);
+            assert_eq!(info.session_key_since, None);
+
+            assert!(storage
+                .devices()
+                .mark_fingerprint_verified(&device_id)
+                .unwrap());
+        }
+
+        // The mark survives reopening storage, and re-pairing with the same key
+        let storage = Storage::new(&db_path).unwrap();
+        let info = device_security_info(&storage, None, &device_id).unwrap();
+        assert!(info.fingerprint_verified);
+        assert!(info.fingerprint_verified_at.is_some());
+        storage.devices().store_device(&device).unwrap();
+        assert!(
+            device_security_info(&storage, None, &device_id)
+                .unwrap()
+                .fingerprint_verified
+        );
+
+        // A new identity key has to be verified again
+        let migrated = DeviceIdentity::generate().unwrap();
+        storage
+            .devices()
+            .migrate_identity(
+                &device_id,
+                &migrated.device_id_hex(),
+                &migrated.public_key(),
+            )
+            .unwrap();
+        let info = device_security_info(&storage, None, &migrated.device_id_hex()).unwrap();
+        assert!(!info.fingerprint_verified);
+
+        assert!(device_security_info(&storage, None, &device_id).is_err());
+        assert!(!storage
+            .devices()
+            .mark_fingerprint_verified(&device_id)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_receive_only_image_is_not_sent_but_accepted() {
+        let settings = TossSettings {
+            image_direction: SyncDirection::ReceiveOnly,
+            ..Default::default()
+        };
+        let image = shared_content("image/png", vec![0u8; 16], None).unwrap();
+        let text = ClipboardContent::text("hello");
+
+        assert_eq!(
+            check_send_allowed(&settings, &image).unwrap_err().message,
+            "Image sync is receive-only"
+        );
+        assert!(settings.receives(ContentType::Image));
+
+        // Text keeps syncing both ways
+        assert!(check_send_allowed(&settings, &text).is_ok());
+        assert!(settings.receives(ContentType::PlainText));
+
+        // Turning the toggle off still wins over the direction
+        let disabled = TossSettings {
+            sync_images: false,
+            ..settings.clone()
+        };
+        assert!(!disabled.receives(ContentType::Image));
+
+        let send_only = TossSettings {
+            image_direction: SyncDirection::SendOnly,
+            ..settings
+        };
+        assert!(check_send_allowed(&send_only, &image).is_ok());
+        assert!(!send_only.receives(ContentType::Image));
+    }
+
+    #[test]
+    fn test_image_history_off_syncs_image_but_keeps_only_text() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().join("test.db")).unwrap();
+        let identity = DeviceIdentity::generate().unwrap();
+        let settings = TossSettings {
+            history_images: false,
+            ..Default::default()
+        };
+        let image = shared_content("image/png", vec![0u8; 16], None).unwrap();
+        let text = ClipboardContent::text("hello");
+        let from = [9u8; 32];
+        storage
+            .devices()
+            .store_device(&StoredDevice {
+                id: hex::encode(from),
+                advertised_name: "Phone".to_string(),
+                public_key: from.to_vec(),
+                session_key: None,
+                last_seen: None,
+                created_at: 1,
+                is_active: true,
+                platform: None,
+                local_nickname: None,
+            })
+            .unwrap();
+
+        // The image is still written to the clipboard, but leaves no history row
+        assert!(settings.receives(ContentType::Image));
+        assert!(
+            !store_received_history(&settings, &storage, &identity, &image, &from, false).unwrap()
+        );
+        assert!(
+            store_received_history(&settings, &storage, &identity, &text, &from, false).unwrap()
+        );
+
+        let items = storage.history().get_all_items(None).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].content_type, ContentType::PlainText as u8);
+        assert_eq!(items[0].source_device, Some(hex::encode(from)));
+
+        // Turning history off wins over the per-type toggles
+        let disabled = TossSettings {
+            history_enabled: false,
+            ..settings
+        };
+        assert!(!disabled.keeps_history(ContentType::PlainText));
+        assert!(
+            !store_received_history(&disabled, &storage, &identity, &text, &from, false).unwrap()
+        );
+        assert_eq!(storage.history().get_all_items(None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_settings_without_directions_default_to_both() {
+        let json = serde_json::json!({
+            "auto_sync": true,
+            "sync_text": true,
+            "sync_rich_text": true,
+            "sync_images": true,
+            "sync_files": false,
+            "max_file_size_mb": 50,
+            "max_image_dimension": 0,
+            "history_enabled": true,
+            "history_days": 7,
+            "relay_url": null,
+            "relay_pinned_key": null,
+            "advertise_content_hint": false,
+            "max_paired_devices": 20,
+            "evict_oldest_device": false,
+            "collect_sync_stats": false,
+        });
+        let settings: TossSettings = serde_json::from_value(json).unwrap();
+        assert_eq!(settings.image_direction, SyncDirection::Both);
+        assert!(settings.sends(ContentType::Image));
+        assert!(settings.receives(ContentType::Image));
+        assert!(!settings.sends(ContentType::File));
+        assert!(settings.keeps_history(ContentType::Image));
+        assert!(settings.quiet_hours().is_none());
+        assert_eq!(settings.quiet_hours_start, 22 * 60);
+
+        assert_eq!(
+            "receive_only".parse::<SyncDirection>().unwrap(),
+            SyncDirection::ReceiveOnly
+        );
+        assert!("sideways".parse::<SyncDirection>().is_err());
+    }
+
+    #[test]
+    fn test_open_url_requested_only_when_allowed_and_url() {
+        let url = ClipboardContent::new_text(ContentType::Url, "https://example.com/slides");
+        let open = ClipboardUpdate::new(url.clone()).with_auto_action(AutoAction::OpenUrl);
+        let from = [7u8; 32];
+
+        let mut settings = TossSettings::default();
+        assert!(remote_action_event(&settings, &open, from).is_none());
+
+        settings.allow_remote_actions = true;
+        match remote_action_event(&settings, &open, from) {
+            Some(TossEvent::OpenUrlRequested { url, device_id }) => {
+                assert_eq!(url, "https://example.com/slides");
+                assert_eq!(device_id, hex::encode(from));
+            }
+            other => panic!("expected OpenUrlRequested, got {:?}", other),
+        }
+
+        // Plain updates request nothing
+        assert!(remote_action_event(&settings, &ClipboardUpdate::new(url), from).is_none());
+
+        // The content has to be a URL, and one that is safe to open
+        let text = ClipboardUpdate::new(ClipboardContent::new_text(
+            ContentType::PlainText,
+            "https://example.com",
+        ))
+        .with_auto_action(AutoAction::OpenUrl);
+        assert!(remote_action_event(&settings, &text, from).is_none());
+
+        let script = ClipboardUpdate::new(ClipboardContent::new_text(
+            ContentType::Url,
+            "javascript:alert(1)",
+        ))
+        .with_auto_action(AutoAction::OpenUrl);
+        assert!(remote_action_event(&settings, &script, from).is_none());
+    }
+
+    #[test]
+    fn test_presence_is_throttled_and_never_carries_content() {
+        let throttle = PresenceThrottle::new(std::time::Duration::from_secs(5));
+        let from = [3u8; 32];
+        let start = std::time::Instant::now();
+        let copying = Presence {
+            state: PresenceState::Copying,
+        };
+        let idle = Presence {
+            state: PresenceState::Idle,
+        };
+
+        // Off by default
+        let mut settings = TossSettings::default();
+        assert!(presence_event(&settings, &throttle, &copying, from, start).is_none());
+
+        settings.share_presence = true;
+        match presence_event(&settings, &throttle, &copying, from, start) {
+            Some(TossEvent::PresenceChanged { device_id, state }) => {
+                assert_eq!(device_id, hex::encode(from));
+                assert_eq!(state, "copying");
+            }
+            other => panic!("expected PresenceChanged, got {:?}", other),
+        }
+
+        // A burst of hints yields no further events until the interval passes
+        for _ in 0..10 {
+            assert!(presence_event(&settings, &throttle, &copying, from, start).is_none());
+        }
+        assert!(presence_event(&settings, &throttle, &idle, from, start).is_some());
+        assert!(presence_event(&settings, &throttle, &copying, from, start).is_none());
+        let later = start + std::time::Duration::from_secs(5);
+        assert!(presence_event(&settings, &throttle, &copying, from, later).is_some());
+
+        // The message is a few bytes, with no room for content
+        assert!(Message::Presence(copying).serialize().unwrap().len() <= 8);
+    }
 }
@@ -0,0 +1,43 @@
+//! Cooperative cancellation of long-running operations
+
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Handle to abort an in-flight operation, such as a pairing search or a send
+///
+/// Clones share the same state, so one clone can be handed to the operation
+/// and another kept to cancel it.
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    cancelled: Arc<watch::Sender<bool>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self {
+            cancelled: Arc::new(watch::channel(false).0),
+        }
+    }
+
+    /// Signal every holder of the token to stop
+    pub fn cancel(&self) {
+        self.cancelled.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    /// Resolves once the token is cancelled
+    pub async fn cancelled(&self) {
+        let mut receiver = self.cancelled.subscribe();
+        // The sender lives as long as `self`, so this only returns on cancel
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,258 @@
+//! Deterministic resolution of concurrent clipboard updates
+//!
+//! Every update carries the sender's `sent_at`, which is inside the encrypted
+//! payload and so authenticated. Updates are ordered by `(sent_at, device_id)`:
+//! a later timestamp wins, and equal timestamps go to the higher device id.
+//! Local updates are stamped after the version already on the clipboard
+//! (Lamport style), so an update made after seeing another one always
+//! orders after it, even with skewed clocks. An incoming update that orders
+//! before the current version was made concurrently and loses; every device
+//! compares the same values, so all of them converge on the same winner.
+//!
+//! Updates sent directly and through the relay can also overtake each other
+//! on the way. Each update therefore carries a per-peer `sequence`, stamped
+//! as it is sent to that peer; the receiver drops an update whose sequence
+//! isn't above the last one it accepted from the sender, so a delayed older
+//! copy never replaces a newer one.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+/// Position of an update in the clipboard order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ClipboardVersion {
+    /// Authenticated send time (Unix milliseconds, sender clock)
+    pub sent_at: u64,
+    /// Device the update came from, the tie-break
+    pub device_id: [u8; 32],
+}
+
+/// What to do with an incoming update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Newer than the current version: write it to the clipboard
+    Apply,
+    /// Lost to the current version: keep it in history only
+    Superseded,
+}
+
+/// Tracks the version of the content on the clipboard
+pub struct ConflictResolver {
+    device_id: [u8; 32],
+    current: Mutex<Option<ClipboardVersion>>,
+    /// Last sequence accepted from each peer
+    received: Mutex<HashMap<[u8; 32], u64>>,
+}
+
+impl ConflictResolver {
+    pub fn new(device_id: [u8; 32]) -> Self {
+        Self {
+            device_id,
+            current: Mutex::new(None),
+            received: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Accept an incoming update's sequence, or reject it as stale
+    ///
+    /// Stale updates were overtaken by a later one from the same peer and
+    /// should be dropped. Unsequenced updates (sequence 0) are always accepted.
+    pub fn accept_sequence(&self, from_device_id: &[u8; 32], sequence: u64) -> bool {
+        if sequence == 0 {
+            return true;
+        }
+        let mut received = self.received.lock();
+        let last = received.entry(*from_device_id).or_insert(0);
+        if sequence <= *last {
+            return false;
+        }
+        *last = sequence;
+        true
+    }
+
+    /// Stamp a local update sent at `now_ms`, making it the current version
+    ///
+    /// Returns the `sent_at` to send, which is after the current version.
+    pub fn stamp_local(&self, now_ms: u64) -> u64 {
+        let mut current = self.current.lock();
+        let sent_at = match *current {
+            Some(version) => now_ms.max(version.sent_at + 1),
+            None => now_ms,
+        };
+        *current = Some(ClipboardVersion {
+            sent_at,
+            device_id: self.device_id,
+        });
+        sent_at
+    }
+
+    /// Resolve an incoming update against the current version
+    ///
+    /// An applied update becomes the current version.
+    pub fn resolve_remote(&self, sent_at: u64, from_device_id: [u8; 32]) -> Resolution {
+        let incoming = ClipboardVersion {
+            sent_at,
+            device_id: from_device_id,
+        };
+        let mut current = self.current.lock();
+        match *current {
+            Some(version) if incoming <= version => Resolution::Superseded,
+            _ => {
+                *current = Some(incoming);
+                Resolution::Apply
+            }
+        }
+    }
+
+    /// The version of the content on the clipboard, if known
+    pub fn current(&self) -> Option<ClipboardVersion> {
+        *self.current.lock()
+    }
+}
+
+/// Sequences stamped on updates sent to each peer
+#[derive(Default)]
+pub struct OutgoingSequences {
+    last: Mutex<HashMap<[u8; 32], u64>>,
+}
+
+impl OutgoingSequences {
+    /// Sequence for the next update sent to `device_id` at `now_ms`
+    ///
+    /// Sequences count up from the clock (Unix milliseconds), so they keep
+    /// increasing across restarts.
+    pub fn next(&self, device_id: &[u8; 32], now_ms: u64) -> u64 {
+        let mut last = self.last.lock();
+        let last = last.entry(*device_id).or_insert(0);
+        *last = now_ms.max(*last + 1);
+        *last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One simulated device: its resolver and the clipboard it shows
+    struct Node {
+        resolver: ConflictResolver,
+        clipboard: &'static str,
+        superseded: Vec<&'static str>,
+    }
+
+    impl Node {
+        fn new(id: u8) -> Self {
+            Self {
+                resolver: ConflictResolver::new([id; 32]),
+                clipboard: "",
+                superseded: Vec::new(),
+            }
+        }
+
+        fn copy(&mut self, content: &'static str, now_ms: u64) -> (u64, [u8; 32], &'static str) {
+            self.clipboard = content;
+            let sent_at = self.resolver.stamp_local(now_ms);
+            (sent_at, self.resolver.device_id, content)
+        }
+
+        fn receive(&mut self, (sent_at, from, content): (u64, [u8; 32], &'static str)) {
+            match self.resolver.resolve_remote(sent_at, from) {
+                Resolution::Apply => self.clipboard = content,
+                Resolution::Superseded => self.superseded.push(content),
+            }
+        }
+    }
+
+    #[test]
+    fn test_simultaneous_copies_converge() {
+        for (laptop_ms, phone_ms) in [(1_000, 1_000), (1_000, 1_400), (1_400, 1_000)] {
+            let mut laptop = Node::new(1);
+            let mut phone = Node::new(2);
+
+            let from_laptop = laptop.copy("laptop", laptop_ms);
+            let from_phone = phone.copy("phone", phone_ms);
+
+            // Deliver in opposite orders on each side
+            phone.receive(from_laptop);
+            laptop.receive(from_phone);
+
+            assert_eq!(laptop.clipboard, phone.clipboard);
+            let winner = laptop.clipboard;
+            let expected = if laptop_ms > phone_ms {
+                "laptop"
+            } else {
+                "phone"
+            };
+            assert_eq!(winner, expected, "later copy wins, then higher device id");
+
+            // The loser is kept as superseded on the device that received it
+            let loser_side = if winner == "laptop" { &phone } else { &laptop };
+            assert_eq!(loser_side.superseded, Vec::<&str>::new());
+            let winner_side = if winner == "laptop" { &laptop } else { &phone };
+            assert_eq!(winner_side.superseded.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_copy_after_receive_wins_despite_slow_clock() {
+        let mut laptop = Node::new(1);
+        let mut phone = Node::new(2);
+
+        // The phone's clock runs a minute behind the laptop's
+        phone.receive(laptop.copy("first", 60_000));
+        let reply = phone.copy("second", 1_000);
+        assert!(reply.0 > 60_000);
+
+        laptop.receive(reply);
+        assert_eq!(laptop.clipboard, "second");
+        assert_eq!(phone.clipboard, "second");
+        assert!(laptop.superseded.is_empty());
+    }
+
+    #[test]
+    fn test_replayed_update_is_superseded() {
+        let resolver = ConflictResolver::new([1u8; 32]);
+        assert_eq!(resolver.resolve_remote(500, [2u8; 32]), Resolution::Apply);
+        assert_eq!(
+            resolver.resolve_remote(500, [2u8; 32]),
+            Resolution::Superseded
+        );
+        assert_eq!(resolver.current().unwrap().sent_at, 500);
+    }
+
+    #[test]
+    fn test_out_of_order_updates_keep_newest() {
+        let laptop = [1u8; 32];
+        let phone = [2u8; 32];
+        let outgoing = OutgoingSequences::default();
+        let resolver = ConflictResolver::new(phone);
+
+        // Three quick copies; the second and third overtake the first
+        let sent: Vec<_> = ["one", "two", "three"]
+            .into_iter()
+            .map(|content| (outgoing.next(&phone, 1_000), content))
+            .collect();
+        assert!(sent.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        let mut clipboard = "";
+        let mut dropped = Vec::new();
+        for &(sequence, content) in [&sent[2], &sent[0], &sent[1]] {
+            if resolver.accept_sequence(&laptop, sequence) {
+                clipboard = content;
+            } else {
+                dropped.push(content);
+            }
+        }
+        assert_eq!(clipboard, "three");
+        assert_eq!(dropped, ["one", "two"]);
+
+        // Each peer has its own sequence, and unsequenced updates pass
+        assert!(resolver.accept_sequence(&[3u8; 32], sent[0].0));
+        assert!(resolver.accept_sequence(&laptop, 0));
+
+        // After a restart the sender counts on from its clock
+        let restarted = OutgoingSequences::default();
+        let next = restarted.next(&phone, 2_000);
+        assert!(resolver.accept_sequence(&laptop, next));
+    }
+}
@@ -0,0 +1,91 @@
+//! Denylist of patterns that outgoing text must never match
+//!
+//! Users list regular expressions for content they never want to leave the
+//! device, such as API keys, internal hostnames or card numbers. Text
+//! content matching any of them isn't sent.
+
+use regex::Regex;
+
+use crate::protocol::{ClipboardContent, ContentType};
+
+/// Compiled denylist patterns
+#[derive(Debug, Clone, Default)]
+pub struct ContentDenylist {
+    patterns: Vec<Regex>,
+}
+
+impl ContentDenylist {
+    /// Compile every pattern, failing on the first invalid one
+    pub fn new(patterns: &[String]) -> Result<Self, String> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|e| format!("Invalid denylist pattern '{}': {}", pattern, e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Whether there are no patterns
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// The first pattern matching `text`, if any
+    pub fn matching_pattern(&self, text: &str) -> Option<&str> {
+        self.patterns
+            .iter()
+            .find(|pattern| pattern.is_match(text))
+            .map(Regex::as_str)
+    }
+
+    /// The first pattern matching text content, if any
+    ///
+    /// Images and files are never matched.
+    pub fn check(&self, content: &ClipboardContent) -> Option<&str> {
+        if self.is_empty() || matches!(content.content_type, ContentType::Image | ContentType::File)
+        {
+            return None;
+        }
+        self.matching_pattern(&content.as_text()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_text_is_blocked() {
+        let denylist = ContentDenylist::new(&[
+            r"sk_live_[0-9a-zA-Z]{8,}".to_string(),
+            r"\b(?:\d[ -]?){13,16}\b".to_string(),
+        ])
+        .unwrap();
+
+        let key = ClipboardContent::text("token = sk_live_4eC39HqLyjWDarjt");
+        assert_eq!(denylist.check(&key), Some(r"sk_live_[0-9a-zA-Z]{8,}"));
+        let card = ClipboardContent::text("4111 1111 1111 1111");
+        assert_eq!(denylist.check(&card), Some(r"\b(?:\d[ -]?){13,16}\b"));
+
+        assert_eq!(
+            denylist.check(&ClipboardContent::text("see you at 5")),
+            None
+        );
+        let code = ClipboardContent::code("let key = \"sk_live_short\";", Some("rust"));
+        assert_eq!(denylist.check(&code), None);
+
+        // Only text is matched
+        let file = ClipboardContent::new(ContentType::File, b"sk_live_4eC39HqLyjWDarjt".to_vec());
+        assert_eq!(denylist.check(&file), None);
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_rejected() {
+        let error = ContentDenylist::new(&["ok".to_string(), "(unclosed".to_string()]).unwrap_err();
+        assert!(error.starts_with("Invalid denylist pattern '(unclosed'"));
+
+        assert!(ContentDenylist::new(&[]).unwrap().is_empty());
+    }
+}
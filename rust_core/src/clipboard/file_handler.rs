@@ -7,7 +7,8 @@
 
 use crate::error::ClipboardError;
 use crate::protocol::ClipboardContent;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 /// File list for clipboard operations
 #[derive(Debug, Clone)]
@@ -71,6 +72,108 @@ impl FileList {
     }
 }
 
+/// Name used for a received file that didn't carry a usable one
+const DEFAULT_RECEIVED_FILE_NAME: &str = "received-file";
+
+/// Longest file name written for a received file, in bytes
+const MAX_RECEIVED_FILE_NAME_LEN: usize = 200;
+
+/// Make a sender-supplied file name safe to create in a directory
+///
+/// Only the last path component is kept, characters that are reserved on
+/// some platform or are control characters become `_`, and leading and
+/// trailing dots and spaces are dropped, so the name can't escape the
+/// directory or hide itself.
+pub fn sanitize_file_name(name: Option<&str>) -> String {
+    let base = name
+        .and_then(|name| name.rsplit(['/', '\\']).next())
+        .unwrap_or_default();
+    let cleaned: String = base
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim_matches(|c| c == '.' || c == ' ');
+    if cleaned.is_empty() {
+        return DEFAULT_RECEIVED_FILE_NAME.to_string();
+    }
+
+    // Shorten the stem, keeping the extension
+    let (stem, extension) = match cleaned.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (cleaned, None),
+    };
+    let room = MAX_RECEIVED_FILE_NAME_LEN.saturating_sub(extension.map_or(0, |e| e.len() + 1));
+    let mut end = stem.len().min(room);
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    match extension {
+        Some(extension) => format!("{}.{}", &stem[..end], extension),
+        None => stem[..end].to_string(),
+    }
+}
+
+/// Write received file content into `dir` under its sanitized name
+///
+/// An existing file is never overwritten: "name (1).ext", "name (2).ext" and
+/// so on are tried instead. Returns the path written.
+pub fn save_received_file(
+    dir: &Path,
+    content: &ClipboardContent,
+) -> Result<PathBuf, ClipboardError> {
+    if !dir.is_dir() {
+        return Err(ClipboardError::OperationFailed(format!(
+            "Directory {} does not exist",
+            dir.display()
+        )));
+    }
+
+    let name = sanitize_file_name(content.metadata.filename.as_deref());
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (name.as_str(), None),
+    };
+
+    for attempt in 0u32.. {
+        let candidate = match (attempt, extension) {
+            (0, _) => name.clone(),
+            (n, Some(extension)) => format!("{} ({}).{}", stem, n, extension),
+            (n, None) => format!("{} ({})", stem, n),
+        };
+        let path = dir.join(candidate);
+        let mut file = match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => {
+                return Err(ClipboardError::OperationFailed(format!(
+                    "Failed to create {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+
+        if let Err(e) = file.write_all(&content.data).and_then(|_| file.sync_all()) {
+            let _ = std::fs::remove_file(&path);
+            return Err(ClipboardError::OperationFailed(format!(
+                "Failed to write {}: {}",
+                path.display(),
+                e
+            )));
+        }
+        return Ok(path);
+    }
+    unreachable!("ran out of file name candidates")
+}
+
 /// Platform-specific file clipboard operations
 pub trait FileClipboardProvider: Send + Sync {
     /// Read file list from clipboard
@@ -423,6 +526,39 @@ mod tests {
         assert_eq!(text, "/test/file.txt");
     }
 
+    #[test]
+    fn test_received_file_saved_under_sanitized_name() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut content =
+            ClipboardContent::new(crate::protocol::ContentType::File, b"%PDF-1.7".to_vec());
+        content.metadata.filename = Some("../../etc/report?.pdf".to_string());
+
+        let path = save_received_file(dir.path(), &content).unwrap();
+        assert_eq!(path, dir.path().join("report_.pdf"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"%PDF-1.7");
+
+        // A second copy doesn't overwrite the first
+        let path = save_received_file(dir.path(), &content).unwrap();
+        assert_eq!(path, dir.path().join("report_ (1).pdf"));
+
+        content.metadata.filename = None;
+        let path = save_received_file(dir.path(), &content).unwrap();
+        assert_eq!(path, dir.path().join("received-file"));
+
+        assert_eq!(
+            sanitize_file_name(Some("C:\\Users\\me\\notes.txt")),
+            "notes.txt"
+        );
+        assert_eq!(sanitize_file_name(Some("..")), "received-file");
+        let long = format!("{}.txt", "a".repeat(300));
+        assert_eq!(
+            sanitize_file_name(Some(&long)).len(),
+            MAX_RECEIVED_FILE_NAME_LEN
+        );
+
+        assert!(save_received_file(&dir.path().join("missing"), &content).is_err());
+    }
+
     #[test]
     fn test_file_list_from_content() {
         let content = ClipboardContent::new(
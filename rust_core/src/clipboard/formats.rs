@@ -5,7 +5,7 @@
 #![allow(dead_code)]
 
 use arboard::ImageData;
-use image::{DynamicImage, ImageFormat, RgbaImage};
+use image::{imageops::FilterType, DynamicImage, ImageFormat, RgbaImage};
 use std::io::Cursor;
 
 use crate::error::ClipboardError;
@@ -67,6 +67,43 @@ pub fn create_thumbnail(data: &[u8], max_size: u32) -> Result<Vec<u8>, Clipboard
     Ok(buffer)
 }
 
+/// Downscale an image so neither side exceeds `max_dimension`, keeping its aspect ratio
+///
+/// Returns `None` if the image already fits or `max_dimension` is 0. JPEG
+/// images stay JPEG; everything else is re-encoded as PNG.
+pub fn downscale_image(data: &[u8], max_dimension: u32) -> Result<Option<Vec<u8>>, ClipboardError> {
+    if max_dimension == 0 {
+        return Ok(None);
+    }
+
+    let (width, height) = get_image_dimensions(data)?;
+    if width <= max_dimension && height <= max_dimension {
+        return Ok(None);
+    }
+
+    let format = image::guess_format(data)
+        .map_err(|e| ClipboardError::ImageConversion(format!("Unknown image format: {}", e)))?;
+    let image = image::load_from_memory_with_format(data, format)
+        .map_err(|e| ClipboardError::ImageConversion(e.to_string()))?;
+
+    let resized = image.resize(max_dimension, max_dimension, FilterType::Triangle);
+    let (resized, format) = match format {
+        // JPEG has no alpha channel
+        ImageFormat::Jpeg => (
+            DynamicImage::ImageRgb8(resized.to_rgb8()),
+            ImageFormat::Jpeg,
+        ),
+        _ => (resized, ImageFormat::Png),
+    };
+
+    let mut buffer = Vec::new();
+    resized
+        .write_to(&mut Cursor::new(&mut buffer), format)
+        .map_err(|e| ClipboardError::ImageConversion(e.to_string()))?;
+
+    Ok(Some(buffer))
+}
+
 /// Get image dimensions from bytes
 pub fn get_image_dimensions(data: &[u8]) -> Result<(u32, u32), ClipboardError> {
     let reader = image::ImageReader::new(Cursor::new(data))
@@ -123,6 +160,30 @@ mod tests {
         assert_eq!(get_image_mime_type(b"not an image"), None);
     }
 
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(width, height));
+        let mut buffer = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)
+            .unwrap();
+        buffer
+    }
+
+    #[test]
+    fn test_downscale_image() {
+        // A large image is scaled to the bound, keeping its aspect ratio
+        let downscaled = downscale_image(&png(800, 200), 100).unwrap().unwrap();
+        assert_eq!(get_image_dimensions(&downscaled).unwrap(), (100, 25));
+        assert_eq!(get_image_mime_type(&downscaled), Some("image/png"));
+
+        let downscaled = downscale_image(&png(150, 300), 100).unwrap().unwrap();
+        assert_eq!(get_image_dimensions(&downscaled).unwrap(), (50, 100));
+
+        // Small images and a bound of 0 leave the original untouched
+        assert!(downscale_image(&png(100, 40), 100).unwrap().is_none());
+        assert!(downscale_image(&png(800, 200), 0).unwrap().is_none());
+    }
+
     #[test]
     fn test_decode_encode_roundtrip() {
         // Skip if test image doesn't exist
@@ -3,6 +3,7 @@
 //! On desktop platforms, uses arboard for clipboard access.
 //! On mobile (Android/iOS), clipboard is handled by Flutter - this provides a stub.
 
+use super::monitor::ChangeMarker;
 use crate::error::ClipboardError;
 use crate::protocol::{ClipboardContent, ContentType};
 
@@ -26,9 +27,35 @@ pub trait ClipboardProvider: Send + Sync {
     /// Read current clipboard content
     fn read(&self) -> Result<Option<ClipboardContent>, ClipboardError>;
 
+    /// Read every available representation of the current clipboard content
+    ///
+    /// Providers that can only see one format return just that one.
+    fn read_all(&self) -> Result<Vec<ClipboardContent>, ClipboardError> {
+        Ok(self.read()?.into_iter().collect())
+    }
+
+    /// Cheap signal for change detection, hashing at most `sample_bytes` of
+    /// content
+    ///
+    /// `None` if the provider has none, in which case the content is read in
+    /// full on every poll.
+    fn change_marker(&self, _sample_bytes: usize) -> Option<ChangeMarker> {
+        None
+    }
+
     /// Write content to clipboard
     fn write(&self, content: &ClipboardContent) -> Result<(), ClipboardError>;
 
+    /// Write several representations of the same content, highest fidelity first
+    ///
+    /// Providers that can only hold one format write the first one.
+    fn write_all(&self, contents: &[ClipboardContent]) -> Result<(), ClipboardError> {
+        match contents.first() {
+            Some(content) => self.write(content),
+            None => Err(ClipboardError::Empty),
+        }
+    }
+
     /// Clear the clipboard
     fn clear(&self) -> Result<(), ClipboardError>;
 
@@ -138,11 +165,110 @@ impl ClipboardProvider for ClipboardHandler {
         Ok(None)
     }
 
+    fn change_marker(&self, sample_bytes: usize) -> Option<ChangeMarker> {
+        // Windows counts clipboard changes; elsewhere sample the raw data,
+        // which skips re-encoding images to PNG
+        #[cfg(target_os = "windows")]
+        {
+            let sequence =
+                unsafe { windows::Win32::System::DataExchange::GetClipboardSequenceNumber() };
+            if sequence != 0 {
+                return Some(ChangeMarker::Sequence(sequence as u64));
+            }
+        }
+
+        let mut clipboard = self.clipboard.lock();
+        if let Ok(text) = clipboard.get_text() {
+            if !text.is_empty() {
+                return Some(ChangeMarker::sample(
+                    ContentType::PlainText,
+                    text.as_bytes(),
+                    sample_bytes,
+                ));
+            }
+        }
+        if let Ok(image) = clipboard.get_image() {
+            return Some(ChangeMarker::sample(
+                ContentType::Image,
+                &image.bytes,
+                sample_bytes,
+            ));
+        }
+
+        // Files and empty clipboards are read in full
+        None
+    }
+
+    fn read_all(&self) -> Result<Vec<ClipboardContent>, ClipboardError> {
+        let mut formats = Vec::new();
+
+        // Rich text goes through the platform provider (not supported everywhere)
+        if let Ok(Some(html)) = self.rich_text_provider.read_rich_text(RichTextFormat::Html) {
+            formats.push(html);
+        }
+
+        {
+            let mut clipboard = self.clipboard.lock();
+
+            if let Ok(text) = clipboard.get_text() {
+                if !text.is_empty() {
+                    formats.push(ClipboardContent::text(&text));
+                }
+            }
+
+            if let Ok(image) = clipboard.get_image() {
+                let png_data = encode_image_to_png(&image)?;
+                formats.push(ClipboardContent::image(
+                    png_data,
+                    Some((image.width as u32, image.height as u32)),
+                    Some("image/png".to_string()),
+                ));
+            }
+        }
+
+        if let Ok(Some(file_list)) = self.file_provider.read_files() {
+            formats.push(file_list.to_content());
+        }
+
+        Ok(formats)
+    }
+
+    fn write_all(&self, contents: &[ClipboardContent]) -> Result<(), ClipboardError> {
+        let html = contents
+            .iter()
+            .find(|c| c.content_type == ContentType::RichText);
+        let text = contents.iter().find(|c| {
+            matches!(
+                c.content_type,
+                ContentType::PlainText | ContentType::Url | ContentType::Code
+            )
+        });
+
+        // arboard can hold HTML with a plain-text alternative; other combinations
+        // fall back to the highest-fidelity representation
+        if let (Some(html), Some(text)) = (html, text) {
+            let html = String::from_utf8(html.data.clone())
+                .map_err(|e| ClipboardError::OperationFailed(e.to_string()))?;
+            let alt_text = String::from_utf8(text.data.clone())
+                .map_err(|e| ClipboardError::OperationFailed(e.to_string()))?;
+            return self
+                .clipboard
+                .lock()
+                .set_html(html, Some(alt_text))
+                .map_err(|e| ClipboardError::OperationFailed(e.to_string()));
+        }
+
+        match contents.first() {
+            Some(content) => self.write(content),
+            None => Err(ClipboardError::Empty),
+        }
+    }
+
     fn write(&self, content: &ClipboardContent) -> Result<(), ClipboardError> {
         let mut clipboard = self.clipboard.lock();
 
         match content.content_type {
-            ContentType::PlainText | ContentType::Url => {
+            ContentType::PlainText | ContentType::Url | ContentType::Code => {
                 let text = String::from_utf8(content.data.clone())
                     .map_err(|e| ClipboardError::OperationFailed(e.to_string()))?;
                 clipboard
@@ -204,6 +330,7 @@ impl ClipboardProvider for ClipboardHandler {
         match content_type {
             ContentType::PlainText => true,
             ContentType::Url => true,
+            ContentType::Code => true,     // Written as plain text
             ContentType::RichText => true, // Written as plain text
             ContentType::Image => true,
             ContentType::File => {
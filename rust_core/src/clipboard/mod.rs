@@ -11,8 +11,12 @@ mod file_handler;
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
 mod formats;
 
+mod conflict;
+mod denylist;
 mod handler;
 mod monitor;
+mod quiet_hours;
+mod snapshot;
 
 // Desktop-only modules
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
@@ -24,10 +28,21 @@ pub mod windows_formats;
 #[cfg(target_os = "linux")]
 pub mod linux_display;
 
+pub use conflict::{ClipboardVersion, ConflictResolver, OutgoingSequences, Resolution};
+pub use denylist::ContentDenylist;
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
-pub use formats::{decode_image, encode_image_to_png};
+pub use file_handler::{sanitize_file_name, save_received_file};
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub use formats::{
+    decode_image, downscale_image, encode_image_to_png, get_image_dimensions, get_image_mime_type,
+};
 pub use handler::{ClipboardHandler, ClipboardProvider};
-pub use monitor::ClipboardMonitor;
+pub use monitor::{ChangeMarker, ClipboardMonitor, DEFAULT_CHANGE_SAMPLE_BYTES};
+pub use quiet_hours::{QuietHours, QuietHoursQueue, MINUTES_PER_DAY};
+pub use snapshot::ClipboardSnapshot;
+
+#[cfg(test)]
+pub(crate) use snapshot::tests::{rich_clipboard, MockClipboard};
 
 use crate::error::ClipboardError;
 use crate::protocol::{ClipboardContent, ContentType};
@@ -52,11 +67,26 @@ impl ClipboardManager {
         self.handler.read()
     }
 
+    /// Read every available representation of the current clipboard content
+    pub fn read_all(&self) -> Result<Vec<ClipboardContent>, ClipboardError> {
+        self.handler.read_all()
+    }
+
     /// Write content to clipboard
     pub fn write(&self, content: &ClipboardContent) -> Result<(), ClipboardError> {
         self.handler.write(content)
     }
 
+    /// Write several representations of the same content, highest fidelity first
+    pub fn write_all(&self, contents: &[ClipboardContent]) -> Result<(), ClipboardError> {
+        self.handler.write_all(contents)
+    }
+
+    /// Capture all clipboard formats as one snapshot, limited to `max_bytes` in total
+    pub fn snapshot(&self, max_bytes: u64) -> Result<Option<ClipboardSnapshot>, ClipboardError> {
+        ClipboardSnapshot::capture(&self.handler, max_bytes)
+    }
+
     /// Clear the clipboard
     pub fn clear(&self) -> Result<(), ClipboardError> {
         self.handler.clear()
@@ -67,6 +97,11 @@ impl ClipboardManager {
         self.handler.supports_type(content_type)
     }
 
+    /// Get the underlying clipboard handler
+    pub fn handler(&self) -> &ClipboardHandler {
+        &self.handler
+    }
+
     /// Get the monitor for change detection
     pub fn monitor(&self) -> &ClipboardMonitor {
         &self.monitor
@@ -77,13 +112,18 @@ impl ClipboardManager {
         &mut self.monitor
     }
 
+    /// Take the current clipboard content as already seen
+    ///
+    /// See [`ClipboardMonitor::seed`].
+    pub fn seed(&mut self) -> bool {
+        self.monitor.seed(&self.handler)
+    }
+
     /// Check if clipboard has changed since last check
+    ///
+    /// Reads the content in full only when a cheap change marker moved.
     pub fn has_changed(&mut self) -> bool {
-        if let Ok(Some(content)) = self.read() {
-            self.monitor.check_change(&content)
-        } else {
-            false
-        }
+        self.monitor.poll_change(&self.handler)
     }
 }
 
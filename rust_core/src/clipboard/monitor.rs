@@ -1,6 +1,10 @@
 //! Clipboard change monitoring
 //!
 //! Monitors clipboard for changes using polling and hash comparison.
+//!
+//! Reading the clipboard can be expensive (a large image is re-encoded on
+//! every read), so each poll first asks the provider for a cheap
+//! [`ChangeMarker`] and only reads the content in full when it moved.
 
 #![allow(dead_code)]
 
@@ -10,15 +14,56 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::broadcast;
 
-use crate::protocol::ClipboardContent;
+use super::handler::ClipboardProvider;
+use crate::protocol::{ClipboardContent, ContentType};
 
 /// Default polling interval in milliseconds
 const DEFAULT_POLL_INTERVAL_MS: u64 = 250;
 
+/// Default bytes of content hashed for a sampled change marker
+pub const DEFAULT_CHANGE_SAMPLE_BYTES: usize = 64 * 1024;
+
+/// Cheap signal that the clipboard may have changed
+///
+/// A marker that stays the same means the content did; one that moves only
+/// means the content has to be read to tell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeMarker {
+    /// OS clipboard change counter
+    Sequence(u64),
+    /// Hash of the content type, length and a sample of the raw data
+    Sample([u8; 32]),
+}
+
+impl ChangeMarker {
+    /// Marker for raw clipboard data, hashing at most `sample_bytes` of it
+    ///
+    /// Data up to `sample_bytes` is hashed whole. Larger data is hashed by
+    /// its length, start and end, so an edit in the middle that keeps the
+    /// length goes unnoticed; raise `sample_bytes` where that matters.
+    pub fn sample(content_type: ContentType, data: &[u8], sample_bytes: usize) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update([content_type as u8]);
+        hasher.update((data.len() as u64).to_le_bytes());
+        if data.len() <= sample_bytes {
+            hasher.update(data);
+        } else {
+            let half = sample_bytes / 2;
+            hasher.update(&data[..half]);
+            hasher.update(&data[data.len() - half..]);
+        }
+        Self::Sample(hasher.finalize().into())
+    }
+}
+
 /// Clipboard monitor for detecting changes
 pub struct ClipboardMonitor {
     /// Hash of last seen clipboard content
     last_hash: Option<[u8; 32]>,
+    /// Change marker at the last poll
+    last_marker: Option<ChangeMarker>,
+    /// Bytes of content hashed for a sampled change marker
+    sample_bytes: usize,
     /// Whether monitoring is active
     running: Arc<AtomicBool>,
     /// Polling interval
@@ -30,6 +75,8 @@ impl ClipboardMonitor {
     pub fn new() -> Self {
         Self {
             last_hash: None,
+            last_marker: None,
+            sample_bytes: DEFAULT_CHANGE_SAMPLE_BYTES,
             running: Arc::new(AtomicBool::new(false)),
             poll_interval: Duration::from_millis(DEFAULT_POLL_INTERVAL_MS),
         }
@@ -39,11 +86,46 @@ impl ClipboardMonitor {
     pub fn with_interval(interval_ms: u64) -> Self {
         Self {
             last_hash: None,
+            last_marker: None,
+            sample_bytes: DEFAULT_CHANGE_SAMPLE_BYTES,
             running: Arc::new(AtomicBool::new(false)),
             poll_interval: Duration::from_millis(interval_ms),
         }
     }
 
+    /// Poll `clipboard` and report whether its content changed
+    ///
+    /// Reads the content in full only if the provider's change marker moved
+    /// or it has none.
+    pub fn poll_change(&mut self, clipboard: &dyn ClipboardProvider) -> bool {
+        if let Some(marker) = clipboard.change_marker(self.sample_bytes) {
+            let unchanged = self.last_marker.replace(marker) == Some(marker);
+            if unchanged && self.last_hash.is_some() {
+                return false;
+            }
+        }
+
+        match clipboard.read() {
+            Ok(Some(content)) => self.check_change(&content),
+            _ => false,
+        }
+    }
+
+    /// Take the clipboard's current content as already seen
+    ///
+    /// Used at startup, so content copied before the app started isn't
+    /// reported as a new copy. Returns whether the clipboard had content.
+    pub fn seed(&mut self, clipboard: &dyn ClipboardProvider) -> bool {
+        self.last_marker = clipboard.change_marker(self.sample_bytes);
+        match clipboard.read() {
+            Ok(Some(content)) => {
+                self.update_hash(&content);
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// Check if content has changed since last check
     pub fn check_change(&mut self, content: &ClipboardContent) -> bool {
         let new_hash = Self::hash_content(content);
@@ -60,6 +142,7 @@ impl ClipboardMonitor {
     /// Reset the last hash (useful when writing to clipboard)
     pub fn reset(&mut self) {
         self.last_hash = None;
+        self.last_marker = None;
     }
 
     /// Update the last hash without checking for change
@@ -77,6 +160,16 @@ impl ClipboardMonitor {
         self.poll_interval = Duration::from_millis(interval_ms);
     }
 
+    /// Get the bytes of content hashed for a sampled change marker
+    pub fn sample_bytes(&self) -> usize {
+        self.sample_bytes
+    }
+
+    /// Set the bytes of content hashed for a sampled change marker
+    pub fn set_sample_bytes(&mut self, sample_bytes: usize) {
+        self.sample_bytes = sample_bytes;
+    }
+
     /// Check if monitoring is running
     pub fn is_running(&self) -> bool {
         self.running.load(Ordering::Relaxed)
@@ -199,4 +292,88 @@ mod tests {
         let monitor = ClipboardMonitor::with_interval(500);
         assert_eq!(monitor.poll_interval(), Duration::from_millis(500));
     }
+
+    #[test]
+    fn test_seeded_content_is_not_a_change() {
+        let clipboard = crate::clipboard::MockClipboard::default();
+        clipboard
+            .write(&ClipboardContent::text("Copied before start"))
+            .unwrap();
+        let mut monitor = ClipboardMonitor::new();
+
+        assert!(monitor.seed(&clipboard));
+        assert!(!monitor.poll_change(&clipboard));
+        assert!(!monitor.poll_change(&clipboard));
+
+        clipboard
+            .write(&ClipboardContent::text("Copied after start"))
+            .unwrap();
+        assert!(monitor.poll_change(&clipboard));
+        assert!(!monitor.poll_change(&clipboard));
+
+        // An empty clipboard leaves the first copy reported as new
+        let empty = crate::clipboard::MockClipboard::default();
+        let mut monitor = ClipboardMonitor::new();
+        assert!(!monitor.seed(&empty));
+        empty.write(&ClipboardContent::text("First")).unwrap();
+        assert!(monitor.poll_change(&empty));
+    }
+
+    /// Clipboard holding one large image, counting full reads
+    #[derive(Default)]
+    struct LargeImageClipboard {
+        data: parking_lot::Mutex<Vec<u8>>,
+        reads: std::sync::atomic::AtomicUsize,
+    }
+
+    impl ClipboardProvider for LargeImageClipboard {
+        fn read(&self) -> Result<Option<ClipboardContent>, crate::error::ClipboardError> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            let data = self.data.lock().clone();
+            Ok(Some(ClipboardContent::image(data, None, None)))
+        }
+
+        fn change_marker(&self, sample_bytes: usize) -> Option<ChangeMarker> {
+            Some(ChangeMarker::sample(
+                ContentType::Image,
+                &self.data.lock(),
+                sample_bytes,
+            ))
+        }
+
+        fn write(&self, _content: &ClipboardContent) -> Result<(), crate::error::ClipboardError> {
+            Ok(())
+        }
+
+        fn clear(&self) -> Result<(), crate::error::ClipboardError> {
+            Ok(())
+        }
+
+        fn supports_type(&self, _content_type: ContentType) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_unchanged_large_content_is_not_reread() {
+        let clipboard = LargeImageClipboard::default();
+        *clipboard.data.lock() = vec![0x42; 40 * 1024 * 1024];
+        let mut monitor = ClipboardMonitor::new();
+
+        assert!(monitor.poll_change(&clipboard));
+        for _ in 0..10 {
+            assert!(!monitor.poll_change(&clipboard));
+        }
+        assert_eq!(clipboard.reads.load(Ordering::SeqCst), 1);
+
+        // A new copy moves the marker and is read
+        clipboard.data.lock().push(0x43);
+        assert!(monitor.poll_change(&clipboard));
+        assert_eq!(clipboard.reads.load(Ordering::SeqCst), 2);
+
+        // After a reset the next poll reads and reports a change again
+        monitor.reset();
+        assert!(monitor.poll_change(&clipboard));
+        assert_eq!(clipboard.reads.load(Ordering::SeqCst), 3);
+    }
 }
@@ -0,0 +1,161 @@
+//! Quiet hours: a daily window in which clipboard sync stays silent
+//!
+//! The window is given in the device's local time, as minutes after
+//! midnight, and may cross midnight (e.g. 22:00 to 07:00). Inside it, local
+//! changes aren't sent automatically and received updates aren't written to
+//! the clipboard. Received updates are held instead, and only the latest is
+//! applied once the window ends.
+
+use chrono::{DateTime, FixedOffset, Timelike, Utc};
+use parking_lot::Mutex;
+
+/// Minutes in a day
+pub const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// A daily window in local time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    start_minute: u32,
+    end_minute: u32,
+}
+
+impl QuietHours {
+    /// Window from `start_minute` to `end_minute` after local midnight
+    ///
+    /// An end before the start crosses midnight. Returns `None` for minutes
+    /// outside the day or an empty window (start equal to end).
+    pub fn new(start_minute: u32, end_minute: u32) -> Option<Self> {
+        if start_minute >= MINUTES_PER_DAY
+            || end_minute >= MINUTES_PER_DAY
+            || start_minute == end_minute
+        {
+            return None;
+        }
+        Some(Self {
+            start_minute,
+            end_minute,
+        })
+    }
+
+    /// Whether a local time, in minutes after midnight, is inside the window
+    ///
+    /// The start is inside, the end is not.
+    pub fn contains_minute(&self, minute: u32) -> bool {
+        if self.start_minute < self.end_minute {
+            (self.start_minute..self.end_minute).contains(&minute)
+        } else {
+            minute >= self.start_minute || minute < self.end_minute
+        }
+    }
+
+    /// Whether `now` is inside the window on a device at `offset` from UTC
+    pub fn is_active(&self, now: DateTime<Utc>, offset: FixedOffset) -> bool {
+        let local = now.with_timezone(&offset);
+        self.contains_minute(local.hour() * 60 + local.minute())
+    }
+}
+
+/// Received updates held back during quiet hours
+///
+/// Only the latest is kept; earlier ones were superseded by it anyway.
+pub struct QuietHoursQueue<T> {
+    latest: Mutex<Option<T>>,
+}
+
+impl<T> QuietHoursQueue<T> {
+    pub fn new() -> Self {
+        Self {
+            latest: Mutex::new(None),
+        }
+    }
+
+    /// Hold an update until the window ends, replacing any held one
+    pub fn hold(&self, update: T) {
+        *self.latest.lock() = Some(update);
+    }
+
+    /// Drop the held update, e.g. because newer local content replaced it
+    pub fn clear(&self) {
+        *self.latest.lock() = None;
+    }
+
+    /// Take the held update once the window is over
+    ///
+    /// Returns `None` while `quiet_hours` is active or nothing is held.
+    /// Disabling quiet hours (`None`) releases the held update too.
+    pub fn release(
+        &self,
+        quiet_hours: Option<QuietHours>,
+        now: DateTime<Utc>,
+        offset: FixedOffset,
+    ) -> Option<T> {
+        if quiet_hours.is_some_and(|q| q.is_active(now, offset)) {
+            return None;
+        }
+        self.latest.lock().take()
+    }
+}
+
+impl<T> Default for QuietHoursQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 10, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_window_crossing_midnight_in_local_time() {
+        // 22:00 to 07:00 local, on a device at UTC+02:00
+        let quiet = QuietHours::new(22 * 60, 7 * 60).unwrap();
+        let offset = FixedOffset::east_opt(2 * 3600).unwrap();
+
+        assert!(quiet.is_active(at(20, 0), offset)); // 22:00 local
+        assert!(quiet.is_active(at(23, 30), offset)); // 01:30 local
+        assert!(quiet.is_active(at(4, 59), offset)); // 06:59 local
+        assert!(!quiet.is_active(at(5, 0), offset)); // 07:00 local
+        assert!(!quiet.is_active(at(19, 59), offset)); // 21:59 local
+
+        // The same UTC time is outside the window in UTC itself
+        assert!(!quiet.is_active(at(20, 0), FixedOffset::east_opt(0).unwrap()));
+
+        // A daytime window doesn't wrap
+        let lunch = QuietHours::new(12 * 60, 13 * 60).unwrap();
+        assert!(lunch.contains_minute(12 * 60 + 30));
+        assert!(!lunch.contains_minute(23 * 60));
+
+        assert!(QuietHours::new(60, 60).is_none());
+        assert!(QuietHours::new(0, MINUTES_PER_DAY).is_none());
+    }
+
+    #[test]
+    fn test_updates_held_in_window_and_latest_applied_after() {
+        let quiet = Some(QuietHours::new(22 * 60, 7 * 60).unwrap());
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let queue = QuietHoursQueue::new();
+
+        queue.hold("first");
+        queue.hold("second");
+        assert_eq!(queue.release(quiet, at(23, 0), utc), None);
+        assert_eq!(queue.release(quiet, at(6, 59), utc), None);
+
+        assert_eq!(queue.release(quiet, at(7, 0), utc), Some("second"));
+        assert_eq!(queue.release(quiet, at(7, 1), utc), None);
+
+        // A local change made during the window wins over held updates
+        queue.hold("third");
+        queue.clear();
+        assert_eq!(queue.release(quiet, at(8, 0), utc), None);
+
+        // Turning quiet hours off releases what was held
+        queue.hold("fourth");
+        assert_eq!(queue.release(None, at(23, 0), utc), Some("fourth"));
+    }
+}
@@ -0,0 +1,187 @@
+//! Multi-format clipboard snapshots
+//!
+//! Captures every representation the clipboard offers (e.g. HTML + plain text
+//! + image) so they can be stored and restored together as one history entry.
+
+use super::handler::ClipboardProvider;
+use crate::error::ClipboardError;
+use crate::protocol::{ClipboardContent, ContentType};
+
+/// All representations of a single clipboard copy, highest fidelity first
+#[derive(Debug, Clone)]
+pub struct ClipboardSnapshot {
+    representations: Vec<ClipboardContent>,
+}
+
+impl ClipboardSnapshot {
+    /// Build a snapshot from representations, ordering them by fidelity
+    ///
+    /// Keeps at most one representation per content type.
+    pub fn new(mut representations: Vec<ClipboardContent>) -> Self {
+        representations.sort_by_key(|c| fidelity_rank(c.content_type));
+        representations.dedup_by_key(|c| c.content_type);
+        Self { representations }
+    }
+
+    /// Capture all formats currently on the clipboard
+    ///
+    /// Representations are kept in fidelity order while the group stays within
+    /// `max_bytes`; any that would exceed it are dropped. Returns `Ok(None)` if
+    /// the clipboard is empty.
+    pub fn capture(
+        provider: &dyn ClipboardProvider,
+        max_bytes: u64,
+    ) -> Result<Option<Self>, ClipboardError> {
+        let all = Self::new(provider.read_all()?);
+        if all.representations.is_empty() {
+            return Ok(None);
+        }
+
+        let mut total = 0u64;
+        let mut kept = Vec::new();
+        for content in all.representations {
+            let size = content.metadata.size_bytes;
+            if total.saturating_add(size) <= max_bytes {
+                total += size;
+                kept.push(content);
+            }
+        }
+
+        if kept.is_empty() {
+            return Err(ClipboardError::UnsupportedFormat(format!(
+                "all clipboard formats exceed the {} byte limit",
+                max_bytes
+            )));
+        }
+
+        Ok(Some(Self {
+            representations: kept,
+        }))
+    }
+
+    /// Write all representations back to the clipboard
+    pub fn restore(&self, provider: &dyn ClipboardProvider) -> Result<(), ClipboardError> {
+        provider.write_all(&self.representations)
+    }
+
+    /// The highest-fidelity representation
+    pub fn primary(&self) -> Option<&ClipboardContent> {
+        self.representations.first()
+    }
+
+    /// All representations, highest fidelity first
+    pub fn representations(&self) -> &[ClipboardContent] {
+        &self.representations
+    }
+
+    /// Combined size of all representations in bytes
+    pub fn total_size(&self) -> u64 {
+        self.representations
+            .iter()
+            .map(|c| c.metadata.size_bytes)
+            .sum()
+    }
+}
+
+/// Lower rank = higher fidelity
+fn fidelity_rank(content_type: ContentType) -> u8 {
+    match content_type {
+        ContentType::RichText => 0,
+        ContentType::Image => 1,
+        ContentType::File => 2,
+        ContentType::Code => 3,
+        ContentType::Url => 4,
+        ContentType::PlainText => 5,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    /// In-memory clipboard holding multiple formats at once
+    #[derive(Default)]
+    pub(crate) struct MockClipboard {
+        pub(crate) formats: Mutex<Vec<ClipboardContent>>,
+    }
+
+    impl ClipboardProvider for MockClipboard {
+        fn read(&self) -> Result<Option<ClipboardContent>, ClipboardError> {
+            Ok(self.formats.lock().first().cloned())
+        }
+
+        fn read_all(&self) -> Result<Vec<ClipboardContent>, ClipboardError> {
+            Ok(self.formats.lock().clone())
+        }
+
+        fn write(&self, content: &ClipboardContent) -> Result<(), ClipboardError> {
+            *self.formats.lock() = vec![content.clone()];
+            Ok(())
+        }
+
+        fn write_all(&self, contents: &[ClipboardContent]) -> Result<(), ClipboardError> {
+            *self.formats.lock() = contents.to_vec();
+            Ok(())
+        }
+
+        fn clear(&self) -> Result<(), ClipboardError> {
+            self.formats.lock().clear();
+            Ok(())
+        }
+
+        fn supports_type(&self, _content_type: ContentType) -> bool {
+            true
+        }
+    }
+
+    pub(crate) fn rich_clipboard() -> MockClipboard {
+        let clipboard = MockClipboard::default();
+        *clipboard.formats.lock() = vec![
+            ClipboardContent::text("Hello"),
+            ClipboardContent::new(ContentType::RichText, b"<b>Hello</b>".to_vec()),
+            ClipboardContent::image(vec![0x89; 64], Some((4, 4)), None),
+        ];
+        clipboard
+    }
+
+    #[test]
+    fn test_capture_orders_by_fidelity() {
+        let snapshot = ClipboardSnapshot::capture(&rich_clipboard(), u64::MAX)
+            .unwrap()
+            .unwrap();
+
+        let types: Vec<_> = snapshot
+            .representations()
+            .iter()
+            .map(|c| c.content_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ContentType::RichText,
+                ContentType::Image,
+                ContentType::PlainText
+            ]
+        );
+        assert_eq!(snapshot.total_size(), 12 + 64 + 5);
+    }
+
+    #[test]
+    fn test_capture_drops_formats_over_limit() {
+        // Room for the HTML and plain text but not the image as well
+        let snapshot = ClipboardSnapshot::capture(&rich_clipboard(), 20)
+            .unwrap()
+            .unwrap();
+        assert_eq!(snapshot.representations().len(), 2);
+        assert!(snapshot
+            .representations()
+            .iter()
+            .all(|c| c.content_type != ContentType::Image));
+
+        assert!(ClipboardSnapshot::capture(&rich_clipboard(), 1).is_err());
+        assert!(ClipboardSnapshot::capture(&MockClipboard::default(), 100)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,61 @@
+//! Time source for expiry checks
+//!
+//! Pairing expiry reads the time through [`Clock`] so tests can move time
+//! forward instead of sleeping.
+
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+/// Source of the current time
+pub trait Clock: Send + Sync {
+    /// Current time
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Current Unix timestamp in seconds
+    fn now_secs(&self) -> u64 {
+        self.now().timestamp().max(0) as u64
+    }
+}
+
+/// The real system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that only moves when told to, for tests
+#[derive(Debug)]
+pub struct ManualClock {
+    millis: AtomicI64,
+}
+
+impl ManualClock {
+    /// Create a clock stopped at `start`
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            millis: AtomicI64::new(start.timestamp_millis()),
+        }
+    }
+
+    /// Jump to `time`
+    pub fn set(&self, time: DateTime<Utc>) {
+        self.millis.store(time.timestamp_millis(), Ordering::SeqCst);
+    }
+
+    /// Move forward by `by`
+    pub fn advance(&self, by: Duration) {
+        self.millis
+            .fetch_add(by.as_millis() as i64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(self.millis.load(Ordering::SeqCst)).unwrap_or_default()
+    }
+}
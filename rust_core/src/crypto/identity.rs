@@ -1,13 +1,60 @@
 //! Device identity using Ed25519 signatures
+//!
+//! Identity keys carry a `KeyAlgorithm` tag, stored in front of the private
+//! key and sent to peers in `DeviceInfo`, so other signature algorithms can be
+//! added later. Ed25519 is the default and the only one implemented; keys
+//! stored before tagging (exactly 32 bytes) are read as Ed25519.
 
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
 use crate::error::CryptoError;
 
+/// Signature algorithm of an identity key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAlgorithm {
+    #[default]
+    Ed25519,
+    /// Reserved tag; this build can't sign or verify with it
+    Ed448,
+}
+
+impl KeyAlgorithm {
+    /// Byte stored in front of a tagged private key
+    pub const fn tag(self) -> u8 {
+        match self {
+            Self::Ed25519 => 1,
+            Self::Ed448 => 2,
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            1 => Some(Self::Ed25519),
+            2 => Some(Self::Ed448),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ed25519 => "ed25519",
+            Self::Ed448 => "ed448",
+        }
+    }
+
+    /// Whether this build can sign and verify with the algorithm
+    pub fn is_supported(self) -> bool {
+        matches!(self, Self::Ed25519)
+    }
+}
+
 /// Device identity containing Ed25519 signing keys
 pub struct DeviceIdentity {
+    algorithm: KeyAlgorithm,
     signing_key: SigningKey,
     verifying_key: VerifyingKey,
     device_id: [u8; 32],
@@ -18,20 +65,17 @@ impl DeviceIdentity {
     pub fn generate() -> Result<Self, CryptoError> {
         let signing_key = SigningKey::generate(&mut OsRng);
         let verifying_key = signing_key.verifying_key();
-
-        // Device ID is SHA-256 hash of public key
-        let mut hasher = Sha256::new();
-        hasher.update(verifying_key.as_bytes());
-        let device_id: [u8; 32] = hasher.finalize().into();
+        let device_id = Self::device_id_for(verifying_key.as_bytes());
 
         Ok(Self {
+            algorithm: KeyAlgorithm::Ed25519,
             signing_key,
             verifying_key,
             device_id,
         })
     }
 
-    /// Load identity from bytes (private key)
+    /// Load an Ed25519 identity from untagged private key bytes
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
         if bytes.len() != 32 {
             return Err(CryptoError::InvalidKey);
@@ -39,28 +83,75 @@ impl DeviceIdentity {
 
         let signing_key = SigningKey::try_from(bytes).map_err(|_| CryptoError::InvalidKey)?;
         let verifying_key = signing_key.verifying_key();
-
-        let mut hasher = Sha256::new();
-        hasher.update(verifying_key.as_bytes());
-        let device_id: [u8; 32] = hasher.finalize().into();
+        let device_id = Self::device_id_for(verifying_key.as_bytes());
 
         Ok(Self {
+            algorithm: KeyAlgorithm::Ed25519,
             signing_key,
             verifying_key,
             device_id,
         })
     }
 
-    /// Export private key bytes for secure storage
+    /// Load identity from tagged private key bytes
+    ///
+    /// Untagged 32-byte keys are treated as Ed25519.
+    pub fn from_tagged_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        if bytes.len() == 32 {
+            return Self::from_bytes(bytes);
+        }
+
+        let (&tag, key) = bytes.split_first().ok_or(CryptoError::InvalidKey)?;
+        match KeyAlgorithm::from_tag(tag).ok_or(CryptoError::InvalidKey)? {
+            KeyAlgorithm::Ed25519 => Self::from_bytes(key),
+            algorithm => Err(CryptoError::UnsupportedAlgorithm(
+                algorithm.as_str().to_string(),
+            )),
+        }
+    }
+
+    /// Export untagged private key bytes
     pub fn to_bytes(&self) -> [u8; 32] {
         self.signing_key.to_bytes()
     }
 
+    /// Export private key bytes prefixed with the algorithm tag, for secure storage
+    pub fn to_tagged_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(33);
+        bytes.push(self.algorithm.tag());
+        bytes.extend_from_slice(&self.signing_key.to_bytes());
+        bytes
+    }
+
+    /// Get the signature algorithm of the identity key
+    pub fn algorithm(&self) -> KeyAlgorithm {
+        self.algorithm
+    }
+
     /// Get the device ID (hash of public key)
     pub fn device_id(&self) -> &[u8; 32] {
         &self.device_id
     }
 
+    /// Device ID belonging to a public key (its SHA-256 hash)
+    pub fn device_id_for(public_key: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(public_key);
+        hasher.finalize().into()
+    }
+
+    /// Fingerprint of a public key, for users to compare between devices
+    ///
+    /// The key's device ID in upper-case hex, in groups of four digits.
+    pub fn fingerprint_for(public_key: &[u8]) -> String {
+        let hex = hex::encode_upper(Self::device_id_for(public_key));
+        hex.as_bytes()
+            .chunks(4)
+            .map(|group| std::str::from_utf8(group).expect("hex is ASCII"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
     /// Get device ID as hex string
     pub fn device_id_hex(&self) -> String {
         hex::encode(self.device_id)
@@ -88,24 +179,38 @@ impl DeviceIdentity {
         self.verifying_key.verify(message, &sig).is_ok()
     }
 
-    /// Verify a signature from another device
+    /// Verify a signature from another device using the algorithm of its key
+    ///
+    /// Fails for algorithms this build doesn't support and for keys or
+    /// signatures of the wrong size for `algorithm`.
     pub fn verify_from_public_key(
-        public_key: &[u8; 32],
+        algorithm: KeyAlgorithm,
+        public_key: &[u8],
         message: &[u8],
-        signature: &[u8; 64],
+        signature: &[u8],
     ) -> bool {
-        let verifying_key = match VerifyingKey::from_bytes(public_key) {
-            Ok(k) => k,
-            Err(_) => return false,
-        };
-        let sig = Signature::from_bytes(signature);
-        verifying_key.verify(message, &sig).is_ok()
+        match algorithm {
+            KeyAlgorithm::Ed25519 => {
+                let Ok(public_key) = <&[u8; 32]>::try_from(public_key) else {
+                    return false;
+                };
+                let verifying_key = match VerifyingKey::from_bytes(public_key) {
+                    Ok(k) => k,
+                    Err(_) => return false,
+                };
+                let Ok(sig) = Signature::from_slice(signature) else {
+                    return false;
+                };
+                verifying_key.verify(message, &sig).is_ok()
+            }
+            KeyAlgorithm::Ed448 => false,
+        }
     }
 }
 
 impl Clone for DeviceIdentity {
     fn clone(&self) -> Self {
-        Self::from_bytes(&self.to_bytes()).unwrap()
+        Self::from_tagged_bytes(&self.to_tagged_bytes()).unwrap()
     }
 }
 
@@ -150,9 +255,63 @@ mod tests {
 
         let public_key = identity.public_key();
         assert!(DeviceIdentity::verify_from_public_key(
+            KeyAlgorithm::Ed25519,
             &public_key,
             message,
             &signature
         ));
     }
+
+    #[test]
+    fn test_tagged_roundtrip() {
+        let identity = DeviceIdentity::generate().unwrap();
+        assert_eq!(identity.algorithm(), KeyAlgorithm::Ed25519);
+
+        let bytes = identity.to_tagged_bytes();
+        assert_eq!(bytes[0], KeyAlgorithm::Ed25519.tag());
+        let restored = DeviceIdentity::from_tagged_bytes(&bytes).unwrap();
+        assert_eq!(restored.algorithm(), KeyAlgorithm::Ed25519);
+        assert_eq!(identity.public_key(), restored.public_key());
+
+        // Keys stored before tagging load as Ed25519
+        let legacy = DeviceIdentity::from_tagged_bytes(&identity.to_bytes()).unwrap();
+        assert_eq!(legacy.algorithm(), KeyAlgorithm::Ed25519);
+        assert_eq!(identity.device_id(), legacy.device_id());
+
+        // Unknown and unsupported tags are refused
+        let mut unknown = bytes.clone();
+        unknown[0] = 0xff;
+        assert!(matches!(
+            DeviceIdentity::from_tagged_bytes(&unknown),
+            Err(CryptoError::InvalidKey)
+        ));
+        let mut ed448 = bytes;
+        ed448[0] = KeyAlgorithm::Ed448.tag();
+        assert!(matches!(
+            DeviceIdentity::from_tagged_bytes(&ed448),
+            Err(CryptoError::UnsupportedAlgorithm(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_other_algorithm() {
+        let identity = DeviceIdentity::generate().unwrap();
+        let message = b"Test message";
+        let signature = identity.sign(message);
+        let public_key = identity.public_key();
+
+        assert!(!DeviceIdentity::verify_from_public_key(
+            KeyAlgorithm::Ed448,
+            &public_key,
+            message,
+            &signature
+        ));
+        // A key of the wrong size for the algorithm is rejected too
+        assert!(!DeviceIdentity::verify_from_public_key(
+            KeyAlgorithm::Ed25519,
+            &public_key[..31],
+            message,
+            &signature
+        ));
+    }
 }
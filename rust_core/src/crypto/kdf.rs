@@ -19,6 +19,10 @@ pub enum DerivedKeyPurpose {
     MessageAuthentication,
     /// Key for encrypting stored data
     StorageEncryption,
+    /// Seed of a clipboard channel ratchet
+    ClipboardChannel,
+    /// Next key in a clipboard channel ratchet
+    ClipboardRatchet,
 }
 
 impl DerivedKeyPurpose {
@@ -27,6 +31,8 @@ impl DerivedKeyPurpose {
             DerivedKeyPurpose::SessionEncryption => b"toss-session-encryption-v1",
             DerivedKeyPurpose::MessageAuthentication => b"toss-message-auth-v1",
             DerivedKeyPurpose::StorageEncryption => b"toss-storage-encryption-v1",
+            DerivedKeyPurpose::ClipboardChannel => b"toss-clipboard-channel-v1",
+            DerivedKeyPurpose::ClipboardRatchet => b"toss-clipboard-ratchet-v1",
         }
     }
 }
@@ -1,23 +1,31 @@
 //! Cryptographic operations for Toss
 //!
 //! This module provides:
-//! - Device identity (Ed25519 signing keys)
+//! - Device identity (Ed25519 signing keys, tagged with their algorithm)
 //! - Key exchange (X25519)
 //! - Symmetric encryption (AES-256-GCM)
 //! - Key derivation (HKDF-SHA256)
+//! - Clipboard channel key ratchet
 //! - Device pairing protocol
 
 mod identity;
 mod kdf;
 mod key_exchange;
 mod pairing;
+mod ratchet;
 mod symmetric;
 
-pub use identity::DeviceIdentity;
+pub use identity::{DeviceIdentity, KeyAlgorithm};
 pub use kdf::{derive_key, DerivedKeyPurpose};
 pub use key_exchange::{EphemeralKeyPair, SharedSecret};
-pub use pairing::{PairingInfo, PairingSession};
-pub use symmetric::{decrypt, encrypt, EncryptedMessage};
+pub use pairing::{
+    generate_qr_data, is_valid_pairing_code, paired_device_id, parse_qr_data, CompletedPairing,
+    PairingInfo, PairingSession, QrPayload, PAIRING_CODE_LENGTH,
+};
+pub use ratchet::{epoch_millis, ChannelKey, ChannelRatchet, EPOCH_SIZE, MAX_RATCHET_SKIP};
+pub use symmetric::{
+    decrypt, encrypt, encrypt_with_nonce, EncryptedMessage, NonceSequence, NONCE_ROTATION_THRESHOLD,
+};
 
 /// Size of AES-256 key in bytes
 pub const KEY_SIZE: usize = 32;
@@ -6,14 +6,23 @@
 
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use super::{derive_key, DerivedKeyPurpose, EphemeralKeyPair, KEY_SIZE};
+use crate::clock::{Clock, SystemClock};
 use crate::error::CryptoError;
 
 /// Pairing session duration in seconds (5 minutes)
 const PAIRING_TIMEOUT_SECS: u64 = 300;
 
+/// Maximum device name length accepted in QR data
+const MAX_QR_DEVICE_NAME_LEN: usize = 64;
+
+/// Number of decimal digits in a pairing code
+pub const PAIRING_CODE_LENGTH: usize = 6;
+
 /// Information about a pairing session (for display/sharing)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PairingInfo {
@@ -27,11 +36,27 @@ pub struct PairingInfo {
     pub public_key: String,
 }
 
+/// A finished pairing, with everything needed to store the peer
+#[derive(Debug, Clone)]
+pub struct CompletedPairing {
+    /// Session key derived from the key exchange
+    pub session_key: [u8; KEY_SIZE],
+    /// Canonical ID of the peer (see [`paired_device_id`])
+    pub device_id: String,
+    /// Name the peer advertised, if the pairing data carried one
+    pub device_name: Option<String>,
+    /// Peer's public key
+    pub public_key: [u8; 32],
+    /// Peer's platform, if the pairing data carried one
+    pub platform: Option<String>,
+}
+
 /// Pairing session state
 pub struct PairingSession {
     code: String,
     ephemeral: EphemeralKeyPair,
     expires_at: u64,
+    clock: Arc<dyn Clock>,
 }
 
 /// QR code payload structure
@@ -45,24 +70,43 @@ pub struct QrPayload {
     pub pk: String,
     /// Device name
     pub name: String,
+    /// When the pairing session expires (Unix timestamp)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
 }
 
 impl PairingSession {
     /// Create a new pairing session
-    pub fn new(_device_name: &str) -> Self {
+    pub fn new(device_name: &str) -> Self {
+        Self::new_with_clock(device_name, Arc::new(SystemClock))
+    }
+
+    /// Create a new pairing session that reads the time from `clock`
+    pub fn new_with_clock(_device_name: &str, clock: Arc<dyn Clock>) -> Self {
         let code = generate_pairing_code();
         let ephemeral = EphemeralKeyPair::generate();
-        let expires_at = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            + PAIRING_TIMEOUT_SECS;
+        let expires_at = clock.now_secs() + PAIRING_TIMEOUT_SECS;
 
         Self {
             code,
             ephemeral,
             expires_at,
+            clock,
+        }
+    }
+
+    /// Rotate the pairing code and restart the expiry timer
+    ///
+    /// The ephemeral key is kept, so a peer that already has our public key
+    /// (e.g. from a scanned QR code) can still complete pairing.
+    pub fn refresh_code(&mut self) {
+        let mut code = generate_pairing_code();
+        while code == self.code {
+            code = generate_pairing_code();
         }
+
+        self.code = code;
+        self.expires_at = self.clock.now_secs() + PAIRING_TIMEOUT_SECS;
     }
 
     /// Get the pairing code
@@ -82,13 +126,12 @@ impl PairingSession {
             self.ephemeral.public_key_bytes(),
         );
 
-        let qr_payload = QrPayload {
-            v: crate::PROTOCOL_VERSION,
-            code: self.code.clone(),
-            pk: public_key.clone(),
-            name: device_name.to_string(),
-        };
-        let qr_data = serde_json::to_string(&qr_payload).unwrap();
+        let qr_data = generate_qr_data(
+            &self.code,
+            self.ephemeral.public_key_bytes(),
+            device_name,
+            self.expires_at,
+        );
 
         PairingInfo {
             code: self.code.clone(),
@@ -99,21 +142,18 @@ impl PairingSession {
     }
 
     /// Check if the session has expired
+    ///
+    /// The session is still valid during its `expires_at` second.
     pub fn is_expired(&self) -> bool {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        now > self.expires_at
+        self.clock.now_secs() > self.expires_at
     }
 
     /// Complete pairing with peer's public key and verification code
-    /// Returns the derived session key
     pub fn complete(
         self,
         peer_public_key: &[u8; 32],
         peer_code: &str,
-    ) -> Result<[u8; KEY_SIZE], CryptoError> {
+    ) -> Result<CompletedPairing, CryptoError> {
         // Check expiration
         if self.is_expired() {
             return Err(CryptoError::SessionExpired);
@@ -126,37 +166,20 @@ impl PairingSession {
             ));
         }
 
-        // Derive shared secret
-        let shared_secret = self.ephemeral.derive_shared_secret(peer_public_key);
-
-        // Derive session key from shared secret
-        let session_key = derive_key(
-            shared_secret.as_bytes(),
-            DerivedKeyPurpose::SessionEncryption,
-            None,
-        )?;
-
-        Ok(session_key)
+        self.finish(peer_public_key)
     }
 
     /// Complete pairing from QR code data
-    pub fn complete_from_qr(
-        self,
-        qr_data: &str,
-    ) -> Result<([u8; KEY_SIZE], String, String), CryptoError> {
-        let payload: QrPayload = serde_json::from_str(qr_data)
-            .map_err(|e| CryptoError::PairingFailed(format!("Invalid QR data: {}", e)))?;
+    pub fn complete_from_qr(self, qr_data: &str) -> Result<CompletedPairing, CryptoError> {
+        let payload = parse_qr_data(qr_data)?;
+        let peer_public_key = payload.public_key_bytes()?;
 
-        // Decode public key
-        let peer_public_key: [u8; 32] =
-            base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &payload.pk)
-                .map_err(|e| CryptoError::PairingFailed(format!("Invalid public key: {}", e)))?
-                .try_into()
-                .map_err(|_| CryptoError::PairingFailed("Invalid public key length".to_string()))?;
+        let completed = self.complete(&peer_public_key, &payload.code)?;
 
-        let session_key = self.complete(&peer_public_key, &payload.code)?;
-
-        Ok((session_key, payload.name, payload.pk))
+        Ok(CompletedPairing {
+            device_name: Some(payload.name),
+            ..completed
+        })
     }
 
     /// Complete pairing with peer's public key only (code already verified via relay/mDNS)
@@ -164,12 +187,17 @@ impl PairingSession {
     pub fn complete_with_peer_key(
         self,
         peer_public_key: &[u8; 32],
-    ) -> Result<[u8; KEY_SIZE], CryptoError> {
+    ) -> Result<CompletedPairing, CryptoError> {
         // Check expiration
         if self.is_expired() {
             return Err(CryptoError::SessionExpired);
         }
 
+        self.finish(peer_public_key)
+    }
+
+    /// Derive the session key with the peer and describe the pairing
+    fn finish(self, peer_public_key: &[u8; 32]) -> Result<CompletedPairing, CryptoError> {
         // Derive shared secret using X25519
         let shared_secret = self.ephemeral.derive_shared_secret(peer_public_key);
 
@@ -180,15 +208,69 @@ impl PairingSession {
             None,
         )?;
 
-        Ok(session_key)
+        Ok(CompletedPairing {
+            session_key,
+            device_id: paired_device_id(peer_public_key),
+            device_name: None,
+            public_key: *peer_public_key,
+            platform: None,
+        })
+    }
+}
+
+impl QrPayload {
+    /// Decode the base64 public key
+    pub fn public_key_bytes(&self) -> Result<[u8; 32], CryptoError> {
+        base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &self.pk)
+            .map_err(|e| CryptoError::PairingFailed(format!("Invalid public key: {}", e)))?
+            .try_into()
+            .map_err(|_| CryptoError::PairingFailed("Invalid public key length".to_string()))
     }
+
+    /// Check if the advertised pairing session has expired
+    pub fn is_expired(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.exp.is_some_and(|exp| now > exp)
+    }
+}
+
+/// Generate QR code data for a pairing code and public key
+pub fn generate_qr_data(
+    code: &str,
+    public_key: &[u8; 32],
+    device_name: &str,
+    expires_at: u64,
+) -> String {
+    let qr_payload = QrPayload {
+        v: crate::PROTOCOL_VERSION,
+        code: code.to_string(),
+        pk: base64::Engine::encode(&base64::engine::general_purpose::STANDARD, public_key),
+        name: device_name.to_string(),
+        exp: Some(expires_at),
+    };
+    serde_json::to_string(&qr_payload).unwrap()
+}
+
+/// Canonical ID of a device paired with `public_key`
+///
+/// The first 16 bytes of the key's SHA-256, hex encoded.
+pub fn paired_device_id(public_key: &[u8; 32]) -> String {
+    hex::encode(&Sha256::digest(public_key)[..16])
 }
 
 /// Generate a 6-digit pairing code
 fn generate_pairing_code() -> String {
     let mut rng = StdRng::from_entropy();
-    let code: u32 = rng.gen_range(0..1_000_000);
-    format!("{:06}", code)
+    let code: u32 = rng.gen_range(0..10u32.pow(PAIRING_CODE_LENGTH as u32));
+    format!("{:0width$}", code, width = PAIRING_CODE_LENGTH)
+}
+
+/// Whether `code` has the pairing code format: [`PAIRING_CODE_LENGTH`] ASCII digits
+pub fn is_valid_pairing_code(code: &str) -> bool {
+    code.len() == PAIRING_CODE_LENGTH && code.chars().all(|c| c.is_ascii_digit())
 }
 
 /// Constant-time comparison to prevent timing attacks
@@ -204,15 +286,46 @@ fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
     result == 0
 }
 
-/// Parse QR code data and extract peer info without completing pairing
+/// Parse and validate QR code data without completing pairing
 pub fn parse_qr_data(qr_data: &str) -> Result<QrPayload, CryptoError> {
-    serde_json::from_str(qr_data)
-        .map_err(|e| CryptoError::PairingFailed(format!("Invalid QR data: {}", e)))
+    let payload: QrPayload = serde_json::from_str(qr_data)
+        .map_err(|e| CryptoError::PairingFailed(format!("Invalid QR data: {}", e)))?;
+
+    if payload.v == 0 || payload.v > crate::PROTOCOL_VERSION {
+        return Err(CryptoError::PairingFailed(format!(
+            "Unsupported QR version: {}",
+            payload.v
+        )));
+    }
+
+    if !is_valid_pairing_code(&payload.code) {
+        return Err(CryptoError::PairingFailed(
+            "Invalid pairing code".to_string(),
+        ));
+    }
+
+    payload.public_key_bytes()?;
+
+    let name = payload.name.trim();
+    if name.is_empty() || name.len() > MAX_QR_DEVICE_NAME_LEN {
+        return Err(CryptoError::PairingFailed(
+            "Invalid device name".to_string(),
+        ));
+    }
+
+    if payload.is_expired() {
+        return Err(CryptoError::SessionExpired);
+    }
+
+    Ok(payload)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::ManualClock;
+    use chrono::DateTime;
+    use std::time::Duration;
 
     #[test]
     fn test_pairing_code_format() {
@@ -245,11 +358,14 @@ mod tests {
         let public_key_b = *session_b.public_key_bytes();
 
         // Both complete with each other's keys and A's code
-        let key_a = session_a.complete(&public_key_b, &code_a).unwrap();
+        let completed = session_a.complete(&public_key_b, &code_a).unwrap();
 
         // For a real pairing, B would use A's code from QR
         // Here we just verify the key derivation works
-        assert_eq!(key_a.len(), KEY_SIZE);
+        assert_eq!(completed.session_key.len(), KEY_SIZE);
+        assert_eq!(completed.public_key, public_key_b);
+        assert_eq!(completed.device_id, paired_device_id(&public_key_b));
+        assert!(completed.device_name.is_none());
     }
 
     #[test]
@@ -262,6 +378,74 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_refresh_code_keeps_public_key() {
+        let mut session = PairingSession::new("Test Device");
+        session.expires_at = 0;
+        let old_code = session.code().to_string();
+        let public_key = *session.public_key_bytes();
+        assert!(session.is_expired());
+
+        session.refresh_code();
+
+        assert_ne!(session.code(), old_code);
+        assert_eq!(session.public_key_bytes(), &public_key);
+        assert!(!session.is_expired());
+
+        let info = session.info("Test Device");
+        assert_eq!(
+            parse_qr_data(&info.qr_data)
+                .unwrap()
+                .public_key_bytes()
+                .unwrap(),
+            public_key
+        );
+
+        let peer_public_key = *PairingSession::new("Peer").public_key_bytes();
+        let code = session.code().to_string();
+        assert!(session.complete(&peer_public_key, &code).is_ok());
+    }
+
+    #[test]
+    fn test_session_expires_after_boundary() {
+        let clock = Arc::new(ManualClock::new(
+            DateTime::from_timestamp(1_000_000, 0).unwrap(),
+        ));
+        let session = PairingSession::new_with_clock("Test Device", clock.clone());
+        assert_eq!(session.info("Test Device").expires_at, 1_000_300);
+
+        // Still valid at exactly `expires_at`
+        clock.advance(Duration::from_secs(PAIRING_TIMEOUT_SECS));
+        assert!(!session.is_expired());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(session.is_expired());
+
+        let code = session.code().to_string();
+        assert!(matches!(
+            session.complete(&[0u8; 32], &code),
+            Err(CryptoError::SessionExpired)
+        ));
+    }
+
+    #[test]
+    fn test_refresh_code_restarts_expiry_from_clock() {
+        let clock = Arc::new(ManualClock::new(
+            DateTime::from_timestamp(1_000_000, 0).unwrap(),
+        ));
+        let mut session = PairingSession::new_with_clock("Test Device", clock.clone());
+
+        clock.advance(Duration::from_secs(PAIRING_TIMEOUT_SECS + 1));
+        assert!(session.is_expired());
+
+        session.refresh_code();
+        assert!(!session.is_expired());
+        assert_eq!(
+            session.info("Test Device").expires_at,
+            1_000_000 + 2 * PAIRING_TIMEOUT_SECS + 1
+        );
+    }
+
     #[test]
     fn test_qr_payload_serialization() {
         let session = PairingSession::new("My Device");
@@ -272,6 +456,82 @@ mod tests {
         assert_eq!(payload.name, "My Device");
     }
 
+    #[test]
+    fn test_parse_valid_qr_data() {
+        let session = PairingSession::new("My Device");
+        let info = session.info("My Device");
+
+        let payload = parse_qr_data(&info.qr_data).unwrap();
+        assert_eq!(payload.code, info.code);
+        assert_eq!(payload.pk, info.public_key);
+        assert_eq!(payload.name, "My Device");
+        assert_eq!(payload.exp, Some(info.expires_at));
+        assert_eq!(
+            &payload.public_key_bytes().unwrap(),
+            session.public_key_bytes()
+        );
+    }
+
+    #[test]
+    fn test_completed_pairing_uses_canonical_device_id() {
+        let session = PairingSession::new("Scanner");
+        let peer = EphemeralKeyPair::generate();
+        let peer_key = *peer.public_key_bytes();
+        let expires_at = session.info("Scanner").expires_at;
+        let qr_data = generate_qr_data(session.code(), &peer_key, "Laptop", expires_at);
+
+        let completed = session.complete_from_qr(&qr_data).unwrap();
+
+        // First 16 bytes of the key's SHA-256, as the device was always stored
+        let digest = Sha256::digest(peer_key);
+        assert_eq!(completed.device_id, hex::encode(&digest[..16]));
+        assert_eq!(completed.device_id.len(), 32);
+        assert_eq!(completed.public_key, peer_key);
+        assert_eq!(completed.device_name.as_deref(), Some("Laptop"));
+        assert!(completed.platform.is_none());
+    }
+
+    #[test]
+    fn test_parse_expired_qr_data() {
+        let qr_data = generate_qr_data("123456", &[7u8; 32], "Old Device", 1);
+        assert!(matches!(
+            parse_qr_data(&qr_data),
+            Err(CryptoError::SessionExpired)
+        ));
+    }
+
+    #[test]
+    fn test_parse_malformed_qr_data() {
+        let pk = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, [7u8; 32]);
+        let short_pk = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, [7u8; 8]);
+
+        let cases = [
+            "not json".to_string(),
+            "{}".to_string(),
+            format!(r#"{{"v":1,"code":"12345","pk":"{}","name":"A"}}"#, pk),
+            format!(r#"{{"v":1,"code":"abcdef","pk":"{}","name":"A"}}"#, pk),
+            format!(
+                r#"{{"v":1,"code":"123456","pk":"{}","name":"A"}}"#,
+                short_pk
+            ),
+            r#"{"v":1,"code":"123456","pk":"!!!","name":"A"}"#.to_string(),
+            format!(r#"{{"v":1,"code":"123456","pk":"{}","name":"  "}}"#, pk),
+            format!(r#"{{"v":999,"code":"123456","pk":"{}","name":"A"}}"#, pk),
+        ];
+
+        for qr_data in &cases {
+            assert!(
+                matches!(parse_qr_data(qr_data), Err(CryptoError::PairingFailed(_))),
+                "accepted malformed QR data: {}",
+                qr_data
+            );
+        }
+
+        // Payloads from older clients without an expiry are still accepted
+        let legacy = format!(r#"{{"v":1,"code":"123456","pk":"{}","name":"A"}}"#, pk);
+        assert!(parse_qr_data(&legacy).unwrap().exp.is_none());
+    }
+
     #[test]
     fn test_constant_time_eq() {
         assert!(constant_time_eq(b"hello", b"hello"));
@@ -0,0 +1,206 @@
+//! Symmetric key ratchet for the clipboard channel
+//!
+//! Relay-queued clipboard messages are encrypted with a per-message key from a
+//! hash chain seeded by the session key: `key_{n+1} = HKDF(key_n)`. Each key
+//! is discarded once used, so a compromise of the current chain key doesn't
+//! expose messages encrypted with earlier keys.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::kdf::{derive_key, DerivedKeyPurpose};
+use super::KEY_SIZE;
+use crate::error::CryptoError;
+
+/// Size of the epoch identifying one ratchet chain
+///
+/// An epoch is the time its chain started, in big-endian Unix milliseconds,
+/// so a sender's later chains always have larger epochs.
+pub const EPOCH_SIZE: usize = 8;
+
+/// Maximum number of keys a receiver skips ahead to reach a message
+pub const MAX_RATCHET_SKIP: u32 = 1000;
+
+/// Key for a single clipboard channel message
+#[derive(Clone)]
+pub struct ChannelKey {
+    /// Chain the key belongs to
+    pub epoch: [u8; EPOCH_SIZE],
+    /// Position in the chain
+    pub index: u32,
+    /// Encryption key for this message only
+    pub key: [u8; KEY_SIZE],
+}
+
+/// One direction of a clipboard channel ratchet
+///
+/// The chain is seeded from the session key, the sending device's ID and an
+/// epoch, so both directions and every restart use distinct chains.
+#[derive(Clone)]
+pub struct ChannelRatchet {
+    epoch: [u8; EPOCH_SIZE],
+    index: u32,
+    chain_key: [u8; KEY_SIZE],
+}
+
+impl ChannelRatchet {
+    /// Start a new sending chain, with an epoch later than any started before
+    pub fn new(session_key: &[u8; KEY_SIZE], sender_id: &[u8; 32]) -> Result<Self, CryptoError> {
+        Self::with_epoch(session_key, sender_id, next_epoch())
+    }
+
+    /// Follow the chain a sender started with `epoch`
+    pub fn with_epoch(
+        session_key: &[u8; KEY_SIZE],
+        sender_id: &[u8; 32],
+        epoch: [u8; EPOCH_SIZE],
+    ) -> Result<Self, CryptoError> {
+        let mut salt = [0u8; 32 + EPOCH_SIZE];
+        salt[..32].copy_from_slice(sender_id);
+        salt[32..].copy_from_slice(&epoch);
+
+        Ok(Self {
+            epoch,
+            index: 0,
+            chain_key: derive_key(
+                session_key,
+                DerivedKeyPurpose::ClipboardChannel,
+                Some(&salt),
+            )?,
+        })
+    }
+
+    /// Chain identifier
+    pub fn epoch(&self) -> [u8; EPOCH_SIZE] {
+        self.epoch
+    }
+
+    /// Index of the next key in the chain
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Take the next key for sending and advance the chain
+    pub fn next_key(&mut self) -> Result<ChannelKey, CryptoError> {
+        let key = ChannelKey {
+            epoch: self.epoch,
+            index: self.index,
+            key: self.chain_key,
+        };
+        self.advance()?;
+        Ok(key)
+    }
+
+    /// Key for a received message at `index`, advancing the chain past it
+    ///
+    /// Keys before the current position have been discarded and can't be
+    /// recovered.
+    pub fn key_at(&mut self, index: u32) -> Result<ChannelKey, CryptoError> {
+        if index < self.index {
+            return Err(CryptoError::Decryption(format!(
+                "ratchet key {} already discarded",
+                index
+            )));
+        }
+        if index - self.index > MAX_RATCHET_SKIP {
+            return Err(CryptoError::Decryption(format!(
+                "ratchet key {} too far ahead of {}",
+                index, self.index
+            )));
+        }
+
+        while self.index < index {
+            self.advance()?;
+        }
+        self.next_key()
+    }
+
+    fn advance(&mut self) -> Result<(), CryptoError> {
+        self.chain_key = derive_key(&self.chain_key, DerivedKeyPurpose::ClipboardRatchet, None)?;
+        self.index = self
+            .index
+            .checked_add(1)
+            .ok_or_else(|| CryptoError::KeyDerivation("ratchet exhausted".to_string()))?;
+        Ok(())
+    }
+}
+
+/// Epoch for a chain starting now, bumped past the last one if the clock hasn't moved
+fn next_epoch() -> [u8; EPOCH_SIZE] {
+    static LAST_EPOCH: AtomicU64 = AtomicU64::new(0);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    let next = |last: u64| now.max(last + 1);
+    let last = LAST_EPOCH
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| Some(next(last)))
+        .expect("epoch update always succeeds");
+    next(last).to_be_bytes()
+}
+
+/// Start time (Unix milliseconds) of the chain with `epoch`
+pub fn epoch_millis(epoch: &[u8; EPOCH_SIZE]) -> u64 {
+    u64::from_be_bytes(*epoch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{decrypt, encrypt};
+
+    #[test]
+    fn test_old_ratchet_key_cannot_decrypt_newer_message() {
+        let session_key = [7u8; KEY_SIZE];
+        let sender_id = [1u8; 32];
+        let mut sender = ChannelRatchet::new(&session_key, &sender_id).unwrap();
+
+        let first = sender.next_key().unwrap();
+        let second = sender.next_key().unwrap();
+        assert_eq!((first.index, second.index), (0, 1));
+        assert_ne!(first.key, second.key);
+
+        let newer = encrypt(&second.key, b"newer clipboard", &sender_id).unwrap();
+        assert!(decrypt(&first.key, &newer, &sender_id).is_err());
+        assert!(decrypt(&session_key, &newer, &sender_id).is_err());
+
+        // The receiver follows the same chain, skipping ahead as needed
+        let mut receiver =
+            ChannelRatchet::with_epoch(&session_key, &sender_id, sender.epoch()).unwrap();
+        let key = receiver.key_at(1).unwrap();
+        assert_eq!(
+            decrypt(&key.key, &newer, &sender_id).unwrap(),
+            b"newer clipboard"
+        );
+
+        // Earlier keys are gone once the chain moved past them
+        assert!(receiver.key_at(0).is_err());
+        assert!(receiver.key_at(1).is_err());
+        assert!(receiver.key_at(2 + MAX_RATCHET_SKIP + 1).is_err());
+    }
+
+    #[test]
+    fn test_chains_differ_per_sender_and_epoch() {
+        let session_key = [7u8; KEY_SIZE];
+        let key = |sender: [u8; 32], epoch: [u8; EPOCH_SIZE]| {
+            ChannelRatchet::with_epoch(&session_key, &sender, epoch)
+                .unwrap()
+                .next_key()
+                .unwrap()
+                .key
+        };
+
+        assert_ne!(key([1u8; 32], [0u8; 8]), key([2u8; 32], [0u8; 8]));
+        assert_ne!(key([1u8; 32], [0u8; 8]), key([1u8; 32], [1u8; 8]));
+        assert_eq!(key([1u8; 32], [0u8; 8]), key([1u8; 32], [0u8; 8]));
+    }
+
+    #[test]
+    fn test_new_chains_have_increasing_epochs() {
+        let session_key = [7u8; KEY_SIZE];
+        let first = ChannelRatchet::new(&session_key, &[1u8; 32]).unwrap();
+        let second = ChannelRatchet::new(&session_key, &[1u8; 32]).unwrap();
+        assert!(epoch_millis(&second.epoch()) > epoch_millis(&first.epoch()));
+    }
+}
@@ -9,6 +9,69 @@ use rand::{rngs::StdRng, RngCore, SeedableRng};
 use super::{KEY_SIZE, NONCE_SIZE, TAG_SIZE};
 use crate::error::CryptoError;
 
+/// Size of the random per-session prefix of a counter nonce
+const NONCE_SALT_SIZE: usize = 8;
+
+/// Number of counter nonces after which the session key should be rotated
+pub const NONCE_ROTATION_THRESHOLD: u64 = 1 << 31;
+
+/// Counter-based nonce sequence for a single session key
+///
+/// Nonces are a random 8-byte per-session salt followed by a 4-byte big-endian
+/// counter, so no nonce repeats until the key rotates. The random salt keeps
+/// both directions of a session sharing one key from colliding.
+#[derive(Debug)]
+pub struct NonceSequence {
+    salt: [u8; NONCE_SALT_SIZE],
+    counter: u64,
+}
+
+impl NonceSequence {
+    /// Start a new sequence with a fresh random salt
+    pub fn new() -> Self {
+        let mut salt = [0u8; NONCE_SALT_SIZE];
+        StdRng::from_entropy().fill_bytes(&mut salt);
+        Self { salt, counter: 0 }
+    }
+
+    /// Get the next nonce, failing once the counter space is exhausted
+    pub fn next_nonce(&mut self) -> Result<[u8; NONCE_SIZE], CryptoError> {
+        let counter = u32::try_from(self.counter)
+            .map_err(|_| CryptoError::Encryption("Nonce space exhausted".to_string()))?;
+        self.counter += 1;
+
+        let mut nonce = [0u8; NONCE_SIZE];
+        nonce[..NONCE_SALT_SIZE].copy_from_slice(&self.salt);
+        nonce[NONCE_SALT_SIZE..].copy_from_slice(&counter.to_be_bytes());
+        Ok(nonce)
+    }
+
+    /// Start a sequence at a given counter value
+    #[cfg(test)]
+    pub(crate) fn with_counter(counter: u64) -> Self {
+        Self {
+            counter,
+            ..Self::new()
+        }
+    }
+
+    /// Number of nonces issued so far
+    pub fn count(&self) -> u64 {
+        self.counter
+    }
+
+    /// Check if the key should be rotated before issuing more nonces
+    pub fn needs_rotation(&self) -> bool {
+        self.counter >= NONCE_ROTATION_THRESHOLD
+    }
+}
+
+impl Default for NonceSequence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Encrypted message with nonce and authentication tag
 #[derive(Debug, Clone)]
 pub struct EncryptedMessage {
@@ -53,13 +116,27 @@ pub fn encrypt(
     plaintext: &[u8],
     aad: &[u8],
 ) -> Result<EncryptedMessage, CryptoError> {
-    let cipher =
-        Aes256Gcm::new_from_slice(key).map_err(|e| CryptoError::Encryption(e.to_string()))?;
-
     // Generate random nonce
     let mut nonce_bytes = [0u8; NONCE_SIZE];
     StdRng::from_entropy().fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    encrypt_with_nonce(key, &nonce_bytes, plaintext, aad)
+}
+
+/// Encrypt plaintext using AES-256-GCM with an explicit nonce
+///
+/// The caller must never reuse a nonce with the same key; use a
+/// [`NonceSequence`] for long-lived session keys.
+pub fn encrypt_with_nonce(
+    key: &[u8; KEY_SIZE],
+    nonce_bytes: &[u8; NONCE_SIZE],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<EncryptedMessage, CryptoError> {
+    let cipher =
+        Aes256Gcm::new_from_slice(key).map_err(|e| CryptoError::Encryption(e.to_string()))?;
+
+    let nonce = Nonce::from_slice(nonce_bytes);
 
     // Encrypt with AAD
     let ciphertext = cipher
@@ -73,7 +150,7 @@ pub fn encrypt(
         .map_err(|e| CryptoError::Encryption(e.to_string()))?;
 
     Ok(EncryptedMessage {
-        nonce: nonce_bytes,
+        nonce: *nonce_bytes,
         ciphertext,
     })
 }
@@ -191,4 +268,55 @@ mod tests {
         assert_ne!(encrypted1.nonce, encrypted2.nonce);
         assert_ne!(encrypted1.ciphertext, encrypted2.ciphertext);
     }
+
+    #[test]
+    fn test_encrypt_with_nonce_roundtrip() {
+        let key = test_key();
+        let mut nonces = NonceSequence::new();
+        let nonce = nonces.next_nonce().unwrap();
+
+        let encrypted = encrypt_with_nonce(&key, &nonce, b"counter nonce", b"aad").unwrap();
+        assert_eq!(encrypted.nonce, nonce);
+
+        let restored = EncryptedMessage::from_bytes(&encrypted.to_bytes()).unwrap();
+        assert_eq!(decrypt(&key, &restored, b"aad").unwrap(), b"counter nonce");
+    }
+
+    #[test]
+    fn test_nonce_sequence_unique() {
+        let key = test_key();
+        let mut sequence = NonceSequence::new();
+        let mut seen = std::collections::HashSet::new();
+
+        for i in 0..100_000u32 {
+            let nonce = sequence.next_nonce().unwrap();
+            assert!(seen.insert(nonce), "nonce reused at message {}", i);
+        }
+        assert_eq!(sequence.count(), 100_000);
+
+        // Nonces from the sequence work for real encryptions too
+        for _ in 0..1_000 {
+            let nonce = sequence.next_nonce().unwrap();
+            let encrypted = encrypt_with_nonce(&key, &nonce, b"frame", b"").unwrap();
+            assert!(seen.insert(encrypted.nonce));
+        }
+
+        // Separate sessions use different salts
+        let mut other = NonceSequence::new();
+        assert!(!seen.contains(&other.next_nonce().unwrap()));
+    }
+
+    #[test]
+    fn test_nonce_sequence_rotation_threshold() {
+        let mut sequence = NonceSequence::with_counter(NONCE_ROTATION_THRESHOLD - 1);
+        assert!(!sequence.needs_rotation());
+
+        sequence.next_nonce().unwrap();
+        assert!(sequence.needs_rotation());
+
+        // The counter never wraps around
+        sequence.counter = u32::MAX as u64;
+        assert!(sequence.next_nonce().is_ok());
+        assert!(sequence.next_nonce().is_err());
+    }
 }
@@ -57,6 +57,9 @@ pub enum CryptoError {
     #[error("Session expired")]
     SessionExpired,
 
+    #[error("Unsupported key algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+
     #[error("Storage error: {0}")]
     Storage(String),
 }
@@ -93,6 +96,15 @@ pub enum NetworkError {
 
     #[error("TLS error: {0}")]
     Tls(String),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    #[error("Security downgrade: {0}")]
+    Downgrade(String),
 }
 
 /// Protocol/message errors
@@ -118,6 +130,9 @@ pub enum ProtocolError {
 
     #[error("Invalid content type")]
     InvalidContentType,
+
+    #[error("Transfer rejected: {0}")]
+    TransferRejected(String),
 }
 
 /// Clipboard operation errors
@@ -6,15 +6,21 @@
 //! - Clipboard operations
 //! - P2P networking with mDNS discovery
 //! - Relay server client
+//! - Shared scratchpad document
 
 pub mod api;
+pub mod cancel;
 pub mod clipboard;
+pub mod clock;
 pub mod crypto;
 pub mod error;
+pub mod log_buffer;
+pub mod log_file;
 pub mod network;
 pub mod pairing;
 pub mod panic_handler;
 pub mod protocol;
+pub mod scratchpad;
 pub mod storage;
 
 pub use error::{CryptoError, NetworkError, ProtocolError, TossError};
@@ -0,0 +1,172 @@
+//! In-memory log buffer
+//!
+//! A tracing layer that keeps the most recent log lines in a ring buffer, so
+//! the app can show or share logs without locating the log file.
+
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::sync::Arc;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Default number of log lines kept in memory
+pub const DEFAULT_LOG_CAPACITY: usize = 1000;
+
+/// Ring buffer of recent log lines
+///
+/// Clones share the same buffer, so one can be handed to the tracing layer
+/// and another kept for reading.
+#[derive(Clone)]
+pub struct LogBuffer {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    /// Create a buffer holding at most `capacity` lines
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Tracing layer that appends formatted events to this buffer
+    pub fn layer(&self) -> LogBufferLayer {
+        LogBufferLayer {
+            buffer: self.clone(),
+        }
+    }
+
+    /// The most recent `limit` lines, oldest first
+    pub fn recent(&self, limit: usize) -> Vec<String> {
+        let lines = self.lines.lock();
+        let skip = lines.len().saturating_sub(limit);
+        lines.iter().skip(skip).cloned().collect()
+    }
+
+    /// Drop all buffered lines
+    pub fn clear(&self) {
+        self.lines.lock().clear();
+    }
+
+    fn push(&self, line: String) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut lines = self.lines.lock();
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+}
+
+/// Tracing layer writing into a [`LogBuffer`]
+pub struct LogBufferLayer {
+    buffer: LogBuffer,
+}
+
+impl<S: Subscriber> Layer<S> for LogBufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut visitor = LineVisitor::default();
+        event.record(&mut visitor);
+
+        self.buffer.push(format!(
+            "{} {:>5} {}: {}{}",
+            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+            metadata.level(),
+            metadata.target(),
+            visitor.message,
+            visitor.fields
+        ));
+    }
+}
+
+/// Collects the message and any extra fields of an event
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for LineVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// Build a log filter from a level (e.g. `"info"`) or full filter directives
+///
+/// A bare level applies to this crate only, matching the default
+/// `toss_core=debug` filter.
+pub fn parse_log_filter(level: &str) -> Result<EnvFilter, String> {
+    let level = level.trim();
+    if level.parse::<tracing::Level>().is_ok() {
+        return Ok(EnvFilter::new(format!(
+            "toss_core={}",
+            level.to_ascii_lowercase()
+        )));
+    }
+
+    EnvFilter::try_new(level).map_err(|e| format!("Invalid log level '{}': {}", level, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::reload;
+
+    #[test]
+    fn test_buffer_keeps_most_recent_lines() {
+        let buffer = LogBuffer::new(3);
+        for i in 0..5 {
+            buffer.push(format!("line {}", i));
+        }
+
+        assert_eq!(buffer.recent(10), vec!["line 2", "line 3", "line 4"]);
+        assert_eq!(buffer.recent(1), vec!["line 4"]);
+
+        buffer.clear();
+        assert!(buffer.recent(10).is_empty());
+    }
+
+    #[test]
+    fn test_log_level_filters_buffered_records() {
+        let buffer = LogBuffer::new(10);
+        let (filter, handle) = reload::Layer::new(parse_log_filter("debug").unwrap());
+        let subscriber = tracing_subscriber::registry()
+            .with(filter)
+            .with(buffer.layer());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!("first debug");
+            handle.reload(parse_log_filter("WARN").unwrap()).unwrap();
+            tracing::debug!("dropped debug");
+            tracing::info!("dropped info");
+            tracing::warn!(peer = 7, "kept warning");
+        });
+
+        let lines = buffer.recent(10);
+        assert_eq!(lines.len(), 2, "unexpected lines: {:?}", lines);
+        assert!(lines[0].contains("DEBUG") && lines[0].ends_with("first debug"));
+        assert!(lines[1].contains(" WARN ") && lines[1].ends_with("kept warning peer=7"));
+    }
+
+    #[test]
+    fn test_parse_log_filter() {
+        assert!(parse_log_filter("trace").is_ok());
+        assert!(parse_log_filter("toss_core=info,quinn=warn").is_ok());
+        assert!(parse_log_filter("toss_core=loud").is_err());
+    }
+}
@@ -0,0 +1,100 @@
+//! Log file writer that can be flushed on demand
+//!
+//! `tracing_appender::non_blocking` hands records to a worker thread that
+//! writes them out in the background, and only drains its queue when its
+//! [`WorkerGuard`] is dropped. [`LogFile`] wraps it so the queue can be
+//! drained while logging stays active: flushing swaps in a fresh writer and
+//! drops the old guard, which blocks until every record it accepted is on
+//! disk.
+
+use parking_lot::Mutex;
+use std::io::Write;
+use std::sync::Arc;
+use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
+use tracing_subscriber::fmt::MakeWriter;
+
+type OpenWriter = dyn Fn() -> (NonBlocking, WorkerGuard) + Send + Sync;
+
+/// Non-blocking log file writer
+///
+/// Clones share the same writer, so one can be handed to the tracing layer
+/// and another kept for flushing.
+#[derive(Clone)]
+pub struct LogFile {
+    current: Arc<Mutex<(NonBlocking, WorkerGuard)>>,
+    open: Arc<OpenWriter>,
+}
+
+impl LogFile {
+    /// Write through `open`, which is called again on every flush
+    ///
+    /// `open` should append to the same file each time.
+    pub fn new<W, F>(open: F) -> Self
+    where
+        W: Write + Send + 'static,
+        F: Fn() -> W + Send + Sync + 'static,
+    {
+        let open: Arc<OpenWriter> = Arc::new(move || tracing_appender::non_blocking(open()));
+        Self {
+            current: Arc::new(Mutex::new(open())),
+            open,
+        }
+    }
+
+    /// Block until every record written so far is on disk
+    pub fn flush(&self) {
+        let fresh = (self.open)();
+        let (_, old_guard) = std::mem::replace(&mut *self.current.lock(), fresh);
+        // Dropping the guard drains the old worker's queue
+        drop(old_guard);
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogFile {
+    type Writer = NonBlocking;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.current.lock().0.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_flush_writes_records_to_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("toss.log");
+        let log_path = path.clone();
+        let log_file = LogFile::new(move || {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&log_path)
+                .unwrap()
+        });
+
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(log_file.clone())
+                .with_ansi(false),
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("before flush");
+            log_file.flush();
+            let contents = std::fs::read_to_string(&path).unwrap();
+            assert!(contents.contains("before flush"));
+
+            // Logging carries on into the same file
+            tracing::info!("after flush");
+            log_file.flush();
+        });
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("before flush"));
+        assert!(contents.contains("after flush"));
+    }
+}
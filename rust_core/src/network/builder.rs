@@ -0,0 +1,214 @@
+//! Builder for [`NetworkManager`]
+
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+
+use super::{
+    CircuitBreaker, ContentSources, GetPublicKeyFn, GetSessionKeyFn, KeyChecks, KeyRotator,
+    LatencyTracker, LockStates, MeteredTraffic, NetworkConfig, NetworkManager, SessionSecurity,
+    TransferTracker, INCOMING_QUEUE_CAPACITY,
+};
+use crate::clipboard::OutgoingSequences;
+use crate::clock::{Clock, SystemClock};
+use crate::crypto::DeviceIdentity;
+use crate::error::NetworkError;
+use crate::scratchpad::Scratchpad;
+
+/// Builds a [`NetworkManager`] from named parts
+///
+/// Only the identity is required; the config defaults to
+/// [`NetworkConfig::default`], the clock to the system clock, the scratchpad
+/// to an empty one, and both key lookups are optional.
+#[derive(Default)]
+pub struct NetworkManagerBuilder {
+    identity: Option<Arc<DeviceIdentity>>,
+    config: NetworkConfig,
+    get_public_key: Option<Arc<GetPublicKeyFn>>,
+    get_session_key: Option<Arc<GetSessionKeyFn>>,
+    clock: Option<Arc<dyn Clock>>,
+    scratchpad: Option<Arc<Scratchpad>>,
+}
+
+impl NetworkManagerBuilder {
+    /// Create a builder with the default config and no key lookups
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the local device identity
+    pub fn identity(mut self, identity: Arc<DeviceIdentity>) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    /// Set the network configuration
+    pub fn config(mut self, config: NetworkConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Set the lookup for a paired device's public key by device ID
+    pub fn public_key_fn(
+        mut self,
+        get_public_key: impl Fn(&[u8; 32]) -> Option<[u8; 32]> + Send + Sync + 'static,
+    ) -> Self {
+        self.get_public_key = Some(Arc::new(Box::new(get_public_key)));
+        self
+    }
+
+    /// Set the lookup for a paired device's session key by device ID
+    pub fn session_key_fn(
+        mut self,
+        get_session_key: impl Fn(&[u8; 32]) -> Option<[u8; 32]> + Send + Sync + 'static,
+    ) -> Self {
+        self.get_session_key = Some(Arc::new(Box::new(get_session_key)));
+        self
+    }
+
+    /// Set the clock used to age session keys
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Set the shared scratchpad document edits from peers are applied to
+    pub fn scratchpad(mut self, scratchpad: Arc<Scratchpad>) -> Self {
+        self.scratchpad = Some(scratchpad);
+        self
+    }
+
+    /// Set an already shared public key lookup
+    pub(super) fn shared_public_key_fn(mut self, f: Option<Arc<GetPublicKeyFn>>) -> Self {
+        self.get_public_key = f;
+        self
+    }
+
+    /// Set an already shared session key lookup
+    pub(super) fn shared_session_key_fn(mut self, f: Option<Arc<GetSessionKeyFn>>) -> Self {
+        self.get_session_key = f;
+        self
+    }
+
+    /// Build the network manager; call `start` on it to bring networking up
+    pub async fn build(self) -> Result<NetworkManager, NetworkError> {
+        let identity = self.identity.ok_or_else(|| {
+            NetworkError::InvalidConfig("NetworkManager requires an identity".to_string())
+        })?;
+        let config = self.config;
+        super::discovery::validate_service_name(&config.service_name)?;
+
+        let (event_tx, _) = broadcast::channel(config.event_channel_capacity.max(1));
+        let (incoming_tx, incoming_rx) = mpsc::channel(INCOMING_QUEUE_CAPACITY);
+        let breaker =
+            CircuitBreaker::new(config.breaker_failure_threshold, config.breaker_cooldown);
+        let transfers = TransferTracker::with_limits(config.transfer_limits());
+        let peers = Arc::new(RwLock::new(HashMap::new()));
+        let ephemeral_keys = Arc::new(RwLock::new(HashMap::new()));
+        let key_rotator = KeyRotator {
+            identity: identity.clone(),
+            peers: peers.clone(),
+            ephemeral_keys: ephemeral_keys.clone(),
+            get_public_key: self.get_public_key.clone(),
+        };
+        let security = Arc::new(SessionSecurity::new(config.min_security_level));
+        let lan_only = Arc::new(AtomicBool::new(config.lan_only));
+        let lock_states = Arc::new(LockStates::new(config.require_peer_unlocked));
+        let scratchpad = self
+            .scratchpad
+            .unwrap_or_else(|| Arc::new(Scratchpad::new(*identity.device_id())));
+
+        Ok(NetworkManager {
+            config,
+            identity,
+            discovery: None,
+            transport: None,
+            relay_client: None,
+            relay_task: None,
+            transfer_reaper_task: None,
+            key_rotation_task: None,
+            clock_sync_task: None,
+            nat_candidates: Vec::new(),
+            last_network_change: None,
+            peers,
+            ephemeral_keys,
+            latency: Arc::new(LatencyTracker::new()),
+            content_sources: Arc::new(ContentSources::new()),
+            transfers: Arc::new(transfers),
+            breaker: Arc::new(breaker),
+            security,
+            key_rotator,
+            key_checks: KeyChecks::default(),
+            metered: Arc::new(MeteredTraffic::default()),
+            lock_states,
+            scratchpad,
+            clipboard_sequences: OutgoingSequences::default(),
+            clock: self.clock.unwrap_or_else(|| Arc::new(SystemClock)),
+            event_tx,
+            incoming_tx,
+            incoming_rx: Mutex::new(Some(incoming_rx)),
+            readers: Mutex::new(HashMap::new()),
+            get_public_key: self.get_public_key,
+            get_session_key: self.get_session_key,
+            lan_only,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_builder_matches_positional_constructor() {
+        let identity = Arc::new(DeviceIdentity::generate().unwrap());
+        let config = NetworkConfig {
+            device_name: "Builder Test".to_string(),
+            event_channel_capacity: 7,
+            ..Default::default()
+        };
+        let peer = [9u8; 32];
+
+        let built = NetworkManagerBuilder::new()
+            .identity(identity.clone())
+            .config(config.clone())
+            .public_key_fn(|_| Some([1u8; 32]))
+            .session_key_fn(|_| Some([2u8; 32]))
+            .build()
+            .await
+            .unwrap();
+
+        let public_key: Arc<GetPublicKeyFn> = Arc::new(Box::new(|_: &[u8; 32]| Some([1u8; 32])));
+        let session_key: Arc<GetSessionKeyFn> = Arc::new(Box::new(|_: &[u8; 32]| Some([2u8; 32])));
+        let positional = NetworkManager::new_with_callbacks(
+            identity,
+            config,
+            Some(public_key),
+            Some(session_key),
+        )
+        .await
+        .unwrap();
+
+        for manager in [&built, &positional] {
+            assert_eq!(manager.config.device_name, "Builder Test");
+            assert_eq!(manager.identity.device_id(), built.identity.device_id());
+            assert_eq!(
+                manager.get_public_key.as_ref().unwrap()(&peer),
+                Some([1u8; 32])
+            );
+            assert_eq!(
+                manager.get_session_key.as_ref().unwrap()(&peer),
+                Some([2u8; 32])
+            );
+            assert!(manager.connected_peers().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_builder_requires_identity() {
+        let result = NetworkManagerBuilder::new().build().await;
+        assert!(matches!(result, Err(NetworkError::InvalidConfig(_))));
+    }
+}
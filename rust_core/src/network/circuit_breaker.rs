@@ -0,0 +1,154 @@
+//! Per-peer circuit breaker for broadcasts
+//!
+//! After `failure_threshold` consecutive failed sends the circuit to a peer
+//! opens and direct sends to it are skipped for the cooldown. Once the
+//! cooldown has passed the circuit is half-open: a single trial send is let
+//! through, which closes the circuit on success or reopens it on failure.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Default number of consecutive failures that open a peer's circuit
+pub const DEFAULT_BREAKER_FAILURE_THRESHOLD: u32 = 3;
+
+/// Default time a peer's circuit stays open before a trial send
+pub const DEFAULT_BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Circuit state for one peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Circuit {
+    /// Sends go through; counts consecutive failures
+    Closed { failures: u32 },
+    /// Sends are skipped until the cooldown ends
+    Open { until: Instant },
+    /// One trial send is in flight
+    HalfOpen,
+}
+
+/// Tracks send failures per peer and decides which peers to skip
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    circuits: Mutex<HashMap<[u8; 32], Circuit>>,
+}
+
+impl CircuitBreaker {
+    /// Create a breaker that opens after `failure_threshold` consecutive failures
+    ///
+    /// A threshold of 0 disables the breaker.
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown,
+            circuits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Check whether a send to a peer should be attempted
+    ///
+    /// Moves an open circuit whose cooldown has passed to half-open and lets
+    /// exactly that one trial through.
+    pub fn allow(&self, device_id: &[u8; 32]) -> bool {
+        let mut circuits = self.circuits.lock();
+        match circuits.get(device_id).copied() {
+            None | Some(Circuit::Closed { .. }) => true,
+            Some(Circuit::Open { until }) if Instant::now() >= until => {
+                circuits.insert(*device_id, Circuit::HalfOpen);
+                true
+            }
+            Some(Circuit::Open { .. }) | Some(Circuit::HalfOpen) => false,
+        }
+    }
+
+    /// Record a successful send, closing the peer's circuit
+    pub fn record_success(&self, device_id: &[u8; 32]) {
+        self.circuits.lock().remove(device_id);
+    }
+
+    /// Record a failed send, opening the circuit at the threshold
+    pub fn record_failure(&self, device_id: &[u8; 32]) {
+        if self.failure_threshold == 0 {
+            return;
+        }
+
+        let mut circuits = self.circuits.lock();
+        let failures = match circuits.get(device_id) {
+            Some(Circuit::Closed { failures }) => failures + 1,
+            // A failed trial reopens the circuit straight away
+            Some(Circuit::HalfOpen) => self.failure_threshold,
+            Some(Circuit::Open { .. }) => return,
+            None => 1,
+        };
+
+        let circuit = if failures >= self.failure_threshold {
+            tracing::debug!(
+                "Opening circuit to {} for {:?} after {} failures",
+                hex::encode(device_id),
+                self.cooldown,
+                failures
+            );
+            Circuit::Open {
+                until: Instant::now() + self.cooldown,
+            }
+        } else {
+            Circuit::Closed { failures }
+        };
+        circuits.insert(*device_id, circuit);
+    }
+
+    /// Forget a peer's failure history
+    pub fn reset(&self, device_id: &[u8; 32]) {
+        self.circuits.lock().remove(device_id);
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(DEFAULT_BREAKER_FAILURE_THRESHOLD, DEFAULT_BREAKER_COOLDOWN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_opens_then_half_opens_after_cooldown() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(50));
+        let peer = [1u8; 32];
+        let other = [2u8; 32];
+
+        breaker.record_failure(&peer);
+        assert!(breaker.allow(&peer));
+        breaker.record_failure(&peer);
+        assert!(!breaker.allow(&peer), "circuit should be open");
+        assert!(breaker.allow(&other), "other peers are unaffected");
+
+        // After the cooldown, exactly one trial goes through
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.allow(&peer));
+        assert!(!breaker.allow(&peer));
+
+        // A failed trial reopens the circuit immediately
+        breaker.record_failure(&peer);
+        assert!(!breaker.allow(&peer));
+
+        // A successful trial closes it
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.allow(&peer));
+        breaker.record_success(&peer);
+        assert!(breaker.allow(&peer));
+        assert!(breaker.allow(&peer));
+    }
+
+    #[test]
+    fn test_zero_threshold_disables_breaker() {
+        let breaker = CircuitBreaker::new(0, Duration::from_secs(60));
+        let peer = [1u8; 32];
+        for _ in 0..10 {
+            breaker.record_failure(&peer);
+        }
+        assert!(breaker.allow(&peer));
+    }
+}
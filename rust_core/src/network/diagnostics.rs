@@ -0,0 +1,91 @@
+//! Snapshot of the network state for troubleshooting
+//!
+//! Gathers what the manager, mDNS discovery, the relay client and NAT
+//! discovery know into one value, e.g. for a support bundle when two devices
+//! don't see each other.
+
+use std::net::SocketAddr;
+
+use super::discovery::DiscoveredPeer;
+use super::nat_traversal::{IceCandidate, NatType};
+
+/// Local mDNS advertisement state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MdnsStatus {
+    /// Turned off in the config
+    Disabled,
+    /// Our service is advertised
+    Registered,
+    /// Enabled, but the service isn't advertised (not started, or
+    /// registration failed)
+    NotRegistered,
+}
+
+impl MdnsStatus {
+    /// Stable lowercase name used in API DTOs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MdnsStatus::Disabled => "disabled",
+            MdnsStatus::Registered => "registered",
+            MdnsStatus::NotRegistered => "not_registered",
+        }
+    }
+}
+
+/// Relay connection state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayStatus {
+    /// No relay URL configured
+    NotConfigured,
+    /// Configured, but unused in LAN-only mode
+    LanOnly,
+    /// Connected over WebSocket
+    Connected,
+    /// Connected through the HTTP long-poll fallback
+    LongPoll,
+    /// Configured but not connected
+    Disconnected,
+}
+
+impl RelayStatus {
+    /// Stable lowercase name used in API DTOs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelayStatus::NotConfigured => "not_configured",
+            RelayStatus::LanOnly => "lan_only",
+            RelayStatus::Connected => "connected",
+            RelayStatus::LongPoll => "long_poll",
+            RelayStatus::Disconnected => "disconnected",
+        }
+    }
+}
+
+/// A connected peer as seen by the manager
+#[derive(Debug, Clone)]
+pub struct ConnectedPeerDiagnostics {
+    pub device_id: [u8; 32],
+    pub device_name: String,
+    pub addresses: Vec<SocketAddr>,
+    /// Connection transport, currently always "quic"
+    pub transport: &'static str,
+    /// Whether the peer is connected over the local network
+    pub is_local: bool,
+    /// Round-trip time estimated by the connection
+    pub rtt_ms: u64,
+}
+
+/// Everything the network manager knows about the network, at one moment
+#[derive(Debug, Clone)]
+pub struct NetworkDiagnostics {
+    /// Address the QUIC endpoint listens on, if started
+    pub local_addr: Option<SocketAddr>,
+    pub mdns: MdnsStatus,
+    /// Peers that answered an mDNS browse, our own service aside
+    pub discovered_peers: Vec<DiscoveredPeer>,
+    pub connected_peers: Vec<ConnectedPeerDiagnostics>,
+    pub relay: RelayStatus,
+    pub relay_url: Option<String>,
+    pub nat_type: NatType,
+    /// Candidates from the last NAT discovery
+    pub candidates: Vec<IceCandidate>,
+}
@@ -1,16 +1,121 @@
 //! mDNS-SD device discovery
 
-use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
-use std::net::SocketAddr;
+use mdns_sd::{ResolvedService, ScopedIp, ServiceDaemon, ServiceEvent, ServiceInfo};
+use parking_lot::Mutex;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
 
 use crate::error::NetworkError;
+use crate::protocol::ContentType;
 
-/// Service type for Toss discovery
-const SERVICE_TYPE: &str = "_toss._udp.local.";
+/// Default mDNS service name (gives `_toss._udp.local.`)
+pub const DEFAULT_SERVICE_NAME: &str = "toss";
+
+/// Default mDNS service domain
+pub const DEFAULT_SERVICE_DOMAIN: &str = "local.";
+
+/// Longest service name RFC 6335 allows
+const MAX_SERVICE_NAME_LEN: usize = 15;
+
+/// Appended to the service name for pairing advertisements
+const PAIRING_SERVICE_SUFFIX: &str = "-pair";
 
 /// Protocol version for discovery
 const DISCOVERY_VERSION: &str = "1";
 
+/// Number of hex characters of the device id advertised in the TXT record
+pub const ADVERTISED_ID_LEN: usize = 16;
+
+/// How long one wait for a browse event may block
+const BROWSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// TXT key for the latest content type
+const TXT_CONTENT_TYPE: &str = "ct";
+
+/// TXT key for the latest content digest
+const TXT_CONTENT_HASH: &str = "ch";
+
+/// Number of digest bytes advertised in the content hint
+const CONTENT_HINT_HASH_LEN: usize = 8;
+
+/// Maximum advertised device name length in bytes
+const MAX_TXT_NAME_LEN: usize = 63;
+
+/// Maximum total TXT record size (RFC 6763 recommends staying under 400 bytes)
+pub const MAX_TXT_RECORD_SIZE: usize = 400;
+
+/// Retry policy for mDNS service registration
+///
+/// The mDNS daemon often fails transiently right after a network change, so
+/// registrations are retried with exponential backoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegistrationRetry {
+    /// Total attempts, including the first
+    pub attempts: u32,
+    /// Delay before the first retry; doubles after each failure
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between attempts
+    pub max_backoff: Duration,
+}
+
+impl Default for RegistrationRetry {
+    fn default() -> Self {
+        Self {
+            attempts: 4,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RegistrationRetry {
+    /// Run `register` until it succeeds or the attempts are used up
+    ///
+    /// Returns the last error if every attempt failed.
+    pub async fn run<T>(
+        &self,
+        what: &str,
+        mut register: impl FnMut() -> Result<T, NetworkError>,
+    ) -> Result<T, NetworkError> {
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            match register() {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < self.attempts => {
+                    tracing::warn!(
+                        "{} failed (attempt {}/{}), retrying in {:?}: {}",
+                        what,
+                        attempt,
+                        self.attempts,
+                        backoff,
+                        e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(self.max_backoff);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Hint about the latest copied content, advertised via mDNS
+///
+/// Only carries the content type and a salted, truncated digest so peers can
+/// tell when something new was copied. The content itself is never advertised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentHint {
+    /// Type of the latest content
+    pub content_type: ContentType,
+    /// Hex-encoded salted digest of the latest content
+    pub hash: String,
+}
+
 /// Discovered peer information
 #[derive(Debug, Clone)]
 pub struct DiscoveredPeer {
@@ -22,44 +127,123 @@ pub struct DiscoveredPeer {
     pub addresses: Vec<SocketAddr>,
     /// Protocol version
     pub version: String,
+    /// Latest content hint, if the peer advertises one
+    pub content_hint: Option<ContentHint>,
+}
+
+/// Build the discovery service type for a service name and domain
+pub fn service_type(service_name: &str, domain: &str) -> String {
+    format!("_{}._udp.{}", service_name, domain)
+}
+
+/// Build the pairing service type for a service name and domain
+pub fn pairing_service_type(service_name: &str, domain: &str) -> String {
+    format!(
+        "_{}{}._udp.{}",
+        service_name, PAIRING_SERVICE_SUFFIX, domain
+    )
+}
+
+/// Check a service name against RFC 6335 (section 5.1)
+///
+/// Letters, digits and hyphens, with at least one letter and no hyphen at
+/// either end or next to another. The pairing name must fit the 15
+/// character limit too, so the name itself gets 10.
+pub fn validate_service_name(service_name: &str) -> Result<(), NetworkError> {
+    let invalid = |reason: &str| {
+        Err(NetworkError::InvalidConfig(format!(
+            "service name {:?} {}",
+            service_name, reason
+        )))
+    };
+    let max_len = MAX_SERVICE_NAME_LEN - PAIRING_SERVICE_SUFFIX.len();
+    if service_name.is_empty() || service_name.len() > max_len {
+        return invalid(&format!("must be 1 to {} characters", max_len));
+    }
+    if !service_name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return invalid("may only contain letters, digits and hyphens");
+    }
+    if !service_name.chars().any(|c| c.is_ascii_alphabetic()) {
+        return invalid("must contain a letter");
+    }
+    if service_name.starts_with('-') || service_name.ends_with('-') || service_name.contains("--") {
+        return invalid("can't start or end with a hyphen or have two in a row");
+    }
+    Ok(())
 }
 
 /// mDNS-SD discovery service
 pub struct MdnsDiscovery {
     daemon: ServiceDaemon,
+    service_type: String,
     device_id: String,
     device_name: String,
     port: u16,
-    service_fullname: Option<String>,
+    service_fullname: Mutex<Option<String>>,
+    /// TXT properties of the current registration, to skip duplicate registers
+    registered_properties: Mutex<Option<Vec<(&'static str, String)>>>,
+    content_hint: Mutex<Option<ContentHint>>,
+    hint_salt: [u8; 16],
 }
 
 impl MdnsDiscovery {
-    /// Create a new discovery service
+    /// Create a new discovery service using the default service type
     pub fn new(device_id: &str, device_name: &str, port: u16) -> Result<Self, NetworkError> {
+        Self::new_with_service_type(
+            device_id,
+            device_name,
+            port,
+            &service_type(DEFAULT_SERVICE_NAME, DEFAULT_SERVICE_DOMAIN),
+        )
+    }
+
+    /// Create a new discovery service advertising and browsing `service_type`
+    ///
+    /// Devices only discover peers registered under the same service type.
+    pub fn new_with_service_type(
+        device_id: &str,
+        device_name: &str,
+        port: u16,
+        service_type: &str,
+    ) -> Result<Self, NetworkError> {
         let daemon = ServiceDaemon::new().map_err(|e| NetworkError::Discovery(e.to_string()))?;
 
+        // Random per-session salt so advertised digests can't be matched against
+        // known content by observers on the network
+        let mut hint_salt = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut hint_salt);
+
         Ok(Self {
             daemon,
+            service_type: service_type.to_string(),
             device_id: device_id.to_string(),
             device_name: device_name.to_string(),
             port,
-            service_fullname: None,
+            service_fullname: Mutex::new(None),
+            registered_properties: Mutex::new(None),
+            content_hint: Mutex::new(None),
+            hint_salt,
         })
     }
 
     /// Register this device on the network
+    ///
+    /// Does nothing if the service is already registered with the same TXT
+    /// properties.
     pub fn register(&self) -> Result<(), NetworkError> {
         let host_name = format!("toss-{}.local.", &self.device_id[..8]);
 
         // Create TXT record properties
-        let properties = [
-            ("v", DISCOVERY_VERSION),
-            ("id", &self.device_id[..16]), // Truncated ID
-            ("name", &self.device_name),
-        ];
+        let properties = self.txt_properties();
+        if self.is_registered() && self.registered_properties.lock().as_ref() == Some(&properties) {
+            return Ok(());
+        }
 
         let service_info = ServiceInfo::new(
-            SERVICE_TYPE,
+            &self.service_type,
             &self.device_name,
             &host_name,
             "",
@@ -68,29 +252,178 @@ impl MdnsDiscovery {
         )
         .map_err(|e| NetworkError::Discovery(format!("Failed to create service info: {}", e)))?;
 
+        let fullname = service_info.get_fullname().to_string();
+
         self.daemon
             .register(service_info)
             .map_err(|e| NetworkError::Discovery(format!("Failed to register service: {}", e)))?;
 
+        *self.service_fullname.lock() = Some(fullname);
+        *self.registered_properties.lock() = Some(properties);
+
         Ok(())
     }
 
+    /// Register this device, retrying transient failures
+    pub async fn register_with_retry(&self, retry: &RegistrationRetry) -> Result<(), NetworkError> {
+        retry.run("mDNS registration", || self.register()).await
+    }
+
+    /// Check whether the service is currently registered
+    pub fn is_registered(&self) -> bool {
+        self.service_fullname.lock().is_some()
+    }
+
     /// Unregister this device
     pub fn unregister(&self) {
-        if let Some(ref fullname) = self.service_fullname {
-            let _ = self.daemon.unregister(fullname);
+        if let Some(fullname) = self.service_fullname.lock().take() {
+            let _ = self.daemon.unregister(&fullname);
+        }
+        *self.registered_properties.lock() = None;
+    }
+
+    /// Advertise a hint about the latest copied content
+    ///
+    /// Re-registers the service if it is already registered so peers see the
+    /// updated TXT record.
+    pub fn set_content_hint(
+        &self,
+        content_type: ContentType,
+        content_hash: &[u8; 32],
+    ) -> Result<(), NetworkError> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.hint_salt);
+        hasher.update(content_hash);
+        let digest = hasher.finalize();
+
+        let hint = ContentHint {
+            content_type,
+            hash: hex::encode(&digest[..CONTENT_HINT_HASH_LEN]),
+        };
+
+        {
+            let mut current = self.content_hint.lock();
+            if current.as_ref() == Some(&hint) {
+                return Ok(());
+            }
+            *current = Some(hint);
+        }
+
+        self.refresh_registration()
+    }
+
+    /// Stop advertising the content hint
+    pub fn clear_content_hint(&self) -> Result<(), NetworkError> {
+        if self.content_hint.lock().take().is_none() {
+            return Ok(());
+        }
+
+        self.refresh_registration()
+    }
+
+    /// Get the port advertised for this device
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Get the mDNS service type this instance advertises and browses
+    pub fn service_type(&self) -> &str {
+        &self.service_type
+    }
+
+    /// Get the currently advertised content hint
+    pub fn content_hint(&self) -> Option<ContentHint> {
+        self.content_hint.lock().clone()
+    }
+
+    /// Re-register with current TXT properties if registered
+    fn refresh_registration(&self) -> Result<(), NetworkError> {
+        if self.is_registered() {
+            self.register()?;
+        }
+        Ok(())
+    }
+
+    /// Build TXT record properties, bounded to `MAX_TXT_RECORD_SIZE`
+    fn txt_properties(&self) -> Vec<(&'static str, String)> {
+        let mut properties = vec![
+            ("v", DISCOVERY_VERSION.to_string()),
+            ("id", self.device_id[..ADVERTISED_ID_LEN].to_string()), // Truncated ID
+            (
+                "name",
+                truncate_utf8(&self.device_name, MAX_TXT_NAME_LEN).to_string(),
+            ),
+        ];
+
+        if let Some(hint) = self.content_hint.lock().as_ref() {
+            let hint_properties = [
+                (TXT_CONTENT_TYPE, (hint.content_type as u8).to_string()),
+                (TXT_CONTENT_HASH, hint.hash.clone()),
+            ];
+            let with_hint = txt_record_size(&properties) + txt_record_size(&hint_properties);
+            if with_hint <= MAX_TXT_RECORD_SIZE {
+                properties.extend(hint_properties);
+            }
         }
+
+        properties
     }
 
     /// Start browsing for other devices
     pub fn browse(&self) -> Result<mdns_sd::Receiver<ServiceEvent>, NetworkError> {
         self.daemon
-            .browse(SERVICE_TYPE)
+            .browse(&self.service_type)
             .map_err(|e| NetworkError::Discovery(format!("Failed to browse: {}", e)))
     }
 
+    /// Browse for `window` and collect the advertised ids of the peers seen
+    ///
+    /// Ids are truncated to [`ADVERTISED_ID_LEN`] characters. Our own service
+    /// is left out.
+    pub async fn present_device_ids(
+        &self,
+        window: Duration,
+    ) -> Result<HashSet<String>, NetworkError> {
+        let peers = self.browse_peers(window).await?;
+        Ok(peers.into_iter().map(|peer| peer.device_id).collect())
+    }
+
+    /// Browse for `window` and collect the peers seen, our own service aside
+    ///
+    /// A peer resolved more than once is listed with its latest details.
+    pub async fn browse_peers(
+        &self,
+        window: Duration,
+    ) -> Result<Vec<DiscoveredPeer>, NetworkError> {
+        let receiver = self.browse()?;
+        let deadline = Instant::now() + window;
+        let mut present: HashMap<String, DiscoveredPeer> = HashMap::new();
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            match receiver.recv_timeout(remaining.min(BROWSE_POLL_INTERVAL)) {
+                Ok(ServiceEvent::ServiceResolved(info)) if !self.is_own_service(&info) => {
+                    if let Some(peer) = Self::parse_service(&info) {
+                        present.insert(peer.device_id.clone(), peer);
+                    }
+                }
+                Ok(_) => {}
+                Err(_) if receiver.is_disconnected() => break,
+                Err(_) => tokio::task::yield_now().await,
+            }
+        }
+
+        if let Err(e) = self.daemon.stop_browse(&self.service_type) {
+            tracing::debug!("Failed to stop mDNS browse: {}", e);
+        }
+        Ok(present.into_values().collect())
+    }
+
     /// Parse a discovered service into peer info
-    pub fn parse_service(info: &ServiceInfo) -> Option<DiscoveredPeer> {
+    pub fn parse_service(info: &ResolvedService) -> Option<DiscoveredPeer> {
         let properties = info.get_properties();
 
         let device_id = properties.get("id").map(|v| v.val_str().to_string())?;
@@ -102,12 +435,20 @@ impl MdnsDiscovery {
             .get("v")
             .map(|v| v.val_str().to_string())
             .unwrap_or_else(|| "1".to_string());
+        let content_hint = Self::parse_content_hint(info);
 
         // Get addresses
         let addresses: Vec<SocketAddr> = info
             .get_addresses()
             .iter()
-            .map(|addr| SocketAddr::new(*addr, info.get_port()))
+            .filter_map(|scoped_ip| {
+                let ip = match scoped_ip {
+                    ScopedIp::V4(v4) => IpAddr::V4(*v4.addr()),
+                    ScopedIp::V6(v6) => IpAddr::V6(*v6.addr()),
+                    _ => return None,
+                };
+                Some(SocketAddr::new(ip, info.get_port()))
+            })
             .collect();
 
         if addresses.is_empty() {
@@ -119,19 +460,63 @@ impl MdnsDiscovery {
             device_name,
             addresses,
             version,
+            content_hint,
+        })
+    }
+
+    /// Parse the content hint from a discovered service, if present
+    fn parse_content_hint(info: &ResolvedService) -> Option<ContentHint> {
+        let properties = info.get_properties();
+
+        let content_type = properties
+            .get(TXT_CONTENT_TYPE)
+            .and_then(|v| v.val_str().parse::<u8>().ok())
+            .and_then(|v| ContentType::try_from(v).ok())?;
+        let hash = properties.get(TXT_CONTENT_HASH)?.val_str();
+
+        if hash.len() != CONTENT_HINT_HASH_LEN * 2 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        Some(ContentHint {
+            content_type,
+            hash: hash.to_string(),
         })
     }
 
     /// Check if this is our own service
-    pub fn is_own_service(&self, info: &ServiceInfo) -> bool {
+    pub fn is_own_service(&self, info: &ResolvedService) -> bool {
         if let Some(id) = info.get_properties().get("id") {
-            id.val_str().starts_with(&self.device_id[..16])
+            id.val_str()
+                .starts_with(&self.device_id[..ADVERTISED_ID_LEN])
         } else {
             false
         }
     }
 }
 
+/// Size in bytes of a TXT record with the given properties
+///
+/// Each entry is encoded as a length byte followed by `key=value`.
+fn txt_record_size<K: AsRef<str>, V: AsRef<str>>(properties: &[(K, V)]) -> usize {
+    properties
+        .iter()
+        .map(|(k, v)| 1 + k.as_ref().len() + 1 + v.as_ref().len())
+        .sum()
+}
+
+/// Truncate a string to at most `max_len` bytes on a char boundary
+fn truncate_utf8(s: &str, max_len: usize) -> &str {
+    if s.len() <= max_len {
+        return s;
+    }
+    let mut end = max_len;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
 impl Drop for MdnsDiscovery {
     fn drop(&mut self) {
         self.unregister();
@@ -142,6 +527,40 @@ impl Drop for MdnsDiscovery {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_registration_retries_transient_failure() {
+        let retry = RegistrationRetry {
+            attempts: 3,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(20),
+        };
+
+        // The daemon fails once, then the retry succeeds
+        let mut calls = 0;
+        let result = retry
+            .run("test registration", || {
+                calls += 1;
+                if calls == 1 {
+                    Err(NetworkError::Discovery("daemon not ready".to_string()))
+                } else {
+                    Ok(calls)
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 2);
+
+        // A persistent failure gives up after all attempts
+        let mut calls = 0;
+        let result: Result<(), _> = retry
+            .run("test registration", || {
+                calls += 1;
+                Err(NetworkError::Discovery("daemon down".to_string()))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+    }
+
     #[test]
     fn test_discovery_creation() {
         let result = MdnsDiscovery::new("test-device-id", "Test Device", 12345);
@@ -150,6 +569,108 @@ mod tests {
 
     #[test]
     fn test_service_type() {
-        assert_eq!(SERVICE_TYPE, "_toss._udp.local.");
+        assert_eq!(
+            service_type(DEFAULT_SERVICE_NAME, DEFAULT_SERVICE_DOMAIN),
+            "_toss._udp.local."
+        );
+        assert_eq!(
+            pairing_service_type(DEFAULT_SERVICE_NAME, DEFAULT_SERVICE_DOMAIN),
+            "_toss-pair._udp.local."
+        );
+        assert_eq!(
+            service_type("acme-toss", DEFAULT_SERVICE_DOMAIN),
+            "_acme-toss._udp.local."
+        );
+
+        let discovery = MdnsDiscovery::new("test-device-id", "Test Device", 12345).unwrap();
+        assert_eq!(discovery.service_type(), "_toss._udp.local.");
+    }
+
+    #[test]
+    fn test_validate_service_name() {
+        for name in [DEFAULT_SERVICE_NAME, "acme-toss", "t0ss", "a-b-c"] {
+            assert!(validate_service_name(name).is_ok(), "{}", name);
+        }
+        // "-pair" would push this past 15 characters
+        for name in [
+            "",
+            "mycorp-toss",
+            "toss_dev",
+            "toss.",
+            "tøss",
+            "1234",
+            "-toss",
+            "toss-",
+            "to--ss",
+        ] {
+            assert!(validate_service_name(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_content_hint_txt_fields() {
+        let device_id = "0123456789abcdef0123456789abcdef";
+        let long_name = "Ä".repeat(100);
+        let discovery = MdnsDiscovery::new(device_id, &long_name, 12345).unwrap();
+
+        // No hint advertised by default
+        let properties = discovery.txt_properties();
+        assert!(properties.iter().all(|(k, _)| *k != TXT_CONTENT_TYPE));
+        assert!(properties.iter().all(|(k, _)| *k != TXT_CONTENT_HASH));
+
+        let content = b"super secret clipboard text";
+        let content_hash: [u8; 32] = Sha256::digest(content).into();
+        discovery
+            .set_content_hint(ContentType::Image, &content_hash)
+            .unwrap();
+
+        let properties = discovery.txt_properties();
+        let get = |key: &str| {
+            properties
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.clone())
+        };
+
+        assert_eq!(get(TXT_CONTENT_TYPE).unwrap(), "2");
+        let hash = get(TXT_CONTENT_HASH).unwrap();
+        assert_eq!(hash.len(), CONTENT_HINT_HASH_LEN * 2);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+
+        // Neither the content nor its plain hash is advertised
+        assert!(properties.iter().all(|(_, v)| !v.contains("secret")));
+        assert!(!hex::encode(content_hash).contains(&hash));
+
+        // Name is truncated on a char boundary and the record stays bounded
+        assert!(get("name").unwrap().len() <= MAX_TXT_NAME_LEN);
+        assert!(txt_record_size(&properties) <= MAX_TXT_RECORD_SIZE);
+        for (k, v) in &properties {
+            assert!(k.len() + 1 + v.len() <= 255);
+        }
+
+        discovery.clear_content_hint().unwrap();
+        assert!(discovery.content_hint().is_none());
+        assert!(discovery
+            .txt_properties()
+            .iter()
+            .all(|(k, _)| *k != TXT_CONTENT_HASH));
+    }
+
+    #[test]
+    fn test_content_hint_salted_per_session() {
+        let device_id = "0123456789abcdef0123456789abcdef";
+        let a = MdnsDiscovery::new(device_id, "A", 12345).unwrap();
+        let b = MdnsDiscovery::new(device_id, "B", 12346).unwrap();
+        let content_hash = [7u8; 32];
+
+        a.set_content_hint(ContentType::PlainText, &content_hash)
+            .unwrap();
+        b.set_content_hint(ContentType::PlainText, &content_hash)
+            .unwrap();
+
+        assert_ne!(
+            a.content_hint().unwrap().hash,
+            b.content_hint().unwrap().hash
+        );
     }
 }
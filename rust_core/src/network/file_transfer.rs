@@ -0,0 +1,306 @@
+//! Payloads of resumable transfers, and files streamed into them
+//!
+//! A transfer payload is an encoded message behind its length, followed by
+//! the content data of a clipboard update. The update itself is encoded with
+//! empty data, so neither side has to build or parse an encoding holding the
+//! whole content. A file goes out as such an update: [`FileSource`] reads the
+//! data from disk as chunks are sent, so only the chunk being sent is
+//! resident. The receiver writes chunks to a temporary file (see
+//! [`ReceivedPayload`]) and [`decode_payload`] reads the data from there
+//! straight into the update.
+
+use parking_lot::Mutex;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use super::transfer::{ReceivedPayload, TransferSource};
+use crate::error::ProtocolError;
+use crate::protocol::{encoding, ClipboardContent, ClipboardUpdate, Message};
+
+/// Size of the length in front of a payload's encoded message
+const MESSAGE_LEN_BYTES: usize = 4;
+
+/// Transfer payload carrying `message`
+pub fn encode_payload(message: &Message) -> Result<Vec<u8>, ProtocolError> {
+    let (header, data) = match message {
+        Message::ClipboardUpdate(update) => (
+            encode_header(&without_data(update))?,
+            update.content.data.as_slice(),
+        ),
+        message => (message_header(encoding::encode(message)?)?, &[][..]),
+    };
+    let mut payload = header;
+    payload.extend_from_slice(data);
+    Ok(payload)
+}
+
+/// Message carried by a completed transfer payload
+///
+/// A clipboard update's content data is read from the payload file into the
+/// update, so the payload is never in memory twice.
+pub fn decode_payload(payload: ReceivedPayload) -> Result<Message, ProtocolError> {
+    let read_error =
+        |e: io::Error| ProtocolError::InvalidFormat(format!("can't read payload: {}", e));
+    let len = payload.len();
+    let mut file = payload.into_file().map_err(read_error)?;
+
+    let mut message_len = [0u8; MESSAGE_LEN_BYTES];
+    file.read_exact(&mut message_len).map_err(read_error)?;
+    let message_len = u32::from_le_bytes(message_len) as u64;
+    let data_len = len
+        .checked_sub(MESSAGE_LEN_BYTES as u64 + message_len)
+        .ok_or_else(|| ProtocolError::InvalidFormat("message longer than payload".to_string()))?;
+
+    let mut header = vec![0u8; message_len as usize];
+    file.read_exact(&mut header).map_err(read_error)?;
+    let message = Message::decode(&header)?;
+    if data_len == 0 {
+        return Ok(message);
+    }
+
+    let Message::ClipboardUpdate(mut update) = message else {
+        return Err(ProtocolError::InvalidFormat(
+            "only clipboard updates carry data".to_string(),
+        ));
+    };
+    if !update.content.data.is_empty() {
+        return Err(ProtocolError::InvalidFormat(
+            "update carries data twice".to_string(),
+        ));
+    }
+    let mut data = Vec::with_capacity(
+        usize::try_from(data_len).map_err(|_| read_error(io::ErrorKind::OutOfMemory.into()))?,
+    );
+    file.read_to_end(&mut data).map_err(read_error)?;
+    update.content.data = data;
+
+    let message = Message::ClipboardUpdate(update);
+    message.validate()?;
+    Ok(message)
+}
+
+/// Encoded `update` behind its length; its content data must be empty
+fn encode_header(update: &ClipboardUpdate) -> Result<Vec<u8>, ProtocolError> {
+    if !update.content.data.is_empty() {
+        return Err(ProtocolError::InvalidFormat(
+            "streamed update must have empty data".to_string(),
+        ));
+    }
+    message_header(encoding::encode(&Message::ClipboardUpdate(update.clone()))?)
+}
+
+/// `encoded` behind its length
+fn message_header(encoded: Vec<u8>) -> Result<Vec<u8>, ProtocolError> {
+    let len = u32::try_from(encoded.len())
+        .map_err(|_| ProtocolError::Serialization("message too large".to_string()))?;
+    let mut header = Vec::with_capacity(MESSAGE_LEN_BYTES + encoded.len());
+    header.extend_from_slice(&len.to_le_bytes());
+    header.extend_from_slice(&encoded);
+    Ok(header)
+}
+
+/// Copy of `update` without its content data
+fn without_data(update: &ClipboardUpdate) -> ClipboardUpdate {
+    ClipboardUpdate {
+        content: ClipboardContent {
+            content_type: update.content.content_type,
+            data: Vec::new(),
+            metadata: update.content.metadata.clone(),
+        },
+        content_hash: update.content_hash,
+        sent_at: update.sent_at,
+        auto_action: update.auto_action,
+        hash_keyed: update.hash_keyed,
+        sequence: update.sequence,
+    }
+}
+
+/// Transfer payload of a `ClipboardUpdate` whose content data is read from a file
+pub struct FileSource {
+    /// Payload up to the data
+    header: Vec<u8>,
+    file: Mutex<File>,
+    file_len: u64,
+}
+
+impl FileSource {
+    /// Source for `update` with its content data read from `file`
+    ///
+    /// The update's content data must be empty, and its `size_bytes` and
+    /// `content_hash` must already describe the file, which must not change
+    /// while it is sent.
+    pub fn new(update: &ClipboardUpdate, file: File) -> io::Result<Self> {
+        let file_len = file.metadata()?.len();
+        if update.content.metadata.size_bytes != file_len {
+            return Err(invalid_input(format!(
+                "update describes {} bytes, file has {}",
+                update.content.metadata.size_bytes, file_len
+            )));
+        }
+        let header = encode_header(update).map_err(invalid_input)?;
+        Ok(Self {
+            header,
+            file: Mutex::new(file),
+            file_len,
+        })
+    }
+
+    fn read_file(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let mut file = self.file.lock();
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(buf)
+    }
+}
+
+impl TransferSource for FileSource {
+    fn size(&self) -> u64 {
+        self.header.len() as u64 + self.file_len
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let header_len = self.header.len() as u64;
+        if offset + buf.len() as u64 > self.size() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let mut filled = 0;
+        if offset < header_len {
+            let part = &self.header[offset as usize..];
+            filled = part.len().min(buf.len());
+            buf[..filled].copy_from_slice(&part[..filled]);
+        }
+        if filled < buf.len() {
+            let at = offset + filled as u64 - header_len;
+            self.read_file(at, &mut buf[filled..])?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid_input(error: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::transfer::TransferTracker;
+    use crate::protocol::{ClipboardContent, ContentType};
+    use std::io::Write;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Source wrapper recording the largest single read
+    struct Metered {
+        inner: FileSource,
+        largest_read: AtomicUsize,
+    }
+
+    impl TransferSource for Metered {
+        fn size(&self) -> u64 {
+            self.inner.size()
+        }
+
+        fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+            self.largest_read.fetch_max(buf.len(), Ordering::SeqCst);
+            self.inner.read_at(offset, buf)
+        }
+    }
+
+    #[test]
+    fn test_large_file_streams_in_bounded_chunks() {
+        const FILE_LEN: usize = 4 * 1024 * 1024;
+        const CHUNK_SIZE: usize = 64 * 1024;
+
+        let mut file = tempfile::tempfile().unwrap();
+        let contents: Vec<u8> = (0..FILE_LEN).map(|i| (i % 251) as u8).collect();
+        file.write_all(&contents).unwrap();
+
+        let mut content = ClipboardContent::new(ContentType::File, Vec::new());
+        content.metadata.size_bytes = FILE_LEN as u64;
+        content.metadata.filename = Some("backup.tar".to_string());
+        let mut update = ClipboardUpdate::new(content);
+        file.seek(SeekFrom::Start(0)).unwrap();
+        update.content_hash = crate::protocol::hash_content_reader(&mut file, None).unwrap();
+
+        let source = Arc::new(Metered {
+            inner: FileSource::new(&update, file).unwrap(),
+            largest_read: AtomicUsize::new(0),
+        });
+        let sender = TransferTracker::new();
+        let (transfer_id, total_chunks) = sender
+            .start_outgoing_source([2u8; 32], source.clone(), CHUNK_SIZE)
+            .unwrap();
+        assert_eq!(
+            total_chunks as u64,
+            source.size().div_ceil(CHUNK_SIZE as u64)
+        );
+
+        let receiver = TransferTracker::new();
+        let mut payload = None;
+        for index in 0..total_chunks {
+            let chunk = sender.outgoing_chunk(transfer_id, index).unwrap().unwrap();
+            assert!(chunk.data.len() <= CHUNK_SIZE);
+            payload = receiver.receive_chunk(&[1u8; 32], chunk).unwrap();
+        }
+
+        // Never more than a chunk (1/64 of the file) was read at once
+        assert!(source.largest_read.load(Ordering::SeqCst) <= CHUNK_SIZE);
+
+        // The receiver gets the update back with the file as its data
+        let payload = payload.expect("transfer complete");
+        assert_eq!(payload.len(), source.size());
+        let Message::ClipboardUpdate(received) = decode_payload(payload).unwrap() else {
+            panic!("expected a clipboard update");
+        };
+        assert!(received.content.data == contents);
+        assert!(received.verify_hash(None));
+        assert_eq!(
+            received.content.metadata.filename.as_deref(),
+            Some("backup.tar")
+        );
+    }
+
+    /// Send `payload` through a transfer, returning what the receiver stored
+    fn transfer(payload: Vec<u8>) -> ReceivedPayload {
+        let receiver = TransferTracker::new();
+        let mut received = None;
+        for chunk in TransferTracker::new().start_outgoing([2u8; 32], payload, 8) {
+            received = receiver.receive_chunk(&[1u8; 32], chunk).unwrap();
+        }
+        received.expect("transfer complete")
+    }
+
+    #[test]
+    fn test_payload_round_trips_through_transfer() {
+        let ping = Message::Ping(crate::protocol::Ping { timestamp: 42 });
+        let update = ClipboardUpdate::new(ClipboardContent::text("in the payload"));
+        for message in [Message::ClipboardUpdate(update), ping.clone()] {
+            let decoded = decode_payload(transfer(encode_payload(&message).unwrap())).unwrap();
+            assert_eq!(
+                encoding::encode(&decoded).unwrap(),
+                encoding::encode(&message).unwrap()
+            );
+        }
+
+        // Data after anything but a clipboard update is refused
+        let mut payload = encode_payload(&ping).unwrap();
+        payload.push(0);
+        assert!(decode_payload(transfer(payload)).is_err());
+    }
+
+    #[test]
+    fn test_update_not_matching_file_is_rejected() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(b"x").unwrap();
+
+        let with_data = ClipboardUpdate::new(ClipboardContent::new(ContentType::File, vec![1]));
+        let err = FileSource::new(&with_data, file.try_clone().unwrap())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("empty data"));
+
+        let mut wrong_size = ClipboardUpdate::new(ClipboardContent::new(ContentType::File, vec![]));
+        wrong_size.content.metadata.size_bytes = 2;
+        assert!(FileSource::new(&wrong_size, file).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+//! Checking a peer stored the same session key
+//!
+//! The session key stored at pairing can go wrong on one side, e.g. after a
+//! re-pair or a migration, while the connection itself keeps working under
+//! a rotated key; clipboard sync through the relay then fails quietly. To
+//! check it, a `KeyCheck` carries a random nonce sealed with the sender's
+//! stored key for the peer. The peer opens it with its own stored key and
+//! seals the nonce again in a `KeyCheckReply`, which the sender opens and
+//! compares. The two directions use different AAD, so a challenge can't be
+//! reflected back as its own answer.
+
+use parking_lot::Mutex;
+use rand::RngCore;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use crate::crypto::{decrypt, encrypt, EncryptedMessage};
+use crate::error::NetworkError;
+use crate::protocol::{KeyCheck, KeyCheckReply};
+
+/// How long to wait for a peer to answer a `KeyCheck`
+pub const KEY_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+const CHALLENGE_AAD: &[u8] = b"toss-key-check-v1:challenge";
+const REPLY_AAD: &[u8] = b"toss-key-check-v1:reply";
+
+/// A challenge waiting for its reply
+pub(super) struct PendingCheck {
+    pub(super) challenge: KeyCheck,
+    nonce: [u8; 32],
+    reply: oneshot::Receiver<Vec<u8>>,
+}
+
+/// The device a challenge went to, and where its reply is delivered
+type Challenged = ([u8; 32], oneshot::Sender<Vec<u8>>);
+
+/// Key checks sent and not yet answered
+#[derive(Default)]
+pub(super) struct KeyChecks {
+    pending: Mutex<HashMap<u64, Challenged>>,
+}
+
+impl KeyChecks {
+    /// Create a challenge for `device_id` sealed with `session_key`
+    pub(super) fn start(
+        &self,
+        device_id: &[u8; 32],
+        session_key: &[u8; 32],
+    ) -> Result<PendingCheck, NetworkError> {
+        let mut nonce = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let sealed_nonce = encrypt(session_key, &nonce, CHALLENGE_AAD)
+            .map_err(|e| NetworkError::Transport(e.to_string()))?
+            .to_bytes();
+
+        let (tx, rx) = oneshot::channel();
+        let challenge_id = rand::random();
+        self.pending.lock().insert(challenge_id, (*device_id, tx));
+        Ok(PendingCheck {
+            challenge: KeyCheck {
+                challenge_id,
+                sealed_nonce,
+            },
+            nonce,
+            reply: rx,
+        })
+    }
+
+    /// Wait for the reply to a challenge, checking it against `session_key`
+    ///
+    /// A reply that doesn't arrive within `timeout` counts as a mismatch,
+    /// since a peer whose key differs can't answer.
+    pub(super) async fn finish(
+        &self,
+        check: PendingCheck,
+        session_key: &[u8; 32],
+        timeout: Duration,
+    ) -> bool {
+        let challenge_id = check.challenge.challenge_id;
+        let reply = tokio::time::timeout(timeout, check.reply).await;
+        self.pending.lock().remove(&challenge_id);
+
+        match reply {
+            Ok(Ok(sealed)) => opens_to(session_key, &sealed, REPLY_AAD, &check.nonce),
+            _ => false,
+        }
+    }
+
+    /// Drop a challenge that couldn't be sent
+    pub(super) fn abandon(&self, check: PendingCheck) {
+        self.pending.lock().remove(&check.challenge.challenge_id);
+    }
+
+    /// Hand a reply to the check waiting for it
+    ///
+    /// Replies from a device other than the one challenged are ignored.
+    pub(super) fn complete(&self, device_id: &[u8; 32], reply: KeyCheckReply) {
+        let mut pending = self.pending.lock();
+        if pending
+            .get(&reply.challenge_id)
+            .is_some_and(|(challenged, _)| challenged == device_id)
+        {
+            if let Some((_, tx)) = pending.remove(&reply.challenge_id) {
+                let _ = tx.send(reply.sealed_nonce);
+            }
+        }
+    }
+}
+
+/// Answer a peer's challenge with our stored session key for it
+pub(super) fn answer(session_key: Option<&[u8; 32]>, challenge: &KeyCheck) -> KeyCheckReply {
+    let sealed_nonce = session_key
+        .and_then(|key| {
+            let nonce = open(key, &challenge.sealed_nonce, CHALLENGE_AAD)?;
+            encrypt(key, &nonce, REPLY_AAD).ok()
+        })
+        .map(|sealed| sealed.to_bytes())
+        .unwrap_or_default();
+    KeyCheckReply {
+        challenge_id: challenge.challenge_id,
+        sealed_nonce,
+    }
+}
+
+fn open(session_key: &[u8; 32], sealed: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+    let message = EncryptedMessage::from_bytes(sealed).ok()?;
+    decrypt(session_key, &message, aad).ok()
+}
+
+fn opens_to(session_key: &[u8; 32], sealed: &[u8], aad: &[u8], nonce: &[u8; 32]) -> bool {
+    open(session_key, sealed, aad).is_some_and(|opened| opened == nonce)
+}
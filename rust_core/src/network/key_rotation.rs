@@ -0,0 +1,287 @@
+//! Session key rotation between connected peers
+//!
+//! Rotation is a two-message exchange. The initiator generates an ephemeral
+//! X25519 key pair, signs the public half with its identity key and sends it
+//! in a `KeyRotation` offer, keeping the secret half until the reply arrives;
+//! it goes on using the old key meanwhile. The responder answers with its own
+//! signed ephemeral key, still under the old key, and then switches. The
+//! initiator switches when the reply arrives. Both derive the new session key
+//! from the same pair of ephemeral keys, so they end up in sync. If both
+//! sides offer at once, each treats the other's offer as the reply.
+
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{GetPublicKeyFn, PeerConnection};
+use crate::crypto::{derive_key, DerivedKeyPurpose, DeviceIdentity, EphemeralKeyPair};
+use crate::error::NetworkError;
+use crate::protocol::{KeyRotation, KeyRotationReason, Message};
+
+/// Ephemeral key state for a peer session
+#[derive(Default)]
+pub(super) struct PeerEphemeralKey {
+    /// Our key pair for an offer the peer hasn't answered yet
+    ///
+    /// Kept until the reply arrives, since the new session key can't be
+    /// derived without its secret half.
+    pub(super) pending: Option<EphemeralKeyPair>,
+}
+
+/// Rotates session keys on the manager's connections
+///
+/// Holds shared handles to the manager's state, so the periodic rotation
+/// check can run as a background task.
+#[derive(Clone)]
+pub(super) struct KeyRotator {
+    pub(super) identity: Arc<DeviceIdentity>,
+    pub(super) peers: Arc<RwLock<HashMap<[u8; 32], Arc<PeerConnection>>>>,
+    pub(super) ephemeral_keys: Arc<RwLock<HashMap<[u8; 32], PeerEphemeralKey>>>,
+    pub(super) get_public_key: Option<Arc<GetPublicKeyFn>>,
+}
+
+impl KeyRotator {
+    /// Offer a new session key to a peer
+    ///
+    /// Does nothing while an earlier offer to the peer is unanswered.
+    pub(super) async fn rotate(
+        &self,
+        device_id: &[u8; 32],
+        reason: KeyRotationReason,
+    ) -> Result<(), NetworkError> {
+        if self.has_pending(device_id) {
+            return Ok(());
+        }
+
+        let ephemeral = EphemeralKeyPair::generate();
+        self.send_public_key(device_id, *ephemeral.public_key_bytes(), reason)
+            .await?;
+
+        self.ephemeral_keys
+            .write()
+            .entry(*device_id)
+            .or_default()
+            .pending = Some(ephemeral);
+        Ok(())
+    }
+
+    /// Offer new session keys to every peer whose key is due for rotation
+    ///
+    /// Called on each keep-alive tick, so keys on idle connections are
+    /// rotated too. Returns the number of offers sent.
+    pub(super) async fn rotate_due(&self) -> usize {
+        let device_ids: Vec<[u8; 32]> = self.peers.read().keys().copied().collect();
+
+        let mut offered = 0;
+        for device_id in device_ids {
+            if self.has_pending(&device_id) {
+                continue;
+            }
+            let Some(conn) = self.connection(&device_id) else {
+                continue;
+            };
+            if !conn.should_rotate_key().await {
+                continue;
+            }
+
+            match self.rotate(&device_id, KeyRotationReason::Scheduled).await {
+                Ok(()) => offered += 1,
+                Err(e) => tracing::warn!(
+                    "Failed to rotate session key for device {}: {}",
+                    hex::encode(device_id),
+                    e
+                ),
+            }
+        }
+        offered
+    }
+
+    /// Handle a `KeyRotation` offer or reply from a peer
+    pub(super) async fn handle(
+        &self,
+        device_id: &[u8; 32],
+        rotation: &KeyRotation,
+    ) -> Result<(), NetworkError> {
+        self.verify(device_id, rotation)?;
+
+        let pending = self
+            .ephemeral_keys
+            .write()
+            .get_mut(device_id)
+            .and_then(|keys| keys.pending.take());
+
+        // A reply to our offer (or a crossing offer) completes the exchange;
+        // a fresh offer is answered under the old key before switching
+        let ephemeral = match pending {
+            Some(ephemeral) => ephemeral,
+            None => {
+                let ephemeral = EphemeralKeyPair::generate();
+                self.send_public_key(device_id, *ephemeral.public_key_bytes(), rotation.reason)
+                    .await?;
+                ephemeral
+            }
+        };
+        let shared_secret = ephemeral.derive_shared_secret(&rotation.new_public_key);
+        let new_session_key = derive_key(
+            shared_secret.as_bytes(),
+            DerivedKeyPurpose::SessionEncryption,
+            None,
+        )
+        .map_err(|e| NetworkError::ConnectionFailed(format!("Key derivation failed: {}", e)))?;
+
+        if let Some(conn) = self.connection(device_id) {
+            conn.set_session_key(new_session_key).await;
+            conn.reset_session_tracker().await;
+        }
+
+        Ok(())
+    }
+
+    fn has_pending(&self, device_id: &[u8; 32]) -> bool {
+        self.ephemeral_keys
+            .read()
+            .get(device_id)
+            .is_some_and(|keys| keys.pending.is_some())
+    }
+
+    /// Check the signature on a peer's new ephemeral key
+    fn verify(&self, device_id: &[u8; 32], rotation: &KeyRotation) -> Result<(), NetworkError> {
+        if let Some(ref get_key_fn) = self.get_public_key {
+            if let Some(peer_public_key) = get_key_fn(device_id) {
+                let algorithm = self
+                    .connection(device_id)
+                    .map(|conn| conn.peer_identity_algorithm())
+                    .unwrap_or_default();
+                if !DeviceIdentity::verify_from_public_key(
+                    algorithm,
+                    &peer_public_key,
+                    &rotation.new_public_key,
+                    &rotation.signature,
+                ) {
+                    return Err(NetworkError::ConnectionFailed(
+                        "Key rotation signature verification failed".to_string(),
+                    ));
+                }
+            } else {
+                tracing::warn!(
+                    "Device public key not found for key rotation verification, device_id: {}",
+                    hex::encode(device_id)
+                );
+                // For backward compatibility, allow if we can't find the key
+                // In production, this should probably be an error
+            }
+        } else {
+            tracing::warn!("No public key lookup function available for key rotation verification");
+            // For backward compatibility, allow if no lookup function is provided
+        }
+        Ok(())
+    }
+
+    /// Sign our new ephemeral public key and send it to the peer
+    async fn send_public_key(
+        &self,
+        device_id: &[u8; 32],
+        new_public_key: [u8; 32],
+        reason: KeyRotationReason,
+    ) -> Result<(), NetworkError> {
+        let conn = self
+            .connection(device_id)
+            .ok_or_else(|| NetworkError::PeerNotFound(hex::encode(device_id)))?;
+
+        let rotation = KeyRotation {
+            new_public_key,
+            signature: self.identity.sign(&new_public_key),
+            reason,
+        };
+        conn.send_message(&Message::KeyRotation(rotation)).await
+    }
+
+    /// Clone a peer's connection out of the map, so it can be awaited on
+    /// without holding the lock
+    fn connection(&self, device_id: &[u8; 32]) -> Option<Arc<PeerConnection>> {
+        self.peers.read().get(device_id).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::network::{NetworkConfig, NetworkManager, QuicTransport};
+    use crate::protocol::Ping;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    async fn manager(identity: Arc<DeviceIdentity>, clock: Arc<ManualClock>) -> NetworkManager {
+        let config = NetworkConfig {
+            enable_mdns: false,
+            enable_nat_discovery: false,
+            session_key_max_age: Duration::from_secs(3600),
+            ..Default::default()
+        };
+        NetworkManager::builder()
+            .identity(identity)
+            .config(config)
+            .clock(clock)
+            .build()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_idle_connection_rotates_on_schedule() {
+        let clock = Arc::new(ManualClock::new(chrono::Utc::now()));
+        let identity_a = Arc::new(DeviceIdentity::generate().unwrap());
+        let identity_b = Arc::new(DeviceIdentity::generate().unwrap());
+        let id_a = *identity_a.device_id();
+        let id_b = *identity_b.device_id();
+        let node_a = manager(identity_a, clock.clone()).await;
+        let node_b = manager(identity_b, clock.clone()).await;
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let client = QuicTransport::new(addr).await.unwrap();
+        let server = QuicTransport::new(addr).await.unwrap();
+        let (conn_a, conn_b) = tokio::join!(client.connect(server.local_addr()), server.accept());
+        let (conn_a, conn_b) = (conn_a.unwrap(), conn_b.unwrap());
+        conn_a.set_session_key([5u8; 32]).await;
+        conn_b.set_session_key([5u8; 32]).await;
+        node_a.register_connection(id_b, conn_a);
+
+        // Nothing is due until the connection is an hour old
+        assert_eq!(node_a.key_rotator.rotate_due().await, 0);
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(node_a.key_rotator.rotate_due().await, 1);
+        // The unanswered offer isn't repeated on the next tick
+        assert_eq!(node_a.key_rotator.rotate_due().await, 0);
+
+        let offer = conn_b.receive_message().await.unwrap();
+        assert!(matches!(offer, Message::KeyRotation(_)));
+        node_b.register_connection(id_a, conn_b);
+        node_b.process_message(&id_a, offer).await.unwrap();
+
+        let reply = node_a
+            .key_rotator
+            .connection(&id_b)
+            .unwrap()
+            .receive_message()
+            .await
+            .unwrap();
+        assert!(matches!(reply, Message::KeyRotation(_)));
+        node_a.process_message(&id_b, reply).await.unwrap();
+
+        // Both sides now use the same new key, and the clock starts over
+        let conn_a = node_a.key_rotator.connection(&id_b).unwrap();
+        let conn_b = node_b.key_rotator.connection(&id_a).unwrap();
+        conn_a
+            .send_message(&Message::Ping(Ping::default()))
+            .await
+            .unwrap();
+        assert!(matches!(
+            conn_b.receive_message().await.unwrap(),
+            Message::Ping(_)
+        ));
+        assert!(!conn_a.should_rotate_key().await);
+        assert!(!conn_b.should_rotate_key().await);
+        assert_eq!(node_a.key_rotator.rotate_due().await, 0);
+    }
+}
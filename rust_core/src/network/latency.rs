@@ -0,0 +1,148 @@
+//! End-to-end sync latency measurement
+//!
+//! The receiver of a clipboard update computes the one-way latency from the
+//! sender's `sent_at` timestamp, corrected by the clock offset measured via
+//! ping/pong, and reports it back in the `ClipboardAck`. The sender stores the
+//! last reported latency per device.
+
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::protocol::{ClipboardAck, ClipboardUpdate, Message, Pong};
+
+/// Tracks clock offsets and last sync latency per peer
+#[derive(Default)]
+pub struct LatencyTracker {
+    /// Estimated peer clock minus local clock (milliseconds)
+    clock_offsets: RwLock<HashMap<[u8; 32], i64>>,
+    /// Last reported one-way sync latency (milliseconds)
+    latencies: RwLock<HashMap<[u8; 32], u64>>,
+}
+
+impl LatencyTracker {
+    /// Create an empty tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record latency-relevant data from a received message
+    pub fn observe(&self, device_id: &[u8; 32], message: &Message, received_at: u64) {
+        match message {
+            Message::Pong(pong) => self.record_pong(device_id, pong, received_at),
+            Message::ClipboardAck(ack) => self.record_ack(device_id, ack),
+            _ => {}
+        }
+    }
+
+    /// Update the clock offset estimate from a pong
+    ///
+    /// Assumes a symmetric path, so the peer's pong timestamp corresponds to
+    /// the midpoint between sending the ping and receiving the pong.
+    pub fn record_pong(&self, device_id: &[u8; 32], pong: &Pong, received_at: u64) {
+        if received_at < pong.ping_timestamp {
+            return;
+        }
+        let midpoint = pong.ping_timestamp + (received_at - pong.ping_timestamp) / 2;
+        let offset = pong.pong_timestamp as i64 - midpoint as i64;
+        self.clock_offsets.write().insert(*device_id, offset);
+    }
+
+    /// Get the estimated clock offset of a peer (peer clock minus local clock)
+    pub fn clock_offset(&self, device_id: &[u8; 32]) -> Option<i64> {
+        self.clock_offsets.read().get(device_id).copied()
+    }
+
+    /// Compute the one-way latency of an update received from a peer
+    ///
+    /// Negative results caused by residual clock skew are clamped to zero.
+    pub fn one_way_latency(
+        &self,
+        device_id: &[u8; 32],
+        update: &ClipboardUpdate,
+        received_at: u64,
+    ) -> u64 {
+        let offset = self.clock_offset(device_id).unwrap_or(0);
+        let sent_at_local = update.sent_at as i64 - offset;
+        (received_at as i64 - sent_at_local).max(0) as u64
+    }
+
+    /// Store the latency reported by a peer's acknowledgment
+    pub fn record_ack(&self, device_id: &[u8; 32], ack: &ClipboardAck) {
+        if let Some(latency_ms) = ack.latency_ms {
+            self.latencies.write().insert(*device_id, latency_ms);
+        }
+    }
+
+    /// Get the last reported sync latency to a peer
+    pub fn last_latency(&self, device_id: &[u8; 32]) -> Option<u64> {
+        self.latencies.read().get(device_id).copied()
+    }
+
+    /// Forget all measurements for a peer
+    pub fn remove(&self, device_id: &[u8; 32]) {
+        self.clock_offsets.write().remove(device_id);
+        self.latencies.write().remove(device_id);
+    }
+}
+
+/// Current time in Unix milliseconds
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::ClipboardContent;
+
+    #[test]
+    fn test_clock_offset_correction() {
+        let tracker = LatencyTracker::new();
+        let peer = [1u8; 32];
+
+        // Peer clock is 5s ahead; 100ms round trip
+        tracker.record_pong(
+            &peer,
+            &Pong {
+                ping_timestamp: 1_000,
+                pong_timestamp: 6_050,
+            },
+            1_100,
+        );
+        assert_eq!(tracker.clock_offset(&peer), Some(5_000));
+
+        let mut update = ClipboardUpdate::new(ClipboardContent::text("hi"));
+        update.sent_at = 10_000; // peer clock
+        assert_eq!(tracker.one_way_latency(&peer, &update, 5_040), 40);
+
+        // Without an offset, skew that would make latency negative is clamped
+        assert_eq!(tracker.one_way_latency(&[2u8; 32], &update, 5_040), 0);
+    }
+
+    #[test]
+    fn test_record_ack() {
+        let tracker = LatencyTracker::new();
+        let peer = [1u8; 32];
+        let mut ack = ClipboardAck {
+            message_id: 0,
+            content_hash: [0; 32],
+            success: true,
+            error: None,
+            latency_ms: None,
+        };
+
+        tracker.record_ack(&peer, &ack);
+        assert_eq!(tracker.last_latency(&peer), None);
+
+        ack.latency_ms = Some(12);
+        tracker.observe(&peer, &Message::ClipboardAck(ack), 0);
+        assert_eq!(tracker.last_latency(&peer), Some(12));
+
+        tracker.remove(&peer);
+        assert_eq!(tracker.last_latency(&peer), None);
+    }
+}
@@ -0,0 +1,108 @@
+//! Screen lock state of this device and its peers
+//!
+//! Each device reports whether its screen is locked with `LockState`
+//! messages, and the last state each peer reported is kept here. With the
+//! unlocked-peers policy on, clipboard sync with a peer known to be locked is
+//! paused in both directions until it reports itself unlocked.
+
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::protocol::Message;
+
+/// Lock states and the policy gating sync on them
+#[derive(Default)]
+pub(super) struct LockStates {
+    require_peer_unlocked: AtomicBool,
+    local_locked: AtomicBool,
+    locked_peers: Mutex<HashSet<[u8; 32]>>,
+}
+
+impl LockStates {
+    pub(super) fn new(require_peer_unlocked: bool) -> Self {
+        Self {
+            require_peer_unlocked: AtomicBool::new(require_peer_unlocked),
+            ..Self::default()
+        }
+    }
+
+    pub(super) fn set_require_peer_unlocked(&self, require: bool) {
+        self.require_peer_unlocked.store(require, Ordering::SeqCst);
+    }
+
+    pub(super) fn requires_peer_unlocked(&self) -> bool {
+        self.require_peer_unlocked.load(Ordering::SeqCst)
+    }
+
+    /// Record this device's lock state, returning whether it changed
+    pub(super) fn set_local_locked(&self, locked: bool) -> bool {
+        self.local_locked.swap(locked, Ordering::SeqCst) != locked
+    }
+
+    pub(super) fn is_local_locked(&self) -> bool {
+        self.local_locked.load(Ordering::SeqCst)
+    }
+
+    pub(super) fn set_peer_locked(&self, device_id: &[u8; 32], locked: bool) {
+        let mut set = self.locked_peers.lock();
+        if locked {
+            set.insert(*device_id);
+        } else {
+            set.remove(device_id);
+        }
+    }
+
+    pub(super) fn is_peer_locked(&self, device_id: &[u8; 32]) -> bool {
+        self.locked_peers.lock().contains(device_id)
+    }
+
+    /// Whether the policy pauses clipboard sync with a peer
+    pub(super) fn is_sync_paused(&self, device_id: &[u8; 32]) -> bool {
+        self.requires_peer_unlocked() && self.is_peer_locked(device_id)
+    }
+
+    /// Note a message from a peer, returning whether it should be let through
+    ///
+    /// `LockState` updates the peer's state; clipboard updates are held back
+    /// while sync with the peer is paused.
+    pub(super) fn accept(&self, device_id: &[u8; 32], message: &Message) -> bool {
+        match message {
+            Message::LockState(state) => {
+                self.set_peer_locked(device_id, state.locked);
+                true
+            }
+            Message::ClipboardUpdate(_) => !self.is_sync_paused(device_id),
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{ClipboardContent, ClipboardUpdate, LockState};
+
+    #[test]
+    fn test_updates_from_locked_peer_are_held_back() {
+        let states = LockStates::new(true);
+        let peer = [7u8; 32];
+        let update = Message::ClipboardUpdate(ClipboardUpdate::new(ClipboardContent::text("hi")));
+        assert!(states.accept(&peer, &update));
+
+        assert!(states.accept(&peer, &Message::LockState(LockState { locked: true })));
+        assert!(states.is_sync_paused(&peer));
+        assert!(!states.accept(&peer, &update));
+        // Other peers are unaffected
+        assert!(states.accept(&[8u8; 32], &update));
+
+        // With the policy off the lock is still tracked, but sync continues
+        states.set_require_peer_unlocked(false);
+        assert!(states.is_peer_locked(&peer));
+        assert!(states.accept(&peer, &update));
+
+        states.set_require_peer_unlocked(true);
+        states.accept(&peer, &Message::LockState(LockState { locked: false }));
+        assert!(states.accept(&peer, &update));
+    }
+}
@@ -0,0 +1,51 @@
+//! Clipboard sync traffic over metered routes
+//!
+//! Bytes exchanged with a peer through the relay, or directly over a
+//! connection that isn't on the local network, are counted per peer until
+//! the owner takes them to persist. A peer can be capped: while capped, sync
+//! messages only go to it over a local connection.
+
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+
+/// Bytes exchanged with one peer over metered routes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MeteredBytes {
+    pub sent: u64,
+    pub received: u64,
+}
+
+/// Metered traffic counted since it was last taken, and the capped peers
+#[derive(Default)]
+pub(super) struct MeteredTraffic {
+    counted: Mutex<HashMap<[u8; 32], MeteredBytes>>,
+    capped: Mutex<HashSet<[u8; 32]>>,
+}
+
+impl MeteredTraffic {
+    pub(super) fn add_sent(&self, device_id: &[u8; 32], bytes: usize) {
+        self.counted.lock().entry(*device_id).or_default().sent += bytes as u64;
+    }
+
+    pub(super) fn add_received(&self, device_id: &[u8; 32], bytes: usize) {
+        self.counted.lock().entry(*device_id).or_default().received += bytes as u64;
+    }
+
+    /// Take the traffic counted so far, starting again from zero
+    pub(super) fn take(&self) -> HashMap<[u8; 32], MeteredBytes> {
+        std::mem::take(&mut *self.counted.lock())
+    }
+
+    pub(super) fn set_capped(&self, device_id: &[u8; 32], capped: bool) {
+        let mut set = self.capped.lock();
+        if capped {
+            set.insert(*device_id);
+        } else {
+            set.remove(device_id);
+        }
+    }
+
+    pub(super) fn is_capped(&self, device_id: &[u8; 32]) -> bool {
+        self.capped.lock().contains(device_id)
+    }
+}
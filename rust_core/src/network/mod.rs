@@ -6,35 +6,118 @@
 //! - Relay server client for remote connections
 //! - Network manager coordinating all networking
 
+pub mod builder;
+pub mod circuit_breaker;
+pub mod diagnostics;
 pub mod discovery;
+pub mod file_transfer;
+mod key_check;
+mod key_rotation;
+pub mod latency;
+mod lock_state;
+mod metered;
 pub mod nat_traversal;
+pub mod presence;
+pub mod pull;
+pub mod reachability;
 pub mod relay_client;
+mod relay_frame;
+pub mod security;
+pub mod transfer;
 pub mod transport;
 pub mod websocket_transport;
 
 use base64::Engine;
 use hex;
-use parking_lot::RwLock;
-use std::collections::HashMap;
+use parking_lot::{Mutex, RwLock};
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
 
+use crate::cancel::CancellationToken;
+use crate::clipboard::OutgoingSequences;
+use crate::clock::Clock;
 use crate::crypto::{
-    decrypt, derive_key, encrypt, DerivedKeyPurpose, DeviceIdentity, EncryptedMessage,
-    EphemeralKeyPair,
+    decrypt, encrypt, epoch_millis, ChannelKey, ChannelRatchet, DeviceIdentity, EncryptedMessage,
+    KeyAlgorithm, EPOCH_SIZE,
 };
-use crate::error::NetworkError;
-use crate::protocol::{KeyRotation, KeyRotationReason, Message};
+use crate::error::{CryptoError, NetworkError, ProtocolError};
+use crate::protocol::{
+    encoding, hash_content_reader, Capabilities, ClipboardAck, ClipboardUpdate, ContentType,
+    DeviceInfo, ErrorMessage, IdentityMigration, KeyRotation, KeyRotationReason, LockState,
+    Message, Ping, Pong, ScratchpadUpdate, SecurityLevel, TransferCancel, TransferChunk,
+    TransferComplete, TransferResume,
+};
+use crate::scratchpad::Scratchpad;
 
-pub use discovery::{DiscoveredPeer, MdnsDiscovery};
+pub use builder::NetworkManagerBuilder;
+pub use circuit_breaker::{
+    CircuitBreaker, DEFAULT_BREAKER_COOLDOWN, DEFAULT_BREAKER_FAILURE_THRESHOLD,
+};
+pub use diagnostics::{ConnectedPeerDiagnostics, MdnsStatus, NetworkDiagnostics, RelayStatus};
+pub use discovery::{ContentHint, DiscoveredPeer, MdnsDiscovery, RegistrationRetry};
+pub use file_transfer::FileSource;
+use key_check::KeyChecks;
+pub use key_check::KEY_CHECK_TIMEOUT;
+use key_rotation::{KeyRotator, PeerEphemeralKey};
+pub use latency::LatencyTracker;
+use lock_state::LockStates;
+pub use metered::MeteredBytes;
+use metered::MeteredTraffic;
 pub use nat_traversal::{
-    gather_candidates, IceCandidate, StunClient, StunConfig, TurnClient, TurnConfig,
+    gather_candidates, nat_type_of, CandidateType, IceCandidate, NatType, StunClient, StunConfig,
+    TurnClient, TurnConfig,
+};
+pub use presence::{PresenceThrottle, DEFAULT_PRESENCE_INTERVAL};
+pub use pull::{choose_pull_peer, ContentSources, PullCandidate, PullRoute};
+pub use reachability::{
+    DeviceReachability, ProbeSightings, ReachabilityPath, DEFAULT_PROBE_TIMEOUT,
+};
+pub use relay_client::{
+    is_connection_lost, message_signing_bytes, RelayBackoff, RelayClient, RelayMessage,
+    RelayQueueStatus, RelayReplayGuard, RELAY_MESSAGE_MAX_AGE,
+};
+pub use security::SessionSecurity;
+pub use transfer::{
+    ReceivedPayload, TransferDirection, TransferLimits, TransferProgress, TransferSource,
+    TransferTracker, DEFAULT_CHUNK_SIZE, DEFAULT_MAX_CONCURRENT_TRANSFERS,
+    DEFAULT_MAX_TRANSFERS_PER_PEER, DEFAULT_MAX_TRANSFER_BYTES, DEFAULT_TRANSFER_STALL_TIMEOUT,
+    TRANSFER_REJECTED_ERROR_CODE,
+};
+pub use transport::{
+    PeerConnection, QuicTransport, CONNECT_STAGGER, DEFAULT_SESSION_KEY_MAX_AGE,
+    KEEP_ALIVE_INTERVAL,
 };
-pub use relay_client::RelayClient;
-pub use transport::{PeerConnection, QuicTransport};
 pub use websocket_transport::{WebSocketPeerConnection, WebSocketTransport};
 
+/// Minimum interval between handled network change notifications
+const NETWORK_CHANGE_DEBOUNCE_MS: u64 = 2000;
+
+/// Relay payload marker for messages encrypted with a clipboard channel key
+const RELAY_MARKER_CHANNEL: u8 = 0x02;
+
+/// How far past our clock a sender's clipboard channel epoch may start
+const CHANNEL_EPOCH_MAX_SKEW: Duration = Duration::from_secs(300);
+
+/// How often connected peers are pinged to refresh their clock offsets
+const CLOCK_SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default number of events buffered for each event subscriber
+pub const DEFAULT_EVENT_CAPACITY: usize = 1024;
+
+/// Default time allowed for a direct send to one peer during a broadcast
+pub const DEFAULT_PEER_SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Received messages that may wait for `process_message` before reading
+/// from peers and the relay pauses
+pub const INCOMING_QUEUE_CAPACITY: usize = 256;
+
+/// A message read from a peer, and the peer it came from
+pub type IncomingMessage = ([u8; 32], Message);
+
 /// Network configuration
 #[derive(Debug, Clone)]
 pub struct NetworkConfig {
@@ -44,8 +127,52 @@ pub struct NetworkConfig {
     pub device_name: String,
     /// Optional relay server URL
     pub relay_url: Option<String>,
+    /// Relay identity key the relay must prove it holds before connecting
+    pub relay_pinned_key: Option<[u8; 32]>,
+    /// Retry schedule for connecting and reconnecting to the relay
+    pub relay_backoff: RelayBackoff,
     /// Enable mDNS discovery
     pub enable_mdns: bool,
+    /// Run STUN-based NAT discovery when the network changes
+    pub enable_nat_discovery: bool,
+    /// mDNS service name; devices only discover peers using the same name.
+    /// Checked by [`discovery::validate_service_name`] when building.
+    pub service_name: String,
+    /// mDNS service domain
+    pub service_domain: String,
+    /// Retry policy for mDNS service registration
+    pub mdns_registration_retry: RegistrationRetry,
+    /// Chunk size for chunked transfers
+    pub file_chunk_size: usize,
+    /// Events buffered per subscriber before the oldest are dropped
+    pub event_channel_capacity: usize,
+    /// Time allowed for a direct send to one peer before falling back to the relay
+    pub peer_send_timeout: Duration,
+    /// Consecutive failed sends after which a peer is skipped (0 = never)
+    pub breaker_failure_threshold: u32,
+    /// How long a failing peer is skipped before a trial send
+    pub breaker_cooldown: Duration,
+    /// Incoming transfers reassembled at once from one peer (0 = unlimited)
+    pub max_transfers_per_peer: usize,
+    /// Incoming transfers reassembled at once from all peers (0 = unlimited)
+    pub max_concurrent_transfers: usize,
+    /// Largest payload accepted in one incoming transfer, in bytes (0 = unlimited)
+    pub max_transfer_bytes: u64,
+    /// Time without a new chunk after which an incoming transfer is dropped
+    pub transfer_stall_timeout: Duration,
+    /// Connection time after which a session key is rotated, even if idle (0 = never)
+    pub session_key_max_age: Duration,
+    /// Lowest protection accepted for peer messages; `Unencrypted` allows the
+    /// plaintext relay fallback for legacy peers
+    pub min_security_level: SecurityLevel,
+    /// Identify clipboard content to peers that support it by a MAC keyed
+    /// with the pairing's session key instead of plain SHA-256
+    pub keyed_content_hash: bool,
+    /// Only exchange messages with peers on the local network: no relay or
+    /// WebSocket fallback, and no direct connections to remote addresses
+    pub lan_only: bool,
+    /// Pause clipboard sync with peers that report their screen locked
+    pub require_peer_unlocked: bool,
 }
 
 impl Default for NetworkConfig {
@@ -54,7 +181,104 @@ impl Default for NetworkConfig {
             quic_port: 0,
             device_name: "Toss Device".to_string(),
             relay_url: None,
+            relay_pinned_key: None,
+            relay_backoff: RelayBackoff::default(),
             enable_mdns: true,
+            enable_nat_discovery: true,
+            service_name: discovery::DEFAULT_SERVICE_NAME.to_string(),
+            service_domain: discovery::DEFAULT_SERVICE_DOMAIN.to_string(),
+            mdns_registration_retry: RegistrationRetry::default(),
+            file_chunk_size: DEFAULT_CHUNK_SIZE,
+            event_channel_capacity: DEFAULT_EVENT_CAPACITY,
+            peer_send_timeout: DEFAULT_PEER_SEND_TIMEOUT,
+            breaker_failure_threshold: DEFAULT_BREAKER_FAILURE_THRESHOLD,
+            breaker_cooldown: DEFAULT_BREAKER_COOLDOWN,
+            max_transfers_per_peer: DEFAULT_MAX_TRANSFERS_PER_PEER,
+            max_concurrent_transfers: DEFAULT_MAX_CONCURRENT_TRANSFERS,
+            max_transfer_bytes: DEFAULT_MAX_TRANSFER_BYTES,
+            transfer_stall_timeout: DEFAULT_TRANSFER_STALL_TIMEOUT,
+            session_key_max_age: DEFAULT_SESSION_KEY_MAX_AGE,
+            min_security_level: SecurityLevel::Encrypted,
+            keyed_content_hash: false,
+            lan_only: false,
+            require_peer_unlocked: false,
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// mDNS service type used for device discovery
+    pub fn service_type(&self) -> String {
+        discovery::service_type(&self.service_name, &self.service_domain)
+    }
+
+    /// mDNS service type used for pairing advertisements
+    pub fn pairing_service_type(&self) -> String {
+        discovery::pairing_service_type(&self.service_name, &self.service_domain)
+    }
+
+    /// Limits on incoming chunked transfers
+    pub fn transfer_limits(&self) -> TransferLimits {
+        TransferLimits {
+            max_per_peer: self.max_transfers_per_peer,
+            max_total: self.max_concurrent_transfers,
+            max_bytes: self.max_transfer_bytes,
+            stall_timeout: self.transfer_stall_timeout,
+        }
+    }
+}
+
+/// How a message reached, or failed to reach, one peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendOutcome {
+    /// Delivered over the direct connection
+    Direct,
+    /// Delivered through the relay server after the direct send failed
+    Relay,
+    /// Not delivered
+    Failed(FailureCategory),
+}
+
+/// Coarse reason a send failed, for local sync statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FailureCategory {
+    /// The peer didn't respond in time
+    Timeout,
+    /// The direct connection failed or was closed
+    Connection,
+    /// The relay server rejected or couldn't deliver the message
+    Relay,
+    /// Anything else
+    Other,
+}
+
+impl FailureCategory {
+    /// Stable lowercase name used in storage and API DTOs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureCategory::Timeout => "timeout",
+            FailureCategory::Connection => "connection",
+            FailureCategory::Relay => "relay",
+            FailureCategory::Other => "other",
+        }
+    }
+}
+
+impl From<&NetworkError> for FailureCategory {
+    fn from(error: &NetworkError) -> Self {
+        match error {
+            NetworkError::Timeout => FailureCategory::Timeout,
+            NetworkError::ConnectionFailed(_)
+            | NetworkError::ConnectionClosed
+            | NetworkError::Transport(_)
+            | NetworkError::Tls(_)
+            | NetworkError::PeerNotFound(_)
+            | NetworkError::Downgrade(_) => FailureCategory::Connection,
+            NetworkError::Relay(_) | NetworkError::NotAuthenticated => FailureCategory::Relay,
+            NetworkError::Discovery(_)
+            | NetworkError::AddressParse(_)
+            | NetworkError::InvalidConfig(_)
+            | NetworkError::Cancelled => FailureCategory::Other,
         }
     }
 }
@@ -80,6 +304,8 @@ pub enum NetworkEvent {
     },
     /// Error occurred
     Error(String),
+    /// A transfer moved to a new whole percent or completed
+    TransferProgress(TransferProgress),
 }
 
 /// Peer information
@@ -92,20 +318,27 @@ pub struct PeerInfo {
     pub is_local: bool,
 }
 
-/// Ephemeral key pair for a peer session
-#[derive(Clone)]
-struct PeerEphemeralKey {
-    #[allow(dead_code)]
-    public_key: [u8; 32], // Our ephemeral public key
-    peer_public_key: Option<[u8; 32]>, // Peer's current ephemeral public key
-}
-
 /// Callback function type for getting device public key by device ID
 pub type GetPublicKeyFn = Box<dyn Fn(&[u8; 32]) -> Option<[u8; 32]> + Send + Sync>;
 
 /// Callback function type for getting session key by device ID (for relay encryption)
 pub type GetSessionKeyFn = Box<dyn Fn(&[u8; 32]) -> Option<[u8; 32]> + Send + Sync>;
 
+/// Manager state the relay receive loop works with
+struct RelayInbox {
+    event_tx: broadcast::Sender<NetworkEvent>,
+    identity: Arc<DeviceIdentity>,
+    get_public_key: Option<Arc<GetPublicKeyFn>>,
+    get_session_key: Option<Arc<GetSessionKeyFn>>,
+    incoming_tx: mpsc::Sender<IncomingMessage>,
+    peers: Arc<RwLock<HashMap<[u8; 32], Arc<PeerConnection>>>>,
+    security: Arc<SessionSecurity>,
+    lan_only: Arc<AtomicBool>,
+    metered: Arc<MeteredTraffic>,
+    scratchpad: Arc<Scratchpad>,
+    clock: Arc<dyn Clock>,
+}
+
 /// Network manager coordinating discovery and connections
 pub struct NetworkManager {
     config: NetworkConfig,
@@ -113,11 +346,46 @@ pub struct NetworkManager {
     discovery: Option<MdnsDiscovery>,
     transport: Option<QuicTransport>,
     relay_client: Option<Arc<RelayClient>>,
-    peers: Arc<RwLock<HashMap<[u8; 32], PeerConnection>>>,
+    relay_task: Option<tokio::task::JoinHandle<()>>,
+    transfer_reaper_task: Option<tokio::task::JoinHandle<()>>,
+    key_rotation_task: Option<tokio::task::JoinHandle<()>>,
+    clock_sync_task: Option<tokio::task::JoinHandle<()>>,
+    nat_candidates: Vec<IceCandidate>,
+    last_network_change: Option<Instant>,
+    peers: Arc<RwLock<HashMap<[u8; 32], Arc<PeerConnection>>>>,
     ephemeral_keys: Arc<RwLock<HashMap<[u8; 32], PeerEphemeralKey>>>,
+    latency: Arc<LatencyTracker>,
+    content_sources: Arc<ContentSources>,
+    transfers: Arc<TransferTracker>,
+    breaker: Arc<CircuitBreaker>,
+    security: Arc<SessionSecurity>,
+    key_rotator: KeyRotator,
+    key_checks: KeyChecks,
+    metered: Arc<MeteredTraffic>,
+    lock_states: Arc<LockStates>,
+    scratchpad: Arc<Scratchpad>,
+    clipboard_sequences: OutgoingSequences,
+    clock: Arc<dyn Clock>,
     event_tx: broadcast::Sender<NetworkEvent>,
+    /// Messages read from direct connections and the relay, queued for
+    /// `process_message`
+    incoming_tx: mpsc::Sender<IncomingMessage>,
+    /// Receiving end of `incoming_tx`, until `take_incoming` hands it out
+    incoming_rx: Mutex<Option<mpsc::Receiver<IncomingMessage>>>,
+    /// Task reading messages from each direct connection
+    readers: Mutex<HashMap<[u8; 32], tokio::task::JoinHandle<()>>>,
     get_public_key: Option<Arc<GetPublicKeyFn>>,
     get_session_key: Option<Arc<GetSessionKeyFn>>,
+    /// Starts as `config.lan_only`, changed with `set_lan_only`
+    lan_only: Arc<AtomicBool>,
+}
+
+/// A manager dropped without `stop`, e.g. when a call still using it kept
+/// `stop` from getting hold of it, still ends its background tasks
+impl Drop for NetworkManager {
+    fn drop(&mut self) {
+        self.stop_local();
+    }
 }
 
 impl NetworkManager {
@@ -145,20 +413,18 @@ impl NetworkManager {
         get_public_key: Option<Arc<GetPublicKeyFn>>,
         get_session_key: Option<Arc<GetSessionKeyFn>>,
     ) -> Result<Self, NetworkError> {
-        let (event_tx, _) = broadcast::channel(100);
+        NetworkManagerBuilder::new()
+            .identity(identity)
+            .config(config)
+            .shared_public_key_fn(get_public_key)
+            .shared_session_key_fn(get_session_key)
+            .build()
+            .await
+    }
 
-        Ok(Self {
-            config,
-            identity,
-            discovery: None,
-            transport: None,
-            relay_client: None,
-            peers: Arc::new(RwLock::new(HashMap::new())),
-            ephemeral_keys: Arc::new(RwLock::new(HashMap::new())),
-            event_tx,
-            get_public_key,
-            get_session_key,
-        })
+    /// Start building a network manager with named setters
+    pub fn builder() -> NetworkManagerBuilder {
+        NetworkManagerBuilder::new()
     }
 
     /// Start the network manager
@@ -172,42 +438,133 @@ impl NetworkManager {
         let local_port = transport.local_addr().port();
         self.transport = Some(transport);
 
-        // Initialize mDNS discovery
+        // Free the buffers of transfers whose sender went quiet
+        if let Some(task) = self.transfer_reaper_task.take() {
+            task.abort();
+        }
+        let transfers = self.transfers.clone();
+        let reap_interval = (self.config.transfer_stall_timeout / 2).max(Duration::from_secs(1));
+        self.transfer_reaper_task = Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(reap_interval);
+            loop {
+                interval.tick().await;
+                let reaped = transfers.reap_stalled();
+                if reaped > 0 {
+                    tracing::info!("Dropped {} stalled incoming transfers", reaped);
+                }
+            }
+        }));
+
+        // Rotate session keys that are due on every keep-alive tick, so idle
+        // connections don't keep one key forever
+        if let Some(task) = self.key_rotation_task.take() {
+            task.abort();
+        }
+        let key_rotator = self.key_rotator.clone();
+        self.key_rotation_task = Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(KEEP_ALIVE_INTERVAL);
+            loop {
+                interval.tick().await;
+                let offered = key_rotator.rotate_due().await;
+                if offered > 0 {
+                    tracing::debug!("Offered new session keys to {} peers", offered);
+                }
+            }
+        }));
+
+        // Re-measure clock offsets, which drift over long-lived connections
+        if let Some(task) = self.clock_sync_task.take() {
+            task.abort();
+        }
+        let peers = self.peers.clone();
+        self.clock_sync_task = Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CLOCK_SYNC_INTERVAL);
+            loop {
+                interval.tick().await;
+                let conns: Vec<_> = peers
+                    .read()
+                    .iter()
+                    .map(|(device_id, conn)| (*device_id, conn.clone()))
+                    .collect();
+                for (device_id, conn) in conns {
+                    if let Err(e) = ping(&conn).await {
+                        tracing::debug!("Failed to ping {}: {}", hex::encode(device_id), e);
+                    }
+                }
+            }
+        }));
+
+        // Initialize mDNS discovery, reusing an existing registration if
+        // `start` is called again on the same port
         if self.config.enable_mdns {
-            let discovery = MdnsDiscovery::new(
-                &self.identity.device_id_hex(),
-                &self.config.device_name,
-                local_port,
-            )?;
-            discovery.register()?;
-            self.discovery = Some(discovery);
+            if self
+                .discovery
+                .as_ref()
+                .is_some_and(|d| d.port() != local_port)
+            {
+                // Withdraw the stale service before advertising the new port
+                self.discovery = None;
+            }
+            if self.discovery.is_none() {
+                self.discovery = Some(MdnsDiscovery::new_with_service_type(
+                    &self.identity.device_id_hex(),
+                    &self.config.device_name,
+                    local_port,
+                    &self.config.service_type(),
+                )?);
+            }
+
+            let discovery = self.discovery.as_ref().expect("discovery initialized");
+            // Keep going without local discovery; the next network change
+            // tries again
+            if let Err(e) = discovery
+                .register_with_retry(&self.config.mdns_registration_retry)
+                .await
+            {
+                tracing::warn!(
+                    "mDNS registration failed, local discovery unavailable: {}",
+                    e
+                );
+            }
         }
 
         // Initialize relay client if URL provided
         if let Some(ref url) = self.config.relay_url {
-            let relay = RelayClient::new(url, self.identity.clone());
-            // Connect to relay server
-            if let Err(e) = relay.connect().await {
-                tracing::warn!("Failed to connect to relay server: {}", e);
-                // Continue without relay - P2P will still work
-            } else {
-                tracing::info!("Connected to relay server at {}", url);
-
-                // Store relay client
-                let relay_arc = Arc::new(relay);
-                let relay_clone = relay_arc.clone();
-                let event_tx = self.event_tx.clone();
-                let identity = self.identity.clone();
-                let get_session_key = self.get_session_key.clone();
-
-                // Spawn task to receive messages from relay
-                tokio::spawn(async move {
-                    Self::relay_receive_loop(&relay_clone, event_tx, identity, get_session_key)
-                        .await;
-                });
+            let relay = RelayClient::new_with_device_name(
+                url,
+                self.identity.clone(),
+                &self.config.device_name,
+            )
+            .with_pinned_key(self.config.relay_pinned_key)
+            .with_backoff(self.config.relay_backoff);
+
+            // Store relay client
+            let relay_arc = Arc::new(relay);
+            let relay_clone = relay_arc.clone();
+            let inbox = RelayInbox {
+                event_tx: self.event_tx.clone(),
+                identity: self.identity.clone(),
+                get_public_key: self.get_public_key.clone(),
+                get_session_key: self.get_session_key.clone(),
+                incoming_tx: self.incoming_tx.clone(),
+                peers: self.peers.clone(),
+                security: self.security.clone(),
+                lan_only: self.lan_only.clone(),
+                metered: self.metered.clone(),
+                scratchpad: self.scratchpad.clone(),
+                clock: self.clock.clone(),
+            };
+
+            // Connect in the background, then receive messages from the relay.
+            // If the relay can't be reached, P2P still works without it.
+            self.relay_task = Some(tokio::spawn(async move {
+                if connect_relay(&relay_clone, &inbox.event_tx).await.is_err() {
+                    return;
+                }
+                Self::relay_receive_loop(&relay_clone, inbox).await;
+            }));
 
-                self.relay_client = Some(relay_arc);
-            }
+            self.relay_client = Some(relay_arc);
         }
 
         Ok(())
@@ -215,6 +572,19 @@ impl NetworkManager {
 
     /// Stop the network manager
     pub async fn stop(&mut self) {
+        self.stop_local();
+
+        // Disconnect relay (async, after lock released)
+        if let Some(ref mut relay) = self.relay_client {
+            relay.disconnect().await;
+        }
+    }
+
+    /// Withdraw discovery, close peer connections and end background tasks
+    ///
+    /// Everything `stop` does except disconnecting the relay, which needs
+    /// to await.
+    fn stop_local(&mut self) {
         // Stop discovery
         if let Some(ref discovery) = self.discovery {
             discovery.unregister();
@@ -228,10 +598,127 @@ impl NetworkManager {
             }
         }
 
-        // Disconnect relay (async, after lock released)
-        if let Some(ref mut relay) = self.relay_client {
+        // Stop the relay receive loop so it releases the connection
+        if let Some(task) = self.relay_task.take() {
+            task.abort();
+        }
+
+        for (_, reader) in self.readers.lock().drain() {
+            reader.abort();
+        }
+
+        if let Some(task) = self.transfer_reaper_task.take() {
+            task.abort();
+        }
+
+        if let Some(task) = self.key_rotation_task.take() {
+            task.abort();
+        }
+
+        if let Some(task) = self.clock_sync_task.take() {
+            task.abort();
+        }
+    }
+
+    /// Handle an OS-level network connectivity change
+    ///
+    /// Re-binds the QUIC socket, restarts mDNS, reconnects the relay and re-runs
+    /// NAT discovery. Rapid successive notifications are debounced; returns
+    /// `Ok(false)` when a notification was ignored for that reason.
+    pub async fn handle_network_change(&mut self) -> Result<bool, NetworkError> {
+        let now = Instant::now();
+        if let Some(last) = self.last_network_change {
+            if now.duration_since(last) < Duration::from_millis(NETWORK_CHANGE_DEBOUNCE_MS) {
+                tracing::debug!("Ignoring network change notification (debounced)");
+                return Ok(false);
+            }
+        }
+        self.last_network_change = Some(now);
+
+        tracing::info!("Network changed, re-initializing networking");
+
+        // Tear down state bound to the previous network
+        if let Some(discovery) = self.discovery.take() {
+            discovery.unregister();
+        }
+
+        let stale_peers: Vec<[u8; 32]> = {
+            let mut peers = self.peers.write();
+            peers
+                .drain()
+                .map(|(id, conn)| {
+                    conn.close();
+                    id
+                })
+                .collect()
+        };
+        for device_id in stale_peers {
+            let _ = self
+                .event_tx
+                .send(NetworkEvent::PeerDisconnected { device_id });
+        }
+        self.ephemeral_keys.write().clear();
+
+        if let Some(transport) = self.transport.take() {
+            transport.close();
+        }
+
+        if let Some(task) = self.relay_task.take() {
+            task.abort();
+        }
+        if let Some(relay) = self.relay_client.take() {
             relay.disconnect().await;
         }
+
+        // Bring everything back up on the new network
+        self.start().await?;
+        self.refresh_nat_candidates().await;
+
+        Ok(true)
+    }
+
+    /// Re-run NAT discovery for the current local address
+    async fn refresh_nat_candidates(&mut self) {
+        let local_addr = match self.local_addr() {
+            Some(addr) => addr,
+            None => return,
+        };
+
+        let stun_config = if self.config.enable_nat_discovery {
+            Some(StunConfig::default())
+        } else {
+            None
+        };
+
+        match gather_candidates(local_addr, stun_config, None).await {
+            Ok(candidates) => self.nat_candidates = candidates,
+            Err(e) => tracing::warn!("NAT discovery failed: {}", e),
+        }
+    }
+
+    /// Advertise the latest copied content's type and digest via mDNS
+    pub fn advertise_content_hint(
+        &self,
+        content_type: crate::protocol::ContentType,
+        content_hash: &[u8; 32],
+    ) -> Result<(), NetworkError> {
+        match self.discovery {
+            Some(ref discovery) => discovery.set_content_hint(content_type, content_hash),
+            None => Ok(()),
+        }
+    }
+
+    /// Stop advertising the latest content hint via mDNS
+    pub fn clear_content_hint(&self) -> Result<(), NetworkError> {
+        match self.discovery {
+            Some(ref discovery) => discovery.clear_content_hint(),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the connection candidates gathered during the last NAT discovery
+    pub fn nat_candidates(&self) -> &[IceCandidate] {
+        &self.nat_candidates
     }
 
     /// Subscribe to network events
@@ -255,83 +742,144 @@ impl NetworkManager {
             .collect()
     }
 
-    /// Rotate session key for a peer
-    async fn rotate_session_key(&self, device_id: &[u8; 32]) -> Result<(), NetworkError> {
-        // Generate new ephemeral key pair
-        let new_ephemeral = EphemeralKeyPair::generate();
-        let new_public_key = *new_ephemeral.public_key_bytes();
-
-        // Get peer's current ephemeral public key
-        let peer_public_key = {
-            let keys = self.ephemeral_keys.read();
-            keys.get(device_id)
-                .and_then(|k| k.peer_public_key)
-                .ok_or_else(|| {
-                    NetworkError::ConnectionFailed("No peer ephemeral key".to_string())
-                })?
-        };
-
-        // Derive new shared secret (consumes new_ephemeral)
-        let shared_secret = new_ephemeral.derive_shared_secret(&peer_public_key);
-
-        // Derive new session key
-        let new_session_key = derive_key(
-            shared_secret.as_bytes(),
-            DerivedKeyPurpose::SessionEncryption,
-            None,
-        )
-        .map_err(|e| NetworkError::ConnectionFailed(format!("Key derivation failed: {}", e)))?;
-
-        // Sign the new public key with identity key
-        let signature = self.identity.sign(&new_public_key);
-
-        // Create KeyRotation message
-        let rotation = KeyRotation {
-            new_public_key,
-            signature,
-            reason: KeyRotationReason::Scheduled,
+    /// Take a snapshot of the network state for troubleshooting
+    ///
+    /// Browses mDNS for `browse_window` to list the peers on the local
+    /// network; everything else is read from current state.
+    pub async fn diagnostics(&self, browse_window: Duration) -> NetworkDiagnostics {
+        let mdns = match self.discovery.as_ref() {
+            _ if !self.config.enable_mdns => MdnsStatus::Disabled,
+            Some(discovery) if discovery.is_registered() => MdnsStatus::Registered,
+            _ => MdnsStatus::NotRegistered,
+        };
+        let discovered_peers = match self.discovery.as_ref() {
+            Some(discovery) => discovery
+                .browse_peers(browse_window)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::debug!("mDNS browse for diagnostics failed: {}", e);
+                    Vec::new()
+                }),
+            None => Vec::new(),
         };
 
-        // Send rotation message
-        let rotation_message = Message::KeyRotation(rotation);
-        self.send_to_peer_internal(device_id, &rotation_message)
-            .await?;
+        let connected_peers = self
+            .peers
+            .read()
+            .iter()
+            .map(|(device_id, conn)| ConnectedPeerDiagnostics {
+                device_id: *device_id,
+                device_name: conn.peer_name().unwrap_or_default(),
+                addresses: conn.addresses().to_vec(),
+                transport: "quic",
+                is_local: conn.is_local(),
+                rtt_ms: conn.rtt().as_millis() as u64,
+            })
+            .collect();
 
-        // Update session key in connection
-        // Get connection pointer first, then drop the lock before await
-        let conn_ptr: Option<*const PeerConnection> = {
-            let peers = self.peers.read();
-            peers
-                .get(device_id)
-                .map(|conn| conn as *const PeerConnection)
+        let relay = if self.config.relay_url.is_none() {
+            RelayStatus::NotConfigured
+        } else if self.is_lan_only() {
+            RelayStatus::LanOnly
+        } else {
+            match self.relay_client.as_ref() {
+                Some(relay) if relay.is_long_poll() => RelayStatus::LongPoll,
+                Some(relay) => {
+                    if relay.is_connected().await {
+                        RelayStatus::Connected
+                    } else {
+                        RelayStatus::Disconnected
+                    }
+                }
+                None => RelayStatus::Disconnected,
+            }
         };
 
-        if let Some(ptr) = conn_ptr {
-            // SAFETY: We're modifying the connection, but set_session_key and reset_session_tracker
-            // use internal mutexes, so this is safe
-            let conn = unsafe { &*ptr };
-            conn.set_session_key(new_session_key).await;
-            conn.reset_session_tracker().await;
+        NetworkDiagnostics {
+            local_addr: self.local_addr(),
+            mdns,
+            discovered_peers,
+            connected_peers,
+            relay,
+            relay_url: self.config.relay_url.clone(),
+            nat_type: nat_type_of(&self.nat_candidates),
+            candidates: self.nat_candidates.clone(),
         }
+    }
 
-        // Update ephemeral keys - store the secret separately for later use
-        // For now, we'll regenerate when needed
-        {
-            let mut keys = self.ephemeral_keys.write();
-            keys.insert(
-                *device_id,
-                PeerEphemeralKey {
-                    public_key: new_public_key,
-                    peer_public_key: Some(peer_public_key),
-                },
-            );
-        }
+    /// Check which of `device_ids` are reachable, without connecting to them
+    ///
+    /// Browses mDNS and asks the relay about devices that aren't connected,
+    /// both within `timeout`. A path that doesn't answer in time counts as
+    /// unreachable.
+    pub async fn probe_reachability(
+        &self,
+        device_ids: &[[u8; 32]],
+        timeout: Duration,
+    ) -> Vec<DeviceReachability> {
+        let connected: HashSet<[u8; 32]> = self
+            .connected_peers()
+            .into_iter()
+            .map(|peer| peer.device_id)
+            .collect();
+        let unconnected: Vec<[u8; 32]> = device_ids
+            .iter()
+            .filter(|id| !connected.contains(*id))
+            .copied()
+            .collect();
+
+        let local = async {
+            let Some(discovery) = self.discovery.as_ref().filter(|_| !unconnected.is_empty())
+            else {
+                return HashSet::new();
+            };
+            discovery
+                .present_device_ids(timeout)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::debug!("mDNS reachability probe failed: {}", e);
+                    HashSet::new()
+                })
+        };
+        let relay = async {
+            let Some(relay) = self.relay_client.as_ref() else {
+                return HashSet::new();
+            };
+            let statuses = futures::future::join_all(unconnected.iter().map(|id| async move {
+                let online =
+                    tokio::time::timeout(timeout, relay.device_online(&hex::encode(id))).await;
+                match online {
+                    Ok(Ok(online)) => online.then_some(*id),
+                    Ok(Err(e)) => {
+                        tracing::debug!("Relay status for {} failed: {}", hex::encode(id), e);
+                        None
+                    }
+                    Err(_) => None,
+                }
+            }))
+            .await;
+            statuses.into_iter().flatten().collect()
+        };
+        let (local, relay) = tokio::join!(local, relay);
 
-        // Store the ephemeral secret for this rotation
-        // Note: In a full implementation, we'd store this securely
-        // For now, we regenerate when handling incoming rotations
+        let sightings = ProbeSightings {
+            connected,
+            local,
+            relay,
+        };
+        device_ids
+            .iter()
+            .map(|id| sightings.reachability(id))
+            .collect()
+    }
 
-        Ok(())
+    /// Rotate session key for a peer
+    ///
+    /// Sends a signed offer; the new key is used once the peer replies.
+    async fn rotate_session_key(&self, device_id: &[u8; 32]) -> Result<(), NetworkError> {
+        self.key_rotator
+            .rotate(device_id, KeyRotationReason::Scheduled)
+            .await
     }
 
     /// Handle incoming KeyRotation message
@@ -340,78 +888,34 @@ impl NetworkManager {
         device_id: &[u8; 32],
         rotation: &KeyRotation,
     ) -> Result<(), NetworkError> {
-        // Verify signature - get the device's identity public key
-        if let Some(ref get_key_fn) = self.get_public_key {
-            if let Some(peer_public_key) = get_key_fn(device_id) {
-                // Verify the signature on the new public key
-                if !DeviceIdentity::verify_from_public_key(
-                    &peer_public_key,
-                    &rotation.new_public_key,
-                    &rotation.signature,
-                ) {
-                    return Err(NetworkError::ConnectionFailed(
-                        "Key rotation signature verification failed".to_string(),
-                    ));
-                }
-            } else {
-                tracing::warn!(
-                    "Device public key not found for key rotation verification, device_id: {}",
-                    hex::encode(device_id)
-                );
-                // For backward compatibility, allow if we can't find the key
-                // In production, this should probably be an error
-            }
-        } else {
-            tracing::warn!("No public key lookup function available for key rotation verification");
-            // For backward compatibility, allow if no lookup function is provided
-        }
-
-        // Generate new ephemeral key pair for this rotation
-        // We generate a new one because we don't store the secret
-        // In a full implementation, we'd store ephemeral secrets securely
-        let new_ephemeral = EphemeralKeyPair::generate();
-        let new_public_key = *new_ephemeral.public_key_bytes();
-
-        // Derive new shared secret with peer's new public key
-        let shared_secret = new_ephemeral.derive_shared_secret(&rotation.new_public_key);
-
-        // Derive new session key
-        let new_session_key = derive_key(
-            shared_secret.as_bytes(),
-            DerivedKeyPurpose::SessionEncryption,
-            None,
-        )
-        .map_err(|e| NetworkError::ConnectionFailed(format!("Key derivation failed: {}", e)))?;
-
-        // Update session key
-        // Get connection pointer first, then drop the lock before await
-        let conn_ptr: Option<*const PeerConnection> = {
-            let peers = self.peers.read();
-            peers
-                .get(device_id)
-                .map(|conn| conn as *const PeerConnection)
-        };
-
-        if let Some(ptr) = conn_ptr {
-            // SAFETY: We're modifying the connection, but set_session_key and reset_session_tracker
-            // use internal mutexes, so this is safe
-            let conn = unsafe { &*ptr };
-            conn.set_session_key(new_session_key).await;
-            conn.reset_session_tracker().await;
-        }
+        self.key_rotator.handle(device_id, rotation).await
+    }
 
-        // Update ephemeral keys with new public key
-        {
-            let mut keys = self.ephemeral_keys.write();
-            keys.insert(
-                *device_id,
-                PeerEphemeralKey {
-                    public_key: new_public_key,
-                    peer_public_key: Some(rotation.new_public_key),
-                },
-            );
+    /// Check that an identity migration is signed by the peer's current identity key
+    ///
+    /// Unlike key rotation, a migration is refused if the peer's key isn't
+    /// known, since accepting it would hand the pairing to an unverified key.
+    fn verify_identity_migration(
+        &self,
+        device_id: &[u8; 32],
+        migration: &IdentityMigration,
+    ) -> Result<(), NetworkError> {
+        let public_key = self
+            .get_public_key
+            .as_ref()
+            .and_then(|get_key_fn| get_key_fn(device_id))
+            .ok_or_else(|| {
+                NetworkError::ConnectionFailed(format!(
+                    "No identity key to verify identity migration from device {}",
+                    hex::encode(device_id)
+                ))
+            })?;
+        let algorithm = peer_algorithm(&self.peers, device_id);
+        if !migration.verify(algorithm, &public_key) {
+            return Err(NetworkError::ConnectionFailed(
+                "Identity migration signature verification failed".to_string(),
+            ));
         }
-
         Ok(())
     }
 
@@ -421,16 +925,10 @@ impl NetworkManager {
         device_id: &[u8; 32],
         message: &Message,
     ) -> Result<(), NetworkError> {
-        // Get a reference to the connection while holding the lock, then drop it
-        let conn_ptr: Option<*const PeerConnection> = {
-            let peers = self.peers.read();
-            peers
-                .get(device_id)
-                .map(|conn| conn as *const PeerConnection)
-        };
+        // Clone the connection out so the lock isn't held across the send
+        let conn = self.peers.read().get(device_id).cloned();
 
-        if let Some(ptr) = conn_ptr {
-            let conn = unsafe { &*ptr };
+        if let Some(conn) = conn {
             match conn.send_message(message).await {
                 Ok(()) => Ok(()),
                 Err(e) => {
@@ -456,25 +954,51 @@ impl NetworkManager {
         }
     }
 
+    /// Key a clipboard update's content hash with the pairing's session key
+    ///
+    /// Only if enabled in the config and the peer advertised support;
+    /// otherwise the message is returned unchanged.
+    fn key_content_hash(&self, device_id: &[u8; 32], message: Message) -> Message {
+        match (message, self.content_hash_key(device_id)) {
+            (Message::ClipboardUpdate(update), Some(key)) if !update.hash_keyed => {
+                Message::ClipboardUpdate(update.with_keyed_hash(&key))
+            }
+            (message, _) => message,
+        }
+    }
+
+    /// Session key to key content hashes sent to a peer with, if enabled
+    /// and supported by the peer
+    fn content_hash_key(&self, device_id: &[u8; 32]) -> Option<[u8; 32]> {
+        let supported = self.config.keyed_content_hash
+            && self
+                .peer_capabilities(device_id)
+                .is_some_and(|c| c.contains(Capabilities::KEYED_CONTENT_HASH));
+        if !supported {
+            return None;
+        }
+        self.get_session_key.as_ref().and_then(|get| get(device_id))
+    }
+
     /// Send message to a specific peer
+    ///
+    /// If the peer has advertised its capabilities, the message is first
+    /// adapted to them; messages the peer can't handle in any form are skipped.
     pub async fn send_to_peer(
         &self,
         device_id: &[u8; 32],
         message: &Message,
     ) -> Result<(), NetworkError> {
-        // Check if rotation is needed before sending
-        // Get connection pointer first, then drop the lock before await
-        let conn_ptr: Option<*const PeerConnection> = {
-            let peers = self.peers.read();
-            peers
-                .get(device_id)
-                .map(|conn| conn as *const PeerConnection)
+        let Some(message) = &self.prepare_for_peer(device_id, message) else {
+            return Ok(());
         };
+        self.ensure_lan_route(device_id)?;
+
+        // Check if rotation is needed before sending
+        // Clone the connection out so the lock isn't held across the await
+        let conn = self.peers.read().get(device_id).cloned();
 
-        let needs_rotation = if let Some(ptr) = conn_ptr {
-            // SAFETY: We're only reading from the connection, not modifying it
-            // The connection is owned by the peers HashMap which is behind a RwLock
-            let conn = unsafe { &*ptr };
+        let needs_rotation = if let Some(conn) = conn {
             conn.should_rotate_key().await
         } else {
             false
@@ -487,54 +1011,57 @@ impl NetworkManager {
             }
         }
 
+        let relay_url = self
+            .config
+            .relay_url
+            .as_ref()
+            .filter(|_| !self.is_lan_only());
+        // A failed send drops the connection, so take the channel key for
+        // the WebSocket fallback up front
+        let channel_key = if relay_url.is_some() {
+            self.next_channel_key(device_id).await
+        } else {
+            None
+        };
+
         // Try QUIC first
         match self.send_to_peer_internal(device_id, message).await {
             Ok(()) => Ok(()),
             Err(quic_error) => {
                 // If QUIC fails, try WebSocket fallback if relay URL is configured
-                if let Some(relay_url) = &self.config.relay_url {
-                    // Use relay URL as base for WebSocket (convert to wss:// if needed)
-                    let ws_url = relay_url
-                        .replace("https://", "wss://")
-                        .replace("http://", "ws://");
-
+                if let Some(relay_url) = relay_url {
                     let device_id_hex = hex::encode(device_id);
                     tracing::debug!(
                         "QUIC failed: {}, attempting WebSocket fallback to {}",
                         quic_error,
-                        ws_url
+                        relay_url
                     );
 
-                    // Try to establish WebSocket connection and send message
-                    match WebSocketPeerConnection::connect(&format!(
-                        "{}/ws/{}",
-                        ws_url, device_id_hex
-                    ))
-                    .await
-                    {
+                    let Some(payload) =
+                        self.relay_payload(device_id, message, channel_key.as_ref())
+                    else {
+                        return Err(quic_error);
+                    };
+
+                    // The relay's peer route takes the same signed payloads as the relay client
+                    let transport =
+                        WebSocketTransport::new(relay_url.clone(), self.identity.clone())
+                            .with_pinned_key(self.config.relay_pinned_key);
+                    match transport.connect(&device_id_hex).await {
                         Ok(ws_conn) => {
-                            // Get session key if available
-                            if let Some(ref get_key) = self.get_session_key {
-                                if let Some(session_key) = get_key(device_id) {
-                                    ws_conn.set_session_key(session_key).await;
-
-                                    match ws_conn.send_message(message).await {
-                                        Ok(()) => {
-                                            tracing::info!(
-                                                "Sent message via WebSocket fallback to {}",
-                                                device_id_hex
-                                            );
-                                            return Ok(());
-                                        }
-                                        Err(ws_error) => {
-                                            tracing::warn!("WebSocket send failed: {}", ws_error);
-                                        }
-                                    }
-                                } else {
-                                    tracing::warn!("No session key for WebSocket fallback");
+                            let result = ws_conn.send_payload(&payload).await;
+                            ws_conn.close().await;
+                            match result {
+                                Ok(()) => {
+                                    tracing::info!(
+                                        "Sent message via WebSocket fallback to {}",
+                                        device_id_hex
+                                    );
+                                    return Ok(());
+                                }
+                                Err(ws_error) => {
+                                    tracing::warn!("WebSocket send failed: {}", ws_error);
                                 }
-                            } else {
-                                tracing::warn!("No session key callback for WebSocket fallback");
                             }
                         }
                         Err(ws_error) => {
@@ -549,15 +1076,127 @@ impl NetworkManager {
         }
     }
 
+    /// Whether messages are limited to peers on the local network
+    pub fn is_lan_only(&self) -> bool {
+        self.lan_only.load(Ordering::SeqCst)
+    }
+
+    /// Limit messages to peers on the local network, or lift the limit
+    ///
+    /// While set, nothing is sent over or accepted from the relay, and peers
+    /// connected over a remote address are neither sent to nor pulled from.
+    pub fn set_lan_only(&self, lan_only: bool) {
+        self.lan_only.store(lan_only, Ordering::SeqCst);
+    }
+
+    /// Whether a peer is connected over the local network
+    fn is_local_peer(&self, device_id: &[u8; 32]) -> bool {
+        self.peers
+            .read()
+            .get(device_id)
+            .is_some_and(|c| c.is_local())
+    }
+
+    /// In LAN-only mode, refuse peers not connected over the local network
+    fn ensure_lan_route(&self, device_id: &[u8; 32]) -> Result<(), NetworkError> {
+        if !self.is_lan_only() || self.is_local_peer(device_id) {
+            return Ok(());
+        }
+        Err(NetworkError::ConnectionFailed(format!(
+            "Device {} is not on the local network",
+            hex::encode(device_id)
+        )))
+    }
+
+    /// Take the sync traffic exchanged over metered routes since the last call
+    ///
+    /// Counts bytes sent to and received from each peer through the relay, and
+    /// sent directly to peers not on the local network.
+    pub fn take_metered_traffic(&self) -> HashMap<[u8; 32], MeteredBytes> {
+        self.metered.take()
+    }
+
+    /// Cap sync to a peer to the local network, or lift the cap
+    ///
+    /// While capped, broadcasts reach the peer only over a local connection:
+    /// the relay fallback and direct sends over the internet are skipped.
+    pub fn set_relay_capped(&self, device_id: &[u8; 32], capped: bool) {
+        self.metered.set_capped(device_id, capped);
+    }
+
+    /// Whether sync to a peer is capped to the local network
+    pub fn is_relay_capped(&self, device_id: &[u8; 32]) -> bool {
+        self.metered.is_capped(device_id)
+    }
+
+    /// Record whether this device's screen is locked
+    ///
+    /// Returns whether the state changed; the caller broadcasts the change.
+    /// While locked, peers connecting later are told so.
+    pub fn set_local_locked(&self, locked: bool) -> bool {
+        self.lock_states.set_local_locked(locked)
+    }
+
+    /// Pause clipboard sync with peers that report their screen locked, or resume it
+    ///
+    /// While set, clipboard updates and files aren't sent to a locked peer,
+    /// and clipboard updates from it are dropped.
+    pub fn set_require_peer_unlocked(&self, require: bool) {
+        self.lock_states.set_require_peer_unlocked(require);
+    }
+
+    /// Whether a peer last reported its screen locked
+    pub fn is_peer_locked(&self, device_id: &[u8; 32]) -> bool {
+        self.lock_states.is_peer_locked(device_id)
+    }
+
+    /// Refuse clipboard sync with a locked peer while the policy is on
+    fn ensure_peer_unlocked(&self, device_id: &[u8; 32]) -> Result<(), NetworkError> {
+        if !self.lock_states.is_sync_paused(device_id) {
+            return Ok(());
+        }
+        Err(NetworkError::ConnectionFailed(format!(
+            "Device {} is locked",
+            hex::encode(device_id)
+        )))
+    }
+
     /// Broadcast message to all connected peers
     /// Returns Ok(()) if at least one peer received the message, or if no peers are connected
     /// Returns Err only if all peers failed and no relay fallback succeeded
     pub async fn broadcast(&self, message: &Message) -> Result<(), NetworkError> {
+        self.broadcast_with_outcomes(message).await.0
+    }
+
+    /// Broadcast message to all connected peers, also reporting how each send went
+    ///
+    /// The result is the same as `broadcast`; the outcomes hold one entry per
+    /// connected peer.
+    pub async fn broadcast_with_outcomes(
+        &self,
+        message: &Message,
+    ) -> (Result<(), NetworkError>, Vec<SendOutcome>) {
+        self.broadcast_cancellable(message, &CancellationToken::new())
+            .await
+    }
+
+    /// Broadcast like `broadcast_with_outcomes`, stopping early if `cancel` fires
+    ///
+    /// Messages larger than `file_chunk_size` go to peers supporting
+    /// resumable transfers in chunks. Once canceled, no further chunks are
+    /// sent, peers with an unfinished transfer are sent a `TransferCancel`,
+    /// the relay fallback is skipped, and the result is
+    /// `NetworkError::Cancelled`.
+    pub async fn broadcast_cancellable(
+        &self,
+        message: &Message,
+        cancel: &CancellationToken,
+    ) -> (Result<(), NetworkError>, Vec<SendOutcome>) {
         // Collect all peer device IDs while holding the lock
         let (device_ids, relay_client, is_empty) = {
             let peers = self.peers.read();
             let device_list: Vec<[u8; 32]> = peers.keys().copied().collect();
-            let relay = self.relay_client.clone();
+            let relay = self.relay_client.clone().filter(|_| !self.is_lan_only());
             let empty = peers.is_empty();
             (device_list, relay, empty)
         }; // Lock is dropped here
@@ -566,100 +1205,43 @@ impl NetworkManager {
         if is_empty {
             if let Some(_relay) = &relay_client {
                 // Serialize message for relay
-                if let Ok(_serialized) = bincode::serialize(message) {
+                if let Ok(_serialized) = encoding::encode(message) {
                     // For now, we'll just log - full implementation would track target devices
                     // and send to each via relay
                     tracing::debug!("No peers connected, message would be queued on relay");
-                    return Ok(()); // Not an error if no peers are connected
+                    return (Ok(()), Vec::new()); // Not an error if no peers are connected
                 }
             }
-            return Ok(()); // No peers is not an error
+            return (Ok(()), Vec::new()); // No peers is not an error
         }
 
         let mut success_count = 0;
         let mut last_error: Option<String> = None;
+        let mut canceled = false;
+        let mut outcomes = Vec::with_capacity(device_ids.len());
 
-        // Send to all peers using send_to_peer which handles the lock properly
-        for device_id in device_ids.iter() {
-            match self.send_to_peer(device_id, message).await {
-                Ok(()) => {
-                    success_count += 1;
-                }
-                Err(e) => {
-                    last_error = Some(format!("{}", e));
-                    // Try relay as fallback
-                    if let Some(ref relay) = relay_client {
-                        let device_id_hex = hex::encode(device_id);
-
-                        // Serialize message for relay
-                        if let Ok(serialized) = bincode::serialize(message) {
-                            // Encrypt message with device's session key if available
-                            let payload = if let Some(ref get_key) = self.get_session_key {
-                                if let Some(session_key) = get_key(device_id) {
-                                    // Encrypt with session key - use device_id as additional authenticated data
-                                    match encrypt(&session_key, &serialized, device_id) {
-                                        Ok(encrypted) => {
-                                            // Prepend a marker byte (0x01) to indicate encrypted message
-                                            let mut payload = vec![0x01];
-                                            payload.extend_from_slice(&encrypted.to_bytes());
-                                            payload
-                                        }
-                                        Err(e) => {
-                                            tracing::warn!("Failed to encrypt relay message for {}: {}, sending unencrypted",
-                                                device_id_hex, e);
-                                            // Fallback to unencrypted with marker byte (0x00)
-                                            let mut payload = vec![0x00];
-                                            payload.extend_from_slice(&serialized);
-                                            payload
-                                        }
-                                    }
-                                } else {
-                                    tracing::warn!("No session key found for device {}, sending unencrypted via relay", device_id_hex);
-                                    // Unencrypted with marker byte (0x00)
-                                    let mut payload = vec![0x00];
-                                    payload.extend_from_slice(&serialized);
-                                    payload
-                                }
-                            } else {
-                                // No session key callback, send unencrypted with marker byte (0x00)
-                                let mut payload = vec![0x00];
-                                payload.extend_from_slice(&serialized);
-                                payload
-                            };
+        let sends = device_ids.iter().map(|device_id| {
+            self.broadcast_to_peer(device_id, message, relay_client.as_ref(), cancel)
+        });
 
-                            match relay.send_to_device(&device_id_hex, &payload).await {
-                                Ok(()) => {
-                                    success_count += 1;
-                                    tracing::debug!(
-                                        "Sent to device {} via relay fallback (encrypted: {})",
-                                        device_id_hex,
-                                        payload[0] == 0x01
-                                    );
-                                }
-                                Err(relay_err) => {
-                                    tracing::warn!(
-                                        "Failed to send to device {} via QUIC and relay: {} / {}",
-                                        device_id_hex,
-                                        e,
-                                        relay_err
-                                    );
-                                }
-                            }
-                        }
-                    } else {
-                        tracing::warn!(
-                            "Failed to send to device {}: {}",
-                            hex::encode(device_id),
-                            e
-                        );
-                    }
-                }
+        // Peers are sent to concurrently so one slow peer can't stall the rest
+        for (outcome, error) in futures::future::join_all(sends).await {
+            if matches!(outcome, SendOutcome::Direct | SendOutcome::Relay) {
+                success_count += 1;
             }
+            if let Some(e) = error {
+                canceled |= matches!(e, NetworkError::Cancelled);
+                last_error = Some(format!("{}", e));
+            }
+            outcomes.push(outcome);
+        }
+        if canceled {
+            return (Err(NetworkError::Cancelled), outcomes);
         }
 
         // Return success if at least one peer received the message
         // Partial failures are acceptable - we log warnings but don't fail the entire broadcast
-        if success_count > 0 {
+        let result = if success_count > 0 {
             if success_count < device_ids.len() {
                 tracing::warn!(
                     "Partial broadcast success: {}/{} devices received message",
@@ -675,6 +1257,672 @@ impl NetworkManager {
             } else {
                 Ok(()) // No peers is not an error
             }
+        };
+        (result, outcomes)
+    }
+
+    /// Send a message to one peer the way a broadcast reaches it
+    ///
+    /// Goes through `send_to_peer`, falling back to the relay if the direct
+    /// send fails or the peer isn't connected, with the same LAN-only, lock
+    /// state and relay cap checks as a broadcast. Returns the result and how
+    /// the send went.
+    pub async fn send_to_device(
+        &self,
+        device_id: &[u8; 32],
+        message: &Message,
+        cancel: &CancellationToken,
+    ) -> (Result<(), NetworkError>, SendOutcome) {
+        let relay_client = self.relay_client.clone().filter(|_| !self.is_lan_only());
+        let (outcome, error) = self
+            .broadcast_to_peer(device_id, message, relay_client.as_ref(), cancel)
+            .await;
+        let result = match (outcome, error) {
+            (SendOutcome::Direct | SendOutcome::Relay, _) => Ok(()),
+            (SendOutcome::Failed(_), Some(e)) => Err(e),
+            (SendOutcome::Failed(_), None) => {
+                Err(NetworkError::PeerNotFound(hex::encode(device_id)))
+            }
+        };
+        (result, outcome)
+    }
+
+    /// Send a broadcast message to one peer, falling back to the relay
+    ///
+    /// The direct send is skipped while the peer's circuit is open. Returns the
+    /// outcome and the direct send error, if there was one.
+    async fn broadcast_to_peer(
+        &self,
+        device_id: &[u8; 32],
+        message: &Message,
+        relay_client: Option<&Arc<RelayClient>>,
+        cancel: &CancellationToken,
+    ) -> (SendOutcome, Option<NetworkError>) {
+        // Not a failure of the peer, so its circuit is left alone
+        if let Err(e) = self.ensure_lan_route(device_id) {
+            tracing::debug!("{}, skipping in LAN-only mode", e);
+            return (SendOutcome::Failed(FailureCategory::from(&e)), Some(e));
+        }
+        if matches!(message, Message::ClipboardUpdate(_)) {
+            if let Err(e) = self.ensure_peer_unlocked(device_id) {
+                tracing::debug!("{}, skipping", e);
+                return (SendOutcome::Failed(FailureCategory::from(&e)), Some(e));
+            }
+        }
+        let is_local = self.is_local_peer(device_id);
+        let capped = self.metered.is_capped(device_id);
+        if capped && !is_local {
+            let e = NetworkError::ConnectionFailed(format!(
+                "Device {} reached its monthly relay cap",
+                hex::encode(device_id)
+            ));
+            tracing::debug!("{}, skipping", e);
+            return (SendOutcome::Failed(FailureCategory::from(&e)), Some(e));
+        }
+        let relay_client = relay_client.filter(|_| !capped);
+
+        // A failed send drops the connection, so take the channel key for
+        // the relay fallback up front
+        let channel_key = if relay_client.is_some() {
+            self.next_channel_key(device_id).await
+        } else {
+            None
+        };
+
+        let direct = if self.breaker.allow(device_id) {
+            let result = send_with_timeout(
+                self.config.peer_send_timeout,
+                self.send_direct(device_id, message, cancel),
+            )
+            .await;
+            match &result {
+                Ok(()) => self.breaker.record_success(device_id),
+                // Not the peer's fault
+                Err(NetworkError::Cancelled) => {}
+                Err(_) => self.breaker.record_failure(device_id),
+            }
+            result
+        } else {
+            tracing::debug!(
+                "Circuit to {} is open, skipping direct send",
+                hex::encode(device_id)
+            );
+            Err(NetworkError::ConnectionFailed("circuit open".to_string()))
+        };
+
+        let e = match direct {
+            Ok(()) => {
+                if !is_local {
+                    if let Ok(data) = encoding::encode(message) {
+                        self.metered.add_sent(device_id, data.len());
+                    }
+                }
+                return (SendOutcome::Direct, None);
+            }
+            Err(e) => e,
+        };
+        if cancel.is_cancelled() {
+            return (
+                SendOutcome::Failed(FailureCategory::from(&NetworkError::Cancelled)),
+                Some(NetworkError::Cancelled),
+            );
+        }
+
+        let mut outcome = SendOutcome::Failed(FailureCategory::from(&e));
+        // Try relay as fallback
+        if let Some(relay) = relay_client {
+            let device_id_hex = hex::encode(device_id);
+
+            if let Some(payload) = self.relay_payload(device_id, message, channel_key.as_ref()) {
+                match relay.send_to_device(&device_id_hex, &payload).await {
+                    Ok(()) => {
+                        outcome = SendOutcome::Relay;
+                        self.metered.add_sent(device_id, payload.len());
+                        tracing::debug!(
+                            "Sent to device {} via relay fallback (encrypted: {})",
+                            device_id_hex,
+                            payload[0] != 0x00
+                        );
+                    }
+                    Err(relay_err) => {
+                        outcome = SendOutcome::Failed(FailureCategory::from(&relay_err));
+                        tracing::warn!(
+                            "Failed to send to device {} via QUIC and relay: {} / {}",
+                            device_id_hex,
+                            e,
+                            relay_err
+                        );
+                    }
+                }
+            }
+        } else {
+            tracing::warn!("Failed to send to device {}: {}", hex::encode(device_id), e);
+        }
+        (outcome, Some(e))
+    }
+
+    /// Serialize and encrypt a message for a peer, for sending through the relay
+    ///
+    /// Prefers the ratcheted `channel_key`, then the peer's session key, and
+    /// otherwise sends the message unencrypted. Returns `None` if the message
+    /// can't be serialized, or would go out unencrypted in a session
+    /// negotiated as encrypted.
+    fn relay_payload(
+        &self,
+        device_id: &[u8; 32],
+        message: &Message,
+        channel_key: Option<&ChannelKey>,
+    ) -> Option<Vec<u8>> {
+        let device_id_hex = hex::encode(device_id);
+        let serialized = encoding::encode(message).ok()?;
+        let sealed = channel_key.and_then(|key| {
+            seal_channel_payload(self.identity.device_id(), key, &serialized)
+                .inspect_err(|e| {
+                    tracing::warn!(
+                        "Failed to encrypt relay message for {} with channel key: {}",
+                        device_id_hex,
+                        e
+                    )
+                })
+                .ok()
+        });
+
+        // Prefer the ratcheted channel key, then the device's session key
+        let payload = if let Some(sealed) = sealed {
+            sealed
+        } else if let Some(ref get_key) = self.get_session_key {
+            if let Some(session_key) = get_key(device_id) {
+                // Encrypt with session key - use device_id as additional authenticated data
+                match encrypt(&session_key, &serialized, device_id) {
+                    Ok(encrypted) => {
+                        // Prepend a marker byte (0x01) to indicate encrypted message
+                        let mut payload = vec![0x01];
+                        payload.extend_from_slice(&encrypted.to_bytes());
+                        payload
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "Failed to encrypt relay message for {}: {}, sending unencrypted",
+                            device_id_hex,
+                            e
+                        );
+                        // Fallback to unencrypted with marker byte (0x00)
+                        let mut payload = vec![0x00];
+                        payload.extend_from_slice(&serialized);
+                        payload
+                    }
+                }
+            } else {
+                tracing::warn!(
+                    "No session key found for device {}, sending unencrypted via relay",
+                    device_id_hex
+                );
+                // Unencrypted with marker byte (0x00)
+                let mut payload = vec![0x00];
+                payload.extend_from_slice(&serialized);
+                payload
+            }
+        } else {
+            // No session key callback, send unencrypted with marker byte (0x00)
+            let mut payload = vec![0x00];
+            payload.extend_from_slice(&serialized);
+            payload
+        };
+
+        // Never fall back to plaintext in a session negotiated as encrypted
+        if payload[0] == 0x00 && self.security.required(device_id) == SecurityLevel::Encrypted {
+            tracing::warn!(
+                "Not sending unencrypted relay message to {} in an encrypted session",
+                device_id_hex
+            );
+            return None;
+        }
+        Some(payload)
+    }
+
+    /// Adapt a message to a peer's capabilities, stamp a clipboard update's
+    /// sequence and key its content hash
+    ///
+    /// Returns `None` if the peer can't handle the message in any form.
+    fn prepare_for_peer(&self, device_id: &[u8; 32], message: &Message) -> Option<Message> {
+        let adapted = match self.peer_capabilities(device_id) {
+            Some(capabilities) => capabilities.adapt(message),
+            None => Some(message.clone()),
+        };
+        let Some(message) = adapted else {
+            tracing::debug!(
+                "Skipping {:?} for device {}: not supported by peer",
+                message.header().message_type,
+                hex::encode(device_id)
+            );
+            return None;
+        };
+        let message = match message {
+            Message::ClipboardUpdate(mut update) => {
+                self.stamp_sequence(device_id, &mut update);
+                Message::ClipboardUpdate(update)
+            }
+            message => message,
+        };
+        Some(self.key_content_hash(device_id, message))
+    }
+
+    /// Stamp a clipboard update with the next sequence for a peer
+    fn stamp_sequence(&self, device_id: &[u8; 32], update: &mut ClipboardUpdate) {
+        let now_ms = self.clock.now().timestamp_millis().max(0) as u64;
+        update.sequence = self.clipboard_sequences.next(device_id, now_ms);
+    }
+
+    /// Send a broadcast message directly to one peer, in resumable chunks if
+    /// it is larger than `file_chunk_size` and the peer can reassemble them
+    async fn send_direct(
+        &self,
+        device_id: &[u8; 32],
+        message: &Message,
+        cancel: &CancellationToken,
+    ) -> Result<(), NetworkError> {
+        if cancel.is_cancelled() {
+            return Err(NetworkError::Cancelled);
+        }
+        let resumable = self
+            .peer_capabilities(device_id)
+            .is_some_and(|caps| caps.contains(Capabilities::RESUMABLE_TRANSFERS));
+        if !resumable {
+            return self.send_to_peer(device_id, message).await;
+        }
+
+        let Some(message) = self.prepare_for_peer(device_id, message) else {
+            return Ok(());
+        };
+        let data = file_transfer::encode_payload(&message)
+            .map_err(|e| NetworkError::Transport(format!("Serialization failed: {}", e)))?;
+        if data.len() <= self.config.file_chunk_size {
+            return self.send_to_peer(device_id, &message).await;
+        }
+        self.ensure_lan_route(device_id)?;
+        let (transfer_id, total_chunks) =
+            self.start_transfer(device_id, Arc::new(data), self.config.file_chunk_size)?;
+        self.send_chunks(device_id, transfer_id, 0..total_chunks, Some(cancel))
+            .await
+    }
+
+    /// Take the next clipboard channel key for a connected peer
+    async fn next_channel_key(&self, device_id: &[u8; 32]) -> Option<ChannelKey> {
+        let conn = self.peers.read().get(device_id).cloned()?;
+        conn.next_channel_key(self.identity.device_id()).await.ok()
+    }
+
+    /// Get the capabilities a connected peer advertised, if any
+    pub fn peer_capabilities(&self, device_id: &[u8; 32]) -> Option<Capabilities> {
+        self.peers
+            .read()
+            .get(device_id)
+            .and_then(|conn| conn.peer_capabilities())
+    }
+
+    /// Identity key algorithm a connected peer advertised
+    pub fn peer_identity_algorithm(&self, device_id: &[u8; 32]) -> Option<KeyAlgorithm> {
+        self.peers
+            .read()
+            .get(device_id)
+            .map(|conn| conn.peer_identity_algorithm())
+    }
+
+    /// When the session key of a connected peer was set up or last rotated
+    pub fn session_key_since(&self, device_id: &[u8; 32]) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.peers
+            .read()
+            .get(device_id)
+            .map(|conn| conn.session_key_since())
+    }
+
+    /// Send our device info and capabilities to a peer
+    ///
+    /// Requires the session key to be set on the connection.
+    pub async fn send_device_info(&self, device_id: &[u8; 32]) -> Result<(), NetworkError> {
+        let info = DeviceInfo::new(*self.identity.device_id(), self.config.device_name.clone())
+            .with_identity_algorithm(self.identity.algorithm())
+            .with_min_security(self.security.minimum());
+        self.send_to_peer(device_id, &Message::DeviceInfo(info))
+            .await
+    }
+
+    /// Record a peer's device info, answering with ours the first time
+    async fn handle_device_info(
+        &self,
+        device_id: &[u8; 32],
+        info: &DeviceInfo,
+    ) -> Result<(), NetworkError> {
+        let conn = self
+            .peers
+            .read()
+            .get(device_id)
+            .cloned()
+            .ok_or_else(|| NetworkError::PeerNotFound(hex::encode(device_id)))?;
+        let first_contact = conn.peer_capabilities().is_none();
+        conn.set_peer_name(info.device_name.clone()).await;
+        conn.set_peer_capabilities(info.capabilities).await;
+        conn.set_peer_identity_algorithm(info.identity_algorithm)
+            .await;
+        self.security.negotiate(device_id, info.min_security);
+
+        if first_contact {
+            self.send_device_info(device_id).await?;
+            // A newly connected peer is unlocked unless it says otherwise
+            self.lock_states.set_peer_locked(device_id, false);
+            if self.lock_states.is_local_locked() {
+                let lock_state = LockState { locked: true };
+                self.send_to_peer(device_id, &Message::LockState(lock_state))
+                    .await?;
+            }
+            self.resume_transfers(device_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Send a message split into resumable chunks
+    ///
+    /// Returns the transfer id, or `None` if the peer doesn't support
+    /// resumable transfers and the message was sent whole. The source is kept
+    /// after a failed send so the peer can resume once it reconnects.
+    pub async fn send_chunked(
+        &self,
+        device_id: &[u8; 32],
+        message: &Message,
+        chunk_size: usize,
+    ) -> Result<Option<u64>, NetworkError> {
+        let resumable = self
+            .peer_capabilities(device_id)
+            .is_some_and(|caps| caps.contains(Capabilities::RESUMABLE_TRANSFERS));
+        if !resumable {
+            return self.send_to_peer(device_id, message).await.map(|()| None);
+        }
+
+        let data = file_transfer::encode_payload(message)
+            .map_err(|e| NetworkError::Transport(format!("Serialization failed: {}", e)))?;
+        let (transfer_id, total_chunks) =
+            self.start_transfer(device_id, Arc::new(data), chunk_size)?;
+        self.send_chunks(device_id, transfer_id, 0..total_chunks, None)
+            .await?;
+
+        Ok(Some(transfer_id))
+    }
+
+    /// Broadcast a file read from disk to all connected peers
+    ///
+    /// `update` describes the file with empty content data, as for
+    /// [`FileSource`]. The file goes to each peer as a resumable transfer
+    /// read one chunk at a time, so it is never held in memory as a whole.
+    /// Peers that can't reassemble resumable transfers aren't sent it. The
+    /// result and outcomes are as for `broadcast_cancellable`.
+    pub async fn broadcast_file(
+        &self,
+        update: &ClipboardUpdate,
+        path: &std::path::Path,
+        cancel: &CancellationToken,
+    ) -> (Result<(), NetworkError>, Vec<SendOutcome>) {
+        let device_ids: Vec<[u8; 32]> = self.peers.read().keys().copied().collect();
+        let sends = device_ids
+            .iter()
+            .map(|device_id| self.send_file_to_peer(device_id, update, path, cancel));
+
+        let mut outcomes = Vec::with_capacity(device_ids.len());
+        let mut last_error = None;
+        let mut canceled = false;
+        for result in futures::future::join_all(sends).await {
+            match result {
+                Ok(()) => outcomes.push(SendOutcome::Direct),
+                Err(e) => {
+                    canceled |= matches!(e, NetworkError::Cancelled);
+                    outcomes.push(SendOutcome::Failed(FailureCategory::from(&e)));
+                    last_error = Some(e.to_string());
+                }
+            }
+        }
+
+        let result = if canceled {
+            Err(NetworkError::Cancelled)
+        } else if outcomes.contains(&SendOutcome::Direct) {
+            Ok(())
+        } else {
+            last_error.map_or(Ok(()), |e| Err(NetworkError::ConnectionFailed(e)))
+        };
+        (result, outcomes)
+    }
+
+    /// Stream a file to one peer as a resumable transfer
+    async fn send_file_to_peer(
+        &self,
+        device_id: &[u8; 32],
+        update: &ClipboardUpdate,
+        path: &std::path::Path,
+        cancel: &CancellationToken,
+    ) -> Result<(), NetworkError> {
+        if cancel.is_cancelled() {
+            return Err(NetworkError::Cancelled);
+        }
+        self.ensure_lan_route(device_id)?;
+        self.ensure_peer_unlocked(device_id)?;
+        if self.metered.is_capped(device_id) && !self.is_local_peer(device_id) {
+            return Err(NetworkError::ConnectionFailed(format!(
+                "Device {} reached its monthly relay cap",
+                hex::encode(device_id)
+            )));
+        }
+        let capabilities = self.peer_capabilities(device_id);
+        if !capabilities.is_some_and(|caps| caps.contains(Capabilities::RESUMABLE_TRANSFERS)) {
+            return Err(NetworkError::ConnectionFailed(format!(
+                "Device {} can't receive streamed files",
+                hex::encode(device_id)
+            )));
+        }
+        if !capabilities.is_some_and(|caps| caps.supports_content_type(ContentType::File)) {
+            tracing::debug!(
+                "Skipping file for device {}: not supported by peer",
+                hex::encode(device_id)
+            );
+            return Ok(());
+        }
+
+        let read_error = |e: std::io::Error| {
+            NetworkError::Transport(format!("Failed to read {}: {}", path.display(), e))
+        };
+        let mut update = update.clone();
+        self.stamp_sequence(device_id, &mut update);
+        if let Some(key) = self.content_hash_key(device_id) {
+            let file = std::fs::File::open(path).map_err(read_error)?;
+            update.content_hash = hash_content_reader(file, Some(&key)).map_err(read_error)?;
+            update.hash_keyed = true;
+        }
+        let file = std::fs::File::open(path).map_err(read_error)?;
+        let source = FileSource::new(&update, file).map_err(read_error)?;
+
+        let (transfer_id, total_chunks) =
+            self.start_transfer(device_id, Arc::new(source), self.config.file_chunk_size)?;
+        self.send_chunks(device_id, transfer_id, 0..total_chunks, Some(cancel))
+            .await
+    }
+
+    /// Start an outgoing transfer, returning its id and number of chunks
+    fn start_transfer(
+        &self,
+        device_id: &[u8; 32],
+        source: Arc<dyn TransferSource>,
+        chunk_size: usize,
+    ) -> Result<(u64, u32), NetworkError> {
+        self.transfers
+            .start_outgoing_source(*device_id, source, chunk_size)
+            .map_err(|e| NetworkError::Transport(format!("Failed to read transfer: {}", e)))
+    }
+
+    /// Ask a peer to resend the chunks missing from unfinished transfers
+    pub async fn resume_transfers(&self, device_id: &[u8; 32]) -> Result<(), NetworkError> {
+        for resume in self.transfers.pending_resumes(device_id) {
+            tracing::debug!(
+                "Resuming transfer {} from {} ({} chunks received)",
+                resume.transfer_id,
+                hex::encode(device_id),
+                resume.received_indices.len()
+            );
+            self.send_to_peer(device_id, &Message::TransferResume(resume))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Send chunks of a transfer in order, reading each from the source as
+    /// it is sent
+    ///
+    /// If `cancel` fires, no further chunks are sent and the transfer is
+    /// canceled with the peer.
+    async fn send_chunks(
+        &self,
+        device_id: &[u8; 32],
+        transfer_id: u64,
+        indices: impl IntoIterator<Item = u32>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<(), NetworkError> {
+        for index in indices {
+            if cancel.is_some_and(CancellationToken::is_cancelled) {
+                tracing::debug!("Send canceled, stopping transfer {}", transfer_id);
+                self.cancel_transfer(transfer_id).await?;
+                return Err(NetworkError::Cancelled);
+            }
+            let chunk = match self.transfers.outgoing_chunk(transfer_id, index) {
+                Some(chunk) => chunk.map_err(|e| {
+                    NetworkError::Transport(format!("Failed to read transfer: {}", e))
+                })?,
+                None => {
+                    tracing::debug!("Transfer {} canceled, not sending more chunks", transfer_id);
+                    break;
+                }
+            };
+            let chunk_bytes = chunk.data.len() as u64;
+            self.send_to_peer(device_id, &Message::TransferChunk(chunk))
+                .await?;
+            self.transfers.mark_sent(transfer_id, index);
+            if let Some(progress) = self.transfers.outgoing_progress(transfer_id) {
+                self.report_transfer_progress(progress, chunk_bytes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Emit a `TransferProgress` event if the chunk reached a milestone
+    fn report_transfer_progress(&self, progress: TransferProgress, chunk_bytes: u64) {
+        if progress.is_milestone(chunk_bytes) {
+            let _ = self.event_tx.send(NetworkEvent::TransferProgress(progress));
+        }
+    }
+
+    /// Progress of transfers being sent or received
+    pub fn active_transfers(&self) -> Vec<TransferProgress> {
+        self.transfers.progress()
+    }
+
+    /// Cancel a transfer in either direction and tell the peer
+    ///
+    /// Local state is dropped even if the peer can't be reached. Returns
+    /// `false` if the transfer is unknown.
+    pub async fn cancel_transfer(&self, transfer_id: u64) -> Result<bool, NetworkError> {
+        let Some((device_id, direction)) = self.transfers.cancel(transfer_id) else {
+            return Ok(false);
+        };
+        tracing::debug!(
+            "Canceled {:?} transfer {} with {}",
+            direction,
+            transfer_id,
+            hex::encode(device_id)
+        );
+        let cancel = Message::TransferCancel(TransferCancel { transfer_id });
+        self.send_to_peer(&device_id, &cancel).await?;
+        Ok(true)
+    }
+
+    /// Store a received chunk and process the message once it is complete
+    ///
+    /// A completed transfer is acknowledged with a `TransferComplete` so the
+    /// sender can release the source. A transfer refused by the size or
+    /// concurrency limits is reported back to the sender with an
+    /// `ErrorMessage`.
+    async fn handle_transfer_chunk(
+        &self,
+        device_id: &[u8; 32],
+        chunk: TransferChunk,
+    ) -> Result<(), NetworkError> {
+        let transfer_id = chunk.transfer_id;
+        let chunk_bytes = chunk.data.len() as u64;
+        let payload = match self.transfers.receive_chunk(device_id, chunk) {
+            Ok(Some(payload)) => {
+                let total_bytes = payload.len();
+                self.report_transfer_progress(
+                    TransferProgress {
+                        transfer_id,
+                        direction: TransferDirection::Incoming,
+                        device_id: *device_id,
+                        total_bytes,
+                        transferred_bytes: total_bytes,
+                    },
+                    chunk_bytes,
+                );
+                let complete = Message::TransferComplete(TransferComplete { transfer_id });
+                if let Err(e) = self.send_to_peer(device_id, &complete).await {
+                    tracing::debug!(
+                        "Failed to acknowledge transfer {} from {}: {}",
+                        transfer_id,
+                        hex::encode(device_id),
+                        e
+                    );
+                }
+                payload
+            }
+            Ok(None) => {
+                if let Some(progress) = self.transfers.incoming_progress(device_id, transfer_id) {
+                    self.report_transfer_progress(progress, chunk_bytes);
+                }
+                return Ok(());
+            }
+            Err(ProtocolError::TransferRejected(reason)) => {
+                tracing::warn!(
+                    "Refusing transfer from {}: {}",
+                    hex::encode(device_id),
+                    reason
+                );
+                let error = Message::Error(ErrorMessage {
+                    code: TRANSFER_REJECTED_ERROR_CODE,
+                    message: reason,
+                    related_message_id: Some(transfer_id),
+                });
+                return self.send_to_peer(device_id, &error).await;
+            }
+            Err(e) => return Err(NetworkError::Transport(e.to_string())),
+        };
+
+        let message = file_transfer::decode_payload(payload)
+            .map_err(|e| NetworkError::Transport(format!("Deserialization failed: {}", e)))?;
+        Box::pin(self.process_message(device_id, message)).await
+    }
+
+    /// Resend the chunks a peer reports missing
+    async fn handle_transfer_resume(
+        &self,
+        device_id: &[u8; 32],
+        resume: &TransferResume,
+    ) -> Result<(), NetworkError> {
+        match self.transfers.missing_outgoing(device_id, resume) {
+            Some(missing) => {
+                self.send_chunks(device_id, resume.transfer_id, missing, None)
+                    .await
+            }
+            None => {
+                tracing::debug!(
+                    "Ignoring resume for unknown transfer {} from {}",
+                    resume.transfer_id,
+                    hex::encode(device_id)
+                );
+                Ok(())
+            }
         }
     }
 
@@ -694,25 +1942,149 @@ impl NetworkManager {
             .peer_device_id()
             .ok_or_else(|| NetworkError::ConnectionFailed("No device ID".to_string()))?;
 
-        // Initialize ephemeral key for this peer
-        let ephemeral = EphemeralKeyPair::generate();
-        let ephemeral_public = *ephemeral.public_key_bytes();
-        self.ephemeral_keys.write().insert(
-            device_id,
-            PeerEphemeralKey {
-                public_key: ephemeral_public,
-                peer_public_key: None, // Will be set during pairing
-            },
-        );
+        self.register_connection(device_id, conn);
+        self.spawn_reader(device_id);
+        self.measure_clock_offset(&device_id).await;
+        Ok(device_id)
+    }
 
-        self.peers.write().insert(device_id, conn);
+    /// Reconnect to a known peer, resuming with 0-RTT when a session ticket
+    /// from an earlier connection is cached
+    pub async fn connect_to_device(
+        &self,
+        addr: SocketAddr,
+        device_id: [u8; 32],
+    ) -> Result<(), NetworkError> {
+        let transport = self.transport.as_ref().ok_or_else(|| {
+            NetworkError::ConnectionFailed("Transport not initialized".to_string())
+        })?;
+
+        let conn = transport.connect_to_device(addr, Some(&device_id)).await?;
+        conn.set_peer_device_id(device_id).await;
+
+        self.register_connection(device_id, conn);
+        self.spawn_reader(device_id);
+        self.measure_clock_offset(&device_id).await;
+        Ok(())
+    }
+
+    /// Connect to a known peer reachable at any of `addresses`, such as a
+    /// [`DiscoveredPeer`]'s
+    ///
+    /// Attempts are staggered by [`CONNECT_STAGGER`] and the first to
+    /// complete its handshake is kept; see [`QuicTransport::connect_any`].
+    pub async fn connect_peer(
+        &self,
+        device_id: [u8; 32],
+        addresses: Vec<SocketAddr>,
+    ) -> Result<(), NetworkError> {
+        let transport = self.transport.as_ref().ok_or_else(|| {
+            NetworkError::ConnectionFailed("Transport not initialized".to_string())
+        })?;
+
+        let conn = transport
+            .connect_any(&addresses, Some(&device_id), CONNECT_STAGGER)
+            .await?;
+        conn.set_peer_device_id(device_id).await;
+
+        self.register_connection(device_id, conn);
+        self.spawn_reader(device_id);
+        self.measure_clock_offset(&device_id).await;
+        Ok(())
+    }
+
+    /// Ping a newly connected peer, so its clock offset is known before its
+    /// first update arrives
+    async fn measure_clock_offset(&self, device_id: &[u8; 32]) {
+        if let Err(e) = self.ping_peer(device_id).await {
+            tracing::debug!(
+                "Couldn't ping {} for its clock offset: {}",
+                hex::encode(device_id),
+                e
+            );
+        }
+    }
+
+    /// Track a newly established connection to a peer
+    fn register_connection(&self, device_id: [u8; 32], conn: PeerConnection) {
+        conn.set_key_rotation_policy(self.config.session_key_max_age, self.clock.clone());
+        // Ephemeral keys are exchanged on the first rotation
+        self.ephemeral_keys
+            .write()
+            .insert(device_id, PeerEphemeralKey::default());
+
+        self.peers.write().insert(device_id, Arc::new(conn));
+        // A fresh connection gets a fresh chance
+        self.breaker.reset(&device_id);
 
         let _ = self.event_tx.send(NetworkEvent::PeerConnected {
             device_id,
             device_name: String::new(),
         });
+    }
 
-        Ok(device_id)
+    /// Read messages from a peer's connection until it closes, queuing
+    /// them for `process_message`
+    ///
+    /// Replaces the reader of an earlier connection to the same peer. Once
+    /// the connection is lost, it is dropped and the peer reported
+    /// disconnected, unless it was replaced in the meantime.
+    fn spawn_reader(&self, device_id: [u8; 32]) {
+        let Some(conn) = self.peers.read().get(&device_id).cloned() else {
+            return;
+        };
+        let incoming_tx = self.incoming_tx.clone();
+        let peers = self.peers.clone();
+        let event_tx = self.event_tx.clone();
+
+        let reader = tokio::spawn(async move {
+            loop {
+                let data = match conn.receive_raw().await {
+                    Ok(data) => data,
+                    // A single stream can fail without the connection
+                    Err(e) if conn.is_connected() => {
+                        tracing::debug!("Dropping frame from {}: {}", hex::encode(device_id), e);
+                        continue;
+                    }
+                    Err(e) => {
+                        tracing::debug!("Connection to {} lost: {}", hex::encode(device_id), e);
+                        break;
+                    }
+                };
+                match conn.decrypt_message(&data).await {
+                    Ok(message) => {
+                        if incoming_tx.send((device_id, message)).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("Dropping message from {}: {}", hex::encode(device_id), e);
+                    }
+                }
+            }
+
+            let mut peers = peers.write();
+            if peers
+                .get(&device_id)
+                .is_some_and(|current| Arc::ptr_eq(current, &conn))
+            {
+                peers.remove(&device_id);
+                let _ = event_tx.send(NetworkEvent::PeerDisconnected { device_id });
+            }
+        });
+
+        if let Some(previous) = self.readers.lock().insert(device_id, reader) {
+            previous.abort();
+        }
+    }
+
+    /// Receiver of the messages read from direct connections and the relay
+    ///
+    /// Pass each to [`process_message`](Self::process_message); reading
+    /// pauses while [`INCOMING_QUEUE_CAPACITY`] of them wait. Only the first
+    /// call gets the receiver. It ends once the manager is dropped.
+    pub fn take_incoming(&self) -> Option<mpsc::Receiver<IncomingMessage>> {
+        self.incoming_rx.lock().take()
     }
 
     /// Process incoming message and handle KeyRotation if needed
@@ -726,6 +2098,109 @@ impl NetworkManager {
             return self.handle_key_rotation(device_id, rotation).await;
         }
 
+        // Only a migration signed by the peer's current key is passed on
+        if let Message::IdentityMigration(migration) = &message {
+            self.verify_identity_migration(device_id, migration)?;
+        }
+
+        // Chunks are reassembled here; only the completed message is emitted
+        match message {
+            Message::TransferChunk(chunk) => {
+                return self.handle_transfer_chunk(device_id, chunk).await;
+            }
+            Message::TransferResume(resume) => {
+                return self.handle_transfer_resume(device_id, &resume).await;
+            }
+            Message::TransferCancel(cancel) => {
+                // The peer may be either end of the transfer
+                if !self
+                    .transfers
+                    .cancel_outgoing(device_id, cancel.transfer_id)
+                {
+                    self.transfers
+                        .cancel_incoming(device_id, cancel.transfer_id);
+                }
+                return Ok(());
+            }
+            Message::TransferComplete(complete) => {
+                self.transfers
+                    .cancel_outgoing(device_id, complete.transfer_id);
+                return Ok(());
+            }
+            Message::KeyCheck(challenge) => {
+                let session_key = self.get_session_key.as_ref().and_then(|get| get(device_id));
+                let reply = key_check::answer(session_key.as_ref(), &challenge);
+                return self
+                    .send_to_peer(device_id, &Message::KeyCheckReply(reply))
+                    .await;
+            }
+            Message::KeyCheckReply(reply) => {
+                self.key_checks.complete(device_id, reply);
+                return Ok(());
+            }
+            Message::ScratchpadUpdate(update) => {
+                return self.handle_scratchpad_update(device_id, update).await;
+            }
+            _ => {}
+        }
+
+        if !self.lock_states.accept(device_id, &message) {
+            tracing::debug!(
+                "Dropping clipboard update from locked device {}",
+                hex::encode(device_id)
+            );
+            return Ok(());
+        }
+
+        let received_at = latency::now_ms();
+        self.latency.observe(device_id, &message, received_at);
+        self.content_sources.observe(device_id, &message);
+
+        match &message {
+            Message::Ping(ping) => {
+                let pong = Message::Pong(Pong::from_ping(ping));
+                if let Err(e) = self.send_to_peer(device_id, &pong).await {
+                    tracing::warn!(
+                        "Failed to answer ping from {}: {}",
+                        hex::encode(device_id),
+                        e
+                    );
+                }
+            }
+            Message::DeviceInfo(info) => {
+                if let Err(e) = self.handle_device_info(device_id, info).await {
+                    tracing::warn!(
+                        "Failed to handle device info from {}: {}",
+                        hex::encode(device_id),
+                        e
+                    );
+                }
+            }
+            Message::Error(error) if error.code == TRANSFER_REJECTED_ERROR_CODE => {
+                tracing::warn!(
+                    "{} refused a transfer: {}",
+                    hex::encode(device_id),
+                    error.message
+                );
+                if let Some(transfer_id) = error.related_message_id {
+                    self.transfers.cancel_outgoing(device_id, transfer_id);
+                }
+            }
+            Message::ClipboardUpdate(update) => {
+                if let Err(e) = self
+                    .acknowledge_update(device_id, update, received_at)
+                    .await
+                {
+                    tracing::warn!(
+                        "Failed to acknowledge update from {}: {}",
+                        hex::encode(device_id),
+                        e
+                    );
+                }
+            }
+            _ => {}
+        }
+
         // Emit event for other message types
         let _ = self.event_tx.send(NetworkEvent::MessageReceived {
             from_device_id: *device_id,
@@ -735,121 +2210,816 @@ impl NetworkManager {
         Ok(())
     }
 
+    /// Apply a scratchpad edit from a peer
+    ///
+    /// Emits the update only if it changed the text, and sends our whole
+    /// document back when the peer is missing edits we have.
+    async fn handle_scratchpad_update(
+        &self,
+        device_id: &[u8; 32],
+        update: ScratchpadUpdate,
+    ) -> Result<(), NetworkError> {
+        let received = self.scratchpad.receive(&update);
+        if received.changed {
+            let _ = self.event_tx.send(NetworkEvent::MessageReceived {
+                from_device_id: *device_id,
+                message: Message::ScratchpadUpdate(update),
+            });
+        }
+        match received.reply {
+            Some(reply) => {
+                self.send_to_peer(device_id, &Message::ScratchpadUpdate(reply))
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// The shared scratchpad document this manager applies edits to
+    pub fn scratchpad(&self) -> &Arc<Scratchpad> {
+        &self.scratchpad
+    }
+
+    /// Acknowledge a received clipboard update, reporting its one-way latency
+    async fn acknowledge_update(
+        &self,
+        device_id: &[u8; 32],
+        update: &ClipboardUpdate,
+        received_at: u64,
+    ) -> Result<(), NetworkError> {
+        let latency_ms = self.latency.one_way_latency(device_id, update, received_at);
+
+        let ack = Message::ClipboardAck(ClipboardAck {
+            message_id: 0,
+            content_hash: update.content_hash,
+            success: true,
+            error: None,
+            latency_ms: Some(latency_ms),
+        });
+
+        self.send_to_peer(device_id, &ack).await
+    }
+
+    /// Check that a connected peer stored the same session key as we did
+    ///
+    /// Sends a `KeyCheck` sealed with our stored key and waits up to
+    /// `timeout` for the answer. `Ok(false)` means the keys differ, or the
+    /// peer didn't answer in time. Fails if the peer isn't connected,
+    /// hasn't advertised support, or there's no stored key for it.
+    pub async fn verify_session_key(
+        &self,
+        device_id: &[u8; 32],
+        timeout: Duration,
+    ) -> Result<bool, NetworkError> {
+        if !self.peers.read().contains_key(device_id) {
+            return Err(NetworkError::PeerNotFound(hex::encode(device_id)));
+        }
+        if !self
+            .peer_capabilities(device_id)
+            .is_some_and(|c| c.contains(Capabilities::KEY_CHECK))
+        {
+            return Err(NetworkError::Transport(
+                "Peer doesn't support key checks".to_string(),
+            ));
+        }
+        let session_key = self
+            .get_session_key
+            .as_ref()
+            .and_then(|get| get(device_id))
+            .ok_or(NetworkError::NotAuthenticated)?;
+
+        let check = self.key_checks.start(device_id, &session_key)?;
+        let challenge = Message::KeyCheck(check.challenge.clone());
+        if let Err(e) = self.send_to_peer(device_id, &challenge).await {
+            self.key_checks.abandon(check);
+            return Err(e);
+        }
+        Ok(self.key_checks.finish(check, &session_key, timeout).await)
+    }
+
+    /// Send a ping over a peer's direct connection to refresh its clock
+    /// offset estimate
+    ///
+    /// Unlike other sends, a failure leaves the connection in place: a ping
+    /// can't go out before the session key is set.
+    pub async fn ping_peer(&self, device_id: &[u8; 32]) -> Result<(), NetworkError> {
+        let conn = self
+            .peers
+            .read()
+            .get(device_id)
+            .cloned()
+            .ok_or_else(|| NetworkError::PeerNotFound(hex::encode(device_id)))?;
+        ping(&conn).await
+    }
+
+    /// Get the last end-to-end sync latency reported by a peer (milliseconds)
+    pub fn last_sync_latency(&self, device_id: &[u8; 32]) -> Option<u64> {
+        self.latency.last_latency(device_id)
+    }
+
+    /// Pick the peer to pull content with `content_hash` from
+    ///
+    /// Only peers whose latest update had this content are considered. A
+    /// local direct connection beats a remote one, which beats the relay;
+    /// then the lowest round-trip time wins. Returns `None` if no reachable
+    /// peer holds the content.
+    pub fn best_peer_for_pull(&self, content_hash: &[u8; 32]) -> Option<[u8; 32]> {
+        let lan_only = self.is_lan_only();
+        let candidates: Vec<PullCandidate> = {
+            let peers = self.peers.read();
+            self.content_sources
+                .peers_with(content_hash)
+                .into_iter()
+                .filter_map(|device_id| match peers.get(&device_id) {
+                    Some(conn) if lan_only && !conn.is_local() => None,
+                    Some(conn) => Some(PullCandidate {
+                        device_id,
+                        route: if conn.is_local() {
+                            PullRoute::Local
+                        } else {
+                            PullRoute::Direct
+                        },
+                        rtt: Some(conn.rtt()),
+                    }),
+                    None if lan_only => None,
+                    None => self.relay_client.as_ref().map(|_| PullCandidate {
+                        device_id,
+                        route: PullRoute::Relay,
+                        rtt: self
+                            .latency
+                            .last_latency(&device_id)
+                            .map(Duration::from_millis),
+                    }),
+                })
+                .collect()
+        };
+        choose_pull_peer(&candidates)
+    }
+
     /// Receive loop for relay messages
-    async fn relay_receive_loop(
-        relay: &RelayClient,
-        event_tx: broadcast::Sender<NetworkEvent>,
-        _identity: Arc<DeviceIdentity>,
-        get_session_key: Option<Arc<GetSessionKeyFn>>,
-    ) {
+    async fn relay_receive_loop(relay: &RelayClient, inbox: RelayInbox) {
+        let RelayInbox {
+            event_tx,
+            identity,
+            get_public_key,
+            get_session_key,
+            incoming_tx,
+            peers,
+            security,
+            lan_only,
+            metered,
+            scratchpad,
+            clock,
+        } = inbox;
+        // Receiving side of each sender's clipboard channel ratchet
+        let mut ratchets: HashMap<[u8; 32], ChannelRatchet> = HashMap::new();
+        let mut replays = RelayReplayGuard::default();
+        let recipient = identity.device_id_hex();
+
         loop {
             match relay.receive().await {
+                Ok(relay_msg) if lan_only.load(Ordering::SeqCst) => {
+                    tracing::debug!(
+                        "Dropping relay message from {} in LAN-only mode",
+                        relay_msg.from_device
+                    );
+                }
                 Ok(relay_msg) => {
-                    // Decode device ID from hex
-                    if let Ok(device_id_bytes) = hex::decode(&relay_msg.from_device) {
-                        if device_id_bytes.len() == 32 {
-                            let mut device_id = [0u8; 32];
-                            device_id.copy_from_slice(&device_id_bytes);
-
-                            // Decode base64 payload
-                            if let Ok(payload) = base64::engine::general_purpose::STANDARD
-                                .decode(&relay_msg.encrypted_payload)
-                            {
-                                if payload.is_empty() {
-                                    tracing::warn!("Received empty relay payload");
+                    let now = clock.now().timestamp_millis().max(0) as u64;
+                    // Check the sender signed it before trusting the relay's from_device
+                    match authenticate_relay_message(
+                        &relay_msg,
+                        &recipient,
+                        get_public_key.as_deref(),
+                        &peers,
+                        &mut replays,
+                        now,
+                    ) {
+                        Err(e) => {
+                            tracing::warn!(
+                                "Dropping relay message from {}: {}",
+                                relay_msg.from_device,
+                                e
+                            );
+                        }
+                        Ok((device_id, payload)) => {
+                            metered.add_received(&device_id, payload.len());
+                            if payload.is_empty() {
+                                tracing::warn!("Received empty relay payload");
+                                continue;
+                            }
+
+                            // Check marker byte: 0x02 = channel key, 0x01 = session key,
+                            // 0x00 = unencrypted
+                            if payload[0] > RELAY_MARKER_CHANNEL {
+                                tracing::warn!(
+                                    "Unknown relay payload marker {:#04x} from {}",
+                                    payload[0],
+                                    relay_msg.from_device
+                                );
+                                continue;
+                            }
+                            let is_encrypted = payload[0] == 0x01;
+                            let data = &payload[1..];
+
+                            let level = if payload[0] == 0x00 {
+                                SecurityLevel::Unencrypted
+                            } else {
+                                SecurityLevel::Encrypted
+                            };
+                            if !verify_session_security(
+                                &security, &peers, &event_tx, &device_id, level,
+                            ) {
+                                continue;
+                            }
+
+                            let message_bytes = if payload[0] == RELAY_MARKER_CHANNEL {
+                                let Some(session_key) =
+                                    get_session_key.as_ref().and_then(|get| get(&device_id))
+                                else {
+                                    tracing::warn!("No session key for device {}, cannot decrypt relay message",
+                                            relay_msg.from_device);
                                     continue;
+                                };
+                                match open_channel_payload(
+                                    &mut ratchets,
+                                    &session_key,
+                                    &device_id,
+                                    data,
+                                    now,
+                                ) {
+                                    Ok(decrypted) => decrypted,
+                                    Err(e) => {
+                                        tracing::warn!(
+                                            "Failed to decrypt relay message from {}: {}",
+                                            relay_msg.from_device,
+                                            e
+                                        );
+                                        continue;
+                                    }
                                 }
-
-                                // Check marker byte: 0x01 = encrypted, 0x00 = unencrypted
-                                let is_encrypted = payload[0] == 0x01;
-                                let data = &payload[1..];
-
-                                let message_bytes = if is_encrypted {
-                                    // Decrypt with session key
-                                    if let Some(ref get_key) = get_session_key {
-                                        if let Some(session_key) = get_key(&device_id) {
-                                            // Parse encrypted message
-                                            match EncryptedMessage::from_bytes(data) {
-                                                Ok(encrypted) => {
-                                                    // Decrypt with device_id as AAD
-                                                    match decrypt(
-                                                        &session_key,
-                                                        &encrypted,
-                                                        &device_id,
-                                                    ) {
-                                                        Ok(decrypted) => decrypted,
-                                                        Err(e) => {
-                                                            tracing::warn!("Failed to decrypt relay message from {}: {}",
+                            } else if is_encrypted {
+                                // Decrypt with session key
+                                if let Some(ref get_key) = get_session_key {
+                                    if let Some(session_key) = get_key(&device_id) {
+                                        // Parse encrypted message
+                                        match EncryptedMessage::from_bytes(data) {
+                                            Ok(encrypted) => {
+                                                // Decrypt with device_id as AAD
+                                                match decrypt(&session_key, &encrypted, &device_id)
+                                                {
+                                                    Ok(decrypted) => decrypted,
+                                                    Err(e) => {
+                                                        tracing::warn!("Failed to decrypt relay message from {}: {}",
                                                                 relay_msg.from_device, e);
-                                                            continue;
-                                                        }
+                                                        continue;
                                                     }
                                                 }
-                                                Err(e) => {
-                                                    tracing::warn!("Failed to parse encrypted relay message: {}", e);
-                                                    continue;
-                                                }
                                             }
-                                        } else {
-                                            tracing::warn!("No session key for device {}, cannot decrypt relay message",
-                                                relay_msg.from_device);
-                                            continue;
+                                            Err(e) => {
+                                                tracing::warn!(
+                                                    "Failed to parse encrypted relay message: {}",
+                                                    e
+                                                );
+                                                continue;
+                                            }
                                         }
                                     } else {
-                                        tracing::warn!("No session key callback, cannot decrypt encrypted relay message");
+                                        tracing::warn!("No session key for device {}, cannot decrypt relay message",
+                                                relay_msg.from_device);
                                         continue;
                                     }
                                 } else {
-                                    // Unencrypted message (legacy or fallback)
-                                    tracing::debug!(
-                                        "Received unencrypted relay message from {}",
-                                        relay_msg.from_device
-                                    );
-                                    data.to_vec()
-                                };
+                                    tracing::warn!("No session key callback, cannot decrypt encrypted relay message");
+                                    continue;
+                                }
+                            } else {
+                                // Unencrypted message (legacy or fallback)
+                                tracing::debug!(
+                                    "Received unencrypted relay message from {}",
+                                    relay_msg.from_device
+                                );
+                                data.to_vec()
+                            };
 
-                                // Deserialize message
-                                match bincode::deserialize(&message_bytes) {
-                                    Ok(message) => {
+                            // Deserialize message
+                            match Message::decode(&message_bytes) {
+                                Ok(Message::ScratchpadUpdate(update)) => {
+                                    // Whole-document replies only go over direct connections
+                                    let received = scratchpad.receive(&update);
+                                    if received.changed {
                                         let _ = event_tx.send(NetworkEvent::MessageReceived {
                                             from_device_id: device_id,
-                                            message,
+                                            message: Message::ScratchpadUpdate(update),
                                         });
                                     }
-                                    Err(e) => {
-                                        tracing::warn!(
-                                            "Failed to deserialize relay message: {}",
-                                            e
-                                        );
+                                }
+                                Ok(message) => {
+                                    // Processed like messages from direct connections
+                                    if incoming_tx.send((device_id, message)).await.is_err() {
+                                        return;
                                     }
                                 }
-                            } else {
-                                tracing::warn!("Failed to decode relay payload");
+                                Err(e) => {
+                                    tracing::warn!("Failed to deserialize relay message: {}", e);
+                                }
                             }
                         }
                     }
                 }
+                Err(e) if is_connection_lost(&e) => {
+                    tracing::warn!("Relay connection lost: {}", e);
+                    let _ = event_tx.send(NetworkEvent::Error(format!("Relay error: {}", e)));
+                    relay.disconnect().await;
+                    if connect_relay(relay, &event_tx).await.is_err() {
+                        return;
+                    }
+                }
                 Err(e) => {
                     tracing::warn!("Relay receive error: {}", e);
                     let _ = event_tx.send(NetworkEvent::Error(format!("Relay error: {}", e)));
-                    // Wait before retrying
-                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
                 }
             }
         }
     }
 }
 
+/// Connect to the relay on its backoff schedule
+///
+/// Every failed attempt is reported as a `NetworkEvent::Error` carrying the
+/// attempt count. Returns the last error once the schedule gives up.
+async fn connect_relay(
+    relay: &RelayClient,
+    event_tx: &broadcast::Sender<NetworkEvent>,
+) -> Result<(), NetworkError> {
+    let max_attempts = relay.backoff().max_attempts;
+    let result = relay
+        .connect_with_retry(|attempt, e| {
+            let message = relay_attempt_message(attempt, max_attempts, e);
+            tracing::warn!("{}", message);
+            let _ = event_tx.send(NetworkEvent::Error(message));
+        })
+        .await;
+
+    match &result {
+        Ok(()) => tracing::info!("Connected to relay server at {}", relay.url()),
+        Err(_) => tracing::warn!(
+            "Giving up on relay server at {}; continuing with direct connections only",
+            relay.url()
+        ),
+    }
+    result
+}
+
+/// Ping a peer; its pong updates the clock offset estimate
+async fn ping(conn: &PeerConnection) -> Result<(), NetworkError> {
+    conn.send_message(&Message::Ping(Ping::default())).await
+}
+
+/// Error message for a failed relay connection attempt
+fn relay_attempt_message(attempt: u32, max_attempts: u32, error: &NetworkError) -> String {
+    if max_attempts == 0 {
+        format!("Relay connection failed (attempt {}): {}", attempt, error)
+    } else {
+        format!(
+            "Relay connection failed (attempt {}/{}): {}",
+            attempt, max_attempts, error
+        )
+    }
+}
+
+/// Check a received message's security level against the peer's session
+///
+/// A downgrade drops the peer: its direct connection is closed and an error
+/// is reported. The negotiated level is kept, so a later plaintext message
+/// from the same peer is rejected too. Returns whether the message may be
+/// processed.
+fn verify_session_security(
+    security: &SessionSecurity,
+    peers: &RwLock<HashMap<[u8; 32], Arc<PeerConnection>>>,
+    event_tx: &broadcast::Sender<NetworkEvent>,
+    device_id: &[u8; 32],
+    level: SecurityLevel,
+) -> bool {
+    let Err(e) = security.check(device_id, level) else {
+        return true;
+    };
+    tracing::warn!("Dropping peer {}: {}", hex::encode(device_id), e);
+
+    let removed = peers.write().remove(device_id);
+    let _ = event_tx.send(NetworkEvent::Error(e.to_string()));
+    if let Some(conn) = removed {
+        conn.close();
+        let _ = event_tx.send(NetworkEvent::PeerDisconnected {
+            device_id: *device_id,
+        });
+    }
+    false
+}
+
+/// Identity key algorithm a peer advertised, defaulting while it isn't connected
+fn peer_algorithm(
+    peers: &RwLock<HashMap<[u8; 32], Arc<PeerConnection>>>,
+    device_id: &[u8; 32],
+) -> KeyAlgorithm {
+    peers
+        .read()
+        .get(device_id)
+        .map(|conn| conn.peer_identity_algorithm())
+        .unwrap_or_default()
+}
+
+/// Check a relayed message's sender signature and decode its payload
+///
+/// The sender's key is looked up by the relay's claimed `from_device`, so a
+/// relay that rewrites it fails verification. Stale messages and replays of
+/// ones already accepted (per `replays`) are refused. Without a key lookup
+/// the payload is accepted unverified, as for key rotation.
+fn authenticate_relay_message(
+    relay_msg: &RelayMessage,
+    recipient: &str,
+    get_public_key: Option<&GetPublicKeyFn>,
+    peers: &RwLock<HashMap<[u8; 32], Arc<PeerConnection>>>,
+    replays: &mut RelayReplayGuard,
+    now: u64,
+) -> Result<([u8; 32], Vec<u8>), NetworkError> {
+    let device_id: [u8; 32] = hex::decode(&relay_msg.from_device)
+        .ok()
+        .and_then(|id| id.try_into().ok())
+        .ok_or_else(|| NetworkError::Relay("Invalid sender device id".to_string()))?;
+
+    let payload = match get_public_key {
+        Some(get_key) => {
+            let public_key = get_key(&device_id)
+                .ok_or_else(|| NetworkError::Relay("Unknown sender".to_string()))?;
+            let algorithm = peer_algorithm(peers, &device_id);
+            let payload = relay_msg.verify_sender(algorithm, &public_key, recipient, now)?;
+            // Verified, so both are set
+            let signed_at = relay_msg.signed_at.unwrap_or_default();
+            let signature = relay_msg.signature.as_deref().unwrap_or_default();
+            if !replays.accept(&device_id, signed_at, signature) {
+                return Err(NetworkError::Relay("Replayed relay message".to_string()));
+            }
+            payload
+        }
+        None => {
+            tracing::warn!(
+                "No public key lookup function available for relay message verification"
+            );
+            base64::engine::general_purpose::STANDARD
+                .decode(&relay_msg.encrypted_payload)
+                .map_err(|_| NetworkError::Relay("Invalid relay payload encoding".to_string()))?
+        }
+    };
+    Ok((device_id, payload))
+}
+
+/// Additional authenticated data binding a channel message to its sender and key
+fn channel_aad(sender_id: &[u8; 32], epoch: &[u8; EPOCH_SIZE], index: u32) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(32 + EPOCH_SIZE + 4);
+    aad.extend_from_slice(sender_id);
+    aad.extend_from_slice(epoch);
+    aad.extend_from_slice(&index.to_be_bytes());
+    aad
+}
+
+/// Encrypt a relay payload with a clipboard channel key
+///
+/// Layout: marker, epoch, big-endian key index, encrypted message.
+fn seal_channel_payload(
+    sender_id: &[u8; 32],
+    key: &ChannelKey,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let aad = channel_aad(sender_id, &key.epoch, key.index);
+    let encrypted = encrypt(&key.key, plaintext, &aad)?;
+
+    let mut payload = vec![RELAY_MARKER_CHANNEL];
+    payload.extend_from_slice(&key.epoch);
+    payload.extend_from_slice(&key.index.to_be_bytes());
+    payload.extend_from_slice(&encrypted.to_bytes());
+    Ok(payload)
+}
+
+/// Decrypt a channel payload (without its marker byte) from `sender_id`
+///
+/// The sender's ratchet only advances once the message decrypts, so a
+/// tampered payload can't burn keys. Only the sender's current chain or a
+/// later one is followed; chains from earlier epochs are never rebuilt, so
+/// a replayed message can't rewind the ratchet. `now` is the current Unix
+/// time in milliseconds, and epochs starting well after it are refused.
+fn open_channel_payload(
+    ratchets: &mut HashMap<[u8; 32], ChannelRatchet>,
+    session_key: &[u8; 32],
+    sender_id: &[u8; 32],
+    data: &[u8],
+    now: u64,
+) -> Result<Vec<u8>, CryptoError> {
+    if data.len() < EPOCH_SIZE + 4 {
+        return Err(CryptoError::Decryption("Message too short".to_string()));
+    }
+    let (epoch, rest) = data.split_at(EPOCH_SIZE);
+    let (index, ciphertext) = rest.split_at(4);
+    let epoch: [u8; EPOCH_SIZE] = epoch.try_into().expect("epoch size");
+    let index = u32::from_be_bytes(index.try_into().expect("index size"));
+
+    let mut ratchet = match ratchets.get(sender_id) {
+        Some(ratchet) if ratchet.epoch() == epoch => ratchet.clone(),
+        Some(ratchet) if epoch_millis(&epoch) < epoch_millis(&ratchet.epoch()) => {
+            return Err(CryptoError::Decryption(
+                "Channel epoch is older than the current chain".to_string(),
+            ));
+        }
+        _ if epoch_millis(&epoch)
+            > now.saturating_add(CHANNEL_EPOCH_MAX_SKEW.as_millis() as u64) =>
+        {
+            return Err(CryptoError::Decryption(
+                "Channel epoch starts in the future".to_string(),
+            ));
+        }
+        // A later epoch means the sender restarted its chain (e.g. after reconnecting)
+        _ => ChannelRatchet::with_epoch(session_key, sender_id, epoch)?,
+    };
+    let key = ratchet.key_at(index)?;
+    let encrypted = EncryptedMessage::from_bytes(ciphertext)?;
+    let plaintext = decrypt(&key.key, &encrypted, &channel_aad(sender_id, &epoch, index))?;
+
+    ratchets.insert(*sender_id, ratchet);
+    Ok(plaintext)
+}
+
+/// Run a direct send to one peer, giving up after `timeout`
+async fn send_with_timeout<F>(timeout: Duration, send: F) -> Result<(), NetworkError>
+where
+    F: std::future::Future<Output = Result<(), NetworkError>>,
+{
+    tokio::time::timeout(timeout, send)
+        .await
+        .unwrap_or(Err(NetworkError::Timeout))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_channel_payload_roundtrip_discards_used_keys() {
+        let session_key = [9u8; 32];
+        let sender_id = [4u8; 32];
+        let mut sender = ChannelRatchet::new(&session_key, &sender_id).unwrap();
+        let mut ratchets = HashMap::new();
+
+        let first = seal_channel_payload(&sender_id, &sender.next_key().unwrap(), b"one").unwrap();
+        let second = seal_channel_payload(&sender_id, &sender.next_key().unwrap(), b"two").unwrap();
+        assert_eq!(first[0], RELAY_MARKER_CHANNEL);
+
+        let now = epoch_millis(&sender.epoch());
+        let mut open = |payload: &[u8]| {
+            open_channel_payload(&mut ratchets, &session_key, &sender_id, &payload[1..], now)
+        };
+
+        // Tampering doesn't advance the receiver
+        let mut tampered = second.clone();
+        *tampered.last_mut().unwrap() ^= 0xFF;
+        assert!(open(&tampered).is_err());
+
+        assert_eq!(open(&second).unwrap(), b"two");
+
+        // The key for the earlier message is gone once the chain moved on
+        assert!(open(&first).is_err());
+    }
+
+    #[test]
+    fn test_channel_payload_never_rewinds_to_an_earlier_epoch() {
+        let session_key = [9u8; 32];
+        let sender_id = [4u8; 32];
+        let mut old_chain = ChannelRatchet::new(&session_key, &sender_id).unwrap();
+        let mut new_chain = ChannelRatchet::new(&session_key, &sender_id).unwrap();
+        let now = epoch_millis(&new_chain.epoch());
+        let mut ratchets = HashMap::new();
+        let mut open = |payload: &[u8]| {
+            open_channel_payload(&mut ratchets, &session_key, &sender_id, &payload[1..], now)
+        };
+
+        let old = seal_channel_payload(&sender_id, &old_chain.next_key().unwrap(), b"old").unwrap();
+        let new = seal_channel_payload(&sender_id, &new_chain.next_key().unwrap(), b"new").unwrap();
+        assert_eq!(open(&old).unwrap(), b"old");
+        assert_eq!(open(&new).unwrap(), b"new");
+
+        // A replay from the earlier chain is refused rather than rebuilding it
+        let replay =
+            seal_channel_payload(&sender_id, &old_chain.next_key().unwrap(), b"again").unwrap();
+        assert!(open(&replay).is_err());
+        let next =
+            seal_channel_payload(&sender_id, &new_chain.next_key().unwrap(), b"next").unwrap();
+        assert_eq!(open(&next).unwrap(), b"next");
+
+        // So is a chain claiming to start well after our clock
+        let future_epoch = (now + 3_600_000).to_be_bytes();
+        let mut future =
+            ChannelRatchet::with_epoch(&session_key, &sender_id, future_epoch).unwrap();
+        let ahead =
+            seal_channel_payload(&sender_id, &future.next_key().unwrap(), b"ahead").unwrap();
+        assert!(open(&ahead).is_err());
+    }
+
+    #[test]
+    fn test_relay_tampered_sender_fails_verification() {
+        let sender = DeviceIdentity::generate().unwrap();
+        let other = DeviceIdentity::generate().unwrap();
+        let recipient = DeviceIdentity::generate().unwrap().device_id_hex();
+        let keys: HashMap<[u8; 32], [u8; 32]> = [
+            (*sender.device_id(), sender.public_key()),
+            (*other.device_id(), other.public_key()),
+        ]
+        .into();
+        let get_public_key: GetPublicKeyFn = Box::new(move |id: &[u8; 32]| keys.get(id).copied());
+
+        let payload = b"\x01ciphertext";
+        let signed_at = 1_700_000_000_000;
+        let engine = base64::engine::general_purpose::STANDARD;
+        let message = RelayMessage {
+            id: "queued".to_string(),
+            from_device: sender.device_id_hex(),
+            to_device: recipient.clone(),
+            encrypted_payload: engine.encode(payload),
+            timestamp: 0,
+            signed_at: Some(signed_at),
+            signature: Some(
+                engine.encode(sender.sign(&message_signing_bytes(&recipient, signed_at, payload))),
+            ),
+        };
+
+        let peers = RwLock::new(HashMap::new());
+        let mut replays = RelayReplayGuard::default();
+        let mut authenticate = |message: &RelayMessage, recipient: &str| {
+            authenticate_relay_message(
+                message,
+                recipient,
+                Some(&get_public_key),
+                &peers,
+                &mut replays,
+                signed_at + 1000,
+            )
+        };
+
+        // Stripping the signature or delivering to someone else fails
+        let unsigned = RelayMessage {
+            signature: None,
+            ..message.clone()
+        };
+        assert!(authenticate(&unsigned, &recipient).is_err());
+        assert!(authenticate(&message, &other.device_id_hex()).is_err());
+
+        let (device_id, decoded) = authenticate(&message, &recipient).unwrap();
+        assert_eq!(device_id, *sender.device_id());
+        assert_eq!(decoded, payload);
+
+        // The relay claims the message came from another paired device
+        let forged = RelayMessage {
+            from_device: other.device_id_hex(),
+            ..message.clone()
+        };
+        assert!(authenticate(&forged, &recipient).is_err());
+
+        // Or delivers it a second time
+        assert!(authenticate(&message, &recipient).is_err());
+    }
+
+    #[test]
+    fn test_relay_message_freshness_and_order() {
+        let sender = DeviceIdentity::generate().unwrap();
+        let recipient = DeviceIdentity::generate().unwrap().device_id_hex();
+        let public_key = sender.public_key();
+        let get_public_key: GetPublicKeyFn = Box::new(move |_: &[u8; 32]| Some(public_key));
+        let engine = base64::engine::general_purpose::STANDARD;
+        let signed = |signed_at: u64, payload: &[u8]| RelayMessage {
+            id: format!("m{}", signed_at),
+            from_device: sender.device_id_hex(),
+            to_device: recipient.clone(),
+            encrypted_payload: engine.encode(payload),
+            timestamp: 0,
+            signed_at: Some(signed_at),
+            signature: Some(
+                engine.encode(sender.sign(&message_signing_bytes(&recipient, signed_at, payload))),
+            ),
+        };
+
+        let now = 1_700_000_000_000;
+        let max_age = RELAY_MESSAGE_MAX_AGE.as_millis() as u64;
+        let peers = RwLock::new(HashMap::new());
+        let mut replays = RelayReplayGuard::default();
+        let mut authenticate = |message: &RelayMessage| {
+            authenticate_relay_message(
+                message,
+                &recipient,
+                Some(&get_public_key),
+                &peers,
+                &mut replays,
+                now,
+            )
+        };
+
+        // Older than the relay keeps messages, or from the future
+        assert!(authenticate(&signed(now - max_age - 1, b"\x01old")).is_err());
+        assert!(authenticate(&signed(now + 3_600_000, b"\x01future")).is_err());
+
+        // Two messages in the same millisecond both get through once
+        assert!(authenticate(&signed(now - 10, b"\x01a")).is_ok());
+        assert!(authenticate(&signed(now - 10, b"\x01b")).is_ok());
+        assert!(authenticate(&signed(now - 10, b"\x01b")).is_err());
+
+        // Anything signed before the newest accepted message is a replay
+        assert!(authenticate(&signed(now - 5, b"\x01c")).is_ok());
+        assert!(authenticate(&signed(now - 10, b"\x01d")).is_err());
+    }
+
     #[tokio::test]
     async fn test_network_config_default() {
         let config = NetworkConfig::default();
         assert_eq!(config.quic_port, 0);
         assert!(config.enable_mdns);
         assert!(config.relay_url.is_none());
+        assert!(config.relay_pinned_key.is_none());
+        assert_eq!(config.service_type(), "_toss._udp.local.");
+        assert_eq!(config.pairing_service_type(), "_toss-pair._udp.local.");
+        assert_eq!(config.file_chunk_size, DEFAULT_CHUNK_SIZE);
+        assert_eq!(config.event_channel_capacity, DEFAULT_EVENT_CAPACITY);
+        assert_eq!(config.peer_send_timeout, DEFAULT_PEER_SEND_TIMEOUT);
+        assert_eq!(
+            config.breaker_failure_threshold,
+            DEFAULT_BREAKER_FAILURE_THRESHOLD
+        );
+        assert_eq!(config.breaker_cooldown, DEFAULT_BREAKER_COOLDOWN);
+        assert_eq!(config.session_key_max_age, DEFAULT_SESSION_KEY_MAX_AGE);
+    }
+
+    #[tokio::test]
+    async fn test_unresponsive_peer_does_not_delay_others() {
+        let timeout = Duration::from_millis(200);
+        let start = Instant::now();
+
+        // Peer 1 never answers; the others answer quickly
+        let sends = (0..3).map(|peer| async move {
+            let result = if peer == 1 {
+                send_with_timeout(timeout, std::future::pending()).await
+            } else {
+                send_with_timeout(timeout, async {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    Ok(())
+                })
+                .await
+            };
+            (result, start.elapsed())
+        });
+        let results = futures::future::join_all(sends).await;
+
+        for peer in [0, 2] {
+            let (result, delivered_after) = &results[peer];
+            assert!(result.is_ok());
+            assert!(*delivered_after < timeout);
+        }
+        assert!(matches!(results[1].0, Err(NetworkError::Timeout)));
+        assert!(start.elapsed() < timeout * 2);
+    }
+
+    async fn started_manager(service_name: &str) -> NetworkManager {
+        started_node(NetworkConfig {
+            service_name: service_name.to_string(),
+            enable_nat_discovery: false,
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Whether `browser` sees `advertiser` via mDNS within a few seconds
+    fn discovers(browser: &NetworkManager, advertiser: &NetworkManager) -> bool {
+        let receiver = browser.discovery.as_ref().unwrap().browse().unwrap();
+        let advertised_id = advertiser.identity.device_id_hex()[..16].to_string();
+        let deadline = Instant::now() + Duration::from_secs(3);
+
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            if let Ok(mdns_sd::ServiceEvent::ServiceResolved(info)) =
+                receiver.recv_timeout(remaining)
+            {
+                let id = info.get_properties().get("id").map(|v| v.val_str());
+                if id == Some(advertised_id.as_str()) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    #[tokio::test]
+    #[ignore = "Requires multicast networking"]
+    async fn test_service_type_isolation() {
+        let advertiser = started_manager("acme-toss").await;
+        let same = started_manager("acme-toss").await;
+        let other = started_manager("toss").await;
+
+        assert!(discovers(&same, &advertiser));
+        assert!(!discovers(&other, &advertiser));
     }
 
     #[tokio::test]
@@ -902,6 +3072,893 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_network_change_reinitializes_state() {
+        let identity = Arc::new(DeviceIdentity::generate().unwrap());
+        let config = NetworkConfig {
+            enable_nat_discovery: false,
+            // Nothing listens here, so the relay connection attempt fails fast
+            relay_url: Some("http://127.0.0.1:9".to_string()),
+            ..Default::default()
+        };
+
+        let mut manager = NetworkManager::new(identity, config).await.unwrap();
+        manager.start().await.unwrap();
+        assert!(manager.discovery.is_some());
+        // The relay connects (and retries) in the background
+        let old_relay = manager.relay_client.clone().unwrap();
+        assert!(manager.relay_task.is_some());
+        assert!(manager.nat_candidates().is_empty());
+
+        assert!(manager.handle_network_change().await.unwrap());
+        assert!(manager.discovery.is_some());
+        assert!(manager.local_addr().is_some());
+        // A fresh relay client replaces the one bound to the old network
+        assert!(!Arc::ptr_eq(
+            &old_relay,
+            manager.relay_client.as_ref().unwrap()
+        ));
+        assert!(manager.relay_task.is_some());
+        assert_eq!(manager.nat_candidates().len(), 1);
+
+        // A second notification right away is debounced
+        assert!(!manager.handle_network_change().await.unwrap());
+
+        manager.stop().await;
+    }
+
+    /// Config for nodes that only talk over loopback
+    fn loopback_config() -> NetworkConfig {
+        NetworkConfig {
+            enable_mdns: false,
+            enable_nat_discovery: false,
+            ..Default::default()
+        }
+    }
+
+    /// A started node with a fresh identity
+    async fn started_node(config: NetworkConfig) -> NetworkManager {
+        let identity = Arc::new(DeviceIdentity::generate().unwrap());
+        let mut node = NetworkManager::new(identity, config).await.unwrap();
+        node.start().await.unwrap();
+        node
+    }
+
+    /// Connect `from` to `to` over loopback
+    ///
+    /// Returns `from`'s end and the end `to` accepted, neither keyed yet.
+    async fn link(from: &NetworkManager, to: &NetworkManager) -> (PeerConnection, PeerConnection) {
+        let addr: SocketAddr = format!("127.0.0.1:{}", to.local_addr().unwrap().port())
+            .parse()
+            .unwrap();
+        let (outgoing, accepted) = tokio::join!(
+            from.transport.as_ref().unwrap().connect(addr),
+            to.transport.as_ref().unwrap().accept()
+        );
+        (outgoing.unwrap(), accepted.unwrap())
+    }
+
+    /// [`link`] with both ends keyed with the same session key
+    async fn keyed_link(
+        from: &NetworkManager,
+        to: &NetworkManager,
+    ) -> (PeerConnection, PeerConnection) {
+        let (outgoing, accepted) = link(from, to).await;
+        outgoing.set_session_key([9u8; 32]).await;
+        accepted.set_session_key([9u8; 32]).await;
+        (outgoing, accepted)
+    }
+
+    /// Add `to` as a keyed peer of `from`, returning the end `to` accepted
+    async fn add_peer(from: &NetworkManager, to: &NetworkManager) -> PeerConnection {
+        let (outgoing, accepted) = keyed_link(from, to).await;
+        from.peers
+            .write()
+            .insert(*to.identity.device_id(), Arc::new(outgoing));
+        accepted
+    }
+
+    /// Node A with B and C as keyed peers, and the ends B and C read from
+    struct ThreeNodes {
+        node_a: NetworkManager,
+        node_b: NetworkManager,
+        node_c: NetworkManager,
+        id_b: [u8; 32],
+        id_c: [u8; 32],
+        conn_b: PeerConnection,
+        conn_c: PeerConnection,
+    }
+
+    impl ThreeNodes {
+        /// Start the nodes, A with `config_a`; with `remote_c`, A sees C as
+        /// a peer reached over the internet
+        async fn start(config_a: NetworkConfig, remote_c: bool) -> Self {
+            let node_a = started_node(config_a).await;
+            let node_b = started_node(loopback_config()).await;
+            let node_c = started_node(loopback_config()).await;
+            let id_b = *node_b.identity.device_id();
+            let id_c = *node_c.identity.device_id();
+
+            let conn_b = add_peer(&node_a, &node_b).await;
+            let (conn_ac, conn_c) = keyed_link(&node_a, &node_c).await;
+            let conn_ac = if remote_c {
+                conn_ac.with_local(false)
+            } else {
+                conn_ac
+            };
+            node_a.peers.write().insert(id_c, Arc::new(conn_ac));
+            Self {
+                node_a,
+                node_b,
+                node_c,
+                id_b,
+                id_c,
+                conn_b,
+                conn_c,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_latency_recorded_between_nodes() {
+        let mut node_a = started_node(loopback_config()).await;
+        let mut node_b = started_node(loopback_config()).await;
+        let id_a = *node_a.identity.device_id();
+        let id_b = *node_b.identity.device_id();
+        let (conn_a, conn_b) = keyed_link(&node_a, &node_b).await;
+
+        // A sends an update, B receives and processes it, acknowledging with latency
+        // Fully populated metadata, since bincode can't round-trip skipped optional fields
+        let mut content = crate::protocol::ClipboardContent::text("hello");
+        content.metadata.filename = Some(String::new());
+        content.metadata.dimensions = Some((0, 0));
+        content.metadata.preview = Some(Vec::new());
+        let update = ClipboardUpdate::new(content);
+        conn_a
+            .send_message(&Message::ClipboardUpdate(update))
+            .await
+            .unwrap();
+        let received = conn_b.receive_message().await.unwrap();
+        node_b.peers.write().insert(id_a, Arc::new(conn_b));
+        node_b.process_message(&id_a, received).await.unwrap();
+
+        // A processes the ack and records the latency
+        let ack = conn_a.receive_message().await.unwrap();
+        assert!(matches!(ack, Message::ClipboardAck(_)));
+        assert_eq!(node_a.last_sync_latency(&id_b), None);
+        node_a.process_message(&id_b, ack).await.unwrap();
+
+        let latency = node_a.last_sync_latency(&id_b).unwrap();
+        assert!(latency < 5_000, "implausible latency: {} ms", latency);
+
+        node_a.stop().await;
+        node_b.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_ping_measures_clock_offset() {
+        let mut node_a = started_node(loopback_config()).await;
+        let mut node_b = started_node(loopback_config()).await;
+        let id_a = *node_a.identity.device_id();
+        let id_b = *node_b.identity.device_id();
+        let (conn_a, conn_b) = link(&node_a, &node_b).await;
+        let conn_a = Arc::new(conn_a);
+        node_a.peers.write().insert(id_b, conn_a.clone());
+
+        // Without a session key the ping can't go out, but the connection stays
+        assert!(node_a.ping_peer(&id_b).await.is_err());
+        assert!(node_a.peers.read().contains_key(&id_b));
+
+        let session_key = [7u8; 32];
+        conn_a.set_session_key(session_key).await;
+        conn_b.set_session_key(session_key).await;
+
+        node_a.ping_peer(&id_b).await.unwrap();
+        let ping = conn_b.receive_message().await.unwrap();
+        assert!(matches!(ping, Message::Ping(_)));
+        node_b.peers.write().insert(id_a, Arc::new(conn_b));
+        node_b.process_message(&id_a, ping).await.unwrap();
+
+        let pong = conn_a.receive_message().await.unwrap();
+        assert!(matches!(pong, Message::Pong(_)));
+        assert_eq!(node_a.latency.clock_offset(&id_b), None);
+        node_a.process_message(&id_b, pong).await.unwrap();
+        // Both nodes share a clock, so the offset is at most the round trip
+        let offset = node_a.latency.clock_offset(&id_b).unwrap();
+        assert!(offset.abs() < 5_000, "implausible offset: {} ms", offset);
+
+        node_a.stop().await;
+        node_b.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_reader_queues_messages_and_completed_transfer_is_acknowledged() {
+        let mut node_a = started_node(loopback_config()).await;
+        let mut node_b = started_node(loopback_config()).await;
+        let id_a = *node_a.identity.device_id();
+        let id_b = *node_b.identity.device_id();
+        let conn_b = add_peer(&node_a, &node_b).await;
+        node_a.spawn_reader(id_b);
+        let mut incoming = node_a.take_incoming().unwrap();
+        assert!(node_a.take_incoming().is_none());
+        let mut events = node_a.subscribe();
+
+        // B sends an update in chunks; A processes what its reader queued
+        let update = ClipboardUpdate::new(crate::protocol::ClipboardContent::text("chunked"));
+        let payload =
+            file_transfer::encode_payload(&Message::ClipboardUpdate(update.clone())).unwrap();
+        let chunks = TransferTracker::new().start_outgoing(id_a, payload, 16);
+        let transfer_id = chunks[0].transfer_id;
+        let total_chunks = chunks.len();
+        for chunk in chunks {
+            conn_b
+                .send_message(&Message::TransferChunk(chunk))
+                .await
+                .unwrap();
+        }
+        for _ in 0..total_chunks {
+            let (from, message) = tokio::time::timeout(Duration::from_secs(5), incoming.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(from, id_b);
+            node_a.process_message(&from, message).await.unwrap();
+        }
+
+        // The sender is told it can release the source, then gets the usual ack
+        assert!(matches!(
+            conn_b.receive_message().await.unwrap(),
+            Message::TransferComplete(complete) if complete.transfer_id == transfer_id
+        ));
+        assert!(matches!(
+            conn_b.receive_message().await.unwrap(),
+            Message::ClipboardAck(_)
+        ));
+        let received = loop {
+            if let NetworkEvent::MessageReceived {
+                message: Message::ClipboardUpdate(received),
+                ..
+            } = events.try_recv().unwrap()
+            {
+                break received;
+            }
+        };
+        assert_eq!(received.content_hash, update.content_hash);
+
+        // Once B goes away, the reader drops the connection
+        conn_b.close();
+        let disconnected = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Ok(NetworkEvent::PeerDisconnected { device_id }) = events.recv().await {
+                    return device_id;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(disconnected, id_b);
+        assert!(!node_a.peers.read().contains_key(&id_b));
+
+        node_a.stop().await;
+        node_b.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_peer_without_rich_text_receives_plain_text() {
+        let mut node_a = started_node(loopback_config()).await;
+        let mut node_b = started_node(loopback_config()).await;
+        let id_a = *node_a.identity.device_id();
+        let id_b = *node_b.identity.device_id();
+        let conn_b = add_peer(&node_a, &node_b).await;
+
+        // B introduces itself without rich text support; A answers with its own info
+        let mut info_b = DeviceInfo::new(id_b, "Device B".to_string());
+        info_b.capabilities.remove(Capabilities::RICH_TEXT);
+        node_a
+            .process_message(&id_b, Message::DeviceInfo(info_b))
+            .await
+            .unwrap();
+        let Message::DeviceInfo(info_a) = conn_b.receive_message().await.unwrap() else {
+            panic!("expected device info in reply");
+        };
+        assert_eq!(info_a.device_id, id_a);
+        assert_eq!(info_a.capabilities, Capabilities::current());
+        assert_eq!(
+            info_a.identity_algorithm,
+            crate::crypto::KeyAlgorithm::Ed25519
+        );
+
+        let capabilities = node_a.peer_capabilities(&id_b).unwrap();
+        assert!(!capabilities.contains(Capabilities::RICH_TEXT));
+
+        // Fully populated metadata, since bincode can't round-trip skipped optional fields
+        let mut content = crate::protocol::ClipboardContent::new(
+            crate::protocol::ContentType::RichText,
+            b"<p>Hello</p>".to_vec(),
+        );
+        content.metadata.filename = Some(String::new());
+        content.metadata.mime_type = Some("text/html".to_string());
+        content.metadata.dimensions = Some((0, 0));
+        content.metadata.preview = Some(Vec::new());
+        content.metadata.text_preview = Some(String::new());
+        node_a
+            .send_to_peer(
+                &id_b,
+                &Message::ClipboardUpdate(ClipboardUpdate::new(content)),
+            )
+            .await
+            .unwrap();
+
+        let Message::ClipboardUpdate(received) = conn_b.receive_message().await.unwrap() else {
+            panic!("expected a clipboard update");
+        };
+        assert_eq!(
+            received.content.content_type,
+            crate::protocol::ContentType::PlainText
+        );
+        assert_eq!(received.content.as_text().unwrap(), "Hello");
+
+        node_a.stop().await;
+        node_b.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_lan_only_mode_reaches_local_peers_only() {
+        let lan_only = NetworkConfig {
+            lan_only: true,
+            ..loopback_config()
+        };
+        // C stands in for a peer reached over the internet
+        let ThreeNodes {
+            mut node_a,
+            mut node_b,
+            mut node_c,
+            id_b,
+            id_c,
+            conn_b,
+            conn_c,
+        } = ThreeNodes::start(lan_only, true).await;
+        assert!(node_a.is_lan_only());
+        assert!(node_a.peers.read()[&id_b].is_local());
+
+        // A relay is configured, but must not be tried
+        let identity_a = node_a.identity.clone();
+        node_a.relay_client = Some(Arc::new(RelayClient::new("http://127.0.0.1:9", identity_a)));
+
+        let message = Message::ClipboardUpdate(ClipboardUpdate::new(
+            crate::protocol::ClipboardContent::text("lan only"),
+        ));
+        let (result, mut outcomes) = node_a.broadcast_with_outcomes(&message).await;
+        assert!(result.is_ok());
+        outcomes.sort_by_key(|outcome| *outcome != SendOutcome::Direct);
+        assert_eq!(
+            outcomes,
+            vec![
+                SendOutcome::Direct,
+                SendOutcome::Failed(FailureCategory::Connection)
+            ]
+        );
+
+        let Message::ClipboardUpdate(received) = conn_b.receive_message().await.unwrap() else {
+            panic!("expected a clipboard update");
+        };
+        assert_eq!(received.content.as_text().unwrap(), "lan only");
+        assert!(
+            tokio::time::timeout(Duration::from_millis(300), conn_c.receive_message())
+                .await
+                .is_err()
+        );
+
+        // Direct sends to the remote peer are refused as well
+        assert!(node_a.send_to_peer(&id_c, &message).await.is_err());
+        assert!(node_a.best_peer_for_pull(&[0u8; 32]).is_none());
+
+        // and go through again once the mode is off
+        node_a.set_lan_only(false);
+        node_a.send_to_peer(&id_c, &message).await.unwrap();
+        assert!(matches!(
+            conn_c.receive_message().await.unwrap(),
+            Message::ClipboardUpdate(_)
+        ));
+
+        node_a.stop().await;
+        node_b.stop().await;
+        node_c.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_send_to_device_reaches_only_that_peer() {
+        let ThreeNodes {
+            mut node_a,
+            mut node_b,
+            mut node_c,
+            id_c,
+            conn_b,
+            conn_c,
+            ..
+        } = ThreeNodes::start(loopback_config(), false).await;
+
+        let message = Message::ClipboardUpdate(ClipboardUpdate::new(
+            crate::protocol::ClipboardContent::text("just for c"),
+        ));
+        let cancel = CancellationToken::new();
+        let (result, outcome) = node_a.send_to_device(&id_c, &message, &cancel).await;
+        assert!(result.is_ok());
+        assert_eq!(outcome, SendOutcome::Direct);
+
+        let Message::ClipboardUpdate(received) = conn_c.receive_message().await.unwrap() else {
+            panic!("expected a clipboard update");
+        };
+        assert_eq!(received.content.as_text().unwrap(), "just for c");
+        assert!(
+            tokio::time::timeout(Duration::from_millis(300), conn_b.receive_message())
+                .await
+                .is_err()
+        );
+
+        // A device that isn't connected, with no relay to fall back on
+        let (result, outcome) = node_a.send_to_device(&[5u8; 32], &message, &cancel).await;
+        assert!(result.is_err());
+        assert!(matches!(outcome, SendOutcome::Failed(_)));
+
+        node_a.stop().await;
+        node_b.stop().await;
+        node_c.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_diagnostics_reflect_manager_state() {
+        let identity_a = Arc::new(DeviceIdentity::generate().unwrap());
+        let mut node_a = NetworkManager::new(identity_a, loopback_config())
+            .await
+            .unwrap();
+
+        // Before starting there is nothing to report
+        let snapshot = node_a.diagnostics(Duration::ZERO).await;
+        assert!(snapshot.local_addr.is_none());
+        assert_eq!(snapshot.mdns, MdnsStatus::Disabled);
+        assert_eq!(snapshot.relay, RelayStatus::NotConfigured);
+        assert!(snapshot.connected_peers.is_empty());
+
+        node_a.start().await.unwrap();
+        let mut node_b = started_node(loopback_config()).await;
+        let id_b = *node_b.identity.device_id();
+        let (conn_ab, _conn_b) = link(&node_a, &node_b).await;
+        let addr_b = conn_ab.remote_addr();
+        node_a.peers.write().insert(id_b, Arc::new(conn_ab));
+
+        let snapshot = node_a.diagnostics(Duration::ZERO).await;
+        assert_eq!(snapshot.local_addr, node_a.local_addr());
+        assert_eq!(snapshot.mdns, MdnsStatus::Disabled);
+        assert!(snapshot.discovered_peers.is_empty());
+        assert_eq!(snapshot.relay, RelayStatus::NotConfigured);
+        assert!(snapshot.relay_url.is_none());
+        assert!(snapshot.candidates.is_empty());
+        assert_eq!(snapshot.nat_type, NatType::Unknown);
+
+        assert_eq!(snapshot.connected_peers.len(), 1);
+        let peer = &snapshot.connected_peers[0];
+        assert_eq!(peer.device_id, id_b);
+        assert_eq!(peer.addresses, vec![addr_b]);
+        assert_eq!(peer.transport, "quic");
+        assert!(peer.is_local);
+
+        node_a.stop().await;
+        node_b.stop().await;
+
+        // A configured relay is reported as unused in LAN-only mode
+        let manager = NetworkManager::new(
+            Arc::new(DeviceIdentity::generate().unwrap()),
+            NetworkConfig {
+                relay_url: Some("http://127.0.0.1:9".to_string()),
+                lan_only: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let snapshot = manager.diagnostics(Duration::ZERO).await;
+        assert_eq!(snapshot.relay, RelayStatus::LanOnly);
+        assert_eq!(snapshot.relay_url.as_deref(), Some("http://127.0.0.1:9"));
+        assert_eq!(snapshot.mdns, MdnsStatus::NotRegistered);
+    }
+
+    #[tokio::test]
+    async fn test_relay_cap_suppresses_remote_sends_only() {
+        // C stands in for a peer reached over the internet
+        let ThreeNodes {
+            mut node_a,
+            mut node_b,
+            mut node_c,
+            id_b,
+            id_c,
+            conn_b,
+            conn_c,
+        } = ThreeNodes::start(loopback_config(), true).await;
+
+        // A relay is configured, but must not be tried for a capped peer
+        let identity_a = node_a.identity.clone();
+        node_a.relay_client = Some(Arc::new(RelayClient::new("http://127.0.0.1:9", identity_a)));
+
+        let message = Message::ClipboardUpdate(ClipboardUpdate::new(
+            crate::protocol::ClipboardContent::text("metered"),
+        ));
+
+        // Only the send to the remote peer is metered
+        let (result, outcomes) = node_a.broadcast_with_outcomes(&message).await;
+        assert!(result.is_ok());
+        assert_eq!(outcomes, vec![SendOutcome::Direct, SendOutcome::Direct]);
+        conn_b.receive_message().await.unwrap();
+        conn_c.receive_message().await.unwrap();
+        let traffic = node_a.take_metered_traffic();
+        assert_eq!(traffic.len(), 1);
+        assert!(traffic[&id_c].sent > 0);
+        assert!(node_a.take_metered_traffic().is_empty());
+
+        // Once both are capped, the local peer is still sent to
+        node_a.set_relay_capped(&id_b, true);
+        node_a.set_relay_capped(&id_c, true);
+        let (result, mut outcomes) = node_a.broadcast_with_outcomes(&message).await;
+        assert!(result.is_ok());
+        outcomes.sort_by_key(|outcome| *outcome != SendOutcome::Direct);
+        assert_eq!(
+            outcomes,
+            vec![
+                SendOutcome::Direct,
+                SendOutcome::Failed(FailureCategory::Connection)
+            ]
+        );
+        assert!(matches!(
+            conn_b.receive_message().await.unwrap(),
+            Message::ClipboardUpdate(_)
+        ));
+        assert!(
+            tokio::time::timeout(Duration::from_millis(300), conn_c.receive_message())
+                .await
+                .is_err()
+        );
+        assert!(node_a.take_metered_traffic().is_empty());
+
+        // Lifting the cap lets sync to the remote peer through again
+        node_a.set_relay_capped(&id_c, false);
+        assert!(!node_a.is_relay_capped(&id_c));
+        let (result, outcomes) = node_a.broadcast_with_outcomes(&message).await;
+        assert!(result.is_ok());
+        assert_eq!(outcomes, vec![SendOutcome::Direct, SendOutcome::Direct]);
+        assert!(matches!(
+            conn_c.receive_message().await.unwrap(),
+            Message::ClipboardUpdate(_)
+        ));
+
+        node_a.stop().await;
+        node_b.stop().await;
+        node_c.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_locked_peer_is_not_synced_with_policy_on() {
+        let config = NetworkConfig {
+            require_peer_unlocked: true,
+            ..loopback_config()
+        };
+        let mut node_a = started_node(config.clone()).await;
+        let mut node_b = started_node(config).await;
+        let id_b = *node_b.identity.device_id();
+        let conn_b = add_peer(&node_a, &node_b).await;
+
+        let update = Message::ClipboardUpdate(ClipboardUpdate::new(
+            crate::protocol::ClipboardContent::text("while locked"),
+        ));
+        let mut events = node_a.subscribe();
+
+        // B reports itself locked
+        node_a
+            .process_message(&id_b, Message::LockState(LockState { locked: true }))
+            .await
+            .unwrap();
+        assert!(node_a.is_peer_locked(&id_b));
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            NetworkEvent::MessageReceived {
+                message: Message::LockState(LockState { locked: true }),
+                ..
+            }
+        ));
+
+        // Nothing is sent to B...
+        let (_, outcomes) = node_a.broadcast_with_outcomes(&update).await;
+        assert_eq!(
+            outcomes,
+            vec![SendOutcome::Failed(FailureCategory::Connection)]
+        );
+        assert!(
+            tokio::time::timeout(Duration::from_millis(300), conn_b.receive_message())
+                .await
+                .is_err()
+        );
+
+        // ...and B's updates are dropped unacknowledged
+        node_a.process_message(&id_b, update.clone()).await.unwrap();
+        assert!(events.try_recv().is_err());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(300), conn_b.receive_message())
+                .await
+                .is_err()
+        );
+
+        // Once B unlocks, sync resumes
+        node_a
+            .process_message(&id_b, Message::LockState(LockState { locked: false }))
+            .await
+            .unwrap();
+        let (_, outcomes) = node_a.broadcast_with_outcomes(&update).await;
+        assert_eq!(outcomes, vec![SendOutcome::Direct]);
+        assert!(matches!(
+            conn_b.receive_message().await.unwrap(),
+            Message::ClipboardUpdate(_)
+        ));
+
+        node_a.stop().await;
+        node_b.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_canceled_broadcast_stops_sending_chunks() {
+        let config = NetworkConfig {
+            file_chunk_size: 16 * 1024,
+            peer_send_timeout: Duration::from_secs(30),
+            ..loopback_config()
+        };
+        let mut node_a = started_node(config.clone()).await;
+        let mut node_b = started_node(config).await;
+        let id_b = *node_b.identity.device_id();
+        let conn_b = add_peer(&node_a, &node_b).await;
+
+        // B advertises resumable transfers, so A sends large content in chunks
+        node_a
+            .process_message(
+                &id_b,
+                Message::DeviceInfo(DeviceInfo::new(id_b, "Device B".to_string())),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            conn_b.receive_message().await.unwrap(),
+            Message::DeviceInfo(_)
+        ));
+
+        // 8 MB in 16 KB chunks; unread streams hold the sender at most ~100
+        // chunks ahead of the receiver
+        let total_chunks = 512;
+        let content = crate::protocol::ClipboardContent::new(
+            crate::protocol::ContentType::File,
+            vec![7u8; total_chunks * 16 * 1024],
+        );
+        let message = Message::ClipboardUpdate(ClipboardUpdate::new(content));
+
+        let cancel = CancellationToken::new();
+        let receiver = async {
+            let mut chunks = 0;
+            loop {
+                match conn_b.receive_message().await.unwrap() {
+                    Message::TransferChunk(_) => {
+                        chunks += 1;
+                        // Give up on the send once it is under way
+                        if chunks == 1 {
+                            cancel.cancel();
+                        }
+                    }
+                    Message::TransferCancel(_) => return chunks,
+                    other => panic!("unexpected {:?}", other.header().message_type),
+                }
+            }
+        };
+        let ((result, outcomes), chunks) = tokio::time::timeout(Duration::from_secs(10), async {
+            tokio::join!(node_a.broadcast_cancellable(&message, &cancel), receiver)
+        })
+        .await
+        .expect("canceled send finishes promptly");
+
+        assert!(matches!(result, Err(NetworkError::Cancelled)));
+        assert_eq!(outcomes, vec![SendOutcome::Failed(FailureCategory::Other)]);
+        assert!(chunks < total_chunks / 2, "{} chunks sent", chunks);
+        // The sender dropped the transfer, and the peer's circuit is untouched
+        assert!(node_a.active_transfers().is_empty());
+        assert!(node_a.breaker.allow(&id_b));
+
+        node_a.stop().await;
+        node_b.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_identity_migration_signed_by_old_key_is_accepted() {
+        let old_identity = DeviceIdentity::generate().unwrap();
+        let new_identity = DeviceIdentity::generate().unwrap();
+        let stranger = DeviceIdentity::generate().unwrap();
+        let old_id = *old_identity.device_id();
+        let old_public_key = old_identity.public_key();
+
+        // The peer knows the migrating device only by its old key
+        let config = NetworkConfig {
+            enable_mdns: false,
+            enable_nat_discovery: false,
+            ..Default::default()
+        };
+        let peer = NetworkManager::builder()
+            .identity(Arc::new(DeviceIdentity::generate().unwrap()))
+            .config(config)
+            .public_key_fn(move |device_id| (*device_id == old_id).then_some(old_public_key))
+            .build()
+            .await
+            .unwrap();
+        let mut events = peer.subscribe();
+
+        // A migration signed by someone else's key is refused and not passed on
+        let forged = IdentityMigration::new(&stranger, &new_identity);
+        assert!(peer
+            .process_message(&old_id, Message::IdentityMigration(forged))
+            .await
+            .is_err());
+        assert!(events.try_recv().is_err());
+
+        let migration = IdentityMigration::new(&old_identity, &new_identity);
+        assert_eq!(migration.new_device_id(), *new_identity.device_id());
+        peer.process_message(&old_id, Message::IdentityMigration(migration))
+            .await
+            .unwrap();
+        match events.try_recv().unwrap() {
+            NetworkEvent::MessageReceived {
+                from_device_id,
+                message: Message::IdentityMigration(received),
+            } => {
+                assert_eq!(from_device_id, old_id);
+                assert_eq!(received.new_public_key, new_identity.public_key());
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unencrypted_message_in_encrypted_session_drops_peer() {
+        // A alone would accept plaintext; B's advertised minimum raises the session
+        let config = NetworkConfig {
+            min_security_level: SecurityLevel::Unencrypted,
+            ..loopback_config()
+        };
+        let mut node_a = started_node(config.clone()).await;
+        let mut node_b = started_node(config).await;
+        let id_b = *node_b.identity.device_id();
+        let conn_b = add_peer(&node_a, &node_b).await;
+
+        let info_b = DeviceInfo::new(id_b, "Device B".to_string())
+            .with_min_security(SecurityLevel::Encrypted);
+        node_a
+            .process_message(&id_b, Message::DeviceInfo(info_b))
+            .await
+            .unwrap();
+        let Message::DeviceInfo(info_a) = conn_b.receive_message().await.unwrap() else {
+            panic!("expected device info in reply");
+        };
+        assert_eq!(info_a.min_security, SecurityLevel::Unencrypted);
+        assert_eq!(node_a.security.required(&id_b), SecurityLevel::Encrypted);
+
+        // Encrypted traffic passes; an injected plaintext message drops the peer
+        let mut events = node_a.subscribe();
+        assert!(verify_session_security(
+            &node_a.security,
+            &node_a.peers,
+            &node_a.event_tx,
+            &id_b,
+            SecurityLevel::Encrypted,
+        ));
+        assert!(!verify_session_security(
+            &node_a.security,
+            &node_a.peers,
+            &node_a.event_tx,
+            &id_b,
+            SecurityLevel::Unencrypted,
+        ));
+        assert!(!node_a.peers.read().contains_key(&id_b));
+
+        match events.try_recv().unwrap() {
+            NetworkEvent::Error(message) => assert!(message.contains("downgrade")),
+            other => panic!("expected an error event, got {:?}", other),
+        }
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            NetworkEvent::PeerDisconnected { device_id } if device_id == id_b
+        ));
+
+        node_a.stop().await;
+        node_b.stop().await;
+    }
+
+    /// Carry a key check from A's connection to B and B's reply back to A
+    async fn exchange_key_check(
+        node_a: &NetworkManager,
+        node_b: &NetworkManager,
+        a_inbox: &PeerConnection,
+        b_inbox: &PeerConnection,
+    ) {
+        let challenge = b_inbox.receive_message().await.unwrap();
+        assert!(matches!(challenge, Message::KeyCheck(_)));
+        node_b
+            .process_message(node_a.identity.device_id(), challenge)
+            .await
+            .unwrap();
+        let reply = a_inbox.receive_message().await.unwrap();
+        assert!(matches!(reply, Message::KeyCheckReply(_)));
+        node_a
+            .process_message(node_b.identity.device_id(), reply)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_key_check_detects_mismatched_session_key() {
+        let config = loopback_config();
+        let identity_a = Arc::new(DeviceIdentity::generate().unwrap());
+        let identity_b = Arc::new(DeviceIdentity::generate().unwrap());
+        let id_b = *identity_b.device_id();
+
+        // B's stored key for A can be swapped out, as a botched re-pair might
+        let stored_b = Arc::new(parking_lot::Mutex::new([5u8; 32]));
+        let key_b = stored_b.clone();
+        let get_key_a: GetSessionKeyFn = Box::new(|_: &[u8; 32]| Some([5u8; 32]));
+        let get_key_b: GetSessionKeyFn = Box::new(move |_: &[u8; 32]| Some(*key_b.lock()));
+        let mut node_a = NetworkManager::new_with_callbacks(
+            identity_a,
+            config.clone(),
+            None,
+            Some(Arc::new(get_key_a)),
+        )
+        .await
+        .unwrap();
+        let mut node_b =
+            NetworkManager::new_with_callbacks(identity_b, config, None, Some(Arc::new(get_key_b)))
+                .await
+                .unwrap();
+        node_a.start().await.unwrap();
+        node_b.start().await.unwrap();
+
+        // One connection each way; the test reads the receiving ends
+        let b_inbox = add_peer(&node_a, &node_b).await;
+        let a_inbox = add_peer(&node_b, &node_a).await;
+
+        // Without B's capabilities A can't tell whether B would answer
+        assert!(node_a
+            .verify_session_key(&id_b, Duration::from_millis(100))
+            .await
+            .is_err());
+        node_a
+            .process_message(
+                &id_b,
+                Message::DeviceInfo(DeviceInfo::new(id_b, "Device B".to_string())),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            b_inbox.receive_message().await.unwrap(),
+            Message::DeviceInfo(_)
+        ));
+
+        let (verified, ()) = tokio::join!(
+            node_a.verify_session_key(&id_b, KEY_CHECK_TIMEOUT),
+            exchange_key_check(&node_a, &node_b, &a_inbox, &b_inbox)
+        );
+        assert!(verified.unwrap());
+
+        *stored_b.lock() = [6u8; 32];
+        let (verified, ()) = tokio::join!(
+            node_a.verify_session_key(&id_b, KEY_CHECK_TIMEOUT),
+            exchange_key_check(&node_a, &node_b, &a_inbox, &b_inbox)
+        );
+        assert!(!verified.unwrap());
+
+        node_a.stop().await;
+        node_b.stop().await;
+    }
+
     #[test]
     fn test_network_config_with_relay() {
         let config = NetworkConfig {
@@ -136,6 +136,20 @@ pub enum NatType {
     Unknown,
 }
 
+impl NatType {
+    /// Stable lowercase name used in API DTOs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NatType::None => "none",
+            NatType::FullCone => "full_cone",
+            NatType::RestrictedCone => "restricted_cone",
+            NatType::PortRestrictedCone => "port_restricted_cone",
+            NatType::Symmetric => "symmetric",
+            NatType::Unknown => "unknown",
+        }
+    }
+}
+
 /// Result of STUN binding discovery
 #[derive(Debug, Clone)]
 pub struct StunBinding {
@@ -1134,6 +1148,7 @@ impl TurnClient {
 }
 
 /// ICE-like candidate gathering for connection establishment
+#[derive(Debug, Clone)]
 pub struct IceCandidate {
     /// Candidate type
     pub candidate_type: CandidateType,
@@ -1153,6 +1168,38 @@ pub enum CandidateType {
     Relay,
 }
 
+impl CandidateType {
+    /// Stable lowercase name used in API DTOs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CandidateType::Host => "host",
+            CandidateType::ServerReflexive => "server_reflexive",
+            CandidateType::Relay => "relay",
+        }
+    }
+}
+
+/// NAT type implied by gathered candidates
+///
+/// There is no NAT when STUN saw one of our host addresses. Otherwise the
+/// type is unknown: telling the cone types apart takes more than one binding
+/// request.
+pub fn nat_type_of(candidates: &[IceCandidate]) -> NatType {
+    let is_host = |addr: &SocketAddr| {
+        candidates
+            .iter()
+            .any(|c| c.candidate_type == CandidateType::Host && c.address == *addr)
+    };
+    let unmapped = candidates
+        .iter()
+        .any(|c| c.candidate_type == CandidateType::ServerReflexive && is_host(&c.address));
+    if unmapped {
+        NatType::None
+    } else {
+        NatType::Unknown
+    }
+}
+
 /// Gather ICE candidates for connection establishment
 pub async fn gather_candidates(
     local_addr: SocketAddr,
@@ -1227,6 +1274,22 @@ mod tests {
         assert!(!client.can_connect_directly(NatType::Symmetric));
     }
 
+    #[test]
+    fn test_nat_type_of_candidates() {
+        let candidate = |candidate_type, address: &str| IceCandidate {
+            candidate_type,
+            address: address.parse().unwrap(),
+            priority: 0,
+        };
+        let host = candidate(CandidateType::Host, "203.0.113.5:4000");
+
+        assert_eq!(nat_type_of(std::slice::from_ref(&host)), NatType::Unknown);
+        let unmapped = candidate(CandidateType::ServerReflexive, "203.0.113.5:4000");
+        assert_eq!(nat_type_of(&[host.clone(), unmapped]), NatType::None);
+        let mapped = candidate(CandidateType::ServerReflexive, "198.51.100.7:61000");
+        assert_eq!(nat_type_of(&[host, mapped]), NatType::Unknown);
+    }
+
     #[tokio::test]
     async fn test_gather_candidates_host_only() {
         let local_addr: SocketAddr = "127.0.0.1:12345".parse().unwrap();
@@ -0,0 +1,125 @@
+//! Rate limiting for presence hints
+//!
+//! Presence ("Laptop is copying…") is only a UX hint, so it is throttled in
+//! both directions. A state is passed on only when it differs from the last
+//! one, and `Copying` at most once per interval. A following `Idle` always
+//! gets through so a peer is never left showing a stale `Copying`, which caps
+//! traffic at two messages per interval.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::protocol::PresenceState;
+
+/// Default minimum time between two `Copying` hints from one device
+pub const DEFAULT_PRESENCE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Last presence passed on for one device
+#[derive(Debug, Clone, Copy, Default)]
+struct PresenceTrack {
+    state: PresenceState,
+    copying_at: Option<Instant>,
+}
+
+impl PresenceTrack {
+    /// Record `state` if it may be passed on at `now`
+    fn admit(&mut self, state: PresenceState, now: Instant, interval: Duration) -> bool {
+        if state == self.state {
+            return false;
+        }
+        if state == PresenceState::Copying {
+            if self
+                .copying_at
+                .is_some_and(|at| now.saturating_duration_since(at) < interval)
+            {
+                return false;
+            }
+            self.copying_at = Some(now);
+        }
+        self.state = state;
+        true
+    }
+}
+
+/// Decides which presence hints to send and which received ones to surface
+pub struct PresenceThrottle {
+    interval: Duration,
+    sent: Mutex<PresenceTrack>,
+    received: Mutex<HashMap<[u8; 32], PresenceTrack>>,
+}
+
+impl PresenceThrottle {
+    /// Create a throttle allowing one `Copying` hint per device per `interval`
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            sent: Mutex::new(PresenceTrack::default()),
+            received: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether to send our `state` now; records it as sent if so
+    pub fn allow_send(&self, state: PresenceState, now: Instant) -> bool {
+        self.sent.lock().admit(state, now, self.interval)
+    }
+
+    /// Whether to surface a peer's `state` now; records it if so
+    pub fn allow_receive(&self, device_id: &[u8; 32], state: PresenceState, now: Instant) -> bool {
+        self.received
+            .lock()
+            .entry(*device_id)
+            .or_default()
+            .admit(state, now, self.interval)
+    }
+
+    /// Forget a peer's presence, e.g. once it disconnects
+    pub fn forget(&self, device_id: &[u8; 32]) {
+        self.received.lock().remove(device_id);
+    }
+}
+
+impl Default for PresenceThrottle {
+    fn default() -> Self {
+        Self::new(DEFAULT_PRESENCE_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copying_is_throttled_and_idle_follows() {
+        let throttle = PresenceThrottle::new(Duration::from_secs(5));
+        let start = Instant::now();
+
+        // Idle is the starting state, so it isn't sent first
+        assert!(!throttle.allow_send(PresenceState::Idle, start));
+        assert!(throttle.allow_send(PresenceState::Copying, start));
+        assert!(!throttle.allow_send(PresenceState::Copying, start));
+
+        // Idle always follows, but copying again has to wait out the interval
+        assert!(throttle.allow_send(PresenceState::Idle, start + Duration::from_secs(1)));
+        assert!(!throttle.allow_send(PresenceState::Copying, start + Duration::from_secs(2)));
+        assert!(throttle.allow_send(PresenceState::Copying, start + Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_received_presence_is_throttled_per_device() {
+        let throttle = PresenceThrottle::new(Duration::from_secs(5));
+        let start = Instant::now();
+        let (laptop, phone) = ([1u8; 32], [2u8; 32]);
+
+        assert!(throttle.allow_receive(&laptop, PresenceState::Copying, start));
+        assert!(throttle.allow_receive(&phone, PresenceState::Copying, start));
+        assert!(!throttle.allow_receive(&laptop, PresenceState::Copying, start));
+        assert!(throttle.allow_receive(&laptop, PresenceState::Idle, start));
+        assert!(!throttle.allow_receive(&laptop, PresenceState::Copying, start));
+
+        // A forgotten peer starts over from idle
+        throttle.forget(&laptop);
+        assert!(!throttle.allow_receive(&laptop, PresenceState::Idle, start));
+        assert!(throttle.allow_receive(&laptop, PresenceState::Copying, start));
+    }
+}
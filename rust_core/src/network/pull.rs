@@ -0,0 +1,136 @@
+//! Choosing which peer to pull content from
+//!
+//! Peers are known to hold the content they last sent us. When several
+//! hold the content being pulled, a direct connection on the local network
+//! is preferred over a remote direct connection, which is preferred over the
+//! relay; within the same route, the lowest measured round-trip time wins.
+
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::protocol::Message;
+
+/// How a pull candidate can be reached, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PullRoute {
+    /// Direct connection on the local network
+    Local,
+    /// Direct connection across networks
+    Direct,
+    /// Through the relay server
+    Relay,
+}
+
+/// A peer that holds the content being pulled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PullCandidate {
+    pub device_id: [u8; 32],
+    pub route: PullRoute,
+    /// Measured round-trip time, if known
+    pub rtt: Option<Duration>,
+}
+
+/// Pick the best candidate: best route first, then lowest RTT
+///
+/// Candidates without a measured RTT rank after those with one.
+pub fn choose_pull_peer(candidates: &[PullCandidate]) -> Option<[u8; 32]> {
+    candidates
+        .iter()
+        .min_by_key(|c| (c.route, c.rtt.unwrap_or(Duration::MAX)))
+        .map(|c| c.device_id)
+}
+
+/// Tracks the content hash each peer last sent
+#[derive(Default)]
+pub struct ContentSources {
+    latest: RwLock<HashMap<[u8; 32], [u8; 32]>>,
+}
+
+impl ContentSources {
+    /// Create an empty tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the content of a clipboard update received from a peer
+    ///
+    /// Content is tracked by its plain hash, which never leaves this device,
+    /// so updates identified by a keyed hash are matched too.
+    pub fn observe(&self, device_id: &[u8; 32], message: &Message) {
+        if let Message::ClipboardUpdate(update) = message {
+            self.latest
+                .write()
+                .insert(*device_id, update.content.hash());
+        }
+    }
+
+    /// Peers whose latest content has the given hash
+    pub fn peers_with(&self, content_hash: &[u8; 32]) -> Vec<[u8; 32]> {
+        self.latest
+            .read()
+            .iter()
+            .filter(|(_, hash)| *hash == content_hash)
+            .map(|(device_id, _)| *device_id)
+            .collect()
+    }
+
+    /// Forget what a peer holds
+    pub fn remove(&self, device_id: &[u8; 32]) {
+        self.latest.write().remove(device_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{ClipboardContent, ClipboardUpdate};
+
+    #[test]
+    fn test_local_and_faster_peer_preferred() {
+        let near = [1u8; 32];
+        let far = [2u8; 32];
+        let sources = ContentSources::new();
+        let update = ClipboardUpdate::new(ClipboardContent::text("shared"));
+        for device_id in [near, far] {
+            sources.observe(&device_id, &Message::ClipboardUpdate(update.clone()));
+        }
+        let mut holders = sources.peers_with(&update.content_hash);
+        holders.sort();
+        assert_eq!(holders, vec![near, far]);
+
+        // A local connection wins over the relay even with a worse RTT
+        let candidates = [
+            PullCandidate {
+                device_id: far,
+                route: PullRoute::Relay,
+                rtt: Some(Duration::from_millis(5)),
+            },
+            PullCandidate {
+                device_id: near,
+                route: PullRoute::Local,
+                rtt: Some(Duration::from_millis(40)),
+            },
+        ];
+        assert_eq!(choose_pull_peer(&candidates), Some(near));
+
+        // On the same route the faster peer wins
+        let candidates = [
+            PullCandidate {
+                device_id: near,
+                route: PullRoute::Local,
+                rtt: Some(Duration::from_millis(40)),
+            },
+            PullCandidate {
+                device_id: far,
+                route: PullRoute::Local,
+                rtt: Some(Duration::from_millis(3)),
+            },
+        ];
+        assert_eq!(choose_pull_peer(&candidates), Some(far));
+
+        // Nobody holds other content
+        assert!(sources.peers_with(&[0u8; 32]).is_empty());
+        assert_eq!(choose_pull_peer(&[]), None);
+    }
+}
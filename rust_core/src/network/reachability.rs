@@ -0,0 +1,107 @@
+//! Reachability probing for paired devices
+//!
+//! Tells which paired devices could be reached right now without opening a
+//! QUIC connection. A live connection counts first, then an answer to an mDNS
+//! browse, then the relay reporting the device online. Each probe is
+//! time-boxed so a quiet network or a slow relay can't stall the device list.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use super::discovery::ADVERTISED_ID_LEN;
+
+/// Default time allowed for one reachability probe
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How a device can currently be reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReachabilityPath {
+    /// Already connected directly
+    Connected,
+    /// Seen on the local network via mDNS
+    Local,
+    /// Reported online by the relay
+    Relay,
+}
+
+impl ReachabilityPath {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReachabilityPath::Connected => "connected",
+            ReachabilityPath::Local => "local",
+            ReachabilityPath::Relay => "relay",
+        }
+    }
+}
+
+/// Probe result for one device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceReachability {
+    pub device_id: [u8; 32],
+    /// Best path found, `None` if the device is unreachable
+    pub path: Option<ReachabilityPath>,
+}
+
+impl DeviceReachability {
+    pub fn is_reachable(&self) -> bool {
+        self.path.is_some()
+    }
+}
+
+/// What one probe saw on each path
+#[derive(Debug, Clone, Default)]
+pub struct ProbeSightings {
+    /// Devices with a live connection
+    pub connected: HashSet<[u8; 32]>,
+    /// Truncated device ids advertised over mDNS
+    pub local: HashSet<String>,
+    /// Devices the relay reports online
+    pub relay: HashSet<[u8; 32]>,
+}
+
+impl ProbeSightings {
+    /// Best path to `device_id` among the sightings
+    pub fn reachability(&self, device_id: &[u8; 32]) -> DeviceReachability {
+        let path = if self.connected.contains(device_id) {
+            Some(ReachabilityPath::Connected)
+        } else if self
+            .local
+            .contains(&hex::encode(device_id)[..ADVERTISED_ID_LEN])
+        {
+            Some(ReachabilityPath::Local)
+        } else if self.relay.contains(device_id) {
+            Some(ReachabilityPath::Relay)
+        } else {
+            None
+        };
+        DeviceReachability {
+            device_id: *device_id,
+            path,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sightings_report_best_path() {
+        let (connected, nearby, remote, offline) = ([1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32]);
+        let mut sightings = ProbeSightings::default();
+        sightings.connected.insert(connected);
+        // mDNS only carries a truncated id
+        sightings
+            .local
+            .insert(hex::encode(nearby)[..ADVERTISED_ID_LEN].to_string());
+        sightings.relay.insert(remote);
+        // A device seen both locally and on the relay is reported as local
+        sightings.relay.insert(nearby);
+
+        let path = |id: &[u8; 32]| sightings.reachability(id).path;
+        assert_eq!(path(&connected), Some(ReachabilityPath::Connected));
+        assert_eq!(path(&nearby), Some(ReachabilityPath::Local));
+        assert_eq!(path(&remote), Some(ReachabilityPath::Relay));
+        assert!(!sightings.reachability(&offline).is_reachable());
+    }
+}
@@ -1,28 +1,141 @@
 //! Relay server client for remote clipboard sync
 
 use futures_util::{SinkExt, StreamExt};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
-use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::{
+    self,
+    client::IntoClientRequest,
+    error::{ProtocolError, SubProtocolError},
+    Message as WsMessage,
+};
 
-use crate::crypto::DeviceIdentity;
+use super::relay_frame::{self, BINARY_SUBPROTOCOL};
+use crate::crypto::{DeviceIdentity, KeyAlgorithm};
 use crate::error::NetworkError;
 
+/// Timeout for a single relay send attempt
+const RELAY_SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of attempts for a relay send
+const RELAY_SEND_MAX_ATTEMPTS: u32 = 3;
+
+/// Time the relay holds a long-poll request open waiting for messages
+const LONG_POLL_TIMEOUT_SECS: u64 = 30;
+
+/// Extra client-side allowance on top of the long-poll wait
+const LONG_POLL_GRACE: Duration = Duration::from_secs(10);
+
+/// Prefix of the message the relay signs to prove its identity
+const IDENTITY_CHALLENGE_PREFIX: &str = "relay-identity:";
+
+/// Prefix of the message the relay signs to prove its identity within a session
+const SESSION_CHALLENGE_PREFIX: &str = "relay-session:";
+
+/// Prefix of the bytes a sender signs for each relayed message
+const MESSAGE_SIGNATURE_PREFIX: &[u8] = b"relay-message:";
+
+/// Oldest relayed message accepted, as long as the relay queues messages
+pub const RELAY_MESSAGE_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// How far ahead of our clock a relayed message's `signed_at` may be
+const RELAY_MESSAGE_MAX_SKEW: Duration = Duration::from_secs(300);
+
+/// Reconnection schedule for the relay connection
+///
+/// Used both for the initial connection and for reconnecting after the
+/// connection drops. Failed attempt `n` (counting from 1) is followed by a
+/// delay of `initial * multiplier^(n - 1)`, capped at `max`. Up to `jitter` of
+/// each delay is taken off at random so clients don't retry in lockstep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RelayBackoff {
+    /// Delay after the first failed attempt
+    pub initial: Duration,
+    /// Upper bound for the delay between attempts
+    pub max: Duration,
+    /// Factor the delay grows by after each failed attempt
+    pub multiplier: f64,
+    /// Fraction of each delay that may be taken off at random (0.0 to 1.0)
+    pub jitter: f64,
+    /// Consecutive failed attempts after which the client gives up (0 = never)
+    pub max_attempts: u32,
+}
+
+impl Default for RelayBackoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(120),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: 10,
+        }
+    }
+}
+
+impl RelayBackoff {
+    /// Delay after failed attempt `attempt`, before jitter
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = self.initial.as_secs_f64() * self.multiplier.max(1.0).powi(exponent);
+        Duration::from_secs_f64(delay.min(self.max.as_secs_f64()))
+    }
+
+    /// Delay after failed attempt `attempt`, less `random` (in `[0, 1)`) of the jitter
+    pub fn jittered_delay(&self, attempt: u32, random: f64) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0) * random.clamp(0.0, 1.0);
+        self.delay(attempt).mul_f64(1.0 - jitter)
+    }
+
+    /// Whether failed attempt `attempt` is followed by another one
+    pub fn retries_after(&self, attempt: u32) -> bool {
+        self.max_attempts == 0 || attempt < self.max_attempts
+    }
+}
+
+/// Whether a receive error means the relay connection is gone
+///
+/// Other errors concern a single message and leave the connection usable.
+pub fn is_connection_lost(error: &NetworkError) -> bool {
+    matches!(
+        error,
+        NetworkError::ConnectionClosed | NetworkError::ConnectionFailed(_) | NetworkError::Timeout
+    )
+}
+
 /// Relay client for connecting to remote relay server
+///
+/// Uses a WebSocket when possible and falls back to HTTP long-polling when
+/// the upgrade fails (e.g. behind proxies that block WebSockets). Over the
+/// WebSocket, relay messages are binary frames if the relay supports them.
 pub struct RelayClient {
     url: String,
     identity: Arc<DeviceIdentity>,
+    device_name: String,
     ws: Mutex<Option<WebSocketConnection>>,
+    /// Whether the relay accepted binary frames on the WebSocket
+    binary: AtomicBool,
     auth_token: Mutex<Option<String>>,
+    http_client: reqwest::Client,
+    long_poll: AtomicBool,
+    pending: Mutex<VecDeque<RelayMessage>>,
+    /// Cursor of the last poll response, acknowledged by the next poll
+    poll_cursor: Mutex<Option<i64>>,
+    pinned_key: Option<[u8; 32]>,
+    backoff: RelayBackoff,
 }
 
 type WebSocketConnection =
     tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
 
 /// Registration request
-#[allow(dead_code)]
 #[derive(Debug, Serialize)]
 struct RegisterRequest {
     device_id: String,
@@ -30,6 +143,8 @@ struct RegisterRequest {
     device_name: String,
     timestamp: u64,
     signature: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    challenge: Option<String>,
 }
 
 /// Registration response
@@ -38,117 +153,535 @@ struct RegisterRequest {
 struct RegisterResponse {
     token: String,
     expires_at: u64,
+    #[serde(default)]
+    identity_signature: Option<String>,
 }
 
 /// Relay message wrapper
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RelayMessage {
+    /// Message id, stable across retries of the same send
+    #[serde(default)]
+    pub id: String,
     pub from_device: String,
     pub to_device: String,
     pub encrypted_payload: String,
     pub timestamp: u64,
+    /// Sender's send time (Unix milliseconds), covered by `signature`
+    #[serde(default)]
+    pub signed_at: Option<u64>,
+    /// Sender's signature (base64), see [`message_signing_bytes`]
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+impl RelayMessage {
+    /// Check the sender's signature and return the decoded payload
+    ///
+    /// `recipient` is our own device id (hex) and `now` the current Unix time
+    /// in milliseconds. Fails for unsigned messages, for messages signed more
+    /// than [`RELAY_MESSAGE_MAX_AGE`] ago or in the future, and for messages
+    /// the relay altered, redirected or attributed to a sender other than the
+    /// holder of `sender_public_key`.
+    pub fn verify_sender(
+        &self,
+        algorithm: KeyAlgorithm,
+        sender_public_key: &[u8; 32],
+        recipient: &str,
+        now: u64,
+    ) -> Result<Vec<u8>, NetworkError> {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let payload = base64::Engine::decode(&engine, &self.encrypted_payload)
+            .map_err(|_| NetworkError::Relay("Invalid relay payload encoding".to_string()))?;
+        let (Some(signed_at), Some(signature)) = (self.signed_at, self.signature.as_deref()) else {
+            return Err(NetworkError::Relay(
+                "Relay message is not signed".to_string(),
+            ));
+        };
+        let signature: [u8; 64] = base64::Engine::decode(&engine, signature)
+            .ok()
+            .and_then(|s| s.try_into().ok())
+            .ok_or_else(|| NetworkError::Relay("Invalid relay message signature".to_string()))?;
+
+        if signed_at > now.saturating_add(RELAY_MESSAGE_MAX_SKEW.as_millis() as u64)
+            || now.saturating_sub(signed_at) > RELAY_MESSAGE_MAX_AGE.as_millis() as u64
+        {
+            return Err(NetworkError::Relay(
+                "Relay message is outside the accepted time window".to_string(),
+            ));
+        }
+
+        let message = message_signing_bytes(recipient, signed_at, &payload);
+        if !DeviceIdentity::verify_from_public_key(
+            algorithm,
+            sender_public_key,
+            &message,
+            &signature,
+        ) {
+            return Err(NetworkError::Relay(
+                "Relay message signature is invalid".to_string(),
+            ));
+        }
+        Ok(payload)
+    }
+}
+
+/// Newest relayed message accepted from each sender
+///
+/// A sender's `signed_at` only moves forward, so a message signed before the
+/// newest one accepted from it is a replay. Messages signed in the same
+/// millisecond are told apart by their signatures.
+#[derive(Debug, Default)]
+pub struct RelayReplayGuard {
+    newest: HashMap<[u8; 32], (u64, Vec<String>)>,
+}
+
+impl RelayReplayGuard {
+    /// Record a verified message from `sender`, returning false for a replay
+    pub fn accept(&mut self, sender: &[u8; 32], signed_at: u64, signature: &str) -> bool {
+        let (newest, signatures) = self.newest.entry(*sender).or_default();
+        if signed_at < *newest {
+            return false;
+        }
+        if signed_at > *newest {
+            *newest = signed_at;
+            signatures.clear();
+        } else if signatures.iter().any(|s| s == signature) {
+            return false;
+        }
+        signatures.push(signature.to_string());
+        true
+    }
+}
+
+/// Bytes a sender signs for a relayed message
+///
+/// Covers the ciphertext, the recipient and the send time. The relay only
+/// vouches for the sender's id, so without this it could forge it.
+pub fn message_signing_bytes(to_device: &str, signed_at: u64, payload: &[u8]) -> Vec<u8> {
+    let mut message =
+        Vec::with_capacity(MESSAGE_SIGNATURE_PREFIX.len() + to_device.len() + 9 + payload.len());
+    message.extend_from_slice(MESSAGE_SIGNATURE_PREFIX);
+    message.extend_from_slice(to_device.as_bytes());
+    message.push(b':');
+    message.extend_from_slice(&signed_at.to_be_bytes());
+    message.extend_from_slice(payload);
+    message
+}
+
+/// A signed send, encoded for whichever transport carries it
+struct SendRequest<'a> {
+    id: &'a str,
+    to_device: &'a str,
+    encrypted_payload: &'a [u8],
+    signed_at: u64,
+    signature: &'a [u8],
+}
+
+impl SendRequest<'_> {
+    /// JSON `send` request, with the payload and signature base64 encoded
+    fn to_json(&self) -> String {
+        let engine = base64::engine::general_purpose::STANDARD;
+        serde_json::json!({
+            "type": "send",
+            "id": self.id,
+            "to_device": self.to_device,
+            "encrypted_payload": base64::Engine::encode(&engine, self.encrypted_payload),
+            "signed_at": self.signed_at,
+            "signature": base64::Engine::encode(&engine, self.signature),
+        })
+        .to_string()
+    }
+}
+
+/// Long-poll response
+#[derive(Debug, Deserialize)]
+struct PollResponse {
+    messages: Vec<RelayMessage>,
+    /// Position of the last message; the relay keeps it queued until acknowledged
+    #[serde(default)]
+    cursor: Option<i64>,
+}
+
+/// Device status response
+#[derive(Debug, Deserialize)]
+struct DeviceStatusResponse {
+    is_online: bool,
+}
+
+/// Messages the relay holds for this device until it connects
+#[derive(Debug, Clone, Deserialize)]
+pub struct RelayQueueStatus {
+    pub count: u64,
+    /// When the oldest message was queued (unix seconds)
+    pub oldest_queued_at: Option<i64>,
+}
+
+/// Queue flush response
+#[derive(Debug, Deserialize)]
+struct FlushQueueResponse {
+    deleted: u64,
+}
+
+/// Relay identity response
+#[derive(Debug, Deserialize)]
+struct IdentityResponse {
+    public_key: String,
+    signature: Option<String>,
 }
 
 impl RelayClient {
     /// Create a new relay client
     pub fn new(url: &str, identity: Arc<DeviceIdentity>) -> Self {
+        Self::new_with_device_name(url, identity, "Toss Device")
+    }
+
+    /// Create a new relay client with the name used when registering over HTTP
+    pub fn new_with_device_name(
+        url: &str,
+        identity: Arc<DeviceIdentity>,
+        device_name: &str,
+    ) -> Self {
         Self {
             url: url.to_string(),
             identity,
+            device_name: device_name.to_string(),
             ws: Mutex::new(None),
+            binary: AtomicBool::new(false),
             auth_token: Mutex::new(None),
+            http_client: reqwest::Client::new(),
+            long_poll: AtomicBool::new(false),
+            pending: Mutex::new(VecDeque::new()),
+            poll_cursor: Mutex::new(None),
+            pinned_key: None,
+            backoff: RelayBackoff::default(),
         }
     }
 
+    /// Only connect to a relay that proves it holds `pinned_key`
+    pub fn with_pinned_key(mut self, pinned_key: Option<[u8; 32]>) -> Self {
+        self.pinned_key = pinned_key;
+        self
+    }
+
+    /// Retry connecting on this schedule
+    pub fn with_backoff(mut self, backoff: RelayBackoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// The reconnection schedule
+    pub fn backoff(&self) -> &RelayBackoff {
+        &self.backoff
+    }
+
+    /// Fetch the relay's identity key and check it signed a fresh challenge
+    pub async fn fetch_identity(&self) -> Result<[u8; 32], NetworkError> {
+        let mut nonce = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let challenge = hex::encode(nonce);
+
+        let response = self
+            .http_client
+            .get(format!(
+                "{}/api/v1/identity?challenge={}",
+                self.url, challenge
+            ))
+            .timeout(RELAY_SEND_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| NetworkError::Relay(format!("HTTP connection failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(NetworkError::Relay(format!(
+                "Identity request failed: {}",
+                response.status()
+            )));
+        }
+
+        let identity: IdentityResponse = response
+            .json()
+            .await
+            .map_err(|e| NetworkError::Relay(format!("Invalid identity response: {}", e)))?;
+
+        verify_identity(&identity, &challenge)
+    }
+
     /// Connect to the relay server
+    ///
+    /// Falls back to HTTP long-polling if the WebSocket upgrade fails. With a
+    /// pinned key, the relay must sign a fresh challenge while authenticating
+    /// us on the same connection (or registration request).
     pub async fn connect(&self) -> Result<(), NetworkError> {
         let ws_url = format!("{}/api/v1/ws", self.url.replace("http", "ws"));
 
-        let (ws_stream, _) = connect_async(&ws_url)
-            .await
-            .map_err(|e| NetworkError::Relay(format!("WebSocket connection failed: {}", e)))?;
+        let connected = match Self::connect_ws(&ws_url, true).await {
+            // Relays predating binary frames don't select a subprotocol
+            Err(tungstenite::Error::Protocol(ProtocolError::SecWebSocketSubProtocolError(
+                SubProtocolError::NoSubProtocol,
+            ))) => {
+                tracing::debug!("Relay does not support binary frames, using JSON");
+                Self::connect_ws(&ws_url, false).await
+            }
+            result => result,
+        };
 
-        *self.ws.lock().await = Some(ws_stream);
+        match connected {
+            Ok((ws_stream, binary)) => {
+                *self.ws.lock().await = Some(ws_stream);
+                self.binary.store(binary, Ordering::SeqCst);
 
-        // Authenticate
-        self.authenticate().await?;
+                // Authenticate
+                let result = self.authenticate().await;
+                if result.is_err() {
+                    *self.ws.lock().await = None;
+                }
+                result
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "WebSocket connection to relay failed ({}), falling back to HTTP long-poll",
+                    e
+                );
+                self.connect_long_poll().await
+            }
+        }
+    }
+
+    /// Open the WebSocket, offering binary frames if `binary` is set
+    ///
+    /// Returns whether the relay accepted them.
+    async fn connect_ws(
+        ws_url: &str,
+        binary: bool,
+    ) -> Result<(WebSocketConnection, bool), tungstenite::Error> {
+        let mut request = ws_url.into_client_request()?;
+        if binary {
+            request.headers_mut().insert(
+                tungstenite::http::header::SEC_WEBSOCKET_PROTOCOL,
+                tungstenite::http::HeaderValue::from_static(BINARY_SUBPROTOCOL),
+            );
+        }
+
+        let (ws_stream, response) = connect_async(request).await?;
+        let accepted = response
+            .headers()
+            .get(tungstenite::http::header::SEC_WEBSOCKET_PROTOCOL)
+            .is_some_and(|protocol| protocol == BINARY_SUBPROTOCOL);
+        Ok((ws_stream, accepted))
+    }
+
+    /// Connect, retrying failures on the backoff schedule
+    ///
+    /// `on_failure` is called with the attempt number (from 1) and error of
+    /// each failed attempt. Returns the last error once the schedule gives up.
+    pub async fn connect_with_retry(
+        &self,
+        mut on_failure: impl FnMut(u32, &NetworkError),
+    ) -> Result<(), NetworkError> {
+        let mut attempt = 1;
+        loop {
+            let error = match self.connect().await {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            // Drop whatever the failed attempt left half set up
+            self.disconnect().await;
+            on_failure(attempt, &error);
+            if !self.backoff.retries_after(attempt) {
+                return Err(error);
+            }
+
+            let delay = self.backoff.jittered_delay(attempt, rand::random());
+            tracing::debug!("Retrying relay connection in {:?}", delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Register over HTTP and switch to the long-poll transport
+    async fn connect_long_poll(&self) -> Result<(), NetworkError> {
+        let token = self.register_http().await?;
+        *self.auth_token.lock().await = Some(token);
+        self.long_poll.store(true, Ordering::SeqCst);
 
         Ok(())
     }
 
-    /// Authenticate with the relay server
-    async fn authenticate(&self) -> Result<(), NetworkError> {
+    /// Register this device over HTTP and return the issued token
+    ///
+    /// With a pinned key, the token is only taken if the relay proves it holds it.
+    async fn register_http(&self) -> Result<String, NetworkError> {
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
 
         let device_id = self.identity.device_id_hex();
-        let challenge = format!("auth:{}:{}", device_id, timestamp);
+        let challenge = format!("register:{}:{}", device_id, timestamp);
         let signature = self.identity.sign(challenge.as_bytes());
+        let session_challenge = self.session_challenge();
 
-        let auth_msg = serde_json::json!({
-            "type": "auth",
-            "device_id": device_id,
-            "timestamp": timestamp,
-            "signature": base64::Engine::encode(&base64::engine::general_purpose::STANDARD, signature),
-        });
+        let request = RegisterRequest {
+            device_id,
+            public_key: base64::Engine::encode(
+                &base64::engine::general_purpose::STANDARD,
+                self.identity.public_key(),
+            ),
+            device_name: self.device_name.clone(),
+            timestamp,
+            signature: base64::Engine::encode(
+                &base64::engine::general_purpose::STANDARD,
+                signature,
+            ),
+            challenge: session_challenge.clone(),
+        };
+
+        let response = self
+            .http_client
+            .post(format!("{}/api/v1/register", self.url))
+            .json(&request)
+            .timeout(RELAY_SEND_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| NetworkError::Relay(format!("HTTP connection failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(NetworkError::Relay(format!(
+                "Registration failed: {}",
+                error_text
+            )));
+        }
+
+        let registration: RegisterResponse = response
+            .json()
+            .await
+            .map_err(|e| NetworkError::Relay(format!("Invalid registration response: {}", e)))?;
+        self.verify_session(
+            session_challenge.as_deref(),
+            registration.identity_signature.as_deref(),
+        )?;
 
-        self.send_ws_message(&auth_msg.to_string()).await?;
+        Ok(registration.token)
+    }
+
+    /// Whether the client is using the HTTP long-poll transport
+    pub fn is_long_poll(&self) -> bool {
+        self.long_poll.load(Ordering::SeqCst)
+    }
+
+    /// Authenticate with the relay server
+    ///
+    /// With a pinned key, the relay must also answer a challenge with it.
+    async fn authenticate(&self) -> Result<(), NetworkError> {
+        let session_challenge = self.session_challenge();
+        self.send_ws_message(&auth_request(&self.identity, session_challenge.as_deref()))
+            .await?;
 
         // Wait for auth response
         let response = self.receive_ws_message().await?;
-        let auth_response: serde_json::Value = serde_json::from_str(&response)
-            .map_err(|e| NetworkError::Relay(format!("Invalid auth response: {}", e)))?;
-
-        if auth_response.get("success").and_then(|v| v.as_bool()) == Some(true) {
-            if let Some(token) = auth_response.get("token").and_then(|v| v.as_str()) {
-                *self.auth_token.lock().await = Some(token.to_string());
-            }
-            Ok(())
-        } else {
-            let error = auth_response
-                .get("error")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unknown error");
-            Err(NetworkError::Relay(format!(
-                "Authentication failed: {}",
-                error
-            )))
+        let token = check_auth_response(
+            &response,
+            &self.identity.device_id_hex(),
+            self.pinned_key.as_ref(),
+            session_challenge.as_deref(),
+        )?;
+        if let Some(token) = token {
+            *self.auth_token.lock().await = Some(token);
         }
+        Ok(())
+    }
+
+    /// A fresh challenge for the relay to sign, if its key is pinned
+    fn session_challenge(&self) -> Option<String> {
+        session_challenge(self.pinned_key.as_ref())
+    }
+
+    /// Check the relay answered `challenge` with the pinned key
+    ///
+    /// Passes when nothing is pinned (and so no challenge was sent).
+    fn verify_session(
+        &self,
+        challenge: Option<&str>,
+        signature: Option<&str>,
+    ) -> Result<(), NetworkError> {
+        let (Some(pinned_key), Some(challenge)) = (self.pinned_key, challenge) else {
+            return Ok(());
+        };
+        verify_session_signature(
+            &pinned_key,
+            challenge,
+            &self.identity.device_id_hex(),
+            signature,
+        )
     }
 
     /// Send a message to another device via relay
+    ///
+    /// Timed-out attempts are retried with the same message id, so the relay
+    /// delivers the message at most once. The payload is signed with our
+    /// identity key so the recipient can tell the relay didn't forge it.
     pub async fn send_to_device(
         &self,
         target_device_id: &str,
         encrypted_payload: &[u8],
     ) -> Result<(), NetworkError> {
-        let msg = RelayMessage {
-            from_device: self.identity.device_id_hex(),
-            to_device: target_device_id.to_string(),
-            encrypted_payload: base64::Engine::encode(
-                &base64::engine::general_purpose::STANDARD,
-                encrypted_payload,
-            ),
-            timestamp: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64,
+        let mut nonce = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let message_id = Self::message_id(target_device_id, encrypted_payload, &nonce);
+
+        let (signed_at, signature) =
+            sign_message(&self.identity, target_device_id, encrypted_payload);
+
+        let request = SendRequest {
+            id: &message_id,
+            to_device: target_device_id,
+            encrypted_payload,
+            signed_at,
+            signature: &signature,
         };
 
-        let json = serde_json::to_string(&serde_json::json!({
-            "type": "relay",
-            "message": msg,
-        }))
-        .map_err(|e| NetworkError::Relay(e.to_string()))?;
+        let mut attempt = 1;
+        loop {
+            match tokio::time::timeout(RELAY_SEND_TIMEOUT, self.send_relay_request(&request)).await
+            {
+                Ok(result) => return result,
+                Err(_) if attempt < RELAY_SEND_MAX_ATTEMPTS => {
+                    tracing::debug!(
+                        "Relay send {} timed out (attempt {}), retrying",
+                        message_id,
+                        attempt
+                    );
+                    attempt += 1;
+                }
+                Err(_) => return Err(NetworkError::Timeout),
+            }
+        }
+    }
 
-        self.send_ws_message(&json).await
+    /// Derive a relay message id from the content, destination and a per-send nonce
+    ///
+    /// The nonce is generated once per send and reused across retries.
+    pub fn message_id(target_device_id: &str, payload: &[u8], nonce: &[u8; 16]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(Sha256::digest(payload));
+        hasher.update(target_device_id.as_bytes());
+        hasher.update(nonce);
+        hex::encode(&hasher.finalize()[..16])
     }
 
     /// Receive a message from the relay
     pub async fn receive(&self) -> Result<RelayMessage, NetworkError> {
-        let response = self.receive_ws_message().await?;
+        if self.is_long_poll() {
+            return self.receive_long_poll().await;
+        }
+
+        let response = match self.receive_ws_frame().await? {
+            WsMessage::Binary(frame) => return relay_frame::decode(&frame),
+            WsMessage::Text(text) => text,
+            _ => return Err(NetworkError::Relay("Unexpected message type".to_string())),
+        };
 
         let envelope: serde_json::Value = serde_json::from_str(&response)
             .map_err(|e| NetworkError::Relay(format!("Invalid message: {}", e)))?;
@@ -163,28 +696,235 @@ impl RelayClient {
         }
     }
 
+    /// Send a `send` request over whichever transport is connected
+    ///
+    /// A binary WebSocket gets a binary frame; otherwise the request is JSON.
+    async fn send_relay_request(&self, request: &SendRequest<'_>) -> Result<(), NetworkError> {
+        if !self.is_long_poll() {
+            let message = if self.binary.load(Ordering::SeqCst) {
+                WsMessage::Binary(
+                    relay_frame::encode_send(
+                        request.id,
+                        request.to_device,
+                        request.signed_at,
+                        request.signature,
+                        request.encrypted_payload,
+                    )?
+                    .into(),
+                )
+            } else {
+                WsMessage::Text(request.to_json().into())
+            };
+            return self.send_ws(message).await;
+        }
+
+        let response = self
+            .http_client
+            .post(format!("{}/api/v1/messages/send", self.url))
+            .bearer_auth(self.bearer_token().await?)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(request.to_json())
+            .send()
+            .await
+            .map_err(|e| NetworkError::Relay(format!("Send failed: {}", e)))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let error_text = response.text().await.unwrap_or_default();
+            Err(NetworkError::Relay(format!("Send failed: {}", error_text)))
+        }
+    }
+
+    /// Return the next message, long-polling the relay until one arrives
+    async fn receive_long_poll(&self) -> Result<RelayMessage, NetworkError> {
+        loop {
+            if let Some(message) = self.pending.lock().await.pop_front() {
+                return Ok(message);
+            }
+
+            let mut request = self
+                .http_client
+                .get(format!("{}/api/v1/messages/poll", self.url))
+                .query(&[("timeout_secs", LONG_POLL_TIMEOUT_SECS)]);
+            // Everything from the last response was handed out, so ack it
+            if let Some(ack) = *self.poll_cursor.lock().await {
+                request = request.query(&[("ack", ack)]);
+            }
+            let response = request
+                .bearer_auth(self.bearer_token().await?)
+                .timeout(Duration::from_secs(LONG_POLL_TIMEOUT_SECS) + LONG_POLL_GRACE)
+                .send()
+                .await
+                .map_err(|e| NetworkError::ConnectionFailed(format!("Receive error: {}", e)))?;
+
+            if !response.status().is_success() {
+                let error_text = response.text().await.unwrap_or_default();
+                return Err(NetworkError::ConnectionFailed(format!(
+                    "Receive error: {}",
+                    error_text
+                )));
+            }
+
+            let poll: PollResponse = response
+                .json()
+                .await
+                .map_err(|e| NetworkError::Relay(format!("Invalid poll response: {}", e)))?;
+
+            if poll.cursor.is_some() {
+                *self.poll_cursor.lock().await = poll.cursor;
+            }
+            self.pending.lock().await.extend(poll.messages);
+        }
+    }
+
+    /// Ask the relay whether a device is currently connected to it
+    ///
+    /// Registers over HTTP first if no token was issued yet. A device the
+    /// relay has never seen is reported offline.
+    pub async fn device_online(&self, device_id: &str) -> Result<bool, NetworkError> {
+        let token = self.http_token().await?;
+
+        let response = self
+            .http_client
+            .get(format!("{}/api/v1/devices/{}/status", self.url, device_id))
+            .bearer_auth(token)
+            .timeout(RELAY_SEND_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| NetworkError::Relay(format!("Status request failed: {}", e)))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        if !response.status().is_success() {
+            return Err(NetworkError::Relay(format!(
+                "Status request failed: {}",
+                response.status()
+            )));
+        }
+
+        let status: DeviceStatusResponse = response
+            .json()
+            .await
+            .map_err(|e| NetworkError::Relay(format!("Invalid status response: {}", e)))?;
+        Ok(status.is_online)
+    }
+
+    /// Ask the relay how many messages it holds for this device
+    ///
+    /// Registers over HTTP first if no token was issued yet.
+    pub async fn queue_status(&self) -> Result<RelayQueueStatus, NetworkError> {
+        let token = self.http_token().await?;
+
+        let response = self
+            .http_client
+            .get(format!("{}/api/v1/queue", self.url))
+            .bearer_auth(token)
+            .timeout(RELAY_SEND_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| NetworkError::Relay(format!("Queue status request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(NetworkError::Relay(format!(
+                "Queue status request failed: {}",
+                response.status()
+            )));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| NetworkError::Relay(format!("Invalid queue status response: {}", e)))
+    }
+
+    /// Drop every message the relay holds for this device, returning how many
+    ///
+    /// Registers over HTTP first if no token was issued yet.
+    pub async fn flush_queue(&self) -> Result<u64, NetworkError> {
+        let token = self.http_token().await?;
+
+        let response = self
+            .http_client
+            .delete(format!("{}/api/v1/queue", self.url))
+            .bearer_auth(token)
+            .timeout(RELAY_SEND_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| NetworkError::Relay(format!("Queue flush request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(NetworkError::Relay(format!(
+                "Queue flush request failed: {}",
+                response.status()
+            )));
+        }
+
+        let flushed: FlushQueueResponse = response
+            .json()
+            .await
+            .map_err(|e| NetworkError::Relay(format!("Invalid queue flush response: {}", e)))?;
+        Ok(flushed.deleted)
+    }
+
+    /// Token for HTTP requests, registering over HTTP if none was issued yet
+    async fn http_token(&self) -> Result<String, NetworkError> {
+        let existing = self.auth_token.lock().await.clone();
+        match existing {
+            Some(token) => Ok(token),
+            None => {
+                let token = self.register_http().await?;
+                *self.auth_token.lock().await = Some(token.clone());
+                Ok(token)
+            }
+        }
+    }
+
+    /// Token issued at HTTP registration
+    async fn bearer_token(&self) -> Result<String, NetworkError> {
+        self.auth_token
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(|| NetworkError::Relay("Not connected".to_string()))
+    }
+
     /// Send WebSocket message
     async fn send_ws_message(&self, message: &str) -> Result<(), NetworkError> {
+        self.send_ws(WsMessage::Text(message.to_string().into()))
+            .await
+    }
+
+    async fn send_ws(&self, message: WsMessage) -> Result<(), NetworkError> {
         let mut ws = self.ws.lock().await;
         let ws = ws
             .as_mut()
             .ok_or_else(|| NetworkError::Relay("Not connected".to_string()))?;
 
-        ws.send(WsMessage::Text(message.to_string().into()))
+        ws.send(message)
             .await
             .map_err(|e| NetworkError::Relay(format!("Send failed: {}", e)))
     }
 
-    /// Receive WebSocket message
+    /// Receive WebSocket text message
     async fn receive_ws_message(&self) -> Result<String, NetworkError> {
+        match self.receive_ws_frame().await? {
+            WsMessage::Text(text) => Ok(text.to_string()),
+            _ => Err(NetworkError::Relay("Unexpected binary message".to_string())),
+        }
+    }
+
+    /// Receive the next text or binary WebSocket message
+    async fn receive_ws_frame(&self) -> Result<WsMessage, NetworkError> {
         let mut ws = self.ws.lock().await;
-        let ws = ws
-            .as_mut()
-            .ok_or_else(|| NetworkError::Relay("Not connected".to_string()))?;
+        let ws = ws.as_mut().ok_or(NetworkError::ConnectionClosed)?;
 
         loop {
             match ws.next().await {
-                Some(Ok(WsMessage::Text(text))) => return Ok(text.to_string()),
+                Some(Ok(message @ (WsMessage::Text(_) | WsMessage::Binary(_)))) => {
+                    return Ok(message)
+                }
                 Some(Ok(WsMessage::Ping(data))) => {
                     ws.send(WsMessage::Pong(data)).await.ok();
                 }
@@ -192,7 +932,10 @@ impl RelayClient {
                     return Err(NetworkError::ConnectionClosed);
                 }
                 Some(Err(e)) => {
-                    return Err(NetworkError::Relay(format!("Receive error: {}", e)));
+                    return Err(NetworkError::ConnectionFailed(format!(
+                        "Receive error: {}",
+                        e
+                    )));
                 }
                 None => {
                     return Err(NetworkError::ConnectionClosed);
@@ -207,12 +950,15 @@ impl RelayClient {
         if let Some(mut ws) = self.ws.lock().await.take() {
             let _ = ws.close(None).await;
         }
+        self.binary.store(false, Ordering::SeqCst);
+        self.long_poll.store(false, Ordering::SeqCst);
+        self.pending.lock().await.clear();
         *self.auth_token.lock().await = None;
     }
 
     /// Check if connected
     pub async fn is_connected(&self) -> bool {
-        self.ws.lock().await.is_some()
+        self.is_long_poll() || self.ws.lock().await.is_some()
     }
 
     /// Get the relay URL
@@ -221,28 +967,465 @@ impl RelayClient {
     }
 }
 
+/// Sign a relayed message for `to_device` now
+///
+/// Returns the `signed_at` time (Unix milliseconds) and the signature over
+/// [`message_signing_bytes`].
+pub(super) fn sign_message(
+    identity: &DeviceIdentity,
+    to_device: &str,
+    payload: &[u8],
+) -> (u64, [u8; 64]) {
+    let signed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    let signature = identity.sign(&message_signing_bytes(to_device, signed_at, payload));
+    (signed_at, signature)
+}
+
+/// A fresh challenge for the relay to sign, if its key is pinned
+pub(super) fn session_challenge(pinned_key: Option<&[u8; 32]>) -> Option<String> {
+    pinned_key.map(|_| {
+        let mut nonce = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        hex::encode(nonce)
+    })
+}
+
+/// The WebSocket `auth` message, with the relay's `session_challenge` if any
+pub(super) fn auth_request(identity: &DeviceIdentity, session_challenge: Option<&str>) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let device_id = identity.device_id_hex();
+    let challenge = format!("auth:{}:{}", device_id, timestamp);
+    let signature = identity.sign(challenge.as_bytes());
+
+    serde_json::json!({
+        "type": "auth",
+        "device_id": device_id,
+        "timestamp": timestamp,
+        "signature": base64::Engine::encode(&base64::engine::general_purpose::STANDARD, signature),
+        "challenge": session_challenge,
+    })
+    .to_string()
+}
+
+/// Check the relay's answer to an [`auth_request`]
+///
+/// With a `pinned_key`, the relay must have answered `session_challenge`
+/// with it. Returns the session token, if the relay issued one.
+pub(super) fn check_auth_response(
+    response: &str,
+    device_id: &str,
+    pinned_key: Option<&[u8; 32]>,
+    session_challenge: Option<&str>,
+) -> Result<Option<String>, NetworkError> {
+    let auth_response: serde_json::Value = serde_json::from_str(response)
+        .map_err(|e| NetworkError::Relay(format!("Invalid auth response: {}", e)))?;
+
+    if auth_response.get("success").and_then(|v| v.as_bool()) != Some(true) {
+        let error = auth_response
+            .get("error")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Unknown error");
+        return Err(NetworkError::Relay(format!(
+            "Authentication failed: {}",
+            error
+        )));
+    }
+
+    if let (Some(pinned_key), Some(challenge)) = (pinned_key, session_challenge) {
+        verify_session_signature(
+            pinned_key,
+            challenge,
+            device_id,
+            auth_response
+                .get("identity_signature")
+                .and_then(|v| v.as_str()),
+        )?;
+    }
+    Ok(auth_response
+        .get("token")
+        .and_then(|v| v.as_str())
+        .map(str::to_string))
+}
+
+/// Check the relay signed `challenge` with the key it reports
+fn verify_identity(identity: &IdentityResponse, challenge: &str) -> Result<[u8; 32], NetworkError> {
+    let decode = |value: &str| {
+        base64::Engine::decode(&base64::engine::general_purpose::STANDARD, value)
+            .map_err(|_| NetworkError::Relay("Invalid relay identity encoding".to_string()))
+    };
+
+    let public_key: [u8; 32] = decode(&identity.public_key)?
+        .try_into()
+        .map_err(|_| NetworkError::Relay("Invalid relay identity key".to_string()))?;
+    let signature: [u8; 64] = decode(identity.signature.as_deref().unwrap_or_default())?
+        .try_into()
+        .map_err(|_| NetworkError::Relay("Missing relay identity signature".to_string()))?;
+
+    let message = format!("{}{}", IDENTITY_CHALLENGE_PREFIX, challenge);
+    if !DeviceIdentity::verify_from_public_key(
+        KeyAlgorithm::Ed25519,
+        &public_key,
+        message.as_bytes(),
+        &signature,
+    ) {
+        return Err(NetworkError::Relay(
+            "Relay identity signature is invalid".to_string(),
+        ));
+    }
+
+    Ok(public_key)
+}
+
+/// Check `signature` (base64) is the relay's answer to a session challenge
+fn verify_session_signature(
+    pinned_key: &[u8; 32],
+    challenge: &str,
+    device_id: &str,
+    signature: Option<&str>,
+) -> Result<(), NetworkError> {
+    let signature: [u8; 64] = signature
+        .and_then(|s| base64::Engine::decode(&base64::engine::general_purpose::STANDARD, s).ok())
+        .and_then(|s| s.try_into().ok())
+        .ok_or_else(|| NetworkError::Relay("Missing relay identity signature".to_string()))?;
+
+    let message = format!("{}{}:{}", SESSION_CHALLENGE_PREFIX, challenge, device_id);
+    if !DeviceIdentity::verify_from_public_key(
+        KeyAlgorithm::Ed25519,
+        pinned_key,
+        message.as_bytes(),
+        &signature,
+    ) {
+        return Err(NetworkError::Relay(
+            "Relay identity does not match the pinned key".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Read one HTTP request, including its body
+    async fn read_request(stream: &mut tokio::net::TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = stream.read(&mut buf).await.unwrap_or(0);
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            let Some((head, body)) = text.split_once("\r\n\r\n") else {
+                if n == 0 {
+                    return text;
+                }
+                continue;
+            };
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    line.to_ascii_lowercase()
+                        .strip_prefix("content-length:")
+                        .map(|v| v.trim().parse().unwrap_or(0))
+                })
+                .unwrap_or(0);
+            if n == 0 || body.len() >= length {
+                return text;
+            }
+        }
+    }
+
+    /// Serve identity and registration requests as a relay holding `relay_identity`
+    async fn serve_identity(relay_identity: DeviceIdentity) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let request = read_request(&mut stream).await;
+                let engine = base64::engine::general_purpose::STANDARD;
+
+                let body = if request.starts_with("POST /api/v1/register") {
+                    let body = request.split_once("\r\n\r\n").unwrap_or_default().1;
+                    let register: serde_json::Value =
+                        serde_json::from_str(body).unwrap_or_default();
+                    let message = format!(
+                        "{}{}:{}",
+                        SESSION_CHALLENGE_PREFIX,
+                        register["challenge"].as_str().unwrap_or_default(),
+                        register["device_id"].as_str().unwrap_or_default()
+                    );
+                    serde_json::json!({
+                        "token": "test-token",
+                        "expires_at": 0,
+                        "identity_signature": base64::Engine::encode(&engine, relay_identity.sign(message.as_bytes())),
+                    })
+                    .to_string()
+                } else {
+                    let challenge = request
+                        .split("challenge=")
+                        .nth(1)
+                        .and_then(|rest| rest.split(' ').next())
+                        .unwrap_or_default();
+                    let message = format!("{}{}", IDENTITY_CHALLENGE_PREFIX, challenge);
+                    serde_json::json!({
+                        "public_key": base64::Engine::encode(&engine, relay_identity.public_key()),
+                        "signature": base64::Engine::encode(&engine, relay_identity.sign(message.as_bytes())),
+                    })
+                    .to_string()
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_pinned_key_mismatch_refuses_relay() {
+        let relay_identity = DeviceIdentity::generate().unwrap();
+        let relay_key = relay_identity.public_key();
+        let url = serve_identity(relay_identity).await;
+        let identity = Arc::new(DeviceIdentity::generate().unwrap());
+
+        // The reported key is what a user would pin
+        let client = RelayClient::new(&url, identity.clone());
+        assert_eq!(client.fetch_identity().await.unwrap(), relay_key);
+
+        let impostor_pin = DeviceIdentity::generate().unwrap().public_key();
+        let client = RelayClient::new(&url, identity.clone()).with_pinned_key(Some(impostor_pin));
+        let result = client.connect().await;
+        assert!(
+            matches!(&result, Err(NetworkError::Relay(e)) if e.contains("pinned")),
+            "connect should refuse the relay: {:?}",
+            result
+        );
+        assert!(!client.is_connected().await);
+
+        // The relay proves the pinned key on the registration itself
+        let client = RelayClient::new(&url, identity).with_pinned_key(Some(relay_key));
+        client.connect().await.unwrap();
+        assert!(client.is_long_poll());
+    }
+
+    #[test]
+    fn test_session_signature_must_cover_challenge_and_device() {
+        let relay_identity = DeviceIdentity::generate().unwrap();
+        let relay_key = relay_identity.public_key();
+        let message = format!("{}abc:device", SESSION_CHALLENGE_PREFIX);
+        let signature = base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            relay_identity.sign(message.as_bytes()),
+        );
+
+        assert!(verify_session_signature(&relay_key, "abc", "device", Some(&signature)).is_ok());
+        assert!(verify_session_signature(&relay_key, "other", "device", Some(&signature)).is_err());
+        assert!(verify_session_signature(&relay_key, "abc", "other", Some(&signature)).is_err());
+        assert!(verify_session_signature(&relay_key, "abc", "device", None).is_err());
+
+        // An answer to the standalone identity challenge doesn't count
+        let identity_message = format!("{}abc:device", IDENTITY_CHALLENGE_PREFIX);
+        let identity_signature = base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            relay_identity.sign(identity_message.as_bytes()),
+        );
+        assert!(
+            verify_session_signature(&relay_key, "abc", "device", Some(&identity_signature))
+                .is_err()
+        );
+    }
+
+    /// Serve registration and device status requests, reporting `online` as connected
+    async fn serve_status(online: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let path = request.split(' ').nth(1).unwrap_or_default().to_string();
+
+                let (status, body) = if path == "/api/v1/register" {
+                    (
+                        "200 OK",
+                        serde_json::json!({ "token": "test-token", "expires_at": 0 }).to_string(),
+                    )
+                } else if !request.contains("Bearer test-token") {
+                    ("401 Unauthorized", String::new())
+                } else if path == format!("/api/v1/devices/{}/status", online) {
+                    (
+                        "200 OK",
+                        serde_json::json!({ "device_id": online, "is_online": true, "last_seen": null })
+                            .to_string(),
+                    )
+                } else {
+                    ("404 Not Found", String::new())
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_device_online_reports_relay_status() {
+        let online = "ab".repeat(32);
+        let url = serve_status(online.clone()).await;
+        let client = RelayClient::new(&url, Arc::new(DeviceIdentity::generate().unwrap()));
+
+        // Registers for a token on first use, without switching transports
+        assert!(client.device_online(&online).await.unwrap());
+        assert!(!client.is_long_poll());
+        assert!(!client.device_online(&"cd".repeat(32)).await.unwrap());
+    }
+
+    #[test]
+    fn test_identity_signature_must_cover_challenge() {
+        let relay_identity = DeviceIdentity::generate().unwrap();
+        let engine = base64::engine::general_purpose::STANDARD;
+        let message = format!("{}abc", IDENTITY_CHALLENGE_PREFIX);
+        let response = IdentityResponse {
+            public_key: base64::Engine::encode(&engine, relay_identity.public_key()),
+            signature: Some(base64::Engine::encode(
+                &engine,
+                relay_identity.sign(message.as_bytes()),
+            )),
+        };
+
+        assert_eq!(
+            verify_identity(&response, "abc").unwrap(),
+            relay_identity.public_key()
+        );
+        assert!(verify_identity(&response, "other").is_err());
+
+        let unsigned = IdentityResponse {
+            signature: None,
+            ..response
+        };
+        assert!(verify_identity(&unsigned, "abc").is_err());
+    }
 
     #[test]
     fn test_relay_client_creation() {
         let identity = Arc::new(DeviceIdentity::generate().unwrap());
         let client = RelayClient::new("http://localhost:8080", identity);
         assert_eq!(client.url(), "http://localhost:8080");
+        assert!(!client.is_long_poll());
+    }
+
+    #[test]
+    fn test_backoff_schedule_grows_and_caps() {
+        let backoff = RelayBackoff {
+            initial: Duration::from_millis(500),
+            max: Duration::from_secs(5),
+            multiplier: 3.0,
+            jitter: 0.5,
+            max_attempts: 6,
+        };
+
+        let delays: Vec<u64> = (1..=6)
+            .map(|attempt| backoff.delay(attempt).as_millis() as u64)
+            .collect();
+        assert_eq!(delays, vec![500, 1500, 4500, 5000, 5000, 5000]);
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(5));
+
+        // Jitter only ever shortens a delay, by at most its configured share
+        assert_eq!(backoff.jittered_delay(2, 0.0), Duration::from_millis(1500));
+        assert_eq!(backoff.jittered_delay(2, 0.5), Duration::from_millis(1125));
+        assert!(backoff.jittered_delay(4, 0.999) > Duration::from_millis(2500));
+
+        assert!(backoff.retries_after(5));
+        assert!(!backoff.retries_after(6));
+        let unlimited = RelayBackoff {
+            max_attempts: 0,
+            ..backoff
+        };
+        assert!(unlimited.retries_after(u32::MAX));
+    }
+
+    #[tokio::test]
+    async fn test_connect_gives_up_after_max_attempts() {
+        // Nothing listens here, so every attempt fails
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let identity = Arc::new(DeviceIdentity::generate().unwrap());
+        let client = RelayClient::new(&url, identity).with_backoff(RelayBackoff {
+            initial: Duration::from_millis(1),
+            max: Duration::from_millis(5),
+            multiplier: 2.0,
+            jitter: 0.0,
+            max_attempts: 3,
+        });
+
+        let mut attempts = Vec::new();
+        let result = client
+            .connect_with_retry(|attempt, _| attempts.push(attempt))
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, vec![1, 2, 3]);
+        assert!(!client.is_connected().await);
     }
 
     #[test]
     fn test_relay_message_serialization() {
         let msg = RelayMessage {
+            id: "msg1".to_string(),
             from_device: "device1".to_string(),
             to_device: "device2".to_string(),
             encrypted_payload: "dGVzdA==".to_string(),
             timestamp: 1234567890,
+            signed_at: None,
+            signature: None,
         };
 
         let json = serde_json::to_string(&msg).unwrap();
         assert!(json.contains("device1"));
         assert!(json.contains("device2"));
     }
+
+    #[test]
+    fn test_relay_message_without_id() {
+        let json = r#"{"from_device":"a","to_device":"b","encrypted_payload":"","timestamp":0}"#;
+        let msg: RelayMessage = serde_json::from_str(json).unwrap();
+        assert!(msg.id.is_empty());
+    }
+
+    #[test]
+    fn test_message_id_stable_per_nonce() {
+        let nonce = [1u8; 16];
+        let id = RelayClient::message_id("device2", b"payload", &nonce);
+
+        assert_eq!(id, RelayClient::message_id("device2", b"payload", &nonce));
+        assert_eq!(id.len(), 32);
+        assert_ne!(id, RelayClient::message_id("device3", b"payload", &nonce));
+        assert_ne!(id, RelayClient::message_id("device2", b"other", &nonce));
+        assert_ne!(
+            id,
+            RelayClient::message_id("device2", b"payload", &[2u8; 16])
+        );
+    }
 }
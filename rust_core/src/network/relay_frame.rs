@@ -0,0 +1,128 @@
+//! Binary relay frames
+//!
+//! When the relay accepts [`BINARY_SUBPROTOCOL`], relay messages are sent as
+//! binary WebSocket frames so the payload travels as raw bytes rather than
+//! base64. Authentication stays JSON text. Integers are big-endian:
+//!
+//! ```text
+//! [from_len: u8][from_device][to_len: u8][to_device][id_len: u8][id]
+//! [timestamp: u64][signed_at: u64, 0 if unsigned][sig_len: u8][signature]
+//! [payload: rest of the frame]
+//! ```
+//!
+//! Frames we send leave `from_device` empty and `timestamp` zero; the relay
+//! fills in both.
+
+use base64::Engine;
+
+use super::relay_client::RelayMessage;
+use crate::error::NetworkError;
+
+/// WebSocket subprotocol selecting binary relay frames
+pub const BINARY_SUBPROTOCOL: &str = "toss-relay-binary.v1";
+
+/// Encode a send request as a binary frame
+pub fn encode_send(
+    id: &str,
+    to_device: &str,
+    signed_at: u64,
+    signature: &[u8],
+    payload: &[u8],
+) -> Result<Vec<u8>, NetworkError> {
+    let mut frame =
+        Vec::with_capacity(to_device.len() + id.len() + signature.len() + payload.len() + 20);
+    put_field(&mut frame, b"")?;
+    put_field(&mut frame, to_device.as_bytes())?;
+    put_field(&mut frame, id.as_bytes())?;
+    frame.extend_from_slice(&0u64.to_be_bytes());
+    frame.extend_from_slice(&signed_at.to_be_bytes());
+    put_field(&mut frame, signature)?;
+    frame.extend_from_slice(payload);
+    Ok(frame)
+}
+
+/// Decode a delivered message
+///
+/// A zero `signed_at` or empty signature decode as absent.
+pub fn decode(frame: &[u8]) -> Result<RelayMessage, NetworkError> {
+    let mut reader = Reader(frame);
+    let from_device = reader.string()?;
+    let to_device = reader.string()?;
+    let id = reader.string()?;
+    let timestamp = reader.u64()?;
+    let signed_at = reader.u64()?;
+    let signature = reader.field()?;
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    Ok(RelayMessage {
+        id,
+        from_device,
+        to_device,
+        encrypted_payload: engine.encode(reader.0),
+        timestamp,
+        signed_at: (signed_at != 0).then_some(signed_at),
+        signature: (!signature.is_empty()).then(|| engine.encode(signature)),
+    })
+}
+
+fn put_field(frame: &mut Vec<u8>, field: &[u8]) -> Result<(), NetworkError> {
+    let len = u8::try_from(field.len())
+        .map_err(|_| NetworkError::Relay("Relay frame field too long".to_string()))?;
+    frame.push(len);
+    frame.extend_from_slice(field);
+    Ok(())
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], NetworkError> {
+        if self.0.len() < len {
+            return Err(NetworkError::Relay("Truncated relay frame".to_string()));
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn field(&mut self) -> Result<&'a [u8], NetworkError> {
+        let len = self.take(1)?[0] as usize;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<String, NetworkError> {
+        String::from_utf8(self.field()?.to_vec())
+            .map_err(|_| NetworkError::Relay("Invalid relay frame field".to_string()))
+    }
+
+    fn u64(&mut self) -> Result<u64, NetworkError> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_be_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sent_frame_decodes_with_payload_intact() {
+        let payload: Vec<u8> = (0..=255).collect();
+        let frame = encode_send("msg-1", "receiver", 42, &[5u8; 64], &payload).unwrap();
+
+        let message = decode(&frame).unwrap();
+        assert_eq!(message.id, "msg-1");
+        assert!(message.from_device.is_empty());
+        assert_eq!(message.to_device, "receiver");
+        assert_eq!(message.timestamp, 0);
+        assert_eq!(message.signed_at, Some(42));
+        let engine = base64::engine::general_purpose::STANDARD;
+        assert_eq!(engine.decode(&message.encrypted_payload).unwrap(), payload);
+        assert_eq!(
+            engine.decode(message.signature.unwrap()).unwrap(),
+            vec![5u8; 64]
+        );
+
+        assert!(decode(&frame[..20]).is_err());
+    }
+}
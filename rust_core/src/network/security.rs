@@ -0,0 +1,126 @@
+//! Downgrade detection for peer sessions
+//!
+//! Each side advertises the lowest `SecurityLevel` it accepts in its
+//! `DeviceInfo`, and a session runs at the higher of the two. Every message
+//! received afterwards is checked against that level. A session that has
+//! seen an encrypted message also stays encrypted. So an attacker who controls
+//! the relay can't switch a peer to the plaintext `0x00` fallback mid-session.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+use crate::error::NetworkError;
+use crate::protocol::SecurityLevel;
+
+/// Security level each peer session was negotiated at or observed to use
+pub struct SessionSecurity {
+    minimum: SecurityLevel,
+    sessions: Mutex<HashMap<[u8; 32], SecurityLevel>>,
+}
+
+impl SessionSecurity {
+    /// Create a tracker enforcing at least `minimum` for every peer
+    pub fn new(minimum: SecurityLevel) -> Self {
+        Self {
+            minimum,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The lowest level this device accepts
+    pub fn minimum(&self) -> SecurityLevel {
+        self.minimum
+    }
+
+    /// Record the level agreed with a peer from its advertised minimum
+    ///
+    /// The session runs at the higher of both minimums and never drops below
+    /// a level already recorded for it.
+    pub fn negotiate(&self, device_id: &[u8; 32], peer_minimum: SecurityLevel) -> SecurityLevel {
+        let mut sessions = self.sessions.lock();
+        let level = sessions
+            .get(device_id)
+            .copied()
+            .unwrap_or(self.minimum)
+            .max(self.minimum)
+            .max(peer_minimum);
+        sessions.insert(*device_id, level);
+        level
+    }
+
+    /// The level messages exchanged with a peer must meet
+    pub fn required(&self, device_id: &[u8; 32]) -> SecurityLevel {
+        self.sessions
+            .lock()
+            .get(device_id)
+            .copied()
+            .unwrap_or(self.minimum)
+            .max(self.minimum)
+    }
+
+    /// Check a received message's level against the peer's session
+    ///
+    /// An encrypted message raises the session to encrypted. A message below
+    /// the required level is a downgrade.
+    pub fn check(&self, device_id: &[u8; 32], level: SecurityLevel) -> Result<(), NetworkError> {
+        let required = self.required(device_id);
+        if level < required {
+            return Err(NetworkError::Downgrade(format!(
+                "{:?} message from {} in a session that requires {:?}",
+                level,
+                hex::encode(device_id),
+                required
+            )));
+        }
+        if level > required {
+            self.sessions.lock().insert(*device_id, level);
+        }
+        Ok(())
+    }
+}
+
+impl Default for SessionSecurity {
+    fn default() -> Self {
+        Self::new(SecurityLevel::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiated_level_is_the_higher_minimum() {
+        let security = SessionSecurity::new(SecurityLevel::Unencrypted);
+        let (legacy, modern) = ([1u8; 32], [2u8; 32]);
+
+        assert_eq!(
+            security.negotiate(&legacy, SecurityLevel::Unencrypted),
+            SecurityLevel::Unencrypted
+        );
+        assert!(security.check(&legacy, SecurityLevel::Unencrypted).is_ok());
+
+        assert_eq!(
+            security.negotiate(&modern, SecurityLevel::Encrypted),
+            SecurityLevel::Encrypted
+        );
+        assert!(matches!(
+            security.check(&modern, SecurityLevel::Unencrypted),
+            Err(NetworkError::Downgrade(_))
+        ));
+    }
+
+    #[test]
+    fn test_encrypted_session_stays_encrypted() {
+        let security = SessionSecurity::new(SecurityLevel::Unencrypted);
+        let peer = [3u8; 32];
+
+        assert!(security.check(&peer, SecurityLevel::Encrypted).is_ok());
+        assert_eq!(security.required(&peer), SecurityLevel::Encrypted);
+        assert!(security.check(&peer, SecurityLevel::Unencrypted).is_err());
+
+        // Renegotiating can't lower it again
+        security.negotiate(&peer, SecurityLevel::Unencrypted);
+        assert!(security.check(&peer, SecurityLevel::Unencrypted).is_err());
+    }
+}
@@ -0,0 +1,1044 @@
+//! Resumable chunked transfers
+//!
+//! Large payloads are split into chunks tagged with a transfer id. The sender
+//! keeps the source for [`TRANSFER_TTL`] and reads each chunk from it when
+//! the chunk is sent, so a source backed by a file never has to be in memory
+//! as a whole (see [`TransferSource`]). The receiver writes each chunk to a
+//! temporary file as it arrives and remembers which chunk indices it already
+//! has for the same period. When a connection drops mid-transfer, the
+//! receiver sends a `TransferResume` after reconnecting and the sender
+//! retransmits only the chunks that are missing. Once the payload is
+//! complete, the receiver sends a `TransferComplete` and the sender releases
+//! the source.
+//!
+//! To bound resources, the receiver refuses transfers larger than a size
+//! limit, limits how many transfers it reassembles at once, per peer and in
+//! total, and drops transfers that stop receiving chunks for longer than the
+//! stall timeout.
+//!
+//! Either side can cancel a transfer; the other is told with a
+//! `TransferCancel` and drops its state too.
+
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::error::ProtocolError;
+use crate::protocol::{TransferChunk, TransferResume};
+
+/// Default size of a single chunk (256 KB)
+pub const DEFAULT_CHUNK_SIZE: usize = 256 * 1024;
+
+/// How long either side keeps transfer state for a resume
+pub const TRANSFER_TTL: Duration = Duration::from_secs(600);
+
+/// Default number of transfers reassembled at once from one peer
+pub const DEFAULT_MAX_TRANSFERS_PER_PEER: usize = 4;
+
+/// Default number of transfers reassembled at once from all peers
+pub const DEFAULT_MAX_CONCURRENT_TRANSFERS: usize = 16;
+
+/// Default size of the largest payload accepted in one transfer (1 GB)
+pub const DEFAULT_MAX_TRANSFER_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Default time without a new chunk after which an incoming transfer is dropped
+pub const DEFAULT_TRANSFER_STALL_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// `ErrorMessage` code sent when an incoming transfer is refused
+pub const TRANSFER_REJECTED_ERROR_CODE: u32 = 429;
+
+/// Limits on incoming transfers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferLimits {
+    /// Transfers reassembled at once from one peer (0 = unlimited)
+    pub max_per_peer: usize,
+    /// Transfers reassembled at once from all peers (0 = unlimited)
+    pub max_total: usize,
+    /// Largest payload accepted in one transfer, in bytes (0 = unlimited)
+    pub max_bytes: u64,
+    /// Time without a new chunk after which a transfer is dropped
+    pub stall_timeout: Duration,
+}
+
+impl Default for TransferLimits {
+    fn default() -> Self {
+        Self {
+            max_per_peer: DEFAULT_MAX_TRANSFERS_PER_PEER,
+            max_total: DEFAULT_MAX_CONCURRENT_TRANSFERS,
+            max_bytes: DEFAULT_MAX_TRANSFER_BYTES,
+            stall_timeout: DEFAULT_TRANSFER_STALL_TIMEOUT,
+        }
+    }
+}
+
+/// Which way a transfer is going
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferDirection {
+    Incoming,
+    Outgoing,
+}
+
+/// Progress of one active transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferProgress {
+    pub transfer_id: u64,
+    pub direction: TransferDirection,
+    /// Peer on the other end of the transfer
+    pub device_id: [u8; 32],
+    /// Payload size
+    pub total_bytes: u64,
+    /// Bytes sent or received so far
+    pub transferred_bytes: u64,
+}
+
+impl TransferProgress {
+    /// Completed share of the transfer, from 0 to 100
+    pub fn percent(&self) -> f64 {
+        if self.total_bytes == 0 {
+            return 0.0;
+        }
+        (self.transferred_bytes as f64 * 100.0 / self.total_bytes as f64).min(100.0)
+    }
+
+    /// Whether the last `chunk_bytes` moved the transfer to a new whole
+    /// percent or completed it
+    ///
+    /// Used to report progress without an event for every chunk.
+    pub fn is_milestone(&self, chunk_bytes: u64) -> bool {
+        if self.transferred_bytes >= self.total_bytes {
+            return true;
+        }
+        let whole_percent = |bytes: u64| bytes * 100 / self.total_bytes;
+        whole_percent(self.transferred_bytes.saturating_sub(chunk_bytes))
+            != whole_percent(self.transferred_bytes)
+    }
+}
+
+/// Payload of an outgoing transfer, read one chunk at a time
+pub trait TransferSource: Send + Sync {
+    /// Payload size in bytes
+    fn size(&self) -> u64;
+
+    /// Fill `buf` with the payload bytes starting at `offset`
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()>;
+}
+
+impl TransferSource for Vec<u8> {
+    fn size(&self) -> u64 {
+        self.len() as u64
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let bytes = usize::try_from(offset)
+            .ok()
+            .and_then(|start| self.get(start..start.checked_add(buf.len())?))
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        buf.copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// Source kept by the sender until the transfer completes or expires
+struct OutgoingTransfer {
+    device_id: [u8; 32],
+    source: Arc<dyn TransferSource>,
+    chunk_size: usize,
+    payload_hash: [u8; 32],
+    expires_at: Instant,
+    /// Chunk indices sent at least once
+    sent: BTreeSet<u32>,
+}
+
+impl OutgoingTransfer {
+    fn total_chunks(&self) -> u32 {
+        self.source.size().div_ceil(self.chunk_size as u64).max(1) as u32
+    }
+
+    fn chunk_range(&self, index: u32) -> std::ops::Range<u64> {
+        let start = index as u64 * self.chunk_size as u64;
+        start..(start + self.chunk_size as u64).min(self.source.size())
+    }
+
+    fn progress(&self, transfer_id: u64) -> TransferProgress {
+        TransferProgress {
+            transfer_id,
+            direction: TransferDirection::Outgoing,
+            device_id: self.device_id,
+            total_bytes: self.source.size(),
+            transferred_bytes: self
+                .sent
+                .iter()
+                .map(|&i| {
+                    let range = self.chunk_range(i);
+                    range.end - range.start
+                })
+                .sum(),
+        }
+    }
+}
+
+/// Read one chunk of an outgoing transfer from its source
+fn read_chunk(
+    source: &dyn TransferSource,
+    range: std::ops::Range<u64>,
+    chunk: TransferChunk,
+) -> io::Result<TransferChunk> {
+    let mut data = vec![0u8; (range.end - range.start) as usize];
+    source.read_at(range.start, &mut data)?;
+    Ok(TransferChunk { data, ..chunk })
+}
+
+/// Chunks received so far for one transfer, written to a temporary file
+struct IncomingTransfer {
+    total_chunks: u32,
+    total_bytes: u64,
+    payload_hash: [u8; 32],
+    /// Size of every chunk but the last, once one of them arrived
+    chunk_size: Option<u64>,
+    /// Payload so far, each chunk written at its offset
+    file: File,
+    /// Length of each chunk received, by index
+    chunks: BTreeMap<u32, u64>,
+    expires_at: Instant,
+    last_chunk_at: Instant,
+}
+
+impl IncomingTransfer {
+    fn received_bytes(&self) -> u64 {
+        self.chunks.values().sum()
+    }
+
+    /// Offset of a chunk in the payload, or `None` if its size doesn't fit
+    /// the transfer
+    ///
+    /// Every chunk but the last has the same size; the last one ends the
+    /// payload.
+    fn chunk_offset(&self, index: u32, len: u64) -> Option<u64> {
+        let last = self.total_chunks - 1;
+        let chunk_size = match self.chunk_size {
+            Some(size) => size,
+            None if index == last => return self.total_bytes.checked_sub(len),
+            None => len,
+        };
+        if index == last {
+            let offset = last as u64 * chunk_size;
+            return (offset + len == self.total_bytes).then_some(offset);
+        }
+        if len != chunk_size || len == 0 {
+            return None;
+        }
+        if self.total_bytes.div_ceil(len) != self.total_chunks as u64 {
+            return None;
+        }
+        // The last chunk may have arrived first and must end where this size
+        // says it starts
+        let last_fits = self
+            .chunks
+            .get(&last)
+            .is_none_or(|&last_len| last as u64 * len + last_len == self.total_bytes);
+        last_fits.then_some(index as u64 * len)
+    }
+
+    fn write_chunk(&mut self, offset: u64, data: &[u8]) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(data)
+    }
+
+    fn progress(&self, device_id: [u8; 32], transfer_id: u64) -> TransferProgress {
+        TransferProgress {
+            transfer_id,
+            direction: TransferDirection::Incoming,
+            device_id,
+            total_bytes: self.total_bytes,
+            transferred_bytes: self.received_bytes(),
+        }
+    }
+}
+
+/// Payload of a completed incoming transfer, kept in a temporary file that
+/// is deleted when it is dropped
+#[derive(Debug)]
+pub struct ReceivedPayload {
+    file: File,
+    len: u64,
+}
+
+impl ReceivedPayload {
+    /// Payload size in bytes
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the payload is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The file holding the payload, positioned at its start
+    pub fn into_file(mut self) -> io::Result<File> {
+        self.file.seek(SeekFrom::Start(0))?;
+        Ok(self.file)
+    }
+
+    /// Read the whole payload into memory
+    pub fn into_bytes(self) -> io::Result<Vec<u8>> {
+        let len =
+            usize::try_from(self.len).map_err(|_| io::Error::from(io::ErrorKind::OutOfMemory))?;
+        let mut bytes = Vec::with_capacity(len);
+        self.into_file()?.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Whether the payload hashes to `expected`
+    fn has_hash(&mut self, expected: &[u8; 32]) -> io::Result<bool> {
+        self.file.seek(SeekFrom::Start(0))?;
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0u8; DEFAULT_CHUNK_SIZE];
+        loop {
+            let n = self.file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        Ok(hasher.finalize().as_slice() == expected)
+    }
+}
+
+/// Sender and receiver state of all resumable transfers
+#[derive(Default)]
+pub struct TransferTracker {
+    limits: TransferLimits,
+    outgoing: Mutex<HashMap<u64, OutgoingTransfer>>,
+    incoming: Mutex<HashMap<([u8; 32], u64), IncomingTransfer>>,
+    /// Refused transfers and when they are forgotten, so the sender is
+    /// only told once per transfer
+    rejected: Mutex<HashMap<([u8; 32], u64), Instant>>,
+}
+
+impl TransferTracker {
+    /// Create an empty tracker with the default limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty tracker with custom limits on incoming transfers
+    pub fn with_limits(limits: TransferLimits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    /// Start sending `data` to a device, returning all of its chunks
+    ///
+    /// The source is kept for [`TRANSFER_TTL`] so missing chunks can be
+    /// resent on resume.
+    pub fn start_outgoing(
+        &self,
+        device_id: [u8; 32],
+        data: Vec<u8>,
+        chunk_size: usize,
+    ) -> Vec<TransferChunk> {
+        let (transfer_id, total_chunks) = self
+            .start_outgoing_source(device_id, Arc::new(data), chunk_size)
+            .expect("reading from memory can't fail");
+        (0..total_chunks)
+            .filter_map(|index| self.outgoing_chunk(transfer_id, index)?.ok())
+            .collect()
+    }
+
+    /// Start sending a payload read from `source` to a device
+    ///
+    /// Returns the transfer id and number of chunks; read each chunk with
+    /// [`outgoing_chunk`](Self::outgoing_chunk) as it is sent. The source is
+    /// read once here to hash the payload, one chunk at a time, and is kept
+    /// for [`TRANSFER_TTL`] so missing chunks can be resent on resume.
+    pub fn start_outgoing_source(
+        &self,
+        device_id: [u8; 32],
+        source: Arc<dyn TransferSource>,
+        chunk_size: usize,
+    ) -> io::Result<(u64, u32)> {
+        let chunk_size = chunk_size.max(1);
+        let mut hasher = Sha256::new();
+        let mut buf = Vec::new();
+        let mut offset = 0;
+        while offset < source.size() {
+            let len = (source.size() - offset).min(chunk_size as u64) as usize;
+            buf.resize(len, 0);
+            source.read_at(offset, &mut buf)?;
+            hasher.update(&buf);
+            offset += len as u64;
+        }
+
+        let transfer_id = rand::random();
+        let transfer = OutgoingTransfer {
+            device_id,
+            payload_hash: hasher.finalize().into(),
+            source,
+            chunk_size,
+            expires_at: Instant::now() + TRANSFER_TTL,
+            sent: BTreeSet::new(),
+        };
+        let total_chunks = transfer.total_chunks();
+
+        let mut outgoing = self.outgoing.lock();
+        outgoing.retain(|_, t| t.expires_at > Instant::now());
+        outgoing.insert(transfer_id, transfer);
+        Ok((transfer_id, total_chunks))
+    }
+
+    /// Read a chunk of an outgoing transfer from its source
+    ///
+    /// `None` if the transfer is unknown, canceled or expired.
+    pub fn outgoing_chunk(
+        &self,
+        transfer_id: u64,
+        index: u32,
+    ) -> Option<io::Result<TransferChunk>> {
+        let (source, range, chunk) = {
+            let outgoing = self.outgoing.lock();
+            let transfer = outgoing
+                .get(&transfer_id)
+                .filter(|t| t.expires_at > Instant::now())?;
+            let chunk = TransferChunk {
+                transfer_id,
+                index,
+                total_chunks: transfer.total_chunks(),
+                total_bytes: transfer.source.size(),
+                payload_hash: transfer.payload_hash,
+                data: Vec::new(),
+            };
+            (transfer.source.clone(), transfer.chunk_range(index), chunk)
+        };
+        // Read outside the lock, so a slow source doesn't hold up other transfers
+        Some(read_chunk(source.as_ref(), range, chunk))
+    }
+
+    /// Indices of the chunks a receiver is still missing, or `None` if the
+    /// transfer is unknown
+    ///
+    /// A resume listing every chunk completes the transfer and releases the
+    /// source.
+    pub fn missing_outgoing(
+        &self,
+        device_id: &[u8; 32],
+        resume: &TransferResume,
+    ) -> Option<Vec<u32>> {
+        let mut outgoing = self.outgoing.lock();
+        let transfer = outgoing.get_mut(&resume.transfer_id)?;
+        if &transfer.device_id != device_id || transfer.expires_at <= Instant::now() {
+            return None;
+        }
+
+        let missing: Vec<u32> = (0..transfer.total_chunks())
+            .filter(|index| !resume.received_indices.contains(index))
+            .collect();
+
+        if missing.is_empty() {
+            outgoing.remove(&resume.transfer_id);
+        } else {
+            transfer.expires_at = Instant::now() + TRANSFER_TTL;
+        }
+        Some(missing)
+    }
+
+    /// Chunks a receiver is still missing, or `None` if the transfer is unknown
+    ///
+    /// Like [`missing_outgoing`](Self::missing_outgoing), but reads every
+    /// missing chunk at once.
+    pub fn resume_outgoing(
+        &self,
+        device_id: &[u8; 32],
+        resume: &TransferResume,
+    ) -> Option<Vec<TransferChunk>> {
+        let missing = self.missing_outgoing(device_id, resume)?;
+        Some(
+            missing
+                .into_iter()
+                .filter_map(|index| self.outgoing_chunk(resume.transfer_id, index)?.ok())
+                .collect(),
+        )
+    }
+
+    /// Whether an outgoing transfer is still active (not canceled or expired)
+    pub fn is_outgoing(&self, transfer_id: u64) -> bool {
+        self.outgoing
+            .lock()
+            .get(&transfer_id)
+            .is_some_and(|t| t.expires_at > Instant::now())
+    }
+
+    /// Record that a chunk of an outgoing transfer was sent
+    pub fn mark_sent(&self, transfer_id: u64, index: u32) {
+        if let Some(transfer) = self.outgoing.lock().get_mut(&transfer_id) {
+            transfer.sent.insert(index);
+        }
+    }
+
+    /// Release the source of a transfer the receiver completed, refused or
+    /// canceled
+    ///
+    /// Returns whether the transfer was known.
+    pub fn cancel_outgoing(&self, device_id: &[u8; 32], transfer_id: u64) -> bool {
+        let mut outgoing = self.outgoing.lock();
+        if outgoing
+            .get(&transfer_id)
+            .is_some_and(|t| &t.device_id == device_id)
+        {
+            outgoing.remove(&transfer_id);
+            return true;
+        }
+        false
+    }
+
+    /// Drop the chunks of an incoming transfer the sender canceled
+    ///
+    /// Chunks of the transfer that are still in flight are ignored. Returns
+    /// whether the transfer was known.
+    pub fn cancel_incoming(&self, device_id: &[u8; 32], transfer_id: u64) -> bool {
+        let key = (*device_id, transfer_id);
+        let removed = self.incoming.lock().remove(&key).is_some();
+        self.rejected
+            .lock()
+            .insert(key, Instant::now() + TRANSFER_TTL);
+        removed
+    }
+
+    /// Cancel an active transfer in either direction
+    ///
+    /// Returns the peer on the other end and the direction, or `None` if the
+    /// transfer is unknown.
+    pub fn cancel(&self, transfer_id: u64) -> Option<([u8; 32], TransferDirection)> {
+        if let Some(transfer) = self.outgoing.lock().remove(&transfer_id) {
+            return Some((transfer.device_id, TransferDirection::Outgoing));
+        }
+
+        let device_id = self
+            .incoming
+            .lock()
+            .keys()
+            .find(|(_, id)| *id == transfer_id)
+            .map(|(from, _)| *from)?;
+        self.cancel_incoming(&device_id, transfer_id);
+        Some((device_id, TransferDirection::Incoming))
+    }
+
+    /// Progress of incoming transfers and of outgoing ones not fully sent
+    pub fn progress(&self) -> Vec<TransferProgress> {
+        let now = Instant::now();
+        let mut active: Vec<TransferProgress> = self
+            .outgoing
+            .lock()
+            .iter()
+            .filter(|(_, t)| t.expires_at > now && t.sent.len() < t.total_chunks() as usize)
+            .map(|(transfer_id, t)| t.progress(*transfer_id))
+            .collect();
+
+        let mut incoming = self.incoming.lock();
+        self.retain_live(&mut incoming);
+        active.extend(
+            incoming
+                .iter()
+                .map(|((from, transfer_id), t)| t.progress(*from, *transfer_id)),
+        );
+        active
+    }
+
+    /// Progress of an outgoing transfer, including once fully sent
+    pub fn outgoing_progress(&self, transfer_id: u64) -> Option<TransferProgress> {
+        self.outgoing
+            .lock()
+            .get(&transfer_id)
+            .map(|t| t.progress(transfer_id))
+    }
+
+    /// Progress of an incoming transfer still being reassembled
+    pub fn incoming_progress(
+        &self,
+        device_id: &[u8; 32],
+        transfer_id: u64,
+    ) -> Option<TransferProgress> {
+        self.incoming
+            .lock()
+            .get(&(*device_id, transfer_id))
+            .map(|t| t.progress(*device_id, transfer_id))
+    }
+
+    /// Store a received chunk, returning the payload once all chunks arrived
+    ///
+    /// Chunks are written to a temporary file as they arrive. Duplicate
+    /// chunks are ignored. A payload that doesn't match the advertised hash
+    /// is discarded with an error. The first chunk of a new transfer larger
+    /// than the size limit or beyond the concurrency limits is refused with
+    /// [`ProtocolError::TransferRejected`]; later chunks of the same transfer
+    /// are dropped silently.
+    pub fn receive_chunk(
+        &self,
+        device_id: &[u8; 32],
+        chunk: TransferChunk,
+    ) -> Result<Option<ReceivedPayload>, ProtocolError> {
+        if chunk.total_chunks == 0
+            || chunk.index >= chunk.total_chunks
+            || chunk.total_chunks as u64 > chunk.total_bytes.max(1)
+        {
+            return Err(ProtocolError::InvalidFormat(format!(
+                "chunk {} out of range for transfer {}",
+                chunk.index, chunk.transfer_id
+            )));
+        }
+
+        let key = (*device_id, chunk.transfer_id);
+        {
+            let mut rejected = self.rejected.lock();
+            rejected.retain(|_, expires_at| *expires_at > Instant::now());
+            if rejected.contains_key(&key) {
+                return Ok(None);
+            }
+        }
+
+        let mut incoming = self.incoming.lock();
+        self.retain_live(&mut incoming);
+
+        let now = Instant::now();
+        if !incoming.contains_key(&key) {
+            let file = match self.limit_reached(&incoming, device_id, chunk.total_bytes) {
+                Some(reason) => Err(reason),
+                None => tempfile::tempfile().map_err(|e| format!("can't store chunks: {}", e)),
+            };
+            let file = file.map_err(|reason| self.reject(key, reason))?;
+            incoming.insert(
+                key,
+                IncomingTransfer {
+                    total_chunks: chunk.total_chunks,
+                    total_bytes: chunk.total_bytes,
+                    payload_hash: chunk.payload_hash,
+                    chunk_size: None,
+                    file,
+                    chunks: BTreeMap::new(),
+                    expires_at: now + TRANSFER_TTL,
+                    last_chunk_at: now,
+                },
+            );
+        }
+
+        let transfer = incoming.get_mut(&key).expect("transfer present");
+        if transfer.total_chunks != chunk.total_chunks
+            || transfer.total_bytes != chunk.total_bytes
+            || transfer.payload_hash != chunk.payload_hash
+        {
+            return Err(ProtocolError::InvalidFormat(format!(
+                "chunk {} doesn't match transfer {}",
+                chunk.index, chunk.transfer_id
+            )));
+        }
+
+        transfer.expires_at = now + TRANSFER_TTL;
+        transfer.last_chunk_at = now;
+        if !transfer.chunks.contains_key(&chunk.index) {
+            let len = chunk.data.len() as u64;
+            let offset = transfer.chunk_offset(chunk.index, len).ok_or_else(|| {
+                ProtocolError::InvalidFormat(format!(
+                    "chunk {} of transfer {} has the wrong size",
+                    chunk.index, chunk.transfer_id
+                ))
+            })?;
+            if let Err(e) = transfer.write_chunk(offset, &chunk.data) {
+                incoming.remove(&key);
+                return Err(self.reject(key, format!("can't store chunks: {}", e)));
+            }
+            if chunk.index != transfer.total_chunks - 1 {
+                transfer.chunk_size = Some(len);
+            }
+            transfer.chunks.insert(chunk.index, len);
+        }
+        if transfer.chunks.len() < transfer.total_chunks as usize {
+            return Ok(None);
+        }
+
+        let transfer = incoming.remove(&key).expect("transfer present");
+        // Hash outside the lock, so a large payload doesn't hold up other transfers
+        drop(incoming);
+        let mut payload = ReceivedPayload {
+            file: transfer.file,
+            len: transfer.total_bytes,
+        };
+        match payload.has_hash(&transfer.payload_hash) {
+            Ok(true) => Ok(Some(payload)),
+            Ok(false) => Err(ProtocolError::InvalidFormat(format!(
+                "hash mismatch for transfer {}",
+                chunk.transfer_id
+            ))),
+            Err(e) => Err(ProtocolError::InvalidFormat(format!(
+                "can't read transfer {}: {}",
+                chunk.transfer_id, e
+            ))),
+        }
+    }
+
+    /// Refuse a transfer, dropping any further chunks of it
+    fn reject(&self, key: ([u8; 32], u64), reason: String) -> ProtocolError {
+        self.rejected
+            .lock()
+            .insert(key, Instant::now() + TRANSFER_TTL);
+        ProtocolError::TransferRejected(format!("transfer {}: {}", key.1, reason))
+    }
+
+    /// Drop incoming transfers that expired or stalled, returning how many
+    pub fn reap_stalled(&self) -> usize {
+        let mut incoming = self.incoming.lock();
+        let before = incoming.len();
+        self.retain_live(&mut incoming);
+        before - incoming.len()
+    }
+
+    /// Number of incoming transfers being reassembled
+    pub fn incoming_count(&self) -> usize {
+        self.incoming.lock().len()
+    }
+
+    fn retain_live(&self, incoming: &mut HashMap<([u8; 32], u64), IncomingTransfer>) {
+        let now = Instant::now();
+        let stall_timeout = self.limits.stall_timeout;
+        incoming.retain(|(from, transfer_id), t| {
+            let live = t.expires_at > now && now.duration_since(t.last_chunk_at) < stall_timeout;
+            if !live {
+                tracing::debug!(
+                    "Dropping stalled transfer {} from {} ({}/{} chunks)",
+                    transfer_id,
+                    hex::encode(from),
+                    t.chunks.len(),
+                    t.total_chunks
+                );
+            }
+            live
+        });
+    }
+
+    /// Why a new transfer of `total_bytes` from `device_id` can't start, if
+    /// it can't
+    fn limit_reached(
+        &self,
+        incoming: &HashMap<([u8; 32], u64), IncomingTransfer>,
+        device_id: &[u8; 32],
+        total_bytes: u64,
+    ) -> Option<String> {
+        let limits = &self.limits;
+        if limits.max_bytes > 0 && total_bytes > limits.max_bytes {
+            return Some(format!(
+                "payload of {} bytes is too large (limit {})",
+                total_bytes, limits.max_bytes
+            ));
+        }
+        if limits.max_total > 0 && incoming.len() >= limits.max_total {
+            return Some(format!(
+                "too many concurrent transfers (limit {})",
+                limits.max_total
+            ));
+        }
+
+        let from_peer = incoming
+            .keys()
+            .filter(|(from, _)| from == device_id)
+            .count();
+        if limits.max_per_peer > 0 && from_peer >= limits.max_per_peer {
+            return Some(format!(
+                "too many concurrent transfers from this device (limit {})",
+                limits.max_per_peer
+            ));
+        }
+        None
+    }
+
+    /// Resume requests for unfinished transfers from a device
+    pub fn pending_resumes(&self, device_id: &[u8; 32]) -> Vec<TransferResume> {
+        let now = Instant::now();
+        self.incoming
+            .lock()
+            .iter()
+            .filter(|((from, _), t)| from == device_id && t.expires_at > now)
+            .map(|((_, transfer_id), t)| TransferResume {
+                transfer_id: *transfer_id,
+                received_indices: t.chunks.keys().copied().collect(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_resends_only_missing_chunks() {
+        let sender = TransferTracker::new();
+        let receiver = TransferTracker::new();
+        let sender_id = [1u8; 32];
+        let receiver_id = [2u8; 32];
+
+        let payload: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+        let chunks = sender.start_outgoing(receiver_id, payload.clone(), 1024);
+        assert_eq!(chunks.len(), 10);
+        let transfer_id = chunks[0].transfer_id;
+
+        // Connection drops after half the chunks
+        for chunk in chunks.into_iter().take(5) {
+            assert!(receiver.receive_chunk(&sender_id, chunk).unwrap().is_none());
+        }
+
+        let resumes = receiver.pending_resumes(&sender_id);
+        assert_eq!(resumes.len(), 1);
+        assert_eq!(resumes[0].transfer_id, transfer_id);
+        assert_eq!(resumes[0].received_indices, vec![0, 1, 2, 3, 4]);
+
+        let missing = sender.resume_outgoing(&receiver_id, &resumes[0]).unwrap();
+        let indices: Vec<u32> = missing.iter().map(|c| c.index).collect();
+        assert_eq!(indices, vec![5, 6, 7, 8, 9]);
+
+        let mut completed = None;
+        for chunk in missing {
+            completed = receiver.receive_chunk(&sender_id, chunk).unwrap();
+        }
+        let completed = completed.expect("transfer complete");
+        assert_eq!(completed.len(), payload.len() as u64);
+        assert_eq!(completed.into_bytes().unwrap(), payload);
+        assert!(receiver.pending_resumes(&sender_id).is_empty());
+
+        // A final resume with everything received releases the source
+        let done = TransferResume {
+            transfer_id,
+            received_indices: (0..10).collect(),
+        };
+        assert!(sender
+            .resume_outgoing(&receiver_id, &done)
+            .unwrap()
+            .is_empty());
+        assert!(sender.resume_outgoing(&receiver_id, &done).is_none());
+    }
+
+    #[test]
+    fn test_resume_rejected_for_other_device_or_corrupt_payload() {
+        let sender = TransferTracker::new();
+        let chunks = sender.start_outgoing([2u8; 32], vec![7u8; 100], 64);
+        let resume = TransferResume {
+            transfer_id: chunks[0].transfer_id,
+            received_indices: vec![],
+        };
+        assert!(sender.resume_outgoing(&[3u8; 32], &resume).is_none());
+
+        let receiver = TransferTracker::new();
+        let mut corrupt = chunks.clone();
+        corrupt[1].data[0] ^= 0xFF;
+        assert!(receiver
+            .receive_chunk(&[1u8; 32], corrupt[0].clone())
+            .unwrap()
+            .is_none());
+        assert!(receiver
+            .receive_chunk(&[1u8; 32], corrupt[1].clone())
+            .is_err());
+    }
+
+    #[test]
+    fn test_transfers_beyond_limit_rejected_and_stalled_reaped() {
+        let receiver = TransferTracker::with_limits(TransferLimits {
+            max_per_peer: 2,
+            max_total: 3,
+            max_bytes: 0,
+            stall_timeout: Duration::from_millis(50),
+        });
+        let sender = TransferTracker::new();
+        let (peer_a, peer_b) = ([1u8; 32], [2u8; 32]);
+        let start = |to| sender.start_outgoing(to, vec![9u8; 100], 10);
+
+        // Two transfers from A fill its per-peer limit
+        let a1 = start([0u8; 32]);
+        let a2 = start([0u8; 32]);
+        let a3 = start([0u8; 32]);
+        assert!(receiver
+            .receive_chunk(&peer_a, a1[0].clone())
+            .unwrap()
+            .is_none());
+        assert!(receiver
+            .receive_chunk(&peer_a, a2[0].clone())
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            receiver.receive_chunk(&peer_a, a3[0].clone()),
+            Err(ProtocolError::TransferRejected(_))
+        ));
+        // Later chunks of a refused transfer are dropped without another error
+        assert!(receiver
+            .receive_chunk(&peer_a, a3[1].clone())
+            .unwrap()
+            .is_none());
+
+        // B gets one slot before the global limit is hit
+        let b1 = start([0u8; 32]);
+        let b2 = start([0u8; 32]);
+        assert!(receiver
+            .receive_chunk(&peer_b, b1[0].clone())
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            receiver.receive_chunk(&peer_b, b2[0].clone()),
+            Err(ProtocolError::TransferRejected(_))
+        ));
+        assert_eq!(receiver.incoming_count(), 3);
+
+        // Chunks of accepted transfers still go through
+        assert!(receiver
+            .receive_chunk(&peer_a, a1[1].clone())
+            .unwrap()
+            .is_none());
+
+        // Transfers without new chunks are reaped, freeing their slots
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(receiver.reap_stalled(), 3);
+        assert!(receiver.pending_resumes(&peer_a).is_empty());
+        let b3 = start([0u8; 32]);
+        assert!(receiver
+            .receive_chunk(&peer_b, b3[0].clone())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_oversized_or_inconsistent_chunks_rejected() {
+        let receiver = TransferTracker::with_limits(TransferLimits {
+            max_bytes: 1000,
+            ..TransferLimits::default()
+        });
+        let sender = TransferTracker::new();
+        let peer = [1u8; 32];
+
+        // A transfer over the size limit is refused before anything is stored
+        let large = sender.start_outgoing([0u8; 32], vec![1u8; 1001], 100);
+        assert!(matches!(
+            receiver.receive_chunk(&peer, large[0].clone()),
+            Err(ProtocolError::TransferRejected(_))
+        ));
+        assert!(receiver
+            .receive_chunk(&peer, large[1].clone())
+            .unwrap()
+            .is_none());
+        assert_eq!(receiver.incoming_count(), 0);
+
+        // Understating the size doesn't get more data through
+        let mut understated = large[2].clone();
+        understated.transfer_id += 1;
+        understated.total_bytes = 1000;
+        understated.data.push(0);
+        assert!(matches!(
+            receiver.receive_chunk(&peer, understated),
+            Err(ProtocolError::InvalidFormat(_))
+        ));
+
+        // Chunks must add up to the advertised size, whatever order they come in
+        let chunks = sender.start_outgoing([0u8; 32], vec![2u8; 250], 100);
+        let mut short = chunks[1].clone();
+        short.data.pop();
+        assert!(receiver
+            .receive_chunk(&peer, chunks[2].clone())
+            .unwrap()
+            .is_none());
+        assert!(receiver.receive_chunk(&peer, short).is_err());
+        assert!(receiver
+            .receive_chunk(&peer, chunks[1].clone())
+            .unwrap()
+            .is_none());
+        let payload = receiver
+            .receive_chunk(&peer, chunks[0].clone())
+            .unwrap()
+            .expect("transfer complete");
+        assert_eq!(payload.into_bytes().unwrap(), vec![2u8; 250]);
+    }
+
+    #[test]
+    fn test_progress_reported_and_cancel_frees_buffers() {
+        let sender = TransferTracker::new();
+        let receiver = TransferTracker::new();
+        let (sender_id, receiver_id) = ([1u8; 32], [2u8; 32]);
+
+        let chunks = sender.start_outgoing(receiver_id, vec![3u8; 4000], 1000);
+        let transfer_id = chunks[0].transfer_id;
+        assert!(receiver.progress().is_empty());
+
+        let mut last_sent = 0;
+        let mut last_received = 0;
+        for chunk in chunks.iter().take(3) {
+            sender.mark_sent(transfer_id, chunk.index);
+            assert!(receiver
+                .receive_chunk(&sender_id, chunk.clone())
+                .unwrap()
+                .is_none());
+
+            let sent = sender.progress();
+            assert_eq!(sent.len(), 1);
+            assert_eq!(sent[0].direction, TransferDirection::Outgoing);
+            assert_eq!(sent[0].total_bytes, 4000);
+            assert!(sent[0].percent() > last_sent as f64);
+            last_sent = sent[0].percent() as u32;
+
+            let received = receiver.progress();
+            assert_eq!(received.len(), 1);
+            assert_eq!(received[0].direction, TransferDirection::Incoming);
+            assert_eq!(received[0].device_id, sender_id);
+            assert_eq!(received[0].total_bytes, 4000);
+            assert!(received[0].transferred_bytes > last_received);
+            last_received = received[0].transferred_bytes;
+        }
+        assert_eq!(last_received, 3000);
+
+        // The receiver cancels: its chunks are dropped and later ones ignored
+        assert_eq!(
+            receiver.cancel(transfer_id),
+            Some((sender_id, TransferDirection::Incoming))
+        );
+        assert_eq!(receiver.incoming_count(), 0);
+        assert!(receiver.progress().is_empty());
+        assert!(receiver
+            .receive_chunk(&sender_id, chunks[3].clone())
+            .unwrap()
+            .is_none());
+        assert_eq!(receiver.cancel(transfer_id), None);
+
+        // The sender drops its source when told
+        assert!(!sender.cancel_outgoing(&[9u8; 32], transfer_id));
+        assert!(sender.cancel_outgoing(&receiver_id, transfer_id));
+        assert!(!sender.is_outgoing(transfer_id));
+        assert!(sender.progress().is_empty());
+    }
+
+    #[test]
+    fn test_progress_milestones_skip_small_chunks() {
+        let sender = TransferTracker::new();
+        let chunks = sender.start_outgoing([2u8; 32], vec![0u8; 100_000], 100);
+        let transfer_id = chunks[0].transfer_id;
+
+        // Ten 0.1% chunks make one milestone
+        let mut milestones = 0;
+        for chunk in chunks.iter().take(20) {
+            sender.mark_sent(transfer_id, chunk.index);
+            let progress = sender.outgoing_progress(transfer_id).unwrap();
+            if progress.is_milestone(chunk.data.len() as u64) {
+                milestones += 1;
+            }
+        }
+        assert_eq!(milestones, 2);
+
+        // Completion always is one, and progress is kept once fully sent
+        for chunk in &chunks {
+            sender.mark_sent(transfer_id, chunk.index);
+        }
+        let done = sender.outgoing_progress(transfer_id).unwrap();
+        assert_eq!(done.transferred_bytes, 100_000);
+        assert!(done.is_milestone(0));
+        assert!(sender.progress().is_empty());
+        assert!(sender.incoming_progress(&[2u8; 32], transfer_id).is_none());
+    }
+}
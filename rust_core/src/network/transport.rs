@@ -1,22 +1,38 @@
 //! QUIC transport for P2P connections
 
-use quinn::{ClientConfig, Connection, Endpoint, ServerConfig, TransportConfig, VarInt};
+use futures::stream::{FuturesUnordered, StreamExt};
+use quinn::{
+    ClientConfig, Connection, Endpoint, ServerConfig, TransportConfig, VarInt, ZeroRttAccepted,
+};
 use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
 
-use crate::crypto::KEY_SIZE;
+use crate::clock::{Clock, SystemClock};
+use crate::crypto::{ChannelKey, ChannelRatchet, KeyAlgorithm, NonceSequence, KEY_SIZE};
 use crate::error::NetworkError;
-use crate::protocol::{Frame, Message};
-use std::time::SystemTime;
+use crate::protocol::{Capabilities, Frame, Message};
 
 /// Max idle timeout for connections
 const IDLE_TIMEOUT_SECS: u64 = 30;
 
 /// Keep-alive interval
-const KEEP_ALIVE_SECS: u64 = 5;
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Messages sent under one session key before it is rotated
+const MAX_SESSION_MESSAGES: u64 = 1000;
+
+/// Default connection time after which a session key is rotated
+pub const DEFAULT_SESSION_KEY_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Number of peers whose session tickets are kept for 0-RTT resumption
+const SESSION_TICKET_CACHE_SIZE: usize = 256;
+
+/// Delay before the next candidate address is tried while earlier attempts
+/// are still connecting
+pub const CONNECT_STAGGER: Duration = Duration::from_millis(250);
 
 /// QUIC transport layer
 pub struct QuicTransport {
@@ -61,14 +77,108 @@ impl QuicTransport {
 
     /// Connect to a peer
     pub async fn connect(&self, addr: SocketAddr) -> Result<PeerConnection, NetworkError> {
-        let connection = self
+        self.connect_to_device(addr, None).await
+    }
+
+    /// Connect to a peer, resuming with 0-RTT if we hold a ticket for it
+    ///
+    /// Session tickets are cached in memory per device ID, so a reconnect to
+    /// a known device can send its first messages without waiting for the
+    /// handshake. Tickets are not persisted; the first connection after a
+    /// restart always does a full handshake.
+    pub async fn connect_to_device(
+        &self,
+        addr: SocketAddr,
+        device_id: Option<&[u8; 32]>,
+    ) -> Result<PeerConnection, NetworkError> {
+        let connecting = self
             .endpoint
-            .connect(addr, "toss")
-            .map_err(|e| NetworkError::ConnectionFailed(e.to_string()))?
-            .await
+            .connect(addr, &ticket_server_name(device_id))
             .map_err(|e| NetworkError::ConnectionFailed(e.to_string()))?;
 
-        Ok(PeerConnection::new(connection, vec![addr], true))
+        match connecting.into_0rtt() {
+            Ok((connection, accepted)) => Ok(PeerConnection::with_zero_rtt(
+                connection,
+                vec![addr],
+                is_local_address(&addr),
+                accepted,
+            )),
+            // No usable ticket: fall back to a full handshake
+            Err(connecting) => {
+                let connection = connecting
+                    .await
+                    .map_err(|e| NetworkError::ConnectionFailed(e.to_string()))?;
+                Ok(PeerConnection::new(
+                    connection,
+                    vec![addr],
+                    is_local_address(&addr),
+                ))
+            }
+        }
+    }
+
+    /// Connect to a peer advertising several addresses, e.g. IPv4, IPv6 and
+    /// VPN addresses from its mDNS announcement
+    ///
+    /// Addresses are tried in order, happy-eyeballs style: each attempt
+    /// starts `stagger` after the previous one, or as soon as it fails, and
+    /// the first to complete its handshake wins. The other attempts are
+    /// dropped, which abandons them.
+    pub async fn connect_any(
+        &self,
+        addrs: &[SocketAddr],
+        device_id: Option<&[u8; 32]>,
+        stagger: Duration,
+    ) -> Result<PeerConnection, NetworkError> {
+        let mut remaining = addrs.iter();
+        let mut attempts = FuturesUnordered::new();
+        let mut last_error =
+            NetworkError::ConnectionFailed("No addresses to connect to".to_string());
+
+        loop {
+            if attempts.is_empty() {
+                let Some(&addr) = remaining.next() else {
+                    return Err(last_error);
+                };
+                attempts.push(self.connect_confirmed(addr, device_id));
+            }
+
+            tokio::select! {
+                Some(result) = attempts.next() => match result {
+                    Ok(conn) => return Ok(conn),
+                    Err(e) => {
+                        tracing::debug!("Connection attempt failed: {}", e);
+                        last_error = e;
+                        if let Some(&addr) = remaining.next() {
+                            attempts.push(self.connect_confirmed(addr, device_id));
+                        }
+                    }
+                },
+                _ = tokio::time::sleep(stagger), if remaining.len() > 0 => {
+                    if let Some(&addr) = remaining.next() {
+                        attempts.push(self.connect_confirmed(addr, device_id));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Connect and wait for the handshake, which a 0-RTT connection would
+    /// otherwise skip before the peer has answered
+    async fn connect_confirmed(
+        &self,
+        addr: SocketAddr,
+        device_id: Option<&[u8; 32]>,
+    ) -> Result<PeerConnection, NetworkError> {
+        let conn = self.connect_to_device(addr, device_id).await?;
+        conn.wait_for_handshake().await;
+        if !conn.is_connected() {
+            return Err(NetworkError::ConnectionFailed(format!(
+                "Handshake with {} failed",
+                addr
+            )));
+        }
+        Ok(conn)
     }
 
     /// Accept an incoming connection
@@ -76,7 +186,11 @@ impl QuicTransport {
         let incoming = self.endpoint.accept().await?;
         let addr = incoming.remote_address();
         let connection = incoming.await.ok()?;
-        Some(PeerConnection::new(connection, vec![addr], true))
+        Some(PeerConnection::new(
+            connection,
+            vec![addr],
+            is_local_address(&addr),
+        ))
     }
 
     /// Close the endpoint
@@ -85,16 +199,44 @@ impl QuicTransport {
     }
 }
 
+/// Whether `addr` is on the local network: loopback, private or link-local
+pub fn is_local_address(addr: &SocketAddr) -> bool {
+    is_local_ip(addr.ip())
+}
+
+fn is_local_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_local_ip(IpAddr::V4(ip)),
+            // Loopback, unique local (fc00::/7) or link-local (fe80::/10)
+            None => {
+                ip.is_loopback()
+                    || (ip.segments()[0] & 0xfe00) == 0xfc00
+                    || (ip.segments()[0] & 0xffc0) == 0xfe80
+            }
+        },
+    }
+}
+
 /// Session tracking for key rotation
 struct SessionTracker {
-    created_at: SystemTime,
+    clock: Arc<dyn Clock>,
+    max_age: Duration,
+    created_at: chrono::DateTime<chrono::Utc>,
     message_count: u64,
 }
 
 impl SessionTracker {
     fn new() -> Self {
+        Self::with_policy(DEFAULT_SESSION_KEY_MAX_AGE, Arc::new(SystemClock))
+    }
+
+    fn with_policy(max_age: Duration, clock: Arc<dyn Clock>) -> Self {
         Self {
-            created_at: SystemTime::now(),
+            created_at: clock.now(),
+            clock,
+            max_age,
             message_count: 0,
         }
     }
@@ -104,24 +246,23 @@ impl SessionTracker {
     }
 
     fn should_rotate(&self) -> bool {
-        const MAX_MESSAGES: u64 = 1000;
-        const MAX_AGE_SECS: u64 = 24 * 60 * 60; // 24 hours
-
-        if self.message_count >= MAX_MESSAGES {
+        if self.message_count >= MAX_SESSION_MESSAGES {
             return true;
         }
 
-        if let Ok(age) = SystemTime::now().duration_since(self.created_at) {
-            if age.as_secs() >= MAX_AGE_SECS {
-                return true;
-            }
+        // A zero max age turns time-based rotation off
+        if self.max_age.is_zero() {
+            return false;
+        }
+        match (self.clock.now() - self.created_at).to_std() {
+            Ok(age) => age >= self.max_age,
+            // The clock went backwards
+            Err(_) => false,
         }
-
-        false
     }
 
     fn reset(&mut self) {
-        self.created_at = SystemTime::now();
+        self.created_at = self.clock.now();
         self.message_count = 0;
     }
 }
@@ -133,24 +274,112 @@ pub struct PeerConnection {
     session_key: Mutex<Option<[u8; KEY_SIZE]>>,
     peer_device_id: Mutex<Option<[u8; 32]>>,
     peer_name: Mutex<Option<String>>,
+    /// Read from sync code on every send, so never behind an async lock
+    peer_capabilities: parking_lot::RwLock<Option<Capabilities>>,
+    peer_identity_algorithm: parking_lot::RwLock<Option<KeyAlgorithm>>,
     is_local: bool,
-    session_tracker: Mutex<SessionTracker>,
+    session_tracker: parking_lot::Mutex<SessionTracker>,
+    nonce_sequence: Mutex<NonceSequence>,
+    channel_ratchet: Mutex<Option<ChannelRatchet>>,
+    /// `None` while 0-RTT data is in flight, then whether the server accepted it
+    handshake: watch::Receiver<Option<bool>>,
+    /// Frames sent as 0-RTT data, kept to resend if the server rejects them
+    early_frames: Arc<parking_lot::Mutex<Option<Vec<Vec<u8>>>>>,
 }
 
 impl PeerConnection {
     /// Create a new peer connection
     pub fn new(connection: Connection, addresses: Vec<SocketAddr>, is_local: bool) -> Self {
+        let (_, handshake) = watch::channel(Some(false));
+        Self::with_handshake(connection, addresses, is_local, handshake, None)
+    }
+
+    /// Create a peer connection that is still in its 0-RTT phase
+    ///
+    /// If the server rejects the early data, the frames sent during that
+    /// phase are resent once the full handshake completes.
+    fn with_zero_rtt(
+        connection: Connection,
+        addresses: Vec<SocketAddr>,
+        is_local: bool,
+        accepted: ZeroRttAccepted,
+    ) -> Self {
+        let (handshake_tx, handshake) = watch::channel(None);
+        let peer = Self::with_handshake(
+            connection.clone(),
+            addresses,
+            is_local,
+            handshake,
+            Some(Vec::new()),
+        );
+
+        let early_frames = peer.early_frames.clone();
+        tokio::spawn(async move {
+            let accepted = accepted.await;
+            let frames = early_frames.lock().take().unwrap_or_default();
+            if !accepted {
+                tracing::debug!(
+                    "0-RTT rejected by {}, resending {} frames",
+                    connection.remote_address(),
+                    frames.len()
+                );
+                for frame in frames {
+                    if let Err(e) = send_frame(&connection, &frame).await {
+                        tracing::warn!("Failed to resend rejected 0-RTT frame: {}", e);
+                    }
+                }
+            }
+            let _ = handshake_tx.send(Some(accepted));
+        });
+
+        peer
+    }
+
+    fn with_handshake(
+        connection: Connection,
+        addresses: Vec<SocketAddr>,
+        is_local: bool,
+        handshake: watch::Receiver<Option<bool>>,
+        early_frames: Option<Vec<Vec<u8>>>,
+    ) -> Self {
         Self {
             connection,
             addresses,
             session_key: Mutex::new(None),
             peer_device_id: Mutex::new(None),
             peer_name: Mutex::new(None),
+            peer_capabilities: parking_lot::RwLock::new(None),
+            peer_identity_algorithm: parking_lot::RwLock::new(None),
             is_local,
-            session_tracker: Mutex::new(SessionTracker::new()),
+            session_tracker: parking_lot::Mutex::new(SessionTracker::new()),
+            nonce_sequence: Mutex::new(NonceSequence::new()),
+            channel_ratchet: Mutex::new(None),
+            handshake,
+            early_frames: Arc::new(parking_lot::Mutex::new(early_frames)),
         }
     }
 
+    /// Check if the connection was resumed with accepted 0-RTT data
+    ///
+    /// Returns `None` while the handshake is still in progress.
+    pub fn is_resumed(&self) -> Option<bool> {
+        *self.handshake.borrow()
+    }
+
+    /// Wait until the handshake is complete
+    ///
+    /// Returns whether 0-RTT data was accepted; `false` for connections that
+    /// did a full handshake.
+    pub async fn wait_for_handshake(&self) -> bool {
+        let mut handshake = self.handshake.clone();
+        let resumed = match handshake.wait_for(|state| state.is_some()).await {
+            Ok(state) => state.unwrap_or(false),
+            // The resend task is gone, so the handshake failed
+            Err(_) => false,
+        };
+        resumed
+    }
+
     /// Check if connection is still active
     pub fn is_connected(&self) -> bool {
         self.connection.close_reason().is_none()
@@ -171,9 +400,50 @@ impl PeerConnection {
         self.is_local
     }
 
+    /// Override whether the connection counts as local, which otherwise
+    /// follows the peer's address
+    pub fn with_local(mut self, is_local: bool) -> Self {
+        self.is_local = is_local;
+        self
+    }
+
+    /// Current round-trip time estimate of the connection
+    pub fn rtt(&self) -> Duration {
+        self.connection.rtt()
+    }
+
     /// Set session key
+    ///
+    /// Starts a fresh nonce sequence, since nonces only need to be unique per key,
+    /// and drops the clipboard channel ratchet derived from the old key.
     pub async fn set_session_key(&self, key: [u8; KEY_SIZE]) {
-        *self.session_key.lock().await = Some(key);
+        let mut session_key = self.session_key.lock().await;
+        *self.nonce_sequence.lock().await = NonceSequence::new();
+        *self.channel_ratchet.lock().await = None;
+        *session_key = Some(key);
+    }
+
+    /// Take the next clipboard channel key for messages sent by `local_id`
+    ///
+    /// The ratchet is started from the session key on first use and advances
+    /// with every call.
+    pub async fn next_channel_key(&self, local_id: &[u8; 32]) -> Result<ChannelKey, NetworkError> {
+        let session_key = self.session_key.lock().await;
+        let session_key = session_key.as_ref().ok_or(NetworkError::NotAuthenticated)?;
+
+        let mut ratchet = self.channel_ratchet.lock().await;
+        if ratchet.is_none() {
+            *ratchet = Some(
+                ChannelRatchet::new(session_key, local_id)
+                    .map_err(|e| NetworkError::Transport(e.to_string()))?,
+            );
+        }
+
+        ratchet
+            .as_mut()
+            .expect("ratchet initialized")
+            .next_key()
+            .map_err(|e| NetworkError::Transport(e.to_string()))
     }
 
     /// Set peer device ID
@@ -200,22 +470,41 @@ impl PeerConnection {
             .and_then(|guard| guard.clone())
     }
 
-    /// Send raw bytes
-    pub async fn send_raw(&self, data: &[u8]) -> Result<(), NetworkError> {
-        let mut send = self
-            .connection
-            .open_uni()
-            .await
-            .map_err(|e| NetworkError::Transport(e.to_string()))?;
+    /// Set the capabilities the peer advertised in its `DeviceInfo`
+    pub async fn set_peer_capabilities(&self, capabilities: Capabilities) {
+        *self.peer_capabilities.write() = Some(capabilities);
+    }
 
-        send.write_all(data)
-            .await
-            .map_err(|e| NetworkError::Transport(e.to_string()))?;
+    /// Get the peer's advertised capabilities, if it has sent any
+    pub fn peer_capabilities(&self) -> Option<Capabilities> {
+        *self.peer_capabilities.read()
+    }
 
-        send.finish()
-            .map_err(|e| NetworkError::Transport(e.to_string()))?;
+    /// Set the identity key algorithm the peer advertised in its `DeviceInfo`
+    pub async fn set_peer_identity_algorithm(&self, algorithm: KeyAlgorithm) {
+        *self.peer_identity_algorithm.write() = Some(algorithm);
+    }
 
-        Ok(())
+    /// Get the peer's identity key algorithm, Ed25519 until it sends `DeviceInfo`
+    pub fn peer_identity_algorithm(&self) -> KeyAlgorithm {
+        self.peer_identity_algorithm.read().unwrap_or_default()
+    }
+
+    /// Send raw bytes
+    ///
+    /// Always waits for the handshake, since raw bytes may not be safe to
+    /// replay.
+    pub async fn send_raw(&self, data: &[u8]) -> Result<(), NetworkError> {
+        self.wait_for_handshake().await;
+        send_frame(&self.connection, data).await
+    }
+
+    /// Send a replay-safe frame, as 0-RTT data if the handshake is pending
+    async fn send_early(&self, data: &[u8]) -> Result<(), NetworkError> {
+        if let Some(frames) = self.early_frames.lock().as_mut() {
+            frames.push(data.to_vec());
+        }
+        send_frame(&self.connection, data).await
     }
 
     /// Receive raw bytes
@@ -238,8 +527,7 @@ impl PeerConnection {
     pub async fn send_message(&self, message: &Message) -> Result<(), NetworkError> {
         // Increment message count (only for non-rotation messages)
         if !matches!(message, Message::KeyRotation(_)) {
-            let mut tracker = self.session_tracker.lock().await;
-            tracker.increment_message_count();
+            self.session_tracker.lock().increment_message_count();
         }
 
         let key = self.session_key.lock().await;
@@ -250,32 +538,64 @@ impl PeerConnection {
             .serialize()
             .map_err(|e| NetworkError::Transport(e.to_string()))?;
 
-        let frame = Frame::encrypt(&header, &payload, key)
+        let nonce = self
+            .nonce_sequence
+            .lock()
+            .await
+            .next_nonce()
+            .map_err(|e| NetworkError::Transport(e.to_string()))?;
+
+        let frame = Frame::encrypt_with_nonce(&header, &payload, key, &nonce)
             .map_err(|e| NetworkError::Transport(e.to_string()))?;
 
-        self.send_raw(&frame.to_bytes()).await
+        // 0-RTT data can be replayed by an attacker, so only idempotent
+        // handshake messages may go out before the handshake is confirmed
+        if is_replay_safe(message) {
+            self.send_early(&frame.to_bytes()).await
+        } else {
+            self.send_raw(&frame.to_bytes()).await
+        }
+    }
+
+    /// Set how long a session key may be used before it is rotated
+    ///
+    /// The age is measured with `clock` from now; a zero `max_age` leaves
+    /// only the message and nonce limits.
+    pub fn set_key_rotation_policy(&self, max_age: Duration, clock: Arc<dyn Clock>) {
+        *self.session_tracker.lock() = SessionTracker::with_policy(max_age, clock);
     }
 
     /// Check if session key should be rotated
     pub async fn should_rotate_key(&self) -> bool {
-        let tracker = self.session_tracker.lock().await;
-        tracker.should_rotate()
+        let due = self.session_tracker.lock().should_rotate();
+        due || self.nonce_sequence.lock().await.needs_rotation()
+    }
+
+    /// When the session key in use was set up or last rotated
+    pub fn session_key_since(&self) -> chrono::DateTime<chrono::Utc> {
+        self.session_tracker.lock().created_at
     }
 
     /// Reset session tracker after rotation
     pub async fn reset_session_tracker(&self) {
-        let mut tracker = self.session_tracker.lock().await;
-        tracker.reset();
+        self.session_tracker.lock().reset();
     }
 
     /// Receive and decrypt a message
     pub async fn receive_message(&self) -> Result<Message, NetworkError> {
+        let data = self.receive_raw().await?;
+        self.decrypt_message(&data).await
+    }
+
+    /// Decrypt a frame read with [`receive_raw`](Self::receive_raw)
+    ///
+    /// The session key is only locked once the frame is in, so waiting for
+    /// one doesn't hold up sends.
+    pub async fn decrypt_message(&self, data: &[u8]) -> Result<Message, NetworkError> {
         let key = self.session_key.lock().await;
         let key = key.as_ref().ok_or(NetworkError::NotAuthenticated)?;
 
-        let data = self.receive_raw().await?;
-
-        let frame = Frame::from_bytes(&data).map_err(|e| NetworkError::Transport(e.to_string()))?;
+        let frame = Frame::from_bytes(data).map_err(|e| NetworkError::Transport(e.to_string()))?;
 
         let (header, payload) = frame
             .decrypt(key)
@@ -290,6 +610,42 @@ impl PeerConnection {
     }
 }
 
+/// Check whether a message may be sent as 0-RTT data
+///
+/// Only `Ping` and the `DeviceInfo` greeting qualify: receiving either twice
+/// has no effect beyond receiving it once.
+fn is_replay_safe(message: &Message) -> bool {
+    matches!(message, Message::Ping(_) | Message::DeviceInfo(_))
+}
+
+/// Send one frame on its own unidirectional stream
+async fn send_frame(connection: &Connection, data: &[u8]) -> Result<(), NetworkError> {
+    let mut send = connection
+        .open_uni()
+        .await
+        .map_err(|e| NetworkError::Transport(e.to_string()))?;
+
+    send.write_all(data)
+        .await
+        .map_err(|e| NetworkError::Transport(e.to_string()))?;
+
+    send.finish()
+        .map_err(|e| NetworkError::Transport(e.to_string()))?;
+
+    Ok(())
+}
+
+/// TLS server name used to key the session ticket cache
+///
+/// Certificates are not verified (peers authenticate via pairing), so the
+/// name only selects which cached ticket to resume with.
+fn ticket_server_name(device_id: Option<&[u8; 32]>) -> String {
+    match device_id {
+        Some(id) => format!("{}.toss", hex::encode(&id[..16])),
+        None => "toss".to_string(),
+    }
+}
+
 /// Generate a self-signed certificate for QUIC
 fn generate_self_signed_cert(
 ) -> Result<(CertificateDer<'static>, PrivateKeyDer<'static>), Box<dyn std::error::Error>> {
@@ -310,7 +666,7 @@ fn configure_server(
 
     let mut transport = TransportConfig::default();
     transport.max_idle_timeout(Some(Duration::from_secs(IDLE_TIMEOUT_SECS).try_into()?));
-    transport.keep_alive_interval(Some(Duration::from_secs(KEEP_ALIVE_SECS)));
+    transport.keep_alive_interval(Some(KEEP_ALIVE_INTERVAL));
 
     server_config.transport_config(Arc::new(transport));
 
@@ -318,11 +674,15 @@ fn configure_server(
 }
 
 /// Configure QUIC client (skip certificate verification for P2P)
+///
+/// Session tickets are cached in memory so reconnects can use 0-RTT.
 fn configure_client() -> Result<ClientConfig, Box<dyn std::error::Error>> {
-    let crypto = rustls::ClientConfig::builder()
+    let mut crypto = rustls::ClientConfig::builder()
         .dangerous()
         .with_custom_certificate_verifier(Arc::new(SkipServerVerification))
         .with_no_client_auth();
+    crypto.enable_early_data = true;
+    crypto.resumption = rustls::client::Resumption::in_memory_sessions(SESSION_TICKET_CACHE_SIZE);
 
     let mut client_config = ClientConfig::new(Arc::new(
         quinn::crypto::rustls::QuicClientConfig::try_from(crypto)?,
@@ -330,7 +690,7 @@ fn configure_client() -> Result<ClientConfig, Box<dyn std::error::Error>> {
 
     let mut transport = TransportConfig::default();
     transport.max_idle_timeout(Some(Duration::from_secs(IDLE_TIMEOUT_SECS).try_into()?));
-    transport.keep_alive_interval(Some(Duration::from_secs(KEEP_ALIVE_SECS)));
+    transport.keep_alive_interval(Some(KEEP_ALIVE_INTERVAL));
 
     client_config.transport_config(Arc::new(transport));
 
@@ -406,4 +766,174 @@ mod tests {
         let transport = transport.unwrap();
         assert_ne!(transport.local_addr().port(), 0);
     }
+
+    #[test]
+    fn test_local_addresses() {
+        for local in [
+            "127.0.0.1:1",
+            "10.1.2.3:1",
+            "172.16.0.1:1",
+            "192.168.1.20:1",
+            "169.254.0.1:1",
+            "[::1]:1",
+            "[fd12::1]:1",
+            "[fe80::1]:1",
+            "[::ffff:192.168.1.20]:1",
+        ] {
+            assert!(is_local_address(&local.parse().unwrap()), "{}", local);
+        }
+        for remote in [
+            "8.8.8.8:1",
+            "172.32.0.1:1",
+            "[2001:db8::1]:1",
+            "[::ffff:1.1.1.1]:1",
+        ] {
+            assert!(!is_local_address(&remote.parse().unwrap()), "{}", remote);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_any_skips_unreachable_addresses() {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let client = QuicTransport::new(addr).await.unwrap();
+        let server = QuicTransport::new(addr).await.unwrap();
+
+        // Bound but never answering, like an address on an unrouted interface
+        let silent: Vec<_> = (0..2)
+            .map(|_| std::net::UdpSocket::bind("127.0.0.1:0").unwrap())
+            .collect();
+        let candidates = [
+            silent[0].local_addr().unwrap(),
+            "[2001:db8::1]:9".parse().unwrap(),
+            silent[1].local_addr().unwrap(),
+            server.local_addr(),
+        ];
+
+        let started = std::time::Instant::now();
+        let (conn, accepted) = tokio::join!(
+            client.connect_any(&candidates, None, CONNECT_STAGGER),
+            server.accept()
+        );
+        let conn = conn.unwrap();
+        assert!(accepted.is_some());
+        assert_eq!(conn.remote_addr(), server.local_addr());
+        // Well before the unreachable attempts would have timed out
+        assert!(started.elapsed() < Duration::from_secs(IDLE_TIMEOUT_SECS / 4));
+
+        assert!(client
+            .connect_any(&[], None, CONNECT_STAGGER)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rotation_at_nonce_threshold() {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let client = QuicTransport::new(addr).await.unwrap();
+        let server = QuicTransport::new(addr).await.unwrap();
+
+        let (conn, accepted) = tokio::join!(client.connect(server.local_addr()), server.accept());
+        let conn = conn.unwrap();
+        let _accepted = accepted.unwrap();
+        conn.set_session_key([3u8; KEY_SIZE]).await;
+
+        *conn.nonce_sequence.lock().await =
+            NonceSequence::with_counter(crate::crypto::NONCE_ROTATION_THRESHOLD - 1);
+        assert!(!conn.should_rotate_key().await);
+
+        let ping = Message::Ping(crate::protocol::Ping::default());
+        conn.send_message(&ping).await.unwrap();
+        assert!(conn.should_rotate_key().await);
+
+        // A new key starts a fresh nonce sequence
+        conn.set_session_key([4u8; KEY_SIZE]).await;
+        assert_eq!(conn.nonce_sequence.lock().await.count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_idle_connection_rotates_after_max_age() {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let client = QuicTransport::new(addr).await.unwrap();
+        let server = QuicTransport::new(addr).await.unwrap();
+
+        let (conn, accepted) = tokio::join!(client.connect(server.local_addr()), server.accept());
+        let conn = conn.unwrap();
+        let _accepted = accepted.unwrap();
+        conn.set_session_key([3u8; KEY_SIZE]).await;
+
+        let clock = Arc::new(crate::clock::ManualClock::new(chrono::Utc::now()));
+        conn.set_key_rotation_policy(Duration::from_secs(3600), clock.clone());
+        clock.advance(Duration::from_secs(3599));
+        assert!(!conn.should_rotate_key().await);
+
+        // No messages were sent; age alone triggers rotation
+        clock.advance(Duration::from_secs(1));
+        assert!(conn.should_rotate_key().await);
+
+        conn.reset_session_tracker().await;
+        assert!(!conn.should_rotate_key().await);
+
+        // A zero max age never rotates on time
+        conn.set_key_rotation_policy(Duration::ZERO, clock.clone());
+        clock.advance(Duration::from_secs(365 * 24 * 3600));
+        assert!(!conn.should_rotate_key().await);
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_resumes_with_zero_rtt() {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let client = QuicTransport::new(addr).await.unwrap();
+        let server = QuicTransport::new(addr).await.unwrap();
+        let device_id = [7u8; 32];
+        let ping = Message::Ping(crate::protocol::Ping::default());
+
+        // First connection: no ticket yet, so a full handshake
+        let (conn, accepted) = tokio::join!(
+            client.connect_to_device(server.local_addr(), Some(&device_id)),
+            server.accept()
+        );
+        let conn = conn.unwrap();
+        let accepted = accepted.unwrap();
+        assert_eq!(conn.is_resumed(), Some(false));
+
+        // A round trip makes sure the server's session ticket has arrived
+        conn.set_session_key([3u8; KEY_SIZE]).await;
+        accepted.set_session_key([3u8; KEY_SIZE]).await;
+        conn.send_message(&ping).await.unwrap();
+        assert!(matches!(
+            accepted.receive_message().await.unwrap(),
+            Message::Ping(_)
+        ));
+        accepted.send_message(&ping).await.unwrap();
+        assert!(matches!(
+            conn.receive_message().await.unwrap(),
+            Message::Ping(_)
+        ));
+        conn.close();
+
+        // Second connection reuses the cached ticket and sends early data
+        let conn = client
+            .connect_to_device(server.local_addr(), Some(&device_id))
+            .await
+            .unwrap();
+        assert_eq!(conn.is_resumed(), None);
+        conn.set_session_key([3u8; KEY_SIZE]).await;
+        conn.send_message(&ping).await.unwrap();
+
+        let accepted = server.accept().await.unwrap();
+        accepted.set_session_key([3u8; KEY_SIZE]).await;
+        assert!(matches!(
+            accepted.receive_message().await.unwrap(),
+            Message::Ping(_)
+        ));
+        assert!(conn.wait_for_handshake().await);
+        assert_eq!(conn.is_resumed(), Some(true));
+
+        // A different device has no ticket
+        let (other, _accepted) = tokio::join!(
+            client.connect_to_device(server.local_addr(), Some(&[8u8; 32])),
+            server.accept()
+        );
+        assert_eq!(other.unwrap().is_resumed(), Some(false));
+    }
 }
@@ -1,132 +1,123 @@
 //! WebSocket transport as fallback when QUIC fails
 //!
 //! Provides WebSocket over TLS transport for restrictive networks
-//! where QUIC/UDP is blocked.
+//! where QUIC/UDP is blocked. The relay's `/ws/{peer}` route carries
+//! sends to one peer: the connection authenticates like the relay client
+//! and every payload goes out as a signed send frame. It is send-only;
+//! messages for this device keep arriving on the relay client's connection.
+
+use std::sync::Arc;
 
-use crate::crypto::KEY_SIZE;
-use crate::error::NetworkError;
-use crate::protocol::{Frame, Message};
 use futures_util::{SinkExt, StreamExt};
-use std::net::SocketAddr;
+use rand::RngCore;
 use tokio::net::TcpStream;
 use tokio::sync::Mutex;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
 use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
 
+use super::relay_client::{
+    auth_request, check_auth_response, session_challenge, sign_message, RelayClient,
+};
+use super::relay_frame;
+use crate::crypto::DeviceIdentity;
+use crate::error::NetworkError;
+
 /// WebSocket connection to a peer
 pub struct WebSocketPeerConnection {
     stream: Mutex<WebSocketStream<MaybeTlsStream<TcpStream>>>,
-    session_key: Mutex<Option<[u8; KEY_SIZE]>>,
-    peer_addr: SocketAddr,
+    identity: Arc<DeviceIdentity>,
+    peer_id: String,
 }
 
 impl WebSocketPeerConnection {
-    /// Create a new WebSocket connection
-    pub async fn connect(url: &str) -> Result<Self, NetworkError> {
+    /// Open a WebSocket at `url` and authenticate for sends to `peer_id`
+    ///
+    /// With a `pinned_key`, the relay must prove it holds it on this
+    /// connection, as for [`RelayClient::connect`].
+    pub async fn connect(
+        url: &str,
+        peer_id: &str,
+        identity: Arc<DeviceIdentity>,
+        pinned_key: Option<&[u8; 32]>,
+    ) -> Result<Self, NetworkError> {
         let (ws_stream, _) = connect_async(url).await.map_err(|e| {
             NetworkError::ConnectionFailed(format!("WebSocket connect failed: {}", e))
         })?;
 
-        // Extract address from URL (simplified - in production would parse properly)
-        let _peer_addr: SocketAddr = url
-            .replace("ws://", "")
-            .replace("wss://", "")
-            .split('/')
-            .next()
-            .unwrap_or("0.0.0.0:0")
-            .parse()
-            .map_err(|_| NetworkError::AddressParse("Invalid WebSocket URL".to_string()))?;
-
-        Ok(Self {
+        let conn = Self {
             stream: Mutex::new(ws_stream),
-            session_key: Mutex::new(None),
-            peer_addr: _peer_addr,
-        })
-    }
-
-    /// Set session key for encryption
-    pub async fn set_session_key(&self, key: [u8; KEY_SIZE]) {
-        *self.session_key.lock().await = Some(key);
-    }
-
-    /// Send an encrypted message
-    pub async fn send_message(&self, message: &Message) -> Result<(), NetworkError> {
-        let key = self.session_key.lock().await;
-        let key = key.as_ref().ok_or(NetworkError::NotAuthenticated)?;
-
-        let header = message.header();
-        let payload = message
-            .serialize()
-            .map_err(|e| NetworkError::Transport(e.to_string()))?;
-
-        let frame = Frame::encrypt(&header, &payload, key)
-            .map_err(|e| NetworkError::Transport(e.to_string()))?;
-
-        let frame_bytes = frame.to_bytes();
-
-        let mut stream = self.stream.lock().await;
-        stream
-            .send(tokio_tungstenite::tungstenite::Message::Binary(
-                frame_bytes.into(),
-            ))
-            .await
-            .map_err(|e| NetworkError::Transport(format!("WebSocket send failed: {}", e)))?;
+            identity,
+            peer_id: peer_id.to_string(),
+        };
 
-        Ok(())
+        let challenge = session_challenge(pinned_key);
+        conn.send(WsMessage::Text(
+            auth_request(&conn.identity, challenge.as_deref()).into(),
+        ))
+        .await?;
+        let response = conn.receive_text().await?;
+        check_auth_response(
+            &response,
+            &conn.identity.device_id_hex(),
+            pinned_key,
+            challenge.as_deref(),
+        )?;
+
+        Ok(conn)
     }
 
-    /// Receive and decrypt a message
-    pub async fn receive_message(&self) -> Result<Message, NetworkError> {
-        let key = self.session_key.lock().await;
-        let key = key.as_ref().ok_or(NetworkError::NotAuthenticated)?;
-
-        let mut stream = self.stream.lock().await;
-        let msg = stream
-            .next()
-            .await
-            .ok_or(NetworkError::ConnectionClosed)?
-            .map_err(|e| NetworkError::Transport(format!("WebSocket receive failed: {}", e)))?;
-
-        let frame_bytes = match msg {
-            tokio_tungstenite::tungstenite::Message::Binary(data) => data,
-            tokio_tungstenite::tungstenite::Message::Close(_) => {
-                return Err(NetworkError::ConnectionClosed);
-            }
-            _ => {
-                return Err(NetworkError::Transport(
-                    "Unexpected WebSocket message type".to_string(),
-                ));
-            }
-        };
+    /// Send a relay payload to the peer, signed with our identity key
+    pub async fn send_payload(&self, payload: &[u8]) -> Result<(), NetworkError> {
+        let mut nonce = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let message_id = RelayClient::message_id(&self.peer_id, payload, &nonce);
+        let (signed_at, signature) = sign_message(&self.identity, &self.peer_id, payload);
 
         let frame =
-            Frame::from_bytes(&frame_bytes).map_err(|e| NetworkError::Transport(e.to_string()))?;
-
-        let (header, payload) = frame
-            .decrypt(key)
-            .map_err(|e| NetworkError::Transport(e.to_string()))?;
+            relay_frame::encode_send(&message_id, &self.peer_id, signed_at, &signature, payload)?;
+        self.send(WsMessage::Binary(frame.into())).await
+    }
 
-        Message::deserialize(&header, &payload).map_err(|e| NetworkError::Transport(e.to_string()))
+    /// The peer this connection sends to (hex device id)
+    pub fn peer_id(&self) -> &str {
+        &self.peer_id
     }
 
-    /// Check if connection is still active
-    pub async fn is_connected(&self) -> bool {
-        // Try to peek at the stream to see if it's still open
-        // This is a simplified check - in production would check stream state
-        self.session_key.lock().await.is_some()
+    /// Close the connection
+    pub async fn close(&self) {
+        let _ = self.stream.lock().await.close(None).await;
     }
 
-    /// Get peer address
-    pub fn peer_address(&self) -> SocketAddr {
-        self.peer_addr
+    async fn send(&self, message: WsMessage) -> Result<(), NetworkError> {
+        self.stream
+            .lock()
+            .await
+            .send(message)
+            .await
+            .map_err(|e| NetworkError::Transport(format!("WebSocket send failed: {}", e)))
     }
 
-    /// Close the connection
-    pub async fn close(&self) {
-        if let Ok(stream) = self.stream.try_lock() {
-            // Close the WebSocket connection
-            // Note: tokio_tungstenite's close() requires a CloseFrame
-            // For now, we'll just drop the stream which will close it
-            drop(stream);
+    /// Receive the next text message, answering pings on the way
+    async fn receive_text(&self) -> Result<String, NetworkError> {
+        let mut stream = self.stream.lock().await;
+        loop {
+            let msg = stream
+                .next()
+                .await
+                .ok_or(NetworkError::ConnectionClosed)?
+                .map_err(|e| NetworkError::Transport(format!("WebSocket receive failed: {}", e)))?;
+            match msg {
+                WsMessage::Text(text) => return Ok(text.to_string()),
+                WsMessage::Ping(data) => {
+                    stream.send(WsMessage::Pong(data)).await.ok();
+                }
+                WsMessage::Close(_) => return Err(NetworkError::ConnectionClosed),
+                _ => {
+                    return Err(NetworkError::Transport(
+                        "Unexpected WebSocket message type".to_string(),
+                    ));
+                }
+            }
         }
     }
 }
@@ -135,19 +126,42 @@ impl WebSocketPeerConnection {
 pub struct WebSocketTransport {
     /// Base URL for WebSocket connections (e.g., "wss://example.com")
     base_url: String,
+    identity: Arc<DeviceIdentity>,
+    pinned_key: Option<[u8; 32]>,
 }
 
 impl WebSocketTransport {
-    /// Create a new WebSocket transport
-    pub fn new(base_url: String) -> Self {
-        Self { base_url }
+    /// Create a new WebSocket transport authenticating as `identity`
+    pub fn new(base_url: String, identity: Arc<DeviceIdentity>) -> Self {
+        Self {
+            base_url,
+            identity,
+            pinned_key: None,
+        }
+    }
+
+    /// Only connect to a relay that proves it holds `pinned_key`
+    pub fn with_pinned_key(mut self, pinned_key: Option<[u8; 32]>) -> Self {
+        self.pinned_key = pinned_key;
+        self
     }
 
     /// Connect to a peer via WebSocket
     /// The peer_id is used to construct the WebSocket URL
     pub async fn connect(&self, peer_id: &str) -> Result<WebSocketPeerConnection, NetworkError> {
-        // Construct WebSocket URL
-        // Format: wss://base_url/ws/peer_id
+        WebSocketPeerConnection::connect(
+            &self.peer_url(peer_id),
+            peer_id,
+            self.identity.clone(),
+            self.pinned_key.as_ref(),
+        )
+        .await
+    }
+
+    /// WebSocket URL of the relay route for `peer_id`
+    ///
+    /// Format: wss://base_url/ws/peer_id
+    fn peer_url(&self, peer_id: &str) -> String {
         let url = if self.base_url.ends_with('/') {
             format!("{}ws/{}", self.base_url, peer_id)
         } else {
@@ -155,11 +169,8 @@ impl WebSocketTransport {
         };
 
         // Convert http/https to ws/wss
-        let url = url
-            .replace("https://", "wss://")
-            .replace("http://", "ws://");
-
-        WebSocketPeerConnection::connect(&url).await
+        url.replace("https://", "wss://")
+            .replace("http://", "ws://")
     }
 }
 
@@ -169,18 +180,18 @@ mod tests {
 
     #[test]
     fn test_websocket_url_construction() {
-        let _transport = WebSocketTransport::new("https://example.com".to_string());
-        // Would test connect() but requires actual server
-    }
-
-    #[test]
-    fn test_url_protocol_conversion() {
-        let https_url = "https://example.com/ws/peer123";
-        let wss_url = https_url.replace("https://", "wss://");
-        assert_eq!(wss_url, "wss://example.com/ws/peer123");
-
-        let http_url = "http://example.com/ws/peer123";
-        let ws_url = http_url.replace("http://", "ws://");
-        assert_eq!(ws_url, "ws://example.com/ws/peer123");
+        let identity = Arc::new(DeviceIdentity::generate().unwrap());
+        let transport =
+            WebSocketTransport::new("https://example.com".to_string(), identity.clone());
+        assert_eq!(
+            transport.peer_url("peer123"),
+            "wss://example.com/ws/peer123"
+        );
+
+        let transport = WebSocketTransport::new("http://relay.local:8080/".to_string(), identity);
+        assert_eq!(
+            transport.peer_url("peer123"),
+            "ws://relay.local:8080/ws/peer123"
+        );
     }
 }
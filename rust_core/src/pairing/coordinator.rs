@@ -4,13 +4,20 @@ use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+use crate::cancel::CancellationToken;
 use crate::error::NetworkError;
+use crate::network::discovery::{
+    pairing_service_type, RegistrationRetry, DEFAULT_SERVICE_DOMAIN, DEFAULT_SERVICE_NAME,
+};
 
-/// Service type for pairing discovery
-const PAIRING_SERVICE_TYPE: &str = "_toss-pair._udp.local.";
+/// How long an mDNS pairing search listens for announcements
+const MDNS_SEARCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often an mDNS pairing search checks for cancellation
+const MDNS_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 /// Result of pairing advertisement registration
 #[derive(Debug, Clone, Default)]
@@ -67,9 +74,37 @@ struct FindPairingResponse {
     expires_at: u64,
 }
 
+/// Request to report a completed pairing to the relay server
+#[derive(Debug, Serialize)]
+struct CompletePairingRequest {
+    public_key: String,
+    device_name: String,
+}
+
+/// Response from waiting on a pairing session's status
+#[derive(Debug, Deserialize)]
+struct PairingStatusResponse {
+    completed: bool,
+    peer_public_key: Option<String>,
+    peer_device_name: Option<String>,
+}
+
+/// Device that completed pairing with an advertised code
+#[derive(Debug, Clone)]
+pub struct CompletedPairing {
+    /// The peer's public key (32 bytes)
+    pub public_key: [u8; 32],
+    /// The peer's name
+    pub device_name: String,
+}
+
 /// Pairing coordinator that handles both mDNS and relay-based pairing
 pub struct PairingCoordinator {
     mdns_daemon: Option<ServiceDaemon>,
+    mdns_retry: RegistrationRetry,
+    /// Code currently registered via mDNS, to skip duplicate registrations
+    mdns_code: RwLock<Option<String>>,
+    service_type: String,
     relay_url: Option<String>,
     device_name: String,
     current_code: RwLock<Option<String>>,
@@ -77,8 +112,21 @@ pub struct PairingCoordinator {
 }
 
 impl PairingCoordinator {
-    /// Create a new pairing coordinator
+    /// Create a new pairing coordinator using the default service type
     pub fn new(device_name: &str, relay_url: Option<String>) -> Result<Self, NetworkError> {
+        Self::new_with_service_type(
+            device_name,
+            relay_url,
+            &pairing_service_type(DEFAULT_SERVICE_NAME, DEFAULT_SERVICE_DOMAIN),
+        )
+    }
+
+    /// Create a new pairing coordinator advertising and browsing `service_type`
+    pub fn new_with_service_type(
+        device_name: &str,
+        relay_url: Option<String>,
+        service_type: &str,
+    ) -> Result<Self, NetworkError> {
         let mdns_daemon = match ServiceDaemon::new() {
             Ok(daemon) => Some(daemon),
             Err(e) => {
@@ -94,6 +142,9 @@ impl PairingCoordinator {
 
         Ok(Self {
             mdns_daemon,
+            mdns_retry: RegistrationRetry::default(),
+            mdns_code: RwLock::new(None),
+            service_type: service_type.to_string(),
             relay_url,
             device_name: device_name.to_string(),
             current_code: RwLock::new(None),
@@ -101,126 +152,115 @@ impl PairingCoordinator {
         })
     }
 
+    /// Use a custom retry policy for mDNS registration
+    pub fn with_registration_retry(mut self, retry: RegistrationRetry) -> Self {
+        self.mdns_retry = retry;
+        self
+    }
+
     /// Start advertising this device for pairing with the given code and public key
-    /// Returns an `AdvertisementResult` indicating which methods succeeded/failed
+    ///
+    /// mDNS and relay registration are attempted independently; a failure on
+    /// one channel is reported in the result without affecting the other.
     pub async fn start_advertisement(
         &self,
         code: &str,
         public_key: &[u8; 32],
-    ) -> Result<AdvertisementResult, NetworkError> {
+    ) -> AdvertisementResult {
         // Store the current code
         *self.current_code.write().await = Some(code.to_string());
 
-        let mut result = AdvertisementResult::default();
-
         // Encode public key as base64
         let public_key_b64 =
             base64::Engine::encode(&base64::engine::general_purpose::STANDARD, public_key);
 
-        // Try mDNS advertisement
-        if let Some(ref daemon) = self.mdns_daemon {
-            let host_name = format!("toss-pair-{}.local.", code);
-            let truncated_pk = &public_key_b64[..43.min(public_key_b64.len())]; // Max TXT record value size
-
-            let properties: HashMap<&str, &str> = [
-                ("code", code),
-                ("pk", truncated_pk),
-                ("name", &self.device_name),
-            ]
-            .into_iter()
-            .collect();
-
-            let properties_vec: Vec<(&str, &str)> = properties.into_iter().collect();
-
-            match ServiceInfo::new(
-                PAIRING_SERVICE_TYPE,
-                &format!("toss-pair-{}", code),
-                &host_name,
-                "",
-                12345, // Arbitrary port for pairing advertisement
-                &properties_vec[..],
-            ) {
-                Ok(service_info) => {
-                    if let Err(e) = daemon.register(service_info) {
-                        let error_msg = format!("Failed to register mDNS service: {}", e);
-                        tracing::warn!("{}", error_msg);
-                        result.mdns_error = Some(error_msg);
-                    } else {
-                        tracing::info!("mDNS pairing service registered with code: {}", code);
-                        result.mdns_registered = true;
-                    }
-                }
-                Err(e) => {
-                    let error_msg = format!("Failed to create mDNS service info: {}", e);
-                    tracing::warn!("{}", error_msg);
-                    result.mdns_error = Some(error_msg);
-                }
+        let (mdns, relay) = tokio::join!(
+            self.advertise_mdns(code, &public_key_b64),
+            self.register_relay(code, &public_key_b64)
+        );
+
+        let mut result = AdvertisementResult::default();
+        match mdns {
+            Ok(()) => result.mdns_registered = true,
+            Err(error_msg) => {
+                tracing::warn!("{}", error_msg);
+                result.mdns_error = Some(error_msg);
+            }
+        }
+        match relay {
+            Ok(()) => result.relay_registered = true,
+            Err(error_msg) => {
+                tracing::warn!("{}", error_msg);
+                result.relay_error = Some(error_msg);
             }
-        } else {
-            result.mdns_error = Some("mDNS daemon not available".to_string());
-            tracing::warn!("mDNS daemon not available for pairing advertisement");
         }
+        result
+    }
 
-        // Try relay server registration
-        if let Some(ref relay_url) = self.relay_url {
-            let request = RegisterPairingRequest {
-                code: code.to_string(),
-                public_key: public_key_b64,
-                device_name: self.device_name.clone(),
-                expires_in_secs: Some(300), // 5 minutes
-            };
+    /// Register the pairing code on the relay server
+    async fn register_relay(&self, code: &str, public_key_b64: &str) -> Result<(), String> {
+        let relay_url = self
+            .relay_url
+            .as_ref()
+            .ok_or("No relay server configured")?;
+
+        let request = RegisterPairingRequest {
+            code: code.to_string(),
+            public_key: public_key_b64.to_string(),
+            device_name: self.device_name.clone(),
+            expires_in_secs: Some(300), // 5 minutes
+        };
 
-            let url = format!("{}/api/v1/pairing/register", relay_url);
-            match self.http_client.post(&url).json(&request).send().await {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        match response.json::<RegisterPairingResponse>().await {
-                            Ok(reg_response) => {
-                                tracing::info!(
-                                    "Pairing registered on relay server with code: {}, expires_at: {}",
-                                    reg_response.code,
-                                    reg_response.expires_at
-                                );
-                                result.relay_registered = true;
-                            }
-                            Err(e) => {
-                                let error_msg =
-                                    format!("Failed to parse registration response: {}", e);
-                                tracing::warn!("{}", error_msg);
-                                result.relay_error = Some(error_msg);
-                            }
-                        }
-                    } else {
-                        let status = response.status();
-                        let error_text = response.text().await.unwrap_or_default();
-                        let error_msg =
-                            format!("Relay registration failed: {} - {}", status, error_text);
-                        tracing::warn!("{}", error_msg);
-                        result.relay_error = Some(error_msg);
-                    }
-                }
-                Err(e) => {
-                    let error_msg = format!("Failed to contact relay server: {}", e);
-                    tracing::warn!("{}", error_msg);
-                    result.relay_error = Some(error_msg);
-                }
-            }
-        } else {
-            result.relay_error = Some("No relay server configured".to_string());
-            tracing::debug!("No relay server configured for pairing advertisement");
+        let url = format!("{}/api/v1/pairing/register", relay_url);
+        let response = self
+            .http_client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to contact relay server: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(format!(
+                "Relay registration failed: {} - {}",
+                status, error_text
+            ));
         }
 
-        Ok(result)
+        let reg_response = response
+            .json::<RegisterPairingResponse>()
+            .await
+            .map_err(|e| format!("Failed to parse registration response: {}", e))?;
+        tracing::info!(
+            "Pairing registered on relay server with code: {}, expires_at: {}",
+            reg_response.code,
+            reg_response.expires_at
+        );
+        Ok(())
     }
 
     /// Find a device by pairing code
     pub async fn find_device(&self, code: &str) -> Result<PairingDeviceInfo, NetworkError> {
+        self.find_device_cancellable(code, &CancellationToken::new())
+            .await
+    }
+
+    /// Find a device by pairing code, stopping early once `cancel` is cancelled
+    ///
+    /// A cancelled search fails with `NetworkError::Cancelled`.
+    pub async fn find_device_cancellable(
+        &self,
+        code: &str,
+        cancel: &CancellationToken,
+    ) -> Result<PairingDeviceInfo, NetworkError> {
         tracing::debug!("Starting device search for pairing code: {}...", &code[..2]);
 
         // First try mDNS discovery
         if let Some(ref daemon) = self.mdns_daemon {
             tracing::debug!("Searching via mDNS...");
-            match self.find_via_mdns(daemon, code).await {
+            match self.find_via_mdns(daemon, code, cancel).await {
                 Ok(Some(info)) => {
                     tracing::info!("Found device via mDNS with code: {}", code);
                     return Ok(info);
@@ -228,6 +268,7 @@ impl PairingCoordinator {
                 Ok(None) => {
                     tracing::debug!("Device not found via mDNS, trying relay...");
                 }
+                Err(NetworkError::Cancelled) => return Err(NetworkError::Cancelled),
                 Err(e) => {
                     tracing::warn!("mDNS search failed: {}, trying relay...", e);
                 }
@@ -239,7 +280,11 @@ impl PairingCoordinator {
         // Fall back to relay server
         if let Some(ref relay_url) = self.relay_url {
             tracing::debug!("Searching via relay server: {}", relay_url);
-            return self.find_via_relay(relay_url, code).await;
+            return tokio::select! {
+                biased;
+                _ = cancel.cancelled() => Err(NetworkError::Cancelled),
+                result = self.find_via_relay(relay_url, code) => result,
+            };
         }
 
         // Provide a helpful error message when relay is not configured
@@ -249,79 +294,95 @@ impl PairingCoordinator {
     }
 
     /// Find device via mDNS
+    ///
+    /// Listens for up to `MDNS_SEARCH_TIMEOUT`, checking `cancel` between
+    /// announcements. The browse is stopped on every exit path.
     async fn find_via_mdns(
         &self,
         daemon: &ServiceDaemon,
         code: &str,
+        cancel: &CancellationToken,
     ) -> Result<Option<PairingDeviceInfo>, NetworkError> {
         tracing::debug!("Starting mDNS browse for pairing code: {}", code);
         let receiver = daemon
-            .browse(PAIRING_SERVICE_TYPE)
+            .browse(&self.service_type)
             .map_err(|e| NetworkError::Discovery(format!("Failed to browse mDNS: {}", e)))?;
 
-        // Listen for pairing services (5 second timeout for slow announcements)
-        let timeout = tokio::time::timeout(Duration::from_secs(5), async {
-            while let Ok(event) = receiver.recv() {
-                tracing::trace!("mDNS event: {:?}", event);
-                if let ServiceEvent::ServiceResolved(info) = event {
-                    if let Some(found_code) = info.get_properties().get("code") {
-                        if found_code.val_str() == code {
-                            // Found matching device
-                            let pk_str = info
-                                .get_properties()
-                                .get("pk")
-                                .map(|v| v.val_str().to_string())
-                                .unwrap_or_default();
-
-                            let device_name = info
-                                .get_properties()
-                                .get("name")
-                                .map(|v| v.val_str().to_string())
-                                .unwrap_or_else(|| "Unknown".to_string());
-
-                            // Decode public key
-                            if let Ok(pk_bytes) = base64::Engine::decode(
-                                &base64::engine::general_purpose::STANDARD,
-                                &pk_str,
-                            ) {
-                                if pk_bytes.len() == 32 {
-                                    let mut public_key = [0u8; 32];
-                                    public_key.copy_from_slice(&pk_bytes);
-
-                                    let addresses: Vec<SocketAddr> = info
-                                        .get_addresses()
-                                        .iter()
-                                        .filter_map(|scoped_ip| {
-                                            let ip: IpAddr = match scoped_ip {
-                                                mdns_sd::ScopedIp::V4(v4) => IpAddr::V4(*v4.addr()),
-                                                mdns_sd::ScopedIp::V6(v6) => IpAddr::V6(*v6.addr()),
-                                                _ => return None, // Handle future variants
-                                            };
-                                            Some(SocketAddr::new(ip, info.get_port()))
-                                        })
-                                        .collect();
-
-                                    return Some(PairingDeviceInfo {
-                                        code: code.to_string(),
-                                        public_key,
-                                        device_name,
-                                        addresses,
-                                        via_relay: false,
-                                        expires_at: None, // mDNS doesn't provide expiration
-                                    });
-                                }
+        let deadline = Instant::now() + MDNS_SEARCH_TIMEOUT;
+        let result = loop {
+            if cancel.is_cancelled() {
+                break Err(NetworkError::Cancelled);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break Ok(None); // Timeout, device not found via mDNS
+            }
+            let event = match receiver.recv_timeout(remaining.min(MDNS_POLL_INTERVAL)) {
+                Ok(event) => event,
+                Err(_) if receiver.is_disconnected() => break Ok(None),
+                Err(_) => {
+                    tokio::task::yield_now().await;
+                    continue;
+                }
+            };
+            tracing::trace!("mDNS event: {:?}", event);
+            if let ServiceEvent::ServiceResolved(info) = event {
+                if let Some(found_code) = info.get_properties().get("code") {
+                    if found_code.val_str() == code {
+                        // Found matching device
+                        let pk_str = info
+                            .get_properties()
+                            .get("pk")
+                            .map(|v| v.val_str().to_string())
+                            .unwrap_or_default();
+
+                        let device_name = info
+                            .get_properties()
+                            .get("name")
+                            .map(|v| v.val_str().to_string())
+                            .unwrap_or_else(|| "Unknown".to_string());
+
+                        // Decode public key
+                        if let Ok(pk_bytes) = base64::Engine::decode(
+                            &base64::engine::general_purpose::STANDARD,
+                            &pk_str,
+                        ) {
+                            if pk_bytes.len() == 32 {
+                                let mut public_key = [0u8; 32];
+                                public_key.copy_from_slice(&pk_bytes);
+
+                                let addresses: Vec<SocketAddr> = info
+                                    .get_addresses()
+                                    .iter()
+                                    .filter_map(|scoped_ip| {
+                                        let ip: IpAddr = match scoped_ip {
+                                            mdns_sd::ScopedIp::V4(v4) => IpAddr::V4(*v4.addr()),
+                                            mdns_sd::ScopedIp::V6(v6) => IpAddr::V6(*v6.addr()),
+                                            _ => return None, // Handle future variants
+                                        };
+                                        Some(SocketAddr::new(ip, info.get_port()))
+                                    })
+                                    .collect();
+
+                                break Ok(Some(PairingDeviceInfo {
+                                    code: code.to_string(),
+                                    public_key,
+                                    device_name,
+                                    addresses,
+                                    via_relay: false,
+                                    expires_at: None, // mDNS doesn't provide expiration
+                                }));
                             }
                         }
                     }
                 }
             }
-            None
-        });
+        };
 
-        match timeout.await {
-            Ok(result) => Ok(result),
-            Err(_) => Ok(None), // Timeout, device not found via mDNS
+        if let Err(e) = daemon.stop_browse(&self.service_type) {
+            tracing::debug!("Failed to stop mDNS browse: {}", e);
         }
+        result
     }
 
     /// Find device via relay server
@@ -377,21 +438,206 @@ impl PairingCoordinator {
         }
     }
 
+    /// Tell the advertiser of `code`, via the relay server, that we paired with it
+    ///
+    /// `public_key` is the key we completed pairing with.
+    pub async fn notify_completion(
+        &self,
+        code: &str,
+        public_key: &[u8; 32],
+    ) -> Result<(), NetworkError> {
+        let relay_url = self
+            .relay_url
+            .as_ref()
+            .ok_or_else(|| NetworkError::Relay("No relay server configured".to_string()))?;
+
+        let request = CompletePairingRequest {
+            public_key: base64::Engine::encode(
+                &base64::engine::general_purpose::STANDARD,
+                public_key,
+            ),
+            device_name: self.device_name.clone(),
+        };
+        let url = format!("{}/api/v1/pairing/{}/complete", relay_url, code);
+        let response = self
+            .http_client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| NetworkError::Relay(format!("Failed to contact relay: {}", e)))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let error_text = response.text().await.unwrap_or_default();
+            Err(NetworkError::Relay(format!("Relay error: {}", error_text)))
+        }
+    }
+
+    /// Wait up to `timeout_secs` for the device that found `code` to complete pairing
+    ///
+    /// Returns `Ok(None)` on timeout. Fails with `NetworkError::Discovery` once
+    /// the code is no longer registered on the relay server.
+    pub async fn wait_for_completion(
+        &self,
+        code: &str,
+        timeout_secs: u64,
+    ) -> Result<Option<CompletedPairing>, NetworkError> {
+        let relay_url = self
+            .relay_url
+            .as_ref()
+            .ok_or_else(|| NetworkError::Relay("No relay server configured".to_string()))?;
+
+        let url = format!(
+            "{}/api/v1/pairing/{}/status?timeout_secs={}",
+            relay_url, code, timeout_secs
+        );
+        let response = self
+            .http_client
+            .get(&url)
+            // The relay holds the request open, so allow longer than the client default
+            .timeout(Duration::from_secs(timeout_secs + 10))
+            .send()
+            .await
+            .map_err(|e| NetworkError::Relay(format!("Failed to contact relay: {}", e)))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(NetworkError::Discovery(
+                "Pairing code not found or expired".to_string(),
+            ));
+        }
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(NetworkError::Relay(format!("Relay error: {}", error_text)));
+        }
+
+        let status: PairingStatusResponse = response
+            .json()
+            .await
+            .map_err(|e| NetworkError::Relay(format!("Invalid response: {}", e)))?;
+        if !status.completed {
+            return Ok(None);
+        }
+
+        let pk_bytes = base64::Engine::decode(
+            &base64::engine::general_purpose::STANDARD,
+            status.peer_public_key.unwrap_or_default(),
+        )
+        .map_err(|e| NetworkError::Relay(format!("Invalid public key encoding: {}", e)))?;
+        let public_key: [u8; 32] = pk_bytes
+            .try_into()
+            .map_err(|_| NetworkError::Relay("Invalid public key length".to_string()))?;
+
+        Ok(Some(CompletedPairing {
+            public_key,
+            device_name: status.peer_device_name.unwrap_or_default(),
+        }))
+    }
+
     /// Stop advertising
     pub async fn stop_advertisement(&self) {
-        if let Some(code) = self.current_code.write().await.take() {
-            // Unregister from mDNS
-            if let Some(ref daemon) = self.mdns_daemon {
-                let fullname = format!("toss-pair-{}.{}", code, PAIRING_SERVICE_TYPE);
-                let _ = daemon.unregister(&fullname);
-            }
+        let code = self.current_code.write().await.take();
+        if let Some(code) = code {
+            self.cancel_advertisement(&code).await;
+        }
+    }
+
+    /// Stop advertising `code`, if it is still the code being advertised
+    pub async fn withdraw_advertisement(&self, code: &str) {
+        let mut current = self.current_code.write().await;
+        if current.as_deref() == Some(code) {
+            *current = None;
+            drop(current);
+            self.cancel_advertisement(code).await;
+        }
+    }
+
+    /// Replace the advertisement for `old_code` with one for `new_code`
+    ///
+    /// Used when a pairing code is rotated; the old code is withdrawn from
+    /// mDNS and the relay server before the new one is registered.
+    pub async fn refresh_advertisement(
+        &self,
+        old_code: &str,
+        new_code: &str,
+        public_key: &[u8; 32],
+    ) -> AdvertisementResult {
+        self.cancel_advertisement(old_code).await;
+        self.start_advertisement(new_code, public_key).await
+    }
+
+    /// Register the pairing code via mDNS, retrying transient failures
+    ///
+    /// Does nothing if the code is already registered.
+    pub async fn advertise_mdns(&self, code: &str, public_key_b64: &str) -> Result<(), String> {
+        let daemon = self
+            .mdns_daemon
+            .as_ref()
+            .ok_or("mDNS daemon not available")?;
+
+        let mut mdns_code = self.mdns_code.write().await;
+        if mdns_code.as_deref() == Some(code) {
+            tracing::debug!("mDNS pairing service already registered for code {}", code);
+            return Ok(());
+        }
+
+        let host_name = format!("toss-pair-{}.local.", code);
+        let truncated_pk = &public_key_b64[..43.min(public_key_b64.len())]; // Max TXT record value size
+
+        let properties: HashMap<&str, &str> = [
+            ("code", code),
+            ("pk", truncated_pk),
+            ("name", &self.device_name),
+        ]
+        .into_iter()
+        .collect();
+
+        let properties_vec: Vec<(&str, &str)> = properties.into_iter().collect();
+
+        self.mdns_retry
+            .run("mDNS pairing registration", || {
+                let service_info = ServiceInfo::new(
+                    &self.service_type,
+                    &format!("toss-pair-{}", code),
+                    &host_name,
+                    "",
+                    12345, // Arbitrary port for pairing advertisement
+                    &properties_vec[..],
+                )
+                .map_err(|e| {
+                    NetworkError::Discovery(format!("Failed to create mDNS service info: {}", e))
+                })?;
+                daemon.register(service_info).map_err(|e| {
+                    NetworkError::Discovery(format!("Failed to register mDNS service: {}", e))
+                })
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+        tracing::info!("mDNS pairing service registered with code: {}", code);
+        *mdns_code = Some(code.to_string());
+        Ok(())
+    }
+
+    /// Withdraw the advertisement for a pairing code
+    async fn cancel_advertisement(&self, code: &str) {
+        // Unregister from mDNS
+        if let Some(ref daemon) = self.mdns_daemon {
+            let fullname = format!("toss-pair-{}.{}", code, self.service_type);
+            let _ = daemon.unregister(&fullname);
 
-            // Cancel on relay server
-            if let Some(ref relay_url) = self.relay_url {
-                let url = format!("{}/api/v1/pairing/{}", relay_url, code);
-                let _ = self.http_client.delete(&url).send().await;
+            let mut mdns_code = self.mdns_code.write().await;
+            if mdns_code.as_deref() == Some(code) {
+                *mdns_code = None;
             }
         }
+
+        // Cancel on relay server
+        if let Some(ref relay_url) = self.relay_url {
+            let url = format!("{}/api/v1/pairing/{}", relay_url, code);
+            let _ = self.http_client.delete(&url).send().await;
+        }
     }
 
     /// Check if we have a relay URL configured
@@ -429,4 +675,129 @@ mod tests {
         assert!(coordinator.is_ok());
         assert!(coordinator.unwrap().has_relay());
     }
+
+    #[test]
+    fn test_pairing_coordinator_service_type() {
+        let coordinator = PairingCoordinator::new("Test Device", None).unwrap();
+        assert_eq!(coordinator.service_type, "_toss-pair._udp.local.");
+
+        let coordinator = PairingCoordinator::new_with_service_type(
+            "Test Device",
+            None,
+            "_acme-toss-pair._udp.local.",
+        )
+        .unwrap();
+        assert_eq!(coordinator.service_type, "_acme-toss-pair._udp.local.");
+    }
+
+    /// Serve relay pairing registration, answering `status` to every request
+    async fn serve_register(status: &'static str) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                let body = serde_json::json!({ "code": "123456", "expires_at": 0 }).to_string();
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_mdns_failure_still_reports_relay_success() {
+        let relay_url = serve_register("200 OK").await;
+        let mut coordinator = PairingCoordinator::new("Test Device", Some(relay_url)).unwrap();
+        coordinator.mdns_daemon = None;
+
+        let result = coordinator.start_advertisement("123456", &[7u8; 32]).await;
+        assert!(result.relay_registered);
+        assert!(result.relay_error.is_none());
+        assert!(!result.mdns_registered);
+        assert_eq!(
+            result.mdns_error.as_deref(),
+            Some("mDNS daemon not available")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_withdraw_leaves_rotated_code_advertised() {
+        let relay_url = serve_register("200 OK").await;
+        let mut coordinator = PairingCoordinator::new("Test Device", Some(relay_url)).unwrap();
+        coordinator.mdns_daemon = None;
+
+        coordinator.start_advertisement("111111", &[7u8; 32]).await;
+        coordinator
+            .refresh_advertisement("111111", "222222", &[7u8; 32])
+            .await;
+
+        // The watcher of the old code stopping doesn't withdraw the new one
+        coordinator.withdraw_advertisement("111111").await;
+        assert_eq!(
+            coordinator.current_code.read().await.as_deref(),
+            Some("222222")
+        );
+
+        coordinator.withdraw_advertisement("222222").await;
+        assert!(coordinator.current_code.read().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_relay_failure_still_reports_mdns_success() {
+        let relay_url = serve_register("503 Service Unavailable").await;
+        let coordinator = PairingCoordinator::new_with_service_type(
+            "Test Device",
+            Some(relay_url),
+            "_toss-test-pair._udp.local.",
+        )
+        .unwrap();
+
+        let result = coordinator.start_advertisement("654321", &[7u8; 32]).await;
+        assert!(result.mdns_registered, "{:?}", result.mdns_error);
+        assert!(result.mdns_error.is_none());
+        assert!(!result.relay_registered);
+        assert!(result.relay_error.unwrap().contains("503"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_find_device_returns_promptly() {
+        // A relay that accepts connections but never answers keeps the search busy
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let relay_url = format!("http://{}", listener.local_addr().unwrap());
+        let coordinator = PairingCoordinator::new("Test Device", Some(relay_url)).unwrap();
+
+        let cancel = CancellationToken::new();
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            canceller.cancel();
+        });
+
+        let started = Instant::now();
+        let result = coordinator.find_device_cancellable("123456", &cancel).await;
+        assert!(matches!(result, Err(NetworkError::Cancelled)));
+        // Well under the mDNS timeout and the relay request timeout
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_find_device_with_cancelled_token() {
+        let coordinator =
+            PairingCoordinator::new("Test Device", Some("http://localhost:1".to_string())).unwrap();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        assert!(cancel.is_cancelled());
+
+        let result = coordinator.find_device_cancellable("123456", &cancel).await;
+        assert!(matches!(result, Err(NetworkError::Cancelled)));
+    }
 }
@@ -1,5 +1,10 @@
 //! Pairing module for device discovery and pairing coordination
 
 mod coordinator;
+mod trust;
 
-pub use coordinator::{AdvertisementResult, PairingCoordinator, PairingDeviceInfo};
+pub use crate::cancel::CancellationToken;
+pub use coordinator::{
+    AdvertisementResult, CompletedPairing, PairingCoordinator, PairingDeviceInfo,
+};
+pub use trust::{PairingTrust, DEFAULT_TRUST_WINDOW};
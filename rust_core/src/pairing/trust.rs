@@ -0,0 +1,98 @@
+//! Skipping the pairing confirmation prompt for trusted devices
+//!
+//! With `confirm_pairing` on, a pairing completed through the relay waits
+//! for the user to accept it. Two things skip the prompt: a time-boxed trust
+//! window, meant for setting up several devices in a row, and the per-device
+//! `auto_accept_pairing` flag. The window is only kept in memory, so it ends
+//! on restart as well as when it expires; the flag is stored with the device
+//! and is the way to make trust permanent.
+
+use parking_lot::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long "trust for now" lasts when no duration is given
+pub const DEFAULT_TRUST_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Time-boxed window in which pairing requests are accepted without a prompt
+#[derive(Debug, Default)]
+pub struct PairingTrust {
+    until: Mutex<Option<Instant>>,
+}
+
+impl PairingTrust {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trust pairing requests for `duration` from `now`
+    ///
+    /// Replaces any window already open, shortening it if need be.
+    pub fn trust_for(&self, duration: Duration, now: Instant) -> Instant {
+        let until = now + duration;
+        *self.until.lock() = Some(until);
+        until
+    }
+
+    /// Close the window before it expires
+    pub fn revoke(&self) {
+        *self.until.lock() = None;
+    }
+
+    /// When the window closes, or `None` if it isn't open at `now`
+    pub fn trusted_until(&self, now: Instant) -> Option<Instant> {
+        let mut until = self.until.lock();
+        if until.is_some_and(|until| now >= until) {
+            *until = None;
+        }
+        *until
+    }
+
+    /// Whether a pairing request arriving at `now` needs the user's confirmation
+    ///
+    /// `confirm_pairing` is the setting; `auto_accept` is whether the request
+    /// comes from a device marked `auto_accept_pairing`.
+    pub fn needs_confirmation(
+        &self,
+        confirm_pairing: bool,
+        auto_accept: bool,
+        now: Instant,
+    ) -> bool {
+        confirm_pairing && !auto_accept && self.trusted_until(now).is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_auto_accepted_only_inside_window() {
+        let trust = PairingTrust::new();
+        let start = Instant::now();
+        assert!(trust.needs_confirmation(true, false, start));
+
+        let until = trust.trust_for(DEFAULT_TRUST_WINDOW, start);
+        assert_eq!(until, start + DEFAULT_TRUST_WINDOW);
+        assert!(!trust.needs_confirmation(true, false, start + Duration::from_secs(30 * 60)));
+
+        // Once the hour is up the prompt is back
+        assert!(trust.needs_confirmation(true, false, until));
+        assert_eq!(trust.trusted_until(until - Duration::from_secs(1)), None);
+
+        trust.trust_for(DEFAULT_TRUST_WINDOW, start);
+        trust.revoke();
+        assert!(trust.needs_confirmation(true, false, start));
+
+        // A restart starts without the window
+        trust.trust_for(DEFAULT_TRUST_WINDOW, start);
+        assert!(PairingTrust::new().needs_confirmation(true, false, start));
+    }
+
+    #[test]
+    fn test_auto_accept_and_setting_skip_prompt() {
+        let trust = PairingTrust::new();
+        let now = Instant::now();
+        assert!(!trust.needs_confirmation(true, true, now));
+        assert!(!trust.needs_confirmation(false, false, now));
+    }
+}
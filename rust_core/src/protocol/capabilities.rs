@@ -0,0 +1,335 @@
+//! Capability negotiation between peers
+//!
+//! Each device advertises the optional protocol features it understands in its
+//! `DeviceInfo`. Senders consult the peer's set before sending and fall back to
+//! a representation the peer can handle (or skip the message) when a feature
+//! is missing.
+
+use serde::{Deserialize, Serialize};
+
+use super::content::{ClipboardContent, ContentMetadata, ContentType};
+use super::message::{ClipboardUpdate, Message};
+
+/// Set of optional protocol features supported by a device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// Rich text (HTML/RTF) clipboard content
+    pub const RICH_TEXT: Self = Self(1 << 0);
+    /// Image clipboard content
+    pub const IMAGES: Self = Self(1 << 1);
+    /// File clipboard content
+    pub const FILES: Self = Self(1 << 2);
+    /// Latency reporting in `ClipboardAck`
+    pub const SYNC_LATENCY: Self = Self(1 << 3);
+    /// `DeviceRemoved` notifications
+    pub const DEVICE_REMOVED: Self = Self(1 << 4);
+    /// Chunked transfers that can resume after a dropped connection
+    pub const RESUMABLE_TRANSFERS: Self = Self(1 << 5);
+    /// Code snippet clipboard content with a language hint
+    pub const CODE_SNIPPETS: Self = Self(1 << 6);
+    /// `auto_action` requests on `ClipboardUpdate`
+    pub const REMOTE_ACTIONS: Self = Self(1 << 7);
+    /// `Presence` hints
+    pub const PRESENCE: Self = Self(1 << 8);
+    /// Keyed MACs as `ClipboardUpdate.content_hash`
+    pub const KEYED_CONTENT_HASH: Self = Self(1 << 9);
+    /// `IdentityMigration` announcements
+    pub const IDENTITY_MIGRATION: Self = Self(1 << 10);
+    /// `KeyCheck` challenges of the stored session key
+    pub const KEY_CHECK: Self = Self(1 << 11);
+    /// `ScratchpadUpdate` edits of the shared scratchpad
+    pub const SCRATCHPAD: Self = Self(1 << 12);
+    /// `LockState` reports of the screen lock
+    pub const LOCK_STATE: Self = Self(1 << 13);
+
+    /// No optional features
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// All features supported by this build
+    pub const fn current() -> Self {
+        Self(
+            Self::RICH_TEXT.0
+                | Self::IMAGES.0
+                | Self::FILES.0
+                | Self::SYNC_LATENCY.0
+                | Self::DEVICE_REMOVED.0
+                | Self::RESUMABLE_TRANSFERS.0
+                | Self::CODE_SNIPPETS.0
+                | Self::REMOTE_ACTIONS.0
+                | Self::PRESENCE.0
+                | Self::KEYED_CONTENT_HASH.0
+                | Self::IDENTITY_MIGRATION.0
+                | Self::KEY_CHECK.0
+                | Self::SCRATCHPAD.0
+                | Self::LOCK_STATE.0,
+        )
+    }
+
+    /// Raw bit representation
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Build from raw bits, keeping unknown bits so newer peers round-trip
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Check whether every feature in `other` is present
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Add the features in `other`
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    /// Remove the features in `other`
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+
+    /// Features supported by both sets
+    pub const fn intersection(&self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// Check whether a peer with these capabilities can receive a content type
+    pub fn supports_content_type(&self, content_type: ContentType) -> bool {
+        match content_type {
+            ContentType::PlainText | ContentType::Url => true,
+            ContentType::RichText => self.contains(Self::RICH_TEXT),
+            ContentType::Image => self.contains(Self::IMAGES),
+            ContentType::File => self.contains(Self::FILES),
+            ContentType::Code => self.contains(Self::CODE_SNIPPETS),
+        }
+    }
+
+    /// Adapt an outgoing message to what a peer with these capabilities supports
+    ///
+    /// Rich text and code are downgraded to plain text, and messages with no usable
+    /// fallback return `None` so the caller can skip them.
+    pub fn adapt(&self, message: &Message) -> Option<Message> {
+        match message {
+            Message::ClipboardUpdate(update)
+                if !self.supports_content_type(update.content.content_type) =>
+            {
+                fallback_content(&update.content).map(|content| {
+                    let mut fallback = ClipboardUpdate::new(content);
+                    fallback.sent_at = update.sent_at;
+                    fallback.sequence = update.sequence;
+                    fallback.auto_action = update
+                        .auto_action
+                        .filter(|_| self.contains(Self::REMOTE_ACTIONS));
+                    Message::ClipboardUpdate(fallback)
+                })
+            }
+            Message::ClipboardUpdate(update)
+                if (update.auto_action.is_some() && !self.contains(Self::REMOTE_ACTIONS))
+                    || (update.hash_keyed && !self.contains(Self::KEYED_CONTENT_HASH)) =>
+            {
+                let mut update = update.clone();
+                if !self.contains(Self::REMOTE_ACTIONS) {
+                    update.auto_action = None;
+                }
+                if !self.contains(Self::KEYED_CONTENT_HASH) {
+                    update = update.with_plain_hash();
+                }
+                Some(Message::ClipboardUpdate(update))
+            }
+            Message::ClipboardAck(ack)
+                if ack.latency_ms.is_some() && !self.contains(Self::SYNC_LATENCY) =>
+            {
+                let mut ack = ack.clone();
+                ack.latency_ms = None;
+                Some(Message::ClipboardAck(ack))
+            }
+            Message::DeviceRemoved(_) if !self.contains(Self::DEVICE_REMOVED) => None,
+            Message::Presence(_) if !self.contains(Self::PRESENCE) => None,
+            Message::IdentityMigration(_) if !self.contains(Self::IDENTITY_MIGRATION) => None,
+            Message::KeyCheck(_) | Message::KeyCheckReply(_) if !self.contains(Self::KEY_CHECK) => {
+                None
+            }
+            Message::ScratchpadUpdate(_) if !self.contains(Self::SCRATCHPAD) => None,
+            Message::LockState(_) if !self.contains(Self::LOCK_STATE) => None,
+            Message::TransferChunk(_)
+            | Message::TransferResume(_)
+            | Message::TransferCancel(_)
+            | Message::TransferComplete(_)
+                if !self.contains(Self::RESUMABLE_TRANSFERS) =>
+            {
+                None
+            }
+            _ => Some(message.clone()),
+        }
+    }
+}
+
+/// Lower-fidelity representation of content for peers that can't receive it
+fn fallback_content(content: &ClipboardContent) -> Option<ClipboardContent> {
+    match content.content_type {
+        ContentType::RichText => {
+            let html = content.as_text()?;
+            let text = strip_tags(&html);
+            if text.trim().is_empty() {
+                return None;
+            }
+
+            // Keep any remaining metadata from the original (e.g. its preview)
+            let mut fallback = ClipboardContent::text(&text);
+            fallback.metadata = ContentMetadata {
+                size_bytes: fallback.metadata.size_bytes,
+                mime_type: fallback.metadata.mime_type,
+                text_preview: fallback.metadata.text_preview,
+                ..content.metadata.clone()
+            };
+            Some(fallback)
+        }
+        ContentType::Code => {
+            let mut fallback = content.clone();
+            fallback.content_type = ContentType::PlainText;
+            fallback.metadata.language = None;
+            Some(fallback)
+        }
+        _ => None,
+    }
+}
+
+/// Drop markup tags, keeping the text between them
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{AutoAction, ClipboardAck, DeviceRemoved, Presence, PresenceState};
+
+    #[test]
+    fn test_contains_and_remove() {
+        let mut caps = Capabilities::current();
+        assert!(caps.contains(Capabilities::RICH_TEXT));
+        caps.remove(Capabilities::RICH_TEXT);
+        assert!(!caps.contains(Capabilities::RICH_TEXT));
+        assert!(caps.contains(Capabilities::IMAGES));
+        assert_eq!(
+            Capabilities::from_bits(caps.bits()),
+            caps,
+            "bits should round-trip"
+        );
+    }
+
+    #[test]
+    fn test_rich_text_falls_back_to_plain_text() {
+        let mut caps = Capabilities::current();
+        caps.remove(Capabilities::RICH_TEXT);
+
+        let mut update = ClipboardUpdate::new(ClipboardContent::new(
+            ContentType::RichText,
+            b"<b>Hello</b> world".to_vec(),
+        ))
+        .with_auto_action(AutoAction::OpenUrl);
+        update.sequence = 7;
+        let adapted = caps.adapt(&Message::ClipboardUpdate(update.clone()));
+
+        let Some(Message::ClipboardUpdate(fallback)) = adapted else {
+            panic!("expected a fallback clipboard update");
+        };
+        assert_eq!(fallback.content.content_type, ContentType::PlainText);
+        assert_eq!(fallback.content.as_text().unwrap(), "Hello world");
+        assert_eq!(fallback.content_hash, fallback.content.hash());
+        assert_eq!(fallback.sent_at, update.sent_at);
+        assert_eq!(fallback.sequence, 7);
+        assert_eq!(fallback.auto_action, Some(AutoAction::OpenUrl));
+
+        // A peer with rich text support gets the original
+        let Some(Message::ClipboardUpdate(original)) =
+            Capabilities::current().adapt(&Message::ClipboardUpdate(update))
+        else {
+            panic!("expected the original clipboard update");
+        };
+        assert_eq!(original.content.content_type, ContentType::RichText);
+    }
+
+    #[test]
+    fn test_code_falls_back_to_plain_text() {
+        let mut caps = Capabilities::current();
+        caps.remove(Capabilities::CODE_SNIPPETS);
+
+        let update = ClipboardUpdate::new(ClipboardContent::code("fn main() {}", Some("rust")));
+        let Some(Message::ClipboardUpdate(fallback)) =
+            caps.adapt(&Message::ClipboardUpdate(update.clone()))
+        else {
+            panic!("expected a fallback clipboard update");
+        };
+        assert_eq!(fallback.content.content_type, ContentType::PlainText);
+        assert_eq!(fallback.content.metadata.language, None);
+        assert_eq!(fallback.content.data, update.content.data);
+        assert_eq!(fallback.content_hash, update.content_hash);
+    }
+
+    #[test]
+    fn test_unsupported_messages_are_skipped_or_trimmed() {
+        let caps = Capabilities::empty();
+
+        let image = ClipboardUpdate::new(ClipboardContent::image(vec![0u8; 8], None, None));
+        assert!(caps.adapt(&Message::ClipboardUpdate(image)).is_none());
+
+        let removed = Message::DeviceRemoved(DeviceRemoved {
+            device_id: [1u8; 32],
+        });
+        assert!(caps.adapt(&removed).is_none());
+
+        let presence = Message::Presence(Presence {
+            state: PresenceState::Copying,
+        });
+        assert!(caps.adapt(&presence).is_none());
+
+        let ack = Message::ClipboardAck(ClipboardAck {
+            message_id: 0,
+            content_hash: [0u8; 32],
+            success: true,
+            error: None,
+            latency_ms: Some(12),
+        });
+        let Some(Message::ClipboardAck(trimmed)) = caps.adapt(&ack) else {
+            panic!("expected an ack");
+        };
+        assert_eq!(trimmed.latency_ms, None);
+
+        let url = ClipboardContent::new_text(ContentType::Url, "https://example.com");
+        let open = ClipboardUpdate::new(url).with_auto_action(AutoAction::OpenUrl);
+        let Some(Message::ClipboardUpdate(plain)) =
+            caps.adapt(&Message::ClipboardUpdate(open.clone()))
+        else {
+            panic!("expected an update");
+        };
+        assert_eq!(plain.auto_action, None);
+        assert_eq!(plain.content_hash, open.content_hash);
+
+        let keyed = ClipboardUpdate::new(ClipboardContent::text("hi")).with_keyed_hash(&[5u8; 32]);
+        let Some(Message::ClipboardUpdate(unkeyed)) = caps.adapt(&Message::ClipboardUpdate(keyed))
+        else {
+            panic!("expected an update");
+        };
+        assert!(!unkeyed.hash_keyed);
+        assert!(unkeyed.verify_hash(None));
+    }
+}
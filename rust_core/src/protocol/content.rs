@@ -4,9 +4,21 @@
 
 #![allow(dead_code)]
 
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+use super::MAX_PREVIEW_SIZE;
+use crate::error::ProtocolError;
+
+/// Bytes of text kept in the preview of new text content
+const TEXT_PREVIEW_LEN: usize = 200;
+
+/// Domain separation label for keyed content identifiers
+const KEYED_HASH_LABEL: &[u8] = b"toss-content-mac:";
+
+type HmacSha256 = Hmac<Sha256>;
+
 /// Type of clipboard content
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(u8)]
@@ -21,6 +33,8 @@ pub enum ContentType {
     File = 3,
     /// URL (detected from text)
     Url = 4,
+    /// Source code, with an optional language hint in the metadata
+    Code = 5,
 }
 
 impl ContentType {
@@ -32,6 +46,19 @@ impl ContentType {
             ContentType::Image => "image/png",
             ContentType::File => "application/octet-stream",
             ContentType::Url => "text/uri-list",
+            ContentType::Code => "text/plain",
+        }
+    }
+
+    /// Stable lowercase name used in API DTOs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentType::PlainText => "text",
+            ContentType::RichText => "rich_text",
+            ContentType::Image => "image",
+            ContentType::File => "file",
+            ContentType::Url => "url",
+            ContentType::Code => "code",
         }
     }
 }
@@ -46,36 +73,109 @@ impl TryFrom<u8> for ContentType {
             2 => Ok(ContentType::Image),
             3 => Ok(ContentType::File),
             4 => Ok(ContentType::Url),
+            5 => Ok(ContentType::Code),
             _ => Err(()),
         }
     }
 }
 
 /// Metadata about clipboard content
+///
+/// Every field is always encoded; see [`super::encoding`].
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ContentMetadata {
     /// Original filename (for files)
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub filename: Option<String>,
 
     /// MIME type
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub mime_type: Option<String>,
 
     /// Image dimensions (width, height)
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub dimensions: Option<(u32, u32)>,
 
     /// Preview/thumbnail data
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub preview: Option<Vec<u8>>,
 
     /// Size in bytes
     pub size_bytes: u64,
 
     /// Text preview (first N characters for text content)
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub text_preview: Option<String>,
+
+    /// Programming language of code content (e.g. "rust")
+    pub language: Option<String>,
+}
+
+impl ContentMetadata {
+    /// Cap the text preview at [`MAX_PREVIEW_SIZE`] bytes, on a char boundary
+    pub fn cap_text_preview(&mut self) {
+        if let Some(preview) = self.text_preview.as_mut() {
+            let len = truncate_at_char_boundary(preview, MAX_PREVIEW_SIZE).len();
+            preview.truncate(len);
+        }
+    }
+
+    /// Check that both previews are within [`MAX_PREVIEW_SIZE`]
+    pub fn validate(&self) -> Result<(), ProtocolError> {
+        let text_len = self.text_preview.as_ref().map_or(0, String::len);
+        let preview_len = self.preview.as_ref().map_or(0, Vec::len);
+        for (name, len) in [("Text preview", text_len), ("Preview", preview_len)] {
+            if len > MAX_PREVIEW_SIZE {
+                return Err(ProtocolError::InvalidFormat(format!(
+                    "{} of {} bytes exceeds {} bytes",
+                    name, len, MAX_PREVIEW_SIZE
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn new_keyed_mac(key: &[u8; 32]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC can take key of any size");
+    mac.update(KEYED_HASH_LABEL);
+    mac
+}
+
+/// Hash of content data read from `reader`, for data too large to hold in memory
+///
+/// Matches [`ClipboardContent::hash`], or [`ClipboardContent::keyed_hash`]
+/// when a key is given.
+pub fn hash_content_reader(
+    mut reader: impl std::io::Read,
+    key: Option<&[u8; 32]>,
+) -> std::io::Result<[u8; 32]> {
+    let mut sha = Sha256::new();
+    let mut mac = key.map(new_keyed_mac);
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        match mac.as_mut() {
+            Some(mac) => mac.update(&buf[..n]),
+            None => sha.update(&buf[..n]),
+        }
+    }
+    Ok(match mac {
+        Some(mac) => mac.finalize().into_bytes().into(),
+        None => sha.finalize().into(),
+    })
+}
+
+/// Longest prefix of `text` at most `max_bytes` long that ends on a char boundary
+fn truncate_at_char_boundary(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
 }
 
 /// Clipboard content with type and data
@@ -106,21 +206,31 @@ impl ClipboardContent {
         }
     }
 
-    /// Create text content
+    /// Create text content, detecting URLs and code snippets
     pub fn text(text: &str) -> Self {
+        if is_url(text) {
+            return Self::new_text(ContentType::Url, text);
+        }
+        match detect_code(text) {
+            Some(code) => Self::code(text, code.language.as_deref()),
+            None => Self::new_text(ContentType::PlainText, text),
+        }
+    }
+
+    /// Create a code snippet with an optional language hint
+    pub fn code(text: &str, language: Option<&str>) -> Self {
+        let mut content = Self::new_text(ContentType::Code, text);
+        content.metadata.language = language.map(str::to_string);
+        content
+    }
+
+    /// Create text content of an explicit text type, without detection
+    pub fn new_text(content_type: ContentType, text: &str) -> Self {
         let data = text.as_bytes().to_vec();
-        let preview = if text.len() > 200 {
-            Some(text[..200].to_string())
-        } else {
-            Some(text.to_string())
-        };
+        let preview = Some(truncate_at_char_boundary(text, TEXT_PREVIEW_LEN).to_string());
 
         Self {
-            content_type: if is_url(text) {
-                ContentType::Url
-            } else {
-                ContentType::PlainText
-            },
+            content_type,
             data,
             metadata: ContentMetadata {
                 size_bytes: text.len() as u64,
@@ -146,22 +256,77 @@ impl ClipboardContent {
     }
 
     /// Calculate SHA-256 hash of content
+    ///
+    /// Covers the raw data bytes only, which are identical on every platform,
+    /// so a mismatch on receipt means the content was altered.
     pub fn hash(&self) -> [u8; 32] {
         let mut hasher = Sha256::new();
         hasher.update(&self.data);
         hasher.finalize().into()
     }
 
+    /// HMAC-SHA256 of the content, keyed with a pairing's session key
+    ///
+    /// Unlike [`hash`](Self::hash), only devices holding `key` can compute it,
+    /// so an observer can't match it against hashes of guessed content.
+    pub fn keyed_hash(&self, key: &[u8; 32]) -> [u8; 32] {
+        self.keyed_mac(key).finalize().into_bytes().into()
+    }
+
+    /// Check a keyed hash in constant time
+    pub fn verify_keyed_hash(&self, key: &[u8; 32], mac: &[u8; 32]) -> bool {
+        self.keyed_mac(key).verify_slice(mac).is_ok()
+    }
+
+    fn keyed_mac(&self, key: &[u8; 32]) -> HmacSha256 {
+        let mut mac = new_keyed_mac(key);
+        mac.update(&self.data);
+        mac
+    }
+
     /// Get content as string (for text types)
     pub fn as_text(&self) -> Option<String> {
         match self.content_type {
-            ContentType::PlainText | ContentType::Url | ContentType::RichText => {
-                String::from_utf8(self.data.clone()).ok()
-            }
+            ContentType::PlainText
+            | ContentType::Url
+            | ContentType::RichText
+            | ContentType::Code => String::from_utf8(self.data.clone()).ok(),
             _ => None,
         }
     }
 
+    /// MIME type from the metadata, or the content type's default
+    pub fn mime_type(&self) -> String {
+        self.metadata
+            .mime_type
+            .clone()
+            .unwrap_or_else(|| self.content_type.mime_type().to_string())
+    }
+
+    /// Short description for display, specific to the content type
+    pub fn preview(&self) -> String {
+        let size = self.metadata.size_bytes;
+        match self.content_type {
+            ContentType::Image => match self.metadata.dimensions {
+                Some((width, height)) => format!("Image {}x{} ({} bytes)", width, height, size),
+                None => format!("Image ({} bytes)", size),
+            },
+            ContentType::File => self
+                .metadata
+                .filename
+                .clone()
+                .unwrap_or_else(|| format!("File ({} bytes)", size)),
+            ContentType::PlainText
+            | ContentType::RichText
+            | ContentType::Url
+            | ContentType::Code => self
+                .metadata
+                .text_preview
+                .clone()
+                .unwrap_or_else(|| format!("{} bytes", size)),
+        }
+    }
+
     /// Check if content is empty
     pub fn is_empty(&self) -> bool {
         self.data.is_empty()
@@ -177,6 +342,144 @@ pub fn is_url(text: &str) -> bool {
         || trimmed.starts_with("file://")
 }
 
+/// Code detected in clipboard text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeHint {
+    /// Language, if it could be identified
+    pub language: Option<String>,
+}
+
+/// Line prefixes characteristic of each language, used to score snippets
+const LANGUAGE_KEYWORDS: &[(&str, &[&str])] = &[
+    (
+        "rust",
+        &[
+            "fn ",
+            "pub fn ",
+            "let ",
+            "impl ",
+            "pub struct ",
+            "use std::",
+            "#[derive(",
+            "mod ",
+        ],
+    ),
+    (
+        "python",
+        &[
+            "def ",
+            "import ",
+            "from ",
+            "elif ",
+            "print(",
+            "if __name__ ==",
+            "class ",
+            "return ",
+        ],
+    ),
+    (
+        "javascript",
+        &[
+            "const ",
+            "function ",
+            "console.log(",
+            "export ",
+            "import {",
+            "let ",
+            "return ",
+        ],
+    ),
+    ("go", &["package ", "func ", "import (", "fmt."]),
+    ("c", &["#include ", "int main(", "printf(", "#define "]),
+    (
+        "sql",
+        &[
+            "SELECT ",
+            "INSERT INTO ",
+            "UPDATE ",
+            "DELETE FROM ",
+            "CREATE TABLE ",
+            "FROM ",
+            "WHERE ",
+        ],
+    ),
+    (
+        "shell",
+        &["echo ", "export ", "sudo ", "if [ ", "fi", "done"],
+    ),
+];
+
+/// Minimum number of keyword lines before text is treated as code
+const MIN_KEYWORD_LINES: usize = 2;
+
+/// Heuristically detect a code snippet and its language
+///
+/// Recognizes fenced blocks (```lang), shebang lines, and text where several
+/// lines start with one language's keywords. Returns `None` for prose and for
+/// text where no single language clearly wins, so it stays plain text.
+pub fn detect_code(text: &str) -> Option<CodeHint> {
+    let trimmed = text.trim();
+    let first_line = trimmed.lines().next()?;
+
+    // Fenced block, e.g. copied from Markdown
+    if let Some(tag) = first_line.strip_prefix("```") {
+        let tag = tag.trim().to_lowercase();
+        return Some(CodeHint {
+            language: (!tag.is_empty()).then_some(tag),
+        });
+    }
+
+    // Script with an interpreter line
+    if let Some(interpreter) = first_line.strip_prefix("#!") {
+        let language = [
+            ("python", "python"),
+            ("node", "javascript"),
+            ("ruby", "ruby"),
+            ("perl", "perl"),
+            ("bash", "shell"),
+            ("sh", "shell"),
+        ]
+        .iter()
+        .find(|(name, _)| interpreter.contains(name))
+        .map(|(_, language)| language.to_string());
+        return Some(CodeHint { language });
+    }
+
+    let mut scores: Vec<(&str, usize)> = LANGUAGE_KEYWORDS
+        .iter()
+        .map(|(language, keywords)| {
+            let lines = trimmed
+                .lines()
+                .map(str::trim_start)
+                .filter(|line| keywords.iter().any(|k| matches_keyword(line, k)))
+                .count();
+            (*language, lines)
+        })
+        .collect();
+    scores.sort_by_key(|&(_, lines)| std::cmp::Reverse(lines));
+
+    match scores.as_slice() {
+        [(language, best), (_, second), ..] if *best >= MIN_KEYWORD_LINES && best > second => {
+            Some(CodeHint {
+                language: Some(language.to_string()),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Whether a line starts with a keyword
+///
+/// Keywords ending in a letter (e.g. `fi`) must make up the whole line, so
+/// they don't match the start of ordinary words.
+fn matches_keyword(line: &str, keyword: &str) -> bool {
+    if keyword.ends_with(|c: char| c.is_ascii_alphanumeric()) {
+        line.trim_end() == keyword
+    } else {
+        line.starts_with(keyword)
+    }
+}
+
 /// Detect content type from raw data
 pub fn detect_content_type(data: &[u8]) -> ContentType {
     // Check for image magic bytes
@@ -206,6 +509,9 @@ pub fn detect_content_type(data: &[u8]) -> ContentType {
         if is_url(text) {
             return ContentType::Url;
         }
+        if detect_code(text).is_some() {
+            return ContentType::Code;
+        }
         // Valid UTF-8 text
         return ContentType::PlainText;
     }
@@ -218,6 +524,28 @@ pub fn detect_content_type(data: &[u8]) -> ContentType {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_text_preview_truncated_on_char_boundary() {
+        // 'é' is two bytes, so byte 200 falls inside a character
+        let text = format!("a{}", "é".repeat(150));
+        let content = ClipboardContent::text(&text);
+        let preview = content.metadata.text_preview.unwrap();
+        assert_eq!(preview.len(), 199);
+        assert!(text.starts_with(&preview));
+
+        let mut metadata = ContentMetadata {
+            text_preview: Some("é".repeat(MAX_PREVIEW_SIZE)),
+            ..Default::default()
+        };
+        assert!(metadata.validate().is_err());
+        metadata.cap_text_preview();
+        assert_eq!(
+            metadata.text_preview.as_ref().unwrap().len(),
+            MAX_PREVIEW_SIZE
+        );
+        assert!(metadata.validate().is_ok());
+    }
+
     #[test]
     fn test_content_type_from_u8() {
         assert_eq!(ContentType::try_from(0).unwrap(), ContentType::PlainText);
@@ -225,6 +553,24 @@ mod tests {
         assert!(ContentType::try_from(255).is_err());
     }
 
+    #[test]
+    fn test_type_specific_preview_and_mime() {
+        let text = ClipboardContent::text("Hello");
+        assert_eq!(text.preview(), "Hello");
+        assert_eq!(text.mime_type(), "text/plain");
+
+        let image = ClipboardContent::image(vec![0u8; 64], Some((4, 4)), None);
+        assert_eq!(image.preview(), "Image 4x4 (64 bytes)");
+        assert_eq!(image.mime_type(), "image/png");
+
+        let mut file = ClipboardContent::new(ContentType::File, vec![0u8; 10]);
+        assert_eq!(file.preview(), "File (10 bytes)");
+        file.metadata.filename = Some("notes.pdf".to_string());
+        file.metadata.mime_type = Some("application/pdf".to_string());
+        assert_eq!(file.preview(), "notes.pdf");
+        assert_eq!(file.mime_type(), "application/pdf");
+    }
+
     #[test]
     fn test_is_url() {
         assert!(is_url("https://example.com"));
@@ -265,6 +611,23 @@ mod tests {
         assert_ne!(content1.hash(), content3.hash());
     }
 
+    #[test]
+    fn test_keyed_hash_differs_per_session_and_matches_across_peers() {
+        let content = ClipboardContent::text("Hello");
+        let (session_a, session_b) = ([1u8; 32], [2u8; 32]);
+
+        // Both ends of a pairing hold the same session key
+        let sent = content.keyed_hash(&session_a);
+        let received = ClipboardContent::text("Hello");
+        assert!(received.verify_keyed_hash(&session_a, &sent));
+        assert_eq!(received.keyed_hash(&session_a), sent);
+
+        // Another pairing, or anyone recomputing a plain hash, gets something else
+        assert_ne!(content.keyed_hash(&session_b), sent);
+        assert_ne!(content.hash(), sent);
+        assert!(!received.verify_keyed_hash(&session_b, &sent));
+    }
+
     #[test]
     fn test_text_content() {
         let content = ClipboardContent::text("Hello, World!");
@@ -272,6 +635,48 @@ mod tests {
         assert_eq!(content.as_text().unwrap(), "Hello, World!");
     }
 
+    #[test]
+    fn test_code_detection() {
+        let rust = "use std::fs;\n\nfn main() {\n    let mut count = 0;\n}";
+        let python = "#!/usr/bin/env python3\nprint('hi')";
+        let fenced = "```TypeScript\nconst x: number = 1;\n```";
+        let sql = "SELECT id, name\nFROM devices\nWHERE is_active = 1";
+
+        for (text, language) in [
+            (rust, "rust"),
+            (python, "python"),
+            (fenced, "typescript"),
+            (sql, "sql"),
+        ] {
+            let content = ClipboardContent::text(text);
+            assert_eq!(content.content_type, ContentType::Code, "{}", text);
+            assert_eq!(content.metadata.language.as_deref(), Some(language));
+            assert_eq!(content.as_text().unwrap(), text);
+        }
+
+        // Fenced block without a language tag
+        assert_eq!(
+            detect_code("```\nmake build\n```"),
+            Some(CodeHint { language: None })
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_text_stays_plain() {
+        for text in [
+            "Hello, World!",
+            "Let me import the photos from the trip.\nThen we can pick the best ones.",
+            // Valid in both Rust and JavaScript
+            "let x = 1;\nlet y = 2;",
+            // A single keyword line isn't enough
+            "def leppard tour dates",
+        ] {
+            let content = ClipboardContent::text(text);
+            assert_eq!(content.content_type, ContentType::PlainText, "{}", text);
+            assert_eq!(content.metadata.language, None);
+        }
+    }
+
     #[test]
     fn test_url_detection_in_text() {
         let content = ClipboardContent::text("https://github.com");
@@ -0,0 +1,107 @@
+//! Canonical binary encoding
+//!
+//! Every bincode payload (wire messages, chunked transfers, encrypted history)
+//! goes through [`encode`] and [`decode`], so both sides of a connection and
+//! every platform produce the same bytes for the same value: fixed-width
+//! little-endian integers, with trailing bytes rejected. This matches the
+//! layout of bincode 1's default `serialize`, so existing data stays readable.
+//!
+//! The encoding is non-self-describing: every field is always written, so
+//! structs sent over the wire must not use `skip_serializing_if`.
+
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::ProtocolError;
+
+/// Version of the canonical encoding, sent in every frame header; bump on any
+/// layout change
+pub const ENCODING_VERSION: u8 = 1;
+
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_little_endian()
+        .reject_trailing_bytes()
+}
+
+/// Encode a value with the canonical options
+pub fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, ProtocolError> {
+    options()
+        .serialize(value)
+        .map_err(|e| ProtocolError::Serialization(e.to_string()))
+}
+
+/// Decode a value encoded with [`encode`]
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, ProtocolError> {
+    options()
+        .deserialize(bytes)
+        .map_err(|e| ProtocolError::Deserialization(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{ClipboardContent, ContentMetadata, ContentType};
+    use sha2::{Digest, Sha256};
+
+    fn fixed_content() -> ClipboardContent {
+        ClipboardContent {
+            content_type: ContentType::PlainText,
+            data: b"hi".to_vec(),
+            metadata: ContentMetadata {
+                size_bytes: 2,
+                language: Some("rust".to_string()),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_encoding_is_fixed_width_little_endian() {
+        let encoded = encode(&fixed_content()).unwrap();
+
+        // Golden bytes: any change here breaks compatibility between peers
+        let expected = hex::decode(concat!(
+            "00000000",         // content_type: PlainText (u32 variant index)
+            "0200000000000000", // data length
+            "6869",             // "hi"
+            "00",               // filename: None
+            "00",               // mime_type: None
+            "00",               // dimensions: None
+            "00",               // preview: None
+            "0200000000000000", // size_bytes
+            "00",               // text_preview: None
+            "01",               // language: Some
+            "0400000000000000", // language length
+            "72757374",         // "rust"
+        ))
+        .unwrap();
+        assert_eq!(encoded, expected);
+        assert_eq!(
+            hex::encode(Sha256::digest(&encoded)),
+            "99c47e662cc91f079dbe5bbda1e7da1ca10f6349841d7c7193025ee5a6c57e9e"
+        );
+
+        let decoded: ClipboardContent = decode(&encoded).unwrap();
+        assert_eq!(decoded.data, b"hi");
+        assert_eq!(decoded.metadata.language.as_deref(), Some("rust"));
+        assert!(decoded.metadata.filename.is_none());
+    }
+
+    #[test]
+    fn test_encode_then_hash_is_stable() {
+        let first = encode(&fixed_content()).unwrap();
+        let second = encode(&decode::<ClipboardContent>(&first).unwrap()).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(Sha256::digest(&first), Sha256::digest(&second));
+    }
+
+    #[test]
+    fn test_trailing_bytes_rejected() {
+        let mut encoded = encode(&fixed_content()).unwrap();
+        encoded.push(0);
+        assert!(decode::<ClipboardContent>(&encoded).is_err());
+    }
+}
@@ -0,0 +1,51 @@
+//! Messages of resumable transfers
+//!
+//! Chunks, resumes and the end of a transfer; the transfers themselves are
+//! run by `network::transfer`.
+
+use serde::{Deserialize, Serialize};
+
+/// One chunk of a resumable transfer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferChunk {
+    /// Transfer this chunk belongs to
+    pub transfer_id: u64,
+    /// Position of this chunk (0-based)
+    pub index: u32,
+    /// Number of chunks in the transfer
+    pub total_chunks: u32,
+    /// Size of the complete payload
+    pub total_bytes: u64,
+    /// SHA-256 of the complete payload
+    pub payload_hash: [u8; 32],
+    /// Chunk bytes
+    pub data: Vec<u8>,
+}
+
+/// Request from a receiver to continue an interrupted transfer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferResume {
+    /// Transfer to resume
+    pub transfer_id: u64,
+    /// Chunk indices already received
+    pub received_indices: Vec<u32>,
+}
+
+/// Notice that one side abandoned a transfer
+///
+/// Sent in either direction; the other side drops its state for the
+/// transfer and ignores any further chunks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferCancel {
+    /// Transfer that was canceled
+    pub transfer_id: u64,
+}
+
+/// Notice from a receiver that it has the whole payload of a transfer
+///
+/// The sender releases the source instead of keeping it for a resume.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferComplete {
+    /// Transfer that was received
+    pub transfer_id: u64,
+}
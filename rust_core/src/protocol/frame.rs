@@ -1,11 +1,13 @@
 //! Wire frame encoding with encryption
 
 use super::message::MessageHeader;
-use crate::crypto::{decrypt, encrypt, EncryptedMessage, KEY_SIZE, NONCE_SIZE, TAG_SIZE};
+use crate::crypto::{
+    decrypt, encrypt, encrypt_with_nonce, EncryptedMessage, KEY_SIZE, NONCE_SIZE, TAG_SIZE,
+};
 use crate::error::{CryptoError, ProtocolError};
 
 /// Frame format:
-/// [version: 2 bytes][type: 1 byte][reserved: 1 byte][message_id: 8 bytes][timestamp: 8 bytes][payload_length: 4 bytes][nonce: 12 bytes][encrypted_payload: N bytes][tag: 16 bytes]
+/// [version: 2 bytes][type: 1 byte][encoding_version: 1 byte][message_id: 8 bytes][timestamp: 8 bytes][payload_length: 4 bytes][nonce: 12 bytes][encrypted_payload: N bytes][tag: 16 bytes]
 const HEADER_SIZE: usize = 2 + 1 + 1 + 8 + 8 + 4; // 24 bytes
 
 /// Wire frame containing encrypted message
@@ -34,6 +36,22 @@ impl Frame {
         })
     }
 
+    /// Create a new frame by encrypting a message with an explicit nonce
+    pub fn encrypt_with_nonce(
+        header: &MessageHeader,
+        payload: &[u8],
+        key: &[u8; KEY_SIZE],
+        nonce: &[u8; NONCE_SIZE],
+    ) -> Result<Self, CryptoError> {
+        let aad = Self::header_to_bytes(header);
+        let encrypted = encrypt_with_nonce(key, nonce, payload, &aad)?;
+
+        Ok(Self {
+            header: header.clone(),
+            encrypted,
+        })
+    }
+
     /// Decrypt the frame payload
     pub fn decrypt(&self, key: &[u8; KEY_SIZE]) -> Result<(MessageHeader, Vec<u8>), CryptoError> {
         let aad = Self::header_to_bytes(&self.header);
@@ -53,7 +71,7 @@ impl Frame {
         // Header
         bytes.extend_from_slice(&self.header.version.to_le_bytes());
         bytes.push(self.header.message_type as u8);
-        bytes.push(0); // Reserved
+        bytes.push(self.header.encoding_version);
         bytes.extend_from_slice(&self.header.message_id.to_le_bytes());
         bytes.extend_from_slice(&self.header.timestamp.to_le_bytes());
         bytes.extend_from_slice(&payload_len.to_le_bytes());
@@ -73,7 +91,7 @@ impl Frame {
         // Parse header
         let version = u16::from_le_bytes([bytes[0], bytes[1]]);
         let message_type = bytes[2].try_into()?;
-        // bytes[3] is reserved
+        let encoding_version = bytes[3];
         let message_id = u64::from_le_bytes(bytes[4..12].try_into().unwrap());
         let timestamp = u64::from_le_bytes(bytes[12..20].try_into().unwrap());
         let payload_len = u32::from_le_bytes(bytes[20..24].try_into().unwrap()) as usize;
@@ -101,6 +119,7 @@ impl Frame {
         let header = MessageHeader {
             version,
             message_type,
+            encoding_version,
             message_id,
             timestamp,
         };
@@ -113,7 +132,7 @@ impl Frame {
         let mut bytes = Vec::with_capacity(20);
         bytes.extend_from_slice(&header.version.to_le_bytes());
         bytes.push(header.message_type as u8);
-        bytes.push(0); // Reserved
+        bytes.push(header.encoding_version);
         bytes.extend_from_slice(&header.message_id.to_le_bytes());
         bytes.extend_from_slice(&header.timestamp.to_le_bytes());
         bytes
@@ -135,6 +154,7 @@ impl Frame {
         Ok(MessageHeader {
             version,
             message_type,
+            encoding_version: bytes[3],
             message_id,
             timestamp,
         })
@@ -144,7 +164,8 @@ impl Frame {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::protocol::MessageType;
+    use crate::protocol::encoding::ENCODING_VERSION;
+    use crate::protocol::{Message, MessageType, Ping};
     use rand::{rngs::StdRng, RngCore, SeedableRng};
 
     fn random_key() -> [u8; KEY_SIZE] {
@@ -168,9 +189,24 @@ mod tests {
         assert_eq!(parsed_header.version, header.version);
         assert_eq!(parsed_header.message_type, header.message_type);
         assert_eq!(parsed_header.message_id, header.message_id);
+        assert_eq!(parsed_header.encoding_version, ENCODING_VERSION);
         assert_eq!(decrypted, payload);
     }
 
+    #[test]
+    fn test_other_encoding_version_rejected() {
+        let key = random_key();
+        let message = Message::Ping(Ping::default());
+        let mut header = message.header();
+        header.encoding_version = ENCODING_VERSION + 1;
+        let payload = message.serialize().unwrap();
+
+        let bytes = Frame::encrypt(&header, &payload, &key).unwrap().to_bytes();
+        let (parsed_header, decrypted) = Frame::from_bytes(&bytes).unwrap().decrypt(&key).unwrap();
+        assert_eq!(parsed_header.encoding_version, ENCODING_VERSION + 1);
+        assert!(Message::deserialize(&parsed_header, &decrypted).is_err());
+    }
+
     #[test]
     fn test_peek_header() {
         let key = random_key();
@@ -1,10 +1,15 @@
 //! Message types for Toss protocol
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
+use super::capabilities::Capabilities;
 use super::content::ClipboardContent;
+use super::file_transfer::{TransferCancel, TransferChunk, TransferComplete, TransferResume};
+use super::MAX_SCRATCHPAD_SIZE;
+use crate::crypto::{DeviceIdentity, KeyAlgorithm};
 use crate::error::ProtocolError;
 
 /// Message type identifier
@@ -17,7 +22,18 @@ pub enum MessageType {
     ClipboardAck = 0x11,
     ClipboardRequest = 0x12,
     DeviceInfo = 0x20,
+    DeviceRemoved = 0x21,
+    Presence = 0x22,
+    LockState = 0x23,
     KeyRotation = 0x30,
+    IdentityMigration = 0x31,
+    KeyCheck = 0x32,
+    KeyCheckReply = 0x33,
+    TransferChunk = 0x40,
+    TransferResume = 0x41,
+    TransferCancel = 0x42,
+    TransferComplete = 0x43,
+    ScratchpadUpdate = 0x50,
     Error = 0xFF,
 }
 
@@ -32,7 +48,18 @@ impl TryFrom<u8> for MessageType {
             0x11 => Ok(MessageType::ClipboardAck),
             0x12 => Ok(MessageType::ClipboardRequest),
             0x20 => Ok(MessageType::DeviceInfo),
+            0x21 => Ok(MessageType::DeviceRemoved),
+            0x22 => Ok(MessageType::Presence),
+            0x23 => Ok(MessageType::LockState),
             0x30 => Ok(MessageType::KeyRotation),
+            0x31 => Ok(MessageType::IdentityMigration),
+            0x32 => Ok(MessageType::KeyCheck),
+            0x33 => Ok(MessageType::KeyCheckReply),
+            0x40 => Ok(MessageType::TransferChunk),
+            0x41 => Ok(MessageType::TransferResume),
+            0x42 => Ok(MessageType::TransferCancel),
+            0x43 => Ok(MessageType::TransferComplete),
+            0x50 => Ok(MessageType::ScratchpadUpdate),
             0xFF => Ok(MessageType::Error),
             _ => Err(ProtocolError::UnknownMessageType(value)),
         }
@@ -82,6 +109,8 @@ pub struct MessageHeader {
     pub version: u16,
     /// Message type
     pub message_type: MessageType,
+    /// Version of the canonical encoding the payload uses
+    pub encoding_version: u8,
     /// Unique message ID
     pub message_id: u64,
     /// Timestamp (Unix milliseconds)
@@ -94,6 +123,7 @@ impl MessageHeader {
         Self {
             version: crate::PROTOCOL_VERSION,
             message_type,
+            encoding_version: super::encoding::ENCODING_VERSION,
             message_id: generate_message_id(),
             timestamp: current_timestamp_ms(),
         }
@@ -130,23 +160,78 @@ impl Pong {
     }
 }
 
+/// Action the sender asks the receiver to take on an update, besides
+/// writing it to the clipboard
+///
+/// Receivers ignore it unless they explicitly allow remote actions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AutoAction {
+    /// Open the URL the update carries
+    OpenUrl,
+}
+
 /// Clipboard update message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClipboardUpdate {
     /// Clipboard content
     pub content: ClipboardContent,
-    /// SHA-256 hash of plaintext content
+    /// SHA-256 hash of plaintext content, or its keyed MAC if `hash_keyed`
     pub content_hash: [u8; 32],
+    /// When the sender created the update (Unix milliseconds, sender clock)
+    pub sent_at: u64,
+    /// Action requested of the receiver, if any
+    pub auto_action: Option<AutoAction>,
+    /// Whether `content_hash` is keyed with the pairing's session key
+    pub hash_keyed: bool,
+    /// Position among the updates sent to this receiver, 0 if unsequenced
+    pub sequence: u64,
 }
 
 impl ClipboardUpdate {
-    pub fn new(content: ClipboardContent) -> Self {
+    /// Wrap content for sending, capping its text preview
+    pub fn new(mut content: ClipboardContent) -> Self {
+        content.metadata.cap_text_preview();
         let content_hash = content.hash();
         Self {
             content,
             content_hash,
+            sent_at: current_timestamp_ms(),
+            auto_action: None,
+            hash_keyed: false,
+            sequence: 0,
         }
     }
+
+    /// Identify the content by a MAC keyed with `session_key` instead of SHA-256
+    pub fn with_keyed_hash(mut self, session_key: &[u8; 32]) -> Self {
+        self.content_hash = self.content.keyed_hash(session_key);
+        self.hash_keyed = true;
+        self
+    }
+
+    /// Identify the content by its plain SHA-256 hash
+    pub fn with_plain_hash(mut self) -> Self {
+        self.content_hash = self.content.hash();
+        self.hash_keyed = false;
+        self
+    }
+
+    /// Check `content_hash` against the content
+    ///
+    /// A keyed hash can only be checked with the sender's session key.
+    pub fn verify_hash(&self, session_key: Option<&[u8; 32]>) -> bool {
+        if self.hash_keyed {
+            session_key.is_some_and(|key| self.content.verify_keyed_hash(key, &self.content_hash))
+        } else {
+            self.content.hash() == self.content_hash
+        }
+    }
+
+    /// Ask the receiver to take `action` on this update
+    pub fn with_auto_action(mut self, action: AutoAction) -> Self {
+        self.auto_action = Some(action);
+        self
+    }
 }
 
 /// Acknowledgment of clipboard receipt
@@ -160,6 +245,8 @@ pub struct ClipboardAck {
     pub success: bool,
     /// Error message if failed
     pub error: Option<String>,
+    /// One-way delivery latency measured by the receiver (milliseconds)
+    pub latency_ms: Option<u64>,
 }
 
 /// Request current clipboard from peer
@@ -180,6 +267,12 @@ pub struct DeviceInfo {
     pub platform: Platform,
     /// Toss version
     pub version: String,
+    /// Optional protocol features the device supports
+    pub capabilities: Capabilities,
+    /// Algorithm of the device's identity key, for verifying its signatures
+    pub identity_algorithm: KeyAlgorithm,
+    /// Lowest protection the device accepts for messages in this session
+    pub min_security: SecurityLevel,
 }
 
 impl DeviceInfo {
@@ -189,8 +282,81 @@ impl DeviceInfo {
             device_name,
             platform: Platform::current(),
             version: crate::VERSION.to_string(),
+            capabilities: Capabilities::current(),
+            identity_algorithm: KeyAlgorithm::default(),
+            min_security: SecurityLevel::default(),
         }
     }
+
+    /// Set the algorithm of the device's identity key
+    pub fn with_identity_algorithm(mut self, algorithm: KeyAlgorithm) -> Self {
+        self.identity_algorithm = algorithm;
+        self
+    }
+
+    /// Set the lowest protection the device accepts
+    pub fn with_min_security(mut self, level: SecurityLevel) -> Self {
+        self.min_security = level;
+        self
+    }
+}
+
+/// Protection a message had on the wire, ordered from weakest to strongest
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityLevel {
+    /// Plain relay payload (`0x00` marker), only for legacy peers
+    Unencrypted,
+    /// Encrypted with the session key or a channel key
+    #[default]
+    Encrypted,
+}
+
+/// Notification that the sender has removed this device from its pairings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceRemoved {
+    /// Device ID of the sender
+    pub device_id: [u8; 32],
+}
+
+/// What a device is doing right now, shown by peers as a transient hint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PresenceState {
+    #[default]
+    Idle,
+    Copying,
+}
+
+impl PresenceState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Idle => "idle",
+            Self::Copying => "copying",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "idle" => Some(Self::Idle),
+            "copying" => Some(Self::Copying),
+            _ => None,
+        }
+    }
+}
+
+/// Presence hint; never carries clipboard content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Presence {
+    pub state: PresenceState,
+}
+
+/// Whether the sending device's screen is locked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockState {
+    pub locked: bool,
 }
 
 /// Key rotation request
@@ -205,6 +371,205 @@ pub struct KeyRotation {
     pub reason: KeyRotationReason,
 }
 
+/// Context prefixed to the statement signed in an `IdentityMigration`
+const IDENTITY_MIGRATION_CONTEXT: &[u8] = b"toss-identity-migration-v1:";
+
+/// Announcement that a device replaced its identity key
+///
+/// Signed by the old identity key, which paired peers already trust, and by
+/// the new one, proving the device holds it. Peers that accept it store the
+/// device under the new key and its new device ID.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentityMigration {
+    /// New identity public key
+    pub new_public_key: [u8; 32],
+    /// Signature algorithm of the new key
+    pub new_algorithm: KeyAlgorithm,
+    /// Signature over the new key by the old identity key
+    #[serde(with = "signature_bytes")]
+    pub old_key_signature: [u8; 64],
+    /// Signature over the new key by the new identity key
+    #[serde(with = "signature_bytes")]
+    pub new_key_signature: [u8; 64],
+}
+
+impl IdentityMigration {
+    /// Announce the move from `old` to `new`, signed by both
+    pub fn new(old: &DeviceIdentity, new: &DeviceIdentity) -> Self {
+        let new_public_key = new.public_key();
+        let statement = Self::statement(new.algorithm(), &new_public_key);
+        Self {
+            new_public_key,
+            new_algorithm: new.algorithm(),
+            old_key_signature: old.sign(&statement),
+            new_key_signature: new.sign(&statement),
+        }
+    }
+
+    /// Check both signatures, given the sender's current identity key
+    pub fn verify(&self, old_algorithm: KeyAlgorithm, old_public_key: &[u8]) -> bool {
+        let statement = Self::statement(self.new_algorithm, &self.new_public_key);
+        DeviceIdentity::verify_from_public_key(
+            old_algorithm,
+            old_public_key,
+            &statement,
+            &self.old_key_signature,
+        ) && DeviceIdentity::verify_from_public_key(
+            self.new_algorithm,
+            &self.new_public_key,
+            &statement,
+            &self.new_key_signature,
+        )
+    }
+
+    /// Device ID the sender has under its new key
+    pub fn new_device_id(&self) -> [u8; 32] {
+        DeviceIdentity::device_id_for(&self.new_public_key)
+    }
+
+    fn statement(algorithm: KeyAlgorithm, public_key: &[u8; 32]) -> Vec<u8> {
+        let mut statement = IDENTITY_MIGRATION_CONTEXT.to_vec();
+        statement.push(algorithm.tag());
+        statement.extend_from_slice(public_key);
+        statement
+    }
+}
+
+/// Challenge checking that the peer stored the same session key
+///
+/// Carries a random nonce sealed with the sender's stored session key for
+/// the peer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyCheck {
+    /// Matches the reply to the challenge
+    pub challenge_id: u64,
+    /// Nonce sealed with the sender's stored session key
+    pub sealed_nonce: Vec<u8>,
+}
+
+/// Answer to a `KeyCheck`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyCheckReply {
+    /// The challenge being answered
+    pub challenge_id: u64,
+    /// The challenge's nonce sealed again with the responder's stored
+    /// session key; empty if the responder couldn't open the challenge
+    pub sealed_nonce: Vec<u8>,
+}
+
+/// Number of edits seen from each device
+///
+/// One version covers another when it has seen at least as many edits from
+/// every device; when neither covers the other, they were made concurrently.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionVector(BTreeMap<[u8; 32], u64>);
+
+impl VersionVector {
+    /// Whether no edits have been seen
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Edits seen from `device_id`
+    pub fn get(&self, device_id: &[u8; 32]) -> u64 {
+        self.0.get(device_id).copied().unwrap_or(0)
+    }
+
+    /// Count one more edit from `device_id`
+    pub fn increment(&mut self, device_id: &[u8; 32]) {
+        *self.0.entry(*device_id).or_default() += 1;
+    }
+
+    /// Whether this version has seen every edit `other` has
+    pub fn covers(&self, other: &VersionVector) -> bool {
+        other
+            .0
+            .iter()
+            .all(|(device_id, &count)| self.get(device_id) >= count)
+    }
+
+    /// Take in every edit `other` has seen
+    pub fn merge(&mut self, other: &VersionVector) {
+        for (device_id, &count) in &other.0 {
+            let entry = self.0.entry(*device_id).or_default();
+            *entry = (*entry).max(count);
+        }
+    }
+}
+
+/// Replacement of a range of text, counted in chars
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScratchpadPatch {
+    /// First replaced char
+    pub start: u32,
+    /// Number of chars removed at `start`
+    pub delete: u32,
+    /// Text inserted at `start`
+    pub insert: String,
+}
+
+impl ScratchpadPatch {
+    /// Smallest single replacement turning `old` into `new`
+    pub fn diff(old: &str, new: &str) -> Self {
+        let old: Vec<char> = old.chars().collect();
+        let new: Vec<char> = new.chars().collect();
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        Self {
+            start: prefix as u32,
+            delete: (old.len() - prefix - suffix) as u32,
+            insert: new[prefix..new.len() - suffix].iter().collect(),
+        }
+    }
+
+    /// Apply to `text`, or `None` if the range is out of bounds
+    pub fn apply(&self, text: &str) -> Option<String> {
+        let chars: Vec<char> = text.chars().collect();
+        let start = self.start as usize;
+        let end = start.checked_add(self.delete as usize)?;
+        if end > chars.len() {
+            return None;
+        }
+
+        let mut patched: String = chars[..start].iter().collect();
+        patched.push_str(&self.insert);
+        patched.extend(&chars[end..]);
+        Some(patched)
+    }
+}
+
+/// Edit of the shared scratchpad document
+///
+/// `patch` turns the text at `base_version` into the text at `version`. An
+/// empty `base_version` means the patch applies to empty text, i.e. carries
+/// the whole document. Concurrent versions are ordered by
+/// `(edited_at, editor)`, like clipboard updates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScratchpadUpdate {
+    /// Version the patch applies to
+    pub base_version: VersionVector,
+    /// Version after the patch
+    pub version: VersionVector,
+    /// Edit time of the resulting text (Unix milliseconds, Lamport style)
+    pub edited_at: u64,
+    /// Device whose edit produced the resulting text
+    pub editor: [u8; 32],
+    pub patch: ScratchpadPatch,
+}
+
+impl ScratchpadUpdate {
+    /// Whether this update carries the whole document
+    pub fn is_full(&self) -> bool {
+        self.base_version.is_empty()
+    }
+}
+
 /// Custom serialization for 64-byte arrays (ed25519 signatures)
 mod signature_bytes {
     use base64::Engine;
@@ -259,8 +624,20 @@ pub enum Message {
     ClipboardAck(ClipboardAck),
     ClipboardRequest(ClipboardRequest),
     DeviceInfo(DeviceInfo),
+    DeviceRemoved(DeviceRemoved),
     KeyRotation(KeyRotation),
+    TransferChunk(TransferChunk),
+    TransferResume(TransferResume),
     Error(ErrorMessage),
+    // Appended so the encoded variant indices of older messages don't change
+    TransferCancel(TransferCancel),
+    Presence(Presence),
+    IdentityMigration(IdentityMigration),
+    KeyCheck(KeyCheck),
+    KeyCheckReply(KeyCheckReply),
+    ScratchpadUpdate(ScratchpadUpdate),
+    LockState(LockState),
+    TransferComplete(TransferComplete),
 }
 
 impl Message {
@@ -273,7 +650,18 @@ impl Message {
             Message::ClipboardAck(_) => MessageType::ClipboardAck,
             Message::ClipboardRequest(_) => MessageType::ClipboardRequest,
             Message::DeviceInfo(_) => MessageType::DeviceInfo,
+            Message::DeviceRemoved(_) => MessageType::DeviceRemoved,
             Message::KeyRotation(_) => MessageType::KeyRotation,
+            Message::TransferChunk(_) => MessageType::TransferChunk,
+            Message::TransferResume(_) => MessageType::TransferResume,
+            Message::TransferCancel(_) => MessageType::TransferCancel,
+            Message::Presence(_) => MessageType::Presence,
+            Message::IdentityMigration(_) => MessageType::IdentityMigration,
+            Message::KeyCheck(_) => MessageType::KeyCheck,
+            Message::KeyCheckReply(_) => MessageType::KeyCheckReply,
+            Message::ScratchpadUpdate(_) => MessageType::ScratchpadUpdate,
+            Message::LockState(_) => MessageType::LockState,
+            Message::TransferComplete(_) => MessageType::TransferComplete,
             Message::Error(_) => MessageType::Error,
         };
         MessageHeader::new(message_type)
@@ -281,7 +669,7 @@ impl Message {
 
     /// Serialize message payload
     pub fn serialize(&self) -> Result<Vec<u8>, ProtocolError> {
-        bincode::serialize(self).map_err(|e| ProtocolError::Serialization(e.to_string()))
+        super::encoding::encode(self)
     }
 
     /// Deserialize message from header and payload
@@ -290,8 +678,39 @@ impl Message {
         if header.version > crate::PROTOCOL_VERSION {
             return Err(ProtocolError::UnsupportedVersion(header.version));
         }
+        // A payload in another encoding would decode to garbage, or not at all
+        if header.encoding_version != super::encoding::ENCODING_VERSION {
+            return Err(ProtocolError::InvalidFormat(format!(
+                "Unsupported encoding version {}",
+                header.encoding_version
+            )));
+        }
+
+        Self::decode(payload)
+    }
 
-        bincode::deserialize(payload).map_err(|e| ProtocolError::Deserialization(e.to_string()))
+    /// Decode a message payload and check it against protocol limits
+    pub fn decode(payload: &[u8]) -> Result<Self, ProtocolError> {
+        let message: Self = super::encoding::decode(payload)?;
+        message.validate()?;
+        Ok(message)
+    }
+
+    /// Check limits the encoding itself can't express
+    pub fn validate(&self) -> Result<(), ProtocolError> {
+        match self {
+            Message::ClipboardUpdate(update) => update.content.metadata.validate(),
+            Message::ScratchpadUpdate(update)
+                if update.patch.insert.len() > MAX_SCRATCHPAD_SIZE =>
+            {
+                Err(ProtocolError::InvalidFormat(format!(
+                    "Scratchpad patch of {} bytes exceeds {} bytes",
+                    update.patch.insert.len(),
+                    MAX_SCRATCHPAD_SIZE
+                )))
+            }
+            _ => Ok(()),
+        }
     }
 }
 
@@ -314,9 +733,9 @@ fn current_timestamp_ms() -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::protocol::MAX_PREVIEW_SIZE;
 
     #[test]
-    #[ignore = "bincode enum encoding needs investigation"]
     fn test_message_serialization_roundtrip() {
         // Test ClipboardUpdate serialization using the Message::serialize method
         let content = ClipboardContent::text("Hello, World!");
@@ -349,6 +768,7 @@ mod tests {
             content_hash: [0xAB; 32],
             success: true,
             error: None,
+            latency_ms: Some(42),
         };
         let message = Message::ClipboardAck(ack.clone());
 
@@ -361,6 +781,7 @@ mod tests {
                 assert_eq!(ack.message_id, deserialized_ack.message_id);
                 assert_eq!(ack.content_hash, deserialized_ack.content_hash);
                 assert_eq!(ack.success, deserialized_ack.success);
+                assert_eq!(ack.latency_ms, deserialized_ack.latency_ms);
             }
             _ => panic!("Expected ClipboardAck"),
         }
@@ -443,6 +864,70 @@ mod tests {
         assert_ne!(update.content_hash, [0u8; 32]);
     }
 
+    #[test]
+    fn test_oversized_text_preview_capped_on_send_and_rejected_on_receive() {
+        let mut content = ClipboardContent::text("big");
+        content.metadata.text_preview = Some("€".repeat(MAX_PREVIEW_SIZE));
+
+        // Building the update caps the preview on a char boundary
+        let update = ClipboardUpdate::new(content);
+        let preview = update.content.metadata.text_preview.as_ref().unwrap();
+        assert!(preview.len() <= MAX_PREVIEW_SIZE);
+        assert_eq!(preview.len() % "€".len(), 0);
+
+        let message = Message::ClipboardUpdate(update.clone());
+        let header = message.header();
+        assert!(Message::deserialize(&header, &message.serialize().unwrap()).is_ok());
+
+        // A peer that skips the cap is rejected on receipt
+        let mut oversized = update;
+        oversized.content.metadata.text_preview = Some("x".repeat(MAX_PREVIEW_SIZE + 1));
+        let message = Message::ClipboardUpdate(oversized);
+        let result = Message::deserialize(&header, &message.serialize().unwrap());
+        assert!(matches!(result, Err(ProtocolError::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn test_scratchpad_patch_roundtrip() {
+        let cases = [
+            ("", "hello"),
+            ("hello", ""),
+            ("hello world", "hello brave world"),
+            ("naïve café", "naïve cafés"),
+            ("aaa", "aa"),
+        ];
+        for (old, new) in cases {
+            let patch = ScratchpadPatch::diff(old, new);
+            assert_eq!(
+                patch.apply(old).as_deref(),
+                Some(new),
+                "{:?} -> {:?}",
+                old,
+                new
+            );
+        }
+
+        let patch = ScratchpadPatch::diff("hello world", "hello brave world");
+        assert_eq!((patch.start, patch.delete), (6, 0));
+        assert_eq!(patch.insert, "brave ");
+        // A range past the end of the text doesn't apply
+        assert_eq!(patch.apply("hi"), None);
+
+        let update = ScratchpadUpdate {
+            base_version: VersionVector::default(),
+            version: VersionVector::default(),
+            edited_at: 0,
+            editor: [0u8; 32],
+            patch: ScratchpadPatch {
+                insert: "x".repeat(MAX_SCRATCHPAD_SIZE + 1),
+                ..Default::default()
+            },
+        };
+        let message = Message::ScratchpadUpdate(update);
+        let result = Message::decode(&message.serialize().unwrap());
+        assert!(matches!(result, Err(ProtocolError::InvalidFormat(_))));
+    }
+
     #[test]
     fn test_device_info() {
         let device_id = [0u8; 32];
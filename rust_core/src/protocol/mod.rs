@@ -3,15 +3,22 @@
 //! This module defines the message types and serialization format
 //! used for communication between Toss devices.
 
+mod capabilities;
 mod content;
+pub mod encoding;
+mod file_transfer;
 mod frame;
 mod message;
 
-pub use content::{ClipboardContent, ContentMetadata, ContentType};
+pub use capabilities::Capabilities;
+pub use content::{hash_content_reader, ClipboardContent, ContentMetadata, ContentType};
+pub use file_transfer::{TransferCancel, TransferChunk, TransferComplete, TransferResume};
 pub use frame::Frame;
 pub use message::{
-    ClipboardAck, ClipboardRequest, ClipboardUpdate, DeviceInfo, ErrorMessage, KeyRotation,
-    KeyRotationReason, Message, MessageHeader, MessageType, Ping, Platform, Pong,
+    AutoAction, ClipboardAck, ClipboardRequest, ClipboardUpdate, DeviceInfo, DeviceRemoved,
+    ErrorMessage, IdentityMigration, KeyCheck, KeyCheckReply, KeyRotation, KeyRotationReason,
+    LockState, Message, MessageHeader, MessageType, Ping, Platform, Pong, Presence, PresenceState,
+    ScratchpadPatch, ScratchpadUpdate, SecurityLevel, VersionVector,
 };
 
 /// Maximum message size (50 MB)
@@ -20,13 +27,15 @@ pub const MAX_MESSAGE_SIZE: usize = 50 * 1024 * 1024;
 /// Maximum preview size for metadata (256 KB)
 pub const MAX_PREVIEW_SIZE: usize = 256 * 1024;
 
+/// Maximum size of the shared scratchpad document (64 KB)
+pub const MAX_SCRATCHPAD_SIZE: usize = 64 * 1024;
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::crypto::{derive_key, DerivedKeyPurpose, EphemeralKeyPair};
 
     #[test]
-    #[ignore = "bincode enum encoding needs investigation"]
     fn test_full_message_roundtrip() {
         // Create a clipboard update message
         let content = ClipboardContent {
@@ -43,7 +52,7 @@ mod tests {
         let header = message.header();
 
         // Test direct message roundtrip
-        let parsed: Message = bincode::deserialize(&payload).unwrap();
+        let parsed: Message = encoding::decode(&payload).unwrap();
         if let Message::ClipboardUpdate(parsed_update) = &parsed {
             assert_eq!(parsed_update.content.data, update.content.data);
         } else {
@@ -74,7 +83,7 @@ mod tests {
         assert_eq!(parsed_header.message_type, header.message_type);
 
         // Deserialize message from decrypted payload
-        let parsed_message: Message = bincode::deserialize(&decrypted_payload).unwrap();
+        let parsed_message: Message = encoding::decode(&decrypted_payload).unwrap();
 
         if let Message::ClipboardUpdate(parsed_update) = parsed_message {
             assert_eq!(parsed_update.content.data, update.content.data);
@@ -0,0 +1,265 @@
+//! Shared scratchpad document
+//!
+//! A small text buffer every paired device can edit. Edits go out as
+//! [`ScratchpadUpdate`]s carrying only the changed range, relative to the
+//! version they were made on. Each device's edits are counted in a version
+//! vector; an update whose version covers ours is applied, and for
+//! concurrent edits the one ordered last by `(edited_at, editor)` wins, so
+//! every device converges on the same text.
+//!
+//! Patching needs the text at the update's base version, so recent versions
+//! are kept. A device that can't apply an update, or is sent a whole document
+//! missing edits it has, answers with its whole document; the exchange ends
+//! with both sides on the same version.
+
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+
+use crate::protocol::{ScratchpadPatch, ScratchpadUpdate, VersionVector};
+
+/// Number of earlier versions kept for patching against
+pub const SCRATCHPAD_HISTORY: usize = 32;
+
+/// What receiving an update did
+#[derive(Debug, Clone)]
+pub struct Received {
+    /// The text changed
+    pub changed: bool,
+    /// Whole document to send back to the sender, which is missing edits
+    pub reply: Option<ScratchpadUpdate>,
+}
+
+struct State {
+    device_id: [u8; 32],
+    text: String,
+    version: VersionVector,
+    edited_at: u64,
+    editor: [u8; 32],
+    /// Text at recent versions, oldest first
+    history: VecDeque<(VersionVector, String)>,
+}
+
+impl State {
+    fn text_at(&self, version: &VersionVector) -> Option<String> {
+        if version.is_empty() {
+            return Some(String::new());
+        }
+        if *version == self.version {
+            return Some(self.text.clone());
+        }
+        self.history
+            .iter()
+            .find(|(v, _)| v == version)
+            .map(|(_, text)| text.clone())
+    }
+
+    fn remember(&mut self, version: VersionVector, text: String) {
+        if version.is_empty() || self.history.iter().any(|(v, _)| *v == version) {
+            return;
+        }
+        if self.history.len() == SCRATCHPAD_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back((version, text));
+    }
+
+    fn remember_current(&mut self) {
+        let (version, text) = (self.version.clone(), self.text.clone());
+        self.remember(version, text);
+    }
+
+    fn full_update(&self) -> ScratchpadUpdate {
+        ScratchpadUpdate {
+            base_version: VersionVector::default(),
+            version: self.version.clone(),
+            edited_at: self.edited_at,
+            editor: self.editor,
+            patch: ScratchpadPatch::diff("", &self.text),
+        }
+    }
+}
+
+/// The scratchpad as this device knows it
+pub struct Scratchpad {
+    state: Mutex<State>,
+}
+
+impl Scratchpad {
+    /// An empty scratchpad edited as `device_id`
+    pub fn new(device_id: [u8; 32]) -> Self {
+        Self {
+            state: Mutex::new(State {
+                device_id,
+                text: String::new(),
+                version: VersionVector::default(),
+                edited_at: 0,
+                editor: [0u8; 32],
+                history: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// Current text
+    pub fn text(&self) -> String {
+        self.state.lock().text.clone()
+    }
+
+    /// Current version
+    pub fn version(&self) -> VersionVector {
+        self.state.lock().version.clone()
+    }
+
+    /// Edit as another device from now on, e.g. after an identity rotation
+    pub fn set_device_id(&self, device_id: [u8; 32]) {
+        self.state.lock().device_id = device_id;
+    }
+
+    /// Replace the text with a local edit made at `now_ms`
+    ///
+    /// Returns the update to send, or `None` if the text is unchanged. The
+    /// edit is stamped after the current text's, so it orders after every
+    /// edit already seen here.
+    pub fn edit(&self, text: &str, now_ms: u64) -> Option<ScratchpadUpdate> {
+        let mut state = self.state.lock();
+        if state.text == text {
+            return None;
+        }
+
+        let base_version = state.version.clone();
+        let patch = ScratchpadPatch::diff(&state.text, text);
+        let device_id = state.device_id;
+        state.version.increment(&device_id);
+        state.edited_at = now_ms.max(state.edited_at + 1);
+        state.editor = device_id;
+        state.text = text.to_string();
+        state.remember_current();
+
+        Some(ScratchpadUpdate {
+            base_version,
+            version: state.version.clone(),
+            edited_at: state.edited_at,
+            editor: state.editor,
+            patch,
+        })
+    }
+
+    /// Apply an update from another device
+    pub fn receive(&self, update: &ScratchpadUpdate) -> Received {
+        let mut state = self.state.lock();
+        let mut changed = false;
+        let mut unknown_base = false;
+
+        if !state.version.covers(&update.version) {
+            let incoming_wins = update.version.covers(&state.version)
+                || (update.edited_at, update.editor) > (state.edited_at, state.editor);
+            let patched = incoming_wins
+                .then(|| state.text_at(&update.base_version))
+                .flatten()
+                .and_then(|base| update.patch.apply(&base));
+
+            match patched {
+                Some(text) => {
+                    state.remember(update.version.clone(), text.clone());
+                    changed = text != state.text;
+                    state.text = text;
+                    state.edited_at = update.edited_at;
+                    state.editor = update.editor;
+                    state.version.merge(&update.version);
+                    state.remember_current();
+                }
+                None if incoming_wins => unknown_base = true,
+                // Concurrent and older: keep our text, but count the edits
+                None => {
+                    state.version.merge(&update.version);
+                    state.remember_current();
+                }
+            }
+        }
+
+        let sender_behind = update.is_full() && !update.version.covers(&state.version);
+        Received {
+            changed,
+            reply: (unknown_base || sender_behind).then(|| state.full_update()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deliver an update, then any replies back and forth until both settle
+    fn deliver(from: &Scratchpad, to: &Scratchpad, update: &ScratchpadUpdate) {
+        let mut next = to.receive(update).reply;
+        let mut pair = (from, to);
+        while let Some(reply) = next {
+            next = pair.0.receive(&reply).reply;
+            pair = (pair.1, pair.0);
+        }
+    }
+
+    #[test]
+    fn test_concurrent_edits_converge() {
+        let a = Scratchpad::new([1u8; 32]);
+        let b = Scratchpad::new([2u8; 32]);
+
+        let first = a.edit("shopping: milk", 1_000).unwrap();
+        deliver(&a, &b, &first);
+        assert_eq!(b.text(), "shopping: milk");
+
+        // A later edit applies as a patch on the shared base
+        let append = b.edit("shopping: milk, eggs", 2_000).unwrap();
+        assert_eq!(append.patch.insert, ", eggs");
+        deliver(&b, &a, &append);
+        assert_eq!(a.text(), "shopping: milk, eggs");
+
+        // Concurrent edits cross in flight; the later one wins on both
+        let from_a = a.edit("shopping: oat milk, eggs", 3_000).unwrap();
+        let from_b = b.edit("shopping: milk, eggs, bread", 3_500).unwrap();
+        deliver(&a, &b, &from_a);
+        deliver(&b, &a, &from_b);
+        assert_eq!(a.text(), "shopping: milk, eggs, bread");
+        assert_eq!(b.text(), a.text());
+        assert_eq!(a.version(), b.version());
+
+        // Either can keep editing from the merged version
+        let next = a.edit("shopping: milk, eggs, bread, tea", 4_000).unwrap();
+        assert!(b.receive(&next).changed);
+        assert_eq!(b.text(), a.text());
+    }
+
+    #[test]
+    fn test_equal_times_break_ties_by_device() {
+        let a = Scratchpad::new([1u8; 32]);
+        let b = Scratchpad::new([2u8; 32]);
+
+        let from_a = a.edit("from a", 5_000).unwrap();
+        let from_b = b.edit("from b", 5_000).unwrap();
+        deliver(&a, &b, &from_a);
+        deliver(&b, &a, &from_b);
+        assert_eq!(a.text(), "from b");
+        assert_eq!(b.text(), "from b");
+    }
+
+    #[test]
+    fn test_unknown_base_resyncs_whole_document() {
+        let a = Scratchpad::new([1u8; 32]);
+        let b = Scratchpad::new([2u8; 32]);
+        deliver(&a, &b, &a.edit("draft", 1_000).unwrap());
+
+        // B restarts and loses the document; A's next patch can't apply
+        let b = Scratchpad::new([2u8; 32]);
+        let update = a.edit("draft two", 2_000).unwrap();
+        let received = b.receive(&update);
+        assert!(!received.changed);
+        let reply = received.reply.expect("B asks with its whole document");
+
+        // A answers with its own, since B's misses edits A has
+        let answer = a.receive(&reply).reply.expect("A sends its document");
+        assert!(answer.is_full());
+        assert!(b.receive(&answer).changed);
+        assert_eq!(b.text(), "draft two");
+        assert_eq!(b.version(), a.version());
+        assert!(b.receive(&answer).reply.is_none());
+    }
+}
@@ -0,0 +1,282 @@
+//! Coalesced clipboard history writes
+//!
+//! A burst of copies (a script filling the clipboard, a peer replaying its
+//! queue) would otherwise write one history row per item. Items stored within
+//! the write window are held back and written together in one batch, and an
+//! item identical to one already waiting replaces it instead of adding a row.
+//! Every read flushes first, so held-back items are never missing from
+//! queries.
+
+use rusqlite::Result as SqliteResult;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::history_storage::{HistoryPrune, HistoryStore, StoredHistoryFormat, StoredHistoryItem};
+
+/// Default time history writes are held back and coalesced
+pub const DEFAULT_HISTORY_WRITE_WINDOW: Duration = Duration::from_millis(500);
+
+/// Items waiting to be written
+#[derive(Default)]
+struct Pending {
+    items: Vec<StoredHistoryItem>,
+    /// When the oldest waiting item was stored
+    since: Option<Instant>,
+}
+
+/// History store that batches writes to another store
+///
+/// A zero window writes every item straight through.
+pub struct BufferedHistoryStore {
+    inner: Box<dyn HistoryStore>,
+    window: Mutex<Duration>,
+    pending: Mutex<Pending>,
+}
+
+impl BufferedHistoryStore {
+    pub fn new(inner: Box<dyn HistoryStore>, window: Duration) -> Self {
+        Self {
+            inner,
+            window: Mutex::new(window),
+            pending: Mutex::new(Pending::default()),
+        }
+    }
+
+    /// Change the write window, writing out anything already held back
+    pub fn set_window(&self, window: Duration) -> SqliteResult<()> {
+        *self.window.lock().unwrap() = window;
+        self.flush()
+    }
+
+    /// Write out held-back items once the window since the oldest has passed
+    pub fn write_due(&self) -> SqliteResult<()> {
+        let window = *self.window.lock().unwrap();
+        let mut pending = self.pending.lock().unwrap();
+        match pending.since {
+            Some(since) if since.elapsed() >= window => {
+                Self::write(self.inner.as_ref(), &mut pending)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn push(&self, item: &StoredHistoryItem, now: Instant) -> SqliteResult<()> {
+        let window = *self.window.lock().unwrap();
+        if window.is_zero() {
+            return self.inner.store_item(item);
+        }
+
+        let mut pending = self.pending.lock().unwrap();
+        let identical = pending.items.iter().position(|waiting| {
+            waiting.content_hash == item.content_hash
+                && waiting.content_type == item.content_type
+                && waiting.source_device == item.source_device
+        });
+        match identical {
+            Some(index) => pending.items[index] = item.clone(),
+            None => pending.items.push(item.clone()),
+        }
+        let since = *pending.since.get_or_insert(now);
+
+        if now.duration_since(since) >= window {
+            Self::write(self.inner.as_ref(), &mut pending)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn write(inner: &dyn HistoryStore, pending: &mut Pending) -> SqliteResult<()> {
+        pending.since = None;
+        let items = std::mem::take(&mut pending.items);
+        if items.is_empty() {
+            return Ok(());
+        }
+        inner.store_items(&items)
+    }
+}
+
+impl HistoryStore for BufferedHistoryStore {
+    fn store_item(&self, item: &StoredHistoryItem) -> SqliteResult<()> {
+        self.push(item, Instant::now())
+    }
+
+    fn store_items(&self, items: &[StoredHistoryItem]) -> SqliteResult<()> {
+        let now = Instant::now();
+        items.iter().try_for_each(|item| self.push(item, now))
+    }
+
+    fn flush(&self) -> SqliteResult<()> {
+        Self::write(self.inner.as_ref(), &mut self.pending.lock().unwrap())
+    }
+
+    fn store_formats(&self, item_id: &str, formats: &[StoredHistoryFormat]) -> SqliteResult<()> {
+        self.flush()?;
+        self.inner.store_formats(item_id, formats)
+    }
+
+    fn get_formats(&self, item_id: &str) -> SqliteResult<Vec<StoredHistoryFormat>> {
+        self.flush()?;
+        self.inner.get_formats(item_id)
+    }
+
+    fn get_item(&self, item_id: &str) -> SqliteResult<Option<StoredHistoryItem>> {
+        self.flush()?;
+        self.inner.get_item(item_id)
+    }
+
+    fn get_all_items(&self, limit: Option<u32>) -> SqliteResult<Vec<StoredHistoryItem>> {
+        self.flush()?;
+        self.inner.get_all_items(limit)
+    }
+
+    fn get_items_after(&self, after: u64, limit: u32) -> SqliteResult<Vec<StoredHistoryItem>> {
+        self.flush()?;
+        self.inner.get_items_after(after, limit)
+    }
+
+    fn remove_item(&self, item_id: &str) -> SqliteResult<()> {
+        self.flush()?;
+        self.inner.remove_item(item_id)
+    }
+
+    fn clear_history(&self) -> SqliteResult<()> {
+        *self.pending.lock().unwrap() = Pending::default();
+        self.inner.clear_history()
+    }
+
+    fn prune(&self, rule: HistoryPrune) -> SqliteResult<usize> {
+        self.flush()?;
+        self.inner.prune(rule)
+    }
+}
+
+impl Drop for BufferedHistoryStore {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            tracing::warn!("Failed to write buffered clipboard history: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryHistoryStore;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Memory store counting the writes that reach it
+    struct CountingStore {
+        inner: MemoryHistoryStore,
+        writes: Arc<AtomicUsize>,
+    }
+
+    impl HistoryStore for CountingStore {
+        fn store_item(&self, item: &StoredHistoryItem) -> SqliteResult<()> {
+            self.writes.fetch_add(1, Ordering::SeqCst);
+            self.inner.store_item(item)
+        }
+
+        fn store_items(&self, items: &[StoredHistoryItem]) -> SqliteResult<()> {
+            self.writes.fetch_add(1, Ordering::SeqCst);
+            self.inner.store_items(items)
+        }
+
+        fn store_formats(
+            &self,
+            item_id: &str,
+            formats: &[StoredHistoryFormat],
+        ) -> SqliteResult<()> {
+            self.inner.store_formats(item_id, formats)
+        }
+
+        fn get_formats(&self, item_id: &str) -> SqliteResult<Vec<StoredHistoryFormat>> {
+            self.inner.get_formats(item_id)
+        }
+
+        fn get_item(&self, item_id: &str) -> SqliteResult<Option<StoredHistoryItem>> {
+            self.inner.get_item(item_id)
+        }
+
+        fn get_all_items(&self, limit: Option<u32>) -> SqliteResult<Vec<StoredHistoryItem>> {
+            self.inner.get_all_items(limit)
+        }
+
+        fn get_items_after(&self, after: u64, limit: u32) -> SqliteResult<Vec<StoredHistoryItem>> {
+            self.inner.get_items_after(after, limit)
+        }
+
+        fn remove_item(&self, item_id: &str) -> SqliteResult<()> {
+            self.inner.remove_item(item_id)
+        }
+
+        fn clear_history(&self) -> SqliteResult<()> {
+            self.inner.clear_history()
+        }
+
+        fn prune(&self, rule: HistoryPrune) -> SqliteResult<usize> {
+            self.inner.prune(rule)
+        }
+    }
+
+    fn item(id: usize, hash: usize) -> StoredHistoryItem {
+        StoredHistoryItem {
+            id: format!("item-{}", id),
+            content_type: 0,
+            content_hash: format!("hash-{}", hash),
+            encrypted_content: vec![],
+            preview: String::new(),
+            source_device: None,
+            created_at: id as u64,
+            language: None,
+            superseded: false,
+        }
+    }
+
+    #[test]
+    fn test_rapid_burst_is_coalesced_into_bounded_writes() {
+        let writes = Arc::new(AtomicUsize::new(0));
+        let store = BufferedHistoryStore::new(
+            Box::new(CountingStore {
+                inner: MemoryHistoryStore::new(),
+                writes: writes.clone(),
+            }),
+            Duration::from_millis(100),
+        );
+
+        // 1000 copies over one second, cycling through 50 distinct contents
+        let start = Instant::now();
+        for i in 0..1000 {
+            store
+                .push(&item(i, i % 50), start + Duration::from_millis(i as u64))
+                .unwrap();
+        }
+        store.flush().unwrap();
+
+        // One batch per window, not one write per copy
+        let batches = writes.load(Ordering::SeqCst);
+        assert!(batches <= 11, "{} writes for a 1s burst", batches);
+
+        // Identical items within a batch are stored once, keeping the latest
+        let items = store.get_all_items(None).unwrap();
+        assert!(items.len() <= 50 * batches);
+        assert!(items.iter().any(|stored| stored.id == "item-999"));
+        assert!(items.iter().all(|stored| stored.id != "item-0"));
+    }
+
+    #[test]
+    fn test_zero_window_writes_through() {
+        let writes = Arc::new(AtomicUsize::new(0));
+        let store = BufferedHistoryStore::new(
+            Box::new(CountingStore {
+                inner: MemoryHistoryStore::new(),
+                writes: writes.clone(),
+            }),
+            Duration::ZERO,
+        );
+
+        store.store_item(&item(1, 1)).unwrap();
+        store.store_item(&item(2, 1)).unwrap();
+        assert_eq!(writes.load(Ordering::SeqCst), 2);
+    }
+}
@@ -9,13 +9,44 @@ use std::time::{SystemTime, UNIX_EPOCH};
 #[derive(Debug, Clone)]
 pub struct StoredDevice {
     pub id: String,
-    pub name: String,
+    /// Name the device advertises for itself, refreshed on every handshake
+    pub advertised_name: String,
     pub public_key: Vec<u8>,
     pub session_key: Option<Vec<u8>>,
     pub last_seen: Option<u64>,
     pub created_at: u64,
     pub is_active: bool,
     pub platform: Option<String>, // Platform: "macos", "windows", "linux", "ios", "android", "unknown"
+    /// Name the user gave the device locally; never overwritten by the device
+    pub local_nickname: Option<String>,
+}
+
+impl StoredDevice {
+    /// Name to show for the device: the local nickname if set, else the advertised name
+    pub fn display_name(&self) -> &str {
+        self.local_nickname
+            .as_deref()
+            .unwrap_or(&self.advertised_name)
+    }
+}
+
+/// What to do when storing a new device would exceed the device limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeviceLimitPolicy {
+    /// Refuse to store the new device
+    #[default]
+    Reject,
+    /// Evict the least recently seen device to make room
+    EvictOldest,
+}
+
+/// Result of storing a device under a limit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreDeviceOutcome {
+    /// The device was stored, after evicting the listed device IDs
+    Stored { evicted: Vec<String> },
+    /// The limit was reached under `DeviceLimitPolicy::Reject`; nothing changed
+    LimitReached,
 }
 
 /// Device storage operations
@@ -38,24 +69,68 @@ impl<'conn> DeviceStorage<'conn> {
             .and_then(|key| encrypt_for_storage(key).ok());
 
         let conn = self.conn.lock().unwrap();
-        conn.execute(
-            r#"
-            INSERT OR REPLACE INTO devices
-            (id, name, public_key, session_key, last_seen, created_at, is_active, platform)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
-            "#,
-            rusqlite::params![
-                device.id,
-                device.name,
-                device.public_key,
-                encrypted_session_key,
-                device.last_seen,
-                device.created_at,
-                device.is_active as i32,
-                device.platform,
-            ],
+        insert_device(&conn, device, encrypted_session_key)
+    }
+
+    /// Store a paired device, keeping at most `max_devices` stored (0 = no limit)
+    ///
+    /// Re-storing an existing device never counts against the limit. When the
+    /// limit is reached, removed devices are purged first, then devices that
+    /// were never seen, then the one with the oldest `last_seen`; active
+    /// devices are only evicted under `DeviceLimitPolicy::EvictOldest`.
+    pub fn store_device_with_limit(
+        &self,
+        device: &StoredDevice,
+        max_devices: usize,
+        policy: DeviceLimitPolicy,
+    ) -> SqliteResult<StoreDeviceOutcome> {
+        let encrypted_session_key = device
+            .session_key
+            .as_ref()
+            .and_then(|key| encrypt_for_storage(key).ok());
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        // A row with the same public key is replaced, so it isn't new either
+        let exists: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM devices WHERE id = ?1 OR public_key = ?2)",
+            rusqlite::params![device.id, device.public_key],
+            |row| row.get(0),
         )?;
-        Ok(())
+
+        let mut evicted = Vec::new();
+        if max_devices > 0 && !exists {
+            loop {
+                let count: i64 =
+                    tx.query_row("SELECT COUNT(*) FROM devices", [], |row| row.get(0))?;
+                if (count as usize) < max_devices {
+                    break;
+                }
+
+                let (id, is_active): (String, bool) = tx.query_row(
+                    r#"
+                    SELECT id, is_active FROM devices
+                    ORDER BY is_active ASC, last_seen IS NOT NULL ASC,
+                             last_seen ASC, created_at ASC
+                    LIMIT 1
+                    "#,
+                    [],
+                    |row| Ok((row.get(0)?, row.get::<_, i32>(1)? != 0)),
+                )?;
+                if is_active && policy == DeviceLimitPolicy::Reject {
+                    // Dropping the transaction rolls back any purged devices
+                    return Ok(StoreDeviceOutcome::LimitReached);
+                }
+
+                tx.execute("DELETE FROM devices WHERE id = ?1", [&id])?;
+                evicted.push(id);
+            }
+        }
+
+        insert_device(&tx, device, encrypted_session_key)?;
+        tx.commit()?;
+        Ok(StoreDeviceOutcome::Stored { evicted })
     }
 
     /// Get a device by ID
@@ -63,7 +138,7 @@ impl<'conn> DeviceStorage<'conn> {
     pub fn get_device(&self, device_id: &str) -> SqliteResult<Option<StoredDevice>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, name, public_key, session_key, last_seen, created_at, is_active, platform FROM devices WHERE id = ?1"
+            "SELECT id, name, public_key, session_key, last_seen, created_at, is_active, platform, local_nickname FROM devices WHERE id = ?1"
         )?;
 
         let device = stmt.query_row([device_id], |row| {
@@ -74,13 +149,14 @@ impl<'conn> DeviceStorage<'conn> {
 
             Ok(StoredDevice {
                 id: row.get(0)?,
-                name: row.get(1)?,
+                advertised_name: row.get(1)?,
                 public_key: row.get(2)?,
                 session_key,
                 last_seen: row.get(4)?,
                 created_at: row.get(5)?,
                 is_active: row.get::<_, i32>(6)? != 0,
-                platform: row.get(7).ok(), // Platform is optional, may not exist in old databases
+                platform: row.get(7)?,
+                local_nickname: row.get(8)?,
             })
         });
 
@@ -96,7 +172,7 @@ impl<'conn> DeviceStorage<'conn> {
     pub fn get_all_devices(&self) -> SqliteResult<Vec<StoredDevice>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, name, public_key, session_key, last_seen, created_at, is_active, platform FROM devices WHERE is_active = 1 ORDER BY created_at DESC"
+            "SELECT id, name, public_key, session_key, last_seen, created_at, is_active, platform, local_nickname FROM devices WHERE is_active = 1 ORDER BY created_at DESC"
         )?;
 
         let devices = stmt
@@ -108,13 +184,14 @@ impl<'conn> DeviceStorage<'conn> {
 
                 Ok(StoredDevice {
                     id: row.get(0)?,
-                    name: row.get(1)?,
+                    advertised_name: row.get(1)?,
                     public_key: row.get(2)?,
                     session_key,
                     last_seen: row.get(4)?,
                     created_at: row.get(5)?,
                     is_active: row.get::<_, i32>(6)? != 0,
-                    platform: row.get(7).ok(), // Platform is optional, may not exist in old databases
+                    platform: row.get(7)?,
+                    local_nickname: row.get(8)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -154,17 +231,242 @@ impl<'conn> DeviceStorage<'conn> {
         Ok(())
     }
 
-    /// Update device name
-    pub fn update_device_name(&self, device_id: &str, new_name: &str) -> SqliteResult<()> {
+    /// Move a device to a new identity key after it migrated
+    ///
+    /// Keeps the session key, names and history, which move to the new id.
+    /// Returns `false` if no device is stored under `old_id`.
+    pub fn migrate_identity(
+        &self,
+        old_id: &str,
+        new_id: &str,
+        new_public_key: &[u8],
+    ) -> SqliteResult<bool> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        // A new key has a new fingerprint, which the user hasn't confirmed
+        let updated = tx.execute(
+            "UPDATE devices SET id = ?1, public_key = ?2, fingerprint_verified_at = NULL WHERE id = ?3",
+            rusqlite::params![new_id, new_public_key, old_id],
+        )?;
+        if updated == 0 {
+            return Ok(false);
+        }
+        tx.execute(
+            "UPDATE clipboard_history SET source_device = ?1 WHERE source_device = ?2",
+            [new_id, old_id],
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Merge devices stored more than once under the same public key
+    ///
+    /// Returns the number of duplicate rows removed. See `merge_duplicate_devices`.
+    pub fn dedupe_devices(&self) -> SqliteResult<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let removed = merge_duplicate_devices(&tx)?;
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// Record the name a device advertised for itself, e.g. during a handshake
+    ///
+    /// Leaves any local nickname in place.
+    pub fn update_advertised_name(&self, device_id: &str, name: &str) -> SqliteResult<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
             "UPDATE devices SET name = ?1 WHERE id = ?2",
-            rusqlite::params![new_name, device_id],
+            rusqlite::params![name, device_id],
+        )?;
+        Ok(())
+    }
+
+    /// Accept pairing requests from a device without asking, or stop doing so
+    ///
+    /// Returns `false` if the device isn't stored.
+    pub fn set_auto_accept_pairing(&self, device_id: &str, enabled: bool) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE devices SET auto_accept_pairing = ?1 WHERE id = ?2",
+            rusqlite::params![enabled as i32, device_id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Whether pairing requests presenting `public_key` are accepted without asking
+    ///
+    /// Only the key is trusted: names are chosen by the device itself, so
+    /// anyone could claim a trusted device's name.
+    pub fn auto_accepts_pairing_from(&self, public_key: &[u8]) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM devices WHERE public_key = ?1 AND is_active = 1 AND auto_accept_pairing = 1)",
+            [public_key],
+            |row| row.get(0),
+        )
+    }
+
+    /// Record that the user confirmed a device's key fingerprint
+    ///
+    /// Returns `false` if the device isn't stored. The mark lasts until the
+    /// device's identity key changes.
+    pub fn mark_fingerprint_verified(&self, device_id: &str) -> SqliteResult<bool> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE devices SET fingerprint_verified_at = ?1 WHERE id = ?2",
+            rusqlite::params![now, device_id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// When the user confirmed a device's key fingerprint, if ever
+    pub fn fingerprint_verified_at(&self, device_id: &str) -> SqliteResult<Option<u64>> {
+        let conn = self.conn.lock().unwrap();
+        let verified_at = conn.query_row(
+            "SELECT fingerprint_verified_at FROM devices WHERE id = ?1",
+            [device_id],
+            |row| row.get(0),
+        );
+        match verified_at {
+            Ok(verified_at) => Ok(verified_at),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Set or clear (`None`) the local nickname of a device
+    pub fn set_local_nickname(&self, device_id: &str, nickname: Option<&str>) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE devices SET local_nickname = ?1 WHERE id = ?2",
+            rusqlite::params![nickname, device_id],
         )?;
         Ok(())
     }
 }
 
+/// Merge device rows that share a public key into one
+///
+/// The most recently seen row keeps its id and name. It takes the latest
+/// `last_seen`, the session key and local nickname of the most recently seen
+/// rows that have one, and the earliest `created_at`, and stays active if any
+/// duplicate was.
+/// History from the removed rows is moved over to it.
+pub(super) fn merge_duplicate_devices(conn: &rusqlite::Connection) -> SqliteResult<usize> {
+    let keys = conn
+        .prepare("SELECT public_key FROM devices GROUP BY public_key HAVING COUNT(*) > 1")?
+        .query_map([], |row| row.get::<_, Vec<u8>>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut removed = 0;
+    for key in keys {
+        // Most recently seen first
+        let rows = conn
+            .prepare(
+                r#"
+                SELECT id, session_key, last_seen, created_at, is_active, local_nickname
+                FROM devices WHERE public_key = ?1
+                ORDER BY last_seen IS NULL ASC, last_seen DESC, created_at DESC
+                "#,
+            )?
+            .query_map([&key], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<Vec<u8>>>(1)?,
+                    row.get::<_, Option<u64>>(2)?,
+                    row.get::<_, u64>(3)?,
+                    row.get::<_, i32>(4)? != 0,
+                    row.get::<_, Option<String>>(5)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let keep_id = &rows[0].0;
+        let session_key = rows.iter().find_map(|row| row.1.clone());
+        let last_seen = rows.iter().filter_map(|row| row.2).max();
+        let created_at = rows.iter().map(|row| row.3).min();
+        let is_active = rows.iter().any(|row| row.4);
+        let local_nickname = rows.iter().find_map(|row| row.5.clone());
+
+        for (id, ..) in &rows[1..] {
+            conn.execute(
+                "UPDATE clipboard_history SET source_device = ?1 WHERE source_device = ?2",
+                rusqlite::params![keep_id, id],
+            )?;
+            conn.execute("DELETE FROM devices WHERE id = ?1", [id])?;
+            removed += 1;
+        }
+
+        conn.execute(
+            r#"
+            UPDATE devices SET session_key = ?1, last_seen = ?2, created_at = ?3, is_active = ?4,
+                local_nickname = ?5
+            WHERE id = ?6
+            "#,
+            rusqlite::params![
+                session_key,
+                last_seen,
+                created_at,
+                is_active as i32,
+                local_nickname,
+                keep_id
+            ],
+        )?;
+    }
+
+    Ok(removed)
+}
+
+/// Insert or replace a device row with an already encrypted session key
+///
+/// A device without a local nickname keeps the one of the row it replaces,
+/// so re-pairing doesn't drop a name the user chose. Auto-accepting pairing
+/// is kept too, and so is a confirmed fingerprint as long as the key is the
+/// same.
+fn insert_device(
+    conn: &rusqlite::Connection,
+    device: &StoredDevice,
+    encrypted_session_key: Option<Vec<u8>>,
+) -> SqliteResult<()> {
+    conn.execute(
+        r#"
+        INSERT OR REPLACE INTO devices
+        (id, name, public_key, session_key, last_seen, created_at, is_active, platform, local_nickname,
+         auto_accept_pairing, fingerprint_verified_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, COALESCE(?9, (
+            SELECT local_nickname FROM devices
+            WHERE (id = ?1 OR public_key = ?3) AND local_nickname IS NOT NULL
+            LIMIT 1
+        )), COALESCE((
+            SELECT MAX(auto_accept_pairing) FROM devices
+            WHERE id = ?1 OR public_key = ?3
+        ), 0), (
+            SELECT fingerprint_verified_at FROM devices
+            WHERE public_key = ?3 AND fingerprint_verified_at IS NOT NULL
+            LIMIT 1
+        ))
+        "#,
+        rusqlite::params![
+            device.id,
+            device.advertised_name,
+            device.public_key,
+            encrypted_session_key,
+            device.last_seen,
+            device.created_at,
+            device.is_active as i32,
+            device.platform,
+            device.local_nickname,
+        ],
+    )?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,13 +482,14 @@ mod tests {
 
         let device = StoredDevice {
             id: "test-device-1".to_string(),
-            name: "Test Device".to_string(),
+            advertised_name: "Test Device".to_string(),
             public_key: vec![1, 2, 3, 4],
             session_key: Some(vec![5, 6, 7, 8]),
             last_seen: None,
             created_at: 1234567890,
             is_active: true,
             platform: None,
+            local_nickname: None,
         };
 
         device_storage.store_device(&device).unwrap();
@@ -195,10 +498,44 @@ mod tests {
         assert!(retrieved.is_some());
         let d = retrieved.unwrap();
         assert_eq!(d.id, device.id);
-        assert_eq!(d.name, device.name);
+        assert_eq!(d.advertised_name, device.advertised_name);
         assert_eq!(d.public_key, device.public_key);
     }
 
+    #[test]
+    fn test_handshake_name_keeps_local_nickname() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let storage = Storage::new(&db_path).unwrap();
+        let device_storage = storage.devices();
+
+        let mut device = device("laptop", Some(10), true);
+        device.advertised_name = "MacBook-Pro".to_string();
+        device_storage.store_device(&device).unwrap();
+        device_storage
+            .set_local_nickname("laptop", Some("Work laptop"))
+            .unwrap();
+
+        // A handshake reports a new name, then re-pairing stores the device again
+        device_storage
+            .update_advertised_name("laptop", "MacBook-Pro-2")
+            .unwrap();
+        let stored = device_storage.get_device("laptop").unwrap().unwrap();
+        assert_eq!(stored.advertised_name, "MacBook-Pro-2");
+        assert_eq!(stored.display_name(), "Work laptop");
+
+        device.advertised_name = "MacBook-Pro-3".to_string();
+        device_storage.store_device(&device).unwrap();
+        let stored = device_storage.get_device("laptop").unwrap().unwrap();
+        assert_eq!(stored.advertised_name, "MacBook-Pro-3");
+        assert_eq!(stored.local_nickname.as_deref(), Some("Work laptop"));
+
+        // Clearing the nickname falls back to the advertised name
+        device_storage.set_local_nickname("laptop", None).unwrap();
+        let stored = device_storage.get_device("laptop").unwrap().unwrap();
+        assert_eq!(stored.display_name(), "MacBook-Pro-3");
+    }
+
     #[test]
     fn test_get_all_devices() {
         let temp_dir = TempDir::new().unwrap();
@@ -208,24 +545,26 @@ mod tests {
 
         let device1 = StoredDevice {
             id: "device-1".to_string(),
-            name: "Device 1".to_string(),
+            advertised_name: "Device 1".to_string(),
             public_key: vec![1],
             session_key: None,
             last_seen: None,
             created_at: 1000,
             is_active: true,
             platform: None,
+            local_nickname: None,
         };
 
         let device2 = StoredDevice {
             id: "device-2".to_string(),
-            name: "Device 2".to_string(),
+            advertised_name: "Device 2".to_string(),
             public_key: vec![2],
             session_key: None,
             last_seen: None,
             created_at: 2000,
             is_active: true,
             platform: None,
+            local_nickname: None,
         };
 
         device_storage.store_device(&device1).unwrap();
@@ -244,13 +583,14 @@ mod tests {
 
         let device = StoredDevice {
             id: "device-to-remove".to_string(),
-            name: "To Remove".to_string(),
+            advertised_name: "To Remove".to_string(),
             public_key: vec![1],
             session_key: None,
             last_seen: None,
             created_at: 1000,
             is_active: true,
             platform: None,
+            local_nickname: None,
         };
 
         device_storage.store_device(&device).unwrap();
@@ -259,4 +599,235 @@ mod tests {
         let all = device_storage.get_all_devices().unwrap();
         assert_eq!(all.len(), 0); // Should be filtered out
     }
+
+    fn device(id: &str, last_seen: Option<u64>, is_active: bool) -> StoredDevice {
+        StoredDevice {
+            id: id.to_string(),
+            advertised_name: id.to_string(),
+            public_key: id.as_bytes().to_vec(),
+            session_key: None,
+            last_seen,
+            created_at: 1000,
+            is_active,
+            platform: None,
+            local_nickname: None,
+        }
+    }
+
+    #[test]
+    fn test_auto_accept_pairing_is_keyed_on_public_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        {
+            let storage = Storage::new(&db_path).unwrap();
+            let devices = storage.devices();
+            devices.store_device(&device("phone", None, true)).unwrap();
+            assert!(!devices.auto_accepts_pairing_from(b"phone").unwrap());
+            assert!(devices.set_auto_accept_pairing("phone", true).unwrap());
+            assert!(!devices.set_auto_accept_pairing("missing", true).unwrap());
+        }
+
+        let storage = Storage::new(&db_path).unwrap();
+        let devices = storage.devices();
+        assert!(devices.auto_accepts_pairing_from(b"phone").unwrap());
+
+        // Claiming the trusted device's name with another key earns nothing
+        let impostor = StoredDevice {
+            advertised_name: "phone".to_string(),
+            ..device("impostor", None, true)
+        };
+        devices.store_device(&impostor).unwrap();
+        assert!(!devices.auto_accepts_pairing_from(b"impostor").unwrap());
+
+        // Storing the device again keeps the flag
+        devices
+            .store_device(&device("phone", Some(5), true))
+            .unwrap();
+        assert!(devices.auto_accepts_pairing_from(b"phone").unwrap());
+
+        devices.set_auto_accept_pairing("phone", false).unwrap();
+        assert!(!devices.auto_accepts_pairing_from(b"phone").unwrap());
+    }
+
+    #[test]
+    fn test_dedupe_devices_sharing_public_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let storage = Storage::new(&db_path).unwrap();
+        let device_storage = storage.devices();
+
+        // Duplicates from before the unique index existed, at the schema
+        // version before the migration that adds it
+        storage
+            .conn
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "DROP INDEX idx_devices_public_key; UPDATE schema_version SET version = 7;",
+            )
+            .unwrap();
+
+        let mut older = device("short-id", Some(100), false);
+        older.public_key = vec![9; 32];
+        older.created_at = 500;
+        let mut newer = device("canonical-id", Some(200), true);
+        newer.public_key = vec![9; 32];
+        device_storage.store_device(&older).unwrap();
+        device_storage.store_device(&newer).unwrap();
+        device_storage
+            .store_device(&device("other", Some(50), true))
+            .unwrap();
+        // The stored session key is an opaque blob; only its row matters here
+        let session_key_of = |id: &str| -> Option<Vec<u8>> {
+            storage
+                .conn
+                .lock()
+                .unwrap()
+                .query_row(
+                    "SELECT session_key FROM devices WHERE id = ?1",
+                    [id],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        storage
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE devices SET session_key = ?1 WHERE id = 'short-id'",
+                [vec![1u8; 44]],
+            )
+            .unwrap();
+
+        assert_eq!(device_storage.dedupe_devices().unwrap(), 1);
+        assert_eq!(device_storage.dedupe_devices().unwrap(), 0);
+
+        assert!(device_storage.get_device("short-id").unwrap().is_none());
+        let merged = device_storage.get_device("canonical-id").unwrap().unwrap();
+        assert_eq!(merged.last_seen, Some(200));
+        assert_eq!(session_key_of("canonical-id"), Some(vec![1; 44]));
+        assert_eq!(merged.created_at, 500);
+        assert!(merged.is_active);
+        assert_eq!(device_storage.get_all_devices().unwrap().len(), 2);
+
+        // Reopening migrates again and restores the index, so a new id for
+        // the same key replaces the row
+        drop(storage);
+        let storage = Storage::new(&db_path).unwrap();
+        let mut renamed = device("new-id", Some(300), true);
+        renamed.public_key = vec![9; 32];
+        storage.devices().store_device(&renamed).unwrap();
+        assert!(storage
+            .devices()
+            .get_device("canonical-id")
+            .unwrap()
+            .is_none());
+        assert_eq!(storage.devices().get_all_devices().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_device_limit_reject() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let storage = Storage::new(&db_path).unwrap();
+        let device_storage = storage.devices();
+
+        for d in [device("a", Some(10), true), device("b", Some(20), true)] {
+            let outcome = device_storage
+                .store_device_with_limit(&d, 2, DeviceLimitPolicy::Reject)
+                .unwrap();
+            assert_eq!(outcome, StoreDeviceOutcome::Stored { evicted: vec![] });
+        }
+
+        let outcome = device_storage
+            .store_device_with_limit(&device("c", None, true), 2, DeviceLimitPolicy::Reject)
+            .unwrap();
+        assert_eq!(outcome, StoreDeviceOutcome::LimitReached);
+        assert!(device_storage.get_device("c").unwrap().is_none());
+        assert_eq!(device_storage.get_all_devices().unwrap().len(), 2);
+
+        // Re-storing an existing device is not a new pairing
+        let outcome = device_storage
+            .store_device_with_limit(&device("a", Some(30), true), 2, DeviceLimitPolicy::Reject)
+            .unwrap();
+        assert_eq!(outcome, StoreDeviceOutcome::Stored { evicted: vec![] });
+
+        // Removed devices are purged to make room even when rejecting
+        device_storage.remove_device("b").unwrap();
+        let outcome = device_storage
+            .store_device_with_limit(&device("c", None, true), 2, DeviceLimitPolicy::Reject)
+            .unwrap();
+        assert_eq!(
+            outcome,
+            StoreDeviceOutcome::Stored {
+                evicted: vec!["b".to_string()]
+            }
+        );
+        assert!(device_storage.get_device("b").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_device_limit_evict_oldest() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let storage = Storage::new(&db_path).unwrap();
+        let device_storage = storage.devices();
+
+        device_storage
+            .store_device(&device("recent", Some(300), true))
+            .unwrap();
+        device_storage
+            .store_device(&device("old", Some(100), true))
+            .unwrap();
+        device_storage
+            .store_device(&device("never-seen", None, true))
+            .unwrap();
+        device_storage
+            .store_device(&device("removed", Some(500), false))
+            .unwrap();
+
+        let policy = DeviceLimitPolicy::EvictOldest;
+        let mut new_1 = device("new-1", None, true);
+        new_1.created_at = 2000;
+        let outcome = device_storage
+            .store_device_with_limit(&new_1, 4, policy)
+            .unwrap();
+        assert_eq!(
+            outcome,
+            StoreDeviceOutcome::Stored {
+                evicted: vec!["removed".to_string()]
+            }
+        );
+
+        let outcome = device_storage
+            .store_device_with_limit(&device("new-2", Some(400), true), 4, policy)
+            .unwrap();
+        assert_eq!(
+            outcome,
+            StoreDeviceOutcome::Stored {
+                evicted: vec!["never-seen".to_string()]
+            }
+        );
+
+        // Lowering the limit evicts down to it, oldest first
+        let outcome = device_storage
+            .store_device_with_limit(&device("new-3", Some(500), true), 3, policy)
+            .unwrap();
+        assert_eq!(
+            outcome,
+            StoreDeviceOutcome::Stored {
+                evicted: vec!["new-1".to_string(), "old".to_string()]
+            }
+        );
+
+        let mut remaining: Vec<_> = device_storage
+            .get_all_devices()
+            .unwrap()
+            .into_iter()
+            .map(|d| d.id)
+            .collect();
+        remaining.sort();
+        assert_eq!(remaining, vec!["new-2", "new-3", "recent"]);
+    }
 }
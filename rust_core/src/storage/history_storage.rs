@@ -1,7 +1,13 @@
 //! Clipboard history storage operations
+//!
+//! History goes through the [`HistoryStore`] trait so the backend can be
+//! chosen when storage is opened: the SQLite database by default, or memory
+//! (see [`MemoryHistoryStore`](super::MemoryHistoryStore)).
 
-use rusqlite::Result as SqliteResult;
-use std::sync::Mutex;
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use rusqlite::{OptionalExtension, Result as SqliteResult};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 /// Stored clipboard history item
 #[derive(Debug, Clone)]
@@ -13,45 +19,216 @@ pub struct StoredHistoryItem {
     pub preview: String,
     pub source_device: Option<String>,
     pub created_at: u64,
+    pub language: Option<String>, // Language hint for code snippets
+    /// Received but lost a conflict, so never written to the clipboard
+    pub superseded: bool,
 }
 
-/// Clipboard history storage operations
-pub struct HistoryStorage<'conn> {
-    conn: &'conn Mutex<rusqlite::Connection>,
+/// One encrypted representation of a multi-format history entry
+#[derive(Debug, Clone)]
+pub struct StoredHistoryFormat {
+    pub item_id: String,
+    pub content_type: u8, // ContentType as u8
+    pub encrypted_content: Vec<u8>,
+}
+
+/// History items copied on one device during one local calendar day
+#[derive(Debug, Clone)]
+pub struct HistoryGroup {
+    pub day: NaiveDate,
+    pub source_device: Option<String>,
+    pub items: Vec<StoredHistoryItem>,
+}
+
+/// Which items [`HistoryStore::prune`] removes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryPrune {
+    /// Items created before the timestamp
+    OlderThan(u64),
+    /// All but the newest N items
+    KeepNewest(u32),
+}
+
+/// Backend holding clipboard history
+///
+/// Storing an item under an existing id replaces it. Removing, clearing or
+/// pruning an item also drops its stored representations.
+pub trait HistoryStore: Send + Sync {
+    /// Store a clipboard history item
+    fn store_item(&self, item: &StoredHistoryItem) -> SqliteResult<()>;
+
+    /// Store several history items at once
+    ///
+    /// Backends that can should write them in a single transaction.
+    fn store_items(&self, items: &[StoredHistoryItem]) -> SqliteResult<()> {
+        items.iter().try_for_each(|item| self.store_item(item))
+    }
+
+    /// Write out any items held back by a buffering backend
+    fn flush(&self) -> SqliteResult<()> {
+        Ok(())
+    }
+
+    /// Store every representation of a multi-format history entry
+    ///
+    /// Replaces any representations previously stored for the same item.
+    fn store_formats(&self, item_id: &str, formats: &[StoredHistoryFormat]) -> SqliteResult<()>;
+
+    /// Get all representations stored for a history item
+    /// Returns an empty list for single-format items
+    fn get_formats(&self, item_id: &str) -> SqliteResult<Vec<StoredHistoryFormat>>;
+
+    /// Get a history item by ID
+    fn get_item(&self, item_id: &str) -> SqliteResult<Option<StoredHistoryItem>>;
+
+    /// Get all history items, ordered by creation time (newest first)
+    fn get_all_items(&self, limit: Option<u32>) -> SqliteResult<Vec<StoredHistoryItem>>;
+
+    /// Get up to `limit` items created after `after`, oldest first
+    ///
+    /// A page never splits a timestamp: if the last item shares its creation
+    /// time with items past the limit, they are included too, so the last
+    /// item's `created_at` is a cursor for the next page that skips nothing.
+    fn get_items_after(&self, after: u64, limit: u32) -> SqliteResult<Vec<StoredHistoryItem>>;
+
+    /// Remove a history item
+    fn remove_item(&self, item_id: &str) -> SqliteResult<()>;
+
+    /// Clear all history
+    fn clear_history(&self) -> SqliteResult<()>;
+
+    /// Remove old items, returning how many were removed
+    ///
+    /// `KeepNewest` cuts at the creation time of the first item over the
+    /// limit, so items sharing that timestamp go too.
+    fn prune(&self, rule: HistoryPrune) -> SqliteResult<usize>;
+
+    /// Get the newest history items grouped by local calendar day and source device
+    ///
+    /// Days are taken in the timezone `offset`, so an item copied just after
+    /// local midnight belongs to the new day. Groups are ordered by their newest
+    /// item, and items within a group stay newest first.
+    fn get_grouped_items(
+        &self,
+        limit: Option<u32>,
+        offset: FixedOffset,
+    ) -> SqliteResult<Vec<HistoryGroup>> {
+        Ok(group_by_day_and_source(self.get_all_items(limit)?, offset))
+    }
+}
+
+/// Clipboard history kept in the SQLite database
+pub struct SqliteHistoryStore {
+    conn: Arc<Mutex<rusqlite::Connection>>,
 }
 
-impl<'conn> HistoryStorage<'conn> {
-    pub fn new(conn: &'conn Mutex<rusqlite::Connection>) -> Self {
+impl SqliteHistoryStore {
+    pub fn new(conn: Arc<Mutex<rusqlite::Connection>>) -> Self {
         Self { conn }
     }
 
-    /// Store a clipboard history item
-    pub fn store_item(&self, item: &StoredHistoryItem) -> SqliteResult<()> {
-        let conn = self.conn.lock().unwrap();
+    /// Delete items created before the timestamp
+    fn prune_old_items(conn: &rusqlite::Connection, before_timestamp: u64) -> SqliteResult<usize> {
         conn.execute(
-            r#"
-            INSERT OR REPLACE INTO clipboard_history 
-            (id, content_type, content_hash, encrypted_content, preview, source_device, created_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
-            "#,
-            rusqlite::params![
-                item.id,
-                item.content_type,
-                item.content_hash,
-                item.encrypted_content,
-                item.preview,
-                item.source_device,
-                item.created_at,
-            ],
+            "DELETE FROM clipboard_history_formats WHERE item_id IN (SELECT id FROM clipboard_history WHERE created_at < ?1)",
+            [before_timestamp],
         )?;
-        Ok(())
+        conn.execute(
+            "DELETE FROM clipboard_history WHERE created_at < ?1",
+            [before_timestamp],
+        )
     }
 
-    /// Get a history item by ID
-    pub fn get_item(&self, item_id: &str) -> SqliteResult<Option<StoredHistoryItem>> {
+    /// Delete all but the most recent N items
+    fn prune_to_limit(conn: &rusqlite::Connection, max_items: u32) -> SqliteResult<usize> {
+        // Get count of items
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM clipboard_history", [], |row| {
+            row.get(0)
+        })?;
+
+        if count <= max_items as i64 {
+            return Ok(0);
+        }
+
+        // Get the timestamp of the Nth item
+        let mut stmt = conn.prepare(
+            "SELECT created_at FROM clipboard_history ORDER BY created_at DESC LIMIT 1 OFFSET ?1",
+        )?;
+        let cutoff_timestamp: Option<u64> = stmt.query_row([max_items], |row| row.get(0)).ok();
+        drop(stmt);
+
+        if let Some(timestamp) = cutoff_timestamp {
+            // Use <= to include the cutoff item in deletion (we want to keep max_items, not max_items+1)
+            conn.execute(
+                "DELETE FROM clipboard_history_formats WHERE item_id IN (SELECT id FROM clipboard_history WHERE created_at <= ?1)",
+                [timestamp],
+            )?;
+            conn.execute(
+                "DELETE FROM clipboard_history WHERE created_at <= ?1",
+                [timestamp],
+            )
+        } else {
+            Ok(0)
+        }
+    }
+}
+
+impl HistoryStore for SqliteHistoryStore {
+    fn store_item(&self, item: &StoredHistoryItem) -> SqliteResult<()> {
+        insert_item(&self.conn.lock().unwrap(), item)
+    }
+
+    fn store_items(&self, items: &[StoredHistoryItem]) -> SqliteResult<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for item in items {
+            insert_item(&tx, item)?;
+        }
+        tx.commit()
+    }
+
+    fn store_formats(&self, item_id: &str, formats: &[StoredHistoryFormat]) -> SqliteResult<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM clipboard_history_formats WHERE item_id = ?1",
+            [item_id],
+        )?;
+        for format in formats {
+            tx.execute(
+                r#"
+                INSERT INTO clipboard_history_formats (item_id, content_type, encrypted_content)
+                VALUES (?1, ?2, ?3)
+                "#,
+                rusqlite::params![item_id, format.content_type, format.encrypted_content],
+            )?;
+        }
+        tx.commit()
+    }
+
+    fn get_formats(&self, item_id: &str) -> SqliteResult<Vec<StoredHistoryFormat>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT item_id, content_type, encrypted_content FROM clipboard_history_formats WHERE item_id = ?1 ORDER BY rowid"
+        )?;
+
+        let formats = stmt
+            .query_map([item_id], |row| {
+                Ok(StoredHistoryFormat {
+                    item_id: row.get(0)?,
+                    content_type: row.get(1)?,
+                    encrypted_content: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(formats)
+    }
+
+    fn get_item(&self, item_id: &str) -> SqliteResult<Option<StoredHistoryItem>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, content_type, content_hash, encrypted_content, preview, source_device, created_at FROM clipboard_history WHERE id = ?1"
+            "SELECT id, content_type, content_hash, encrypted_content, preview, source_device, created_at, language, superseded FROM clipboard_history WHERE id = ?1"
         )?;
 
         let item = stmt.query_row([item_id], |row| {
@@ -63,6 +240,8 @@ impl<'conn> HistoryStorage<'conn> {
                 preview: row.get(4)?,
                 source_device: row.get(5)?,
                 created_at: row.get(6)?,
+                language: row.get(7)?,
+                superseded: row.get(8)?,
             })
         });
 
@@ -73,151 +252,430 @@ impl<'conn> HistoryStorage<'conn> {
         }
     }
 
-    /// Get all history items, ordered by creation time (newest first)
-    pub fn get_all_items(&self, limit: Option<u32>) -> SqliteResult<Vec<StoredHistoryItem>> {
+    fn get_all_items(&self, limit: Option<u32>) -> SqliteResult<Vec<StoredHistoryItem>> {
         let query = if let Some(limit) = limit {
             format!(
-                "SELECT id, content_type, content_hash, encrypted_content, preview, source_device, created_at FROM clipboard_history ORDER BY created_at DESC LIMIT {}",
+                "SELECT id, content_type, content_hash, encrypted_content, preview, source_device, created_at, language, superseded FROM clipboard_history ORDER BY created_at DESC LIMIT {}",
                 limit
             )
         } else {
-            "SELECT id, content_type, content_hash, encrypted_content, preview, source_device, created_at FROM clipboard_history ORDER BY created_at DESC".to_string()
+            "SELECT id, content_type, content_hash, encrypted_content, preview, source_device, created_at, language, superseded FROM clipboard_history ORDER BY created_at DESC".to_string()
         };
 
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(&query)?;
 
         let items = stmt
-            .query_map([], |row| {
-                Ok(StoredHistoryItem {
-                    id: row.get(0)?,
-                    content_type: row.get(1)?,
-                    content_hash: row.get(2)?,
-                    encrypted_content: row.get(3)?,
-                    preview: row.get(4)?,
-                    source_device: row.get(5)?,
-                    created_at: row.get(6)?,
-                })
-            })?
+            .query_map([], item_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(items)
     }
 
-    /// Remove a history item
-    pub fn remove_item(&self, item_id: &str) -> SqliteResult<()> {
+    fn get_items_after(&self, after: u64, limit: u32) -> SqliteResult<Vec<StoredHistoryItem>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.conn.lock().unwrap();
+
+        // Creation time of the last item on the page; the page runs through it
+        let last: Option<u64> = conn
+            .query_row(
+                "SELECT created_at FROM clipboard_history WHERE created_at > ?1 ORDER BY created_at LIMIT 1 OFFSET ?2",
+                rusqlite::params![after, limit - 1],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, content_type, content_hash, encrypted_content, preview, source_device, created_at, language, superseded FROM clipboard_history WHERE created_at > ?1 AND created_at <= ?2 ORDER BY created_at, id",
+        )?;
+        let items = stmt
+            .query_map(
+                rusqlite::params![after, last.unwrap_or(i64::MAX as u64)],
+                item_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(items)
+    }
+
+    fn remove_item(&self, item_id: &str) -> SqliteResult<()> {
         let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM clipboard_history_formats WHERE item_id = ?1",
+            [item_id],
+        )?;
         conn.execute("DELETE FROM clipboard_history WHERE id = ?1", [item_id])?;
         Ok(())
     }
 
-    /// Clear all history
-    pub fn clear_history(&self) -> SqliteResult<()> {
+    fn clear_history(&self) -> SqliteResult<()> {
         let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM clipboard_history_formats", [])?;
         conn.execute("DELETE FROM clipboard_history", [])?;
         Ok(())
     }
 
-    /// Prune old history items (keep only items newer than the given timestamp)
-    pub fn prune_old_items(&self, before_timestamp: u64) -> SqliteResult<usize> {
+    fn prune(&self, rule: HistoryPrune) -> SqliteResult<usize> {
         let conn = self.conn.lock().unwrap();
-        let count = conn.execute(
-            "DELETE FROM clipboard_history WHERE created_at < ?1",
-            [before_timestamp],
-        )?;
-        Ok(count)
+        match rule {
+            HistoryPrune::OlderThan(before_timestamp) => {
+                Self::prune_old_items(&conn, before_timestamp)
+            }
+            HistoryPrune::KeepNewest(max_items) => Self::prune_to_limit(&conn, max_items),
+        }
     }
+}
 
-    /// Prune history to keep only the most recent N items
-    pub fn prune_to_limit(&self, max_items: u32) -> SqliteResult<usize> {
-        let conn = self.conn.lock().unwrap();
-        // Get count of items
-        let count: i64 = conn.query_row("SELECT COUNT(*) FROM clipboard_history", [], |row| {
-            row.get(0)
-        })?;
+/// Read a history row selected with the columns in `insert_item` order
+fn item_from_row(row: &rusqlite::Row) -> SqliteResult<StoredHistoryItem> {
+    Ok(StoredHistoryItem {
+        id: row.get(0)?,
+        content_type: row.get(1)?,
+        content_hash: row.get(2)?,
+        encrypted_content: row.get(3)?,
+        preview: row.get(4)?,
+        source_device: row.get(5)?,
+        created_at: row.get(6)?,
+        language: row.get(7)?,
+        superseded: row.get(8)?,
+    })
+}
 
-        if count <= max_items as i64 {
-            return Ok(0);
-        }
+/// Insert or replace one history row
+fn insert_item(conn: &rusqlite::Connection, item: &StoredHistoryItem) -> SqliteResult<()> {
+    conn.execute(
+        r#"
+        INSERT OR REPLACE INTO clipboard_history
+        (id, content_type, content_hash, encrypted_content, preview, source_device, created_at, language, superseded)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        "#,
+        rusqlite::params![
+            item.id,
+            item.content_type,
+            item.content_hash,
+            item.encrypted_content,
+            item.preview,
+            item.source_device,
+            item.created_at,
+            item.language,
+            item.superseded,
+        ],
+    )?;
+    Ok(())
+}
 
-        // Get the timestamp of the Nth item
-        let mut stmt = conn.prepare(
-            "SELECT created_at FROM clipboard_history ORDER BY created_at DESC LIMIT 1 OFFSET ?1",
-        )?;
-        let cutoff_timestamp: Option<u64> = stmt.query_row([max_items], |row| row.get(0)).ok();
-        drop(stmt);
+/// Bucket items (newest first) by local calendar day and source device
+fn group_by_day_and_source(
+    items: Vec<StoredHistoryItem>,
+    offset: FixedOffset,
+) -> Vec<HistoryGroup> {
+    let mut groups: Vec<HistoryGroup> = Vec::new();
+    let mut index: HashMap<(NaiveDate, Option<String>), usize> = HashMap::new();
 
-        if let Some(timestamp) = cutoff_timestamp {
-            // Delete directly instead of calling prune_old_items to avoid deadlock
-            // Use <= to include the cutoff item in deletion (we want to keep max_items, not max_items+1)
-            let deleted = conn.execute(
-                "DELETE FROM clipboard_history WHERE created_at <= ?1",
-                [timestamp],
-            )?;
-            Ok(deleted)
-        } else {
-            Ok(0)
-        }
+    for item in items {
+        let day = DateTime::from_timestamp(item.created_at as i64, 0)
+            .unwrap_or_default()
+            .with_timezone(&offset)
+            .date_naive();
+        let key = (day, item.source_device.clone());
+
+        let position = *index.entry(key).or_insert_with(|| {
+            groups.push(HistoryGroup {
+                day,
+                source_device: item.source_device.clone(),
+                items: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[position].items.push(item);
     }
+
+    groups
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::storage::Storage;
+    use crate::storage::{HistoryBackend, Storage, StoredDevice};
     use tempfile::TempDir;
 
+    /// Run a test against every history backend
+    fn for_each_backend(test: impl Fn(&dyn HistoryStore)) {
+        for_each_backend_with_devices(&[], test)
+    }
+
+    /// Run a test against every history backend, with `device_ids` paired
+    /// so items can name them as their source
+    fn for_each_backend_with_devices(device_ids: &[&str], test: impl Fn(&dyn HistoryStore)) {
+        for backend in [HistoryBackend::Sqlite, HistoryBackend::Memory] {
+            let temp_dir = TempDir::new().unwrap();
+            let db_path = temp_dir.path().join("test.db");
+            let storage = Storage::with_history_backend(&db_path, backend).unwrap();
+            for id in device_ids {
+                storage
+                    .devices()
+                    .store_device(&StoredDevice {
+                        id: id.to_string(),
+                        advertised_name: id.to_string(),
+                        public_key: id.as_bytes().to_vec(),
+                        session_key: None,
+                        last_seen: None,
+                        created_at: 0,
+                        is_active: true,
+                        platform: None,
+                        local_nickname: None,
+                    })
+                    .unwrap();
+            }
+            test(storage.history());
+        }
+    }
+
+    fn item(id: &str, created_at: u64) -> StoredHistoryItem {
+        StoredHistoryItem {
+            id: id.to_string(),
+            content_type: 0,
+            content_hash: id.to_string(),
+            encrypted_content: vec![],
+            preview: id.to_string(),
+            source_device: None,
+            created_at,
+            language: None,
+            superseded: false,
+        }
+    }
+
     #[test]
     fn test_store_and_retrieve_history_item() {
-        let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("test.db");
-        let storage = Storage::new(&db_path).unwrap();
-        let history_storage = storage.history();
-
-        let item = StoredHistoryItem {
-            id: "test-item-1".to_string(),
-            content_type: 0, // PlainText
-            content_hash: "abc123".to_string(),
-            encrypted_content: vec![1, 2, 3],
-            preview: "Test content".to_string(),
-            source_device: None,
-            created_at: 1234567890,
-        };
+        for_each_backend(|history_storage| {
+            let item = StoredHistoryItem {
+                id: "test-item-1".to_string(),
+                content_type: 0, // PlainText
+                content_hash: "abc123".to_string(),
+                encrypted_content: vec![1, 2, 3],
+                preview: "Test content".to_string(),
+                source_device: None,
+                created_at: 1234567890,
+                language: None,
+                superseded: false,
+            };
 
-        history_storage.store_item(&item).unwrap();
-        let retrieved = history_storage.get_item("test-item-1").unwrap();
+            history_storage.store_item(&item).unwrap();
+            let retrieved = history_storage.get_item("test-item-1").unwrap();
 
-        assert!(retrieved.is_some());
-        let i = retrieved.unwrap();
-        assert_eq!(i.id, item.id);
-        assert_eq!(i.preview, item.preview);
+            assert!(retrieved.is_some());
+            let i = retrieved.unwrap();
+            assert_eq!(i.id, item.id);
+            assert_eq!(i.preview, item.preview);
+        });
     }
 
     #[test]
     fn test_prune_history() {
-        let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("test.db");
-        let storage = Storage::new(&db_path).unwrap();
-        let history_storage = storage.history();
+        for_each_backend(|history_storage| {
+            // Add multiple items
+            for i in 0..10 {
+                let item = StoredHistoryItem {
+                    id: format!("item-{}", i),
+                    content_type: 0,
+                    content_hash: format!("hash-{}", i),
+                    encrypted_content: vec![],
+                    preview: format!("Item {}", i),
+                    source_device: None,
+                    created_at: 1000 + i as u64,
+                    language: None,
+                    superseded: false,
+                };
+                history_storage.store_item(&item).unwrap();
+            }
+
+            // Prune to keep only 5 items
+            history_storage.prune(HistoryPrune::KeepNewest(5)).unwrap();
+
+            let all = history_storage.get_all_items(None).unwrap();
+            assert_eq!(all.len(), 5);
+        });
+    }
+
+    #[test]
+    fn test_grouped_items_by_local_day_and_source() {
+        for_each_backend_with_devices(&["phone", "laptop"], |history_storage| {
+            // 2024-03-10 00:00:00 UTC
+            let midnight_utc = 1_710_028_800u64;
+            let items = [
+                ("phone-late", Some("phone"), midnight_utc - 3600), // 23:00 UTC on the 9th
+                ("laptop-late", Some("laptop"), midnight_utc - 1800), // 23:30 UTC on the 9th
+                ("phone-early", Some("phone"), midnight_utc + 600), // 00:10 UTC on the 10th
+                ("local", None, midnight_utc + 7200),
+                ("phone-later", Some("phone"), midnight_utc + 9000),
+            ];
+            for (id, source, created_at) in items {
+                history_storage
+                    .store_item(&StoredHistoryItem {
+                        id: id.to_string(),
+                        content_type: 0,
+                        content_hash: id.to_string(),
+                        encrypted_content: vec![],
+                        preview: id.to_string(),
+                        source_device: source.map(str::to_string),
+                        created_at,
+                        language: None,
+                        superseded: false,
+                    })
+                    .unwrap();
+            }
+
+            let summarize =
+                |groups: Vec<HistoryGroup>| -> Vec<(String, Option<String>, Vec<String>)> {
+                    groups
+                        .into_iter()
+                        .map(|g| {
+                            (
+                                g.day.to_string(),
+                                g.source_device,
+                                g.items.into_iter().map(|i| i.id).collect(),
+                            )
+                        })
+                        .collect()
+                };
 
-        // Add multiple items
-        for i in 0..10 {
+            let utc = FixedOffset::east_opt(0).unwrap();
+            assert_eq!(
+                summarize(history_storage.get_grouped_items(None, utc).unwrap()),
+                vec![
+                    (
+                        "2024-03-10".to_string(),
+                        Some("phone".to_string()),
+                        vec!["phone-later".to_string(), "phone-early".to_string()]
+                    ),
+                    ("2024-03-10".to_string(), None, vec!["local".to_string()]),
+                    (
+                        "2024-03-09".to_string(),
+                        Some("laptop".to_string()),
+                        vec!["laptop-late".to_string()]
+                    ),
+                    (
+                        "2024-03-09".to_string(),
+                        Some("phone".to_string()),
+                        vec!["phone-late".to_string()]
+                    ),
+                ]
+            );
+
+            // At UTC+01:00 both late items already fall on the 10th locally
+            let plus_one = FixedOffset::east_opt(3600).unwrap();
+            let groups = summarize(history_storage.get_grouped_items(None, plus_one).unwrap());
+            assert_eq!(groups.len(), 3);
+            assert!(groups.iter().all(|(day, _, _)| day == "2024-03-10"));
+            assert_eq!(
+                groups[0].2,
+                vec!["phone-later", "phone-early", "phone-late"]
+            );
+        });
+    }
+
+    #[test]
+    fn test_store_and_remove_formats() {
+        for_each_backend(|history_storage| {
             let item = StoredHistoryItem {
-                id: format!("item-{}", i),
-                content_type: 0,
-                content_hash: format!("hash-{}", i),
-                encrypted_content: vec![],
-                preview: format!("Item {}", i),
+                id: "snapshot-1".to_string(),
+                content_type: 1, // RichText
+                content_hash: "abc123".to_string(),
+                encrypted_content: vec![1],
+                preview: "Snapshot".to_string(),
                 source_device: None,
-                created_at: 1000 + i as u64,
+                created_at: 1000,
+                language: None,
+                superseded: false,
             };
             history_storage.store_item(&item).unwrap();
-        }
 
-        // Prune to keep only 5 items
-        history_storage.prune_to_limit(5).unwrap();
+            let formats: Vec<_> = [1u8, 2, 0]
+                .iter()
+                .map(|&content_type| StoredHistoryFormat {
+                    item_id: item.id.clone(),
+                    content_type,
+                    encrypted_content: vec![content_type; 4],
+                })
+                .collect();
+            history_storage.store_formats(&item.id, &formats).unwrap();
+
+            let retrieved = history_storage.get_formats(&item.id).unwrap();
+            let types: Vec<u8> = retrieved.iter().map(|f| f.content_type).collect();
+            assert_eq!(types, vec![1, 2, 0]);
+
+            history_storage.remove_item(&item.id).unwrap();
+            assert!(history_storage.get_formats(&item.id).unwrap().is_empty());
+        });
+    }
 
-        let all = history_storage.get_all_items(None).unwrap();
-        assert_eq!(all.len(), 5);
+    #[test]
+    fn test_replace_and_prune_by_age() {
+        for_each_backend(|history| {
+            for (id, created_at) in [("a", 1000), ("b", 2000), ("c", 3000)] {
+                history.store_item(&item(id, created_at)).unwrap();
+            }
+            let format = StoredHistoryFormat {
+                item_id: "a".to_string(),
+                content_type: 2,
+                encrypted_content: vec![2],
+            };
+            history.store_formats("a", &[format]).unwrap();
+
+            // Storing an existing id replaces the item instead of adding one
+            let mut updated = item("b", 4000);
+            updated.preview = "updated".to_string();
+            history.store_item(&updated).unwrap();
+            let ids: Vec<String> = history
+                .get_all_items(None)
+                .unwrap()
+                .into_iter()
+                .map(|i| i.id)
+                .collect();
+            assert_eq!(ids, vec!["b", "c", "a"]);
+            assert_eq!(history.get_item("b").unwrap().unwrap().preview, "updated");
+            assert_eq!(history.get_all_items(Some(1)).unwrap().len(), 1);
+
+            // Pruning by age takes the representations of pruned items along
+            assert_eq!(history.prune(HistoryPrune::OlderThan(3000)).unwrap(), 1);
+            assert!(history.get_item("a").unwrap().is_none());
+            assert!(history.get_formats("a").unwrap().is_empty());
+            assert_eq!(history.prune(HistoryPrune::KeepNewest(5)).unwrap(), 0);
+
+            history.clear_history().unwrap();
+            assert!(history.get_all_items(None).unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn test_pages_after_cursor_keep_timestamp_ties_together() {
+        for_each_backend(|history| {
+            // Three items per second, stored out of order
+            for i in (0..12u64).rev() {
+                history
+                    .store_item(&item(&format!("item-{:02}", i), 1000 + i / 3))
+                    .unwrap();
+            }
+
+            let mut seen = Vec::new();
+            let mut page_sizes = Vec::new();
+            let mut after = 0;
+            loop {
+                let page = history.get_items_after(after, 4).unwrap();
+                let Some(last) = page.last() else { break };
+                after = last.created_at;
+                page_sizes.push(page.len());
+                seen.extend(page.into_iter().map(|item| item.id));
+            }
+
+            // A page of four would split a second, so each runs to the second's end
+            assert_eq!(page_sizes, vec![6, 6]);
+            let expected: Vec<_> = (0..12).map(|i| format!("item-{:02}", i)).collect();
+            assert_eq!(seen, expected);
+            assert!(history.get_items_after(0, 0).unwrap().is_empty());
+        });
     }
 }
@@ -0,0 +1,146 @@
+//! Clipboard history kept in memory
+//!
+//! Nothing survives a restart. Useful for tests and for sessions that
+//! shouldn't leave history on disk.
+
+use super::history_storage::{HistoryPrune, HistoryStore, StoredHistoryFormat, StoredHistoryItem};
+use rusqlite::Result as SqliteResult;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+#[derive(Default)]
+struct MemoryHistory {
+    /// Items in insertion order
+    items: Vec<StoredHistoryItem>,
+    /// Representations of multi-format items, by item ID
+    formats: HashMap<String, Vec<StoredHistoryFormat>>,
+}
+
+impl MemoryHistory {
+    /// Drop representations whose history item no longer exists
+    fn delete_orphaned_formats(&mut self) {
+        let items = &self.items;
+        self.formats
+            .retain(|item_id, _| items.iter().any(|item| &item.id == item_id));
+    }
+
+    /// Remove items matching `remove`, returning how many went
+    fn remove_where(&mut self, remove: impl Fn(&StoredHistoryItem) -> bool) -> usize {
+        let before = self.items.len();
+        self.items.retain(|item| !remove(item));
+        self.delete_orphaned_formats();
+        before - self.items.len()
+    }
+}
+
+/// Clipboard history held in memory, with the same behavior as the database
+#[derive(Default)]
+pub struct MemoryHistoryStore {
+    history: Mutex<MemoryHistory>,
+}
+
+impl MemoryHistoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl HistoryStore for MemoryHistoryStore {
+    fn store_item(&self, item: &StoredHistoryItem) -> SqliteResult<()> {
+        let mut history = self.history.lock().unwrap();
+        history.items.retain(|stored| stored.id != item.id);
+        history.items.push(item.clone());
+        Ok(())
+    }
+
+    fn store_formats(&self, item_id: &str, formats: &[StoredHistoryFormat]) -> SqliteResult<()> {
+        let mut history = self.history.lock().unwrap();
+        history.formats.insert(
+            item_id.to_string(),
+            formats
+                .iter()
+                .map(|format| StoredHistoryFormat {
+                    item_id: item_id.to_string(),
+                    ..format.clone()
+                })
+                .collect(),
+        );
+        Ok(())
+    }
+
+    fn get_formats(&self, item_id: &str) -> SqliteResult<Vec<StoredHistoryFormat>> {
+        let history = self.history.lock().unwrap();
+        Ok(history.formats.get(item_id).cloned().unwrap_or_default())
+    }
+
+    fn get_item(&self, item_id: &str) -> SqliteResult<Option<StoredHistoryItem>> {
+        let history = self.history.lock().unwrap();
+        Ok(history
+            .items
+            .iter()
+            .find(|item| item.id == item_id)
+            .cloned())
+    }
+
+    fn get_all_items(&self, limit: Option<u32>) -> SqliteResult<Vec<StoredHistoryItem>> {
+        let mut items = self.history.lock().unwrap().items.clone();
+        items.sort_by_key(|item| std::cmp::Reverse(item.created_at));
+        if let Some(limit) = limit {
+            items.truncate(limit as usize);
+        }
+        Ok(items)
+    }
+
+    fn get_items_after(&self, after: u64, limit: u32) -> SqliteResult<Vec<StoredHistoryItem>> {
+        let mut items: Vec<_> = self
+            .history
+            .lock()
+            .unwrap()
+            .items
+            .iter()
+            .filter(|item| item.created_at > after)
+            .cloned()
+            .collect();
+        items.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        if let Some(last) = items.get(limit as usize - 1).map(|item| item.created_at) {
+            items.retain(|item| item.created_at <= last);
+        }
+        Ok(items)
+    }
+
+    fn remove_item(&self, item_id: &str) -> SqliteResult<()> {
+        let mut history = self.history.lock().unwrap();
+        history.items.retain(|item| item.id != item_id);
+        history.formats.remove(item_id);
+        Ok(())
+    }
+
+    fn clear_history(&self) -> SqliteResult<()> {
+        *self.history.lock().unwrap() = MemoryHistory::default();
+        Ok(())
+    }
+
+    fn prune(&self, rule: HistoryPrune) -> SqliteResult<usize> {
+        let mut history = self.history.lock().unwrap();
+        match rule {
+            HistoryPrune::OlderThan(before_timestamp) => {
+                Ok(history.remove_where(|item| item.created_at < before_timestamp))
+            }
+            HistoryPrune::KeepNewest(max_items) => {
+                let mut created: Vec<u64> = history.items.iter().map(|i| i.created_at).collect();
+                if created.len() <= max_items as usize {
+                    return Ok(0);
+                }
+                // Same cut as the database: everything at or before the
+                // creation time of the first item over the limit
+                created.sort_unstable_by(|a, b| b.cmp(a));
+                let cutoff = created[max_items as usize];
+                Ok(history.remove_where(|item| item.created_at <= cutoff))
+            }
+        }
+    }
+}
@@ -0,0 +1,361 @@
+//! Versioned schema migrations
+//!
+//! The schema version is kept in a one-row `schema_version` table. When
+//! storage is opened, every migration past the stored version runs in order,
+//! each in its own transaction together with the version bump, so an
+//! interrupted upgrade resumes where it stopped. Databases from before
+//! versioning start at version 0 and may already have some of the columns;
+//! migrations add columns only if they are missing. A database written by a
+//! newer build is refused rather than modified.
+
+use rusqlite::{Connection, Result as SqliteResult, Transaction};
+
+use super::device_storage;
+
+/// One schema step, run inside the transaction that records it
+type Migration = fn(&Transaction<'_>) -> SqliteResult<()>;
+
+/// Migrations in order; entry `i` brings the schema to version `i + 1`
+///
+/// Append only: never edit or reorder a migration that has shipped.
+const MIGRATIONS: &[Migration] = &[
+    create_base_tables,
+    add_device_platform,
+    add_history_formats,
+    add_history_language,
+    add_sync_stats,
+    add_history_superseded,
+    add_device_local_nickname,
+    unique_device_keys,
+    add_device_auto_accept_pairing,
+    add_relay_usage,
+    add_device_fingerprint_verified,
+];
+
+/// Schema version this build writes
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Bring the schema up to [`SCHEMA_VERSION`]
+///
+/// Fails without changing anything if the database is newer than this build.
+pub fn migrate(conn: &mut Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL)",
+        [],
+    )?;
+    let current = schema_version(conn)?;
+    if current > SCHEMA_VERSION {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_ERROR),
+            Some(format!(
+                "Database schema version {} is newer than supported version {}",
+                current, SCHEMA_VERSION
+            )),
+        ));
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        let version = index as u32 + 1;
+        let tx = conn.transaction()?;
+        migration(&tx)?;
+        tx.execute("DELETE FROM schema_version", [])?;
+        tx.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [version],
+        )?;
+        tx.commit()?;
+        tracing::debug!("Migrated storage schema to version {}", version);
+    }
+    Ok(())
+}
+
+/// The stored schema version, 0 if none was recorded yet
+pub fn schema_version(conn: &Connection) -> SqliteResult<u32> {
+    let version: Option<u32> =
+        conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| {
+            row.get(0)
+        })?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Add a column unless a database from before versioning already has it
+fn add_column(
+    tx: &Transaction<'_>,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> SqliteResult<()> {
+    let exists = tx
+        .prepare(&format!("PRAGMA table_info({})", table))?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .any(|name| name == column);
+    if !exists {
+        tx.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn create_base_tables(tx: &Transaction<'_>) -> SqliteResult<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS devices (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            public_key BLOB NOT NULL,
+            session_key BLOB,
+            last_seen INTEGER,
+            created_at INTEGER NOT NULL,
+            is_active INTEGER DEFAULT 1
+        );
+
+        CREATE TABLE IF NOT EXISTS clipboard_history (
+            id TEXT PRIMARY KEY,
+            content_type INTEGER NOT NULL,
+            content_hash TEXT NOT NULL,
+            encrypted_content BLOB,
+            preview TEXT,
+            source_device TEXT,
+            created_at INTEGER NOT NULL,
+            FOREIGN KEY (source_device) REFERENCES devices(id)
+        );
+
+        -- For efficient pruning
+        CREATE INDEX IF NOT EXISTS idx_clipboard_history_created_at
+        ON clipboard_history(created_at DESC);
+
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        "#,
+    )
+}
+
+fn add_device_platform(tx: &Transaction<'_>) -> SqliteResult<()> {
+    add_column(tx, "devices", "platform", "TEXT")
+}
+
+/// Extra representations of multi-format history entries
+fn add_history_formats(tx: &Transaction<'_>) -> SqliteResult<()> {
+    tx.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS clipboard_history_formats (
+            item_id TEXT NOT NULL,
+            content_type INTEGER NOT NULL,
+            encrypted_content BLOB NOT NULL,
+            PRIMARY KEY (item_id, content_type),
+            FOREIGN KEY (item_id) REFERENCES clipboard_history(id)
+        )
+        "#,
+        [],
+    )?;
+    Ok(())
+}
+
+fn add_history_language(tx: &Transaction<'_>) -> SqliteResult<()> {
+    add_column(tx, "clipboard_history", "language", "TEXT")
+}
+
+/// Sync statistics (aggregate counters only)
+fn add_sync_stats(tx: &Transaction<'_>) -> SqliteResult<()> {
+    tx.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS stats (
+            key TEXT PRIMARY KEY,
+            value INTEGER NOT NULL
+        )
+        "#,
+        [],
+    )?;
+    Ok(())
+}
+
+fn add_history_superseded(tx: &Transaction<'_>) -> SqliteResult<()> {
+    add_column(
+        tx,
+        "clipboard_history",
+        "superseded",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+}
+
+fn add_device_local_nickname(tx: &Transaction<'_>) -> SqliteResult<()> {
+    add_column(tx, "devices", "local_nickname", "TEXT")
+}
+
+/// One row per device: merge duplicates stored under different ids, then enforce it
+///
+/// Comes after the columns the merge reads and writes.
+fn unique_device_keys(tx: &Transaction<'_>) -> SqliteResult<()> {
+    device_storage::merge_duplicate_devices(tx)?;
+    tx.execute(
+        r#"
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_devices_public_key
+        ON devices(public_key)
+        "#,
+        [],
+    )?;
+    Ok(())
+}
+
+/// Whether pairing requests from the device skip the confirmation prompt
+fn add_device_auto_accept_pairing(tx: &Transaction<'_>) -> SqliteResult<()> {
+    add_column(
+        tx,
+        "devices",
+        "auto_accept_pairing",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+}
+
+/// Per-device metered traffic, one row per device and month
+fn add_relay_usage(tx: &Transaction<'_>) -> SqliteResult<()> {
+    tx.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS relay_usage (
+            device_id TEXT NOT NULL,
+            month TEXT NOT NULL,
+            bytes_sent INTEGER NOT NULL DEFAULT 0,
+            bytes_received INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (device_id, month)
+        )
+        "#,
+        [],
+    )?;
+    Ok(())
+}
+
+/// When the user confirmed a device's key fingerprint, NULL if never
+fn add_device_fingerprint_verified(tx: &Transaction<'_>) -> SqliteResult<()> {
+    add_column(tx, "devices", "fingerprint_verified_at", "INTEGER")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use tempfile::TempDir;
+
+    /// Schema and rows as written by a build from before versioning
+    fn old_schema_fixture(conn: &Connection) {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE devices (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                public_key BLOB NOT NULL,
+                session_key BLOB,
+                last_seen INTEGER,
+                created_at INTEGER NOT NULL,
+                is_active INTEGER DEFAULT 1,
+                platform TEXT
+            );
+            CREATE TABLE clipboard_history (
+                id TEXT PRIMARY KEY,
+                content_type INTEGER NOT NULL,
+                content_hash TEXT NOT NULL,
+                encrypted_content BLOB,
+                preview TEXT,
+                source_device TEXT,
+                created_at INTEGER NOT NULL
+            );
+            CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+
+            INSERT INTO devices (id, name, public_key, session_key, last_seen, created_at, platform)
+            VALUES ('laptop', 'Laptop', X'01', X'02', 50, 1000, 'linux');
+            INSERT INTO clipboard_history
+                (id, content_type, content_hash, encrypted_content, preview, source_device, created_at)
+            VALUES ('item-1', 0, 'hash', X'0303', 'preview', 'laptop', 2000);
+            INSERT INTO settings (key, value) VALUES ('auto_sync', 'true');
+            "#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_old_schema_is_migrated_without_data_loss() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("old.db");
+        old_schema_fixture(&Connection::open(&db_path).unwrap());
+
+        let storage = Storage::new(&db_path).unwrap();
+        assert_eq!(
+            schema_version(&Connection::open(&db_path).unwrap()).unwrap(),
+            SCHEMA_VERSION
+        );
+
+        let device = storage.devices().get_device("laptop").unwrap().unwrap();
+        assert_eq!(device.advertised_name, "Laptop");
+        assert_eq!(device.platform.as_deref(), Some("linux"));
+        assert_eq!(device.local_nickname, None);
+
+        let items = storage.history().get_all_items(None).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].encrypted_content, vec![3, 3]);
+        assert_eq!(items[0].language, None);
+        assert!(!items[0].superseded);
+
+        // Reopening finds nothing left to do
+        drop(storage);
+        let mut conn = Connection::open(&db_path).unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+
+        // The encrypted session key is carried over untouched
+        let session_key: Vec<u8> = conn
+            .query_row(
+                "SELECT session_key FROM devices WHERE id = 'laptop'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(session_key, vec![2]);
+        let setting: String = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = 'auto_sync'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(setting, "true");
+    }
+
+    #[test]
+    fn test_fresh_database_gets_current_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn test_newer_schema_is_refused() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("newer.db");
+        {
+            let mut conn = Connection::open(&db_path).unwrap();
+            migrate(&mut conn).unwrap();
+            conn.execute(
+                "UPDATE schema_version SET version = ?1",
+                [SCHEMA_VERSION + 1],
+            )
+            .unwrap();
+        }
+
+        let error = Storage::new(&db_path).err().expect("newer schema opened");
+        assert!(error.to_string().contains("newer than supported"));
+        assert_eq!(
+            schema_version(&Connection::open(&db_path).unwrap()).unwrap(),
+            SCHEMA_VERSION + 1
+        );
+    }
+}
@@ -1,28 +1,69 @@
 //! Storage module for persisting paired devices and settings
 //!
-//! Uses SQLite for local storage with encrypted session keys.
+//! Uses SQLite for local storage with encrypted session keys. If the
+//! database file can't be opened, an in-memory database can stand in for the
+//! session; nothing stored in it survives a restart. A corrupt database file
+//! is moved aside and rebuilt (see [`StorageRecovery`]).
 
+mod buffered_history;
 mod device_storage;
 mod history_storage;
+mod memory_history_storage;
+mod migrations;
+mod recovery;
+mod relay_usage_storage;
 mod secure_storage;
+mod settings_storage;
+mod stats_storage;
 
-pub use device_storage::{DeviceStorage, StoredDevice};
-pub use history_storage::{HistoryStorage, StoredHistoryItem};
+pub use buffered_history::{BufferedHistoryStore, DEFAULT_HISTORY_WRITE_WINDOW};
+pub use device_storage::{DeviceLimitPolicy, DeviceStorage, StoreDeviceOutcome, StoredDevice};
+pub use history_storage::{
+    HistoryGroup, HistoryPrune, HistoryStore, SqliteHistoryStore, StoredHistoryFormat,
+    StoredHistoryItem,
+};
+pub use memory_history_storage::MemoryHistoryStore;
+pub use migrations::SCHEMA_VERSION;
+pub use recovery::StorageRecovery;
+pub use relay_usage_storage::{RelayUsage, RelayUsageStorage};
 pub use secure_storage::{
     decrypt_from_storage, delete_identity_key, encrypt_for_storage,
-    get_or_create_storage_encryption_key, retrieve_identity_key, store_identity_key,
+    get_or_create_storage_encryption_key, probe_secure_storage, retrieve_identity_key,
+    store_identity_key, wipe_key_material,
 };
+pub use settings_storage::SettingsStorage;
+pub use stats_storage::{StatsStorage, SyncStats};
 
 use rusqlite::{Connection, Result as SqliteResult};
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long a connection waits for a competing writer before failing
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Path SQLite uses for an in-memory database
+const IN_MEMORY_PATH: &str = ":memory:";
+
+/// Where clipboard history is kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HistoryBackend {
+    /// The storage database, alongside devices and settings
+    #[default]
+    Sqlite,
+    /// Memory only; history is lost when storage is dropped
+    Memory,
+}
 
 /// Storage manager
 /// Note: rusqlite::Connection is not Sync, so we wrap operations in Mutex
 /// when needed for thread-safe access
 pub struct Storage {
-    conn: Mutex<Connection>,
+    conn: Arc<Mutex<Connection>>,
+    history: BufferedHistoryStore,
     db_path: PathBuf,
+    in_memory: bool,
+    recovery: Option<StorageRecovery>,
 }
 
 // Safety: We ensure all access to Connection is through the Mutex,
@@ -33,82 +74,100 @@ unsafe impl Sync for Storage {}
 impl Storage {
     /// Create or open storage at the given path
     pub fn new<P: AsRef<Path>>(db_path: P) -> SqliteResult<Self> {
+        Self::with_history_backend(db_path, HistoryBackend::default())
+    }
+
+    /// Create or open storage at the given path, keeping history in `history`
+    pub fn with_history_backend<P: AsRef<Path>>(
+        db_path: P,
+        history: HistoryBackend,
+    ) -> SqliteResult<Self> {
         let path = db_path.as_ref().to_path_buf();
-        let conn = Connection::open(&path)?;
-        let storage = Self {
-            conn: Mutex::new(conn),
-            db_path: path,
+        let open = || -> SqliteResult<Connection> {
+            let conn = Connection::open(&path)?;
+            Self::configure(&conn)?;
+            recovery::check_integrity(&conn)?;
+            Ok(conn)
+        };
+
+        let (conn, recovery) = match open() {
+            Ok(conn) => (conn, None),
+            Err(e) if recovery::is_corruption(&e) => {
+                tracing::error!("Database {} is corrupt: {}", path.display(), e);
+                let (conn, recovery) = recovery::rebuild(&path, Self::configure)?;
+                (conn, Some(recovery))
+            }
+            Err(e) => return Err(e),
         };
+
+        let mut storage = Self::from_connection(conn, history, path, false);
+        storage.recovery = recovery;
         storage.init_schema()?;
         Ok(storage)
     }
 
+    /// Apply the settings every file connection uses
+    fn configure(conn: &Connection) -> SqliteResult<()> {
+        // Other connections (e.g. the network session key lookup) open the
+        // same file: WAL lets their reads run alongside writes, and the busy
+        // timeout makes competing writers wait instead of failing
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+        conn.busy_timeout(BUSY_TIMEOUT)
+    }
+
+    /// Create storage backed by an in-memory database
+    ///
+    /// Offers the same accessors as file storage, but everything is lost
+    /// when it is dropped.
+    pub fn in_memory() -> SqliteResult<Self> {
+        let storage = Self::from_connection(
+            Connection::open_in_memory()?,
+            HistoryBackend::default(),
+            PathBuf::from(IN_MEMORY_PATH),
+            true,
+        );
+        storage.init_schema()?;
+        Ok(storage)
+    }
+
+    fn from_connection(
+        conn: Connection,
+        history: HistoryBackend,
+        db_path: PathBuf,
+        in_memory: bool,
+    ) -> Self {
+        let conn = Arc::new(Mutex::new(conn));
+        let history: Box<dyn HistoryStore> = match history {
+            HistoryBackend::Sqlite => Box::new(SqliteHistoryStore::new(conn.clone())),
+            HistoryBackend::Memory => Box::new(MemoryHistoryStore::new()),
+        };
+        Self {
+            conn,
+            history: BufferedHistoryStore::new(history, DEFAULT_HISTORY_WRITE_WINDOW),
+            db_path,
+            in_memory,
+            recovery: None,
+        }
+    }
+
     /// Get the database path
     pub fn db_path(&self) -> &Path {
         &self.db_path
     }
 
-    /// Initialize database schema
-    fn init_schema(&self) -> SqliteResult<()> {
-        let conn = self.conn.lock().unwrap();
-        // Create devices table
-        conn.execute(
-            r#"
-            CREATE TABLE IF NOT EXISTS devices (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                public_key BLOB NOT NULL,
-                session_key BLOB,
-                last_seen INTEGER,
-                created_at INTEGER NOT NULL,
-                is_active INTEGER DEFAULT 1,
-                platform TEXT
-            )
-            "#,
-            [],
-        )?;
-
-        // Add platform column if it doesn't exist (migration for existing databases)
-        let _ = conn.execute("ALTER TABLE devices ADD COLUMN platform TEXT", []);
-
-        // Create clipboard history table
-        conn.execute(
-            r#"
-            CREATE TABLE IF NOT EXISTS clipboard_history (
-                id TEXT PRIMARY KEY,
-                content_type INTEGER NOT NULL,
-                content_hash TEXT NOT NULL,
-                encrypted_content BLOB,
-                preview TEXT,
-                source_device TEXT,
-                created_at INTEGER NOT NULL,
-                FOREIGN KEY (source_device) REFERENCES devices(id)
-            )
-            "#,
-            [],
-        )?;
-
-        // Create index on created_at for efficient pruning
-        conn.execute(
-            r#"
-            CREATE INDEX IF NOT EXISTS idx_clipboard_history_created_at
-            ON clipboard_history(created_at DESC)
-            "#,
-            [],
-        )?;
+    /// Whether this is in-memory storage that isn't persisted
+    pub fn is_in_memory(&self) -> bool {
+        self.in_memory
+    }
 
-        // Create settings table
-        conn.execute(
-            r#"
-            CREATE TABLE IF NOT EXISTS settings (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL
-            )
-            "#,
-            [],
-        )?;
+    /// How the database was rebuilt when opened, if it was found corrupt
+    pub fn recovery(&self) -> Option<&StorageRecovery> {
+        self.recovery.as_ref()
+    }
 
-        Ok(())
+    /// Initialize the database schema, migrating an older one
+    fn init_schema(&self) -> SqliteResult<()> {
+        migrations::migrate(&mut self.conn.lock().unwrap())
     }
 
     /// Get device storage operations
@@ -117,8 +176,74 @@ impl Storage {
     }
 
     /// Get history storage operations
-    pub fn history(&self) -> HistoryStorage<'_> {
-        HistoryStorage::new(&self.conn)
+    pub fn history(&self) -> &dyn HistoryStore {
+        &self.history
+    }
+
+    /// Set how long history writes are held back and coalesced
+    ///
+    /// Zero writes every item immediately.
+    pub fn set_history_write_window(&self, window: Duration) -> SqliteResult<()> {
+        self.history.set_window(window)
+    }
+
+    /// Write out held-back history whose write window has passed
+    ///
+    /// Call periodically so the last items of a burst don't wait for the
+    /// next read.
+    pub fn write_due_history(&self) -> SqliteResult<()> {
+        self.history.write_due()
+    }
+
+    /// Get settings storage operations
+    pub fn settings(&self) -> SettingsStorage<'_> {
+        SettingsStorage::new(&self.conn)
+    }
+
+    /// Get sync statistics storage operations
+    pub fn stats(&self) -> StatsStorage<'_> {
+        StatsStorage::new(&self.conn)
+    }
+
+    /// Get per-device relay usage storage operations
+    pub fn relay_usage(&self) -> RelayUsageStorage<'_> {
+        RelayUsageStorage::new(&self.conn)
+    }
+
+    /// Check that the database accepts writes, without changing it
+    pub fn check_writable(&self) -> SqliteResult<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('self_test', '1')",
+            [],
+        )?;
+        tx.rollback()
+    }
+
+    /// Delete all devices, history, settings, sync statistics and relay usage
+    ///
+    /// Enables SQLite `secure_delete` so freed pages are zeroed, then vacuums
+    /// the file so deleted rows don't linger in free pages.
+    pub fn wipe_all(&self) -> SqliteResult<()> {
+        // History may live outside the database; clear it before locking the
+        // connection, which the SQLite history store locks too
+        self.history.clear_history()?;
+
+        let mut conn = self.conn.lock().unwrap();
+        conn.pragma_update(None, "secure_delete", "ON")?;
+
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM clipboard_history_formats", [])?;
+        tx.execute("DELETE FROM clipboard_history", [])?;
+        tx.execute("DELETE FROM devices", [])?;
+        tx.execute("DELETE FROM settings", [])?;
+        tx.execute("DELETE FROM stats", [])?;
+        tx.execute("DELETE FROM relay_usage", [])?;
+        tx.commit()?;
+
+        conn.execute("VACUUM", [])?;
+        Ok(())
     }
 }
 
@@ -156,4 +281,106 @@ mod tests {
         assert!(tables.contains(&"devices".to_string()));
         assert!(tables.contains(&"settings".to_string()));
     }
+
+    #[test]
+    fn test_concurrent_connections() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let storage = Storage::new(&db_path).unwrap();
+        let other = Storage::new(&db_path).unwrap();
+
+        let journal_mode: String = storage
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(journal_mode.to_lowercase(), "wal");
+
+        // Hold a write transaction open on the first connection
+        let conn = storage.conn.lock().unwrap();
+        conn.execute_batch("BEGIN IMMEDIATE; INSERT INTO settings (key, value) VALUES ('a', '1');")
+            .unwrap();
+
+        // Reads on another connection proceed during the write
+        assert!(other.devices().get_all_devices().unwrap().is_empty());
+
+        // A competing writer waits for the transaction instead of hitting SQLITE_BUSY
+        let writer = std::thread::spawn(move || {
+            other
+                .conn
+                .lock()
+                .unwrap()
+                .execute("INSERT INTO settings (key, value) VALUES ('b', '2')", [])
+        });
+        std::thread::sleep(Duration::from_millis(200));
+        conn.execute_batch("COMMIT").unwrap();
+        drop(conn);
+        writer.join().unwrap().unwrap();
+
+        let settings: i64 = storage
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM settings", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(settings, 2);
+    }
+
+    #[test]
+    fn test_wipe_all() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let storage = Storage::new(&db_path).unwrap();
+
+        storage
+            .devices()
+            .store_device(&StoredDevice {
+                id: "device-1".to_string(),
+                advertised_name: "Device 1".to_string(),
+                public_key: vec![1],
+                session_key: None,
+                last_seen: None,
+                created_at: 1000,
+                is_active: true,
+                platform: None,
+                local_nickname: None,
+            })
+            .unwrap();
+        storage
+            .history()
+            .store_item(&StoredHistoryItem {
+                id: "item-1".to_string(),
+                content_type: 0,
+                content_hash: "hash".to_string(),
+                encrypted_content: vec![1, 2, 3],
+                preview: "preview".to_string(),
+                source_device: None,
+                created_at: 1000,
+                language: None,
+                superseded: false,
+            })
+            .unwrap();
+        storage
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO settings (key, value) VALUES ('auto_sync', 'true')",
+                [],
+            )
+            .unwrap();
+
+        storage.wipe_all().unwrap();
+
+        assert!(storage.devices().get_device("device-1").unwrap().is_none());
+        assert!(storage.history().get_all_items(None).unwrap().is_empty());
+        let settings: i64 = storage
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM settings", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(settings, 0);
+    }
 }
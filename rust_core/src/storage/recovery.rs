@@ -0,0 +1,253 @@
+//! Recovery from a corrupt database file
+//!
+//! A write cut short by power loss can leave `toss.db` unreadable. When
+//! opening storage finds the file corrupt, it is moved aside (together with
+//! its WAL) rather than deleted, a fresh database is created in its place,
+//! and every table that can still be read from the corrupt copy is copied
+//! over. Tables that can't be read start empty.
+
+use rusqlite::{ffi, Connection, ErrorCode, Result as SqliteResult};
+use std::path::{Path, PathBuf};
+
+use super::migrations;
+
+/// Tables copied from a corrupt database, parents before children
+const SALVAGED_TABLES: &[&str] = &[
+    "devices",
+    "settings",
+    "clipboard_history",
+    "clipboard_history_formats",
+    "stats",
+];
+
+/// Outcome of rebuilding a corrupt database when storage was opened
+#[derive(Debug, Clone)]
+pub struct StorageRecovery {
+    /// Where the corrupt file was kept
+    pub backup_path: PathBuf,
+    /// Tables whose rows couldn't be read back and were lost
+    pub lost_tables: Vec<String>,
+}
+
+/// Fail with `SQLITE_CORRUPT` unless `PRAGMA integrity_check` reports "ok"
+pub(super) fn check_integrity(conn: &Connection) -> SqliteResult<()> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let problems = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    if problems.len() == 1 && problems[0] == "ok" {
+        return Ok(());
+    }
+    Err(rusqlite::Error::SqliteFailure(
+        ffi::Error::new(ffi::SQLITE_CORRUPT),
+        Some(format!("Integrity check failed: {}", problems.join("; "))),
+    ))
+}
+
+/// Whether an error means the file is damaged, as opposed to e.g. locked
+pub(super) fn is_corruption(error: &rusqlite::Error) -> bool {
+    matches!(
+        error.sqlite_error_code(),
+        Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+    )
+}
+
+/// Move the corrupt database aside and open a fresh one at `path`, copying
+/// over what can still be read
+///
+/// `configure` applies the usual connection settings to the new database.
+pub(super) fn rebuild(
+    path: &Path,
+    configure: impl Fn(&Connection) -> SqliteResult<()>,
+) -> SqliteResult<(Connection, StorageRecovery)> {
+    let backup_path = backup_path(path);
+    move_aside(path, &backup_path)?;
+
+    let mut conn = Connection::open(path)?;
+    configure(&conn)?;
+    migrations::migrate(&mut conn)?;
+
+    let lost_tables = salvage(&conn, &backup_path);
+    if lost_tables.is_empty() {
+        tracing::warn!(
+            "Rebuilt corrupt database; the damaged file was kept at {}",
+            backup_path.display()
+        );
+    } else {
+        tracing::error!(
+            "Rebuilt corrupt database, losing {}; the damaged file was kept at {}",
+            lost_tables.join(", "),
+            backup_path.display()
+        );
+    }
+
+    Ok((
+        conn,
+        StorageRecovery {
+            backup_path,
+            lost_tables,
+        },
+    ))
+}
+
+/// `toss.db` -> `toss.db.corrupt-<unix seconds>`
+fn backup_path(path: &Path) -> PathBuf {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".corrupt-{}", now));
+    path.with_file_name(name)
+}
+
+/// Rename the database and its WAL and shared-memory files, which must move
+/// together for the WAL to still apply
+fn move_aside(path: &Path, backup_path: &Path) -> SqliteResult<()> {
+    for suffix in ["", "-wal", "-shm"] {
+        let mut from = path.as_os_str().to_os_string();
+        from.push(suffix);
+        let mut to = backup_path.as_os_str().to_os_string();
+        to.push(suffix);
+
+        match std::fs::rename(&from, &to) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !suffix.is_empty() => {}
+            Err(e) => {
+                return Err(rusqlite::Error::SqliteFailure(
+                    ffi::Error::new(ffi::SQLITE_CANTOPEN),
+                    Some(format!(
+                        "Failed to move corrupt database to {}: {}",
+                        backup_path.display(),
+                        e
+                    )),
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Copy each table's readable rows from the corrupt file, returning the
+/// tables that couldn't be copied
+///
+/// Only columns present in both schemas are copied, so a corrupt file from an
+/// older schema version still yields its rows.
+fn salvage(conn: &Connection, backup_path: &Path) -> Vec<String> {
+    let all_lost = || SALVAGED_TABLES.iter().map(|t| t.to_string()).collect();
+
+    let Some(backup) = backup_path.to_str() else {
+        return all_lost();
+    };
+    if let Err(e) = conn.execute("ATTACH DATABASE ?1 AS corrupt", [backup]) {
+        tracing::warn!("Could not read corrupt database: {}", e);
+        return all_lost();
+    }
+
+    let lost = SALVAGED_TABLES
+        .iter()
+        .filter(|table| {
+            if let Err(e) = copy_table(conn, table) {
+                tracing::warn!("Could not recover table {}: {}", table, e);
+                return true;
+            }
+            false
+        })
+        .map(|table| table.to_string())
+        .collect();
+
+    if let Err(e) = conn.execute("DETACH DATABASE corrupt", []) {
+        tracing::warn!("Failed to detach corrupt database: {}", e);
+    }
+    lost
+}
+
+fn copy_table(conn: &Connection, table: &str) -> SqliteResult<()> {
+    let columns = |schema: &str| -> SqliteResult<Vec<String>> {
+        let mut stmt = conn.prepare(&format!("PRAGMA {}.table_info({})", schema, table))?;
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(names)
+    };
+
+    let available = columns("corrupt")?;
+    let shared: Vec<_> = columns("main")?
+        .into_iter()
+        .filter(|column| available.contains(column))
+        .collect();
+    if shared.is_empty() {
+        // The corrupt file predates the table
+        return Ok(());
+    }
+
+    let columns = shared.join(", ");
+    conn.execute(
+        &format!(
+            "INSERT OR IGNORE INTO main.{table} ({columns}) SELECT {columns} FROM corrupt.{table}"
+        ),
+        [],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::{Storage, StoredDevice};
+    use std::io::{Seek, SeekFrom, Write};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_corrupt_database_is_rebuilt_and_kept() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("toss.db");
+
+        let device = StoredDevice {
+            id: "device-1".to_string(),
+            advertised_name: "Device 1".to_string(),
+            public_key: vec![1],
+            session_key: None,
+            last_seen: None,
+            created_at: 1000,
+            is_active: true,
+            platform: None,
+            local_nickname: None,
+        };
+        {
+            let storage = Storage::new(&db_path).unwrap();
+            storage.devices().store_device(&device).unwrap();
+            assert!(storage.recovery().is_none());
+        }
+
+        // Scribble over the header, as a torn write might
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&db_path)
+            .unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.write_all(&[0xAB; 100]).unwrap();
+        drop(file);
+        let corrupt = std::fs::read(&db_path).unwrap();
+
+        let storage = Storage::new(&db_path).unwrap();
+        let recovery = storage.recovery().expect("corruption detected");
+
+        // The damaged file is preserved byte for byte
+        assert_eq!(std::fs::read(&recovery.backup_path).unwrap(), corrupt);
+        assert!(recovery
+            .backup_path
+            .to_string_lossy()
+            .contains("toss.db.corrupt-"));
+        // An unreadable header loses everything, and says so
+        assert!(recovery.lost_tables.contains(&"devices".to_string()));
+
+        // The fresh database works
+        assert!(storage.devices().get_all_devices().unwrap().is_empty());
+        storage.devices().store_device(&device).unwrap();
+        assert_eq!(storage.devices().get_all_devices().unwrap().len(), 1);
+        drop(storage);
+
+        // and opens cleanly afterwards
+        assert!(Storage::new(&db_path).unwrap().recovery().is_none());
+    }
+}
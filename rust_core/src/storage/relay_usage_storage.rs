@@ -0,0 +1,132 @@
+//! Per-device sync traffic over metered routes, by month
+//!
+//! Bytes sent to and received from each paired device through the relay or
+//! over the internet. Every calendar month has its own row, so the counters
+//! start from zero when a month begins.
+
+use rusqlite::{OptionalExtension, Result as SqliteResult};
+use std::sync::Mutex;
+
+/// Metered traffic with one device in one month
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelayUsage {
+    pub device_id: String,
+    /// Calendar month, "YYYY-MM"
+    pub month: String,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+/// Relay usage storage operations
+pub struct RelayUsageStorage<'conn> {
+    conn: &'conn Mutex<rusqlite::Connection>,
+}
+
+impl<'conn> RelayUsageStorage<'conn> {
+    pub fn new(conn: &'conn Mutex<rusqlite::Connection>) -> Self {
+        Self { conn }
+    }
+
+    /// Add traffic to a device's counters for `month`, returning the new totals
+    pub fn add(
+        &self,
+        device_id: &str,
+        month: &str,
+        bytes_sent: u64,
+        bytes_received: u64,
+    ) -> SqliteResult<RelayUsage> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "INSERT INTO relay_usage (device_id, month, bytes_sent, bytes_received)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(device_id, month) DO UPDATE SET
+                 bytes_sent = bytes_sent + excluded.bytes_sent,
+                 bytes_received = bytes_received + excluded.bytes_received
+             RETURNING bytes_sent, bytes_received",
+            rusqlite::params![device_id, month, bytes_sent as i64, bytes_received as i64],
+            |row| {
+                Ok(RelayUsage {
+                    device_id: device_id.to_string(),
+                    month: month.to_string(),
+                    bytes_sent: row.get::<_, i64>(0)? as u64,
+                    bytes_received: row.get::<_, i64>(1)? as u64,
+                })
+            },
+        )
+    }
+
+    /// Get a device's counters for `month`, zero if nothing was recorded
+    pub fn get(&self, device_id: &str, month: &str) -> SqliteResult<RelayUsage> {
+        let conn = self.conn.lock().unwrap();
+        let counters = conn
+            .query_row(
+                "SELECT bytes_sent, bytes_received FROM relay_usage
+                 WHERE device_id = ?1 AND month = ?2",
+                rusqlite::params![device_id, month],
+                |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64)),
+            )
+            .optional()?;
+        let (bytes_sent, bytes_received) = counters.unwrap_or_default();
+        Ok(RelayUsage {
+            device_id: device_id.to_string(),
+            month: month.to_string(),
+            bytes_sent,
+            bytes_received,
+        })
+    }
+
+    /// Get the counters of every device with traffic in `month`
+    pub fn get_month(&self, month: &str) -> SqliteResult<Vec<RelayUsage>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT device_id, bytes_sent, bytes_received FROM relay_usage
+             WHERE month = ?1 ORDER BY device_id",
+        )?;
+        let rows = stmt.query_map([month], |row| {
+            Ok(RelayUsage {
+                device_id: row.get(0)?,
+                month: month.to_string(),
+                bytes_sent: row.get::<_, i64>(1)? as u64,
+                bytes_received: row.get::<_, i64>(2)? as u64,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Reset every device's counters to zero
+    pub fn reset(&self) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM relay_usage", [])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::Storage;
+
+    #[test]
+    fn test_relay_usage_accumulates_per_month() {
+        let storage = Storage::in_memory().unwrap();
+        let usage = storage.relay_usage();
+        assert_eq!(usage.get("laptop", "2026-10").unwrap().bytes_sent, 0);
+
+        usage.add("laptop", "2026-10", 100, 5).unwrap();
+        let total = usage.add("laptop", "2026-10", 50, 0).unwrap();
+        assert_eq!((total.bytes_sent, total.bytes_received), (150, 5));
+        usage.add("phone", "2026-10", 7, 7).unwrap();
+
+        // A new month starts from zero
+        let next = usage.add("laptop", "2026-11", 1, 0).unwrap();
+        assert_eq!((next.bytes_sent, next.bytes_received), (1, 0));
+        assert_eq!(usage.get("laptop", "2026-10").unwrap().bytes_sent, 150);
+
+        let october = usage.get_month("2026-10").unwrap();
+        let devices: Vec<_> = october.iter().map(|u| u.device_id.as_str()).collect();
+        assert_eq!(devices, vec!["laptop", "phone"]);
+
+        usage.reset().unwrap();
+        assert!(usage.get_month("2026-10").unwrap().is_empty());
+        assert_eq!(usage.get("laptop", "2026-11").unwrap().bytes_sent, 0);
+    }
+}
@@ -34,6 +34,9 @@ const IDENTITY_KEY_NAME: &str = "device_identity_key";
 /// Key name for storage encryption key (used to encrypt session keys)
 const STORAGE_ENCRYPTION_KEY_NAME: &str = "storage_encryption_key";
 
+/// Key name for the throwaway entry written by the self-test
+const SELF_TEST_KEY_NAME: &str = "self_test_probe";
+
 /// Platform-agnostic secure storage trait
 pub trait SecureStorage {
     /// Store a value securely
@@ -47,24 +50,22 @@ pub trait SecureStorage {
 }
 
 /// Store device identity key securely
-pub fn store_identity_key(key: &[u8; 32]) -> Result<(), CryptoError> {
+///
+/// `key` is the algorithm-tagged form from `DeviceIdentity::to_tagged_bytes`.
+pub fn store_identity_key(key: &[u8]) -> Result<(), CryptoError> {
     let storage = get_platform_storage()?;
     storage.store(IDENTITY_KEY_NAME, key)
 }
 
 /// Retrieve device identity key from secure storage
-pub fn retrieve_identity_key() -> Result<Option<[u8; 32]>, CryptoError> {
+///
+/// Returns the stored bytes for `DeviceIdentity::from_tagged_bytes`, which
+/// also accepts untagged keys stored by older versions.
+pub fn retrieve_identity_key() -> Result<Option<Vec<u8>>, CryptoError> {
     let storage = get_platform_storage()?;
     match storage.retrieve(IDENTITY_KEY_NAME)? {
-        Some(bytes) => {
-            if bytes.len() != 32 {
-                return Err(CryptoError::InvalidKey);
-            }
-            let mut key = [0u8; 32];
-            key.copy_from_slice(&bytes);
-            Ok(Some(key))
-        }
-        None => Ok(None),
+        Some(bytes) if bytes.len() < 32 => Err(CryptoError::InvalidKey),
+        other => Ok(other),
     }
 }
 
@@ -74,6 +75,71 @@ pub fn delete_identity_key() -> Result<(), CryptoError> {
     storage.delete(IDENTITY_KEY_NAME)
 }
 
+/// Overwrite and delete all Toss key material from secure storage
+///
+/// Removes both the identity key and the storage encryption key. Every key is
+/// attempted even if an earlier one fails; failures are reported together.
+pub fn wipe_key_material() -> Result<(), CryptoError> {
+    let storage = get_platform_storage()?;
+    wipe_keys(storage.as_ref())
+}
+
+/// Overwrite each key with zeros before deleting it, so backends that only
+/// unlink entries don't leave the old secret behind
+fn wipe_keys(storage: &dyn SecureStorage) -> Result<(), CryptoError> {
+    let mut failures = Vec::new();
+
+    for key in [IDENTITY_KEY_NAME, STORAGE_ENCRYPTION_KEY_NAME] {
+        if let Err(e) = storage
+            .store(key, &[0u8; 32])
+            .and_then(|_| storage.delete(key))
+        {
+            failures.push(format!("{}: {}", key, e));
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(CryptoError::Storage(format!(
+            "Failed to wipe keys: {}",
+            failures.join("; ")
+        )))
+    }
+}
+
+/// Check that secure storage can store, read back and delete an entry
+///
+/// Uses a throwaway key; the identity and storage keys are never touched.
+pub fn probe_secure_storage() -> Result<(), CryptoError> {
+    let storage = get_platform_storage()?;
+    probe(storage.as_ref())
+}
+
+fn probe(storage: &dyn SecureStorage) -> Result<(), CryptoError> {
+    use rand::RngCore;
+    let mut value = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut value);
+
+    storage.store(SELF_TEST_KEY_NAME, &value)?;
+    // Always try to clean up, even if the read failed
+    let read = storage.retrieve(SELF_TEST_KEY_NAME);
+    let deleted = storage.delete(SELF_TEST_KEY_NAME);
+
+    if read?.as_deref() != Some(&value[..]) {
+        return Err(CryptoError::Storage(
+            "Read back a different value".to_string(),
+        ));
+    }
+    deleted?;
+    if storage.retrieve(SELF_TEST_KEY_NAME)?.is_some() {
+        return Err(CryptoError::Storage(
+            "Entry still present after delete".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 /// Get or generate the storage encryption key
 /// This key is used to encrypt session keys before storing in SQLite
 pub fn get_or_create_storage_encryption_key() -> Result<[u8; 32], CryptoError> {
@@ -808,4 +874,25 @@ mod tests {
         let value = storage.retrieve("test_key").unwrap();
         assert_eq!(value, None);
     }
+
+    #[test]
+    fn test_wipe_keys() {
+        let storage = MemoryStorage::new();
+        storage.store(IDENTITY_KEY_NAME, &[7u8; 32]).unwrap();
+        storage
+            .store(STORAGE_ENCRYPTION_KEY_NAME, &[9u8; 32])
+            .unwrap();
+
+        wipe_keys(&storage).unwrap();
+
+        assert_eq!(storage.retrieve(IDENTITY_KEY_NAME).unwrap(), None);
+        assert_eq!(storage.retrieve(STORAGE_ENCRYPTION_KEY_NAME).unwrap(), None);
+    }
+
+    #[test]
+    fn test_probe_leaves_no_entry() {
+        let storage = MemoryStorage::new();
+        probe(&storage).unwrap();
+        assert_eq!(storage.retrieve(SELF_TEST_KEY_NAME).unwrap(), None);
+    }
 }
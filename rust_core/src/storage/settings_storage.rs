@@ -0,0 +1,176 @@
+//! User settings, one row per setting
+//!
+//! Each field of the settings struct is a row of the `settings` table keyed
+//! by its name, with its value as JSON. A setting added in a later version
+//! has no row yet and takes its default; rows for settings that no longer
+//! exist are ignored. A stored value that can't be read as its setting also
+//! takes the default, leaving the other settings as stored.
+
+use rusqlite::types::Type;
+use rusqlite::Result as SqliteResult;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Mutex;
+
+/// Settings storage operations
+pub struct SettingsStorage<'conn> {
+    conn: &'conn Mutex<rusqlite::Connection>,
+}
+
+impl<'conn> SettingsStorage<'conn> {
+    pub fn new(conn: &'conn Mutex<rusqlite::Connection>) -> Self {
+        Self { conn }
+    }
+
+    /// Load the stored settings, with defaults for any not stored or unreadable
+    pub fn load<T: Serialize + DeserializeOwned + Default>(&self) -> SqliteResult<T> {
+        let Value::Object(mut fields) = serde_json::to_value(T::default())
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?
+        else {
+            return Ok(T::default());
+        };
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT key, value FROM settings")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (key, value) = row?;
+            if !fields.contains_key(&key) {
+                continue;
+            }
+            let Ok(value) = serde_json::from_str::<Value>(&value) else {
+                tracing::warn!("Setting {} is not valid JSON, using its default", key);
+                continue;
+            };
+
+            // Keep the value only if the settings still deserialize with it
+            let default = fields.insert(key.clone(), value);
+            if serde_json::from_value::<T>(Value::Object(fields.clone())).is_err() {
+                tracing::warn!("Setting {} has an unreadable value, using its default", key);
+                if let Some(default) = default {
+                    fields.insert(key, default);
+                }
+            }
+        }
+
+        serde_json::from_value(Value::Object(fields))
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, Type::Text, Box::new(e)))
+    }
+
+    /// Store every field of `settings`, replacing their previous values
+    pub fn save<T: Serialize>(&self, settings: &T) -> SqliteResult<()> {
+        let Value::Object(fields) = serde_json::to_value(settings)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?
+        else {
+            return Err(rusqlite::Error::ToSqlConversionFailure(
+                "settings must serialize to an object".into(),
+            ));
+        };
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt =
+                tx.prepare("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)")?;
+            for (key, value) in &fields {
+                stmt.execute(rusqlite::params![key, value.to_string()])?;
+            }
+        }
+        tx.commit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, serde::Deserialize)]
+    struct Settings {
+        auto_sync: bool,
+        limit_mb: u32,
+        #[serde(default)]
+        relay_url: Option<String>,
+    }
+
+    impl Default for Settings {
+        fn default() -> Self {
+            Self {
+                auto_sync: true,
+                limit_mb: 50,
+                relay_url: None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_settings_round_trip_with_defaults_for_missing() {
+        let storage = Storage::in_memory().unwrap();
+        let settings = storage.settings();
+        assert_eq!(settings.load::<Settings>().unwrap(), Settings::default());
+
+        let changed = Settings {
+            auto_sync: false,
+            limit_mb: 10,
+            relay_url: Some("https://relay.example".to_string()),
+        };
+        settings.save(&changed).unwrap();
+        assert_eq!(settings.load::<Settings>().unwrap(), changed);
+
+        // A setting without a row takes its default; unknown rows are ignored
+        {
+            let conn = storage.conn.lock().unwrap();
+            conn.execute("DELETE FROM settings WHERE key = 'limit_mb'", [])
+                .unwrap();
+            conn.execute(
+                "INSERT INTO settings (key, value) VALUES ('retired', '1')",
+                [],
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            settings.load::<Settings>().unwrap(),
+            Settings {
+                limit_mb: 50,
+                ..changed
+            }
+        );
+    }
+
+    #[test]
+    fn test_unreadable_setting_takes_its_default() {
+        let storage = Storage::in_memory().unwrap();
+        let settings = storage.settings();
+        let changed = Settings {
+            auto_sync: false,
+            limit_mb: 10,
+            relay_url: Some("https://relay.example".to_string()),
+        };
+        settings.save(&changed).unwrap();
+
+        // A value of the wrong type, and one that isn't JSON at all
+        {
+            let conn = storage.conn.lock().unwrap();
+            conn.execute(
+                "UPDATE settings SET value = '\"lots\"' WHERE key = 'limit_mb'",
+                [],
+            )
+            .unwrap();
+            conn.execute(
+                "UPDATE settings SET value = '{not json' WHERE key = 'relay_url'",
+                [],
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            settings.load::<Settings>().unwrap(),
+            Settings {
+                auto_sync: false,
+                ..Settings::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Local sync statistics
+//!
+//! Aggregate counters only: no content, and no peer identities.
+
+use rusqlite::Result as SqliteResult;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::network::SendOutcome;
+
+const SEND_ATTEMPTS: &str = "send_attempts";
+const SEND_SUCCESSES: &str = "send_successes";
+const RELAY_FALLBACKS: &str = "relay_fallbacks";
+const FAILURE_PREFIX: &str = "failure:";
+
+/// Aggregate send counters
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncStats {
+    /// Messages sent to a peer, one per peer per broadcast
+    pub send_attempts: u64,
+    /// Sends that reached the peer, directly or via the relay
+    pub send_successes: u64,
+    /// Sends that reached the peer only through the relay fallback
+    pub relay_fallbacks: u64,
+    /// Failed sends by category (e.g. "timeout")
+    pub failures: BTreeMap<String, u64>,
+}
+
+/// Sync statistics storage operations
+pub struct StatsStorage<'conn> {
+    conn: &'conn Mutex<rusqlite::Connection>,
+}
+
+impl<'conn> StatsStorage<'conn> {
+    pub fn new(conn: &'conn Mutex<rusqlite::Connection>) -> Self {
+        Self { conn }
+    }
+
+    /// Add the outcomes of one broadcast to the counters
+    pub fn record_sends(&self, outcomes: &[SendOutcome]) -> SqliteResult<()> {
+        let mut increments: BTreeMap<String, u64> = BTreeMap::new();
+        for outcome in outcomes {
+            *increments.entry(SEND_ATTEMPTS.to_string()).or_default() += 1;
+            match outcome {
+                SendOutcome::Direct => {
+                    *increments.entry(SEND_SUCCESSES.to_string()).or_default() += 1;
+                }
+                SendOutcome::Relay => {
+                    *increments.entry(SEND_SUCCESSES.to_string()).or_default() += 1;
+                    *increments.entry(RELAY_FALLBACKS.to_string()).or_default() += 1;
+                }
+                SendOutcome::Failed(category) => {
+                    let key = format!("{}{}", FAILURE_PREFIX, category.as_str());
+                    *increments.entry(key).or_default() += 1;
+                }
+            }
+        }
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for (key, count) in increments {
+            tx.execute(
+                "INSERT INTO stats (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = value + excluded.value",
+                rusqlite::params![key, count as i64],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Get the current counters
+    pub fn get_sync_stats(&self) -> SqliteResult<SyncStats> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT key, value FROM stats")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut stats = SyncStats::default();
+        for (key, value) in rows {
+            match key.as_str() {
+                SEND_ATTEMPTS => stats.send_attempts = value,
+                SEND_SUCCESSES => stats.send_successes = value,
+                RELAY_FALLBACKS => stats.relay_fallbacks = value,
+                _ => {
+                    if let Some(category) = key.strip_prefix(FAILURE_PREFIX) {
+                        stats.failures.insert(category.to_string(), value);
+                    }
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Reset all counters to zero
+    pub fn reset(&self) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM stats", [])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::FailureCategory;
+    use crate::storage::Storage;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sync_stats_aggregate_send_outcomes() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().join("test.db")).unwrap();
+        let stats = storage.stats();
+        assert_eq!(stats.get_sync_stats().unwrap(), SyncStats::default());
+
+        // One broadcast to three peers, then one to two peers
+        stats
+            .record_sends(&[
+                SendOutcome::Direct,
+                SendOutcome::Relay,
+                SendOutcome::Failed(FailureCategory::Timeout),
+            ])
+            .unwrap();
+        stats
+            .record_sends(&[
+                SendOutcome::Failed(FailureCategory::Timeout),
+                SendOutcome::Failed(FailureCategory::Relay),
+            ])
+            .unwrap();
+
+        let aggregate = stats.get_sync_stats().unwrap();
+        assert_eq!(aggregate.send_attempts, 5);
+        assert_eq!(aggregate.send_successes, 2);
+        assert_eq!(aggregate.relay_fallbacks, 1);
+        assert_eq!(
+            aggregate.failures,
+            BTreeMap::from([("relay".to_string(), 1), ("timeout".to_string(), 2)])
+        );
+
+        stats.reset().unwrap();
+        assert_eq!(stats.get_sync_stats().unwrap(), SyncStats::default());
+    }
+}